@@ -80,6 +80,12 @@ pub enum Command {
     /// Manage active invocations
     #[clap(subcommand)]
     Invocations(invocations::Invocations),
+    /// Manage subscriptions of services to event sources
+    #[clap(subcommand)]
+    Subscriptions(subscriptions::Subscriptions),
+    /// Export and import the schema registry
+    #[clap(subcommand)]
+    Schemas(schemas::Schemas),
     /// Runs SQL queries against the data fusion service
     #[clap(hide = true)]
     Sql(sql::Sql),
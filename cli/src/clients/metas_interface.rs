@@ -13,7 +13,10 @@ use super::metas_client::Envelope;
 use super::MetasClient;
 
 use restate_meta_rest_model::deployments::*;
+use restate_meta_rest_model::manifest::*;
+use restate_meta_rest_model::schemas::*;
 use restate_meta_rest_model::services::*;
+use restate_meta_rest_model::subscriptions::*;
 
 pub trait MetaClientInterface {
     /// Check if the meta service is healthy by invoking /health
@@ -34,11 +37,37 @@ pub trait MetaClientInterface {
 
     async fn cancel_invocation(&self, id: &str, kill: bool) -> reqwest::Result<Envelope<()>>;
 
+    async fn get_subscriptions(&self) -> reqwest::Result<Envelope<ListSubscriptionsResponse>>;
+
+    async fn update_subscription(
+        &self,
+        id: &str,
+        req: UpdateSubscriptionRequest,
+    ) -> reqwest::Result<Envelope<SubscriptionResponse>>;
+
     async fn patch_state(
         &self,
         service: &str,
         req: ModifyServiceStateRequest,
     ) -> reqwest::Result<Envelope<()>>;
+
+    async fn export_schemas(&self) -> reqwest::Result<Envelope<SchemasArchive>>;
+
+    async fn import_schemas(
+        &self,
+        archive: SchemasArchive,
+        force: bool,
+    ) -> reqwest::Result<Envelope<ImportSchemasResponse>>;
+
+    async fn reconcile_schemas(
+        &self,
+        manifest: Manifest,
+        dry_run: bool,
+        prune: bool,
+        force: bool,
+    ) -> reqwest::Result<Envelope<ReconcilePlan>>;
+
+    async fn apply_reconcile_plan(&self, plan_id: u64) -> reqwest::Result<Envelope<ReconcilePlan>>;
 }
 
 impl MetaClientInterface for MetasClient {
@@ -110,6 +139,24 @@ impl MetaClientInterface for MetasClient {
         self.run(reqwest::Method::DELETE, url).await
     }
 
+    async fn get_subscriptions(&self) -> reqwest::Result<Envelope<ListSubscriptionsResponse>> {
+        let url = self.base_url.join("/subscriptions").expect("Bad url!");
+        self.run(reqwest::Method::GET, url).await
+    }
+
+    async fn update_subscription(
+        &self,
+        id: &str,
+        req: UpdateSubscriptionRequest,
+    ) -> reqwest::Result<Envelope<SubscriptionResponse>> {
+        let url = self
+            .base_url
+            .join(&format!("/subscriptions/{}", id))
+            .expect("Bad url!");
+
+        self.run_with_body(reqwest::Method::PATCH, url, req).await
+    }
+
     async fn patch_state(
         &self,
         service: &str,
@@ -122,4 +169,48 @@ impl MetaClientInterface for MetasClient {
 
         self.run_with_body(reqwest::Method::POST, url, req).await
     }
+
+    async fn export_schemas(&self) -> reqwest::Result<Envelope<SchemasArchive>> {
+        let url = self.base_url.join("/schemas/export").expect("Bad url!");
+        self.run(reqwest::Method::GET, url).await
+    }
+
+    async fn import_schemas(
+        &self,
+        archive: SchemasArchive,
+        force: bool,
+    ) -> reqwest::Result<Envelope<ImportSchemasResponse>> {
+        let mut url = self.base_url.join("/schemas/import").expect("Bad url!");
+
+        url.set_query(Some(&format!("force={}", force)));
+
+        self.run_with_body(reqwest::Method::POST, url, archive)
+            .await
+    }
+
+    async fn reconcile_schemas(
+        &self,
+        manifest: Manifest,
+        dry_run: bool,
+        prune: bool,
+        force: bool,
+    ) -> reqwest::Result<Envelope<ReconcilePlan>> {
+        let mut url = self.base_url.join("/schemas/reconcile").expect("Bad url!");
+
+        url.set_query(Some(&format!(
+            "dry_run={}&prune={}&force={}",
+            dry_run, prune, force
+        )));
+
+        self.run_with_body(reqwest::Method::POST, url, manifest)
+            .await
+    }
+
+    async fn apply_reconcile_plan(&self, plan_id: u64) -> reqwest::Result<Envelope<ReconcilePlan>> {
+        let url = self
+            .base_url
+            .join(&format!("/schemas/reconcile/{}", plan_id))
+            .expect("Bad url!");
+        self.run(reqwest::Method::POST, url).await
+    }
 }
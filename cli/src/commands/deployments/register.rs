@@ -360,6 +360,24 @@ pub async fn run_register(State(env): State<CliEnv>, discover_opts: &Register) -
         }
     }
 
+    if !dry_run_result.wire_incompatibilities.is_empty() {
+        c_println!();
+        c_println!(
+            "❯ CHANGES {} WITH THE REGISTERED SERVICES:",
+            Styled(Style::Danger, "NOT WIRE COMPATIBLE")
+        );
+        for incompatibility in &dry_run_result.wire_incompatibilities {
+            c_indentln!(2, "- {}", Styled(Style::Danger, incompatibility));
+        }
+        c_println!();
+        if !discover_opts.force {
+            c_error!(
+                "The new service revisions would break in-flight invocations. Use --force to register them anyway."
+            );
+            return Ok(());
+        }
+    }
+
     confirm_or_exit(&env, "Are you sure you want to apply those changes?")?;
 
     let progress = ProgressBar::new_spinner();
@@ -29,14 +29,15 @@ use crate::ui::deployments::{
 };
 use crate::ui::service_methods::icon_for_service_flavor;
 use crate::ui::stylesheet::Style;
-use crate::{c_eprintln, c_error, c_indentln, c_success};
+use crate::{c_eprintln, c_error, c_indentln, c_success, c_warn};
 
 #[derive(Run, Parser, Collect, Clone)]
 #[clap(visible_alias = "rm")]
 #[cling(run = "run_remove")]
 pub struct Remove {
     /// Force removal of a deployment if it's not drained. This is dangeous and will
-    /// break in-flight invocations pinned to this deployment.
+    /// break in-flight invocations pinned to this deployment. Without this flag, a
+    /// draining deployment stops accepting new invocations and is removed once drained.
     #[clap(long)]
     force: bool,
     // TODO: Support inference of endpoint or ID, but this require the deployment
@@ -141,13 +142,12 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
             );
             false
         }
-        crate::ui::deployments::DeploymentStatus::Draining => {
-            // unsafe to remove, use --force
+        crate::ui::deployments::DeploymentStatus::Draining if opts.force => {
+            // unsafe to remove
             c_error!(
                 indoc! {
                 "Deployment is still {}. There are {} invocations that will break if you proceed
-                    with this operation. Please make sure in-flight invocations are completed (deployment is Drained)
-                    or killed/cancelled before continuing.
+                    with this operation.
                 "
                 },
                 Styled(Style::Warn, "Draining"),
@@ -155,6 +155,21 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
             );
             false
         }
+        crate::ui::deployments::DeploymentStatus::Draining => {
+            // safe to remove once drained
+            c_warn!(
+                indoc! {
+                "Deployment is still {}. There are {} in-flight invocations pinned to it. The deployment
+                    will stop accepting new invocations, and it will be removed once they're completed.
+                    Use {} to remove it immediately instead.
+                "
+                },
+                Styled(Style::Warn, "Draining"),
+                Styled(Style::Warn, total_active_inv),
+                Styled(Style::Notice, "--force"),
+            );
+            true
+        }
         crate::ui::deployments::DeploymentStatus::Drained => {
             // safe to remove
             c_success!("The deployment is fully drained and is safe to remove");
@@ -172,16 +187,19 @@ pub async fn run_remove(State(env): State<CliEnv>, opts: &Remove) -> Result<()>
 
     confirm_or_exit(&env, "Are you sure you want to remove this deployment?")?;
 
-    let result = client
-        .remove_deployment(
-            &opts.deployment_id,
-            //TODO: Use opts.force when the server implements the false + validation case!
-            true,
-        )
-        .await?;
+    // Without force, the server drains the deployment and removes it once drained.
+    let force = opts.force || status == crate::ui::deployments::DeploymentStatus::Drained;
+    let result = client.remove_deployment(&opts.deployment_id, force).await?;
     let _ = result.success_or_error()?;
 
     c_println!();
-    c_success!("Deployment {} removed successfully", &opts.deployment_id);
+    if force {
+        c_success!("Deployment {} removed successfully", &opts.deployment_id);
+    } else {
+        c_success!(
+            "Deployment {} is draining, it will be removed once drained",
+            &opts.deployment_id
+        );
+    }
     Ok(())
 }
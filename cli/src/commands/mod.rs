@@ -11,7 +11,9 @@
 pub mod deployments;
 pub mod examples;
 pub mod invocations;
+pub mod schemas;
 pub mod services;
 pub mod sql;
 pub mod state;
+pub mod subscriptions;
 pub mod whoami;
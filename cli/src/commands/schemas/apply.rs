@@ -0,0 +1,164 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use cling::prelude::*;
+
+use restate_meta_rest_model::manifest::{Manifest, PlannedChange, ReconcilePlan};
+
+use crate::cli_env::CliEnv;
+use crate::clients::{MetaClientInterface, MetasClient};
+use crate::ui::console::{confirm_or_exit, Styled};
+use crate::ui::stylesheet::Style;
+use crate::{c_indentln, c_println, c_success};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_apply")]
+pub struct Apply {
+    /// Only show the changes, without applying them
+    #[clap(long)]
+    dry_run: bool,
+    /// Remove the deployments and subscriptions missing from the manifest.
+    /// Deployments are drained. Deployments serving the latest revision of a service
+    /// can't be drained, and are removed together with their services only with `--force`.
+    #[clap(long)]
+    prune: bool,
+    /// Register again the deployments whose services changed, and remove the pruned deployments
+    /// serving the latest revision of a service. This is dangerous and might break in-flight
+    /// invocations pinned to these deployments.
+    #[clap(long)]
+    force: bool,
+    /// Manifest file, in YAML or JSON format
+    file: PathBuf,
+}
+
+pub async fn run_apply(State(env): State<CliEnv>, opts: &Apply) -> Result<()> {
+    let content = tokio::fs::read(&opts.file)
+        .await
+        .with_context(|| format!("Failed to read {}", opts.file.display()))?;
+    // JSON is a subset of YAML, hence both formats are parsed by the YAML parser
+    let manifest: Manifest =
+        serde_yaml::from_slice(&content).context("Failed to parse the manifest")?;
+
+    let client = MetasClient::new(&env)?;
+
+    // Compute the plan first, to show it before applying it
+    let plan = client
+        .reconcile_schemas(manifest, true, opts.prune, opts.force)
+        .await?
+        .into_body()
+        .await?;
+    if plan.changes.is_empty() {
+        c_success!("The registry already matches the manifest, nothing to do");
+        return Ok(());
+    }
+    render_plan(&plan);
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    confirm_or_exit(&env, "Are you sure you want to apply these changes?")?;
+
+    // Apply the shown plan, rather than reconciling the manifest again
+    let plan_id = plan
+        .plan_id
+        .context("The server didn't return the id of the reconcile plan")?;
+    let plan = client
+        .apply_reconcile_plan(plan_id)
+        .await?
+        .into_body()
+        .await?;
+
+    c_println!();
+    c_success!("Applied {} changes", plan.changes.len());
+    Ok(())
+}
+
+fn render_plan(plan: &ReconcilePlan) {
+    c_println!("❯ PLANNED CHANGES:");
+    for change in &plan.changes {
+        match change {
+            PlannedChange::RegisterDeployment {
+                id,
+                address,
+                services,
+                wire_incompatibilities,
+            } => {
+                c_indentln!(
+                    1,
+                    "{} deployment {} ({})",
+                    Styled(Style::Success, "+ register"),
+                    address,
+                    id
+                );
+                for svc in services {
+                    c_indentln!(2, "- {} [revision {}]", svc.name, svc.revision);
+                }
+                for incompatibility in wire_incompatibilities {
+                    c_indentln!(
+                        2,
+                        "{} {}",
+                        Styled(Style::Danger, "incompatible:"),
+                        incompatibility
+                    );
+                }
+            }
+            PlannedChange::DrainDeployment { id, address } => {
+                c_indentln!(
+                    1,
+                    "{} deployment {} ({})",
+                    Styled(Style::Warn, "~ drain"),
+                    address,
+                    id
+                );
+            }
+            PlannedChange::RemoveDeployment { id, address } => {
+                c_indentln!(
+                    1,
+                    "{} deployment {} ({}), together with the services it serves",
+                    Styled(Style::Danger, "- remove"),
+                    address,
+                    id
+                );
+            }
+            PlannedChange::ModifyService { name, public } => {
+                c_indentln!(
+                    1,
+                    "{} service {} to {}",
+                    Styled(Style::Warn, "~ modify"),
+                    name,
+                    if *public { "public" } else { "private" }
+                );
+            }
+            PlannedChange::CreateSubscription { source, sink, .. } => {
+                c_indentln!(
+                    1,
+                    "{} subscription from {} to {}",
+                    Styled(Style::Success, "+ create"),
+                    source,
+                    sink
+                );
+            }
+            PlannedChange::DeleteSubscription { id, source, sink } => {
+                c_indentln!(
+                    1,
+                    "{} subscription {} from {} to {}",
+                    Styled(Style::Danger, "- delete"),
+                    id,
+                    source,
+                    sink
+                );
+            }
+        }
+    }
+    c_println!();
+}
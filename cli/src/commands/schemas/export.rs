@@ -0,0 +1,49 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use cling::prelude::*;
+
+use crate::cli_env::CliEnv;
+use crate::clients::{MetaClientInterface, MetasClient};
+use crate::{c_println, c_success};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_export")]
+pub struct Export {
+    /// File to write the archive to. If not set, the archive is printed to stdout
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+}
+
+pub async fn run_export(State(env): State<CliEnv>, opts: &Export) -> Result<()> {
+    let client = MetasClient::new(&env)?;
+
+    let archive = client.export_schemas().await?.into_body().await?;
+    let json = serde_json::to_string_pretty(&archive).context("Failed to serialize the archive")?;
+
+    match &opts.output {
+        Some(output) => {
+            tokio::fs::write(output, json)
+                .await
+                .with_context(|| format!("Failed to write the archive to {}", output.display()))?;
+            c_success!(
+                "Exported {} deployments and {} subscriptions to {}",
+                archive.deployments.len(),
+                archive.subscriptions.len(),
+                output.display()
+            );
+        }
+        None => c_println!("{}", json),
+    }
+    Ok(())
+}
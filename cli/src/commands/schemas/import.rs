@@ -0,0 +1,69 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use cling::prelude::*;
+
+use restate_meta_rest_model::schemas::SchemasArchive;
+
+use crate::cli_env::CliEnv;
+use crate::clients::{MetaClientInterface, MetasClient};
+use crate::ui::console::confirm_or_exit;
+use crate::{c_indentln, c_println, c_success};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_import")]
+pub struct Import {
+    /// Override the registered deployments and subscriptions with the same ID
+    /// but a different definition
+    #[clap(long)]
+    force: bool,
+    /// File containing the archive to import
+    file: PathBuf,
+}
+
+pub async fn run_import(State(env): State<CliEnv>, opts: &Import) -> Result<()> {
+    let content = tokio::fs::read(&opts.file)
+        .await
+        .with_context(|| format!("Failed to read {}", opts.file.display()))?;
+    let archive: SchemasArchive =
+        serde_json::from_slice(&content).context("Failed to parse the archive")?;
+
+    c_println!(
+        "The archive contains {} deployments, {} services and {} subscriptions.",
+        archive.deployments.len(),
+        archive.services.len(),
+        archive.subscriptions.len()
+    );
+    confirm_or_exit(&env, "Are you sure you want to import it?")?;
+
+    let client = MetasClient::new(&env)?;
+    let response = client
+        .import_schemas(archive, opts.force)
+        .await?
+        .into_body()
+        .await?;
+
+    c_println!();
+    c_success!(
+        "Imported {} deployments and {} subscriptions",
+        response.deployments.len(),
+        response.subscriptions.len()
+    );
+    for deployment_id in response.deployments {
+        c_indentln!(1, "- Deployment {}", deployment_id);
+    }
+    for subscription_id in response.subscriptions {
+        c_indentln!(1, "- Subscription {}", subscription_id);
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+mod apply;
+mod export;
+mod import;
+
+use cling::prelude::*;
+
+#[derive(Run, Subcommand, Clone)]
+#[clap(visible_alias = "schema")]
+pub enum Schemas {
+    /// Reconcile the registered deployments, services and subscriptions against a manifest
+    Apply(apply::Apply),
+    /// Export the registered deployments, services and subscriptions to an archive
+    Export(export::Export),
+    /// Import an archive produced by the export command
+    Import(import::Import),
+}
@@ -0,0 +1,89 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::c_error;
+use crate::cli_env::CliEnv;
+use crate::clients::MetaClientInterface;
+use crate::console::c_println;
+use crate::ui::console::{Styled, StyledTable};
+use crate::ui::stylesheet::Style;
+use crate::ui::watcher::Watch;
+
+use anyhow::Result;
+use cling::prelude::*;
+use comfy_table::{Cell, Table};
+
+#[derive(Run, Parser, Collect, Clone)]
+#[clap(visible_alias = "ls")]
+#[cling(run = "run_list")]
+pub struct List {
+    /// Show the options of the subscriptions
+    #[clap(long)]
+    extra: bool,
+
+    #[clap(flatten)]
+    watch: Watch,
+}
+
+pub async fn run_list(State(env): State<CliEnv>, opts: &List) -> Result<()> {
+    opts.watch.run(|| list(&env, opts)).await
+}
+
+async fn list(env: &CliEnv, list_opts: &List) -> Result<()> {
+    let client = crate::clients::MetasClient::new(env)?;
+
+    let mut subscriptions = client
+        .get_subscriptions()
+        .await?
+        .into_body()
+        .await?
+        .subscriptions;
+
+    if subscriptions.is_empty() {
+        c_error!("No subscriptions were found!");
+        return Ok(());
+    }
+    subscriptions.sort_unstable_by(|a, b| a.source.cmp(&b.source).then(a.sink.cmp(&b.sink)));
+
+    let mut table = Table::new_styled(&env.ui_config);
+    let mut header = vec!["ID", "SOURCE", "SINK", "STATUS"];
+    if list_opts.extra {
+        header.push("OPTIONS");
+    }
+    table.set_styled_header(header);
+
+    for subscription in subscriptions {
+        let mut row = vec![
+            Cell::new(subscription.id),
+            Cell::new(&subscription.source),
+            Cell::new(&subscription.sink),
+            Cell::new(if subscription.paused {
+                Styled(Style::Warn, "Paused")
+            } else {
+                Styled(Style::Success, "Running")
+            }),
+        ];
+        if list_opts.extra {
+            let mut options: Vec<_> = subscription
+                .options
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect();
+            options.sort_unstable();
+            row.push(Cell::new(options.join("\n")));
+        }
+
+        table.add_row(row);
+    }
+
+    c_println!("{}", table);
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+mod list;
+mod pause;
+mod resume;
+
+use cling::prelude::*;
+
+#[derive(Run, Subcommand, Clone)]
+#[clap(visible_alias = "sub", alias = "subscription")]
+pub enum Subscriptions {
+    /// List the registered subscriptions
+    List(list::List),
+    /// Stop consuming events of a subscription, retaining its consumed offsets
+    Pause(pause::Pause),
+    /// Resume consuming events of a paused subscription
+    Resume(resume::Resume),
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::cli_env::CliEnv;
+use crate::clients::{MetaClientInterface, MetasClient};
+use crate::{c_println, c_success};
+
+use restate_meta_rest_model::subscriptions::UpdateSubscriptionRequest;
+
+use anyhow::Result;
+use cling::prelude::*;
+
+#[derive(Run, Parser, Collect, Clone)]
+#[cling(run = "run_pause")]
+pub struct Pause {
+    /// The ID of the subscription to pause
+    subscription_id: String,
+}
+
+pub async fn run_pause(State(env): State<CliEnv>, opts: &Pause) -> Result<()> {
+    let client = MetasClient::new(&env)?;
+
+    let subscription = client
+        .update_subscription(
+            &opts.subscription_id,
+            UpdateSubscriptionRequest {
+                paused: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?
+        .into_body()
+        .await?;
+
+    c_println!();
+    c_success!(
+        "Subscription {} from {} to {} paused successfully",
+        subscription.id,
+        subscription.source,
+        subscription.sink
+    );
+    Ok(())
+}
@@ -0,0 +1,280 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Removal of the draining deployments once no invocation is pinned to them anymore.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use datafusion::arrow::array::{Array, AsArray};
+use datafusion::arrow::datatypes::Int64Type;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use futures::TryStreamExt;
+use restate_meta::MetaHandle;
+use restate_schema_api::deployment::DeploymentResolver;
+use restate_schema_impl::Schemas;
+use restate_storage_query_datafusion::context::QueryContext;
+use restate_types::identifiers::DeploymentId;
+use tokio::sync::Mutex;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+/// Maximum age of the cached pinned invocations counts served by [`PinnedInvocations::get`].
+const PINNED_INVOCATIONS_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Counts of the in-flight invocations pinned to each deployment.
+///
+/// Counting scans the whole status table, hence the REST API reads the counts cached by the last scan,
+/// while the [`DrainedDeploymentsRemover`] refreshes them on every check.
+#[derive(Clone)]
+pub struct PinnedInvocations {
+    query_context: QueryContext,
+    cached: Arc<Mutex<Option<(Instant, Arc<HashMap<DeploymentId, u64>>)>>>,
+}
+
+impl PinnedInvocations {
+    pub(crate) fn new(query_context: QueryContext) -> Self {
+        Self {
+            query_context,
+            cached: Default::default(),
+        }
+    }
+
+    /// Returns the cached counts, scanning the status table only if they're older than [`PINNED_INVOCATIONS_MAX_AGE`].
+    pub(crate) async fn get(&self) -> Result<Arc<HashMap<DeploymentId, u64>>, DataFusionError> {
+        // Concurrent callers wait for the same scan, rather than starting their own
+        let mut cached = self.cached.lock().await;
+        if let Some((counted_at, pinned_invocations)) = cached.as_ref() {
+            if counted_at.elapsed() < PINNED_INVOCATIONS_MAX_AGE {
+                return Ok(Arc::clone(pinned_invocations));
+            }
+        }
+
+        let pinned_invocations = Arc::new(count_pinned_invocations(&self.query_context).await?);
+        *cached = Some((Instant::now(), Arc::clone(&pinned_invocations)));
+        Ok(pinned_invocations)
+    }
+
+    /// Scans the status table and caches the result.
+    pub(crate) async fn refresh(&self) -> Result<Arc<HashMap<DeploymentId, u64>>, DataFusionError> {
+        let mut cached = self.cached.lock().await;
+        let pinned_invocations = Arc::new(count_pinned_invocations(&self.query_context).await?);
+        *cached = Some((Instant::now(), Arc::clone(&pinned_invocations)));
+        Ok(pinned_invocations)
+    }
+}
+
+/// Counts the in-flight invocations pinned to each deployment, using the status table.
+async fn count_pinned_invocations(
+    query_context: &QueryContext,
+) -> Result<HashMap<DeploymentId, u64>, DataFusionError> {
+    let batches: Vec<RecordBatch> = query_context
+        .execute(
+            "SELECT pinned_deployment_id, COUNT(id) AS inv_count \
+            FROM sys_status \
+            WHERE pinned_deployment_id IS NOT NULL \
+            GROUP BY pinned_deployment_id",
+        )
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut pinned_invocations = HashMap::new();
+    for batch in batches {
+        let deployment_ids = batch.column(0).as_string::<i64>();
+        let inv_counts = batch.column(1).as_primitive::<Int64Type>();
+        for row in 0..batch.num_rows() {
+            if deployment_ids.is_null(row) {
+                continue;
+            }
+            if let Ok(deployment_id) = deployment_ids.value(row).parse::<DeploymentId>() {
+                pinned_invocations.insert(deployment_id, inv_counts.value(row) as u64);
+            }
+        }
+    }
+
+    Ok(pinned_invocations)
+}
+
+pub(crate) struct DrainedDeploymentsRemover {
+    schemas: Schemas,
+    meta_handle: MetaHandle,
+    pinned_invocations: PinnedInvocations,
+    interval: Duration,
+}
+
+impl DrainedDeploymentsRemover {
+    pub(crate) fn new(
+        schemas: Schemas,
+        meta_handle: MetaHandle,
+        pinned_invocations: PinnedInvocations,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            schemas,
+            meta_handle,
+            pinned_invocations,
+            interval,
+        }
+    }
+
+    pub(crate) async fn run(self, drain: drain::Watch) {
+        let shutdown = drain.signaled();
+        tokio::pin!(shutdown);
+
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // A deployment is removed only when found drained by two consecutive checks.
+        // This gives the invocations which selected the deployment right before it started draining
+        // the time to get pinned to it.
+        let mut drained = HashSet::new();
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    debug!("Stopping the drained deployments remover");
+                    return;
+                },
+                _ = interval.tick() => {
+                    drained = self.remove_drained_deployments(drained).await;
+                }
+            }
+        }
+    }
+
+    async fn remove_drained_deployments(
+        &self,
+        previously_drained: HashSet<DeploymentId>,
+    ) -> HashSet<DeploymentId> {
+        let draining: Vec<_> = self
+            .schemas
+            .get_deployments()
+            .into_iter()
+            .map(|(deployment, _)| deployment.id)
+            .filter(|deployment_id| {
+                self.schemas
+                    .get_deployment_state(deployment_id)
+                    .is_some_and(|state| state.is_draining())
+            })
+            .collect();
+        if draining.is_empty() {
+            return HashSet::new();
+        }
+
+        let pinned_invocations = match self.pinned_invocations.refresh().await {
+            Ok(pinned_invocations) => pinned_invocations,
+            Err(err) => {
+                warn!(
+                    error = %err,
+                    "Cannot count the invocations pinned to the draining deployments"
+                );
+                return previously_drained;
+            }
+        };
+
+        let (to_remove, mut drained) =
+            drained_deployments(draining, &pinned_invocations, &previously_drained);
+        for deployment_id in to_remove {
+            // The deployment might have been undrained in the meantime
+            if !self
+                .schemas
+                .get_deployment_state(&deployment_id)
+                .is_some_and(|state| state.is_draining())
+            {
+                continue;
+            }
+
+            info!(restate.deployment.id = %deployment_id, "Removing drained deployment");
+            if let Err(err) = self.meta_handle.remove_deployment(deployment_id).await {
+                warn!(
+                    restate.deployment.id = %deployment_id,
+                    error = %err,
+                    "Failed to remove drained deployment, will retry"
+                );
+                drained.insert(deployment_id);
+            }
+        }
+
+        drained
+    }
+}
+
+/// Splits the draining deployments with no pinned invocations into the ones to remove,
+/// because they were found drained by the previous check too, and the ones to check again.
+fn drained_deployments(
+    draining: Vec<DeploymentId>,
+    pinned_invocations: &HashMap<DeploymentId, u64>,
+    previously_drained: &HashSet<DeploymentId>,
+) -> (Vec<DeploymentId>, HashSet<DeploymentId>) {
+    let mut to_remove = vec![];
+    let mut drained = HashSet::new();
+    for deployment_id in draining {
+        if pinned_invocations.contains_key(&deployment_id) {
+            continue;
+        }
+        if previously_drained.contains(&deployment_id) {
+            to_remove.push(deployment_id);
+        } else {
+            drained.insert(deployment_id);
+        }
+    }
+
+    (to_remove, drained)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_deployments_drained_on_two_consecutive_checks() {
+        let deployment_1 = DeploymentId::new();
+        let deployment_2 = DeploymentId::new();
+
+        // First check: deployment_2 has pinned invocations, deployment_1 is drained but not removed yet
+        let (to_remove, drained) = drained_deployments(
+            vec![deployment_1, deployment_2],
+            &HashMap::from([(deployment_2, 3)]),
+            &HashSet::new(),
+        );
+        assert!(to_remove.is_empty());
+        assert_eq!(drained, HashSet::from([deployment_1]));
+
+        // Second check: deployment_1 is removed, deployment_2 is now drained
+        let (to_remove, drained) =
+            drained_deployments(vec![deployment_1, deployment_2], &HashMap::new(), &drained);
+        assert_eq!(to_remove, vec![deployment_1]);
+        assert_eq!(drained, HashSet::from([deployment_2]));
+
+        // Third check: deployment_2 got an invocation pinned in the meantime, so it starts over
+        let (to_remove, drained) = drained_deployments(
+            vec![deployment_2],
+            &HashMap::from([(deployment_2, 1)]),
+            &drained,
+        );
+        assert!(to_remove.is_empty());
+        assert!(drained.is_empty());
+    }
+
+    #[test]
+    fn keep_undrained_deployments() {
+        let deployment = DeploymentId::new();
+
+        let (_, drained) = drained_deployments(vec![deployment], &HashMap::new(), &HashSet::new());
+        assert_eq!(drained, HashSet::from([deployment]));
+
+        // The deployment was undrained before the second check
+        let (to_remove, drained) = drained_deployments(vec![], &HashMap::new(), &drained);
+        assert!(to_remove.is_empty());
+        assert!(drained.is_empty());
+    }
+}
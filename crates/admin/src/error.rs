@@ -21,6 +21,13 @@ pub enum Error {
         #[source]
         source: hyper::Error,
     },
+    #[error("cannot restrict the tables of query role '{role}' specified in 'admin.query_roles': {source}")]
+    #[code(unknown)]
+    QueryRole {
+        role: String,
+        #[source]
+        source: datafusion::error::DataFusionError,
+    },
     #[error("error while running admin server: {0}")]
     #[code(unknown)]
     Running(hyper::Error),
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod drained_deployments;
 mod error;
 mod options;
 mod rest_api;
@@ -15,5 +16,5 @@ pub mod service;
 mod state;
 mod storage_query;
 
-pub use crate::options::{Options, OptionsBuilder, OptionsBuilderError};
+pub use crate::options::{Options, OptionsBuilder, OptionsBuilderError, QueryRole};
 pub use error::Error;
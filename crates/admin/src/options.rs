@@ -13,7 +13,9 @@ use restate_meta::MetaHandle;
 use restate_schema_impl::Schemas;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use crate::service::AdminService;
 
@@ -33,6 +35,46 @@ pub struct Options {
     ///
     /// Concurrency limit for the Admin APIs.
     pub concurrency_limit: usize,
+
+    /// # Drained deployments removal interval
+    ///
+    /// Interval to check whether draining deployments have no pinned invocations left, and to remove them.
+    /// Unsetting means that drained deployments are kept until they're forcefully removed.
+    /// Drained deployments are removed only when the storage query is enabled.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")]
+    #[cfg_attr(feature = "options_schema", schemars(with = "Option<String>"))]
+    pub drained_deployments_removal_interval: Option<humantime::Duration>,
+
+    /// # Query roles
+    ///
+    /// Roles restricting the tables accessible through the `/query` endpoint, by role name.
+    /// When at least one role is configured, `/query` requests must carry the `Authorization: Bearer <token>` header
+    /// with the token of one of the roles. Unset means that `/query` can access every table, but cannot run write statements.
+    pub query_roles: HashMap<String, QueryRole>,
+}
+
+/// # Query role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub struct QueryRole {
+    /// # Token
+    ///
+    /// Bearer token authenticating the role.
+    pub token: String,
+
+    /// # Tables
+    ///
+    /// Tables the role can query, e.g. `sys_status` or `state`.
+    pub tables: Vec<String>,
+
+    /// # Allow mutations
+    ///
+    /// Whether the role can run write statements, such as `DELETE FROM state`.
+    /// Requires the write statements to be enabled in the storage query options.
+    #[serde(default)]
+    pub allow_mutations: bool,
 }
 
 impl Default for Options {
@@ -40,6 +82,8 @@ impl Default for Options {
         Self {
             bind_address: "0.0.0.0:9070".parse().unwrap(),
             concurrency_limit: 1000,
+            drained_deployments_removal_interval: Some(Duration::from_secs(60).into()),
+            query_roles: HashMap::new(),
         }
     }
 }
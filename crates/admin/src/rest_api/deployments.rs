@@ -29,6 +29,9 @@ use okapi_operation::okapi::openapi3::{MediaType, Responses};
 use okapi_operation::okapi::Map;
 use okapi_operation::*;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
 
 /// Create deployment and return discovered services.
 #[openapi(
@@ -100,6 +103,11 @@ pub async fn create_deployment<W>(
     let response_body = RegisterDeploymentResponse {
         id: registration_result.deployment,
         services: registration_result.services,
+        wire_incompatibilities: registration_result
+            .wire_incompatibilities
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
     };
 
     Ok((
@@ -132,15 +140,54 @@ pub async fn get_deployment<W>(
         .schemas()
         .get_deployment_and_services(&deployment_id)
         .ok_or_else(|| MetaApiError::DeploymentNotFound(deployment_id))?;
+    let pinned_invocations = count_pinned_invocations(&state).await;
 
     Ok(DetailedDeploymentResponse {
         id: deployment.id,
         deployment: deployment.metadata.into(),
         services,
+        state: state
+            .schemas()
+            .get_deployment_state(&deployment_id)
+            .unwrap_or_default(),
+        pinned_invocations: pinned_invocations
+            .map(|pinned_invocations| pinned_invocations_of(&pinned_invocations, &deployment_id)),
     }
     .into())
 }
 
+/// Modify deployment
+#[openapi(
+    summary = "Modify deployment",
+    description = "Modify the state of a deployment. Draining a deployment stops routing new invocations to it, and fails if it's the only deployment serving the latest revision of a service. Setting a draining deployment back to active cancels its removal.",
+    operation_id = "modify_deployment",
+    tags = "deployment",
+    parameters(path(
+        name = "deployment",
+        description = "Deployment identifier",
+        schema = "std::string::String"
+    ))
+)]
+pub async fn modify_deployment<W>(
+    State(state): State<AdminServiceState<W>>,
+    Path(deployment_id): Path<DeploymentId>,
+    #[request_body(required = true)] Json(ModifyDeploymentRequest {
+        state: deployment_state,
+    }): Json<ModifyDeploymentRequest>,
+) -> Result<Json<DetailedDeploymentResponse>, MetaApiError> {
+    match deployment_state {
+        DeploymentState::Active => {
+            state
+                .meta_handle()
+                .undrain_deployment(deployment_id)
+                .await?
+        }
+        DeploymentState::Draining => state.meta_handle().drain_deployment(deployment_id).await?,
+    }
+
+    get_deployment(State(state), Path(deployment_id)).await
+}
+
 /// Return deployment descriptors
 #[openapi(
     summary = "Get deployment descriptors",
@@ -175,6 +222,8 @@ pub async fn get_deployment_descriptors<W>(
 pub async fn list_deployments<W>(
     State(state): State<AdminServiceState<W>>,
 ) -> Json<ListDeploymentsResponse> {
+    let pinned_invocations = count_pinned_invocations(&state).await;
+
     ListDeploymentsResponse {
         deployments: state
             .schemas()
@@ -187,12 +236,44 @@ pub async fn list_deployments<W>(
                     .into_iter()
                     .map(|(name, revision)| ServiceNameRevPair { name, revision })
                     .collect(),
+                state: state
+                    .schemas()
+                    .get_deployment_state(&deployment.id)
+                    .unwrap_or_default(),
+                pinned_invocations: pinned_invocations.as_ref().map(|pinned_invocations| {
+                    pinned_invocations_of(pinned_invocations, &deployment.id)
+                }),
             })
             .collect(),
     }
     .into()
 }
 
+async fn count_pinned_invocations<W>(
+    state: &AdminServiceState<W>,
+) -> Option<Arc<HashMap<DeploymentId, u64>>> {
+    match state.pinned_invocations()?.get().await {
+        Ok(pinned_invocations) => Some(pinned_invocations),
+        Err(err) => {
+            warn!(
+                error = %err,
+                "Cannot count the invocations pinned to the deployments"
+            );
+            None
+        }
+    }
+}
+
+fn pinned_invocations_of(
+    pinned_invocations: &HashMap<DeploymentId, u64>,
+    deployment_id: &DeploymentId,
+) -> u64 {
+    pinned_invocations
+        .get(deployment_id)
+        .copied()
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct DeleteDeploymentParams {
     pub force: Option<bool>,
@@ -201,7 +282,7 @@ pub struct DeleteDeploymentParams {
 /// Discover endpoint and return discovered endpoints.
 #[openapi(
     summary = "Delete deployment",
-    description = "Delete deployment. Currently it's supported to remove a deployment only using the force flag",
+    description = "Delete deployment. Unless the force flag is set, the deployment is drained first, and removed once no invocation is pinned to it anymore.",
     operation_id = "delete_deployment",
     tags = "deployment",
     parameters(
@@ -212,7 +293,7 @@ pub struct DeleteDeploymentParams {
         ),
         query(
             name = "force",
-            description = "If true, the deployment will be forcefully deleted. This might break in-flight invocations, use with caution.",
+            description = "If true, the deployment will be forcefully deleted. This might break in-flight invocations, use with caution. Otherwise, the deployment is drained: it accepts no new invocations, and it's removed once no invocation is pinned to it anymore.",
             required = false,
             style = "simple",
             allow_empty_value = false,
@@ -226,11 +307,6 @@ pub struct DeleteDeploymentParams {
             description = "Accepted",
             content = "okapi_operation::Empty",
         ),
-        response(
-            status = "501",
-            description = "Not implemented. Only using the force flag is supported at the moment.",
-            content = "okapi_operation::Empty",
-        ),
         from_type = "MetaApiError",
     )
 )]
@@ -241,10 +317,10 @@ pub async fn delete_deployment<W>(
 ) -> Result<StatusCode, MetaApiError> {
     if let Some(true) = force {
         state.meta_handle().remove_deployment(deployment_id).await?;
-        Ok(StatusCode::ACCEPTED)
     } else {
-        Ok(StatusCode::NOT_IMPLEMENTED)
+        state.meta_handle().drain_deployment(deployment_id).await?;
     }
+    Ok(StatusCode::ACCEPTED)
 }
 
 pub struct ProtoBytes(Bytes);
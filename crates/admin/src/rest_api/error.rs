@@ -64,7 +64,17 @@ impl IntoResponse for MetaApiError {
             | MetaApiError::MethodNotFound { .. }
             | MetaApiError::DeploymentNotFound(_)
             | MetaApiError::SubscriptionNotFound(_) => StatusCode::NOT_FOUND,
-            MetaApiError::Meta(MetaError::SchemaRegistry(SchemasUpdateError::BadDescriptor(_))) => {
+            MetaApiError::Meta(MetaError::SchemaRegistry(SchemasUpdateError::BadDescriptor(_)))
+            | MetaApiError::Meta(MetaError::SchemaRegistry(
+                SchemasUpdateError::InvalidServiceRouting(_, _),
+            ))
+            | MetaApiError::Meta(MetaError::SchemaRegistry(
+                SchemasUpdateError::InvalidServiceConfiguration(_, _),
+            ))
+            | MetaApiError::Meta(MetaError::SchemaRegistry(
+                SchemasUpdateError::UnsupportedOffsetsReset(_),
+            ))
+            | MetaApiError::Meta(MetaError::UnsupportedSchemasArchiveVersion(_)) => {
                 StatusCode::BAD_REQUEST
             }
             MetaApiError::Meta(MetaError::SchemaRegistry(
@@ -72,7 +82,15 @@ impl IntoResponse for MetaApiError {
             ))
             | MetaApiError::Meta(MetaError::SchemaRegistry(
                 SchemasUpdateError::IncompatibleServiceChange(_),
-            )) => StatusCode::CONFLICT,
+            ))
+            | MetaApiError::Meta(MetaError::SchemaRegistry(
+                SchemasUpdateError::DrainLatestDeployment(_, _),
+            ))
+            | MetaApiError::Meta(MetaError::SchemaRegistry(
+                SchemasUpdateError::ImportOutdatedServiceRevision(_, _, _),
+            ))
+            | MetaApiError::Meta(MetaError::OutdatedReconcilePlan(_)) => StatusCode::CONFLICT,
+            MetaApiError::Meta(MetaError::UnknownReconcilePlan(_)) => StatusCode::NOT_FOUND,
             MetaApiError::Meta(MetaError::SchemaRegistry(SchemasUpdateError::UnknownService(
                 _,
             ))) => StatusCode::NOT_FOUND,
@@ -83,7 +101,12 @@ impl IntoResponse for MetaApiError {
                 SchemasUpdateError::ModifyInternalService(_),
             )) => StatusCode::FORBIDDEN,
             MetaApiError::InvalidField(_, _) => StatusCode::BAD_REQUEST,
-            MetaApiError::Worker(_) => StatusCode::SERVICE_UNAVAILABLE,
+            MetaApiError::Meta(MetaError::Worker(restate_worker_api::Error::ResetOffsets(_))) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            MetaApiError::Worker(_) | MetaApiError::Meta(MetaError::Worker(_)) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let body = Json(match &self {
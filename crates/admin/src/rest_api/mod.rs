@@ -15,10 +15,11 @@ mod error;
 mod health;
 mod invocations;
 mod methods;
+mod schemas;
 mod services;
 mod subscriptions;
 
-use okapi_operation::axum_integration::{delete, get, patch, post};
+use okapi_operation::axum_integration::{delete, get, patch, post, put};
 use okapi_operation::*;
 
 use crate::state::AdminServiceState;
@@ -48,6 +49,10 @@ pub fn create_router<W: restate_worker_api::Handle + Send + Sync + 'static>(
             "/deployments/:deployment",
             delete(openapi_handler!(deployments::delete_deployment)),
         )
+        .route(
+            "/deployments/:deployment",
+            patch(openapi_handler!(deployments::modify_deployment)),
+        )
         .route("/services", get(openapi_handler!(services::list_services)))
         .route(
             "/services/:service",
@@ -57,6 +62,10 @@ pub fn create_router<W: restate_worker_api::Handle + Send + Sync + 'static>(
             "/services/:service",
             patch(openapi_handler!(services::modify_service)),
         )
+        .route(
+            "/services/:service/routing",
+            put(openapi_handler!(services::modify_service_routing)),
+        )
         .route(
             "/services/:service/descriptors",
             get(openapi_handler!(services::list_service_descriptors)),
@@ -73,6 +82,22 @@ pub fn create_router<W: restate_worker_api::Handle + Send + Sync + 'static>(
             "/services/:service/methods/:method",
             get(openapi_handler!(methods::get_service_method)),
         )
+        .route(
+            "/schemas/export",
+            get(openapi_handler!(schemas::export_schemas)),
+        )
+        .route(
+            "/schemas/import",
+            post(openapi_handler!(schemas::import_schemas)),
+        )
+        .route(
+            "/schemas/reconcile",
+            post(openapi_handler!(schemas::reconcile_schemas)),
+        )
+        .route(
+            "/schemas/reconcile/:plan",
+            post(openapi_handler!(schemas::apply_reconcile_plan)),
+        )
         .route(
             "/invocations/:invocation_id",
             delete(openapi_handler!(invocations::delete_invocation)),
@@ -93,6 +118,14 @@ pub fn create_router<W: restate_worker_api::Handle + Send + Sync + 'static>(
             "/subscriptions/:subscription",
             delete(openapi_handler!(subscriptions::delete_subscription)),
         )
+        .route(
+            "/subscriptions/:subscription",
+            patch(openapi_handler!(subscriptions::update_subscription)),
+        )
+        .route(
+            "/subscriptions/:subscription/offsets/reset",
+            post(openapi_handler!(subscriptions::reset_subscription_offsets)),
+        )
         .route("/health", get(openapi_handler!(health::health)))
         .route_openapi_specification(
             "/openapi",
@@ -0,0 +1,188 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::error::*;
+use crate::state::AdminServiceState;
+
+use restate_meta::{ApplyMode, Force};
+use restate_meta_rest_model::manifest::*;
+use restate_meta_rest_model::schemas::*;
+use restate_service_client::Endpoint;
+use restate_service_protocol::discovery::DiscoverEndpoint;
+use restate_types::identifiers::InvalidLambdaARN;
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use okapi_operation::*;
+
+/// Export the schema registry
+#[openapi(
+    summary = "Export schemas",
+    description = "Export the schema registry, including deployments, service settings, Protobuf descriptors and subscriptions. The returned archive can be imported in another cluster.",
+    operation_id = "export_schemas",
+    tags = "schemas"
+)]
+pub async fn export_schemas<W>(
+    State(state): State<AdminServiceState<W>>,
+) -> Result<Json<SchemasArchive>, MetaApiError> {
+    Ok(restate_meta::export_schemas(state.schemas())?.into())
+}
+
+/// Import a schema registry archive
+#[openapi(
+    summary = "Import schemas",
+    description = "Import an archive produced by the schemas export. Deployments and subscriptions already registered are skipped, hence the import can be safely retried. Either the whole archive is imported, or nothing is.",
+    operation_id = "import_schemas",
+    tags = "schemas",
+    parameters(query(
+        name = "force",
+        description = "If true, the deployments are imported even if they contain incompatible changes with respect to the registered service revisions.",
+        required = false,
+        style = "simple",
+        allow_empty_value = false,
+        schema = "bool",
+    ))
+)]
+pub async fn import_schemas<W>(
+    State(state): State<AdminServiceState<W>>,
+    Query(ImportSchemasParams { force }): Query<ImportSchemasParams>,
+    #[request_body(required = true)] Json(archive): Json<SchemasArchive>,
+) -> Result<Json<ImportSchemasResponse>, MetaApiError> {
+    let force = if force.unwrap_or_default() {
+        Force::Yes
+    } else {
+        Force::No
+    };
+
+    Ok(state
+        .meta_handle()
+        .import_schemas(archive, force)
+        .await?
+        .into())
+}
+
+/// Reconcile the schema registry against a manifest
+#[openapi(
+    summary = "Reconcile schemas",
+    description = "Reconcile the schema registry against the given manifest, registering the missing or changed deployments and subscriptions, and updating the services visibility. Returns the planned changes, which are applied unless `dry_run` is set. In dry-run mode, the returned plan id can be used to apply the shown changes. Either all the changes are applied, or none is.",
+    operation_id = "reconcile_schemas",
+    tags = "schemas",
+    parameters(
+        query(
+            name = "dry_run",
+            description = "If true, the changes are computed but not applied.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "bool",
+        ),
+        query(
+            name = "prune",
+            description = "If true, the deployments missing from the manifest are drained, and the subscriptions missing from the manifest are deleted. Deployments serving the latest revision of a service can't be drained: they are removed together with their services if `force` is set, otherwise the reconciliation fails.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "bool",
+        ),
+        query(
+            name = "force",
+            description = "If true, the deployments whose discovered services changed are registered again, even if this breaks the in-flight invocations.",
+            required = false,
+            style = "simple",
+            allow_empty_value = false,
+            schema = "bool",
+        )
+    )
+)]
+pub async fn reconcile_schemas<W>(
+    State(state): State<AdminServiceState<W>>,
+    Query(ReconcileParams {
+        dry_run,
+        prune,
+        force,
+    }): Query<ReconcileParams>,
+    #[request_body(required = true)] Json(manifest): Json<Manifest>,
+) -> Result<Json<ReconcilePlan>, MetaApiError> {
+    let deployments = manifest
+        .deployments
+        .into_iter()
+        .map(|deployment| {
+            Ok(match deployment {
+                ManifestDeployment::Http {
+                    uri,
+                    additional_headers,
+                } => DiscoverEndpoint::new(
+                    Endpoint::Http(uri, Default::default()),
+                    additional_headers.unwrap_or_default().into(),
+                ),
+                ManifestDeployment::Lambda {
+                    arn,
+                    assume_role_arn,
+                    additional_headers,
+                } => DiscoverEndpoint::new(
+                    Endpoint::Lambda(
+                        arn.parse().map_err(|e: InvalidLambdaARN| {
+                            MetaApiError::InvalidField("arn", e.to_string())
+                        })?,
+                        assume_role_arn.map(Into::into),
+                    ),
+                    additional_headers.unwrap_or_default().into(),
+                ),
+            })
+        })
+        .collect::<Result<Vec<_>, MetaApiError>>()?;
+
+    let force = if force.unwrap_or_default() {
+        Force::Yes
+    } else {
+        Force::No
+    };
+    let apply_changes = if dry_run.unwrap_or_default() {
+        ApplyMode::DryRun
+    } else {
+        ApplyMode::Apply
+    };
+
+    Ok(state
+        .meta_handle()
+        .reconcile(
+            deployments,
+            manifest.services,
+            manifest.subscriptions,
+            prune.unwrap_or_default(),
+            force,
+            apply_changes,
+        )
+        .await?
+        .into())
+}
+
+/// Apply a reconcile plan
+#[openapi(
+    summary = "Apply reconcile plan",
+    description = "Apply the changes of a reconcile plan computed in dry-run mode, without reconciling the manifest again. Fails if the schema registry changed since computing the plan.",
+    operation_id = "apply_reconcile_plan",
+    tags = "schemas",
+    parameters(path(
+        name = "plan",
+        description = "Reconcile plan identifier",
+        schema = "u64"
+    ))
+)]
+pub async fn apply_reconcile_plan<W>(
+    State(state): State<AdminServiceState<W>>,
+    Path(plan_id): Path<u64>,
+) -> Result<Json<ReconcilePlan>, MetaApiError> {
+    Ok(state
+        .meta_handle()
+        .apply_reconcile_plan(plan_id)
+        .await?
+        .into())
+}
@@ -70,7 +70,7 @@ pub async fn get_service<W>(
 /// Modify a service
 #[openapi(
     summary = "Modify a service",
-    description = "Modify a registered service.",
+    description = "Modify the visibility, the configuration and the call policy of a registered service.",
     operation_id = "modify_service",
     tags = "service",
     parameters(path(
@@ -82,13 +82,53 @@ pub async fn get_service<W>(
 pub async fn modify_service<W>(
     State(state): State<AdminServiceState<W>>,
     Path(service_name): Path<String>,
-    #[request_body(required = true)] Json(ModifyServiceRequest { public }): Json<
-        ModifyServiceRequest,
-    >,
+    #[request_body(required = true)] Json(ModifyServiceRequest {
+        public,
+        configuration,
+        call_policy,
+    }): Json<ModifyServiceRequest>,
 ) -> Result<Json<ServiceMetadata>, MetaApiError> {
     state
         .meta_handle()
-        .modify_service(service_name.clone(), public)
+        .modify_service(service_name.clone(), public, configuration, call_policy)
+        .await?;
+
+    state
+        .schemas()
+        .resolve_latest_service_metadata(&service_name)
+        .map(Into::into)
+        .ok_or_else(|| MetaApiError::ServiceNotFound(service_name))
+}
+
+/// Modify the routing of a service
+#[openapi(
+    summary = "Modify the routing of a service",
+    description = "Modify how the new invocations of a service are routed among its revisions. Use it to gradually shift the traffic to a new revision, or to pin the invocations carrying a given header to a revision.",
+    operation_id = "modify_service_routing",
+    tags = "service",
+    parameters(path(
+        name = "service",
+        description = "Fully qualified service name.",
+        schema = "std::string::String"
+    ))
+)]
+pub async fn modify_service_routing<W>(
+    State(state): State<AdminServiceState<W>>,
+    Path(service_name): Path<String>,
+    #[request_body(required = true)] Json(ModifyServiceRoutingRequest {
+        weights,
+        header_pins,
+    }): Json<ModifyServiceRoutingRequest>,
+) -> Result<Json<ServiceMetadata>, MetaApiError> {
+    state
+        .meta_handle()
+        .modify_service_routing(
+            service_name.clone(),
+            ServiceRouting {
+                weights,
+                header_pins,
+            },
+        )
         .await?;
 
     state
@@ -138,6 +138,36 @@ pub async fn list_subscriptions<W>(
     .into()
 }
 
+/// Update subscription.
+#[openapi(
+    summary = "Update subscription",
+    description = "Update the sink and the options of a subscription, or pause and resume it. The subscription retains its consumed offsets.",
+    operation_id = "update_subscription",
+    tags = "subscription",
+    parameters(path(
+        name = "subscription",
+        description = "Subscription identifier",
+        schema = "std::string::String"
+    ))
+)]
+pub async fn update_subscription<W>(
+    State(state): State<AdminServiceState<W>>,
+    Path(subscription_id): Path<SubscriptionId>,
+    #[request_body(required = true)] Json(payload): Json<UpdateSubscriptionRequest>,
+) -> Result<Json<SubscriptionResponse>, MetaApiError> {
+    let subscription = state
+        .meta_handle()
+        .update_subscription(
+            subscription_id,
+            payload.sink,
+            payload.options,
+            payload.paused,
+        )
+        .await?;
+
+    Ok(SubscriptionResponse::from(subscription).into())
+}
+
 /// Delete subscription.
 #[openapi(
     summary = "Delete subscription",
@@ -170,3 +200,37 @@ pub async fn delete_subscription<W>(
 
     Ok(StatusCode::ACCEPTED)
 }
+
+/// Reset subscription offsets.
+#[openapi(
+    summary = "Reset subscription offsets",
+    description = "Reset the consumed offsets of a subscription to the earliest or latest offsets, or to a timestamp, in order to replay or skip events. Events consumed again after the reset are not deduplicated against the previously processed ones. Only Kafka subscriptions are supported.",
+    operation_id = "reset_subscription_offsets",
+    tags = "subscription",
+    parameters(path(
+        name = "subscription",
+        description = "Subscription identifier",
+        schema = "std::string::String"
+    )),
+    responses(
+        ignore_return_type = true,
+        response(
+            status = "200",
+            description = "OK",
+            content = "Json<SubscriptionResponse>",
+        ),
+        from_type = "MetaApiError",
+    )
+)]
+pub async fn reset_subscription_offsets<W>(
+    State(state): State<AdminServiceState<W>>,
+    Path(subscription_id): Path<SubscriptionId>,
+    #[request_body(required = true)] Json(payload): Json<ResetSubscriptionOffsetsRequest>,
+) -> Result<Json<SubscriptionResponse>, MetaApiError> {
+    let subscription = state
+        .meta_handle()
+        .reset_subscription_offsets(subscription_id, payload.to)
+        .await?;
+
+    Ok(SubscriptionResponse::from(subscription).into())
+}
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::error_handling::HandleErrorLayer;
@@ -21,6 +22,7 @@ use restate_schema_impl::Schemas;
 use restate_storage_query_datafusion::context::QueryContext;
 use tracing::info;
 
+use crate::drained_deployments::{DrainedDeploymentsRemover, PinnedInvocations};
 use crate::{rest_api, state, storage_query};
 use crate::{Error, Options};
 
@@ -47,14 +49,45 @@ impl AdminService {
         worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
         query_context: Option<QueryContext>,
     ) -> Result<(), Error> {
-        let rest_state =
-            state::AdminServiceState::new(self.meta_handle, self.schemas, worker_handle);
+        let pinned_invocations = query_context.clone().map(PinnedInvocations::new);
+        if let (Some(interval), Some(pinned_invocations)) = (
+            self.opts.drained_deployments_removal_interval,
+            pinned_invocations.clone(),
+        ) {
+            tokio::spawn(
+                DrainedDeploymentsRemover::new(
+                    self.schemas.clone(),
+                    self.meta_handle.clone(),
+                    pinned_invocations,
+                    interval.into(),
+                )
+                .run(drain.clone()),
+            );
+        }
+
+        let rest_state = state::AdminServiceState::new(
+            self.meta_handle,
+            self.schemas,
+            worker_handle,
+            pinned_invocations,
+        );
 
         let router = axum::Router::new();
 
         // Stitch query http endpoint if enabled
         let router = if let Some(query_context) = query_context {
-            let query_state = Arc::new(state::QueryServiceState { query_context });
+            let mut role_query_contexts = HashMap::with_capacity(self.opts.query_roles.len());
+            for (role, query_role) in self.opts.query_roles {
+                let restricted = query_context
+                    .restricted(&query_role.tables, query_role.allow_mutations)
+                    .await
+                    .map_err(|source| Error::QueryRole { role, source })?;
+                role_query_contexts.insert(query_role.token, restricted);
+            }
+            let query_state = Arc::new(state::QueryServiceState {
+                query_context,
+                role_query_contexts,
+            });
             // Merge storage query router
             router.merge(storage_query::create_router(query_state))
         } else {
@@ -9,28 +9,41 @@
 // by the Apache License, Version 2.0.
 //
 
+use crate::drained_deployments::PinnedInvocations;
+
 use restate_meta::MetaHandle;
 use restate_schema_impl::Schemas;
 use restate_storage_query_datafusion::context::QueryContext;
+use std::collections::HashMap;
 
 #[derive(Clone, derive_builder::Builder)]
 pub struct AdminServiceState<W> {
     meta_handle: MetaHandle,
     schemas: Schemas,
     worker_handle: W,
+    pinned_invocations: Option<PinnedInvocations>,
 }
 
 #[derive(Clone)]
 pub struct QueryServiceState {
     pub query_context: QueryContext,
+    /// Query contexts restricted to the tables of each role, by token.
+    /// If empty, queries run unrestricted on `query_context`, which cannot run write statements.
+    pub role_query_contexts: HashMap<String, QueryContext>,
 }
 
 impl<W> AdminServiceState<W> {
-    pub fn new(meta_handle: MetaHandle, schemas: Schemas, worker_handle: W) -> Self {
+    pub fn new(
+        meta_handle: MetaHandle,
+        schemas: Schemas,
+        worker_handle: W,
+        pinned_invocations: Option<PinnedInvocations>,
+    ) -> Self {
         Self {
             meta_handle,
             schemas,
             worker_handle,
+            pinned_invocations,
         }
     }
 
@@ -45,4 +58,8 @@ impl<W> AdminServiceState<W> {
     pub fn worker_handle(&self) -> &W {
         &self.worker_handle
     }
+
+    pub fn pinned_invocations(&self) -> Option<&PinnedInvocations> {
+        self.pinned_invocations.as_ref()
+    }
 }
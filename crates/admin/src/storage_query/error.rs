@@ -26,6 +26,8 @@ use serde::Serialize;
 pub enum StorageApiError {
     #[error(transparent)]
     DataFusionError(#[from] DataFusionError),
+    #[error("missing or unknown query role token")]
+    Unauthorized,
 }
 
 /// # Error description response
@@ -38,7 +40,10 @@ struct ErrorDescriptionResponse {
 
 impl IntoResponse for StorageApiError {
     fn into_response(self) -> Response {
-        let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+        let status_code = match &self {
+            StorageApiError::DataFusionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            StorageApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+        };
 
         (
             status_code,
@@ -59,6 +64,9 @@ impl ToResponses for StorageApiError {
                 "400".into() => okapi::openapi3::RefOr::Object(
                     okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
                 ),
+                "401".into() => okapi::openapi3::RefOr::Object(
+                    okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
+                ),
                 "403".into() => okapi::openapi3::RefOr::Object(
                     okapi::openapi3::Response { content: error_media_type.clone(), ..Default::default() }
                 ),
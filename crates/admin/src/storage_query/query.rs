@@ -13,6 +13,7 @@ use std::sync::Arc;
 
 use axum::body::StreamBody;
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::{http, Json};
 use bytes::Bytes;
@@ -28,9 +29,11 @@ use datafusion::error::DataFusionError;
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use futures::{stream, StreamExt};
 use okapi_operation::*;
+use restate_storage_query_datafusion::context::QueryContext;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_with::serde_as;
+use subtle::ConstantTimeEq;
 
 use crate::state::QueryServiceState;
 
@@ -41,7 +44,10 @@ use super::error::StorageApiError;
 pub struct QueryRequest {
     /// # Query
     ///
-    /// SQL query to run against the storage
+    /// SQL query to run against the storage.
+    /// Besides `SELECT`, `DELETE FROM state [WHERE ...]` deletes state entries,
+    /// and `CALL restate.cancel(...)`/`CALL restate.kill(...)` terminate invocations,
+    /// if the query role of the request is allowed to run write statements.
     #[serde_as(as = "serde_with::DisplayFromStr")]
     #[schemars(with = "String")]
     pub query: String,
@@ -50,17 +56,17 @@ pub struct QueryRequest {
 /// Query storage
 #[openapi(
     summary = "Query storage",
-    description = "Query the storage API",
+    description = "Query the storage API. When query roles are configured, the request must authenticate with the bearer token of a role, and can access only the tables of that role.",
     operation_id = "query",
     tags = "storage",
     responses(ignore_return_type = true, from_type = "StorageApiError")
 )]
 pub async fn query(
     State(state): State<Arc<QueryServiceState>>,
+    headers: HeaderMap,
     #[request_body(required = true)] Json(payload): Json<QueryRequest>,
 ) -> Result<impl IntoResponse, StorageApiError> {
-    let stream = state
-        .query_context
+    let stream = query_context(&state, &headers)?
         .execute(payload.query.as_str())
         .await
         .map_err(StorageApiError::DataFusionError)?;
@@ -121,6 +127,35 @@ pub async fn query(
     ))
 }
 
+/// Returns the query context of the role authenticated by the bearer token of the request.
+fn query_context<'a>(
+    state: &'a QueryServiceState,
+    headers: &HeaderMap,
+) -> Result<&'a QueryContext, StorageApiError> {
+    if state.role_query_contexts.is_empty() {
+        return Ok(&state.query_context);
+    }
+
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StorageApiError::Unauthorized)?;
+
+    // Compare the token with all the role tokens in constant time, not to leak them through timing
+    state
+        .role_query_contexts
+        .iter()
+        .fold(None, |found, (role_token, query_context)| {
+            if bool::from(role_token.as_bytes().ct_eq(token.as_bytes())) {
+                Some(query_context)
+            } else {
+                found
+            }
+        })
+        .ok_or(StorageApiError::Unauthorized)
+}
+
 fn convert_schema(schema: SchemaRef) -> SchemaRef {
     let mut fields = Vec::with_capacity(schema.fields.len());
     for field in schema.fields.iter() {
@@ -187,3 +222,69 @@ enum LabelledStream {
     Batch(Result<RecordBatch, DataFusionError>),
     End,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::ptr;
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::AUTHORIZATION, authorization.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn without_query_roles() {
+        let state = QueryServiceState {
+            query_context: QueryContext::default(),
+            role_query_contexts: HashMap::new(),
+        };
+
+        assert!(ptr::eq(
+            query_context(&state, &HeaderMap::new()).unwrap(),
+            &state.query_context
+        ));
+        assert!(ptr::eq(
+            query_context(&state, &headers("Bearer token")).unwrap(),
+            &state.query_context
+        ));
+    }
+
+    #[test]
+    fn query_role_tokens() {
+        let state = QueryServiceState {
+            query_context: QueryContext::default(),
+            role_query_contexts: HashMap::from([
+                ("token-a".to_owned(), QueryContext::default()),
+                ("token-b".to_owned(), QueryContext::default()),
+            ]),
+        };
+
+        for token in ["token-a", "token-b"] {
+            assert!(ptr::eq(
+                query_context(&state, &headers(&format!("Bearer {token}"))).unwrap(),
+                &state.role_query_contexts[token]
+            ));
+        }
+        for authorization in [
+            "Bearer token-c",
+            "Bearer token-",
+            "Bearer token-aa",
+            "Bearer ",
+            "Basic token-a",
+            "token-a",
+        ] {
+            assert!(matches!(
+                query_context(&state, &headers(authorization)),
+                Err(StorageApiError::Unauthorized)
+            ));
+        }
+        assert!(matches!(
+            query_context(&state, &HeaderMap::new()),
+            Err(StorageApiError::Unauthorized)
+        ));
+    }
+}
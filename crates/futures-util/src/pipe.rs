@@ -83,9 +83,10 @@ enum PipeState {
 /// * _Ready_
 /// * _Closed_: Terminal state
 ///
-/// The state becomes _Ready_ when [`Pipe::poll_next_input`] returns [`Poll::Ready`] with a value.
+/// The state becomes _Ready_ when [`Pipe::poll_next_input`] returns [`Poll::Ready`] with a value,
+/// or when [`Pipe::poll_ready`] returns [`Poll::Ready`] with [`Ok`].
 /// After sending a message with [`Pipe::write`], the state transitions back to _NotReady_,
-/// requiring to invoke [`Pipe::poll_next_input`] again before the next [`Pipe::write`].
+/// requiring to invoke [`Pipe::poll_next_input`] or [`Pipe::poll_ready`] again before the next [`Pipe::write`].
 #[pin_project]
 pub struct Pipe<T, In, U, Target> {
     #[pin]
@@ -114,6 +115,30 @@ where
         }
     }
 
+    /// Waits for the target to be ready, without receiving from the input.
+    ///
+    /// Use it to [`Self::write`] messages which don't originate from the input,
+    /// e.g. when a single input message maps to many output messages.
+    pub fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), PipeError>> {
+        let this = self.project();
+        let state = this.state;
+
+        match state {
+            PipeState::NotReady => match ready!(this.pipe_target.poll_ready(cx)) {
+                Ok(_) => {
+                    *state = PipeState::Ready;
+                    Poll::Ready(Ok(()))
+                }
+                Err(err) => {
+                    *state = PipeState::Closed(err);
+                    Poll::Ready(Err(err))
+                }
+            },
+            PipeState::Ready => Poll::Ready(Ok(())),
+            PipeState::Closed(err) => Poll::Ready(Err(*err)),
+        }
+    }
+
     pub fn poll_next_input(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -557,6 +582,36 @@ mod tests {
         pipe_handle.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn pipe_write_many_per_input() {
+        let (input_tx, input_rx) = mpsc::channel(1);
+        let (output_tx, mut output_rx) = mpsc::channel(1);
+
+        let pipe = Pipe::new(
+            ReceiverPipeInput::new(input_rx, "input"),
+            new_sender_pipe_target(output_tx, "output"),
+        );
+        tokio::pin!(pipe);
+
+        input_tx.send(3u32).await.unwrap();
+        let n = poll_fn(|cx| pipe.as_mut().poll_next_input(cx))
+            .await
+            .unwrap();
+        pipe.as_mut().write(0).unwrap();
+        for i in 1..n {
+            // The output channel has capacity 1, the pipe is ready once the previous message is received
+            let (ready, received) =
+                tokio::join!(poll_fn(|cx| pipe.as_mut().poll_ready(cx)), output_rx.recv());
+            ready.unwrap();
+            assert_eq!(received.unwrap(), i - 1);
+            pipe.as_mut().write(i).unwrap();
+        }
+        let (ready, received) =
+            tokio::join!(poll_fn(|cx| pipe.as_mut().poll_ready(cx)), output_rx.recv());
+        ready.unwrap();
+        assert_eq!(received.unwrap(), n - 1);
+    }
+
     #[tokio::test]
     async fn multi_input_pipe() {
         let (input_1_tx, input_1_rx) = mpsc::channel(2);
@@ -8,10 +8,27 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use anyhow::anyhow;
+use base64::Engine;
+use bytes::{Buf, Bytes};
+use hyper::client::HttpConnector;
+use hyper::{StatusCode, Uri};
+use hyper_rustls::HttpsConnector;
 use prost::{encoding, Message};
+use prost_reflect::DeserializeOptions;
 use restate_pb::restate::Event;
-use restate_schema_api::subscription::{FieldRemapType, InputEventRemap};
+use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper};
+use restate_schema_api::subscription::{
+    FieldRemapType, InputEventRemap, PayloadDecoding, PayloadFormat,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default timeout of the requests to the schema registry, see [`PayloadDecoder::with_schema_registry_timeout`].
+pub const DEFAULT_SCHEMA_REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug, thiserror::Error)]
 #[error("Field {field_name} cannot be mapped to field tag {tag} because it's not a valid UTF-8 string: {reason}")]
@@ -61,6 +78,11 @@ impl Message for MappedEvent<'_> {
     where
         B: bytes::BufMut,
     {
+        // The decoded payload is already an encoded input message. Encoding it first lets the
+        // remapped fields below take precedence, as Protobuf merges concatenated messages.
+        if self.1.payload_decoding.is_some() {
+            buf.put_slice(&self.0.payload);
+        }
         if self.1.key.is_some() && !self.0.key.is_empty() {
             encoding::bytes::encode(self.1.key.as_ref().unwrap().0, &self.0.key, buf);
         }
@@ -95,7 +117,11 @@ impl Message for MappedEvent<'_> {
 
     #[inline]
     fn encoded_len(&self) -> usize {
-        (if self.1.key.is_some() && !self.0.key.is_empty() {
+        (if self.1.payload_decoding.is_some() {
+            self.0.payload.len()
+        } else {
+            0
+        }) + (if self.1.key.is_some() && !self.0.key.is_empty() {
             encoding::bytes::encoded_len(self.1.key.as_ref().unwrap().0, &self.0.key)
         } else {
             0
@@ -124,3 +150,385 @@ impl fmt::Debug for MappedEvent<'_> {
         fmt::Debug::fmt(&self.0, f)
     }
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum PayloadDecodingError {
+    #[error("the payload is not framed with the Confluent wire format")]
+    Framing,
+    #[error("cannot resolve schema {id} from the schema registry: {reason}")]
+    SchemaRegistry { id: u32, reason: anyhow::Error },
+    #[error("the schema registry is unavailable to resolve schema {id}: {reason}")]
+    SchemaRegistryUnavailable { id: u32, reason: anyhow::Error },
+    #[error("cannot decode the avro payload: {0}")]
+    Avro(#[from] apache_avro::Error),
+    #[error("cannot parse the json payload: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("cannot transcode the payload to the input message: {0}")]
+    Transcoding(anyhow::Error),
+}
+
+impl PayloadDecodingError {
+    /// Returns true if decoding the same payload again can succeed, because the schema registry
+    /// couldn't be reached, timed out or failed with a server error. Any other error, including
+    /// the schema registry rejecting the schema id, is caused by the payload.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, PayloadDecodingError::SchemaRegistryUnavailable { .. })
+    }
+}
+
+type JsonToProtobuf =
+    dyn Fn(&str, &str, serde_json::Value) -> Result<Bytes, PayloadDecodingError> + Send + Sync;
+
+/// Decodes event payloads to the input message of the sink method, as specified by [`PayloadDecoding`].
+///
+/// Avro schemas are resolved from the schema registry once, and then cached for the lifetime of the decoder.
+#[derive(Clone)]
+pub struct PayloadDecoder {
+    json_to_protobuf: Arc<JsonToProtobuf>,
+    http_client: hyper::Client<HttpsConnector<HttpConnector>>,
+    schema_registry_timeout: Duration,
+    avro_schemas: Arc<Mutex<HashMap<(String, u32), Arc<apache_avro::Schema>>>>,
+}
+
+impl fmt::Debug for PayloadDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadDecoder").finish_non_exhaustive()
+    }
+}
+
+impl PayloadDecoder {
+    pub fn new<JMR>(json_mapper_resolver: JMR) -> Self
+    where
+        JMR: JsonMapperResolver + Send + Sync + 'static,
+    {
+        let json_to_protobuf = move |service_name: &str, method_name: &str, json| {
+            let (json_to_protobuf_mapper, _) = json_mapper_resolver
+                .resolve_json_mapper_for_service(service_name, method_name)
+                .ok_or_else(|| {
+                    PayloadDecodingError::Transcoding(anyhow!(
+                        "cannot find the input message of {}/{}",
+                        service_name,
+                        method_name
+                    ))
+                })?;
+            json_to_protobuf_mapper
+                .json_value_to_protobuf(json, &DeserializeOptions::default())
+                .map_err(PayloadDecodingError::Transcoding)
+        };
+
+        Self {
+            json_to_protobuf: Arc::new(json_to_protobuf),
+            http_client: http_client(),
+            schema_registry_timeout: DEFAULT_SCHEMA_REGISTRY_TIMEOUT,
+            avro_schemas: Default::default(),
+        }
+    }
+
+    /// Sets the timeout of the requests to the schema registry, after which decoding fails
+    /// with a transient error.
+    pub fn with_schema_registry_timeout(mut self, timeout: Duration) -> Self {
+        self.schema_registry_timeout = timeout;
+        self
+    }
+
+    pub async fn decode(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        payload_decoding: &PayloadDecoding,
+        payload: Bytes,
+    ) -> Result<Bytes, PayloadDecodingError> {
+        let (schema_id, mut payload) = if payload_decoding.schema_registry_url.is_some() {
+            let (schema_id, payload) = split_confluent_framing(payload)?;
+            (Some(schema_id), payload)
+        } else {
+            (None, payload)
+        };
+
+        match payload_decoding.format {
+            PayloadFormat::Protobuf => {
+                if schema_id.is_some() {
+                    skip_confluent_message_indexes(&mut payload)?;
+                }
+                Ok(payload)
+            }
+            PayloadFormat::Json => {
+                let json = serde_json::from_slice(&payload)?;
+                (self.json_to_protobuf)(service_name, method_name, json)
+            }
+            PayloadFormat::Avro => {
+                let (Some(schema_registry_url), Some(schema_id)) =
+                    (&payload_decoding.schema_registry_url, schema_id)
+                else {
+                    return Err(PayloadDecodingError::Framing);
+                };
+                let schema = self.avro_schema(schema_registry_url, schema_id).await?;
+                let value = apache_avro::from_avro_datum(&schema, &mut payload.reader(), None)?;
+                (self.json_to_protobuf)(service_name, method_name, avro_to_json(value)?)
+            }
+        }
+    }
+
+    async fn avro_schema(
+        &self,
+        schema_registry_url: &str,
+        id: u32,
+    ) -> Result<Arc<apache_avro::Schema>, PayloadDecodingError> {
+        let cache_key = (schema_registry_url.to_owned(), id);
+        if let Some(schema) = self
+            .avro_schemas
+            .lock()
+            .expect("avro schemas lock should not be poisoned")
+            .get(&cache_key)
+        {
+            return Ok(Arc::clone(schema));
+        }
+
+        let schema = fetch_avro_schema(
+            &self.http_client,
+            self.schema_registry_timeout,
+            schema_registry_url,
+            id,
+        )
+        .await?;
+
+        let schema = Arc::new(schema);
+        self.avro_schemas
+            .lock()
+            .expect("avro schemas lock should not be poisoned")
+            .insert(cache_key, Arc::clone(&schema));
+        Ok(schema)
+    }
+}
+
+fn http_client() -> hyper::Client<HttpsConnector<HttpConnector>> {
+    hyper::Client::builder().build(
+        hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build(),
+    )
+}
+
+/// Fetches the schema with the given id from the Confluent schema registry.
+async fn fetch_avro_schema(
+    http_client: &hyper::Client<HttpsConnector<HttpConnector>>,
+    timeout: Duration,
+    schema_registry_url: &str,
+    id: u32,
+) -> Result<apache_avro::Schema, PayloadDecodingError> {
+    #[derive(Deserialize)]
+    struct SchemaResponse {
+        schema: String,
+    }
+
+    let uri = format!(
+        "{}/schemas/ids/{}",
+        schema_registry_url.trim_end_matches('/'),
+        id
+    )
+    .parse::<Uri>()
+    .map_err(|e| PayloadDecodingError::SchemaRegistry {
+        id,
+        reason: e.into(),
+    })?;
+    let unavailable =
+        move |reason: anyhow::Error| PayloadDecodingError::SchemaRegistryUnavailable { id, reason };
+
+    let body = tokio::time::timeout(timeout, async {
+        let response = http_client
+            .get(uri)
+            .await
+            .map_err(|e| unavailable(e.into()))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(id, status));
+        }
+        hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| unavailable(e.into()))
+    })
+    .await
+    .map_err(|_| unavailable(anyhow!("timed out after {:?}", timeout)))??;
+
+    async {
+        let schema_response: SchemaResponse = serde_json::from_slice(&body)?;
+        Ok(apache_avro::Schema::parse_str(&schema_response.schema)?)
+    }
+    .await
+    .map_err(|reason| PayloadDecodingError::SchemaRegistry { id, reason })
+}
+
+/// Only server errors are transient, while client errors such as an unknown schema id are
+/// caused by the payload and would fail the same way when retried.
+fn status_error(id: u32, status: StatusCode) -> PayloadDecodingError {
+    let reason = anyhow!("unexpected status code {}", status);
+    if status.is_server_error() {
+        PayloadDecodingError::SchemaRegistryUnavailable { id, reason }
+    } else {
+        PayloadDecodingError::SchemaRegistry { id, reason }
+    }
+}
+
+/// Splits the magic byte and the schema id of the Confluent wire format from the payload.
+fn split_confluent_framing(mut payload: Bytes) -> Result<(u32, Bytes), PayloadDecodingError> {
+    if payload.len() < 5 || payload.get_u8() != 0 {
+        return Err(PayloadDecodingError::Framing);
+    }
+    let schema_id = payload.get_u32();
+    Ok((schema_id, payload))
+}
+
+/// Skips the indexes of the message type within the schema, which the Confluent wire format
+/// prepends to Protobuf payloads. The payload is always decoded to the input message of the sink.
+fn skip_confluent_message_indexes(payload: &mut Bytes) -> Result<(), PayloadDecodingError> {
+    let count = encoding::decode_varint(payload).map_err(|_| PayloadDecodingError::Framing)?;
+    // Zig-zag encoded, where 0 is a shorthand for the first message type
+    let count = (count >> 1) as i64 ^ -((count & 1) as i64);
+    for _ in 0..count {
+        encoding::decode_varint(payload).map_err(|_| PayloadDecodingError::Framing)?;
+    }
+    Ok(())
+}
+
+/// Converts an avro value to the JSON representation expected by the Protobuf JSON mapping.
+fn avro_to_json(
+    value: apache_avro::types::Value,
+) -> Result<serde_json::Value, PayloadDecodingError> {
+    use apache_avro::types::Value;
+
+    Ok(match value {
+        Value::Bytes(bytes) | Value::Fixed(_, bytes) => {
+            serde_json::Value::String(base64::prelude::BASE64_STANDARD.encode(bytes))
+        }
+        Value::Enum(_, symbol) => serde_json::Value::String(symbol),
+        Value::Union(_, value) => avro_to_json(*value)?,
+        Value::Array(values) => serde_json::Value::Array(
+            values
+                .into_iter()
+                .map(avro_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => serde_json::Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| Ok((k, avro_to_json(v)?)))
+                .collect::<Result<_, PayloadDecodingError>>()?,
+        ),
+        Value::Record(fields) => serde_json::Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| Ok((k, avro_to_json(v)?)))
+                .collect::<Result<_, PayloadDecodingError>>()?,
+        ),
+        value => serde_json::Value::try_from(value)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confluent_protobuf_framing() {
+        // magic byte, schema id 42, message indexes [0]
+        let mut payload = Bytes::from_static(&[0, 0, 0, 0, 42, 0, 8, 1]);
+
+        let (schema_id, mut payload) = split_confluent_framing(payload.split_to(8)).unwrap();
+        skip_confluent_message_indexes(&mut payload).unwrap();
+
+        assert_eq!(schema_id, 42);
+        assert_eq!(payload, Bytes::from_static(&[8, 1]));
+    }
+
+    #[test]
+    fn confluent_protobuf_framing_with_nested_message_indexes() {
+        // magic byte, schema id 1, message indexes [1, 2] (zig-zag encoded length 2)
+        let payload = Bytes::from_static(&[0, 0, 0, 0, 1, 4, 1, 2, 8, 1]);
+
+        let (schema_id, mut payload) = split_confluent_framing(payload).unwrap();
+        skip_confluent_message_indexes(&mut payload).unwrap();
+
+        assert_eq!(schema_id, 1);
+        assert_eq!(payload, Bytes::from_static(&[8, 1]));
+    }
+
+    #[test]
+    fn avro_bytes_are_base64_encoded() {
+        use apache_avro::types::Value;
+
+        let json = avro_to_json(Value::Record(vec![
+            ("data".to_owned(), Value::Bytes(vec![1, 2, 3])),
+            (
+                "name".to_owned(),
+                Value::Union(1, Box::new(Value::String("Francesco".to_owned()))),
+            ),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({"data": "AQID", "name": "Francesco"})
+        );
+    }
+
+    /// Serves a single request of the schema registry with the given status line,
+    /// or never responds if `None`. Returns the url of the schema registry.
+    async fn schema_registry(status_line: Option<&'static str>) -> String {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            match status_line {
+                Some(status_line) => {
+                    let response = format!("{status_line}\r\ncontent-length: 0\r\n\r\n");
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                }
+                None => std::future::pending::<()>().await,
+            }
+        });
+        url
+    }
+
+    async fn resolve_avro_schema(
+        schema_registry_url: &str,
+    ) -> Result<apache_avro::Schema, PayloadDecodingError> {
+        fetch_avro_schema(
+            &http_client(),
+            Duration::from_millis(100),
+            schema_registry_url,
+            1,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_schema_is_not_transient() {
+        let url = schema_registry(Some("HTTP/1.1 404 Not Found")).await;
+
+        let err = resolve_avro_schema(&url).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            PayloadDecodingError::SchemaRegistry { id: 1, .. }
+        ));
+        assert!(!err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn schema_registry_server_error_is_transient() {
+        let url = schema_registry(Some("HTTP/1.1 503 Service Unavailable")).await;
+
+        assert!(resolve_avro_schema(&url).await.unwrap_err().is_transient());
+    }
+
+    #[tokio::test]
+    async fn schema_registry_timeout_is_transient() {
+        let url = schema_registry(None).await;
+
+        assert!(resolve_avro_schema(&url).await.unwrap_err().is_transient());
+    }
+}
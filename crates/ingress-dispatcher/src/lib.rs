@@ -8,6 +8,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use bytes::Bytes;
 use bytestring::ByteString;
 use prost::Message;
@@ -15,11 +17,14 @@ use restate_pb::restate::Event;
 use restate_schema_api::subscription::{EventReceiverServiceInstanceType, Sink, Subscription};
 use restate_types::errors::InvocationError;
 use restate_types::identifiers::{FullInvocationId, InvocationUuid, PeerId};
-use restate_types::invocation::{ServiceInvocation, ServiceInvocationSpanContext, SpanRelation};
+use restate_types::invocation::{
+    Header, InvocationFailurePolicy, ServiceInvocation, ServiceInvocationSpanContext, SpanRelation,
+};
 use restate_types::message::{AckKind, MessageIndex};
+use restate_types::time::MillisSinceEpoch;
 use restate_types::GenerationalNodeId;
 use std::fmt::Display;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tokio::sync::{mpsc, oneshot};
 
 // -- Re-export dispatcher service
@@ -28,6 +33,7 @@ mod event_remapping;
 mod service;
 
 pub use event_remapping::Error as EventError;
+pub use event_remapping::{PayloadDecoder, PayloadDecodingError, DEFAULT_SCHEMA_REGISTRY_TIMEOUT};
 pub use service::Error as ServiceError;
 pub use service::Service;
 
@@ -37,6 +43,8 @@ pub type IngressRequestSender = mpsc::UnboundedSender<IngressRequest>;
 pub type IngressRequestReceiver = mpsc::UnboundedReceiver<IngressRequest>;
 pub type IngressResponseSender = oneshot::Sender<IngressResponse>;
 pub type IngressResponseReceiver = oneshot::Receiver<IngressResponse>;
+pub type IngressResponseChunkSender = mpsc::UnboundedSender<Bytes>;
+pub type IngressResponseChunkReceiver = mpsc::UnboundedReceiver<Bytes>;
 pub type AckSender = oneshot::Sender<()>;
 pub type AckReceiver = oneshot::Receiver<()>;
 
@@ -53,13 +61,32 @@ impl IdempotencyMode {
 }
 
 #[derive(Debug)]
-pub struct IngressRequest {
+pub struct IngressRequest(IngressRequestKind);
+
+#[derive(Debug)]
+enum IngressRequestKind {
+    Invocation(IngressInvocation, IngressRequestMode),
+    BatchBackgroundInvocation(Vec<IngressInvocation>, AckSender),
+}
+
+#[derive(Debug)]
+struct IngressInvocation {
     fid: FullInvocationId,
     method_name: ByteString,
     argument: Bytes,
     span_context: ServiceInvocationSpanContext,
-    request_mode: IngressRequestMode,
     idempotency: IdempotencyMode,
+    headers: Vec<Header>,
+    failure_policy: InvocationFailurePolicy,
+}
+
+/// Single invocation of a [`IngressRequest::batch_background_invocation`].
+#[derive(Debug)]
+pub struct BatchInvocation {
+    pub fid: FullInvocationId,
+    pub method_name: ByteString,
+    pub argument: Bytes,
+    pub idempotency: IdempotencyMode,
 }
 
 #[derive(Debug, Clone)]
@@ -91,10 +118,16 @@ impl From<Result<Bytes, InvocationError>> for IngressResponse {
 
 pub type IngressDeduplicationId = (String, MessageIndex);
 
+/// Default retention period of the deduplication entries created by
+/// [`IngressRequest::idempotent_background_invocation`].
+pub const DEFAULT_IDEMPOTENCY_RETENTION_PERIOD: Duration = Duration::from_secs(30 * 60);
+
 #[derive(Debug)]
 enum IngressRequestMode {
     RequestResponse(IngressResponseSender),
+    RequestStreamingResponse(IngressResponseSender, IngressResponseChunkSender),
     DedupFireAndForget(IngressDeduplicationId, AckSender),
+    ExpiringDedupFireAndForget(IngressDeduplicationId, MillisSinceEpoch, AckSender),
     FireAndForget(AckSender),
 }
 
@@ -103,26 +136,67 @@ pub trait DeduplicationId: Display {
 }
 
 impl IngressRequest {
+    fn single(invocation: IngressInvocation, request_mode: IngressRequestMode) -> Self {
+        IngressRequest(IngressRequestKind::Invocation(invocation, request_mode))
+    }
+
     pub fn invocation(
         fid: FullInvocationId,
         method_name: impl Into<ByteString>,
         argument: impl Into<Bytes>,
         related_span: SpanRelation,
         idempotency: IdempotencyMode,
+        headers: Vec<Header>,
     ) -> (Self, IngressResponseReceiver) {
         let span_context = ServiceInvocationSpanContext::start(&fid, related_span);
         let (result_tx, result_rx) = oneshot::channel();
 
         (
-            IngressRequest {
-                fid,
-                method_name: method_name.into(),
-                argument: argument.into(),
-                request_mode: IngressRequestMode::RequestResponse(result_tx),
-                span_context,
-                idempotency,
-            },
+            IngressRequest::single(
+                IngressInvocation {
+                    fid,
+                    method_name: method_name.into(),
+                    argument: argument.into(),
+                    span_context,
+                    idempotency,
+                    headers,
+                    failure_policy: Default::default(),
+                },
+                IngressRequestMode::RequestResponse(result_tx),
+            ),
+            result_rx,
+        )
+    }
+
+    /// Like [`IngressRequest::invocation`], but for server-streaming methods.
+    /// The response chunks are delivered on the returned chunk receiver, which is closed
+    /// right before the final response is sent.
+    pub fn streaming_invocation(
+        fid: FullInvocationId,
+        method_name: impl Into<ByteString>,
+        argument: impl Into<Bytes>,
+        related_span: SpanRelation,
+        headers: Vec<Header>,
+    ) -> (Self, IngressResponseReceiver, IngressResponseChunkReceiver) {
+        let span_context = ServiceInvocationSpanContext::start(&fid, related_span);
+        let (result_tx, result_rx) = oneshot::channel();
+        let (chunk_tx, chunk_rx) = mpsc::unbounded_channel();
+
+        (
+            IngressRequest::single(
+                IngressInvocation {
+                    fid,
+                    method_name: method_name.into(),
+                    argument: argument.into(),
+                    span_context,
+                    idempotency: IdempotencyMode::None,
+                    headers,
+                    failure_policy: Default::default(),
+                },
+                IngressRequestMode::RequestStreamingResponse(result_tx, chunk_tx),
+            ),
             result_rx,
+            chunk_rx,
         )
     }
 
@@ -137,17 +211,99 @@ impl IngressRequest {
         let (ack_tx, ack_rx) = oneshot::channel();
 
         (
-            IngressRequest {
-                fid,
-                method_name: method_name.into(),
-                argument: argument.into(),
-                span_context,
-                request_mode: match ingress_deduplication_id {
+            IngressRequest::single(
+                IngressInvocation {
+                    fid,
+                    method_name: method_name.into(),
+                    argument: argument.into(),
+                    span_context,
+                    idempotency: IdempotencyMode::None,
+                    headers: vec![],
+                    failure_policy: Default::default(),
+                },
+                match ingress_deduplication_id {
                     None => IngressRequestMode::FireAndForget(ack_tx),
                     Some(dedup_id) => IngressRequestMode::DedupFireAndForget(dedup_id, ack_tx),
                 },
-                idempotency: IdempotencyMode::None,
-            },
+            ),
+            ack_rx,
+        )
+    }
+
+    /// Like [`IngressRequest::background_invocation`], but deduplicated using the given idempotency key.
+    /// Retries with the same idempotency key towards the same service instance are acknowledged without being
+    /// executed again, until the retention period expires.
+    pub fn idempotent_background_invocation(
+        fid: FullInvocationId,
+        method_name: impl Into<ByteString>,
+        argument: impl Into<Bytes>,
+        related_span: SpanRelation,
+        idempotency_key: &str,
+        retention_period: Option<Duration>,
+        headers: Vec<Header>,
+    ) -> (Self, AckReceiver) {
+        let span_context = ServiceInvocationSpanContext::start(&fid, related_span);
+        let (ack_tx, ack_rx) = oneshot::channel();
+
+        let dedup_id = (
+            format!(
+                "idempotency-key/{}/{}/{}",
+                fid.service_id.service_name,
+                BASE64_STANDARD.encode(&fid.service_id.key),
+                idempotency_key
+            ),
+            0,
+        );
+        let expiration_time = MillisSinceEpoch::from(
+            SystemTime::now() + retention_period.unwrap_or(DEFAULT_IDEMPOTENCY_RETENTION_PERIOD),
+        );
+
+        (
+            IngressRequest::single(
+                IngressInvocation {
+                    fid,
+                    method_name: method_name.into(),
+                    argument: argument.into(),
+                    span_context,
+                    idempotency: IdempotencyMode::None,
+                    headers,
+                    failure_policy: Default::default(),
+                },
+                IngressRequestMode::ExpiringDedupFireAndForget(dedup_id, expiration_time, ack_tx),
+            ),
+            ack_rx,
+        )
+    }
+
+    /// Background invocation of many services at once.
+    /// The dispatcher fans out the invocations grouped by partition key,
+    /// and notifies the returned [`AckReceiver`] once all of them have been acknowledged.
+    pub fn batch_background_invocation(
+        invocations: Vec<BatchInvocation>,
+        related_span: SpanRelation,
+        headers: Vec<Header>,
+    ) -> (Self, AckReceiver) {
+        let (ack_tx, ack_rx) = oneshot::channel();
+
+        (
+            IngressRequest(IngressRequestKind::BatchBackgroundInvocation(
+                invocations
+                    .into_iter()
+                    .map(|invocation| IngressInvocation {
+                        span_context: ServiceInvocationSpanContext::start(
+                            &invocation.fid,
+                            related_span.clone(),
+                        ),
+                        fid: invocation.fid,
+                        method_name: invocation.method_name,
+                        argument: invocation.argument,
+                        idempotency: invocation.idempotency,
+                        headers: headers.clone(),
+                        failure_policy: Default::default(),
+                    })
+                    .collect(),
+                ack_tx,
+            )),
             ack_rx,
         )
     }
@@ -210,6 +366,16 @@ impl IngressRequest {
         // Generate span context
         let span_context = ServiceInvocationSpanContext::start(&target_fid, related_span);
 
+        // The failure policy travels with the invocation, to be applied by the invoker and the partition processor
+        let subscription_failure_policy = subscription.failure_policy();
+        let failure_policy = InvocationFailurePolicy {
+            max_attempts: subscription_failure_policy.max_attempts,
+            dead_letter: subscription_failure_policy
+                .dead_letter
+                .as_ref()
+                .map(|dead_letter| (dead_letter.cluster.clone(), dead_letter.topic.clone())),
+        };
+
         // Perform event remapping
         let argument = Bytes::from(if let Some(event_remap) = input_event_remap.as_ref() {
             event_remapping::MappedEvent::new(&mut event, event_remap)?.encode_to_vec()
@@ -223,36 +389,53 @@ impl IngressRequest {
                 FullInvocationId::generate(restate_pb::PROXY_SERVICE_NAME, proxying_key);
 
             (
-                IngressRequest {
-                    fid: proxy_fid,
-                    method_name: ByteString::from_static(
-                        restate_pb::PROXY_PROXY_THROUGH_METHOD_NAME,
-                    ),
-                    argument: restate_pb::restate::internal::ProxyThroughRequest {
-                        target_service: target_fid.service_id.service_name.to_string(),
-                        target_method: method.to_string(),
-                        target_key: target_fid.service_id.key,
-                        target_invocation_uuid: target_fid.invocation_uuid.into(),
-                        input: argument,
-                    }
-                    .encode_to_vec()
-                    .into(),
-                    span_context,
+                IngressRequest::single(
+                    IngressInvocation {
+                        fid: proxy_fid,
+                        method_name: ByteString::from_static(
+                            restate_pb::PROXY_PROXY_THROUGH_METHOD_NAME,
+                        ),
+                        argument: restate_pb::restate::internal::ProxyThroughRequest {
+                            target_service: target_fid.service_id.service_name.to_string(),
+                            target_method: method.to_string(),
+                            target_key: target_fid.service_id.key,
+                            target_invocation_uuid: target_fid.invocation_uuid.into(),
+                            input: argument,
+                            max_attempts: failure_policy.max_attempts,
+                            dead_letter_cluster: failure_policy
+                                .dead_letter
+                                .as_ref()
+                                .map(|(cluster, _)| cluster.clone()),
+                            dead_letter_topic: failure_policy
+                                .dead_letter
+                                .as_ref()
+                                .map(|(_, topic)| topic.clone()),
+                        }
+                        .encode_to_vec()
+                        .into(),
+                        span_context,
+                        idempotency: IdempotencyMode::None,
+                        headers: vec![],
+                        failure_policy: Default::default(),
+                    },
                     request_mode,
-                    idempotency: IdempotencyMode::None,
-                },
+                ),
                 ack_rx,
             )
         } else {
             (
-                IngressRequest {
-                    fid: target_fid,
-                    method_name: ByteString::from(&**method),
-                    argument,
-                    span_context,
+                IngressRequest::single(
+                    IngressInvocation {
+                        fid: target_fid,
+                        method_name: ByteString::from(&**method),
+                        argument,
+                        span_context,
+                        idempotency: IdempotencyMode::None,
+                        headers: vec![],
+                        failure_policy,
+                    },
                     request_mode,
-                    idempotency: IdempotencyMode::None,
-                },
+                ),
                 ack_rx,
             )
         })
@@ -271,6 +454,13 @@ pub struct IngressResponseMessage {
     pub ack_target: AckTarget,
 }
 
+#[derive(Debug, Clone)]
+pub struct IngressResponseChunkMessage {
+    pub full_invocation_id: FullInvocationId,
+    pub chunk: Bytes,
+    pub ack_target: AckTarget,
+}
+
 #[derive(Debug, Clone)]
 pub struct AckTarget {
     pub shuffle_target: PeerId,
@@ -296,6 +486,7 @@ impl AckTarget {
 #[derive(Debug)]
 pub enum IngressDispatcherInput {
     Response(IngressResponseMessage),
+    ResponseChunk(IngressResponseChunkMessage),
     MessageAck(MessageIndex),
     DedupMessageAck(String, MessageIndex),
 }
@@ -312,6 +503,10 @@ impl IngressDispatcherInput {
     pub fn response(response: IngressResponseMessage) -> Self {
         IngressDispatcherInput::Response(response)
     }
+
+    pub fn response_chunk(response_chunk: IngressResponseChunkMessage) -> Self {
+        IngressDispatcherInput::ResponseChunk(response_chunk)
+    }
 }
 
 #[derive(Debug)]
@@ -320,6 +515,7 @@ pub enum IngressDispatcherOutput {
         service_invocation: ServiceInvocation,
         from_node_id: GenerationalNodeId,
         deduplication_source: Option<String>,
+        deduplication_expiration_time: Option<MillisSinceEpoch>,
         msg_index: MessageIndex,
     },
     Ack(AckResponse),
@@ -336,12 +532,14 @@ impl IngressDispatcherOutput {
         service_invocation: ServiceInvocation,
         from_node_id: GenerationalNodeId,
         deduplication_source: Option<String>,
+        deduplication_expiration_time: Option<MillisSinceEpoch>,
         msg_index: MessageIndex,
     ) -> Self {
         Self::Invocation {
             service_invocation,
             from_node_id,
             deduplication_source,
+            deduplication_expiration_time,
             msg_index,
         }
     }
@@ -358,6 +556,16 @@ pub mod mocks {
     use restate_test_util::let_assert;
 
     impl IngressRequest {
+        pub fn headers(&self) -> &[Header] {
+            match &self.0 {
+                IngressRequestKind::Invocation(invocation, _) => &invocation.headers,
+                IngressRequestKind::BatchBackgroundInvocation(invocations, _) => invocations
+                    .first()
+                    .map(|invocation| invocation.headers.as_slice())
+                    .unwrap_or_default(),
+            }
+        }
+
         pub fn expect_invocation(
             self,
         ) -> (
@@ -369,15 +577,17 @@ pub mod mocks {
             IngressResponseSender,
         ) {
             let_assert!(
-                IngressRequest {
-                    fid,
-                    method_name,
-                    argument,
-                    span_context,
-                    request_mode: IngressRequestMode::RequestResponse(ingress_response_sender),
-                    idempotency,
-                    ..
-                } = self
+                IngressRequest(IngressRequestKind::Invocation(
+                    IngressInvocation {
+                        fid,
+                        method_name,
+                        argument,
+                        span_context,
+                        idempotency,
+                        ..
+                    },
+                    IngressRequestMode::RequestResponse(ingress_response_sender)
+                )) = self
             );
             (
                 fid,
@@ -389,6 +599,41 @@ pub mod mocks {
             )
         }
 
+        pub fn expect_streaming_invocation(
+            self,
+        ) -> (
+            FullInvocationId,
+            ByteString,
+            Bytes,
+            ServiceInvocationSpanContext,
+            IngressResponseSender,
+            IngressResponseChunkSender,
+        ) {
+            let_assert!(
+                IngressRequest(IngressRequestKind::Invocation(
+                    IngressInvocation {
+                        fid,
+                        method_name,
+                        argument,
+                        span_context,
+                        ..
+                    },
+                    IngressRequestMode::RequestStreamingResponse(
+                        ingress_response_sender,
+                        ingress_response_chunk_sender,
+                    )
+                )) = self
+            );
+            (
+                fid,
+                method_name,
+                argument,
+                span_context,
+                ingress_response_sender,
+                ingress_response_chunk_sender,
+            )
+        }
+
         pub fn expect_background_invocation(
             self,
         ) -> (
@@ -399,14 +644,16 @@ pub mod mocks {
             AckSender,
         ) {
             let_assert!(
-                IngressRequest {
-                    fid,
-                    method_name,
-                    argument,
-                    span_context,
-                    request_mode: IngressRequestMode::FireAndForget(ack_sender),
-                    ..
-                } = self
+                IngressRequest(IngressRequestKind::Invocation(
+                    IngressInvocation {
+                        fid,
+                        method_name,
+                        argument,
+                        span_context,
+                        ..
+                    },
+                    IngressRequestMode::FireAndForget(ack_sender)
+                )) = self
             );
             (fid, method_name, argument, span_context, ack_sender)
         }
@@ -422,14 +669,16 @@ pub mod mocks {
             AckSender,
         ) {
             let_assert!(
-                IngressRequest {
-                    fid,
-                    method_name,
-                    argument,
-                    span_context,
-                    request_mode: IngressRequestMode::DedupFireAndForget(dedup_id, ack_sender),
-                    ..
-                } = self
+                IngressRequest(IngressRequestKind::Invocation(
+                    IngressInvocation {
+                        fid,
+                        method_name,
+                        argument,
+                        span_context,
+                        ..
+                    },
+                    IngressRequestMode::DedupFireAndForget(dedup_id, ack_sender)
+                )) = self
             );
             (
                 fid,
@@ -440,5 +689,68 @@ pub mod mocks {
                 ack_sender,
             )
         }
+
+        pub fn expect_idempotent_background_invocation(
+            self,
+        ) -> (
+            FullInvocationId,
+            ByteString,
+            Bytes,
+            IngressDeduplicationId,
+            MillisSinceEpoch,
+            AckSender,
+        ) {
+            let_assert!(
+                IngressRequest(IngressRequestKind::Invocation(
+                    IngressInvocation {
+                        fid,
+                        method_name,
+                        argument,
+                        ..
+                    },
+                    IngressRequestMode::ExpiringDedupFireAndForget(
+                        dedup_id,
+                        expiration_time,
+                        ack_sender
+                    )
+                )) = self
+            );
+            (
+                fid,
+                method_name,
+                argument,
+                dedup_id,
+                expiration_time,
+                ack_sender,
+            )
+        }
+
+        pub fn expect_batch_background_invocation(
+            self,
+        ) -> (
+            Vec<(FullInvocationId, ByteString, Bytes, IdempotencyMode)>,
+            AckSender,
+        ) {
+            let_assert!(
+                IngressRequest(IngressRequestKind::BatchBackgroundInvocation(
+                    invocations,
+                    ack_sender
+                )) = self
+            );
+            (
+                invocations
+                    .into_iter()
+                    .map(|invocation| {
+                        (
+                            invocation.fid,
+                            invocation.method_name,
+                            invocation.argument,
+                            invocation.idempotency,
+                        )
+                    })
+                    .collect(),
+                ack_sender,
+            )
+        }
     }
 }
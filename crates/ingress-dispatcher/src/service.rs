@@ -10,7 +10,6 @@
 
 use super::*;
 
-use bytes::{BufMut, BytesMut};
 use prost::Message;
 use restate_futures_util::pipe::{
     new_sender_pipe_target, Either, EitherPipeInput, Pipe, PipeError, ReceiverPipeInput,
@@ -19,10 +18,10 @@ use restate_futures_util::pipe::{
 use restate_pb::restate::internal::{
     idempotent_invoke_response, IdempotentInvokeRequest, IdempotentInvokeResponse,
 };
-use restate_types::identifiers::FullInvocationId;
+use restate_types::identifiers::{FullInvocationId, WithPartitionKey};
 use restate_types::invocation::{ServiceInvocationResponseSink, Source};
 use restate_types::GenerationalNodeId;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::future::poll_fn;
 use tokio::select;
 use tokio::sync::mpsc;
@@ -95,6 +94,8 @@ impl Service {
         tokio::pin!(pipe);
 
         let mut handler = DispatcherLoopHandler::new(my_node_id);
+        // Outputs of a batch, written to the pipe one at a time before receiving the next input
+        let mut pending_outputs = VecDeque::new();
 
         loop {
             select! {
@@ -102,16 +103,23 @@ impl Service {
                     info!("Shut down of ResponseDispatcher requested. Shutting down now.");
                     break;
                 },
-                pipe_input = poll_fn(|cx| pipe.as_mut().poll_next_input(cx)) => {
+                ready = poll_fn(|cx| pipe.as_mut().poll_ready(cx)), if !pending_outputs.is_empty() => {
+                    ready?;
+                    pipe.as_mut().write(pending_outputs.pop_front().expect("pending outputs must be non empty"))?;
+                },
+                pipe_input = poll_fn(|cx| pipe.as_mut().poll_next_input(cx)), if pending_outputs.is_empty() => {
                     match pipe_input? {
                         Either::Left(ingress_input) => {
                             if let Some(output) = handler.handle_network_input(ingress_input) {
                                 pipe.as_mut().write(output)?;
                             }
                         },
-                        Either::Right(invocation_or_response) => pipe.as_mut().write(
-                            handler.handle_ingress_command(invocation_or_response)
-                        )?
+                        Either::Right(IngressRequest(IngressRequestKind::Invocation(invocation, request_mode))) => pipe.as_mut().write(
+                            handler.handle_invocation(invocation, request_mode)
+                        )?,
+                        Either::Right(IngressRequest(IngressRequestKind::BatchBackgroundInvocation(invocations, ack_sender))) => {
+                            pending_outputs.extend(handler.handle_batch_background_invocation(invocations, ack_sender));
+                        }
                     }
                 }
             }
@@ -181,8 +189,13 @@ struct DispatcherLoopHandler {
     // This map can be unbounded, because we enforce concurrency limits in the ingress
     // services using the global semaphore
     waiting_responses: HashMap<FullInvocationId, (MapResponseAction, IngressResponseSender)>,
+    waiting_response_chunks: HashMap<FullInvocationId, IngressResponseChunkSender>,
     waiting_for_acks: HashMap<MessageIndex, AckSender>,
-    waiting_for_acks_with_custom_id: HashMap<IngressDeduplicationId, AckSender>,
+    // Concurrent requests with the same idempotency key share the same deduplication id
+    waiting_for_acks_with_custom_id: HashMap<IngressDeduplicationId, Vec<AckSender>>,
+    // Batches are identified by the message index of their first invocation
+    waiting_for_batch_item_acks: HashMap<MessageIndex, MessageIndex>,
+    waiting_for_batch_acks: HashMap<MessageIndex, (usize, AckSender)>,
 }
 
 impl DispatcherLoopHandler {
@@ -191,8 +204,11 @@ impl DispatcherLoopHandler {
             my_node_id,
             msg_index: 0,
             waiting_responses: HashMap::new(),
+            waiting_response_chunks: HashMap::new(),
             waiting_for_acks: HashMap::default(),
             waiting_for_acks_with_custom_id: Default::default(),
+            waiting_for_batch_item_acks: Default::default(),
+            waiting_for_batch_acks: Default::default(),
         }
     }
 
@@ -202,6 +218,10 @@ impl DispatcherLoopHandler {
     ) -> Option<IngressDispatcherOutput> {
         match input {
             IngressDispatcherInput::Response(response) => {
+                // Dropping the chunk sender closes the stream of chunks before the final response
+                self.waiting_response_chunks
+                    .remove(&response.full_invocation_id);
+
                 if let Some((map_response_action, sender)) =
                     self.waiting_responses.remove(&response.full_invocation_id)
                 {
@@ -227,21 +247,48 @@ impl DispatcherLoopHandler {
                     response.ack_target.acknowledge(),
                 ))
             }
+            IngressDispatcherInput::ResponseChunk(response_chunk) => {
+                if let Some(sender) = self
+                    .waiting_response_chunks
+                    .get(&response_chunk.full_invocation_id)
+                {
+                    if sender.send(response_chunk.chunk.clone()).is_err() {
+                        debug!(
+                            "Failed to send response chunk for '{}' because the handler has been closed, \
+                    probably caused by the client connection that went away",
+                            response_chunk.full_invocation_id
+                        );
+                        self.waiting_response_chunks
+                            .remove(&response_chunk.full_invocation_id);
+                    }
+                } else {
+                    debug!("Failed to handle response chunk '{:?}' because no handler was found locally waiting for its invocation key", &response_chunk);
+                }
+
+                Some(IngressDispatcherOutput::Ack(
+                    response_chunk.ack_target.acknowledge(),
+                ))
+            }
             IngressDispatcherInput::MessageAck(acked_index) => {
                 trace!("Received message ack: {acked_index:?}.");
 
                 if let Some(ack_sender) = self.waiting_for_acks.remove(&acked_index) {
                     // Receivers might be gone if they are not longer interested in the ack notification
                     let _ = ack_sender.send(());
+                } else if let Some(batch_index) =
+                    self.waiting_for_batch_item_acks.remove(&acked_index)
+                {
+                    self.ack_batch_item(batch_index);
                 }
                 None
             }
             IngressDispatcherInput::DedupMessageAck(dedup_name, dedup_seq_number) => {
                 trace!("Received dedup message ack: {dedup_name} {dedup_seq_number:?}.");
 
-                if let Some(ack_sender) = self
+                for ack_sender in self
                     .waiting_for_acks_with_custom_id
                     .remove(&(dedup_name, dedup_seq_number))
+                    .unwrap_or_default()
                 {
                     // Receivers might be gone if they are not longer interested in the ack notification
                     let _ = ack_sender.send(());
@@ -251,92 +298,63 @@ impl DispatcherLoopHandler {
         }
     }
 
-    fn handle_ingress_command(
+    fn handle_invocation(
         &mut self,
-        ingress_request: IngressRequest,
+        invocation: IngressInvocation,
+        request_mode: IngressRequestMode,
     ) -> IngressDispatcherOutput {
-        let IngressRequest {
-            fid,
-            method_name,
-            argument,
-            span_context,
+        let response_sink = if matches!(
             request_mode,
-            idempotency,
-        } = ingress_request;
-
-        let response_sink = if matches!(request_mode, IngressRequestMode::RequestResponse(_)) {
+            IngressRequestMode::RequestResponse(_)
+                | IngressRequestMode::RequestStreamingResponse(_, _)
+        ) {
             Some(ServiceInvocationResponseSink::Ingress(self.my_node_id))
         } else {
             None
         };
 
         let (service_invocation, map_response_action) =
-            if let IdempotencyMode::Key(idempotency_key, retention_period) = idempotency {
-                // Use service name + user provided idempotency key for the actual idempotency key
-                let mut idempotency_fid_key = BytesMut::with_capacity(
-                    fid.service_id.service_name.len() + idempotency_key.len(),
-                );
-                idempotency_fid_key.put(fid.service_id.service_name.clone().into_bytes());
-                idempotency_fid_key.put(idempotency_key.clone());
-
-                (
-                    ServiceInvocation {
-                        fid: FullInvocationId::generate(
-                            restate_pb::IDEMPOTENT_INVOKER_SERVICE_NAME,
-                            idempotency_fid_key.freeze(),
-                        ),
-                        method_name: restate_pb::IDEMPOTENT_INVOKER_INVOKE_METHOD_NAME
-                            .to_string()
-                            .into(),
-                        argument: IdempotentInvokeRequest {
-                            idempotency_id: idempotency_key,
-                            service_name: fid.service_id.service_name.into(),
-                            service_key: fid.service_id.key,
-                            invocation_uuid: fid.invocation_uuid.into(),
-                            method: method_name.into(),
-                            argument,
-                            retention_period_sec: retention_period.unwrap_or_default().as_secs()
-                                as u32,
-                        }
-                        .encode_to_vec()
-                        .into(),
-                        source: Source::Ingress,
-                        response_sink,
-                        span_context,
-                    },
-                    MapResponseAction::IdempotentInvokerResponse,
-                )
-            } else {
-                (
-                    ServiceInvocation {
-                        fid,
-                        method_name,
-                        argument,
-                        source: Source::Ingress,
-                        response_sink,
-                        span_context,
-                    },
-                    MapResponseAction::None,
-                )
-            };
+            Self::create_service_invocation(invocation, response_sink);
 
-        let (dedup_source, msg_index) = match request_mode {
+        let (dedup_source, dedup_expiration_time, msg_index) = match request_mode {
             IngressRequestMode::RequestResponse(response_sender) => {
                 self.waiting_responses.insert(
                     service_invocation.fid.clone(),
                     (map_response_action, response_sender),
                 );
-                (None, self.get_and_increment_msg_index())
+                (None, None, self.get_and_increment_msg_index())
+            }
+            IngressRequestMode::RequestStreamingResponse(response_sender, chunk_sender) => {
+                self.waiting_response_chunks
+                    .insert(service_invocation.fid.clone(), chunk_sender);
+                self.waiting_responses.insert(
+                    service_invocation.fid.clone(),
+                    (map_response_action, response_sender),
+                );
+                (None, None, self.get_and_increment_msg_index())
             }
             IngressRequestMode::FireAndForget(ack_sender) => {
                 let msg_index = self.get_and_increment_msg_index();
                 self.waiting_for_acks.insert(msg_index, ack_sender);
-                (None, msg_index)
+                (None, None, msg_index)
             }
             IngressRequestMode::DedupFireAndForget(dedup_id, ack_sender) => {
                 self.waiting_for_acks_with_custom_id
-                    .insert(dedup_id.clone(), ack_sender);
-                (Some(dedup_id.0), dedup_id.1)
+                    .entry(dedup_id.clone())
+                    .or_default()
+                    .push(ack_sender);
+                (Some(dedup_id.0), None, dedup_id.1)
+            }
+            IngressRequestMode::ExpiringDedupFireAndForget(
+                dedup_id,
+                expiration_time,
+                ack_sender,
+            ) => {
+                self.waiting_for_acks_with_custom_id
+                    .entry(dedup_id.clone())
+                    .or_default()
+                    .push(ack_sender);
+                (Some(dedup_id.0), Some(expiration_time), dedup_id.1)
             }
         };
 
@@ -344,10 +362,121 @@ impl DispatcherLoopHandler {
             service_invocation,
             self.my_node_id,
             dedup_source,
+            dedup_expiration_time,
             msg_index,
         )
     }
 
+    fn create_service_invocation(
+        invocation: IngressInvocation,
+        response_sink: Option<ServiceInvocationResponseSink>,
+    ) -> (ServiceInvocation, MapResponseAction) {
+        let IngressInvocation {
+            fid,
+            method_name,
+            argument,
+            span_context,
+            idempotency,
+            headers,
+            failure_policy,
+        } = invocation;
+
+        if let IdempotencyMode::Key(idempotency_key, retention_period) = idempotency {
+            let (idempotent_invoker_fid, idempotent_invoke_request) =
+                IdempotentInvokeRequest::new_with_idempotent_invoker_fid(
+                    fid,
+                    method_name.into(),
+                    argument,
+                    idempotency_key,
+                    retention_period,
+                    headers,
+                );
+
+            (
+                ServiceInvocation {
+                    fid: idempotent_invoker_fid,
+                    method_name: restate_pb::IDEMPOTENT_INVOKER_INVOKE_METHOD_NAME
+                        .to_string()
+                        .into(),
+                    argument: idempotent_invoke_request.encode_to_vec().into(),
+                    source: Source::Ingress,
+                    response_sink,
+                    span_context,
+                    headers: vec![],
+                    failure_policy: Default::default(),
+                },
+                MapResponseAction::IdempotentInvokerResponse,
+            )
+        } else {
+            (
+                ServiceInvocation {
+                    fid,
+                    method_name,
+                    argument,
+                    source: Source::Ingress,
+                    response_sink,
+                    span_context,
+                    headers,
+                    failure_policy,
+                },
+                MapResponseAction::None,
+            )
+        }
+    }
+
+    fn handle_batch_background_invocation(
+        &mut self,
+        invocations: Vec<IngressInvocation>,
+        ack_sender: AckSender,
+    ) -> Vec<IngressDispatcherOutput> {
+        if invocations.is_empty() {
+            let _ = ack_sender.send(());
+            return vec![];
+        }
+
+        let mut service_invocations: Vec<_> = invocations
+            .into_iter()
+            .map(|invocation| Self::create_service_invocation(invocation, None).0)
+            .collect();
+        // Group the invocations by partition key, so consecutive messages target the same partition
+        service_invocations
+            .sort_by_key(|service_invocation| service_invocation.fid.partition_key());
+
+        let batch_index = self.msg_index;
+        self.waiting_for_batch_acks
+            .insert(batch_index, (service_invocations.len(), ack_sender));
+
+        service_invocations
+            .into_iter()
+            .map(|service_invocation| {
+                let msg_index = self.get_and_increment_msg_index();
+                self.waiting_for_batch_item_acks
+                    .insert(msg_index, batch_index);
+                IngressDispatcherOutput::service_invocation(
+                    service_invocation,
+                    self.my_node_id,
+                    None,
+                    None,
+                    msg_index,
+                )
+            })
+            .collect()
+    }
+
+    fn ack_batch_item(&mut self, batch_index: MessageIndex) {
+        if let Some((remaining, _)) = self.waiting_for_batch_acks.get_mut(&batch_index) {
+            *remaining -= 1;
+            if *remaining == 0 {
+                let (_, ack_sender) = self
+                    .waiting_for_batch_acks
+                    .remove(&batch_index)
+                    .expect("batch must be present");
+                // Receivers might be gone if they are not longer interested in the ack notification
+                let _ = ack_sender.send(());
+            }
+        }
+    }
+
     fn get_and_increment_msg_index(&mut self) -> MessageIndex {
         let current_msg_index = self.msg_index;
         self.msg_index += 1;
@@ -387,6 +516,7 @@ mod tests {
             Bytes::default(),
             SpanRelation::None,
             IdempotencyMode::None,
+            vec![],
         );
         command_sender.send(invocation).unwrap();
         drop(response_rx);
@@ -406,6 +536,78 @@ mod tests {
         loop_handle.await.unwrap().unwrap()
     }
 
+    #[test(tokio::test)]
+    async fn streaming_invoke() {
+        let (output_tx, mut output_rx) = mpsc::channel(10);
+
+        let my_node_id = GenerationalNodeId::new(1, 1);
+        let ingress_dispatcher = Service::new(my_node_id, 1);
+        let handler_tx = ingress_dispatcher.create_ingress_request_sender();
+        let network_tx = ingress_dispatcher.create_ingress_dispatcher_input_sender();
+
+        // Start the dispatcher loop
+        let (drain_signal, watch) = drain::channel();
+        tokio::spawn(ingress_dispatcher.run(output_tx, watch));
+
+        let fid = FullInvocationId::generate("MySvc", "MyKey");
+        let (invocation, response_rx, mut chunk_rx) = IngressRequest::streaming_invocation(
+            fid.clone(),
+            "pippo",
+            Bytes::new(),
+            SpanRelation::None,
+            vec![],
+        );
+        handler_tx.send(invocation).unwrap();
+
+        let_assert!(
+            IngressDispatcherOutput::Invocation {
+                service_invocation,
+                ..
+            } = output_rx.recv().await.unwrap()
+        );
+        assert_eq!(service_invocation.fid, fid);
+        assert_that!(
+            service_invocation.response_sink,
+            some(eq(ServiceInvocationResponseSink::Ingress(my_node_id)))
+        );
+
+        // Chunks are forwarded in order, and acknowledged
+        for chunk in [Bytes::from_static(b"1"), Bytes::from_static(b"2")] {
+            network_tx
+                .send(IngressDispatcherInput::response_chunk(
+                    IngressResponseChunkMessage {
+                        full_invocation_id: fid.clone(),
+                        chunk,
+                        ack_target: AckTarget::new(0, 0),
+                    },
+                ))
+                .await
+                .unwrap();
+            let_assert!(IngressDispatcherOutput::Ack(_) = output_rx.recv().await.unwrap());
+        }
+        assert_eq!(chunk_rx.recv().await.unwrap(), Bytes::from_static(b"1"));
+        assert_eq!(chunk_rx.recv().await.unwrap(), Bytes::from_static(b"2"));
+
+        // The final response closes the chunk stream
+        network_tx
+            .send(IngressDispatcherInput::Response(IngressResponseMessage {
+                full_invocation_id: fid,
+                result: Ok(Bytes::new()),
+                ack_target: AckTarget::new(0, 0),
+            }))
+            .await
+            .unwrap();
+        assert_that!(
+            response_rx.await.unwrap(),
+            pat!(IngressResponse {
+                result: ok(eq(Bytes::new()))
+            })
+        );
+        assert!(chunk_rx.recv().await.is_none());
+
+        drain_signal.drain().await;
+    }
+
     #[test(tokio::test)]
     async fn idempotent_invoke() {
         let (output_tx, mut output_rx) = mpsc::channel(2);
@@ -429,6 +631,7 @@ mod tests {
             argument.clone(),
             SpanRelation::None,
             IdempotencyMode::key(idempotency_key.clone(), None),
+            vec![Header::new("x-tenant-id", "my-tenant")],
         );
         handler_tx.send(invocation).unwrap();
 
@@ -457,7 +660,11 @@ mod tests {
                     service_key: eq("MyKey"),
                     method: eq("pippo"),
                     argument: eq(argument),
-                    retention_period_sec: eq(0)
+                    retention_period_sec: eq(0),
+                    headers: eq(HashMap::from([(
+                        "x-tenant-id".to_string(),
+                        "my-tenant".to_string()
+                    )]))
                 }))
             })
         );
@@ -491,4 +698,156 @@ mod tests {
 
         drain_signal.drain().await;
     }
+
+    #[test(tokio::test)]
+    async fn idempotent_background_invoke() {
+        let (output_tx, mut output_rx) = mpsc::channel(2);
+
+        let my_node_id = GenerationalNodeId::new(1, 1);
+        let ingress_dispatcher = Service::new(my_node_id, 1);
+        let handler_tx = ingress_dispatcher.create_ingress_request_sender();
+        let network_tx = ingress_dispatcher.create_ingress_dispatcher_input_sender();
+
+        // Start the dispatcher loop
+        let (drain_signal, watch) = drain::channel();
+        tokio::spawn(ingress_dispatcher.run(output_tx, watch));
+
+        let fid = FullInvocationId::generate("MySvc", "MyKey");
+        // Two concurrent requests with the same idempotency key
+        let mut ack_rxs = vec![];
+        for _ in 0..2 {
+            let (invocation, ack_rx) = IngressRequest::idempotent_background_invocation(
+                fid.clone(),
+                "pippo",
+                Bytes::new(),
+                SpanRelation::None,
+                "123",
+                None,
+                vec![],
+            );
+            handler_tx.send(invocation).unwrap();
+            ack_rxs.push(ack_rx);
+        }
+
+        let mut dedup_ids = vec![];
+        for _ in 0..2 {
+            let_assert!(
+                IngressDispatcherOutput::Invocation {
+                    service_invocation,
+                    deduplication_source: Some(deduplication_source),
+                    deduplication_expiration_time: Some(_),
+                    msg_index,
+                    ..
+                } = output_rx.recv().await.unwrap()
+            );
+            assert_eq!(service_invocation.fid, fid);
+            assert_that!(service_invocation.response_sink, none());
+            assert_eq!(deduplication_source, "idempotency-key/MySvc/TXlLZXk=/123");
+            dedup_ids.push((deduplication_source, msg_index));
+        }
+        assert_eq!(dedup_ids[0], dedup_ids[1]);
+
+        // Acknowledging the deduplication id notifies both requests
+        let (deduplication_source, msg_index) = dedup_ids.pop().unwrap();
+        network_tx
+            .send(IngressDispatcherInput::dedup_message_ack(
+                deduplication_source,
+                msg_index,
+            ))
+            .await
+            .unwrap();
+        for ack_rx in ack_rxs {
+            ack_rx.await.unwrap();
+        }
+
+        drain_signal.drain().await;
+    }
+
+    #[test(tokio::test)]
+    async fn batch_background_invoke() {
+        let (output_tx, mut output_rx) = mpsc::channel(2);
+
+        let my_node_id = GenerationalNodeId::new(1, 1);
+        let ingress_dispatcher = Service::new(my_node_id, 1);
+        let handler_tx = ingress_dispatcher.create_ingress_request_sender();
+        let network_tx = ingress_dispatcher.create_ingress_dispatcher_input_sender();
+
+        // Start the dispatcher loop
+        let (drain_signal, watch) = drain::channel();
+        tokio::spawn(ingress_dispatcher.run(output_tx, watch));
+
+        let fids: Vec<_> = ["a", "b", "c", "a"]
+            .into_iter()
+            .map(|key| FullInvocationId::generate("MySvc", key))
+            .collect();
+        let idempotency_key = Bytes::copy_from_slice(b"123");
+        let (batch, mut ack_rx) = IngressRequest::batch_background_invocation(
+            fids.iter()
+                .enumerate()
+                .map(|(i, fid)| BatchInvocation {
+                    fid: fid.clone(),
+                    method_name: "pippo".into(),
+                    argument: Bytes::new(),
+                    idempotency: if i == 2 {
+                        IdempotencyMode::key(idempotency_key.clone(), None)
+                    } else {
+                        IdempotencyMode::None
+                    },
+                })
+                .collect(),
+            SpanRelation::None,
+            vec![],
+        );
+        handler_tx.send(batch).unwrap();
+
+        let mut partition_keys = vec![];
+        let mut msg_indexes = vec![];
+        for _ in 0..fids.len() {
+            let_assert!(
+                IngressDispatcherOutput::Invocation {
+                    service_invocation,
+                    msg_index,
+                    ..
+                } = output_rx.recv().await.unwrap()
+            );
+            assert_that!(service_invocation.response_sink, none());
+            if service_invocation.fid.service_id.service_name
+                == restate_pb::IDEMPOTENT_INVOKER_SERVICE_NAME
+            {
+                assert_that!(
+                    service_invocation.argument,
+                    protobuf_decoded(pat!(IdempotentInvokeRequest {
+                        idempotency_id: eq(idempotency_key.clone()),
+                        service_key: eq("c"),
+                    }))
+                );
+            }
+            partition_keys.push(service_invocation.fid.partition_key());
+            msg_indexes.push(msg_index);
+        }
+
+        // Invocations are grouped by partition key
+        let mut sorted_partition_keys = partition_keys.clone();
+        sorted_partition_keys.sort();
+        assert_eq!(partition_keys, sorted_partition_keys);
+
+        // The batch is acknowledged only once all its invocations are
+        let (last_msg_index, msg_indexes) = msg_indexes.split_last().unwrap();
+        for msg_index in msg_indexes {
+            network_tx
+                .send(IngressDispatcherInput::message_ack(*msg_index))
+                .await
+                .unwrap();
+        }
+        tokio::task::yield_now().await;
+        assert!(ack_rx.try_recv().is_err());
+
+        network_tx
+            .send(IngressDispatcherInput::message_ack(*last_msg_index))
+            .await
+            .unwrap();
+        ack_rx.await.unwrap();
+
+        drain_signal.drain().await;
+    }
 }
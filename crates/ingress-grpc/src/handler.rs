@@ -18,22 +18,32 @@ use crate::metric_definitions::{
 };
 use crate::reflection::ServerReflectionService;
 use futures::future::{ok, BoxFuture};
-use futures::{FutureExt, TryFutureExt};
+use futures::{stream, FutureExt, StreamExt, TryFutureExt};
 use http::{Request, Response, StatusCode};
 use http_body::Body;
 use hyper::Body as HyperBody;
 use metrics::{counter, histogram};
 use opentelemetry::trace::{SpanContext, TraceContextExt};
 use prost::Message;
-use restate_ingress_dispatcher::{IdempotencyMode, IngressRequest, IngressRequestSender};
+use prost_reflect::{DeserializeOptions, ReflectMessage};
+use restate_ingress_dispatcher::{
+    BatchInvocation, IdempotencyMode, IngressRequest, IngressRequestSender,
+};
 use restate_pb::grpc::health;
 use restate_pb::grpc::reflection::server_reflection_server::ServerReflectionServer;
-use restate_schema_api::json::JsonMapperResolver;
+use restate_pb::restate::{
+    batch_invoke_request, BatchInvokeRequest, BatchInvokeResponse, GetInvocationStatusResponse,
+    InvokeRequest, InvokeResponse, TerminateInvocationRequest,
+};
+use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper};
 use restate_schema_api::key::KeyExtractor;
 use restate_schema_api::proto_symbol::ProtoSymbolResolver;
-use restate_schema_api::service::ServiceMetadataResolver;
-use restate_types::errors::InvocationError;
-use restate_types::invocation::SpanRelation;
+use restate_schema_api::service::{InstanceType, ServiceMetadataResolver};
+use restate_types::errors::{IdDecodeError, InvocationError, UserErrorCode};
+use restate_types::identifiers::{InvocationId, InvocationUuid};
+use restate_types::invocation::{Header, InvocationTermination, SpanRelation};
+use restate_worker_api::{InvocationStatus, InvocationStatusKind};
+use serde::Serialize;
 use std::sync::Arc;
 use std::task::Poll;
 use std::time::{Duration, Instant};
@@ -44,51 +54,65 @@ use tower::{BoxError, Layer, Service};
 use tracing::{debug, info, info_span, trace, warn, Instrument};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-pub struct Handler<Schemas, ProtoSymbols>
+pub struct Handler<Schemas, ProtoSymbols, InvocationHandle>
 where
     ProtoSymbols: ProtoSymbolResolver + Clone + Send + Sync + 'static,
 {
     json: JsonOptions,
+    propagated_headers: Arc<[String]>,
+    invocation_owner_header: Option<Arc<str>>,
     schemas: Schemas,
     reflection_server:
         GrpcWebService<ServerReflectionServer<ServerReflectionService<ProtoSymbols>>>,
     request_tx: IngressRequestSender,
+    invocation_handle: InvocationHandle,
     global_concurrency_semaphore: Arc<Semaphore>,
 }
 
-impl<Schemas, ProtoSymbols> Clone for Handler<Schemas, ProtoSymbols>
+impl<Schemas, ProtoSymbols, InvocationHandle> Clone
+    for Handler<Schemas, ProtoSymbols, InvocationHandle>
 where
     Schemas: Clone,
     ProtoSymbols: ProtoSymbolResolver + Clone + Send + Sync + 'static,
+    InvocationHandle: Clone,
 {
     fn clone(&self) -> Self {
         Self {
             json: self.json.clone(),
+            propagated_headers: self.propagated_headers.clone(),
+            invocation_owner_header: self.invocation_owner_header.clone(),
             schemas: self.schemas.clone(),
             reflection_server: self.reflection_server.clone(),
             request_tx: self.request_tx.clone(),
+            invocation_handle: self.invocation_handle.clone(),
             global_concurrency_semaphore: self.global_concurrency_semaphore.clone(),
         }
     }
 }
 
-impl<Schemas> Handler<Schemas, Schemas>
+impl<Schemas, InvocationHandle> Handler<Schemas, Schemas, InvocationHandle>
 where
     Schemas: ProtoSymbolResolver + Clone + Send + Sync + 'static,
 {
     pub(crate) fn new(
         json: JsonOptions,
+        propagated_headers: Vec<String>,
+        invocation_owner_header: Option<String>,
         schemas: Schemas,
         request_tx: IngressRequestSender,
+        invocation_handle: InvocationHandle,
         global_concurrency_semaphore: Arc<Semaphore>,
     ) -> Self {
         Self {
             json,
+            propagated_headers: propagated_headers.into(),
+            invocation_owner_header: invocation_owner_header.map(Into::into),
             schemas: schemas.clone(),
             reflection_server: GrpcWebLayer::new().layer(ServerReflectionServer::new(
                 ServerReflectionService(schemas),
             )),
             request_tx,
+            invocation_handle,
             global_concurrency_semaphore,
         }
     }
@@ -96,10 +120,12 @@ where
 
 // TODO When porting to hyper 1.0 https://github.com/restatedev/restate/issues/96
 //  replace this impl with hyper::Service impl
-impl<Schemas, JsonDecoder, JsonEncoder> Service<Request<HyperBody>> for Handler<Schemas, Schemas>
+impl<Schemas, JsonDecoder, JsonEncoder, InvocationHandle> Service<Request<HyperBody>>
+    for Handler<Schemas, Schemas, InvocationHandle>
 where
     JsonDecoder: Send,
-    JsonEncoder: Send,
+    JsonEncoder: Clone + Send + 'static,
+    InvocationHandle: restate_worker_api::InvocationHandle + Send + Sync + 'static,
     Schemas: JsonMapperResolver<JsonToProtobufMapper = JsonDecoder, ProtobufToJsonMapper = JsonEncoder>
         + KeyExtractor
         + ServiceMetadataResolver
@@ -204,6 +230,9 @@ where
         // Encapsulate in this closure the remaining part of the processing
         let schemas = self.schemas.clone();
         let request_tx = self.request_tx.clone();
+        let propagated_headers = self.propagated_headers.clone();
+        let invocation_owner_header = self.invocation_owner_header.clone();
+        let invocation_handle = self.invocation_handle.clone();
 
         let client_connect_info = req.extensions().get::<ConnectInfo>().cloned();
 
@@ -289,6 +318,124 @@ where
                     )
                 }
 
+                // --- Invocation management built-in methods, served directly by the worker
+                if restate_pb::INGRESS_SERVICE_NAME == service_name
+                    && (restate_pb::INGRESS_CANCEL_METHOD_NAME == method_name
+                        || restate_pb::INGRESS_KILL_METHOD_NAME == method_name
+                        || restate_pb::INGRESS_GET_STATUS_METHOD_NAME == method_name)
+                {
+                    let Some(owner_header) = invocation_owner_header else {
+                        return Err(Status::permission_denied(
+                            "Invocation management is disabled, configure 'worker.ingress_grpc.invocation_owner_header' to enable it"
+                        ));
+                    };
+                    // Cancel, Kill and GetStatus requests all have the invocation id as first field
+                    let invocation_id: InvocationId = TerminateInvocationRequest::decode(req_payload)
+                        .map_err(|e| Status::invalid_argument(e.to_string()))?
+                        .id
+                        .parse()
+                        .map_err(|e: IdDecodeError| Status::invalid_argument(e.to_string()))?;
+
+                    let status = invocation_handle
+                        .invocation_status(invocation_id.clone())
+                        .await
+                        .map_err(|e| Status::unavailable(e.to_string()))?;
+                    // Completed invocations don't retain their headers, and can't be terminated anyway
+                    if status.kind != InvocationStatusKind::Completed
+                        && !is_invocation_owner(&owner_header, &status, &req_headers.metadata)
+                    {
+                        return Err(Status::permission_denied(format!(
+                            "Invocation {} was not started by this client",
+                            invocation_id
+                        )));
+                    }
+
+                    if restate_pb::INGRESS_GET_STATUS_METHOD_NAME == method_name {
+                        use restate_pb::restate::get_invocation_status_response;
+                        return Ok(HandlerResponse::from_message(GetInvocationStatusResponse {
+                            status: match status.kind {
+                                InvocationStatusKind::Pending => get_invocation_status_response::Status::Pending,
+                                InvocationStatusKind::Running => get_invocation_status_response::Status::Running,
+                                InvocationStatusKind::Suspended => get_invocation_status_response::Status::Suspended,
+                                InvocationStatusKind::Virtual => get_invocation_status_response::Status::Virtual,
+                                InvocationStatusKind::Completed => get_invocation_status_response::Status::Completed,
+                            }.into()
+                        }));
+                    }
+
+                    if status.kind != InvocationStatusKind::Completed {
+                        let invocation_termination = if restate_pb::INGRESS_KILL_METHOD_NAME == method_name {
+                            InvocationTermination::kill(invocation_id)
+                        } else {
+                            InvocationTermination::cancel(invocation_id)
+                        };
+                        invocation_handle
+                            .terminate_invocation(invocation_termination)
+                            .await
+                            .map_err(|e| Status::unavailable(e.to_string()))?;
+                    }
+                    return Ok(HandlerResponse::from(Bytes::new()));
+                }
+
+                // --- Batch invoke built-in method, fanned out directly by the ingress dispatcher
+                if restate_pb::INGRESS_SERVICE_NAME == service_name
+                    && restate_pb::INGRESS_BATCH_INVOKE_METHOD_NAME == method_name
+                {
+                    if !matches!(parse_idempotency_key_and_retention_period(req_headers.metadata.clone())?, IdempotencyMode::None) {
+                        return Err(Status::invalid_argument(
+                            "Idempotency-Key is not supported for BatchInvoke, use the per-invocation idempotency_key instead"
+                        ));
+                    }
+                    let batch_invoke_req = BatchInvokeRequest::decode(req_payload)
+                        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                    if batch_invoke_req.invocations.len() > restate_pb::INGRESS_BATCH_INVOKE_MAX_SIZE {
+                        return Err(Status::invalid_argument(format!(
+                            "BatchInvoke supports at most {} invocations",
+                            restate_pb::INGRESS_BATCH_INVOKE_MAX_SIZE
+                        )));
+                    }
+
+                    // Invalid invocations are reported back per item, the others are dispatched
+                    let mut invocations = Vec::with_capacity(batch_invoke_req.invocations.len());
+                    let results: Vec<_> = batch_invoke_req
+                        .invocations
+                        .into_iter()
+                        .map(|item| {
+                            prepare_batch_invocation(&schemas, item).map(|invocation| {
+                                let id = invocation.fid.to_string();
+                                invocations.push(invocation);
+                                id
+                            })
+                        })
+                        .collect();
+                    let (batch, ack_rx) = IngressRequest::batch_background_invocation(
+                        invocations,
+                        SpanRelation::Parent(ingress_span_context),
+                        extract_propagated_headers(&propagated_headers, &req_headers.metadata),
+                    );
+                    if request_tx.send(batch).is_err() {
+                        debug!("Ingress dispatcher is closed while there is still an invocation in flight.");
+                        return Err(Status::unavailable("Unavailable"));
+                    }
+                    if ack_rx.await.is_err() {
+                        warn!("Ack channel was closed");
+                        return Err(Status::unavailable("Unavailable"));
+                    }
+
+                    use restate_pb::restate::batch_invoke_response::{Item, item};
+                    return Ok(HandlerResponse::from_message(BatchInvokeResponse {
+                        invocations: results
+                            .into_iter()
+                            .map(|result| Item {
+                                result: Some(match result {
+                                    Ok(id) => item::Result::Id(id),
+                                    Err(error) => item::Result::Failure(error.into()),
+                                }),
+                            })
+                            .collect(),
+                    }));
+                }
+
                 // Extract the key
                 let key = schemas
                     .extract(&service_name, &method_name, req_payload.clone())
@@ -301,19 +448,119 @@ where
                     })?;
 
 
-                let fid = FullInvocationId::generate(service_name, key);
                 let span_relation = SpanRelation::Parent(ingress_span_context);
 
+                // Collect the headers to propagate to the service
+                let headers = extract_propagated_headers(&propagated_headers, &req_headers.metadata);
+
+                // --- One-way built-in methods, deduplicated by the partition processor using the Idempotency-Key
+                let is_ingress_invoke = restate_pb::INGRESS_SERVICE_NAME == service_name
+                    && restate_pb::INGRESS_INVOKE_METHOD_NAME == method_name;
+                let is_awakeable_completion = restate_pb::AWAKEABLES_SERVICE_NAME == service_name
+                    && (restate_pb::AWAKEABLES_RESOLVE_METHOD_NAME == method_name
+                        || restate_pb::AWAKEABLES_REJECT_METHOD_NAME == method_name);
+                if is_ingress_invoke || is_awakeable_completion {
+                    if let IdempotencyMode::Key(idempotency_key, retention_period) = parse_idempotency_key_and_retention_period(req_headers.metadata.clone())? {
+                        let idempotency_key = String::from_utf8(idempotency_key.to_vec())
+                            .map_err(|e| Status::invalid_argument(format!("bad idempotency id format: {}", e)))?;
+
+                        let (fid, method_name, argument) = if is_ingress_invoke {
+                            // The target service is invoked directly, so its invocation id is known upfront
+                            let invoke_req = InvokeRequest::decode(req_payload)
+                                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                            let (service, key, method, argument) = prepare_invoke_request(&schemas, invoke_req)?;
+                            (idempotent_fid(&schemas, service, key, &idempotency_key), method, argument)
+                        } else {
+                            (idempotent_fid(&schemas, service_name, key, &idempotency_key), method_name, req_payload)
+                        };
+                        let response = if is_ingress_invoke {
+                            InvokeResponse { id: fid.to_string() }.encode_to_vec().into()
+                        } else {
+                            Bytes::new()
+                        };
+
+                        let (invocation, ack_rx) = IngressRequest::idempotent_background_invocation(
+                            fid,
+                            method_name,
+                            argument,
+                            span_relation,
+                            &idempotency_key,
+                            retention_period,
+                            headers,
+                        );
+                        if request_tx.send(invocation).is_err() {
+                            debug!("Ingress dispatcher is closed while there is still an invocation in flight.");
+                            return Err(Status::unavailable("Unavailable"));
+                        }
+                        if ack_rx.await.is_err() {
+                            warn!("Ack channel was closed");
+                            return Err(Status::unavailable("Unavailable"));
+                        }
+                        return Ok(HandlerResponse::from(response));
+                    }
+                }
+
+                let fid = FullInvocationId::generate(service_name, key);
+
                 // Check if Idempotency-Key is available
                 let idempotency_mode = parse_idempotency_key_and_retention_period(req_headers.metadata)?;
 
+                // Server-streaming methods forward the response chunks as they're produced
+                if schemas.is_server_streaming(&service_name, &method_name) == Some(true) {
+                    if !matches!(idempotency_mode, IdempotencyMode::None) {
+                        return Err(Status::invalid_argument(
+                            "Idempotency-Key is not supported for server-streaming methods"
+                        ));
+                    }
+
+                    let (invocation, response_rx, chunk_rx) = IngressRequest::streaming_invocation(
+                        fid,
+                        method_name,
+                        req_payload,
+                        span_relation,
+                        headers,
+                    );
+                    if request_tx.send(invocation).is_err() {
+                        debug!("Ingress dispatcher is closed while there is still an invocation in flight.");
+                        return Err(Status::unavailable("Unavailable"));
+                    }
+
+                    // The chunk stream is closed right before the final response is sent
+                    let chunks = stream::unfold(chunk_rx, |mut chunk_rx| async move {
+                        chunk_rx.recv().await.map(|chunk| (Ok(chunk), chunk_rx))
+                    });
+                    let completion = stream::once(response_rx).filter_map(|response| async move {
+                        match response.map(Result::<Bytes, InvocationError>::from) {
+                            Ok(Ok(_)) => {
+                                trace!("Complete external gRPC streaming request successfully");
+                                None
+                            },
+                            Ok(Err(error)) => {
+                                let status = Status::new(error.code().into(), error.message());
+                                info!(rpc.grpc.status_code = ?status.code(), rpc.grpc.status_message = ?status.message(), "Complete external gRPC streaming request with a failure");
+                                Some(Err(status))
+                            },
+                            Err(_) => {
+                                warn!("Response channel was closed");
+                                Some(Err(Status::unavailable("Unavailable")))
+                            }
+                        }
+                    });
+
+                    return Ok(HandlerResponse::from_stream(
+                        MetadataMap::new(),
+                        chunks.chain(completion).boxed()
+                    ));
+                }
+
                 // Send the service invocation
                 let (invocation, response_rx) = IngressRequest::invocation(
                     fid,
                     method_name,
                     req_payload,
                     span_relation,
-                    idempotency_mode
+                    idempotency_mode,
+                    headers,
                 );
                 if request_tx.send(invocation).is_err() {
                     debug!("Ingress dispatcher is closed while there is still an invocation in flight.");
@@ -398,6 +645,144 @@ fn encode_http_status_code(status_code: StatusCode) -> Response<BoxBody> {
     res
 }
 
+fn extract_propagated_headers(
+    propagated_headers: &[String],
+    metadata: &MetadataMap,
+) -> Vec<Header> {
+    propagated_headers
+        .iter()
+        .filter_map(|header_name| {
+            let value = metadata.get(header_name.as_str())?.to_str().ok()?;
+            Some(Header::new(header_name.to_ascii_lowercase(), value))
+        })
+        .collect()
+}
+
+/// Checks whether the request comes from the client which started the invocation,
+/// by comparing the owner header of the request with the one persisted together with the invocation.
+fn is_invocation_owner(
+    owner_header: &str,
+    invocation_status: &InvocationStatus,
+    metadata: &MetadataMap,
+) -> bool {
+    metadata
+        .get(owner_header)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|caller| invocation_status.has_header(owner_header, caller))
+}
+
+fn prepare_batch_invocation<Schemas>(
+    schemas: &Schemas,
+    item: batch_invoke_request::Item,
+) -> Result<BatchInvocation, InvocationError>
+where
+    Schemas: JsonMapperResolver + KeyExtractor + ServiceMetadataResolver,
+{
+    let (service, key, method, argument) =
+        prepare_invoke_request(schemas, item.invocation.unwrap_or_default())?;
+
+    Ok(BatchInvocation {
+        fid: FullInvocationId::generate(service, key),
+        method_name: method.into(),
+        argument,
+        idempotency: if item.idempotency_key.is_empty() {
+            IdempotencyMode::None
+        } else {
+            IdempotencyMode::key(item.idempotency_key, None)
+        },
+    })
+}
+
+/// Resolves the target service, key, method and protobuf argument of an [`InvokeRequest`].
+fn prepare_invoke_request<Schemas>(
+    schemas: &Schemas,
+    invoke_request: InvokeRequest,
+) -> Result<(String, Bytes, String, Bytes), InvocationError>
+where
+    Schemas: JsonMapperResolver + KeyExtractor + ServiceMetadataResolver,
+{
+    use restate_pb::restate::invoke_request::Argument;
+
+    let InvokeRequest {
+        service,
+        method,
+        argument,
+    } = invoke_request;
+
+    match schemas.is_service_public(&service) {
+        None => return Err(InvocationError::service_not_found(&service)),
+        Some(false) => {
+            return Err(InvocationError::new(
+                UserErrorCode::PermissionDenied,
+                format!("Service {} is not accessible", service),
+            ))
+        }
+        _ => {}
+    };
+
+    // Extract the argument
+    let argument = match argument {
+        None => Bytes::new(),
+        Some(Argument::Pb(bytes)) => bytes,
+        Some(Argument::Json(json)) => {
+            let serde_json_value = json
+                .transcode_to_dynamic()
+                .serialize(serde_json::value::Serializer)
+                .map_err(InvocationError::internal)?;
+            let (json_to_proto, _) = schemas
+                .resolve_json_mapper_for_service(&service, &method)
+                .ok_or_else(|| InvocationError::service_method_not_found(&service, &method))?;
+            json_to_proto
+                .json_value_to_protobuf(serde_json_value, &DeserializeOptions::default())
+                .map_err(|e| InvocationError::new(UserErrorCode::InvalidArgument, e))?
+        }
+    };
+
+    // Extract the key
+    let key = schemas
+        .extract(&service, &method, argument.clone())
+        .map_err(|err| match err {
+            restate_schema_api::key::KeyExtractorError::NotFound => {
+                InvocationError::service_method_not_found(&service, &method)
+            }
+            err => InvocationError::new(UserErrorCode::InvalidArgument, err),
+        })?;
+
+    Ok((service, key, method, argument))
+}
+
+/// Generates the [`FullInvocationId`] of an invocation deduplicated by the given idempotency key.
+/// The invocation uuid is derived from the service instance and the idempotency key, and the key of
+/// unkeyed services from the idempotency key, so retries get the same invocation id and target the same partition.
+fn idempotent_fid<Schemas>(
+    schemas: &Schemas,
+    service_name: String,
+    key: Bytes,
+    idempotency_key: &str,
+) -> FullInvocationId
+where
+    Schemas: ServiceMetadataResolver,
+{
+    if matches!(
+        schemas
+            .resolve_latest_service_metadata(&service_name)
+            .map(|service_metadata| service_metadata.instance_type),
+        Some(InstanceType::Unkeyed)
+    ) {
+        let invocation_uuid =
+            InvocationUuid::deterministic(&[service_name.as_bytes(), idempotency_key.as_bytes()]);
+        let key = Bytes::from(invocation_uuid.to_string());
+        FullInvocationId::new(service_name, key, invocation_uuid)
+    } else {
+        let invocation_uuid = InvocationUuid::deterministic(&[
+            service_name.as_bytes(),
+            &key,
+            idempotency_key.as_bytes(),
+        ]);
+        FullInvocationId::new(service_name, key, invocation_uuid)
+    }
+}
+
 fn parse_idempotency_key_and_retention_period(
     headers: MetadataMap,
 ) -> Result<IdempotencyMode, Status> {
@@ -22,6 +22,8 @@ pub use options::{Options, OptionsBuilder, OptionsBuilderError};
 pub use server::{HyperServerIngress, IngressServerError, StartSignal};
 
 use bytes::Bytes;
+use futures::future::ready;
+use futures::stream::{self, BoxStream, StreamExt};
 use opentelemetry::Context;
 use restate_types::identifiers::FullInvocationId;
 use tonic::metadata::MetadataMap;
@@ -54,15 +56,41 @@ impl IngressRequestHeaders {
 }
 type HandlerRequest = (IngressRequestHeaders, Bytes);
 
-#[derive(Debug, Clone)]
+/// Messages of a server-streaming response. The stream ends with an `Err` if the invocation failed.
+type HandlerResponseStream = BoxStream<'static, Result<Bytes, Status>>;
+
+enum HandlerResponseBody {
+    Unary(Bytes),
+    ServerStreaming(HandlerResponseStream),
+}
+
+impl HandlerResponseBody {
+    fn into_stream(self) -> HandlerResponseStream {
+        match self {
+            HandlerResponseBody::Unary(body) => stream::once(ready(Ok(body))).boxed(),
+            HandlerResponseBody::ServerStreaming(stream) => stream,
+        }
+    }
+}
+
 struct HandlerResponse {
     metadata: MetadataMap,
-    body: Bytes,
+    body: HandlerResponseBody,
 }
 
 impl HandlerResponse {
     pub fn from_parts(metadata: MetadataMap, body: Bytes) -> Self {
-        Self { metadata, body }
+        Self {
+            metadata,
+            body: HandlerResponseBody::Unary(body),
+        }
+    }
+
+    pub fn from_stream(metadata: MetadataMap, stream: HandlerResponseStream) -> Self {
+        Self {
+            metadata,
+            body: HandlerResponseBody::ServerStreaming(stream),
+        }
     }
 
     pub fn from_message<T: prost::Message>(t: T) -> Self {
@@ -72,10 +100,7 @@ impl HandlerResponse {
 
 impl From<Bytes> for HandlerResponse {
     fn from(body: Bytes) -> Self {
-        HandlerResponse {
-            metadata: Default::default(),
-            body,
-        }
+        HandlerResponse::from_parts(Default::default(), body)
     }
 }
 
@@ -108,6 +133,56 @@ impl ConnectInfo {
 mod mocks {
     use restate_schema_api::deployment::{DeliveryOptions, DeploymentMetadata, ProtocolType};
     use restate_schema_impl::Schemas;
+    use restate_types::identifiers::InvocationId;
+    use restate_types::invocation::InvocationTermination;
+    use restate_worker_api::{InvocationHandle, InvocationStatus};
+    use std::sync::{Arc, Mutex};
+
+    /// Returns the same status for every invocation, and records the terminations.
+    #[derive(Debug, Clone)]
+    pub(super) struct MockInvocationHandle {
+        status: InvocationStatus,
+        terminations: Arc<Mutex<Vec<InvocationTermination>>>,
+    }
+
+    impl MockInvocationHandle {
+        pub(super) fn new(status: InvocationStatus) -> Self {
+            Self {
+                status,
+                terminations: Default::default(),
+            }
+        }
+
+        pub(super) fn terminations(&self) -> Vec<InvocationTermination> {
+            self.terminations.lock().unwrap().clone()
+        }
+    }
+
+    impl Default for MockInvocationHandle {
+        fn default() -> Self {
+            Self::new(InvocationStatus::completed())
+        }
+    }
+
+    impl InvocationHandle for MockInvocationHandle {
+        async fn terminate_invocation(
+            &self,
+            invocation_termination: InvocationTermination,
+        ) -> Result<(), restate_worker_api::Error> {
+            self.terminations
+                .lock()
+                .unwrap()
+                .push(invocation_termination);
+            Ok(())
+        }
+
+        async fn invocation_status(
+            &self,
+            _: InvocationId,
+        ) -> Result<InvocationStatus, restate_worker_api::Error> {
+            Ok(self.status.clone())
+        }
+    }
 
     pub(super) fn test_schemas() -> Schemas {
         let schemas = Schemas::default();
@@ -108,6 +108,20 @@ pub struct Options {
     ///
     /// JSON/Protobuf conversion options.
     json: JsonOptions,
+
+    /// # Propagated headers
+    ///
+    /// Names of the request headers to propagate to the invoked service. Header names are case-insensitive.
+    /// These headers are persisted together with the invocation, and are sent to the service deployment.
+    propagated_headers: Vec<String>,
+
+    /// # Invocation owner header
+    ///
+    /// Name of the request header identifying the client which starts an invocation, e.g. a tenant or client id set by a trusted proxy.
+    /// When set, the `dev.restate.Ingress` `Cancel`, `Kill` and `GetStatus` methods are enabled, and only a request carrying
+    /// the same header value the invocation was started with can use them. The header is always propagated to the invoked service.
+    /// When unset, these methods are disabled on the ingress, and `Cancel` and `Kill` are disabled for the services calling them too.
+    invocation_owner_header: Option<String>,
 }
 
 impl Default for Options {
@@ -116,16 +130,23 @@ impl Default for Options {
             bind_address: "0.0.0.0:8080".parse().unwrap(),
             concurrency_limit: 10_000_000,
             json: Default::default(),
+            propagated_headers: vec![],
+            invocation_owner_header: None,
         }
     }
 }
 
 impl Options {
-    pub fn build<Schemas, JsonDecoder, JsonEncoder>(
+    pub fn invocation_owner_header(&self) -> Option<&str> {
+        self.invocation_owner_header.as_deref()
+    }
+
+    pub fn build<Schemas, JsonDecoder, JsonEncoder, InvocationHandle>(
         self,
         request_tx: restate_ingress_dispatcher::IngressRequestSender,
         schemas: Schemas,
-    ) -> HyperServerIngress<Schemas>
+        invocation_handle: InvocationHandle,
+    ) -> HyperServerIngress<Schemas, InvocationHandle>
     where
         Schemas: JsonMapperResolver<
                 JsonToProtobufMapper = JsonDecoder,
@@ -138,17 +159,38 @@ impl Options {
             + Sync
             + 'static,
         JsonDecoder: Send,
-        JsonEncoder: Send,
+        JsonEncoder: Clone + Send + 'static,
+        InvocationHandle: restate_worker_api::InvocationHandle + Send + Sync + 'static,
     {
         let Options {
             bind_address,
             concurrency_limit,
             json,
+            mut propagated_headers,
+            invocation_owner_header,
         } = self;
 
+        // The owner of an invocation is checked against the headers persisted with it
+        if let Some(owner_header) = &invocation_owner_header {
+            if !propagated_headers
+                .iter()
+                .any(|header| header.eq_ignore_ascii_case(owner_header))
+            {
+                propagated_headers.push(owner_header.clone());
+            }
+        }
+
         crate::metric_definitions::describe_metrics();
-        let (hyper_ingress_server, _) =
-            HyperServerIngress::new(bind_address, concurrency_limit, json, schemas, request_tx);
+        let (hyper_ingress_server, _) = HyperServerIngress::new(
+            bind_address,
+            concurrency_limit,
+            json,
+            propagated_headers,
+            invocation_owner_header,
+            schemas,
+            request_tx,
+            invocation_handle,
+        );
 
         hyper_ingress_server
     }
@@ -8,19 +8,29 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use crate::IngressRequestHeaders;
-use bytes::Bytes;
+use crate::{HandlerResponseStream, IngressRequestHeaders};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::StreamExt;
 use http::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
 use hyper::Body;
 use prost_reflect::{DeserializeOptions, SerializeOptions};
 use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper, ProtobufToJsonMapper};
 use restate_types::errors::InvocationError;
+use std::convert::Infallible;
 use tonic::{Code, Status};
 use tracing::warn;
 
 const APPLICATION_JSON: &str = "application/json";
 const APPLICATION_PROTO: &str = "application/proto";
+const APPLICATION_CONNECT_JSON: &str = "application/connect+json";
+const APPLICATION_CONNECT_PROTO: &str = "application/connect+proto";
+const TEXT_EVENT_STREAM: &str = "text/event-stream";
+
+// Connect streaming envelope, see https://connectrpc.com/docs/protocol/#streaming-request
+const ENVELOPE_HEADER_LEN: usize = 5;
+const ENVELOPE_COMPRESSED_FLAG: u8 = 0b01;
+const ENVELOPE_END_STREAM_FLAG: u8 = 0b10;
 
 // Clippy false positive, might be caused by Bytes contained within HeaderValue.
 // https://github.com/rust-lang/rust/issues/40543#issuecomment-1212981256
@@ -28,6 +38,14 @@ const APPLICATION_PROTO: &str = "application/proto";
 const APPLICATION_JSON_HEADER: HeaderValue = HeaderValue::from_static(APPLICATION_JSON);
 #[allow(clippy::declare_interior_mutable_const)]
 const APPLICATION_PROTO_HEADER: HeaderValue = HeaderValue::from_static(APPLICATION_PROTO);
+#[allow(clippy::declare_interior_mutable_const)]
+const APPLICATION_CONNECT_JSON_HEADER: HeaderValue =
+    HeaderValue::from_static(APPLICATION_CONNECT_JSON);
+#[allow(clippy::declare_interior_mutable_const)]
+const APPLICATION_CONNECT_PROTO_HEADER: HeaderValue =
+    HeaderValue::from_static(APPLICATION_CONNECT_PROTO);
+#[allow(clippy::declare_interior_mutable_const)]
+const TEXT_EVENT_STREAM_HEADER: HeaderValue = HeaderValue::from_static(TEXT_EVENT_STREAM);
 
 pub(super) fn verify_headers_and_infer_body_type<B: http_body::Body>(
     request: &mut Request<B>,
@@ -59,12 +77,18 @@ pub enum ConnectBodyType {
     Protobuf,
     Json,
     OnlyJsonResponse,
+    StreamingProtobuf,
+    StreamingJson,
 }
 
 impl ConnectBodyType {
     fn parse_from_header(content_type: &HeaderValue) -> Option<Self> {
         if let Ok(ct) = content_type.to_str() {
-            return if ct.starts_with(APPLICATION_JSON) {
+            return if ct.starts_with(APPLICATION_CONNECT_JSON) {
+                Some(ConnectBodyType::StreamingJson)
+            } else if ct.starts_with(APPLICATION_CONNECT_PROTO) {
+                Some(ConnectBodyType::StreamingProtobuf)
+            } else if ct.starts_with(APPLICATION_JSON) {
                 Some(ConnectBodyType::Json)
             } else if ct.starts_with(APPLICATION_PROTO) {
                 Some(ConnectBodyType::Protobuf)
@@ -75,6 +99,14 @@ impl ConnectBodyType {
         None
     }
 
+    /// Returns true if the request uses the Connect streaming protocol.
+    pub(super) fn is_streaming(&self) -> bool {
+        matches!(
+            self,
+            ConnectBodyType::StreamingProtobuf | ConnectBodyType::StreamingJson
+        )
+    }
+
     pub(super) fn infer_encoder_and_decoder<MapperResolver, JsonDecoder, JsonEncoder>(
         &self,
         ingress_request_header: &IngressRequestHeaders,
@@ -88,7 +120,10 @@ impl ConnectBodyType {
     {
         match self {
             ConnectBodyType::Protobuf => Ok((Decoder::Protobuf, Encoder::Protobuf)),
-            ConnectBodyType::Json => {
+            ConnectBodyType::StreamingProtobuf => {
+                Ok((Decoder::EnvelopedProtobuf, Encoder::Protobuf))
+            }
+            ConnectBodyType::Json | ConnectBodyType::StreamingJson => {
                 let (json_decoder, json_encoder) = mapper_resolver
                     .resolve_json_mapper_for_service(
                         &ingress_request_header.service_name,
@@ -104,7 +139,14 @@ impl ConnectBodyType {
                         )
                     })?;
 
-                Ok((Decoder::Json(json_decoder), Encoder::Json(json_encoder)))
+                Ok((
+                    if self.is_streaming() {
+                        Decoder::EnvelopedJson(json_decoder)
+                    } else {
+                        Decoder::Json(json_decoder)
+                    },
+                    Encoder::Json(json_encoder),
+                ))
             }
             ConnectBodyType::OnlyJsonResponse => {
                 let (_, json_encoder) = mapper_resolver
@@ -132,6 +174,8 @@ pub(super) enum Decoder<JsonDecoder> {
     Protobuf,
     Empty,
     Json(JsonDecoder),
+    EnvelopedProtobuf,
+    EnvelopedJson(JsonDecoder),
 }
 
 impl<JsonDecoder: JsonToProtobufMapper> Decoder<JsonDecoder> {
@@ -154,24 +198,171 @@ impl<JsonDecoder: JsonToProtobufMapper> Decoder<JsonDecoder> {
                 unreachable!()
             }
             Decoder::Protobuf => Ok(collected_body),
-            Decoder::Json(mapper) => mapper
-                .json_to_protobuf(collected_body, json_deserialize_options)
-                .map_err(|e| {
-                    warn!("Error when parsing request: {}", e);
-                    status::status_response(Status::invalid_argument(format!(
-                        "Error when parsing request: {}",
-                        e
-                    )))
-                }),
+            Decoder::Json(mapper) => decode_json(mapper, collected_body, json_deserialize_options),
+            Decoder::EnvelopedProtobuf => read_envelope(collected_body),
+            Decoder::EnvelopedJson(mapper) => decode_json(
+                mapper,
+                read_envelope(collected_body)?,
+                json_deserialize_options,
+            ),
         }
     }
 }
 
+fn decode_json<JsonDecoder: JsonToProtobufMapper>(
+    mapper: JsonDecoder,
+    json_body: Bytes,
+    json_deserialize_options: &DeserializeOptions,
+) -> Result<Bytes, Response<Body>> {
+    mapper
+        .json_to_protobuf(json_body, json_deserialize_options)
+        .map_err(|e| {
+            warn!("Error when parsing request: {}", e);
+            status::status_response(Status::invalid_argument(format!(
+                "Error when parsing request: {}",
+                e
+            )))
+        })
+}
+
+/// Unwraps the single enveloped message of a Connect streaming request.
+fn read_envelope(mut body: Bytes) -> Result<Bytes, Response<Body>> {
+    if body.len() < ENVELOPE_HEADER_LEN {
+        return Err(status::status_response(Status::invalid_argument(
+            "Request body is not a valid Connect envelope",
+        )));
+    }
+    let flags = body.get_u8();
+    let len = body.get_u32() as usize;
+    if flags & ENVELOPE_COMPRESSED_FLAG != 0 {
+        return Err(status::not_implemented());
+    }
+    if body.len() != len {
+        return Err(status::status_response(Status::invalid_argument(format!(
+            "Connect envelope declares {} bytes, but the body contains {} bytes",
+            len,
+            body.len()
+        ))));
+    }
+    Ok(body)
+}
+
 pub(super) enum Encoder<JsonEncoder> {
     Protobuf,
     Json(JsonEncoder),
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum StreamingFraming {
+    /// Connect streaming envelopes, terminated by an end-of-stream message.
+    Envelope,
+    /// Server-sent events, used for plain JSON and GET requests.
+    ServerSentEvents,
+}
+
+impl StreamingFraming {
+    fn frame_message(&self, message: Bytes) -> Bytes {
+        match self {
+            StreamingFraming::Envelope => envelope(0, &message),
+            StreamingFraming::ServerSentEvents => {
+                let mut buf = BytesMut::with_capacity(message.len() + 8);
+                buf.put_slice(b"data: ");
+                buf.put_slice(&message);
+                buf.put_slice(b"\n\n");
+                buf.freeze()
+            }
+        }
+    }
+
+    fn frame_end(&self, status: Option<&Status>) -> Bytes {
+        match (self, status) {
+            (StreamingFraming::Envelope, status) => {
+                envelope(ENVELOPE_END_STREAM_FLAG, &status::end_stream_json(status))
+            }
+            (StreamingFraming::ServerSentEvents, Some(status)) => {
+                let mut buf = BytesMut::new();
+                buf.put_slice(b"event: error\ndata: ");
+                buf.put_slice(&status::status_json(status));
+                buf.put_slice(b"\n\n");
+                buf.freeze()
+            }
+            (StreamingFraming::ServerSentEvents, None) => Bytes::new(),
+        }
+    }
+}
+
+fn envelope(flags: u8, message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(ENVELOPE_HEADER_LEN + message.len());
+    buf.put_u8(flags);
+    buf.put_u32(message.len() as u32);
+    buf.put_slice(message);
+    buf.freeze()
+}
+
+impl<JsonEncoder: ProtobufToJsonMapper + Clone + Send + 'static> Encoder<JsonEncoder> {
+    fn encode_message(
+        &self,
+        message: Bytes,
+        json_serialize_options: &SerializeOptions,
+    ) -> Result<Bytes, Status> {
+        match self {
+            Encoder::Protobuf => Ok(message),
+            Encoder::Json(encoder) => encoder
+                .clone()
+                .protobuf_to_json(message, json_serialize_options)
+                .map_err(|err| {
+                    warn!("The response payload cannot be serialized: {}", err);
+                    Status::internal(format!("The response payload cannot be serialized: {err}"))
+                }),
+        }
+    }
+
+    /// Encodes a stream of response messages using the given framing.
+    /// A failure terminates the stream, and is sent as the last message.
+    pub(super) fn encode_streaming_response(
+        self,
+        framing: StreamingFraming,
+        response_stream: HandlerResponseStream,
+        json_serialize_options: SerializeOptions,
+    ) -> Response<Body> {
+        let content_type = match (framing, &self) {
+            (StreamingFraming::Envelope, Encoder::Protobuf) => APPLICATION_CONNECT_PROTO_HEADER,
+            (StreamingFraming::Envelope, Encoder::Json(_)) => APPLICATION_CONNECT_JSON_HEADER,
+            (StreamingFraming::ServerSentEvents, _) => TEXT_EVENT_STREAM_HEADER,
+        };
+
+        let body_stream = futures::stream::unfold(
+            Some((response_stream, self, json_serialize_options)),
+            move |state| async move {
+                let (mut response_stream, encoder, json_serialize_options) = state?;
+                let frame = match response_stream.next().await {
+                    Some(Ok(message)) => {
+                        match encoder.encode_message(message, &json_serialize_options) {
+                            Ok(message) => {
+                                return Some((
+                                    framing.frame_message(message),
+                                    Some((response_stream, encoder, json_serialize_options)),
+                                ))
+                            }
+                            Err(status) => framing.frame_end(Some(&status)),
+                        }
+                    }
+                    Some(Err(status)) => framing.frame_end(Some(&status)),
+                    None => framing.frame_end(None),
+                };
+                Some((frame, None))
+            },
+        )
+        .map(Ok::<_, Infallible>);
+
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::wrap_stream(body_stream))
+            .unwrap()
+    }
+}
+
 impl<JsonEncoder: ProtobufToJsonMapper> Encoder<JsonEncoder> {
     pub(super) fn encode_response(
         self,
@@ -238,6 +429,12 @@ pub(super) mod status {
         message: &'a str,
     }
 
+    #[derive(Serialize)]
+    struct EndStreamJsonResponse<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<StatusJsonResponse<'a>>,
+    }
+
     impl<'a> From<&'a Code> for StatusJsonResponse<'a> {
         fn from(code: &'a Code) -> Self {
             Self {
@@ -276,6 +473,18 @@ pub(super) mod status {
             .unwrap()
     }
 
+    pub(super) fn status_json(status: &Status) -> Vec<u8> {
+        let status_response: StatusJsonResponse<'_> = status.into();
+        serde_json::to_vec(&status_response).unwrap()
+    }
+
+    pub(super) fn end_stream_json(status: Option<&Status>) -> Vec<u8> {
+        serde_json::to_vec(&EndStreamJsonResponse {
+            error: status.map(Into::into),
+        })
+        .unwrap()
+    }
+
     pub(super) fn http_code_response(status_code: StatusCode) -> Response<Body> {
         Response::builder()
             .status(status_code)
@@ -353,6 +562,44 @@ mod tests {
             .unwrap(),
             ConnectBodyType::Json
         );
+        assert_eq!(
+            ConnectBodyType::parse_from_header(&HeaderValue::from_static(
+                "application/connect+json"
+            ))
+            .unwrap(),
+            ConnectBodyType::StreamingJson
+        );
+        assert_eq!(
+            ConnectBodyType::parse_from_header(&HeaderValue::from_static(
+                "application/connect+proto"
+            ))
+            .unwrap(),
+            ConnectBodyType::StreamingProtobuf
+        );
+    }
+
+    #[test]
+    fn read_enveloped_message() {
+        let message = envelope(0, b"hello");
+        assert_eq!(
+            read_envelope(message).unwrap(),
+            Bytes::from_static(b"hello")
+        );
+
+        let err_response = read_envelope(Bytes::from_static(&[0, 0, 0, 0, 10, 1])).unwrap_err();
+        assert_eq!(err_response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn end_stream_message() {
+        assert_eq!(status::end_stream_json(None), b"{}");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&status::end_stream_json(Some(
+                &Status::not_found("my error")
+            )))
+            .unwrap(),
+            json!({"error": {"code": "not_found", "message": "my error"}})
+        );
     }
 
     #[test]
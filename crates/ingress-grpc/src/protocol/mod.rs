@@ -42,7 +42,8 @@ impl Protocol {
             (&Method::POST, Some(ct))
                 if ct.as_bytes().starts_with(b"application/json")
                     || ct.as_bytes().starts_with(b"application/proto")
-                    || ct.as_bytes().starts_with(b"application/protobuf") =>
+                    || ct.as_bytes().starts_with(b"application/protobuf")
+                    || ct.as_bytes().starts_with(b"application/connect+") =>
             {
                 Some(Protocol::Connect)
             }
@@ -75,6 +76,7 @@ impl Protocol {
     ) -> Result<Response<BoxBody>, BoxError>
     where
         MapperResolver: JsonMapperResolver,
+        MapperResolver::ProtobufToJsonMapper: Clone + Send + 'static,
         Handler: FnOnce(HandlerRequest) -> HandlerFut + Send + 'static,
         HandlerFut: Future<Output = HandlerResult> + Send,
     {
@@ -117,12 +119,12 @@ impl Protocol {
     {
         // Why FnOnce and service_fn_once are safe here?
         //
-        // The reason is that the interface of Grpc::unary() is probably incorrect,
+        // The reason is that the interface of Grpc::server_streaming() is probably incorrect,
         // because it gets the ownership of the service, rather than a &self mut borrow.
         //
         // There is no reason to get the ownership, as the service could be reused.
-        // There is also no reason for which Grpc::unary() should invoke twice Service::call() within
-        // its code (you can verify this point by looking inside the Grpc::unary() implementation).
+        // There is also no reason for which Grpc::server_streaming() should invoke twice Service::call() within
+        // its code (you can verify this point by looking inside the Grpc::server_streaming() implementation).
         //
         // Hence we can safely provide a service which after the first Service::call()
         // is consumed and it cannot be reused anymore.
@@ -130,8 +132,8 @@ impl Protocol {
         let mut s = tonic_web::GrpcWebLayer::new().layer(service_fn_once(move |hyper_req| async {
             Ok::<_, Status>(
                 Grpc::new(tonic_adapter::NoopCodec)
-                    .unary(
-                        tonic_adapter::TonicUnaryServiceAdapter::new(
+                    .server_streaming(
+                        tonic_adapter::TonicServiceAdapter::new(
                             ingress_request_headers,
                             handler_fn,
                         ),
@@ -156,6 +158,7 @@ impl Protocol {
     ) -> Response<hyper::Body>
     where
         MapperResolver: JsonMapperResolver,
+        MapperResolver::ProtobufToJsonMapper: Clone + Send + 'static,
         Handler: FnOnce(HandlerRequest) -> HandlerFut + Send + 'static,
         HandlerFut: Future<Output = HandlerResult> + Send,
     {
@@ -175,18 +178,40 @@ impl Protocol {
             Ok(c) => c,
             Err(res) => return res,
         };
-        let handler_response =
+        let HandlerResponse { metadata, body } =
             match handler_fn((ingress_request_headers, ingress_request_body)).await {
                 Ok(ingress_response_body) => ingress_response_body,
                 Err(error) => return connect_adapter::status::status_response(error),
             };
 
+        let mut response = match (content_type.is_streaming(), body) {
+            (false, HandlerResponseBody::Unary(body)) => {
+                encoder.encode_response(body, &json.to_serialize_options())
+            }
+            (true, body) => encoder.encode_streaming_response(
+                connect_adapter::StreamingFraming::Envelope,
+                body.into_stream(),
+                json.to_serialize_options(),
+            ),
+            // Plain JSON and GET clients can consume server-streaming responses as server-sent events
+            (false, HandlerResponseBody::ServerStreaming(stream))
+                if matches!(encoder, connect_adapter::Encoder::Json(_)) =>
+            {
+                encoder.encode_streaming_response(
+                    connect_adapter::StreamingFraming::ServerSentEvents,
+                    stream,
+                    json.to_serialize_options(),
+                )
+            }
+            (false, HandlerResponseBody::ServerStreaming(_)) => {
+                return connect_adapter::status::status_response(Status::invalid_argument(
+                    "Server-streaming methods require the application/connect+proto content type",
+                ))
+            }
+        };
+
         // Add headers
-        let mut response =
-            encoder.encode_response(handler_response.body, &json.to_serialize_options());
-        response
-            .headers_mut()
-            .extend(handler_response.metadata.into_headers());
+        response.headers_mut().extend(metadata.into_headers());
         response
     }
 }
@@ -300,6 +325,82 @@ mod tests {
         );
     }
 
+    fn greeter_streaming_service_fn(ingress_req: HandlerRequest) -> Ready<HandlerResult> {
+        let person = restate_pb::mocks::greeter::GreetingRequest::decode(ingress_req.1)
+            .unwrap()
+            .person;
+        let messages: Vec<Result<Bytes, Status>> = ["Hello", "Bonjour"]
+            .into_iter()
+            .map(|greeting| {
+                Ok(restate_pb::mocks::greeter::GreetingResponse {
+                    greeting: format!("{greeting} {person}"),
+                }
+                .encode_to_vec()
+                .into())
+            })
+            .collect();
+        ok(HandlerResponse::from_stream(
+            MetadataMap::new(),
+            futures::stream::iter(messages).boxed(),
+        ))
+    }
+
+    #[test(tokio::test)]
+    async fn handle_streaming_json_connect_request() {
+        let request_body = json!({"person": "Francesco"}).to_string();
+        let mut enveloped_request_body = vec![0];
+        enveloped_request_body.extend_from_slice(&(request_body.len() as u32).to_be_bytes());
+        enveloped_request_body.extend_from_slice(request_body.as_bytes());
+
+        let request = Request::builder()
+            .uri("http://localhost/greeter.Greeter/Greet")
+            .method(Method::POST)
+            .header(CONTENT_TYPE, "application/connect+json")
+            .body(enveloped_request_body.into())
+            .unwrap();
+
+        assert!(let Some(Protocol::Connect) = Protocol::pick_protocol(request.method(), request.headers()));
+
+        let res = Protocol::handle_connect_request(
+            IngressRequestHeaders::new(
+                "greeter.Greeter".to_string(),
+                "Greet".to_string(),
+                Context::default(),
+                MetadataMap::default(),
+            ),
+            mocks::test_schemas(),
+            JsonOptions::default(),
+            request,
+            greeter_streaming_service_fn,
+        )
+        .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "application/connect+json"
+        );
+
+        let mut body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let mut messages = vec![];
+        while !body.is_empty() {
+            let flags = body[0];
+            let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+            let message: serde_json::Value = serde_json::from_slice(&body[5..5 + len]).unwrap();
+            messages.push((flags, message));
+            body = body.slice(5 + len..);
+        }
+
+        assert_eq!(
+            messages,
+            vec![
+                (0, json!({"greeting": "Hello Francesco"})),
+                (0, json!({"greeting": "Bonjour Francesco"})),
+                (2, json!({})),
+            ]
+        );
+    }
+
     #[test(tokio::test)]
     async fn handle_protobuf_connect_request() {
         let request = Request::builder()
@@ -17,17 +17,19 @@ use std::task::{ready, Poll};
 use bytes::{Buf, BufMut, Bytes};
 use pin_project::pin_project;
 use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
-use tonic::server::UnaryService;
+use tonic::server::ServerStreamingService;
 use tonic::{Extensions, Status};
 
-// --- UnaryService adapter that can execute call only once
+// --- ServerStreamingService adapter that can execute call only once
 
-pub(super) struct TonicUnaryServiceAdapter<H> {
+// We always serve requests as server streaming, because on the wire a unary response is
+// indistinguishable from a server streaming response carrying a single message.
+pub(super) struct TonicServiceAdapter<H> {
     ingress_request_headers: Option<IngressRequestHeaders>,
     inner_fn: Option<H>,
 }
 
-impl<H> TonicUnaryServiceAdapter<H> {
+impl<H> TonicServiceAdapter<H> {
     pub(super) fn new(ingress_request_headers: IngressRequestHeaders, inner_fn: H) -> Self {
         Self {
             ingress_request_headers: Some(ingress_request_headers),
@@ -36,13 +38,14 @@ impl<H> TonicUnaryServiceAdapter<H> {
     }
 }
 
-impl<H, F> UnaryService<Bytes> for TonicUnaryServiceAdapter<H>
+impl<H, F> ServerStreamingService<Bytes> for TonicServiceAdapter<H>
 where
     H: FnOnce(HandlerRequest) -> F + Send,
     F: Future<Output = HandlerResult> + Send,
 {
     type Response = Bytes;
-    type Future = TonicUnaryServiceAdapterFuture<F>;
+    type ResponseStream = HandlerResponseStream;
+    type Future = TonicServiceAdapterFuture<F>;
 
     fn call(&mut self, request: tonic::Request<Bytes>) -> Self::Future {
         let inner_fn = self
@@ -53,23 +56,27 @@ where
             self.ingress_request_headers.take().unwrap(),
             request.into_inner(),
         ));
-        TonicUnaryServiceAdapterFuture(fut)
+        TonicServiceAdapterFuture(fut)
     }
 }
 
 #[pin_project]
-pub(super) struct TonicUnaryServiceAdapterFuture<F>(#[pin] F);
+pub(super) struct TonicServiceAdapterFuture<F>(#[pin] F);
 
-impl<F> Future for TonicUnaryServiceAdapterFuture<F>
+impl<F> Future for TonicServiceAdapterFuture<F>
 where
     F: Future<Output = HandlerResult>,
 {
-    type Output = Result<tonic::Response<Bytes>, Status>;
+    type Output = Result<tonic::Response<HandlerResponseStream>, Status>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
         Poll::Ready(ready!(this.0.poll(cx)).map(|response| {
-            tonic::Response::from_parts(response.metadata, response.body, Extensions::default())
+            tonic::Response::from_parts(
+                response.metadata,
+                response.body.into_stream(),
+                Extensions::default(),
+            )
         }))
     }
 }
@@ -49,20 +49,24 @@ pub enum IngressServerError {
     Running(#[from] hyper::Error),
 }
 
-pub struct HyperServerIngress<Schemas> {
+pub struct HyperServerIngress<Schemas, InvocationHandle> {
     listening_addr: SocketAddr,
     concurrency_limit: usize,
 
     // Parameters to build the layers
     json: JsonOptions,
+    propagated_headers: Vec<String>,
+    invocation_owner_header: Option<String>,
     schemas: Schemas,
     request_tx: IngressRequestSender,
+    invocation_handle: InvocationHandle,
 
     // Signals
     start_signal_tx: oneshot::Sender<SocketAddr>,
 }
 
-impl<Schemas, JsonDecoder, JsonEncoder> HyperServerIngress<Schemas>
+impl<Schemas, JsonDecoder, JsonEncoder, InvocationHandle>
+    HyperServerIngress<Schemas, InvocationHandle>
 where
     Schemas: JsonMapperResolver<JsonToProtobufMapper = JsonDecoder, ProtobufToJsonMapper = JsonEncoder>
         + ServiceMetadataResolver
@@ -73,15 +77,19 @@ where
         + Sync
         + 'static,
     JsonDecoder: Send,
-    JsonEncoder: Send,
+    JsonEncoder: Clone + Send + 'static,
+    InvocationHandle: restate_worker_api::InvocationHandle + Send + Sync + 'static,
 {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         listening_addr: SocketAddr,
         concurrency_limit: usize,
         json: JsonOptions,
+        propagated_headers: Vec<String>,
+        invocation_owner_header: Option<String>,
         schemas: Schemas,
         request_tx: IngressRequestSender,
+        invocation_handle: InvocationHandle,
     ) -> (Self, StartSignal) {
         let (start_signal_tx, start_signal_rx) = oneshot::channel();
 
@@ -89,8 +97,11 @@ where
             listening_addr,
             concurrency_limit,
             json,
+            propagated_headers,
+            invocation_owner_header,
             schemas,
             request_tx,
+            invocation_handle,
             start_signal_tx,
         };
 
@@ -102,8 +113,11 @@ where
             listening_addr,
             concurrency_limit,
             json,
+            propagated_headers,
+            invocation_owner_header,
             schemas,
             request_tx,
+            invocation_handle,
             start_signal_tx,
         } = self;
 
@@ -120,8 +134,11 @@ where
             .layer(CorsLayer::very_permissive())
             .service(handler::Handler::new(
                 json,
+                propagated_headers,
+                invocation_owner_header,
                 schemas,
                 request_tx,
+                invocation_handle,
                 global_concurrency_limit_semaphore,
             ));
 
@@ -176,6 +193,10 @@ mod tests {
 
     use restate_ingress_dispatcher::{IdempotencyMode, IngressRequest};
     use restate_test_util::assert_eq;
+    use restate_types::errors::{InvocationError, UserErrorCode};
+    use restate_types::identifiers::{InvocationId, InvocationUuid};
+    use restate_types::invocation::{Header, InvocationTermination};
+    use restate_worker_api::{InvocationStatus, InvocationStatusKind};
 
     use crate::mocks::*;
 
@@ -281,6 +302,103 @@ mod tests {
         handle.close().await;
     }
 
+    #[test(tokio::test)]
+    async fn test_grpc_server_streaming_call() {
+        let (address, input, handle) = bootstrap_test().await;
+        let process_fut = tokio::spawn(async move {
+            let (fid, method_name, mut argument, _, response_tx, chunk_tx) =
+                input.await.unwrap().unwrap().expect_streaming_invocation();
+            assert_eq!(fid.service_id.service_name, "greeter.Greeter");
+            assert_eq!(method_name, "GreetStream");
+            let greeting_req =
+                restate_pb::mocks::greeter::GreetingRequest::decode(&mut argument).unwrap();
+            assert_eq!(&greeting_req.person, "Francesco");
+
+            for greetings in 1..=2 {
+                chunk_tx
+                    .send(
+                        restate_pb::mocks::greeter::CountResponse { greetings }
+                            .encode_to_vec()
+                            .into(),
+                    )
+                    .unwrap();
+            }
+            // The dispatcher closes the chunk stream before sending the final response
+            drop(chunk_tx);
+            response_tx.send(Ok(Bytes::new()).into()).unwrap();
+        });
+
+        let mut client = restate_pb::mocks::greeter::greeter_client::GreeterClient::connect(
+            format!("http://{address}"),
+        )
+        .await
+        .unwrap();
+
+        let mut stream = client
+            .greet_stream(restate_pb::mocks::greeter::GreetingRequest {
+                person: "Francesco".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let mut messages = vec![];
+        while let Some(message) = stream.message().await.unwrap() {
+            messages.push(message.greetings);
+        }
+        assert_eq!(messages, vec![1, 2]);
+
+        // Check that the input processing completed
+        process_fut.await.unwrap();
+
+        handle.close().await;
+    }
+
+    #[test(tokio::test)]
+    async fn test_grpc_server_streaming_call_failure() {
+        let (address, input, handle) = bootstrap_test().await;
+        let process_fut = tokio::spawn(async move {
+            let (_, _, _, _, response_tx, chunk_tx) =
+                input.await.unwrap().unwrap().expect_streaming_invocation();
+
+            chunk_tx
+                .send(
+                    restate_pb::mocks::greeter::CountResponse { greetings: 1 }
+                        .encode_to_vec()
+                        .into(),
+                )
+                .unwrap();
+            drop(chunk_tx);
+            response_tx
+                .send(Err(InvocationError::new(UserErrorCode::Internal, "boom")).into())
+                .unwrap();
+        });
+
+        let mut client = restate_pb::mocks::greeter::greeter_client::GreeterClient::connect(
+            format!("http://{address}"),
+        )
+        .await
+        .unwrap();
+
+        let mut stream = client
+            .greet_stream(restate_pb::mocks::greeter::GreetingRequest {
+                person: "Francesco".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        // The chunks produced before the failure are delivered, then the stream fails
+        assert_eq!(stream.message().await.unwrap().unwrap().greetings, 1);
+        let status = stream.message().await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Internal);
+        assert_eq!(status.message(), "boom");
+
+        process_fut.await.unwrap();
+
+        handle.close().await;
+    }
+
     #[test(tokio::test)]
     async fn idempotency_key_parsing() {
         let expected_greeting_response = restate_pb::mocks::greeter::GreetingResponse {
@@ -332,7 +450,283 @@ mod tests {
         handle.close().await;
     }
 
+    #[test(tokio::test)]
+    async fn propagate_allowed_headers() {
+        let (address, input, handle) = bootstrap_test().await;
+        let process_fut = tokio::spawn(async move {
+            let ingress_request = input.await.unwrap().unwrap();
+            assert_eq!(
+                ingress_request.headers(),
+                &[Header::new("x-tenant-id", "my-tenant")]
+            );
+            let (_, _, _, _, _, response_tx) = ingress_request.expect_invocation();
+            response_tx.send(Ok(Bytes::new()).into()).unwrap();
+        });
+
+        let mut client = restate_pb::mocks::greeter::greeter_client::GreeterClient::connect(
+            format!("http://{address}"),
+        )
+        .await
+        .unwrap();
+
+        let mut request = Request::new(restate_pb::mocks::greeter::GreetingRequest {
+            person: "Francesco".to_string(),
+        });
+        request.metadata_mut().insert(
+            MetadataKey::from_static("x-tenant-id"),
+            AsciiMetadataValue::from_static("my-tenant"),
+        );
+        request.metadata_mut().insert(
+            MetadataKey::from_static("x-not-propagated"),
+            AsciiMetadataValue::from_static("value"),
+        );
+
+        client.greet(request).await.unwrap();
+
+        // Check that the input processing completed
+        process_fut.await.unwrap();
+
+        handle.close().await;
+    }
+
+    #[test(tokio::test)]
+    async fn batch_invoke() {
+        let (address, input, handle) = bootstrap_test().await;
+        let process_fut = tokio::spawn(async move {
+            let (invocations, ack_tx) = input
+                .await
+                .unwrap()
+                .unwrap()
+                .expect_batch_background_invocation();
+            assert_eq!(invocations.len(), 1);
+            let (fid, method_name, mut argument, idempotency_mode) =
+                invocations.into_iter().next().unwrap();
+            assert_eq!(fid.service_id.service_name, "greeter.Greeter");
+            assert_eq!(method_name, "Greet");
+            let greeting_req =
+                restate_pb::mocks::greeter::GreetingRequest::decode(&mut argument).unwrap();
+            assert_eq!(&greeting_req.person, "Francesco");
+            assert_eq!(
+                idempotency_mode,
+                IdempotencyMode::key(Bytes::from_static(b"123456"), None)
+            );
+            ack_tx.send(()).unwrap();
+            fid
+        });
+
+        // Send the request
+        let json_payload = json!({"invocations": [
+            {
+                "invocation": {
+                    "service": "greeter.Greeter",
+                    "method": "Greet",
+                    "argument": {"person": "Francesco"}
+                },
+                "idempotencyKey": "123456"
+            },
+            {
+                "invocation": {"service": "greeter.Unknown", "method": "Greet"}
+            }
+        ]});
+        let http_response = hyper::Client::new()
+            .request(
+                hyper::Request::post(format!("http://{address}/dev.restate.Ingress/BatchInvoke"))
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::to_vec(&json_payload).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(http_response.status(), StatusCode::OK);
+
+        // check that the input processing has completed
+        let fid = process_fut.await.unwrap();
+
+        // Read the http_response_future
+        let (_, response_body) = http_response.into_parts();
+        let response_bytes = hyper::body::to_bytes(response_body).await.unwrap();
+        let response_json_value: serde_json::Value =
+            serde_json::from_slice(&response_bytes).unwrap();
+        let invocations = response_json_value
+            .get("invocations")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(invocations.len(), 2);
+        assert_eq!(
+            invocations[0].get("id").unwrap().as_str().unwrap(),
+            fid.to_string()
+        );
+        assert_eq!(
+            invocations[1]
+                .get("failure")
+                .unwrap()
+                .get("code")
+                .unwrap()
+                .as_u64()
+                .unwrap(),
+            5
+        );
+
+        handle.close().await;
+    }
+
+    #[test(tokio::test)]
+    async fn idempotent_ingress_invoke() {
+        let (address, input, handle) = bootstrap_test().await;
+        let process_fut = tokio::spawn(async move {
+            let (fid, method_name, mut argument, (dedup_source, _), _, ack_tx) = input
+                .await
+                .unwrap()
+                .unwrap()
+                .expect_idempotent_background_invocation();
+            assert_eq!(fid.service_id.service_name, "greeter.Greeter");
+            assert_eq!(method_name, "Greet");
+            let greeting_req =
+                restate_pb::mocks::greeter::GreetingRequest::decode(&mut argument).unwrap();
+            assert_eq!(&greeting_req.person, "Francesco");
+            // The key of the unkeyed greeter is derived from the idempotency key
+            assert_eq!(
+                fid.service_id.key,
+                Bytes::from(fid.invocation_uuid.to_string())
+            );
+            assert!(dedup_source.starts_with("idempotency-key/greeter.Greeter/"));
+            assert!(dedup_source.ends_with("/123456"));
+            ack_tx.send(()).unwrap();
+            fid
+        });
+
+        // Send the request
+        let json_payload = json!({
+            "service": "greeter.Greeter",
+            "method": "Greet",
+            "argument": {"person": "Francesco"}
+        });
+        let http_response = hyper::Client::new()
+            .request(
+                hyper::Request::post(format!("http://{address}/dev.restate.Ingress/Invoke"))
+                    .header(CONTENT_TYPE, "application/json")
+                    .header("idempotency-key", "123456")
+                    .body(Body::from(serde_json::to_vec(&json_payload).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(http_response.status(), StatusCode::OK);
+
+        // check that the input processing has completed
+        let fid = process_fut.await.unwrap();
+        assert_eq!(
+            fid.invocation_uuid,
+            InvocationUuid::deterministic(&[b"greeter.Greeter", b"123456"])
+        );
+
+        // Read the http_response_future
+        let (_, response_body) = http_response.into_parts();
+        let response_bytes = hyper::body::to_bytes(response_body).await.unwrap();
+        let response_json_value: serde_json::Value =
+            serde_json::from_slice(&response_bytes).unwrap();
+        assert_eq!(
+            response_json_value.get("id").unwrap().as_str().unwrap(),
+            fid.to_string()
+        );
+
+        handle.close().await;
+    }
+
+    fn running_invocation_of(tenant: &str) -> MockInvocationHandle {
+        MockInvocationHandle::new(InvocationStatus {
+            kind: InvocationStatusKind::Running,
+            headers: vec![Header::new("x-tenant-id", tenant)],
+        })
+    }
+
+    async fn call_ingress_method(
+        address: SocketAddr,
+        method: &str,
+        invocation_id: &InvocationId,
+        tenant: &str,
+    ) -> hyper::Response<Body> {
+        hyper::Client::new()
+            .request(
+                hyper::Request::post(format!("http://{address}/dev.restate.Ingress/{method}"))
+                    .header(CONTENT_TYPE, "application/json")
+                    .header("x-tenant-id", tenant)
+                    .body(Body::from(
+                        serde_json::to_vec(&json!({"id": invocation_id.to_string()})).unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test(tokio::test)]
+    async fn cancel_invocation() {
+        let invocation_handle = running_invocation_of("my-tenant");
+        let (address, _, handle) =
+            bootstrap_test_with_invocation_handle(invocation_handle.clone()).await;
+        let invocation_id = InvocationId::mock_random();
+
+        let http_response =
+            call_ingress_method(address, "Cancel", &invocation_id, "my-tenant").await;
+        assert_eq!(http_response.status(), StatusCode::OK);
+        assert_eq!(
+            invocation_handle.terminations(),
+            vec![InvocationTermination::cancel(invocation_id)]
+        );
+
+        handle.close().await;
+    }
+
+    #[test(tokio::test)]
+    async fn cancel_invocation_of_another_client() {
+        let invocation_handle = running_invocation_of("my-tenant");
+        let (address, _, handle) =
+            bootstrap_test_with_invocation_handle(invocation_handle.clone()).await;
+        let invocation_id = InvocationId::mock_random();
+
+        let http_response =
+            call_ingress_method(address, "Kill", &invocation_id, "another-tenant").await;
+        assert_eq!(http_response.status(), StatusCode::FORBIDDEN);
+        assert!(invocation_handle.terminations().is_empty());
+
+        handle.close().await;
+    }
+
+    #[test(tokio::test)]
+    async fn get_invocation_status() {
+        let (address, _, handle) =
+            bootstrap_test_with_invocation_handle(running_invocation_of("my-tenant")).await;
+
+        let http_response = call_ingress_method(
+            address,
+            "GetStatus",
+            &InvocationId::mock_random(),
+            "my-tenant",
+        )
+        .await;
+        assert_eq!(http_response.status(), StatusCode::OK);
+
+        let (_, response_body) = http_response.into_parts();
+        let response_bytes = hyper::body::to_bytes(response_body).await.unwrap();
+        let response_json_value: serde_json::Value =
+            serde_json::from_slice(&response_bytes).unwrap();
+        assert_eq!(
+            response_json_value.get("status").unwrap().as_str().unwrap(),
+            "RUNNING"
+        );
+
+        handle.close().await;
+    }
+
     async fn bootstrap_test() -> (SocketAddr, JoinHandle<Option<IngressRequest>>, TestHandle) {
+        bootstrap_test_with_invocation_handle(MockInvocationHandle::default()).await
+    }
+
+    async fn bootstrap_test_with_invocation_handle(
+        invocation_handle: MockInvocationHandle,
+    ) -> (SocketAddr, JoinHandle<Option<IngressRequest>>, TestHandle) {
         let (drain, watch) = drain::channel();
         let (ingress_request_tx, mut ingress_request_rx) = mpsc::unbounded_channel();
 
@@ -341,8 +735,11 @@ mod tests {
             "0.0.0.0:0".parse().unwrap(),
             Semaphore::MAX_PERMITS,
             JsonOptions::default(),
+            vec!["x-tenant-id".to_string()],
+            Some("x-tenant-id".to_string()),
             test_schemas(),
             ingress_request_tx,
+            invocation_handle,
         );
         let ingress_handle = tokio::spawn(ingress.run(watch));
 
@@ -8,48 +8,38 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::offsets::SubscriptionOffsets;
+use crate::source_connector::{
+    Error, InFlightMessages, MessageSender, SourceConnector, SourceMessage,
+};
 use base64::Engine;
 use bytes::Bytes;
-use opentelemetry_api::trace::TraceContextExt;
-use rdkafka::consumer::{Consumer, DefaultConsumerContext, StreamConsumer};
-use rdkafka::error::KafkaError;
-use rdkafka::message::BorrowedMessage;
-use rdkafka::{ClientConfig, Message};
-use restate_ingress_dispatcher::{
-    DeduplicationId, EventError, IngressRequest, IngressRequestSender,
+use futures::future::{self, BoxFuture};
+use futures::FutureExt;
+use rdkafka::consumer::stream_consumer::StreamPartitionQueue;
+use rdkafka::consumer::{
+    BaseConsumer, CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer,
 };
-use restate_pb::restate::Event;
+use rdkafka::error::{KafkaError, KafkaResult};
+use rdkafka::message::{BorrowedMessage, Headers};
+use rdkafka::{ClientConfig, ClientContext, Message, Offset, TopicPartitionList};
+use restate_ingress_dispatcher::DeduplicationId;
 use restate_schema_api::subscription::{
-    EventReceiverServiceInstanceType, KafkaOrderingKeyFormat, Sink, Source, Subscription,
+    EventReceiverServiceInstanceType, KafkaOrderingKeyFormat, OffsetReset, Sink, Source,
+    Subscription,
 };
 use restate_types::identifiers::SubscriptionId;
-use restate_types::invocation::SpanRelation;
 use restate_types::message::MessageIndex;
 use std::collections::HashMap;
 use std::fmt;
-use tokio::sync::oneshot;
-use tracing::{debug, info, info_span, Instrument};
-use tracing_opentelemetry::OpenTelemetrySpanExt;
-
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error(transparent)]
-    Kafka(#[from] KafkaError),
-    #[error(
-        "error processing message topic {topic} partition {partition} offset {offset}: {cause}"
-    )]
-    Event {
-        topic: String,
-        partition: i32,
-        offset: i64,
-        #[source]
-        cause: EventError,
-    },
-    #[error("ingress dispatcher channel is closed")]
-    IngressDispatcherClosed,
-}
+use std::panic;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::{AbortHandle, JoinSet};
+use tracing::{debug, warn};
 
-type MessageConsumer = StreamConsumer<DefaultConsumerContext>;
+type MessageConsumer = StreamConsumer<RebalanceContext>;
 
 pub struct KafkaDeduplicationId(String);
 
@@ -85,152 +75,280 @@ impl DeduplicationId for KafkaDeduplicationId {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MessageSender {
-    subscription: Subscription,
-    tx: IngressRequestSender,
+/// Converts the Kafka message to a [`SourceMessage`].
+fn source_message(
+    ordering_key_format: &KafkaOrderingKeyFormat,
+    consumer_group_id: &str,
+    subscription: &Subscription,
+    msg: &BorrowedMessage<'_>,
+) -> SourceMessage<KafkaDeduplicationId> {
+    SourceMessage {
+        system: "kafka",
+        source_name: msg.topic().to_owned(),
+        position: format!(
+            "topic {} partition {} offset {}",
+            msg.topic(),
+            msg.partition(),
+            msg.offset()
+        ),
+        ordering_key: generate_ordering_key(ordering_key_format, consumer_group_id, msg),
+        key: if let Some(k) = msg.key() {
+            Bytes::copy_from_slice(k)
+        } else {
+            Bytes::default()
+        },
+        payload: if let Some(p) = msg.payload() {
+            Bytes::copy_from_slice(p)
+        } else {
+            Bytes::default()
+        },
+        headers: msg
+            .headers()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .map(|header| {
+                        (
+                            header.key.to_owned(),
+                            header
+                                .value
+                                .map(|v| String::from_utf8_lossy(v).into_owned())
+                                .unwrap_or_default(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        attributes: generate_events_attributes(msg, subscription.id()),
+        deduplication: generate_deduplication_id(
+            consumer_group_id,
+            subscription.offsets_generation(),
+            msg,
+        ),
+    }
 }
 
-impl MessageSender {
-    pub fn new(subscription: Subscription, tx: IngressRequestSender) -> Self {
-        Self { subscription, tx }
+fn generate_ordering_key(
+    ordering_key_format: &KafkaOrderingKeyFormat,
+    ordering_key_prefix: &str,
+    msg: &impl Message,
+) -> String {
+    let partition = msg.partition().to_string();
+
+    let mut buf =
+        String::with_capacity(ordering_key_prefix.len() + msg.topic().len() + partition.len());
+    buf.push_str(ordering_key_prefix);
+    buf.push_str(msg.topic());
+    buf.push_str(&partition);
+
+    if let (KafkaOrderingKeyFormat::ConsumerGroupTopicPartitionKey, Some(key)) =
+        (ordering_key_format, msg.key())
+    {
+        buf.push_str(&base64::prelude::BASE64_STANDARD.encode(key));
     }
 
-    async fn send(
-        &mut self,
-        consumer_group_id: &str,
-        msg: &BorrowedMessage<'_>,
-    ) -> Result<(), Error> {
-        let Sink::Service { name, method, .. } = self.subscription.sink();
-        let Source::Kafka {
-            ordering_key_format,
-            ..
-        } = self.subscription.source();
-
-        // Prepare ingress span
-        let ingress_span = info_span!(
-            "kafka_ingress_consume",
-            otel.name = format!("kafka_ingress_consume"),
-            messaging.system = "kafka",
-            messaging.operation = "receive",
-            messaging.source.name = msg.topic(),
-            messaging.destination.name = format!("{}/{}", name, method)
-        );
-        info!(parent: &ingress_span, "Processing Kafka ingress request");
-        let ingress_span_context = ingress_span.context().span().span_context().clone();
-
-        let event = Event {
-            ordering_key: Self::generate_ordering_key(ordering_key_format, consumer_group_id, msg),
-            key: if let Some(k) = msg.key() {
-                Bytes::copy_from_slice(k)
-            } else {
-                Bytes::default()
-            },
-            payload: if let Some(p) = msg.payload() {
-                Bytes::copy_from_slice(p)
-            } else {
-                Bytes::default()
-            },
-            attributes: Self::generate_events_attributes(msg, self.subscription.id()),
-        };
+    buf
+}
 
-        let (req, rx) = IngressRequest::event(
-            &self.subscription,
-            event,
-            SpanRelation::Parent(ingress_span_context),
-            Some(Self::generate_deduplication_id(consumer_group_id, msg)),
-        )
-        .map_err(|cause| Error::Event {
-            topic: msg.topic().to_string(),
-            partition: msg.partition(),
-            offset: msg.offset(),
-            cause,
-        })?;
-
-        async {
-            self.tx
-                .send(req)
-                .map_err(|_| Error::IngressDispatcherClosed)?;
-            rx.await.map_err(|_| Error::IngressDispatcherClosed)?;
-
-            Ok(())
-        }
-        .instrument(ingress_span)
-        .await
+fn generate_events_attributes(
+    msg: &impl Message,
+    subscription_id: SubscriptionId,
+) -> HashMap<String, String> {
+    let mut attributes = HashMap::with_capacity(3);
+    attributes.insert("kafka.offset".to_string(), msg.offset().to_string());
+    attributes.insert("kafka.topic".to_string(), msg.topic().to_string());
+    attributes.insert("kafka.partition".to_string(), msg.partition().to_string());
+    if let Some(timestamp) = msg.timestamp().to_millis() {
+        attributes.insert("kafka.timestamp".to_string(), timestamp.to_string());
     }
+    attributes.insert(
+        "restate.subscription.id".to_string(),
+        subscription_id.to_string(),
+    );
+    attributes
+}
 
-    fn generate_ordering_key(
-        ordering_key_format: &KafkaOrderingKeyFormat,
-        ordering_key_prefix: &str,
-        msg: &impl Message,
-    ) -> String {
-        let partition = msg.partition().to_string();
-
-        let mut buf =
-            String::with_capacity(ordering_key_prefix.len() + msg.topic().len() + partition.len());
-        buf.push_str(ordering_key_prefix);
-        buf.push_str(msg.topic());
-        buf.push_str(&partition);
-
-        if let (KafkaOrderingKeyFormat::ConsumerGroupTopicPartitionKey, Some(key)) =
-            (ordering_key_format, msg.key())
-        {
-            buf.push_str(&base64::prelude::BASE64_STANDARD.encode(key));
-        }
+fn generate_deduplication_id(
+    consumer_group: &str,
+    offsets_generation: u32,
+    msg: &impl Message,
+) -> (KafkaDeduplicationId, MessageIndex) {
+    let mut deduplication_id = format!("{consumer_group}-{}-{}", msg.topic(), msg.partition());
+    // After an offsets reset, the replayed messages must not be deduplicated against the
+    // previously processed ones
+    if offsets_generation > 0 {
+        deduplication_id.push_str(&format!("-{offsets_generation}"));
+    }
+    (KafkaDeduplicationId(deduplication_id), msg.offset() as u64)
+}
+
+/// Max number of messages per Kafka partition which have been sent to the ingress, but not yet acknowledged.
+const MAX_IN_FLIGHT_MESSAGES_PER_PARTITION: usize = 32;
+
+/// Timeout of the requests to the Kafka cluster issued when resetting the offsets.
+const RESET_OFFSETS_TIMEOUT: Duration = Duration::from_secs(10);
 
-        buf
+/// Commits, for every partition of the topic, the offset corresponding to `to` for the consumer
+/// group configured in `client_config`.
+///
+/// The commit is rejected by the Kafka cluster while the consumer group has active members,
+/// hence the consumer of the subscription must be stopped first.
+pub(crate) fn reset_offsets(
+    client_config: &ClientConfig,
+    topic: &str,
+    to: OffsetReset,
+) -> Result<(), KafkaError> {
+    let consumer: BaseConsumer = client_config.create()?;
+
+    let metadata = consumer.fetch_metadata(Some(topic), RESET_OFFSETS_TIMEOUT)?;
+    let partitions: Vec<i32> = metadata
+        .topics()
+        .iter()
+        .flat_map(|t| t.partitions().iter().map(|p| p.id()))
+        .collect();
+
+    let mut offsets = TopicPartitionList::with_capacity(partitions.len());
+    for partition in partitions {
+        let (low, high) = consumer.fetch_watermarks(topic, partition, RESET_OFFSETS_TIMEOUT)?;
+        let offset = match to {
+            OffsetReset::Earliest => low,
+            OffsetReset::Latest => high,
+            OffsetReset::Timestamp(timestamp) => {
+                let mut timestamps = TopicPartitionList::with_capacity(1);
+                timestamps.add_partition_offset(
+                    topic,
+                    partition,
+                    Offset::Offset(timestamp as i64),
+                )?;
+                match consumer
+                    .offsets_for_times(timestamps, RESET_OFFSETS_TIMEOUT)?
+                    .find_partition(topic, partition)
+                    .map(|elem| elem.offset())
+                {
+                    Some(Offset::Offset(offset)) => offset,
+                    // No message with a greater or equal timestamp
+                    _ => high,
+                }
+            }
+        };
+        debug!("Resetting offset of topic {topic} partition {partition} to {offset}");
+        offsets.add_partition_offset(topic, partition, Offset::Offset(offset))?;
     }
 
-    fn generate_events_attributes(
-        msg: &impl Message,
-        subscription_id: SubscriptionId,
-    ) -> HashMap<String, String> {
-        let mut attributes = HashMap::with_capacity(3);
-        attributes.insert("kafka.offset".to_string(), msg.offset().to_string());
-        attributes.insert("kafka.topic".to_string(), msg.topic().to_string());
-        attributes.insert("kafka.partition".to_string(), msg.partition().to_string());
-        if let Some(timestamp) = msg.timestamp().to_millis() {
-            attributes.insert("kafka.timestamp".to_string(), timestamp.to_string());
+    consumer.commit(&offsets, CommitMode::Sync)?;
+    Ok(())
+}
+
+enum PartitionEvent {
+    Assigned {
+        topic: String,
+        partition: i32,
+        /// `None` if the queue couldn't be split, in which case the partition messages are
+        /// yielded by the main queue.
+        queue: Option<StreamPartitionQueue<RebalanceContext>>,
+    },
+    Revoked {
+        topic: String,
+        partition: i32,
+    },
+}
+
+/// Splits the queue of every assigned partition from the main consumer queue, so that each
+/// partition can be consumed by its own pipeline.
+///
+/// Splitting happens in the rebalance callback right before the assignment, so no message of the
+/// partition can be fetched into the main queue.
+struct RebalanceContext {
+    consumer: OnceLock<Weak<MessageConsumer>>,
+    partition_events_tx: mpsc::UnboundedSender<PartitionEvent>,
+}
+
+impl ClientContext for RebalanceContext {}
+
+impl ConsumerContext for RebalanceContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance<'_>) {
+        let Rebalance::Assign(partitions) = rebalance else {
+            return;
+        };
+        let Some(consumer) = self.consumer.get().and_then(Weak::upgrade) else {
+            return;
+        };
+        for elem in partitions.elements() {
+            let queue = consumer.split_partition_queue(elem.topic(), elem.partition());
+            if queue.is_none() {
+                warn!(
+                    "Cannot split the queue of topic {} partition {}, consuming it from the main queue",
+                    elem.topic(),
+                    elem.partition()
+                );
+            }
+            let _ = self.partition_events_tx.send(PartitionEvent::Assigned {
+                topic: elem.topic().to_owned(),
+                partition: elem.partition(),
+                queue,
+            });
         }
-        attributes.insert(
-            "restate.subscription.id".to_string(),
-            subscription_id.to_string(),
-        );
-        attributes
     }
 
-    fn generate_deduplication_id(
-        consumer_group: &str,
-        msg: &impl Message,
-    ) -> (KafkaDeduplicationId, MessageIndex) {
-        (
-            KafkaDeduplicationId(format!(
-                "{consumer_group}-{}-{}",
-                msg.topic(),
-                msg.partition()
-            )),
-            msg.offset() as u64,
-        )
+    fn post_rebalance(&self, rebalance: &Rebalance<'_>) {
+        match rebalance {
+            Rebalance::Assign(_) => {}
+            Rebalance::Revoke(partitions) => {
+                for elem in partitions.elements() {
+                    let _ = self.partition_events_tx.send(PartitionEvent::Revoked {
+                        topic: elem.topic().to_owned(),
+                        partition: elem.partition(),
+                    });
+                }
+            }
+            Rebalance::Error(err) => {
+                warn!("Error during rebalance of the Kafka consumer: {err}");
+            }
+        }
     }
 }
 
+/// Message yielded by the main queue, routed to the pipeline of its partition.
+type MainQueueMessage = (SourceMessage<KafkaDeduplicationId>, i64);
+
+struct RunningPipeline {
+    abort_handle: AbortHandle,
+    main_queue_tx: mpsc::UnboundedSender<MainQueueMessage>,
+}
+
 #[derive(Clone)]
 pub struct ConsumerTask {
     client_config: ClientConfig,
     topics: Vec<String>,
+    ordering_key_format: KafkaOrderingKeyFormat,
     sender: MessageSender,
+    offsets: SubscriptionOffsets,
+}
+
+impl SourceConnector for ConsumerTask {
+    fn run(&self, shutdown: oneshot::Receiver<()>) -> BoxFuture<'static, Result<(), Error>> {
+        self.clone().run(shutdown).boxed()
+    }
 }
 
 impl ConsumerTask {
-    pub fn new(client_config: ClientConfig, topics: Vec<String>, sender: MessageSender) -> Self {
+    pub fn new(
+        client_config: ClientConfig,
+        topics: Vec<String>,
+        ordering_key_format: KafkaOrderingKeyFormat,
+        sender: MessageSender,
+        offsets: SubscriptionOffsets,
+    ) -> Self {
         Self {
             client_config,
             topics,
+            ordering_key_format,
             sender,
+            offsets,
         }
     }
 
-    pub async fn run(mut self, mut rx: oneshot::Receiver<()>) -> Result<(), Error> {
+    async fn run(self, mut rx: oneshot::Receiver<()>) -> Result<(), Error> {
         // Create the consumer and subscribe to the topic
         let consumer_group_id = self
             .client_config
@@ -242,20 +360,74 @@ impl ConsumerTask {
             self.topics, self.client_config
         );
 
-        let consumer: MessageConsumer = self.client_config.create()?;
+        let (partition_events_tx, mut partition_events_rx) = mpsc::unbounded_channel();
+        let consumer: Arc<MessageConsumer> =
+            Arc::new(self.client_config.create_with_context(RebalanceContext {
+                consumer: OnceLock::new(),
+                partition_events_tx,
+            })?);
+        let _ = consumer.context().consumer.set(Arc::downgrade(&consumer));
+
         let topics: Vec<&str> = self.topics.iter().map(|x| &**x).collect();
         consumer.subscribe(&topics)?;
 
+        let mut pipelines = JoinSet::new();
+        let mut running_pipelines: HashMap<(String, i32), RunningPipeline> = HashMap::new();
+
         loop {
             tokio::select! {
+                // The main queue must be polled to serve the rebalance callbacks. It yields messages
+                // only of the partitions whose queue couldn't be split.
                 res = consumer.recv() => {
                     let msg = res?;
-                    self.sender.send(&consumer_group_id, &msg).await?;
-                    // This method tells rdkafka that we have processed this message,
-                    // so its offset can be safely committed.
-                    // rdkafka periodically commits these offsets asynchronously, with a period configurable
-                    // with auto.commit.interval.ms
-                    consumer.store_offset_from_message(&msg)?;
+                    // The assignment might have been notified while serving this very poll
+                    while let Ok(partition_event) = partition_events_rx.try_recv() {
+                        self.handle_partition_event(
+                            partition_event,
+                            &consumer,
+                            &consumer_group_id,
+                            &mut pipelines,
+                            &mut running_pipelines,
+                        );
+                    }
+                    // Routed to the pipeline of the partition, so that the messages of a partition
+                    // are sent and their offsets stored by a single pipeline, in order
+                    match running_pipelines.get(&(msg.topic().to_owned(), msg.partition())) {
+                        Some(pipeline) => {
+                            let _ = pipeline.main_queue_tx.send((
+                                source_message(
+                                    &self.ordering_key_format,
+                                    &consumer_group_id,
+                                    self.sender.subscription(),
+                                    &msg,
+                                ),
+                                msg.offset(),
+                            ));
+                        }
+                        None => debug!(
+                            "Discarding message of topic {} partition {} offset {}, since the partition is not assigned",
+                            msg.topic(),
+                            msg.partition(),
+                            msg.offset()
+                        ),
+                    }
+                }
+                Some(partition_event) = partition_events_rx.recv() => {
+                    self.handle_partition_event(
+                        partition_event,
+                        &consumer,
+                        &consumer_group_id,
+                        &mut pipelines,
+                        &mut running_pipelines,
+                    );
+                }
+                Some(res) = pipelines.join_next() => {
+                    match res {
+                        Ok(res) => res?,
+                        Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+                        // Aborted because the partition has been revoked
+                        Err(_) => {}
+                    }
                 }
                 _ = &mut rx => {
                     return Ok(());
@@ -263,4 +435,126 @@ impl ConsumerTask {
             }
         }
     }
+
+    fn handle_partition_event(
+        &self,
+        partition_event: PartitionEvent,
+        consumer: &Arc<MessageConsumer>,
+        consumer_group_id: &str,
+        pipelines: &mut JoinSet<Result<(), Error>>,
+        running_pipelines: &mut HashMap<(String, i32), RunningPipeline>,
+    ) {
+        match partition_event {
+            PartitionEvent::Assigned {
+                topic,
+                partition,
+                queue,
+            } => {
+                debug!("Starting consumption of topic {topic} partition {partition}");
+                let (main_queue_tx, main_queue_rx) = mpsc::unbounded_channel();
+                let pipeline = Self::run_partition_pipeline(
+                    Arc::clone(consumer),
+                    queue,
+                    main_queue_rx,
+                    topic.clone(),
+                    partition,
+                    consumer_group_id.to_owned(),
+                    self.ordering_key_format.clone(),
+                    self.sender.clone(),
+                    self.offsets.clone(),
+                );
+                let running_pipeline = RunningPipeline {
+                    abort_handle: pipelines.spawn(pipeline),
+                    main_queue_tx,
+                };
+                if let Some(previous) =
+                    running_pipelines.insert((topic, partition), running_pipeline)
+                {
+                    previous.abort_handle.abort();
+                }
+            }
+            PartitionEvent::Revoked { topic, partition } => {
+                debug!("Stopping consumption of topic {topic} partition {partition}");
+                self.offsets
+                    .remove_partition(self.sender.subscription().id(), &topic, partition);
+                if let Some(pipeline) = running_pipelines.remove(&(topic, partition)) {
+                    // Messages still in flight will be consumed again by the new owner of the partition
+                    pipeline.abort_handle.abort();
+                }
+            }
+        }
+    }
+
+    /// Consumes a single partition, keeping up to [`MAX_IN_FLIGHT_MESSAGES_PER_PARTITION`] messages
+    /// in flight towards the ingress.
+    ///
+    /// The messages of the partition yielded by the main queue are routed here as well, so this
+    /// pipeline is the only one storing the offsets of the partition.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_partition_pipeline(
+        consumer: Arc<MessageConsumer>,
+        queue: Option<StreamPartitionQueue<RebalanceContext>>,
+        mut main_queue_rx: mpsc::UnboundedReceiver<MainQueueMessage>,
+        topic: String,
+        partition: i32,
+        consumer_group_id: String,
+        ordering_key_format: KafkaOrderingKeyFormat,
+        sender: MessageSender,
+        offsets: SubscriptionOffsets,
+    ) -> Result<(), Error> {
+        let mut in_flight = InFlightMessages::new(sender.clone());
+
+        loop {
+            tokio::select! {
+                biased;
+                // Not bounded, since the main queue must not be blocked by a single partition
+                Some((msg, offset)) = main_queue_rx.recv() => {
+                    in_flight.push(msg, offset);
+                }
+                res = recv_partition(queue.as_ref()), if in_flight.len() < MAX_IN_FLIGHT_MESSAGES_PER_PARTITION => {
+                    let msg = res?;
+                    in_flight.push(
+                        source_message(
+                            &ordering_key_format,
+                            &consumer_group_id,
+                            sender.subscription(),
+                            &msg,
+                        ),
+                        msg.offset(),
+                    );
+                }
+                Some(res) = in_flight.next(), if !in_flight.is_empty() => {
+                    let offset = res?;
+                    // Acks are yielded in the same order the messages were sent, hence all the
+                    // previous messages of this partition have been acknowledged as well.
+                    // rdkafka stores offset + 1, that is the offset of the next message to consume,
+                    // and periodically commits the stored offsets asynchronously, with a period
+                    // configurable with auto.commit.interval.ms
+                    consumer.store_offset(&topic, partition, offset)?;
+                    // The watermarks are cached by rdkafka from the fetch responses
+                    let high_watermark = consumer
+                        .get_watermark_offsets(&topic, partition)
+                        .ok()
+                        .map(|(_, high)| high);
+                    offsets.update(
+                        sender.subscription().id(),
+                        &topic,
+                        partition,
+                        Some(offset + 1),
+                        high_watermark,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Receives from the partition queue, or never completes if the queue couldn't be split.
+async fn recv_partition(
+    queue: Option<&StreamPartitionQueue<RebalanceContext>>,
+) -> KafkaResult<BorrowedMessage<'_>> {
+    match queue {
+        Some(queue) => queue.recv().await,
+        None => future::pending().await,
+    }
 }
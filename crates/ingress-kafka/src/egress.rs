@@ -0,0 +1,136 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::options::{KafkaClusterOptions, Options};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use restate_storage_api::outbox_table::KafkaRecord;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::debug;
+
+/// Header carrying the [`KafkaRecord::message_id`], which consumers can use to deduplicate records.
+pub const MESSAGE_ID_HEADER: &str = "restate.message.id";
+
+/// Upper bound for enqueueing and delivering a record. It is shorter than the shuffle retry
+/// timeout, so a failed publish is reported before the record is sent again.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum EgressError {
+    #[error("unknown kafka cluster '{0}'. Make sure it is defined in the KafkaOptions")]
+    UnknownCluster(String),
+    #[error(transparent)]
+    Kafka(#[from] KafkaError),
+}
+
+impl EgressError {
+    /// Returns true if publishing the same record again can never succeed.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, EgressError::UnknownCluster(_))
+    }
+}
+
+/// Publishes records to the configured Kafka clusters.
+///
+/// Producers are created lazily, one per cluster, and are shared among all clones. They are
+/// configured to be idempotent, so retries of the producer itself don't introduce duplicates.
+#[derive(Clone)]
+pub struct KafkaEgress {
+    clusters: Arc<HashMap<String, KafkaClusterOptions>>,
+    producers: Arc<Mutex<HashMap<String, FutureProducer>>>,
+}
+
+impl KafkaEgress {
+    pub(crate) fn new(options: &Options) -> Self {
+        Self {
+            clusters: Arc::new(options.clusters.clone()),
+            producers: Default::default(),
+        }
+    }
+
+    pub async fn publish(&self, record: KafkaRecord) -> Result<(), EgressError> {
+        let producer = self.producer(&record.cluster)?;
+
+        let mut headers =
+            OwnedHeaders::new_with_capacity(record.headers.len() + 1).insert(Header {
+                key: MESSAGE_ID_HEADER,
+                value: Some(record.message_id.as_bytes()),
+            });
+        for header in &record.headers {
+            headers = headers.insert(Header {
+                key: &header.name,
+                value: Some(header.value.as_bytes()),
+            });
+        }
+
+        let mut future_record = FutureRecord::to(&record.topic)
+            .payload(record.payload.as_ref())
+            .headers(headers);
+        if let Some(key) = &record.key {
+            future_record = future_record.key(key.as_ref());
+        }
+
+        let (partition, offset) = producer
+            .send(future_record, Timeout::After(PUBLISH_TIMEOUT))
+            .await
+            .map_err(|(err, _)| err)?;
+        debug!(
+            messaging.destination.name = record.topic,
+            messaging.kafka.destination.partition = partition,
+            messaging.kafka.message.offset = offset,
+            restate.kafka.message_id = record.message_id,
+            "Published record to Kafka"
+        );
+
+        Ok(())
+    }
+
+    fn producer(&self, cluster: &str) -> Result<FutureProducer, EgressError> {
+        let mut producers = self
+            .producers
+            .lock()
+            .expect("producers lock should not be poisoned");
+        if let Some(producer) = producers.get(cluster) {
+            return Ok(producer.clone());
+        }
+
+        let cluster_options = self
+            .clusters
+            .get(cluster)
+            .ok_or_else(|| EgressError::UnknownCluster(cluster.to_owned()))?;
+
+        let mut client_config = ClientConfig::new();
+        client_config.set("metadata.broker.list", cluster_options.servers.clone());
+        for (k, v) in cluster_options.additional_options.clone() {
+            client_config.set(k, v);
+        }
+        // Retries of the producer must not duplicate nor reorder records
+        client_config.set("enable.idempotence", "true");
+        if !cluster_options
+            .additional_options
+            .contains_key("message.timeout.ms")
+        {
+            client_config.set(
+                "message.timeout.ms",
+                PUBLISH_TIMEOUT.as_millis().to_string(),
+            );
+        }
+
+        let producer: FutureProducer = client_config.create()?;
+        producers.insert(cluster.to_owned(), producer.clone());
+
+        Ok(producer)
+    }
+}
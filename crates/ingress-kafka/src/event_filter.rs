@@ -0,0 +1,420 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Predicate language used by the subscription filters and routes.
+//!
+//! The syntax is a small subset of CEL:
+//!
+//! ```text
+//! expr      := and ( "||" and )*
+//! and       := unary ( "&&" unary )*
+//! unary     := "!" unary | "(" expr ")" | "true" | "false" | STRING "in" map | predicate
+//! predicate := field ( ("==" | "!=") STRING | "." method "(" STRING ")" )
+//! field     := "key" | "ordering_key" | map "[" STRING "]"
+//! map       := "headers" | "attributes"
+//! method    := "startsWith" | "endsWith" | "contains"
+//! ```
+//!
+//! For example: `headers["type"] == "order.created" && !key.startsWith("test-")`.
+//! Comparisons against a missing header or attribute evaluate to false, except `!=`.
+
+use crate::source_connector::SourceMessage;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum EventFilterError {
+    #[error("unexpected character '{0}' at position {1}")]
+    UnexpectedCharacter(char, usize),
+    #[error("unterminated string literal starting at position {0}")]
+    UnterminatedString(usize),
+    #[error("unexpected {found}, expected {expected}")]
+    UnexpectedToken {
+        found: String,
+        expected: &'static str,
+    },
+    #[error("unknown field '{0}', supported fields are: key, ordering_key, headers, attributes")]
+    UnknownField(String),
+    #[error("unknown method '{0}', supported methods are: startsWith, endsWith, contains")]
+    UnknownMethod(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Eq,
+    NotEq,
+    And,
+    Or,
+    Not,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "'{}'", ident),
+            Token::Str(s) => write!(f, "string \"{}\"", s),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+            Token::LBracket => f.write_str("'['"),
+            Token::RBracket => f.write_str("']'"),
+            Token::Dot => f.write_str("'.'"),
+            Token::Eq => f.write_str("'=='"),
+            Token::NotEq => f.write_str("'!='"),
+            Token::And => f.write_str("'&&'"),
+            Token::Or => f.write_str("'||'"),
+            Token::Not => f.write_str("'!'"),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, EventFilterError> {
+    let mut tokens = vec![];
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '.' => Token::Dot,
+            '=' if next_is(&mut chars, '=') => Token::Eq,
+            '!' if next_is(&mut chars, '=') => Token::NotEq,
+            '!' => Token::Not,
+            '&' if next_is(&mut chars, '&') => Token::And,
+            '|' if next_is(&mut chars, '|') => Token::Or,
+            '"' | '\'' => Token::Str(read_string(&mut chars, c, pos)?),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            c => return Err(EventFilterError::UnexpectedCharacter(c, pos)),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn next_is(chars: &mut Peekable<CharIndices<'_>>, expected: char) -> bool {
+    chars.next_if(|(_, c)| *c == expected).is_some()
+}
+
+fn read_string(
+    chars: &mut Peekable<CharIndices<'_>>,
+    quote: char,
+    start: usize,
+) -> Result<String, EventFilterError> {
+    let mut s = String::new();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, c)) => s.push(c),
+                None => break,
+            },
+            c if c == quote => return Ok(s),
+            c => s.push(c),
+        }
+    }
+    Err(EventFilterError::UnterminatedString(start))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Key,
+    OrderingKey,
+    Header(String),
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Eq,
+    NotEq,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(bool),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Field, Operator, String),
+    HasHeader(String),
+    HasAttribute(String),
+}
+
+/// Compiled filter expression, see the module documentation for the syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventFilter(Expr);
+
+impl FromStr for EventFilter {
+    type Err = EventFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?.into_iter().peekable(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.next() {
+            return Err(EventFilterError::UnexpectedToken {
+                found: token.to_string(),
+                expected: "end of expression",
+            });
+        }
+        Ok(EventFilter(expr))
+    }
+}
+
+impl EventFilter {
+    pub(crate) fn matches<D>(&self, msg: &SourceMessage<D>) -> bool {
+        self.0.eval(msg)
+    }
+}
+
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn expect(
+        &mut self,
+        expected_token: Token,
+        expected: &'static str,
+    ) -> Result<(), EventFilterError> {
+        match self.tokens.next() {
+            Some(token) if token == expected_token => Ok(()),
+            found => Err(Self::unexpected(found, expected)),
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, EventFilterError> {
+        match self.tokens.next() {
+            Some(Token::Str(s)) => Ok(s),
+            found => Err(Self::unexpected(found, "a string literal")),
+        }
+    }
+
+    fn unexpected(found: Option<Token>, expected: &'static str) -> EventFilterError {
+        EventFilterError::UnexpectedToken {
+            found: found
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "end of expression".to_owned()),
+            expected,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, EventFilterError> {
+        let mut expr = self.parse_and()?;
+        while self.tokens.next_if_eq(&Token::Or).is_some() {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, EventFilterError> {
+        let mut expr = self.parse_unary()?;
+        while self.tokens.next_if_eq(&Token::And).is_some() {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, EventFilterError> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some(Token::Str(name)) => {
+                self.expect(Token::Ident("in".to_owned()), "'in'")?;
+                match self.tokens.next() {
+                    Some(Token::Ident(map)) if map == "headers" => Ok(Expr::HasHeader(name)),
+                    Some(Token::Ident(map)) if map == "attributes" => Ok(Expr::HasAttribute(name)),
+                    found => Err(Self::unexpected(found, "'headers' or 'attributes'")),
+                }
+            }
+            Some(Token::Ident(ident)) if ident == "true" => Ok(Expr::Literal(true)),
+            Some(Token::Ident(ident)) if ident == "false" => Ok(Expr::Literal(false)),
+            Some(Token::Ident(ident)) => {
+                let field = self.parse_field(ident)?;
+                self.parse_predicate(field)
+            }
+            found => Err(Self::unexpected(found, "an expression")),
+        }
+    }
+
+    fn parse_field(&mut self, ident: String) -> Result<Field, EventFilterError> {
+        match ident.as_str() {
+            "key" => Ok(Field::Key),
+            "ordering_key" => Ok(Field::OrderingKey),
+            "headers" | "attributes" => {
+                self.expect(Token::LBracket, "'['")?;
+                let name = self.expect_string()?;
+                self.expect(Token::RBracket, "']'")?;
+                Ok(if ident == "headers" {
+                    Field::Header(name)
+                } else {
+                    Field::Attribute(name)
+                })
+            }
+            _ => Err(EventFilterError::UnknownField(ident)),
+        }
+    }
+
+    fn parse_predicate(&mut self, field: Field) -> Result<Expr, EventFilterError> {
+        let operator = match self.tokens.next() {
+            Some(Token::Eq) => Operator::Eq,
+            Some(Token::NotEq) => Operator::NotEq,
+            Some(Token::Dot) => {
+                let operator = match self.tokens.next() {
+                    Some(Token::Ident(method)) => match method.as_str() {
+                        "startsWith" => Operator::StartsWith,
+                        "endsWith" => Operator::EndsWith,
+                        "contains" => Operator::Contains,
+                        _ => return Err(EventFilterError::UnknownMethod(method)),
+                    },
+                    found => return Err(Self::unexpected(found, "a method name")),
+                };
+                self.expect(Token::LParen, "'('")?;
+                let value = self.expect_string()?;
+                self.expect(Token::RParen, "')'")?;
+                return Ok(Expr::Compare(field, operator, value));
+            }
+            found => return Err(Self::unexpected(found, "'==', '!=' or a method call")),
+        };
+        Ok(Expr::Compare(field, operator, self.expect_string()?))
+    }
+}
+
+impl Field {
+    fn value<'a, D>(&self, msg: &'a SourceMessage<D>) -> Option<Cow<'a, str>> {
+        match self {
+            Field::Key => Some(String::from_utf8_lossy(&msg.key)),
+            Field::OrderingKey => Some(Cow::Borrowed(&msg.ordering_key)),
+            Field::Header(name) => lookup(&msg.headers, name),
+            Field::Attribute(name) => lookup(&msg.attributes, name),
+        }
+    }
+}
+
+fn lookup<'a>(map: &'a HashMap<String, String>, name: &str) -> Option<Cow<'a, str>> {
+    map.get(name).map(|v| Cow::Borrowed(v.as_str()))
+}
+
+impl Expr {
+    fn eval<D>(&self, msg: &SourceMessage<D>) -> bool {
+        match self {
+            Expr::Literal(b) => *b,
+            Expr::Not(expr) => !expr.eval(msg),
+            Expr::And(left, right) => left.eval(msg) && right.eval(msg),
+            Expr::Or(left, right) => left.eval(msg) || right.eval(msg),
+            Expr::Compare(field, operator, expected) => match field.value(msg) {
+                Some(value) => match operator {
+                    Operator::Eq => value == expected.as_str(),
+                    Operator::NotEq => value != expected.as_str(),
+                    Operator::StartsWith => value.starts_with(expected.as_str()),
+                    Operator::EndsWith => value.ends_with(expected.as_str()),
+                    Operator::Contains => value.contains(expected.as_str()),
+                },
+                None => *operator == Operator::NotEq,
+            },
+            Expr::HasHeader(name) => msg.headers.contains_key(name),
+            Expr::HasAttribute(name) => msg.attributes.contains_key(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+
+    fn message() -> SourceMessage<()> {
+        SourceMessage {
+            system: "test",
+            source_name: "my-topic".to_owned(),
+            position: "offset 0".to_owned(),
+            ordering_key: "my-group-my-topic-0".to_owned(),
+            key: Bytes::from_static(b"eu-123"),
+            payload: Bytes::default(),
+            headers: HashMap::from([("type".to_owned(), "order.created".to_owned())]),
+            attributes: HashMap::from([("kafka.partition".to_owned(), "0".to_owned())]),
+            deduplication: ((), 0),
+        }
+    }
+
+    fn matches(expr: &str) -> bool {
+        expr.parse::<EventFilter>().unwrap().matches(&message())
+    }
+
+    #[test]
+    fn evaluate() {
+        assert!(matches(r#"headers["type"] == "order.created""#));
+        assert!(matches(
+            r#"key.startsWith('eu-') && attributes["kafka.partition"] != "1""#
+        ));
+        assert!(matches(r#"!("type" in attributes) && "type" in headers"#));
+        assert!(matches(
+            r#"ordering_key.endsWith("-1") || headers["type"].contains("created")"#
+        ));
+        assert!(matches(r#"headers["missing"] != "a""#));
+        assert!(!matches(r#"headers["missing"] == "a""#));
+        assert!(!matches(r#"true && false"#));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "key ==".parse::<EventFilter>(),
+            Err(EventFilterError::UnexpectedToken {
+                found: "end of expression".to_owned(),
+                expected: "a string literal"
+            })
+        );
+        assert_eq!(
+            "value == \"a\"".parse::<EventFilter>(),
+            Err(EventFilterError::UnknownField("value".to_owned()))
+        );
+        assert_eq!(
+            "key == 'a".parse::<EventFilter>(),
+            Err(EventFilterError::UnterminatedString(7))
+        );
+        assert_eq!(
+            "key == 'a' 'b'".parse::<EventFilter>(),
+            Err(EventFilterError::UnexpectedToken {
+                found: "string \"b\"".to_owned(),
+                expected: "end of expression"
+            })
+        );
+    }
+}
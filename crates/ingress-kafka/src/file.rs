@@ -0,0 +1,628 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::source_connector::{
+    ordering_key_is_deduplication_scope, Error, InFlightMessages, MessageSender, SourceConnector,
+    SourceMessage,
+};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use restate_ingress_dispatcher::DeduplicationId;
+use restate_schema_api::subscription::Subscription;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use std::{fmt, io, mem};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader, SeekFrom};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Interval to check for new files and new lines.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Max number of lines which have been sent to the ingress, but not yet acknowledged.
+const MAX_IN_FLIGHT_MESSAGES: usize = 32;
+
+/// Prefix of the hidden file storing the read positions, see [`FileConsumerTask`].
+const POSITIONS_FILE_PREFIX: &str = ".restate-positions-";
+
+/// Read position of every tailed file, see [`Position`].
+type Positions = BTreeMap<PathBuf, Position>;
+
+/// Identifies the file behind a path, to detect when it's deleted and created again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct FileIdentity {
+    inode: u64,
+    /// Creation time in nanoseconds since the epoch, if supported by the file system.
+    created: Option<u64>,
+}
+
+impl FileIdentity {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        Self {
+            inode,
+            created: metadata
+                .created()
+                .ok()
+                .and_then(|created| created.duration_since(UNIX_EPOCH).ok())
+                .map(|created| created.as_nanos() as u64),
+        }
+    }
+}
+
+/// Read position of a tailed file, that is the offset after the last acknowledged line.
+///
+/// The generation is bumped whenever the file behind the path is replaced or truncated, and it's
+/// part of the deduplication id, so the lines read again from the beginning aren't discarded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct Position {
+    identity: FileIdentity,
+    generation: u64,
+    offset: u64,
+}
+
+pub struct FileDeduplicationId(String);
+
+impl fmt::Display for FileDeduplicationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl DeduplicationId for FileDeduplicationId {
+    fn requires_proxying(subscription: &Subscription) -> bool {
+        !ordering_key_is_deduplication_scope(subscription)
+    }
+}
+
+/// Tails a file, or all the files of a directory, sending every line as event.
+///
+/// The read positions are stored in the hidden file `.restate-positions-<subscription_id>`,
+/// within the tailed directory or next to the tailed file, and on restart the tail resumes from
+/// there. Lines sent again because their acknowledgement wasn't stored yet are discarded by the
+/// ingress deduplication, since the byte offset of a line is used as deduplication index.
+///
+/// On every poll the tailed files are checked for truncation and replacement (e.g. log rotation
+/// with copytruncate, or delete and create), in which case they're read again from the beginning
+/// under a new generation of the deduplication id.
+///
+/// Only files within `root` are read, also when reached through symlinks.
+#[derive(Clone)]
+pub struct FileConsumerTask {
+    path: PathBuf,
+    root: PathBuf,
+    sender: MessageSender,
+}
+
+impl SourceConnector for FileConsumerTask {
+    fn run(&self, shutdown: oneshot::Receiver<()>) -> BoxFuture<'static, Result<(), Error>> {
+        self.clone().run(shutdown).boxed()
+    }
+}
+
+impl FileConsumerTask {
+    pub fn new(path: PathBuf, root: PathBuf, sender: MessageSender) -> Self {
+        Self { path, root, sender }
+    }
+
+    async fn run(self, mut rx: oneshot::Receiver<()>) -> Result<(), Error> {
+        debug!("Starting tail of {}", self.path.display());
+
+        let root = tokio::fs::canonicalize(&self.root).await?;
+        if !tokio::fs::canonicalize(&self.path)
+            .await?
+            .starts_with(&root)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} is not within the file source root {}",
+                    self.path.display(),
+                    self.root.display()
+                ),
+            )
+            .into());
+        }
+
+        let positions_path = self.positions_path().await?;
+        let mut positions = read_positions(&positions_path).await?;
+        let mut positions_changed = false;
+
+        let mut tails: BTreeMap<PathBuf, FileTail> = BTreeMap::new();
+        let mut in_flight = InFlightMessages::new(self.sender.clone());
+
+        loop {
+            for path in self.list_files(&root).await? {
+                let previous = match tails.get(&path) {
+                    Some(tail) if !tail.is_replaced(&path).await? => continue,
+                    Some(tail) => tail.position(),
+                    None => positions.get(&path).copied().unwrap_or_default(),
+                };
+                let tail = FileTail::open(&path, previous).await?;
+                if tail.generation != previous.generation {
+                    debug!(
+                        "{} has been truncated or replaced, reading it from the beginning",
+                        path.display()
+                    );
+                    // Stored right away, so a restart doesn't resume the new file from the
+                    // offset of the previous generation
+                    positions.insert(path.clone(), tail.position());
+                    positions_changed = true;
+                }
+                tails.insert(path, tail);
+            }
+
+            if positions_changed {
+                write_positions(&positions_path, &positions).await?;
+                positions_changed = false;
+            }
+
+            for (path, tail) in tails.iter_mut() {
+                while let Some((offset, line)) = tail.next_line().await? {
+                    if line.is_empty() {
+                        continue;
+                    }
+                    while in_flight.len() >= MAX_IN_FLIGHT_MESSAGES {
+                        if let Some(res) = in_flight.next().await {
+                            let (path, position) = res?;
+                            positions_changed |= acknowledge(&mut positions, path, position);
+                        }
+                    }
+                    in_flight.push(
+                        self.source_message(path, tail.generation, offset, line),
+                        (path.clone(), tail.position()),
+                    );
+                }
+            }
+
+            if positions_changed {
+                write_positions(&positions_path, &positions).await?;
+                positions_changed = false;
+            }
+
+            let poll_timer = tokio::time::sleep(POLL_INTERVAL);
+            tokio::pin!(poll_timer);
+            loop {
+                tokio::select! {
+                    _ = &mut poll_timer => break,
+                    Some(res) = in_flight.next(), if !in_flight.is_empty() => {
+                        let (path, position) = res?;
+                        positions_changed |= acknowledge(&mut positions, path, position);
+                    }
+                    _ = &mut rx => {
+                        if positions_changed {
+                            write_positions(&positions_path, &positions).await?;
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the path itself if it's a file, otherwise the regular files within the directory.
+    /// Files resolving outside of the root are skipped.
+    async fn list_files(&self, root: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        if !tokio::fs::metadata(&self.path).await?.is_dir() {
+            return Ok(vec![self.path.clone()]);
+        }
+
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(&self.path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if is_hidden || !tokio::fs::metadata(entry.path()).await?.is_file() {
+                continue;
+            }
+            if !tokio::fs::canonicalize(entry.path())
+                .await?
+                .starts_with(root)
+            {
+                debug!(
+                    "Skipping {} since it's not within the file source root",
+                    entry.path().display()
+                );
+                continue;
+            }
+            files.push(entry.path());
+        }
+        Ok(files)
+    }
+
+    /// Returns the path of the positions file, within the tailed directory or next to the tailed file.
+    async fn positions_path(&self) -> Result<PathBuf, io::Error> {
+        let file_name = format!(
+            "{}{}",
+            POSITIONS_FILE_PREFIX,
+            self.sender.subscription().id()
+        );
+        if tokio::fs::metadata(&self.path).await?.is_dir() {
+            return Ok(self.path.join(file_name));
+        }
+        Ok(self
+            .path
+            .parent()
+            .expect("absolute file path should have a parent")
+            .join(file_name))
+    }
+
+    fn source_message(
+        &self,
+        path: &Path,
+        generation: u64,
+        offset: u64,
+        line: Bytes,
+    ) -> SourceMessage<FileDeduplicationId> {
+        let subscription_id = self.sender.subscription().id();
+        let deduplication_id = format!("{}-{}-{}", subscription_id, path.display(), generation);
+
+        let mut attributes = HashMap::with_capacity(4);
+        attributes.insert("file.path".to_string(), path.display().to_string());
+        attributes.insert("file.generation".to_string(), generation.to_string());
+        attributes.insert("file.offset".to_string(), offset.to_string());
+        attributes.insert(
+            "restate.subscription.id".to_string(),
+            subscription_id.to_string(),
+        );
+
+        SourceMessage {
+            system: "file",
+            source_name: path.display().to_string(),
+            position: format!(
+                "file {} generation {} offset {}",
+                path.display(),
+                generation,
+                offset
+            ),
+            ordering_key: deduplication_id.clone(),
+            key: Bytes::default(),
+            payload: line,
+            headers: HashMap::new(),
+            attributes,
+            deduplication: (FileDeduplicationId(deduplication_id), offset),
+        }
+    }
+}
+
+struct FileTail {
+    reader: BufReader<File>,
+    identity: FileIdentity,
+    generation: u64,
+    offset: u64,
+    line: Vec<u8>,
+}
+
+impl FileTail {
+    /// Opens the file at the given position. If the file has been replaced, or it's shorter
+    /// because it has been truncated, it's read from the beginning under the next generation.
+    async fn open(path: &Path, position: Position) -> Result<Self, io::Error> {
+        let mut file = File::open(path).await?;
+        let metadata = file.metadata().await?;
+        let identity = FileIdentity::of(&metadata);
+        let (generation, offset) =
+            if identity == position.identity && position.offset <= metadata.len() {
+                (position.generation, position.offset)
+            } else if position == Position::default() {
+                // Never read before
+                (0, 0)
+            } else {
+                (position.generation + 1, 0)
+            };
+        file.seek(SeekFrom::Start(offset)).await?;
+
+        Ok(Self {
+            reader: BufReader::new(file),
+            identity,
+            generation,
+            offset,
+            line: vec![],
+        })
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            identity: self.identity,
+            generation: self.generation,
+            offset: self.offset,
+        }
+    }
+
+    /// Whether the path now points to another file, or the file has been truncated before the
+    /// read position.
+    async fn is_replaced(&self, path: &Path) -> Result<bool, io::Error> {
+        let metadata = tokio::fs::metadata(path).await?;
+        Ok(FileIdentity::of(&metadata) != self.identity || metadata.len() < self.offset)
+    }
+
+    /// Returns the next complete line together with its byte offset, if any.
+    /// Incomplete lines are buffered until their terminating new line is appended to the file.
+    async fn next_line(&mut self) -> Result<Option<(u64, Bytes)>, io::Error> {
+        let read = self.reader.read_until(b'\n', &mut self.line).await?;
+        if read == 0 || !self.line.ends_with(b"\n") {
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        self.offset += self.line.len() as u64;
+
+        let mut line = mem::take(&mut self.line);
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        Ok(Some((offset, Bytes::from(line))))
+    }
+}
+
+/// Stores the acknowledged position, unless the file has been reopened under a newer generation
+/// meanwhile. Returns whether the positions changed.
+fn acknowledge(positions: &mut Positions, path: PathBuf, position: Position) -> bool {
+    if matches!(positions.get(&path), Some(stored) if stored.generation > position.generation) {
+        return false;
+    }
+    positions.insert(path, position);
+    true
+}
+
+async fn read_positions(path: &Path) -> Result<Positions, io::Error> {
+    match tokio::fs::read(path).await {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Positions::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the positions to a temporary file first, so the positions file is replaced atomically.
+async fn write_positions(path: &Path, positions: &Positions) -> Result<(), io::Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    tokio::fs::write(&tmp_path, serde_json::to_vec(positions)?).await?;
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::Message;
+    use restate_ingress_dispatcher::{IngressRequestReceiver, PayloadDecoder};
+    use restate_pb::restate::Event;
+    use restate_schema_api::subscription::{EventReceiverServiceInstanceType, Sink, Source};
+    use restate_schema_impl::Schemas;
+    use std::io::Write;
+    use tokio::sync::mpsc;
+
+    fn file_consumer_task(path: &Path, root: &Path) -> (FileConsumerTask, IngressRequestReceiver) {
+        let subscription = Subscription::new(
+            Default::default(),
+            Source::File {
+                path: path.display().to_string(),
+            },
+            Sink::Service {
+                name: "MySvc".to_string(),
+                method: "MyMethod".to_string(),
+                input_event_remap: None,
+                // Singleton sinks receive the events without proxying
+                instance_type: EventReceiverServiceInstanceType::Singleton,
+            },
+            Default::default(),
+        );
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = FileConsumerTask::new(
+            path.to_owned(),
+            root.to_owned(),
+            MessageSender::new(subscription, tx, PayloadDecoder::new(Schemas::default())),
+        );
+        (task, rx)
+    }
+
+    #[tokio::test]
+    async fn tail_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = std::fs::File::create(dir.path().join("events.log")).unwrap();
+        writeln!(file, "first").unwrap();
+        write!(file, "sec").unwrap();
+        file.flush().unwrap();
+
+        let (task, mut rx) = file_consumer_task(dir.path(), dir.path());
+        let (_close_tx, close_rx) = oneshot::channel();
+        let task_handle = tokio::spawn(SourceConnector::run(&task, close_rx));
+
+        // The incomplete line is sent only once terminated
+        let (_, _, argument, _, (_, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "first");
+        assert_eq!(index, 0);
+        ack_tx.send(()).unwrap();
+
+        writeln!(file, "ond").unwrap();
+        file.flush().unwrap();
+
+        let (_, _, argument, _, (_, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "second");
+        assert_eq!(index, 6);
+        ack_tx.send(()).unwrap();
+
+        task_handle.abort();
+    }
+    #[tokio::test]
+    async fn resume_from_stored_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = std::fs::File::create(dir.path().join("events.log")).unwrap();
+        writeln!(file, "first").unwrap();
+        writeln!(file, "second").unwrap();
+        file.flush().unwrap();
+
+        let (task, mut rx) = file_consumer_task(dir.path(), dir.path());
+
+        // Acknowledge only the first line, then stop the task
+        let (close_tx, close_rx) = oneshot::channel();
+        let task_handle = tokio::spawn(SourceConnector::run(&task, close_rx));
+        let (_, _, _, _, (_, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(index, 0);
+        ack_tx.send(()).unwrap();
+        let (_, _, _, _, _, _pending_ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        while !task.positions_path().await.unwrap().exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        close_tx.send(()).unwrap();
+        task_handle.await.unwrap().unwrap();
+
+        // The restarted task resumes from the second line, and doesn't read the positions file
+        let (close_tx, close_rx) = oneshot::channel();
+        let task_handle = tokio::spawn(SourceConnector::run(&task, close_rx));
+        let (_, _, argument, _, (_, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "second");
+        assert_eq!(index, 6);
+        ack_tx.send(()).unwrap();
+
+        close_tx.send(()).unwrap();
+        task_handle.await.unwrap().unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn read_truncated_and_replaced_file_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.log");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "first").unwrap();
+        file.flush().unwrap();
+
+        let (task, mut rx) = file_consumer_task(dir.path(), dir.path());
+        let (_close_tx, close_rx) = oneshot::channel();
+        let task_handle = tokio::spawn(SourceConnector::run(&task, close_rx));
+
+        let (_, _, argument, _, (first_id, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "first");
+        assert_eq!(index, 0);
+        ack_tx.send(()).unwrap();
+
+        // Truncate in place, as done by copytruncate, then append once the truncation is noticed
+        file.set_len(0).unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "second line").unwrap();
+        file.flush().unwrap();
+
+        let (_, _, argument, _, (second_id, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "second line");
+        assert_eq!(index, 0);
+        assert_ne!(second_id, first_id);
+        ack_tx.send(()).unwrap();
+
+        // Replace with a longer file, as done by log rotation
+        let new_path = dir.path().join(".events.log.new");
+        let mut new_file = std::fs::File::create(&new_path).unwrap();
+        writeln!(new_file, "third and longest line").unwrap();
+        new_file.flush().unwrap();
+        std::fs::rename(&new_path, &path).unwrap();
+
+        let (_, _, argument, _, (third_id, index), ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(
+            Event::decode(argument).unwrap().payload,
+            "third and longest line"
+        );
+        assert_eq!(index, 0);
+        assert_ne!(third_id, first_id);
+        assert_ne!(third_id, second_id);
+        ack_tx.send(()).unwrap();
+
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(rx.try_recv().is_err());
+
+        task_handle.abort();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn skip_files_outside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let mut secret = std::fs::File::create(outside.path().join("secret")).unwrap();
+        writeln!(secret, "secret").unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("secret"),
+            root.path().join("secret.log"),
+        )
+        .unwrap();
+        let mut file = std::fs::File::create(root.path().join("events.log")).unwrap();
+        writeln!(file, "event").unwrap();
+
+        let (task, mut rx) = file_consumer_task(root.path(), root.path());
+        let (_close_tx, close_rx) = oneshot::channel();
+        let task_handle = tokio::spawn(SourceConnector::run(&task, close_rx));
+
+        let (_, _, argument, _, _, ack_tx) = rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(Event::decode(argument).unwrap().payload, "event");
+        ack_tx.send(()).unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(rx.try_recv().is_err());
+
+        task_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn reject_path_outside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        let (task, _rx) = file_consumer_task(outside.path(), root.path());
+        let (_close_tx, close_rx) = oneshot::channel();
+
+        assert!(SourceConnector::run(&task, close_rx).await.is_err());
+    }
+}
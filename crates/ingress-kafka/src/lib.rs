@@ -9,13 +9,23 @@
 // by the Apache License, Version 2.0.
 
 mod consumer_task;
+mod egress;
+mod event_filter;
+mod file;
+mod metric_definitions;
+mod nats;
+mod offsets;
 mod options;
+mod source_connector;
 mod subscription_controller;
 
 use tokio::sync::mpsc;
 
+pub use egress::{EgressError, KafkaEgress, MESSAGE_ID_HEADER};
+pub use offsets::SubscriptionOffsets;
 pub use options::{
-    KafkaClusterOptions, Options, OptionsBuilder, OptionsBuilderError, ValidationError,
+    KafkaClusterOptions, NatsClusterOptions, Options, OptionsBuilder, OptionsBuilderError,
+    ValidationError,
 };
 pub use subscription_controller::{Command, Error, Service};
 
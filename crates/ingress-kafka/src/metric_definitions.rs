@@ -0,0 +1,37 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+/// Optional to have but adds description/help message to the metrics emitted to
+/// the metrics' sink.
+use metrics::{describe_gauge, Unit};
+
+pub const KAFKA_INGRESS_STORED_OFFSET: &str = "restate.kafka_ingress.stored_offset";
+pub const KAFKA_INGRESS_HIGH_WATERMARK: &str = "restate.kafka_ingress.high_watermark";
+pub const KAFKA_INGRESS_CONSUMER_LAG: &str = "restate.kafka_ingress.consumer_lag";
+
+pub(crate) fn describe_metrics() {
+    describe_gauge!(
+        KAFKA_INGRESS_STORED_OFFSET,
+        Unit::Count,
+        "Offset of the next message to consume, per subscription and partition"
+    );
+
+    describe_gauge!(
+        KAFKA_INGRESS_HIGH_WATERMARK,
+        Unit::Count,
+        "Offset of the next message to be appended, per subscription and partition"
+    );
+
+    describe_gauge!(
+        KAFKA_INGRESS_CONSUMER_LAG,
+        Unit::Count,
+        "Number of messages not yet consumed, per subscription and partition"
+    );
+}
@@ -0,0 +1,174 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::source_connector::{
+    ordering_key_is_deduplication_scope, Error, InFlightMessages, MessageSender, SourceConnector,
+    SourceMessage,
+};
+use async_nats::jetstream;
+use async_nats::jetstream::consumer::{pull, AckPolicy};
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use restate_ingress_dispatcher::DeduplicationId;
+use restate_schema_api::subscription::Subscription;
+use std::collections::HashMap;
+use std::fmt;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Subscription option for the name of the durable JetStream consumer.
+/// Defaults to the subscription id.
+pub(crate) const CONSUMER_NAME_OPTION: &str = "consumer.name";
+
+/// Max number of messages which have been sent to the ingress, but not yet acknowledged.
+const MAX_IN_FLIGHT_MESSAGES: usize = 32;
+
+pub struct NatsDeduplicationId(String);
+
+impl fmt::Display for NatsDeduplicationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl DeduplicationId for NatsDeduplicationId {
+    fn requires_proxying(subscription: &Subscription) -> bool {
+        !ordering_key_is_deduplication_scope(subscription)
+    }
+}
+
+/// Consumes a subject of a NATS JetStream stream through a durable pull consumer.
+///
+/// Messages are acknowledged to JetStream individually, once the ingress has acknowledged them.
+/// The consumer position is tracked by JetStream, so it survives restarts of the connector.
+#[derive(Clone)]
+pub struct NatsConsumerTask {
+    servers: String,
+    subject: String,
+    consumer_name: String,
+    sender: MessageSender,
+}
+
+impl SourceConnector for NatsConsumerTask {
+    fn run(&self, shutdown: oneshot::Receiver<()>) -> BoxFuture<'static, Result<(), Error>> {
+        self.clone().run(shutdown).boxed()
+    }
+}
+
+impl NatsConsumerTask {
+    pub fn new(
+        servers: String,
+        subject: String,
+        consumer_name: String,
+        sender: MessageSender,
+    ) -> Self {
+        Self {
+            servers,
+            subject,
+            consumer_name,
+            sender,
+        }
+    }
+
+    async fn run(self, mut rx: oneshot::Receiver<()>) -> Result<(), Error> {
+        debug!(
+            "Starting NATS consumer {} for subject {} on {}",
+            self.consumer_name, self.subject, self.servers
+        );
+
+        let client = async_nats::connect(self.servers.as_str())
+            .await
+            .map_err(Error::nats)?;
+        let context = jetstream::new(client);
+        let stream_name = context
+            .stream_by_subject(self.subject.as_str())
+            .await
+            .map_err(Error::nats)?;
+        let stream = context
+            .get_stream(&stream_name)
+            .await
+            .map_err(Error::nats)?;
+        let consumer = stream
+            .get_or_create_consumer(
+                &self.consumer_name,
+                pull::Config {
+                    durable_name: Some(self.consumer_name.clone()),
+                    filter_subject: self.subject.clone(),
+                    ack_policy: AckPolicy::Explicit,
+                    max_ack_pending: MAX_IN_FLIGHT_MESSAGES as i64,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(Error::nats)?;
+        let mut messages = consumer.messages().await.map_err(Error::nats)?;
+
+        // Stream sequences are unique within the stream, and increase monotonically within a subject
+        let deduplication_id = format!("{}-{}-{}", self.consumer_name, stream_name, self.subject);
+        let mut in_flight = InFlightMessages::new(self.sender.clone());
+        let mut jetstream_acks = FuturesUnordered::new();
+
+        loop {
+            tokio::select! {
+                Some(res) = messages.next(), if in_flight.len() + jetstream_acks.len() < MAX_IN_FLIGHT_MESSAGES => {
+                    let msg = res.map_err(Error::nats)?;
+                    let info = msg.info().map_err(Error::nats)?;
+
+                    let mut attributes = HashMap::with_capacity(4);
+                    attributes.insert("nats.subject".to_string(), msg.subject.to_string());
+                    attributes.insert("nats.stream".to_string(), info.stream.to_string());
+                    attributes.insert("nats.sequence".to_string(), info.stream_sequence.to_string());
+                    attributes.insert(
+                        "restate.subscription.id".to_string(),
+                        self.sender.subscription().id().to_string(),
+                    );
+
+                    let source_message = SourceMessage {
+                        system: "nats",
+                        source_name: msg.subject.to_string(),
+                        position: format!(
+                            "subject {} stream {} sequence {}",
+                            msg.subject, info.stream, info.stream_sequence
+                        ),
+                        ordering_key: deduplication_id.clone(),
+                        key: Default::default(),
+                        payload: msg.payload.clone(),
+                        headers: msg
+                            .headers
+                            .iter()
+                            .flat_map(|headers| headers.iter())
+                            .filter_map(|(name, values)| {
+                                values.first().map(|value| (name.to_string(), value.to_string()))
+                            })
+                            .collect(),
+                        attributes,
+                        deduplication: (
+                            NatsDeduplicationId(deduplication_id.clone()),
+                            info.stream_sequence,
+                        ),
+                    };
+
+                    in_flight.push(source_message, msg);
+                }
+                Some(res) = in_flight.next(), if !in_flight.is_empty() => {
+                    let msg = res?;
+                    jetstream_acks.push(async move { msg.ack().await.map_err(Error::Nats) });
+                }
+                Some(res) = jetstream_acks.next() => {
+                    res?;
+                }
+                _ = &mut rx => {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::metric_definitions::{
+    KAFKA_INGRESS_CONSUMER_LAG, KAFKA_INGRESS_HIGH_WATERMARK, KAFKA_INGRESS_STORED_OFFSET,
+};
+use metrics::gauge;
+use restate_schema_api::subscription::{SubscriptionOffsetsResolver, SubscriptionPartitionOffsets};
+use restate_types::identifiers::SubscriptionId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+type PartitionKey = (SubscriptionId, String, i32);
+
+/// Latest known offsets of the Kafka partitions consumed by the running subscriptions.
+///
+/// Every update is also reported through the `restate.kafka_ingress.*` gauges.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionOffsets(Arc<Mutex<BTreeMap<PartitionKey, SubscriptionPartitionOffsets>>>);
+
+impl SubscriptionOffsets {
+    pub(crate) fn update(
+        &self,
+        subscription_id: SubscriptionId,
+        topic: &str,
+        partition: i32,
+        stored_offset: Option<i64>,
+        high_watermark: Option<i64>,
+    ) {
+        let mut partitions = self.0.lock().expect("lock should not be poisoned");
+        let offsets = partitions
+            .entry((subscription_id, topic.to_owned(), partition))
+            .or_insert_with(|| SubscriptionPartitionOffsets {
+                subscription_id,
+                topic: topic.to_owned(),
+                partition,
+                stored_offset: None,
+                high_watermark: None,
+            });
+        if stored_offset.is_some() {
+            offsets.stored_offset = stored_offset;
+        }
+        if high_watermark.is_some() {
+            offsets.high_watermark = high_watermark;
+        }
+
+        let subscription = subscription_id.to_string();
+        let partition = partition.to_string();
+        if let Some(stored_offset) = offsets.stored_offset {
+            gauge!(KAFKA_INGRESS_STORED_OFFSET, "subscription" => subscription.clone(), "topic" => topic.to_owned(), "partition" => partition.clone())
+                .set(stored_offset as f64);
+        }
+        if let Some(high_watermark) = offsets.high_watermark {
+            gauge!(KAFKA_INGRESS_HIGH_WATERMARK, "subscription" => subscription.clone(), "topic" => topic.to_owned(), "partition" => partition.clone())
+                .set(high_watermark as f64);
+        }
+        if let Some(lag) = offsets.lag() {
+            gauge!(KAFKA_INGRESS_CONSUMER_LAG, "subscription" => subscription, "topic" => topic.to_owned(), "partition" => partition)
+                .set(lag as f64);
+        }
+    }
+
+    /// Forgets the partition, once it's not consumed anymore by this process.
+    pub(crate) fn remove_partition(
+        &self,
+        subscription_id: SubscriptionId,
+        topic: &str,
+        partition: i32,
+    ) {
+        self.0
+            .lock()
+            .expect("lock should not be poisoned")
+            .remove(&(subscription_id, topic.to_owned(), partition));
+    }
+
+    pub(crate) fn remove_subscription(&self, subscription_id: SubscriptionId) {
+        self.0
+            .lock()
+            .expect("lock should not be poisoned")
+            .retain(|(id, _, _), _| *id != subscription_id);
+    }
+}
+
+impl SubscriptionOffsetsResolver for SubscriptionOffsets {
+    fn list_subscription_offsets(&self) -> Vec<SubscriptionPartitionOffsets> {
+        self.0
+            .lock()
+            .expect("lock should not be poisoned")
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_keeps_the_latest_known_offsets() {
+        let offsets = SubscriptionOffsets::default();
+        let subscription_id = SubscriptionId::new();
+
+        offsets.update(subscription_id, "my-topic", 0, None, Some(10));
+        assert_eq!(offsets.list_subscription_offsets()[0].lag(), None);
+
+        offsets.update(subscription_id, "my-topic", 0, Some(4), None);
+        offsets.update(subscription_id, "my-topic", 1, Some(2), Some(2));
+
+        let partitions = offsets.list_subscription_offsets();
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].stored_offset, Some(4));
+        assert_eq!(partitions[0].high_watermark, Some(10));
+        assert_eq!(partitions[0].lag(), Some(6));
+        assert_eq!(partitions[1].lag(), Some(0));
+
+        offsets.remove_partition(subscription_id, "my-topic", 0);
+        assert_eq!(offsets.list_subscription_offsets().len(), 1);
+        offsets.remove_subscription(subscription_id);
+        assert!(offsets.list_subscription_offsets().is_empty());
+    }
+}
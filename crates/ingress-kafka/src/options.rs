@@ -8,11 +8,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::egress::KafkaEgress;
+use crate::event_filter::EventFilter;
+use crate::metric_definitions;
+use crate::nats;
 use crate::subscription_controller::Service;
-use restate_ingress_dispatcher::IngressRequestSender;
+use restate_ingress_dispatcher::{
+    IngressRequestSender, PayloadDecoder, DEFAULT_SCHEMA_REGISTRY_TIMEOUT,
+};
 use restate_schema_api::subscription::{Source, Subscription, SubscriptionValidator};
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use tracing::warn;
 
 /// # Kafka cluster options
@@ -36,34 +45,134 @@ pub struct KafkaClusterOptions {
     pub(crate) additional_options: HashMap<String, String>,
 }
 
+/// # NATS cluster options
+///
+/// Configuration options to connect to a NATS cluster with JetStream enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub struct NatsClusterOptions {
+    /// # Servers
+    ///
+    /// List of server urls as CSV, e.g. `nats://localhost:4222`.
+    pub(crate) servers: String,
+}
+
 /// # Subscription options
-#[derive(Debug, Clone, Default, Serialize, Deserialize, derive_builder::Builder)]
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, derive_builder::Builder)]
 #[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "options_schema", schemars(rename = "SubscriptionOptions"))]
 #[builder(default)]
 pub struct Options {
     /// # Kafka clusters
     ///
-    /// Configuration parameters for the known kafka clusters
+    /// Configuration parameters for the known kafka clusters, used both by subscriptions and by the `dev.restate.Kafka` built-in service to publish records
     pub(crate) clusters: HashMap<String, KafkaClusterOptions>,
+
+    /// # NATS clusters
+    ///
+    /// Configuration parameters for the known NATS clusters, used by subscriptions with a `nats://` source
+    #[serde(default)]
+    pub(crate) nats_clusters: HashMap<String, NatsClusterOptions>,
+
+    /// # File source root
+    ///
+    /// Directory containing the files which can be consumed by subscriptions with a `file://` source.
+    /// The read position of every tailed file is stored in a hidden file next to it.
+    /// If unset, subscriptions with a `file://` source are rejected.
+    #[serde(default)]
+    pub(crate) file_source_root: Option<PathBuf>,
+
+    /// # Schema registry timeout
+    ///
+    /// Timeout of the requests to the schema registry when decoding the payloads of subscriptions.
+    /// Payloads whose schema cannot be fetched within the timeout are consumed again later.
+    ///
+    /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+    #[serde(default = "default_schema_registry_timeout")]
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "options_schema", schemars(with = "String"))]
+    pub(crate) schema_registry_timeout: humantime::Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            clusters: Default::default(),
+            nats_clusters: Default::default(),
+            file_source_root: None,
+            schema_registry_timeout: default_schema_registry_timeout(),
+        }
+    }
+}
+
+fn default_schema_registry_timeout() -> humantime::Duration {
+    DEFAULT_SCHEMA_REGISTRY_TIMEOUT.into()
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("invalid option '{name}'. Reason: {reason}")]
 pub struct ValidationError {
     name: &'static str,
-    reason: &'static str,
+    reason: Cow<'static, str>,
 }
 
 impl SubscriptionValidator for Options {
     type Error = ValidationError;
 
-    fn validate(&self, mut subscription: Subscription) -> Result<Subscription, Self::Error> {
+    fn validate(&self, subscription: Subscription) -> Result<Subscription, Self::Error> {
+        validate_filters(&subscription)?;
+        if let Some(dead_letter) = &subscription.failure_policy().dead_letter {
+            if !self.clusters.contains_key(&dead_letter.cluster) {
+                return Err(ValidationError {
+                    name: "dead letter cluster",
+                    reason: format!("the Kafka cluster '{}' of the dead letter topic does not exist. Make sure it is defined in the KafkaOptions", dead_letter.cluster).into(),
+                });
+            }
+        }
+
+        match subscription.source() {
+            Source::Kafka { .. } => self.validate_kafka(subscription),
+            Source::Nats { .. } => self.validate_nats(subscription),
+            Source::File { path } => {
+                self.validate_file(Path::new(path))?;
+                Ok(subscription)
+            }
+        }
+    }
+}
+
+fn validate_filters(subscription: &Subscription) -> Result<(), ValidationError> {
+    if let Some(filter) = subscription.filter() {
+        filter.parse::<EventFilter>().map_err(|e| ValidationError {
+            name: "filter",
+            reason: e.to_string().into(),
+        })?;
+    }
+    for route in subscription.routes() {
+        route
+            .filter
+            .parse::<EventFilter>()
+            .map_err(|e| ValidationError {
+                name: "route filter",
+                reason: format!("route '{}': {}", route.name, e).into(),
+            })?;
+    }
+    Ok(())
+}
+
+impl Options {
+    fn validate_kafka(
+        &self,
+        mut subscription: Subscription,
+    ) -> Result<Subscription, ValidationError> {
         // Retrieve the cluster option and merge them with subscription metadata
-        let Source::Kafka { cluster, .. } = subscription.source();
+        let Source::Kafka { cluster, .. } = subscription.source() else {
+            unreachable!("checked by the caller");
+        };
         let cluster_options = &self.clusters.get(cluster).ok_or(ValidationError {
             name: "source",
-            reason: "specified cluster in the source URI does not exist. Make sure it is defined in the KafkaOptions",
+            reason: "specified cluster in the source URI does not exist. Make sure it is defined in the KafkaOptions".into(),
         })?.additional_options;
 
         if cluster_options.contains_key("enable.auto.commit")
@@ -101,10 +210,167 @@ impl SubscriptionValidator for Options {
 
         Ok(subscription)
     }
+
+    fn validate_nats(
+        &self,
+        mut subscription: Subscription,
+    ) -> Result<Subscription, ValidationError> {
+        let Source::Nats { cluster, .. } = subscription.source() else {
+            unreachable!("checked by the caller");
+        };
+        if !self.nats_clusters.contains_key(cluster) {
+            return Err(ValidationError {
+                name: "source",
+                reason: "specified NATS cluster in the source URI does not exist. Make sure it is defined in the nats_clusters of the SubscriptionOptions".into(),
+            });
+        }
+
+        // Set the durable consumer name if unset, so consumption resumes where it stopped
+        if !subscription
+            .metadata()
+            .contains_key(nats::CONSUMER_NAME_OPTION)
+        {
+            let consumer_name = subscription.id().to_string();
+
+            subscription
+                .metadata_mut()
+                .insert(nats::CONSUMER_NAME_OPTION.to_string(), consumer_name);
+        }
+
+        Ok(subscription)
+    }
+
+    fn validate_file(&self, path: &Path) -> Result<(), ValidationError> {
+        let Some(root) = &self.file_source_root else {
+            return Err(ValidationError {
+                name: "source",
+                reason: "file sources are disabled. Set the file_source_root of the SubscriptionOptions to enable them".into(),
+            });
+        };
+        if !path.is_absolute() {
+            return Err(ValidationError {
+                name: "source",
+                reason: "specified path in the source URI must be absolute".into(),
+            });
+        }
+        // Symlinks pointing outside the root are rejected by the connector when reading
+        if path.components().any(|c| c == Component::ParentDir) || !path.starts_with(root) {
+            return Err(ValidationError {
+                name: "source",
+                reason: format!(
+                    "specified path in the source URI must be within the file source root '{}'",
+                    root.display()
+                )
+                .into(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn build(self, tx: IngressRequestSender, payload_decoder: PayloadDecoder) -> Service {
+        metric_definitions::describe_metrics();
+        let payload_decoder =
+            payload_decoder.with_schema_registry_timeout(self.schema_registry_timeout.into());
+        Service::new(self, tx, payload_decoder)
+    }
+
+    pub fn build_egress(&self) -> KafkaEgress {
+        KafkaEgress::new(self)
+    }
 }
 
-impl Options {
-    pub fn build(self, tx: IngressRequestSender) -> Service {
-        Service::new(self, tx)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_schema_api::subscription::{EventReceiverServiceInstanceType, Sink};
+
+    fn subscription(source: Source) -> Subscription {
+        Subscription::new(
+            Default::default(),
+            source,
+            Sink::Service {
+                name: "MySvc".to_string(),
+                method: "MyMethod".to_string(),
+                input_event_remap: None,
+                instance_type: EventReceiverServiceInstanceType::Singleton,
+            },
+            Default::default(),
+        )
+    }
+
+    fn file_source(path: &str) -> Source {
+        Source::File {
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn file_source_requires_root() {
+        let options = Options::default();
+
+        assert!(options
+            .validate(subscription(file_source("/var/events")))
+            .is_err());
+    }
+
+    #[test]
+    fn file_source_must_be_within_root() {
+        let options = OptionsBuilder::default()
+            .file_source_root(Some(PathBuf::from("/var/events")))
+            .build()
+            .unwrap();
+
+        assert!(options
+            .validate(subscription(file_source("/var/events/orders")))
+            .is_ok());
+        assert!(options
+            .validate(subscription(file_source("/var/events")))
+            .is_ok());
+        assert!(options
+            .validate(subscription(file_source("/var/events-other")))
+            .is_err());
+        assert!(options
+            .validate(subscription(file_source("/var/events/../secrets")))
+            .is_err());
+        assert!(options
+            .validate(subscription(file_source("events/orders")))
+            .is_err());
+    }
+
+    #[test]
+    fn nats_source_sets_consumer_name() {
+        let options = OptionsBuilder::default()
+            .nats_clusters(HashMap::from([(
+                "my-cluster".to_string(),
+                NatsClusterOptions {
+                    servers: "nats://localhost:4222".to_string(),
+                },
+            )]))
+            .build()
+            .unwrap();
+
+        let subscription = options
+            .validate(subscription(Source::Nats {
+                cluster: "my-cluster".to_string(),
+                subject: "orders".to_string(),
+            }))
+            .unwrap();
+        assert_eq!(
+            subscription.metadata().get(nats::CONSUMER_NAME_OPTION),
+            Some(&subscription.id().to_string())
+        );
+    }
+
+    #[test]
+    fn nats_source_requires_known_cluster() {
+        let err = Options::default()
+            .validate(subscription(Source::Nats {
+                cluster: "my-cluster".to_string(),
+                subject: "orders".to_string(),
+            }))
+            .unwrap_err();
+
+        assert!(err.to_string().contains("nats_clusters"));
     }
 }
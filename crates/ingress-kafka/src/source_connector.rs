@@ -0,0 +1,491 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use crate::event_filter::EventFilter;
+use bytes::Bytes;
+use futures::future::{self, BoxFuture, Either};
+use futures::stream::FuturesOrdered;
+use futures::{ready, FutureExt, StreamExt, TryFutureExt};
+use opentelemetry_api::trace::TraceContextExt;
+use rdkafka::error::KafkaError;
+use restate_ingress_dispatcher::{
+    DeduplicationId, EventError, IngressRequest, IngressRequestSender, PayloadDecoder,
+    PayloadDecodingError,
+};
+use restate_pb::restate::Event;
+use restate_schema_api::subscription::{EventReceiverServiceInstanceType, Sink, Subscription};
+use restate_types::invocation::SpanRelation;
+use restate_types::message::MessageIndex;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::task::{Context, Poll};
+use tokio::sync::oneshot;
+use tracing::{debug, info, info_span, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Kafka(#[from] KafkaError),
+    #[error("nats error: {0}")]
+    Nats(async_nats::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("error processing message {message}: {cause}")]
+    Event {
+        message: String,
+        #[source]
+        cause: EventError,
+    },
+    #[error("error decoding payload of message {message}: {cause}")]
+    PayloadDecoding {
+        message: String,
+        #[source]
+        cause: PayloadDecodingError,
+    },
+    #[error("ingress dispatcher channel is closed")]
+    IngressDispatcherClosed,
+}
+
+impl Error {
+    pub(crate) fn nats(err: impl Into<async_nats::Error>) -> Self {
+        Error::Nats(err.into())
+    }
+}
+
+/// Source of the events of a subscription.
+///
+/// Connectors must deliver the events at least once: a message is acknowledged to the source
+/// only after the ingress acknowledged the corresponding event, see [`MessageSender::send`].
+/// Redelivered messages are discarded by the ingress, as long as every message carries a
+/// [`DeduplicationId`] together with an index monotonically increasing within it.
+pub(crate) trait SourceConnector: Send + Sync + 'static {
+    /// Consumes the source until `shutdown` is completed or dropped.
+    ///
+    /// This method is invoked again to restart the connector after a failure.
+    fn run(&self, shutdown: oneshot::Receiver<()>) -> BoxFuture<'static, Result<(), Error>>;
+}
+
+/// Returns true if events with the same ordering key are always processed by the same
+/// partition. Connectors using the ordering key as deduplication scope don't need proxying then.
+pub(crate) fn ordering_key_is_deduplication_scope(subscription: &Subscription) -> bool {
+    matches!(
+        subscription.sink(),
+        Sink::Service {
+            instance_type: EventReceiverServiceInstanceType::Keyed {
+                ordering_key_is_key: true,
+            },
+            ..
+        } | Sink::Service {
+            instance_type: EventReceiverServiceInstanceType::Singleton,
+            ..
+        }
+    )
+}
+
+/// Message consumed from a source, to be sent to the ingress as [`Event`].
+pub(crate) struct SourceMessage<D> {
+    /// Name of the messaging system, as in the OpenTelemetry messaging semantic conventions
+    pub(crate) system: &'static str,
+    /// Name of the topic, subject or file the message was consumed from
+    pub(crate) source_name: String,
+    /// Human readable position of the message within the source, used for error messages
+    pub(crate) position: String,
+    pub(crate) ordering_key: String,
+    pub(crate) key: Bytes,
+    pub(crate) payload: Bytes,
+    /// Headers of the message, available to the subscription filters only
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) attributes: HashMap<String, String>,
+    pub(crate) deduplication: (D, MessageIndex),
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageSender {
+    subscription: Subscription,
+    filter: Option<EventFilter>,
+    /// Filter of every route, together with the subscription to dispatch the matching events with
+    routes: Vec<(EventFilter, Subscription)>,
+    tx: IngressRequestSender,
+    payload_decoder: PayloadDecoder,
+}
+
+impl MessageSender {
+    pub fn new(
+        subscription: Subscription,
+        tx: IngressRequestSender,
+        payload_decoder: PayloadDecoder,
+    ) -> Self {
+        let filter = subscription.filter().map(|filter| {
+            filter
+                .parse()
+                .expect("subscription filter must be validated on creation")
+        });
+        let routes = subscription
+            .routes()
+            .iter()
+            .map(|route| {
+                (
+                    route
+                        .filter
+                        .parse()
+                        .expect("route filter must be validated on creation"),
+                    subscription.with_sink(route.sink.clone()),
+                )
+            })
+            .collect();
+
+        Self {
+            subscription,
+            filter,
+            routes,
+            tx,
+            payload_decoder,
+        }
+    }
+
+    pub(crate) fn subscription(&self) -> &Subscription {
+        &self.subscription
+    }
+
+    /// Sends the message to the ingress dispatcher, returning a future completed once the
+    /// ingress has acknowledged it.
+    ///
+    /// Messages whose payload cannot be decoded are discarded, unless the schema registry failed.
+    pub(crate) async fn send<D: DeduplicationId>(
+        &self,
+        msg: SourceMessage<D>,
+    ) -> Result<impl Future<Output = Result<(), Error>> + Send + 'static, Error> {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.matches(&msg))
+        {
+            debug!(
+                "Discarding message {} not matching the subscription filter",
+                msg.position
+            );
+            return Ok(Either::Left(future::ok(())));
+        }
+
+        // The first matching route determines the sink
+        let target = self
+            .routes
+            .iter()
+            .find(|(filter, _)| filter.matches(&msg))
+            .map(|(_, subscription)| subscription)
+            .unwrap_or(&self.subscription);
+
+        let Sink::Service {
+            name,
+            method,
+            input_event_remap,
+            ..
+        } = target.sink();
+
+        // Prepare ingress span
+        let ingress_span = info_span!(
+            "subscription_ingress_consume",
+            otel.name = format!("{}_ingress_consume", msg.system),
+            messaging.system = msg.system,
+            messaging.operation = "receive",
+            messaging.source.name = msg.source_name,
+            messaging.destination.name = format!("{}/{}", name, method)
+        );
+        info!(parent: &ingress_span, "Processing {} ingress request", msg.system);
+        let ingress_span_context = ingress_span.context().span().span_context().clone();
+
+        let mut event = Event {
+            ordering_key: msg.ordering_key,
+            key: msg.key,
+            payload: msg.payload,
+            attributes: msg.attributes,
+        };
+
+        if let Some(payload_decoding) = input_event_remap
+            .as_ref()
+            .and_then(|remap| remap.payload_decoding.as_ref())
+        {
+            if !event.payload.is_empty() {
+                event.payload = match self
+                    .payload_decoder
+                    .decode(name, method, payload_decoding, event.payload)
+                    .instrument(ingress_span.clone())
+                    .await
+                {
+                    Ok(payload) => payload,
+                    Err(cause) if cause.is_transient() => {
+                        return Err(Error::PayloadDecoding {
+                            message: msg.position,
+                            cause,
+                        });
+                    }
+                    Err(cause) => {
+                        // Consuming the message again would fail the same way, stalling the subscription
+                        warn!(
+                            parent: &ingress_span,
+                            "Discarding message {} whose payload cannot be decoded: {cause}",
+                            msg.position
+                        );
+                        return Ok(Either::Left(future::ok(())));
+                    }
+                };
+            }
+        }
+
+        let (req, rx) = IngressRequest::event(
+            target,
+            event,
+            SpanRelation::Parent(ingress_span_context),
+            Some(msg.deduplication),
+        )
+        .map_err(|cause| Error::Event {
+            message: msg.position,
+            cause,
+        })?;
+
+        ingress_span.in_scope(|| {
+            self.tx
+                .send(req)
+                .map_err(|_| Error::IngressDispatcherClosed)
+        })?;
+
+        Ok(Either::Right(
+            async move {
+                rx.await.map_err(|_| Error::IngressDispatcherClosed)?;
+
+                Ok(())
+            }
+            .instrument(ingress_span),
+        ))
+    }
+}
+
+type AckFuture = BoxFuture<'static, Result<(), Error>>;
+
+/// Messages on their way to the ingress, together with their position within the source.
+///
+/// Messages are sent one at a time in the order they've been pushed, since sending can await the
+/// payload decoding. Sending is driven by [`InFlightMessages::next`], so that connectors can keep
+/// polling their source and the acks of the previous messages meanwhile.
+pub(crate) struct InFlightMessages<D, P> {
+    sender: MessageSender,
+    pending: VecDeque<(SourceMessage<D>, P)>,
+    sending: Option<BoxFuture<'static, Result<(AckFuture, P), Error>>>,
+    acks: FuturesOrdered<BoxFuture<'static, Result<P, Error>>>,
+}
+
+impl<D, P> InFlightMessages<D, P>
+where
+    D: DeduplicationId + Send + 'static,
+    P: Send + 'static,
+{
+    pub(crate) fn new(sender: MessageSender) -> Self {
+        Self {
+            sender,
+            pending: VecDeque::new(),
+            sending: None,
+            acks: FuturesOrdered::new(),
+        }
+    }
+
+    /// Number of messages which have been pushed, but not yet acknowledged.
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len() + usize::from(self.sending.is_some()) + self.acks.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn push(&mut self, msg: SourceMessage<D>, position: P) {
+        self.pending.push_back((msg, position));
+    }
+
+    /// Returns the position of the next acknowledged message, in the order the messages have been
+    /// pushed, or `None` if there are no messages in flight.
+    pub(crate) async fn next(&mut self) -> Option<Result<P, Error>> {
+        future::poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<P, Error>>> {
+        loop {
+            if self.sending.is_none() {
+                let Some((msg, position)) = self.pending.pop_front() else {
+                    break;
+                };
+                let sender = self.sender.clone();
+                self.sending = Some(
+                    async move {
+                        let ack = sender.send(msg).await?;
+                        Ok((ack.boxed(), position))
+                    }
+                    .boxed(),
+                );
+            }
+
+            let sending = self.sending.as_mut().expect("sending is set above");
+            match sending.poll_unpin(cx) {
+                Poll::Ready(res) => {
+                    self.sending = None;
+                    let (ack, position) = match res {
+                        Ok(sent) => sent,
+                        Err(err) => return Poll::Ready(Some(Err(err))),
+                    };
+                    self.acks.push_back(ack.map_ok(move |_| position).boxed());
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        match ready!(self.acks.poll_next_unpin(cx)) {
+            Some(res) => Poll::Ready(Some(res)),
+            None if self.is_empty() => Poll::Ready(None),
+            // Waiting for the message being sent
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::Message;
+    use restate_ingress_dispatcher::{IngressDeduplicationId, PayloadDecoder};
+    use restate_schema_api::subscription::{
+        InputEventRemap, PayloadDecoding, PayloadFormat, Source,
+    };
+    use restate_schema_impl::Schemas;
+    use std::fmt;
+    use tokio::sync::mpsc;
+
+    struct TestDeduplicationId;
+
+    impl fmt::Display for TestDeduplicationId {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("test")
+        }
+    }
+
+    impl DeduplicationId for TestDeduplicationId {
+        fn requires_proxying(_: &Subscription) -> bool {
+            false
+        }
+    }
+
+    fn source_message(index: MessageIndex) -> SourceMessage<TestDeduplicationId> {
+        SourceMessage {
+            system: "test",
+            source_name: "test".to_string(),
+            position: format!("index {index}"),
+            ordering_key: "test".to_string(),
+            key: Bytes::default(),
+            payload: Bytes::from(index.to_string()),
+            headers: HashMap::new(),
+            attributes: HashMap::new(),
+            deduplication: (TestDeduplicationId, index),
+        }
+    }
+
+    fn expect_event(
+        rx: &mut mpsc::UnboundedReceiver<IngressRequest>,
+    ) -> (IngressDeduplicationId, oneshot::Sender<()>) {
+        let (_, _, argument, _, dedup_id, ack_tx) = rx
+            .try_recv()
+            .unwrap()
+            .expect_dedupable_background_invocation();
+        assert_eq!(
+            Event::decode(argument).unwrap().payload,
+            dedup_id.1.to_string()
+        );
+        (dedup_id, ack_tx)
+    }
+
+    fn subscription(input_event_remap: Option<InputEventRemap>) -> Subscription {
+        Subscription::new(
+            Default::default(),
+            Source::File {
+                path: "test".to_string(),
+            },
+            Sink::Service {
+                name: "MySvc".to_string(),
+                method: "MyMethod".to_string(),
+                input_event_remap,
+                instance_type: EventReceiverServiceInstanceType::Singleton,
+            },
+            Default::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn in_flight_messages_are_acknowledged_in_order() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut in_flight = InFlightMessages::new(MessageSender::new(
+            subscription(None),
+            tx,
+            PayloadDecoder::new(Schemas::default()),
+        ));
+        assert!(in_flight.next().await.is_none());
+
+        for index in 0..3 {
+            in_flight.push(source_message(index), index);
+        }
+        assert_eq!(in_flight.len(), 3);
+
+        // Sending is driven by polling for the acks
+        assert!(in_flight.next().now_or_never().is_none());
+        let (dedup_id_0, ack_tx_0) = expect_event(&mut rx);
+        let (dedup_id_1, ack_tx_1) = expect_event(&mut rx);
+        let (dedup_id_2, ack_tx_2) = expect_event(&mut rx);
+        assert_eq!(dedup_id_0.1, 0);
+        assert_eq!(dedup_id_1.1, 1);
+        assert_eq!(dedup_id_2.1, 2);
+
+        // The position of a message is returned once all the previous messages are acknowledged
+        ack_tx_1.send(()).unwrap();
+        assert!(in_flight.next().now_or_never().is_none());
+        ack_tx_0.send(()).unwrap();
+        assert_eq!(in_flight.next().await.unwrap().unwrap(), 0);
+        assert_eq!(in_flight.next().await.unwrap().unwrap(), 1);
+        assert_eq!(in_flight.len(), 1);
+
+        ack_tx_2.send(()).unwrap();
+        assert_eq!(in_flight.next().await.unwrap().unwrap(), 2);
+        assert!(in_flight.is_empty());
+        assert!(in_flight.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn undecodable_payload_is_discarded() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sender = MessageSender::new(
+            subscription(Some(InputEventRemap {
+                key: None,
+                payload: None,
+                attributes_index: None,
+                payload_decoding: Some(PayloadDecoding {
+                    format: PayloadFormat::Json,
+                    schema_registry_url: None,
+                }),
+            })),
+            tx,
+            PayloadDecoder::new(Schemas::default()),
+        );
+
+        let mut msg = source_message(0);
+        msg.payload = Bytes::from_static(b"not json");
+
+        // The message is acknowledged right away, so the consumption of the source can proceed
+        let ack = sender.send(msg).await.unwrap();
+        ack.await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+}
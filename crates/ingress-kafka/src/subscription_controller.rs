@@ -8,29 +8,41 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use super::consumer_task::MessageSender;
+use super::offsets::SubscriptionOffsets;
 use super::options::Options;
+use super::source_connector::{MessageSender, SourceConnector};
 use super::*;
 
 use crate::subscription_controller::task_orchestrator::TaskOrchestrator;
 use rdkafka::error::KafkaError;
-use restate_ingress_dispatcher::IngressRequestSender;
-use restate_schema_api::subscription::{Source, Subscription};
+use restate_ingress_dispatcher::{IngressRequestSender, PayloadDecoder};
+use restate_schema_api::subscription::{OffsetReset, Source, Subscription};
 use restate_types::identifiers::SubscriptionId;
 use restate_types::retries::RetryPolicy;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
 
 #[derive(Debug)]
 pub enum Command {
     StartSubscription(Subscription),
     StopSubscription(SubscriptionId),
+    /// Commits the offsets of the subscription consumer group. The subscription must be stopped.
+    ResetOffsets(
+        Subscription,
+        OffsetReset,
+        oneshot::Sender<Result<(), Error>>,
+    ),
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
     Kafka(#[from] KafkaError),
+    #[error("only subscriptions with a Kafka source support resetting the offsets")]
+    UnsupportedOffsetsReset,
 }
 
 // For simplicity of the current implementation, this currently lives in this module
@@ -38,18 +50,26 @@ pub enum Error {
 pub struct Service {
     options: Options,
     ingress_tx: IngressRequestSender,
+    payload_decoder: PayloadDecoder,
+    offsets: SubscriptionOffsets,
 
     commands_tx: SubscriptionCommandSender,
     commands_rx: SubscriptionCommandReceiver,
 }
 
 impl Service {
-    pub(crate) fn new(options: Options, ingress_tx: IngressRequestSender) -> Service {
+    pub(crate) fn new(
+        options: Options,
+        ingress_tx: IngressRequestSender,
+        payload_decoder: PayloadDecoder,
+    ) -> Service {
         let (commands_tx, commands_rx) = mpsc::channel(10);
 
         Service {
             options,
             ingress_tx,
+            payload_decoder,
+            offsets: SubscriptionOffsets::default(),
             commands_tx,
             commands_rx,
         }
@@ -59,6 +79,11 @@ impl Service {
         self.commands_tx.clone()
     }
 
+    /// Offsets of the Kafka partitions consumed by the running subscriptions.
+    pub fn subscription_offsets(&self) -> SubscriptionOffsets {
+        self.offsets.clone()
+    }
+
     pub async fn run(mut self, drain: drain::Watch) {
         let shutdown = drain.signaled();
         tokio::pin!(shutdown);
@@ -75,7 +100,8 @@ impl Service {
                 Some(cmd) = self.commands_rx.recv() => {
                     match cmd {
                         Command::StartSubscription(sub) => self.handle_start_subscription(sub, &mut task_orchestrator),
-                        Command::StopSubscription(sub_id) => self.handle_stop_subscription(sub_id, &mut task_orchestrator)
+                        Command::StopSubscription(sub_id) => self.handle_stop_subscription(sub_id, &mut task_orchestrator),
+                        Command::ResetOffsets(sub, to, reply_tx) => self.handle_reset_offsets(sub, to, reply_tx),
                     }
                 }
                 _ = task_orchestrator.poll(), if !task_orchestrator.is_empty() => {},
@@ -94,11 +120,76 @@ impl Service {
         subscription: Subscription,
         task_orchestrator: &mut TaskOrchestrator,
     ) {
-        let mut client_config = rdkafka::ClientConfig::new();
+        let subscription_id = subscription.id();
 
-        let Source::Kafka { cluster, topic, .. } = subscription.source();
+        // Create the connector of the subscription source
+        let connector: Arc<dyn SourceConnector> = match subscription.source().clone() {
+            Source::Kafka {
+                cluster,
+                topic,
+                ordering_key_format,
+            } => {
+                let mut client_config = self.kafka_client_config(&cluster, &subscription);
+
+                // Options required by the business logic of our consumer,
+                // see ConsumerTask::run
+                client_config.set("enable.auto.commit", "true");
+                client_config.set("enable.auto.offset.store", "false");
+
+                Arc::new(consumer_task::ConsumerTask::new(
+                    client_config,
+                    vec![topic],
+                    ordering_key_format,
+                    self.message_sender(subscription),
+                    self.offsets.clone(),
+                ))
+            }
+            Source::Nats { cluster, subject } => {
+                let cluster_options =
+                    self.options.nats_clusters.get(&cluster).unwrap_or_else(|| {
+                        panic!("KafkaOptions should contain the NATS cluster '{}'", cluster)
+                    });
+                let consumer_name = subscription
+                    .metadata()
+                    .get(nats::CONSUMER_NAME_OPTION)
+                    .cloned()
+                    .expect("consumer name must be set");
+
+                Arc::new(nats::NatsConsumerTask::new(
+                    cluster_options.servers.clone(),
+                    subject,
+                    consumer_name,
+                    self.message_sender(subscription),
+                ))
+            }
+            Source::File { path } => {
+                let Some(root) = self.options.file_source_root.clone() else {
+                    warn!(
+                        "Cannot start subscription {}: file sources are disabled",
+                        subscription_id
+                    );
+                    return;
+                };
+
+                Arc::new(file::FileConsumerTask::new(
+                    PathBuf::from(path),
+                    root,
+                    self.message_sender(subscription),
+                ))
+            }
+        };
+
+        task_orchestrator.start(subscription_id, connector);
+    }
+
+    /// Copies cluster options and subscription metadata into the client configuration.
+    fn kafka_client_config(
+        &self,
+        cluster: &str,
+        subscription: &Subscription,
+    ) -> rdkafka::ClientConfig {
+        let mut client_config = rdkafka::ClientConfig::new();
 
-        // Copy cluster options and subscription metadata into client_config
         let cluster_options = self
             .options
             .clusters
@@ -113,21 +204,15 @@ impl Service {
             client_config.set(k, v);
         }
 
-        // Options required by the business logic of our consumer,
-        // see ConsumerTask::run
-        client_config.set("enable.auto.commit", "true");
-        client_config.set("enable.auto.offset.store", "false");
-
-        let subscription_id = subscription.id();
-
-        // Create the consumer task
-        let consumer_task = consumer_task::ConsumerTask::new(
-            client_config,
-            vec![topic.to_string()],
-            MessageSender::new(subscription, self.ingress_tx.clone()),
-        );
+        client_config
+    }
 
-        task_orchestrator.start(subscription_id, consumer_task);
+    fn message_sender(&self, subscription: Subscription) -> MessageSender {
+        MessageSender::new(
+            subscription,
+            self.ingress_tx.clone(),
+            self.payload_decoder.clone(),
+        )
     }
 
     fn handle_stop_subscription(
@@ -136,15 +221,66 @@ impl Service {
         task_orchestrator: &mut TaskOrchestrator,
     ) {
         task_orchestrator.stop(subscription_id);
+        self.offsets.remove_subscription(subscription_id);
+    }
+
+    /// Resets the offsets in the background, not to block the other commands while waiting for
+    /// the Kafka cluster. The caller must wait for the reply before starting the subscription again.
+    fn handle_reset_offsets(
+        &mut self,
+        subscription: Subscription,
+        to: OffsetReset,
+        reply_tx: oneshot::Sender<Result<(), Error>>,
+    ) {
+        let subscription_id = subscription.id();
+        let Source::Kafka { cluster, topic, .. } = subscription.source() else {
+            let _ = reply_tx.send(Err(Error::UnsupportedOffsetsReset));
+            return;
+        };
+        let client_config = self.kafka_client_config(cluster, &subscription);
+        let topic = topic.clone();
+
+        tokio::spawn(async move {
+            // The stopped consumer leaves the consumer group asynchronously, and until then
+            // the commit is rejected by the Kafka cluster
+            let res = RetryPolicy::fixed_delay(Duration::from_secs(1), 10)
+                .retry_operation(|| {
+                    let client_config = client_config.clone();
+                    let topic = topic.clone();
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            consumer_task::reset_offsets(&client_config, &topic, to)
+                        })
+                        .await
+                        .expect("reset offsets task should not panic")
+                    }
+                })
+                .await
+                .map_err(Error::from);
+
+            match &res {
+                Ok(()) => info!(
+                    "Reset the offsets of subscription {} to {:?}",
+                    subscription_id, to
+                ),
+                Err(e) => warn!(
+                    "Cannot reset the offsets of subscription {}: {}",
+                    subscription_id, e
+                ),
+            }
+            // If error, the caller went away
+            let _ = reply_tx.send(res);
+        });
     }
 }
 
 mod task_orchestrator {
-    use crate::consumer_task;
+    use crate::source_connector::{self, SourceConnector};
     use restate_timer_queue::TimerQueue;
     use restate_types::identifiers::SubscriptionId;
     use restate_types::retries::{RetryIter, RetryPolicy};
     use std::collections::HashMap;
+    use std::sync::Arc;
     use std::time::SystemTime;
     use tokio::sync::oneshot;
     use tokio::task;
@@ -152,8 +288,8 @@ mod task_orchestrator {
     use tracing::{debug, warn};
 
     struct TaskState {
-        // We use this to restart the consumer task in case of a failure
-        consumer_task_clone: consumer_task::ConsumerTask,
+        // We use this to restart the connector in case of a failure
+        connector: Arc<dyn SourceConnector>,
         task_state_inner: TaskStateInner,
         retry_iter: RetryIter,
     }
@@ -170,7 +306,7 @@ mod task_orchestrator {
         retry_policy: RetryPolicy,
         running_tasks_to_subscriptions: HashMap<task::Id, SubscriptionId>,
         subscription_id_to_task_state: HashMap<SubscriptionId, TaskState>,
-        tasks: JoinSet<Result<(), consumer_task::Error>>,
+        tasks: JoinSet<Result<(), source_connector::Error>>,
         timer_queue: TimerQueue<SubscriptionId>,
     }
 
@@ -202,7 +338,7 @@ mod task_orchestrator {
 
         fn handle_task_closed(
             &mut self,
-            result: Result<(task::Id, Result<(), consumer_task::Error>), JoinError>,
+            result: Result<(task::Id, Result<(), source_connector::Error>), JoinError>,
         ) {
             match result {
                 Ok((id, Ok(_))) => {
@@ -260,20 +396,17 @@ mod task_orchestrator {
                 _ => {}
             };
 
-            let TaskState {
-                consumer_task_clone,
-                ..
-            } = self
+            let TaskState { connector, .. } = self
                 .subscription_id_to_task_state
                 .remove(&subscription_id)
                 .expect("Checked in the previous match statement");
-            self.start(subscription_id, consumer_task_clone);
+            self.start(subscription_id, connector);
         }
 
         pub(super) fn start(
             &mut self,
             subscription_id: SubscriptionId,
-            consumer_task_clone: consumer_task::ConsumerTask,
+            connector: Arc<dyn SourceConnector>,
         ) {
             // Shutdown old task, if any
             if let Some(task_state) = self.subscription_id_to_task_state.remove(&subscription_id) {
@@ -290,14 +423,14 @@ mod task_orchestrator {
                 "Spawning the consumer task for subscription id {}",
                 subscription_id
             );
-            let task_id = self.tasks.spawn(consumer_task_clone.clone().run(rx)).id();
+            let task_id = self.tasks.spawn(connector.run(rx)).id();
 
             self.running_tasks_to_subscriptions
                 .insert(task_id, subscription_id);
             self.subscription_id_to_task_state.insert(
                 subscription_id,
                 TaskState {
-                    consumer_task_clone: consumer_task_clone.clone(),
+                    connector,
                     task_state_inner: TaskStateInner::Running {
                         task_id,
                         _close_ch: tx,
@@ -325,3 +458,52 @@ mod task_orchestrator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_schema_api::subscription::{EventReceiverServiceInstanceType, Sink};
+    use restate_schema_impl::Schemas;
+
+    #[tokio::test]
+    async fn reset_offsets_requires_kafka_source() {
+        let (ingress_tx, _ingress_rx) = mpsc::unbounded_channel();
+        let service = Options::default().build(ingress_tx, PayloadDecoder::new(Schemas::default()));
+        let commands_tx = service.create_command_sender();
+        let (drain_signal, watch) = drain::channel();
+        let service_handle = tokio::spawn(service.run(watch));
+
+        let subscription = Subscription::new(
+            Default::default(),
+            Source::Nats {
+                cluster: "my-cluster".to_string(),
+                subject: "orders".to_string(),
+            },
+            Sink::Service {
+                name: "MySvc".to_string(),
+                method: "MyMethod".to_string(),
+                input_event_remap: None,
+                instance_type: EventReceiverServiceInstanceType::Singleton,
+            },
+            Default::default(),
+        );
+        let (reply_tx, reply_rx) = oneshot::channel();
+        commands_tx
+            .send(Command::ResetOffsets(
+                subscription,
+                OffsetReset::Earliest,
+                reply_tx,
+            ))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            reply_rx.await.unwrap(),
+            Err(Error::UnsupportedOffsetsReset)
+        ));
+
+        drain_signal.drain().await;
+        service_handle.await.unwrap();
+    }
+}
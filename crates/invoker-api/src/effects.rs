@@ -35,6 +35,8 @@ pub enum EffectKind {
     },
     /// This is sent always after [`Self::JournalEntry`] with `OutputStreamEntry`(s).
     End,
+    /// This is sent when an attempt of an invocation limiting its attempts failed, before retrying it.
+    AttemptFailed,
     /// This is sent when the invoker exhausted all its attempts to make progress on the specific invocation.
     Failed(InvocationError),
 }
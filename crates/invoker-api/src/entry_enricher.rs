@@ -9,14 +9,17 @@
 // by the Apache License, Version 2.0.
 
 use restate_types::errors::InvocationError;
+use restate_types::identifiers::FullInvocationId;
 use restate_types::invocation::ServiceInvocationSpanContext;
 use restate_types::journal::enriched::EnrichedRawEntry;
 use restate_types::journal::raw::PlainRawEntry;
 
 pub trait EntryEnricher {
+    /// Enrich the entry produced by the invocation identified by `full_invocation_id`.
     fn enrich_entry(
         &self,
         entry: PlainRawEntry,
+        full_invocation_id: &FullInvocationId,
         invocation_span_context: &ServiceInvocationSpanContext,
     ) -> Result<EnrichedRawEntry, InvocationError>;
 }
@@ -25,7 +28,8 @@ pub trait EntryEnricher {
 pub mod mocks {
     use super::*;
 
-    use restate_types::identifiers::{InvocationId, InvocationUuid};
+    use crate::transcoder::{Transcoder, TranscoderResolver};
+    use restate_types::identifiers::{DeploymentId, InvocationId, InvocationUuid};
     use restate_types::invocation::ServiceInvocationSpanContext;
     use restate_types::journal::enriched::{
         AwakeableEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry, InvokeEnrichmentResult,
@@ -39,6 +43,7 @@ pub mod mocks {
         fn enrich_entry(
             &self,
             raw_entry: PlainRawEntry,
+            _full_invocation_id: &FullInvocationId,
             invocation_span_context: &ServiceInvocationSpanContext,
         ) -> Result<EnrichedRawEntry, InvocationError> {
             let (header, entry) = raw_entry.into_inner();
@@ -47,6 +52,7 @@ pub mod mocks {
                     EnrichedEntryHeader::PollInputStream { is_completed }
                 }
                 PlainEntryHeader::OutputStream {} => EnrichedEntryHeader::OutputStream {},
+                PlainEntryHeader::OutputStreamChunk {} => EnrichedEntryHeader::OutputStreamChunk {},
                 PlainEntryHeader::GetState { is_completed } => {
                     EnrichedEntryHeader::GetState { is_completed }
                 }
@@ -68,6 +74,7 @@ pub mod mocks {
                                 service_key: Default::default(),
                                 service_name: Default::default(),
                                 span_context: invocation_span_context.clone(),
+                                denied_by_call_policy: None,
                             }),
                         }
                     } else {
@@ -85,6 +92,7 @@ pub mod mocks {
                             service_key: Default::default(),
                             service_name: Default::default(),
                             span_context: invocation_span_context.clone(),
+                            denied_by_call_policy: None,
                         },
                     }
                 }
@@ -105,4 +113,15 @@ pub mod mocks {
             Ok(RawEntry::new(enriched_header, entry))
         }
     }
+
+    impl TranscoderResolver for MockEntryEnricher {
+        fn resolve_transcoder(
+            &self,
+            _deployment_id: &DeploymentId,
+            _service_name: &str,
+            _method_name: &str,
+        ) -> Option<Box<dyn Transcoder + Send>> {
+            None
+        }
+    }
 }
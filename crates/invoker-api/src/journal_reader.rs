@@ -11,7 +11,7 @@
 use bytestring::ByteString;
 use futures::Stream;
 use restate_types::identifiers::{DeploymentId, FullInvocationId};
-use restate_types::invocation::ServiceInvocationSpanContext;
+use restate_types::invocation::{Header, ServiceInvocationSpanContext};
 use restate_types::journal::raw::PlainRawEntry;
 use restate_types::journal::EntryIndex;
 use std::future::Future;
@@ -23,6 +23,11 @@ pub struct JournalMetadata {
     pub span_context: ServiceInvocationSpanContext,
     pub method: ByteString,
     pub deployment_id: Option<DeploymentId>,
+    pub headers: Vec<Header>,
+    /// If set, the invocation is failed after this number of attempts.
+    pub max_attempts: Option<u32>,
+    /// Number of failed attempts, as last stored by the partition processor.
+    pub attempts: u32,
 }
 
 impl JournalMetadata {
@@ -31,14 +36,24 @@ impl JournalMetadata {
         span_context: ServiceInvocationSpanContext,
         method: ByteString,
         deployment_id: Option<DeploymentId>,
+        headers: Vec<Header>,
     ) -> Self {
         Self {
             deployment_id,
             method,
             span_context,
             length,
+            headers,
+            max_attempts: None,
+            attempts: 0,
         }
     }
+
+    pub fn with_attempts(mut self, max_attempts: Option<u32>, attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self.attempts = attempts;
+        self
+    }
 }
 
 pub trait JournalReader {
@@ -74,6 +89,7 @@ pub mod mocks {
                     ServiceInvocationSpanContext::empty(),
                     "test".into(),
                     None,
+                    vec![],
                 ),
                 futures::stream::empty(),
             ))
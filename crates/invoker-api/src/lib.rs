@@ -14,6 +14,7 @@ mod handle;
 pub mod journal_reader;
 pub mod state_reader;
 pub mod status_handle;
+pub mod transcoder;
 
 pub use effects::*;
 pub use entry_enricher::EntryEnricher;
@@ -21,3 +22,4 @@ pub use handle::*;
 pub use journal_reader::{JournalMetadata, JournalReader};
 pub use state_reader::{EagerState, StateReader};
 pub use status_handle::{InvocationErrorReport, InvocationStatusReport, StatusHandle};
+pub use transcoder::{Transcoder, TranscoderResolver};
@@ -0,0 +1,48 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_types::errors::InvocationError;
+use restate_types::identifiers::{DeploymentId, EntryIndex};
+use restate_types::journal::raw::PlainRawEntry;
+use restate_types::journal::Completion;
+
+/// Transcodes the payloads exchanged with a deployment which doesn't use protobuf messages as payloads.
+/// The journal always stores the payloads as protobuf messages.
+///
+/// A transcoder is used by a single invocation attempt, and sees the entries in the journal order.
+pub trait Transcoder {
+    /// Transcode the entry at `entry_index` before sending it to the deployment.
+    fn encode_entry(
+        &mut self,
+        entry_index: EntryIndex,
+        entry: PlainRawEntry,
+    ) -> Result<PlainRawEntry, InvocationError>;
+
+    /// Transcode the entry at `entry_index` received from the deployment, before enriching it.
+    fn decode_entry(
+        &mut self,
+        entry_index: EntryIndex,
+        entry: PlainRawEntry,
+    ) -> Result<PlainRawEntry, InvocationError>;
+
+    /// Transcode the completion before sending it to the deployment.
+    fn encode_completion(&mut self, completion: Completion) -> Result<Completion, InvocationError>;
+}
+
+pub trait TranscoderResolver {
+    /// Resolve the transcoder of the invocation of `service_name`/`method_name` executed by the deployment `deployment_id`.
+    /// Returns `None` if the deployment exchanges protobuf messages.
+    fn resolve_transcoder(
+        &self,
+        deployment_id: &DeploymentId,
+        service_name: &str,
+        method_name: &str,
+    ) -> Option<Box<dyn Transcoder + Send>>;
+}
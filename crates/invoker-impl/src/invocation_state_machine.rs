@@ -23,6 +23,9 @@ use tokio::task::AbortHandle;
 pub(super) struct InvocationStateMachine {
     invocation_state: InvocationState,
     retry_iter: retries::RetryIter,
+    attempts: u32,
+    // Overrides the retry policy, if the invocation limits its attempts
+    max_attempts: Option<u32>,
 }
 
 /// This struct tracks which entries the invocation task generates,
@@ -131,9 +134,21 @@ impl InvocationStateMachine {
         Self {
             invocation_state: InvocationState::New,
             retry_iter: retry_policy.into_iter(),
+            attempts: 0,
+            max_attempts: None,
         }
     }
 
+    /// Limits the attempts, starting from the failed attempts stored by the partition processor.
+    pub(super) fn limit_attempts(&mut self, max_attempts: u32, attempts: u32) {
+        self.max_attempts = Some(max_attempts);
+        self.attempts = self.attempts.max(attempts);
+    }
+
+    pub(super) fn limits_attempts(&self) -> bool {
+        self.max_attempts.is_some()
+    }
+
     pub(super) fn start(
         &mut self,
         abort_handle: AbortHandle,
@@ -285,7 +300,15 @@ impl InvocationStateMachine {
                 *journal_tracker
             }
         };
-        let next_timer = self.retry_iter.next();
+        self.attempts += 1;
+        let next_timer = if self
+            .max_attempts
+            .is_some_and(|max_attempts| self.attempts >= max_attempts)
+        {
+            None
+        } else {
+            self.retry_iter.next()
+        };
 
         if next_timer.is_some() {
             self.invocation_state = InvocationState::WaitingRetry {
@@ -343,6 +366,29 @@ mod tests {
         check!(let InvocationState::WaitingRetry { .. } = invocation_state_machine.invocation_state);
     }
 
+    #[test]
+    fn handle_error_when_max_attempts_exhausted() {
+        let mut invocation_state_machine =
+            InvocationStateMachine::create(RetryPolicy::fixed_delay(Duration::from_secs(1), 10));
+        invocation_state_machine.limit_attempts(2, 0);
+
+        assert!(invocation_state_machine.handle_task_error().is_some());
+        invocation_state_machine.notify_retry_timer_fired();
+
+        // The second attempt is the last one, regardless of the retry policy
+        assert!(invocation_state_machine.handle_task_error().is_none());
+    }
+
+    #[test]
+    fn handle_error_when_stored_attempts_exhausted() {
+        let mut invocation_state_machine =
+            InvocationStateMachine::create(RetryPolicy::fixed_delay(Duration::from_secs(1), 10));
+        // Two attempts failed before the invoker restarted
+        invocation_state_machine.limit_attempts(3, 2);
+
+        assert!(invocation_state_machine.handle_task_error().is_none());
+    }
+
     #[test(tokio::test)]
     async fn handle_requires_ack() {
         let mut invocation_state_machine =
@@ -22,7 +22,8 @@ use opentelemetry::sdk::propagation::TraceContextPropagator;
 use opentelemetry_http::HeaderInjector;
 use restate_errors::warn_it;
 use restate_invoker_api::{
-    EagerState, EntryEnricher, InvokeInputJournal, JournalReader, StateReader,
+    EagerState, EntryEnricher, InvokeInputJournal, JournalReader, StateReader, Transcoder,
+    TranscoderResolver,
 };
 use restate_schema_api::deployment::{
     DeploymentMetadata, DeploymentResolver, DeploymentType, ProtocolType,
@@ -35,10 +36,10 @@ use restate_types::errors::InvocationError;
 use restate_types::identifiers::{
     DeploymentId, EntryIndex, FullInvocationId, PartitionLeaderEpoch,
 };
-use restate_types::invocation::ServiceInvocationSpanContext;
+use restate_types::invocation::{Header, ServiceInvocationSpanContext};
 use restate_types::journal::enriched::EnrichedRawEntry;
 use restate_types::journal::raw::PlainRawEntry;
-use restate_types::journal::EntryType;
+use restate_types::journal::{Completion, EntryType};
 use std::collections::HashSet;
 use std::error::Error;
 
@@ -97,6 +98,8 @@ pub(crate) enum InvocationTaskError {
     ResponseTimeout,
     #[error("cannot process received entry at index {0} of type {1}: {2}")]
     EntryEnrichment(EntryIndex, EntryType, #[source] InvocationError),
+    #[error("cannot transcode the payloads of the entry at index {0}: {1}")]
+    EntryTranscoding(EntryIndex, #[source] InvocationError),
     #[error(transparent)]
     #[code(restate_errors::RT0007)]
     ErrorMessageReceived(#[from] InvocationError),
@@ -114,6 +117,14 @@ impl InvokerError for InvocationTaskError {
     fn to_invocation_error(&self) -> InvocationError {
         match self {
             InvocationTaskError::ErrorMessageReceived(e) => e.clone(),
+            InvocationTaskError::EntryTranscoding(entry_index, e) => InvocationError::new(
+                e.code(),
+                format!(
+                    "Error when transcoding the payloads of entry {}: {}",
+                    entry_index,
+                    e.message()
+                ),
+            ),
             InvocationTaskError::EntryEnrichment(entry_index, entry_type, e) => {
                 let msg = format!(
                     "Error when processing entry {} of type {}: {}",
@@ -180,6 +191,11 @@ pub(super) enum InvocationTaskOutputInner {
     Closed,
     Suspended(HashSet<EntryIndex>),
     Failed(InvocationTaskError),
+    /// The failure policy of the invocation limits the number of attempts to execute it.
+    MaxAttempts {
+        max_attempts: u32,
+        attempts: u32,
+    },
 }
 
 impl From<InvocationTaskError> for InvocationTaskOutputInner {
@@ -211,6 +227,8 @@ pub(super) struct InvocationTask<JR, SR, EE, DMR> {
     // Encoder/Decoder
     encoder: Encoder,
     decoder: Decoder,
+    /// Set if the deployment doesn't exchange protobuf payloads
+    transcoder: Option<Box<dyn Transcoder + Send>>,
 
     // Task state
     next_journal_index: EntryIndex,
@@ -251,7 +269,7 @@ where
     <JR as JournalReader>::JournalStream: Unpin + Send + 'static,
     SR: StateReader + Clone + Send + Sync + 'static,
     <SR as StateReader>::StateIter: Send,
-    EE: EntryEnricher,
+    EE: EntryEnricher + TranscoderResolver,
     DMR: DeploymentResolver,
 {
     #[allow(clippy::too_many_arguments)]
@@ -288,6 +306,7 @@ where
             invoker_rx,
             encoder: Encoder::new(protocol_version),
             decoder: Decoder::new(message_size_warning, message_size_limit),
+            transcoder: None,
         }
     }
 
@@ -357,6 +376,17 @@ where
         let ((journal_metadata, journal_stream), state_iter) =
             shortcircuit!(tokio::try_join!(read_journal_future, read_state_future));
 
+        if let Some(max_attempts) = journal_metadata.max_attempts {
+            let _ = self.invoker_tx.send(InvocationTaskOutput {
+                partition: self.partition,
+                full_invocation_id: self.full_invocation_id.clone(),
+                inner: InvocationTaskOutputInner::MaxAttempts {
+                    max_attempts,
+                    attempts: journal_metadata.attempts,
+                },
+            });
+        }
+
         // Resolve the deployment metadata
         let (deployment, deployment_changed) =
             if let Some(deployment_id) = journal_metadata.deployment_id {
@@ -368,12 +398,17 @@ where
                     .ok_or_else(|| InvocationTaskError::UnknownDeployment(deployment_id)));
                 (deployment_metadata, /* has_changed= */ false)
             } else {
-                // We can choose the freshest deployment for the latest revision
-                // of the registered service.
+                // We can choose the freshest deployment for the revision selected by the
+                // routing of the registered service. The random bits of the invocation uuid
+                // spread the invocations over the routing weights.
+                let routing_key =
+                    u128::from_be_bytes(self.full_invocation_id.invocation_uuid.to_bytes()) as u64;
                 let deployment = shortcircuit!(self
                     .deployment_metadata_resolver
-                    .resolve_latest_deployment_for_service(
-                        &self.full_invocation_id.service_id.service_name
+                    .resolve_deployment_for_invocation(
+                        &self.full_invocation_id.service_id.service_name,
+                        routing_key,
+                        &journal_metadata.headers
                     )
                     .ok_or(InvocationTaskError::NoDeploymentForService));
                 (deployment, /* has_changed= */ true)
@@ -385,6 +420,12 @@ where
             inner: InvocationTaskOutputInner::SelectedDeployment(deployment.id, deployment_changed),
         });
 
+        self.transcoder = self.entry_enricher.resolve_transcoder(
+            &deployment.id,
+            &self.full_invocation_id.service_id.service_name,
+            &journal_metadata.method,
+        );
+
         // Figure out the protocol type. Force RequestResponse if inactivity_timeout is zero
         let protocol_type = if self.inactivity_timeout.is_zero() {
             ProtocolType::RequestResponse
@@ -431,8 +472,13 @@ where
         // Prepare the request and send start message
         let (mut http_stream_tx, request) = self.prepare_request(path, deployment.metadata);
         shortcircuit!(
-            self.write_start(&mut http_stream_tx, journal_size, state_iter)
-                .await
+            self.write_start(
+                &mut http_stream_tx,
+                journal_size,
+                state_iter,
+                journal_metadata.headers
+            )
+            .await
         );
 
         // Initialize the response stream state
@@ -495,6 +541,7 @@ where
                 opt_je = journal_stream.next() => {
                     match opt_je {
                         Some(je) => {
+                            let je = shortcircuit!(self.encode_entry(je));
                             shortcircuit!(self.write(http_stream_tx, ProtocolMessage::UnparsedEntry(je)).await);
                             self.next_journal_index += 1;
                         },
@@ -522,6 +569,7 @@ where
                     match opt_completion {
                         Some(Notification::Completion(completion)) => {
                             trace!("Sending the completion to the wire");
+                            let completion = shortcircuit!(self.encode_completion(completion));
                             shortcircuit!(self.write(&mut http_stream_tx, completion.into()).await);
                         },
                         Some(Notification::Ack(entry_index)) => {
@@ -590,6 +638,7 @@ where
         http_stream_tx: &mut Sender,
         journal_size: u32,
         state_entries: EagerState<I>,
+        headers: Vec<Header>,
     ) -> Result<(), InvocationTaskError> {
         let is_partial = state_entries.is_partial();
 
@@ -602,6 +651,7 @@ where
                 journal_size,
                 is_partial,
                 state_entries,
+                headers,
             ),
         )
         .await
@@ -679,9 +729,10 @@ where
             ProtocolMessage::End(_) => TerminalLoopState::Closed,
             ProtocolMessage::UnparsedEntry(entry) => {
                 let entry_type = entry.header().as_entry_type();
+                let entry = shortcircuit!(self.decode_entry(entry));
                 let enriched_entry = shortcircuit!(self
                     .entry_enricher
-                    .enrich_entry(entry, parent_span_context)
+                    .enrich_entry(entry, &self.full_invocation_id, parent_span_context)
                     .map_err(|e| InvocationTaskError::EntryEnrichment(
                         self.next_journal_index,
                         entry_type,
@@ -704,6 +755,41 @@ where
         }
     }
 
+    // --- Transcoding of the payloads, see Transcoder
+
+    fn encode_entry(&mut self, entry: PlainRawEntry) -> Result<PlainRawEntry, InvocationTaskError> {
+        let entry_index = self.next_journal_index;
+        match &mut self.transcoder {
+            Some(transcoder) => transcoder
+                .encode_entry(entry_index, entry)
+                .map_err(|e| InvocationTaskError::EntryTranscoding(entry_index, e)),
+            None => Ok(entry),
+        }
+    }
+
+    fn decode_entry(&mut self, entry: PlainRawEntry) -> Result<PlainRawEntry, InvocationTaskError> {
+        let entry_index = self.next_journal_index;
+        match &mut self.transcoder {
+            Some(transcoder) => transcoder
+                .decode_entry(entry_index, entry)
+                .map_err(|e| InvocationTaskError::EntryTranscoding(entry_index, e)),
+            None => Ok(entry),
+        }
+    }
+
+    fn encode_completion(
+        &mut self,
+        completion: Completion,
+    ) -> Result<Completion, InvocationTaskError> {
+        let entry_index = completion.entry_index;
+        match &mut self.transcoder {
+            Some(transcoder) => transcoder
+                .encode_completion(completion)
+                .map_err(|e| InvocationTaskError::EntryTranscoding(entry_index, e)),
+            None => Ok(completion),
+        }
+    }
+
     fn prepare_request(
         &mut self,
         path: PathAndQuery,
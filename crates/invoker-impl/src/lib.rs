@@ -27,7 +27,7 @@ use metrics::counter;
 use restate_errors::warn_it;
 use restate_invoker_api::{
     Effect, EffectKind, EntryEnricher, InvocationErrorReport, InvokeInputJournal, JournalReader,
-    StateReader,
+    StateReader, TranscoderResolver,
 };
 use restate_queue::SegmentQueue;
 use restate_schema_api::deployment::DeploymentResolver;
@@ -111,7 +111,7 @@ where
     <JR as JournalReader>::JournalStream: Unpin + Send + 'static,
     SR: StateReader + Clone + Send + Sync + 'static,
     <SR as StateReader>::StateIter: Send,
-    EE: EntryEnricher + Clone + Send + 'static,
+    EE: EntryEnricher + TranscoderResolver + Clone + Send + 'static,
     DMR: DeploymentResolver + Clone + Send + 'static,
 {
     fn start_invocation_task(
@@ -123,17 +123,29 @@ where
         input_journal: InvokeInputJournal,
         task_pool: &mut JoinSet<()>,
     ) -> AbortHandle {
+        // The configuration of the service overrides the invoker options
+        let configuration = self
+            .deployment_metadata_resolver
+            .resolve_service_configuration(&fid.service_id.service_name)
+            .unwrap_or_default();
+
         task_pool.spawn(
             InvocationTask::new(
                 self.client.clone(),
                 partition,
                 fid,
                 0,
-                self.inactivity_timeout,
-                self.abort_timeout,
+                configuration
+                    .inactivity_timeout
+                    .map(Into::into)
+                    .unwrap_or(self.inactivity_timeout),
+                configuration
+                    .abort_timeout
+                    .map(Into::into)
+                    .unwrap_or(self.abort_timeout),
                 self.disable_eager_state,
                 self.message_size_warning,
-                self.message_size_limit,
+                configuration.message_size_limit.or(self.message_size_limit),
                 self.journal_reader.clone(),
                 self.state_reader.clone(),
                 self.entry_enricher.clone(),
@@ -217,7 +229,7 @@ where
     <JR as JournalReader>::JournalStream: Unpin + Send + 'static,
     SR: StateReader + Clone + Send + Sync + 'static,
     <SR as StateReader>::StateIter: Send,
-    EE: EntryEnricher + Clone + Send + 'static,
+    EE: EntryEnricher + TranscoderResolver + Clone + Send + 'static,
     EMR: DeploymentResolver + Clone + Send + 'static,
 {
     pub fn handle(&self) -> ChannelServiceHandle {
@@ -368,6 +380,9 @@ where
                     InvocationTaskOutputInner::Suspended(indexes) => {
                         self.handle_invocation_task_suspended(partition, full_invocation_id, indexes).await
                     }
+                    InvocationTaskOutputInner::MaxAttempts { max_attempts, attempts } => {
+                        self.handle_max_attempts(partition, full_invocation_id, max_attempts, attempts)
+                    }
                 };
             },
             timer = self.retry_timers.await_timer() => {
@@ -470,6 +485,38 @@ where
         .await;
     }
 
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            rpc.service = %full_invocation_id.service_id.service_name,
+            restate.invocation.id = %full_invocation_id,
+            restate.invoker.partition_leader_epoch = ?partition,
+        )
+    )]
+    fn handle_max_attempts(
+        &mut self,
+        partition: PartitionLeaderEpoch,
+        full_invocation_id: FullInvocationId,
+        max_attempts: u32,
+        attempts: u32,
+    ) {
+        if let Some((_, ism)) = self
+            .invocation_state_machine_manager
+            .resolve_invocation(partition, &full_invocation_id)
+        {
+            trace!(
+                "Limiting the invocation to {} attempts, {} already failed",
+                max_attempts,
+                attempts
+            );
+            ism.limit_attempts(max_attempts, attempts);
+        } else {
+            // If no state machine, this might be an event for an aborted invocation.
+            trace!("No state machine found for max attempts");
+        }
+    }
+
     #[instrument(
         level = "trace",
         skip_all,
@@ -808,6 +855,17 @@ where
                     humantime::format_duration(next_retry_timer_duration));
                 trace!("Invocation state: {:?}.", ism.invocation_state_debug());
                 let next_retry_at = SystemTime::now() + next_retry_timer_duration;
+                if ism.limits_attempts() {
+                    let _ = self
+                        .invocation_state_machine_manager
+                        .resolve_partition_sender(partition)
+                        .expect("Partition should be registered")
+                        .send(Effect {
+                            full_invocation_id: full_invocation_id.clone(),
+                            kind: EffectKind::AttemptFailed,
+                        })
+                        .await;
+                }
                 self.status_store.on_failure(
                     partition,
                     full_invocation_id.clone(),
@@ -22,7 +22,7 @@ use serde_with::serde_as;
 // Export schema types to be used by other crates without exposing the fact
 // that we are using proxying to restate-schema-api or restate-types
 use restate_schema_api::deployment::DeploymentType;
-pub use restate_schema_api::deployment::{DeploymentMetadata, ProtocolType};
+pub use restate_schema_api::deployment::{DeploymentMetadata, DeploymentState, ProtocolType};
 pub use restate_types::identifiers::{DeploymentId, LambdaARN};
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -113,7 +113,8 @@ pub enum RegisterDeploymentRequest {
         /// # Dry-run mode
         ///
         /// If `true`, discovery will run but the deployment will not be registered.
-        /// This is useful to see the impact of a new deployment before registering it.
+        /// This is useful to see the impact of a new deployment before registering it,
+        /// including the changes breaking the wire compatibility with the registered services.
         #[serde(default = "restate_serde_util::default::bool::<false>")]
         dry_run: bool,
     },
@@ -147,12 +148,23 @@ pub enum RegisterDeploymentRequest {
         /// # Dry-run mode
         ///
         /// If `true`, discovery will run but the deployment will not be registered.
-        /// This is useful to see the impact of a new deployment before registering it.
+        /// This is useful to see the impact of a new deployment before registering it,
+        /// including the changes breaking the wire compatibility with the registered services.
         #[serde(default = "restate_serde_util::default::bool::<false>")]
         dry_run: bool,
     },
 }
 
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifyDeploymentRequest {
+    /// # State
+    ///
+    /// Set to `draining` to stop routing new invocations to the deployment,
+    /// or to `active` to route new invocations to a draining deployment again.
+    pub state: DeploymentState,
+}
+
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceNameRevPair {
@@ -165,6 +177,13 @@ pub struct ServiceNameRevPair {
 pub struct RegisterDeploymentResponse {
     pub id: DeploymentId,
     pub services: Vec<ServiceMetadata>,
+    /// # Wire incompatibilities
+    ///
+    /// Changes of the registered services which break the Protobuf wire compatibility with their previous revision,
+    /// such as field renumbering, field type changes, key field changes and enum value removals.
+    /// Unless `force` is set, the registration fails if there's any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wire_incompatibilities: Vec<String>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -185,6 +204,18 @@ pub struct DeploymentResponse {
     ///
     /// List of services exposed by this deployment.
     pub services: Vec<ServiceNameRevPair>,
+
+    /// # State
+    ///
+    /// A draining deployment accepts no new invocations, and can be removed once no invocation is pinned to it.
+    #[serde(default)]
+    pub state: DeploymentState,
+
+    /// # Pinned invocations
+    ///
+    /// Number of in-flight invocations pinned to this deployment. Unset if the storage query is disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_invocations: Option<u64>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -199,4 +230,16 @@ pub struct DetailedDeploymentResponse {
     ///
     /// List of services exposed by this deployment.
     pub services: Vec<ServiceMetadata>,
+
+    /// # State
+    ///
+    /// A draining deployment accepts no new invocations, and can be removed once no invocation is pinned to it.
+    #[serde(default)]
+    pub state: DeploymentState,
+
+    /// # Pinned invocations
+    ///
+    /// Number of in-flight invocations pinned to this deployment. Unset if the storage query is disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_invocations: Option<u64>,
 }
@@ -9,6 +9,8 @@
 // by the Apache License, Version 2.0.
 
 pub mod deployments;
+pub mod manifest;
 pub mod methods;
+pub mod schemas;
 pub mod services;
 pub mod subscriptions;
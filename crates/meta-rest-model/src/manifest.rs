@@ -0,0 +1,185 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+
+use http::Uri;
+use restate_serde_util::SerdeableHeaderHashMap;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use super::deployments::{DeploymentId, ServiceNameRevPair};
+use restate_types::identifiers::SubscriptionId;
+
+/// Desired state of the registry. The registry is reconciled against it,
+/// registering the missing deployments and subscriptions, and updating the visibility of the services.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// # Deployments
+    ///
+    /// Deployments to register. A deployment is registered again if the discovered services differ from the registered ones.
+    #[serde(default)]
+    pub deployments: Vec<ManifestDeployment>,
+
+    /// # Services
+    ///
+    /// Visibility of the services exposed by the deployments.
+    #[serde(default)]
+    pub services: Vec<ManifestService>,
+
+    /// # Subscriptions
+    ///
+    /// Subscriptions to register. A subscription is identified by its source, sink and options.
+    #[serde(default)]
+    pub subscriptions: Vec<ManifestSubscription>,
+}
+
+#[serde_as]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestDeployment {
+    Http {
+        /// # Uri
+        ///
+        /// Uri to use to discover/invoke the http deployment.
+        #[serde_as(as = "serde_with::DisplayFromStr")]
+        #[cfg_attr(feature = "schema", schemars(with = "String"))]
+        uri: Uri,
+
+        /// # Additional headers
+        ///
+        /// Additional headers added to the discover/invoke requests to the deployment.
+        additional_headers: Option<SerdeableHeaderHashMap>,
+    },
+    Lambda {
+        /// # ARN
+        ///
+        /// ARN to use to discover/invoke the lambda deployment.
+        arn: String,
+
+        /// # Assume role ARN
+        ///
+        /// Optional ARN of a role to assume when invoking the addressed Lambda, to support role chaining
+        assume_role_arn: Option<String>,
+
+        /// # Additional headers
+        ///
+        /// Additional headers added to the discover/invoke requests to the deployment.
+        additional_headers: Option<SerdeableHeaderHashMap>,
+    },
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestService {
+    pub name: String,
+
+    /// # Public
+    ///
+    /// If true, the service can be invoked through the ingress.
+    pub public: bool,
+}
+
+#[serde_as]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestSubscription {
+    /// # Source
+    ///
+    /// Source uri, see the subscription creation for the accepted forms.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub source: Uri,
+
+    /// # Sink
+    ///
+    /// Sink uri, see the subscription creation for the accepted forms.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub sink: Uri,
+
+    /// # Options
+    ///
+    /// Additional options to apply to the subscription, see the subscription creation for the accepted options.
+    pub options: Option<HashMap<String, String>>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReconcileParams {
+    /// If true, the changes are computed but not applied.
+    pub dry_run: Option<bool>,
+    /// If true, the deployments missing from the manifest are drained and the subscriptions missing from the manifest are removed.
+    /// Deployments serving the latest revision of a service can't be drained, and are removed only if `force` is set.
+    pub prune: Option<bool>,
+    /// If true, the deployments whose discovered services changed are registered again, even if this breaks the in-flight invocations.
+    pub force: Option<bool>,
+}
+
+/// Changes needed to reconcile the registry against the manifest.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconcilePlan {
+    /// # Changes
+    ///
+    /// Changes in the order they're applied. Empty if the registry already matches the manifest.
+    pub changes: Vec<PlannedChange>,
+
+    /// # Applied
+    ///
+    /// True if the changes were applied, false in dry-run mode or if there are no changes.
+    pub applied: bool,
+
+    /// # Plan id
+    ///
+    /// Set in dry-run mode, to apply the shown changes without reconciling the manifest again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_id: Option<u64>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlannedChange {
+    RegisterDeployment {
+        id: DeploymentId,
+        address: String,
+        services: Vec<ServiceNameRevPair>,
+        /// Changes of the registered services which break the Protobuf wire compatibility with their previous revision.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        wire_incompatibilities: Vec<String>,
+    },
+    /// The deployment accepts no new invocations, and it's removed once drained.
+    DrainDeployment {
+        id: DeploymentId,
+        address: String,
+    },
+    /// The deployment serves the latest revision of some services, which are removed together with it.
+    RemoveDeployment {
+        id: DeploymentId,
+        address: String,
+    },
+    ModifyService {
+        name: String,
+        public: bool,
+    },
+    CreateSubscription {
+        id: SubscriptionId,
+        source: String,
+        sink: String,
+    },
+    DeleteSubscription {
+        id: SubscriptionId,
+        source: String,
+        sink: String,
+    },
+}
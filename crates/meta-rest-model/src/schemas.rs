@@ -0,0 +1,110 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use super::deployments::{DeploymentId, DeploymentMetadata, DeploymentState, ServiceNameRevPair};
+use super::services::{ServiceCallPolicy, ServiceConfiguration, ServiceRouting};
+use super::subscriptions::Subscription;
+use restate_types::identifiers::SubscriptionId;
+
+/// Version of the archive format produced by the export.
+pub const SCHEMAS_ARCHIVE_VERSION: u32 = 1;
+
+/// Archive of the schema registry, used to back it up or to move it to another cluster.
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemasArchive {
+    /// # Version
+    ///
+    /// Version of the archive format.
+    pub version: u32,
+
+    /// # Deployments
+    ///
+    /// Registered deployments, sorted by creation time.
+    pub deployments: Vec<ArchivedDeployment>,
+
+    /// # Services
+    ///
+    /// Settings of the registered services.
+    pub services: Vec<ArchivedService>,
+
+    /// # Subscriptions
+    ///
+    /// Registered subscriptions.
+    pub subscriptions: Vec<Subscription>,
+}
+
+#[serde_as]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedDeployment {
+    pub id: DeploymentId,
+
+    pub metadata: DeploymentMetadata,
+
+    #[serde(default)]
+    pub state: DeploymentState,
+
+    /// # Services
+    ///
+    /// List of services exposed by this deployment.
+    pub services: Vec<ServiceNameRevPair>,
+
+    /// # Descriptor pool
+    ///
+    /// Protobuf descriptor pool of the deployment, serialized as protobuf type google.protobuf.FileDescriptorSet and encoded in base64.
+    #[serde_as(as = "serde_with::base64::Base64")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub descriptor_pool: Bytes,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedService {
+    pub name: String,
+
+    /// # Public
+    ///
+    /// If true, the service can be invoked through the ingress.
+    pub public: bool,
+
+    #[serde(default)]
+    pub routing: ServiceRouting,
+
+    #[serde(default)]
+    pub configuration: ServiceConfiguration,
+
+    #[serde(default)]
+    pub call_policy: ServiceCallPolicy,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSchemasResponse {
+    /// # Deployments
+    ///
+    /// Ids of the imported deployments. Deployments already registered are not included.
+    pub deployments: Vec<DeploymentId>,
+
+    /// # Subscriptions
+    ///
+    /// Ids of the imported subscriptions. Subscriptions already registered are not included.
+    pub subscriptions: Vec<SubscriptionId>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportSchemasParams {
+    pub force: Option<bool>,
+}
@@ -14,7 +14,10 @@ use std::collections::HashMap;
 
 // Export schema types to be used by other crates without exposing the fact
 // that we are using proxying to restate-schema-api or restate-types
-pub use restate_schema_api::service::{InstanceType, MethodMetadata, ServiceMetadata};
+pub use restate_schema_api::service::{
+    HeaderPin, InstanceType, MethodMetadata, ServiceCallPolicy, ServiceConfiguration,
+    ServiceMetadata, ServiceRouting,
+};
 pub use restate_types::identifiers::ServiceRevision;
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -30,7 +33,40 @@ pub struct ModifyServiceRequest {
     ///
     /// If true, the service can be invoked through the ingress.
     /// If false, the service can be invoked only from another Restate service.
-    pub public: bool,
+    /// If unset, the visibility of the service is left unchanged.
+    #[serde(default)]
+    pub public: Option<bool>,
+
+    /// # Configuration
+    ///
+    /// Replaces the configuration of the service.
+    /// If unset, the configuration of the service is left unchanged.
+    #[serde(default)]
+    pub configuration: Option<ServiceConfiguration>,
+
+    /// # Call policy
+    ///
+    /// Replaces the policy restricting which services can invoke the service.
+    /// If unset, the call policy of the service is left unchanged.
+    #[serde(default)]
+    pub call_policy: Option<ServiceCallPolicy>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifyServiceRoutingRequest {
+    /// # Weights
+    ///
+    /// Percentage of the new invocations routed to each revision of the service.
+    /// The remaining percentage is routed to the latest revision.
+    /// The weights must sum up to at most 100.
+    #[serde(default)]
+    pub weights: std::collections::BTreeMap<ServiceRevision, u8>,
+    /// # Header pins
+    ///
+    /// New invocations carrying a matching header are routed to the pinned revision, regardless of the weights.
+    #[serde(default)]
+    pub header_pins: Vec<HeaderPin>,
 }
 
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
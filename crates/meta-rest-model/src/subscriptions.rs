@@ -17,7 +17,7 @@ use serde_with::serde_as;
 
 // Export schema types to be used by other crates without exposing the fact
 // that we are using proxying to restate-schema-api or restate-types
-pub use restate_schema_api::subscription::{ListSubscriptionFilter, Subscription};
+pub use restate_schema_api::subscription::{ListSubscriptionFilter, OffsetReset, Subscription};
 
 #[serde_as]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -29,6 +29,8 @@ pub struct CreateSubscriptionRequest {
     /// Source uri. Accepted forms:
     ///
     /// * `kafka://<cluster_name>/<topic_name>`, e.g. `service://my-cluster/my-topic`
+    /// * `nats://<cluster_name>/<subject>`, e.g. `nats://my-cluster/orders.created`
+    /// * `file://localhost/<path>`, e.g. `file://localhost/var/events`. Every line of the file, or of the files within the directory, is an event
     #[serde_as(as = "serde_with::DisplayFromStr")]
     #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub source: Uri,
@@ -42,10 +44,58 @@ pub struct CreateSubscriptionRequest {
     pub sink: Uri,
     /// # Options
     ///
-    /// Additional options to apply to the subscription.
+    /// Additional options to apply to the subscription. Besides the source specific options, accepted options are:
+    ///
+    /// * `filter`: expression selecting the events to dispatch, e.g. `headers["type"] == "order.created" && !key.startsWith("test-")`.
+    ///   Fields are `key`, `ordering_key`, `headers["<name>"]` and `attributes["<name>"]`, compared with `==`, `!=`, `.startsWith()`, `.endsWith()` and `.contains()`,
+    ///   and combined with `&&`, `||` and `!`. `"<name>" in headers` checks the presence of a header.
+    /// * `route.<name>.filter` and `route.<name>.sink`: events matching the route filter are dispatched to the route sink, rather than to the subscription sink.
+    ///   Routes are evaluated in the lexicographic order of their names, and the first matching one is used.
+    /// * `max.attempts`: maximum number of attempts to process an event. Once exhausted, the event is given up,
+    ///   so the following events with the same key can be processed. By default, the processing is retried until it succeeds.
+    /// * `dead.letter.topic`: Kafka topic where the given up events are published, otherwise they're skipped.
+    ///   The record value is the input of the failed invocation, and the headers `restate.invocation.id`, `restate.error.code`
+    ///   and `restate.error.message` describe the failure.
+    /// * `dead.letter.cluster`: Kafka cluster of the dead letter topic. Defaults to the source cluster for Kafka sources.
     pub options: Option<HashMap<String, String>>,
 }
 
+#[serde_as]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateSubscriptionRequest {
+    /// # Sink
+    ///
+    /// If set, replaces the sink uri. See [`CreateSubscriptionRequest::sink`] for the accepted forms.
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    #[serde(default)]
+    pub sink: Option<Uri>,
+    /// # Options
+    ///
+    /// Options to merge with the existing subscription options.
+    /// An empty `filter`, `route.<name>.sink`, `max.attempts` or `dead.letter.topic` removes respectively
+    /// the filter, the route, the attempts limit or the dead letter topic.
+    #[serde(default)]
+    pub options: Option<HashMap<String, String>>,
+    /// # Paused
+    ///
+    /// If true, the subscription stops consuming events, while retaining its consumed offsets.
+    /// If false, the subscription resumes consuming events.
+    #[serde(default)]
+    pub paused: Option<bool>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetSubscriptionOffsetsRequest {
+    /// # To
+    ///
+    /// Position to reset the offsets to: `"earliest"`, `"latest"`, or `{"timestamp": <millis>}`
+    /// to replay the events starting from the given unix timestamp in milliseconds.
+    pub to: OffsetReset,
+}
+
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SubscriptionResponse {
@@ -53,6 +103,39 @@ pub struct SubscriptionResponse {
     pub source: String,
     pub sink: String,
     pub options: HashMap<String, String>,
+    /// # Paused
+    ///
+    /// If true, the subscription is not consuming events.
+    #[serde(default)]
+    pub paused: bool,
+    /// # Filter
+    ///
+    /// Expression selecting the dispatched events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// # Routes
+    ///
+    /// Routes to alternative sinks, in evaluation order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<SubscriptionRouteResponse>,
+    /// # Max attempts
+    ///
+    /// Maximum number of attempts to process an event, before giving it up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// # Dead letter topic
+    ///
+    /// Kafka topic where the given up events are published, as `kafka://<cluster_name>/<topic_name>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter_topic: Option<String>,
+}
+
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubscriptionRouteResponse {
+    pub name: String,
+    pub filter: String,
+    pub sink: String,
 }
 
 impl From<Subscription> for SubscriptionResponse {
@@ -62,6 +145,25 @@ impl From<Subscription> for SubscriptionResponse {
             source: value.source().to_string(),
             sink: value.sink().to_string(),
             options: value.metadata().clone(),
+            paused: value.paused(),
+            filter: value.filter().map(str::to_owned),
+            routes: value
+                .routes()
+                .iter()
+                .map(|route| SubscriptionRouteResponse {
+                    name: route.name.clone(),
+                    filter: route.filter.clone(),
+                    sink: route.sink.to_string(),
+                })
+                .collect(),
+            max_attempts: value.failure_policy().max_attempts,
+            dead_letter_topic: value
+                .failure_policy()
+                .dead_letter
+                .as_ref()
+                .map(|dead_letter| {
+                    format!("kafka://{}/{}", dead_letter.cluster, dead_letter.topic)
+                }),
         }
     }
 }
@@ -27,10 +27,22 @@ pub enum Error {
     #[error(transparent)]
     #[code(unknown)]
     SchemaRegistry(#[from] SchemasUpdateError),
+    #[error("unsupported schemas archive version {0}")]
+    #[code(unknown)]
+    UnsupportedSchemasArchiveVersion(u32),
+    #[error("unknown reconcile plan {0}, compute the plan again")]
+    #[code(unknown)]
+    UnknownReconcilePlan(u64),
+    #[error("the schema registry changed since computing the reconcile plan {0}")]
+    #[code(unknown)]
+    OutdatedReconcilePlan(u64),
     #[error("meta closed")]
     #[code(unknown)]
     MetaClosed,
     #[error("request aborted because the client went away")]
     #[code(unknown)]
     RequestAborted,
+    #[error(transparent)]
+    #[code(unknown)]
+    Worker(#[from] restate_worker_api::Error),
 }
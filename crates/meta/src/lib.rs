@@ -9,6 +9,8 @@
 // by the Apache License, Version 2.0.
 
 mod error;
+mod reconcile;
+mod schemas_archive;
 mod service;
 mod storage;
 
@@ -21,6 +23,7 @@ pub use restate_service_client::{
     Options as ServiceClientOptions, OptionsBuilder as ServiceClientOptionsBuilder,
     OptionsBuilderError as LambdaClientOptionsBuilderError,
 };
+pub use schemas_archive::export_schemas;
 pub use service::{ApplyMode, Force, MetaHandle, MetaService};
 pub use storage::{FileMetaStorage, MetaStorage};
 
@@ -50,6 +53,12 @@ pub struct Options {
     /// Root path for Meta storage.
     storage_path: String,
 
+    /// # Storage compaction threshold
+    ///
+    /// Number of updates stored since the latest compaction, after which the stored updates
+    /// are replaced by a snapshot of the schema registry. Set to 0 to disable the compaction.
+    storage_compaction_threshold: usize,
+
     service_client: ServiceClientOptions,
 }
 
@@ -57,6 +66,7 @@ impl Default for Options {
     fn default() -> Self {
         Self {
             storage_path: "target/meta/".to_string(),
+            storage_compaction_threshold: 100,
             service_client: Default::default(),
         }
     }
@@ -72,7 +82,7 @@ impl Options {
         let client = self.service_client.build(AssumeRoleCacheMode::None);
         Ok(MetaService::new(
             schemas.clone(),
-            FileMetaStorage::new(self.storage_path.into())?,
+            FileMetaStorage::new(self.storage_path.into(), self.storage_compaction_threshold)?,
             // Total duration roughly 66 seconds
             RetryPolicy::exponential(
                 Duration::from_millis(100),
@@ -84,3 +94,64 @@ impl Options {
         ))
     }
 }
+
+// Contains some mocks we use in unit tests in this crate
+#[cfg(test)]
+mod mocks {
+    use http::Uri;
+    use restate_pb::mocks::{DESCRIPTOR_POOL, GREETER_SERVICE_NAME};
+    use restate_schema_api::deployment::Deployment;
+    use restate_schema_api::subscription::{Subscription, SubscriptionValidator};
+    use restate_schema_impl::Schemas;
+    use std::convert::Infallible;
+
+    /// Accepts every subscription as it is.
+    pub(super) struct NoopValidator;
+
+    impl SubscriptionValidator for NoopValidator {
+        type Error = Infallible;
+
+        fn validate(&self, subscription: Subscription) -> Result<Subscription, Self::Error> {
+            Ok(subscription)
+        }
+    }
+
+    /// Source and sink of a Kafka subscription to the `Greet` method of the greeter service.
+    pub(super) fn kafka_subscription_uris() -> (Uri, Uri) {
+        (
+            "kafka://my-cluster/my-topic".parse().unwrap(),
+            format!("service://{}/Greet", GREETER_SERVICE_NAME)
+                .parse()
+                .unwrap(),
+        )
+    }
+
+    /// Registers a mock deployment at the given uri, serving the greeter service.
+    pub(super) fn register_mock_deployment(schemas: &Schemas, uri: &str) -> Deployment {
+        let deployment = Deployment::mock_with_uri(uri);
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment.id),
+                        deployment.metadata.clone(),
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR_POOL.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        deployment
+    }
+
+    /// Adds the Kafka subscription of [`kafka_subscription_uris`].
+    pub(super) fn add_kafka_subscription(schemas: &Schemas) -> Subscription {
+        let (source, sink) = kafka_subscription_uris();
+        let (subscription, add_subscription) = schemas
+            .compute_add_subscription(None, source, sink, None, NoopValidator)
+            .unwrap();
+        schemas.apply_updates(vec![add_subscription]).unwrap();
+        subscription
+    }
+}
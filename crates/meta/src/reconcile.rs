@@ -0,0 +1,414 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Reconciliation of the schema registry against a manifest.
+
+use super::error::Error;
+use super::service::{DiscoveredDescriptors, Force};
+
+use std::collections::HashSet;
+
+use restate_meta_rest_model::deployments::ServiceNameRevPair;
+use restate_meta_rest_model::manifest::{ManifestService, ManifestSubscription, PlannedChange};
+use restate_schema_api::deployment::{DeploymentMetadata, DeploymentResolver};
+use restate_schema_api::service::ServiceMetadataResolver;
+use restate_schema_api::subscription::{Subscription, SubscriptionResolver, SubscriptionValidator};
+use restate_schema_impl::{Schemas, SchemasUpdateCommand, SchemasUpdateError};
+use restate_types::identifiers::SubscriptionId;
+
+/// Changes reconciling the registry against a manifest, together with the commands applying them.
+pub(crate) struct ReconcileChanges {
+    pub(crate) changes: Vec<PlannedChange>,
+    pub(crate) update_commands: Vec<SchemasUpdateCommand>,
+    pub(crate) created_subscriptions: Vec<Subscription>,
+    pub(crate) deleted_subscriptions: Vec<SubscriptionId>,
+}
+
+/// Compute the changes reconciling the registry against the discovered deployments, the services visibility and the subscriptions.
+///
+/// When pruning, the deployments missing from the manifest are drained. Draining the only deployment serving the
+/// latest revision of a service is not possible, hence such deployments are removed together with their services
+/// only if `force` is set, otherwise the reconciliation fails.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compute_reconcile_changes<V: SubscriptionValidator>(
+    schemas: &Schemas,
+    deployments: Vec<(DeploymentMetadata, DiscoveredDescriptors)>,
+    services: Vec<ManifestService>,
+    subscriptions: Vec<ManifestSubscription>,
+    prune: bool,
+    force: Force,
+    validator: impl Fn() -> V,
+) -> Result<ReconcileChanges, Error> {
+    // As for the import, the commands are computed against a fork of the registry,
+    // which is updated only if the whole manifest can be reconciled.
+    let fork = schemas.fork();
+    let mut update_commands = vec![];
+    let mut changes = vec![];
+    let mut compute = |commands: Vec<SchemasUpdateCommand>| -> Result<(), Error> {
+        fork.apply_updates(commands.clone())?;
+        update_commands.extend(commands);
+        Ok(())
+    };
+
+    let mut desired_addresses = HashSet::new();
+    for (deployment_metadata, discovered_metadata) in deployments {
+        let address = deployment_metadata.ty.normalized_address();
+        desired_addresses.insert(address.clone());
+
+        if let Some((existing_deployment, existing_services)) = fork
+            .get_deployments()
+            .into_iter()
+            .find(|(deployment, _)| deployment.metadata.ty.normalized_address() == address)
+        {
+            let mut existing_services: Vec<_> = existing_services
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            existing_services.sort();
+            let mut discovered_services = discovered_metadata.services.clone();
+            discovered_services.sort();
+            let same_descriptor_pool = fork
+                .get_deployment_descriptor_pool(&existing_deployment.id)
+                .is_some_and(|descriptor_pool| {
+                    descriptor_pool == discovered_metadata.descriptor_pool.encode_to_vec()
+                });
+            if existing_services == discovered_services && same_descriptor_pool {
+                continue;
+            }
+        }
+
+        let wire_incompatibilities = fork.compute_wire_incompatibilities(
+            discovered_metadata.services.clone(),
+            &discovered_metadata.descriptor_pool,
+        )?;
+        let address = deployment_metadata.address_display().to_string();
+        let commands = fork.compute_new_deployment(
+            None, /* requested_deployment_id */
+            deployment_metadata,
+            discovered_metadata.services,
+            discovered_metadata.descriptor_pool,
+            force.force_enabled(),
+        )?;
+        for command in &commands {
+            if let SchemasUpdateCommand::InsertDeployment {
+                deployment_id,
+                services,
+                ..
+            } = command
+            {
+                changes.push(PlannedChange::RegisterDeployment {
+                    id: *deployment_id,
+                    address: address.clone(),
+                    services: services
+                        .iter()
+                        .map(|svc| ServiceNameRevPair {
+                            name: svc.name.clone(),
+                            revision: svc.revision,
+                        })
+                        .collect(),
+                    wire_incompatibilities: wire_incompatibilities
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                });
+            }
+        }
+        compute(commands)?;
+    }
+
+    for service in services {
+        let public = fork
+            .is_service_public(&service.name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(service.name.clone()))?;
+        if public != service.public {
+            compute(vec![
+                fork.compute_modify_service(service.name.clone(), service.public)?
+            ])?;
+            changes.push(PlannedChange::ModifyService {
+                name: service.name,
+                public: service.public,
+            });
+        }
+    }
+
+    if prune {
+        for (deployment, _) in fork.get_deployments() {
+            if desired_addresses.contains(&deployment.metadata.ty.normalized_address())
+                || fork
+                    .get_deployment_state(&deployment.id)
+                    .is_some_and(|state| state.is_draining())
+            {
+                continue;
+            }
+            let address = deployment.metadata.address_display().to_string();
+
+            match fork.compute_drain_deployment(deployment.id) {
+                Ok(command) => {
+                    compute(vec![command])?;
+                    changes.push(PlannedChange::DrainDeployment {
+                        id: deployment.id,
+                        address,
+                    });
+                }
+                // Removing the only deployment serving the latest revision of a service removes the service as well
+                Err(SchemasUpdateError::DrainLatestDeployment(_, _)) if force.force_enabled() => {
+                    compute(fork.compute_remove_deployment(deployment.id)?)?;
+                    changes.push(PlannedChange::RemoveDeployment {
+                        id: deployment.id,
+                        address,
+                    });
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    let existing_subscriptions = fork.list_subscriptions(&[]);
+    let mut desired_subscriptions = HashSet::new();
+    let mut created_subscriptions = vec![];
+    for subscription in subscriptions {
+        let (sub, command) = fork.compute_add_subscription(
+            None,
+            subscription.source.clone(),
+            subscription.sink.clone(),
+            subscription.options,
+            validator(),
+        )?;
+        if let Some(existing) = existing_subscriptions
+            .iter()
+            .find(|existing| same_subscription_definition(existing, &sub))
+        {
+            desired_subscriptions.insert(existing.id());
+            continue;
+        }
+
+        compute(vec![command])?;
+        changes.push(PlannedChange::CreateSubscription {
+            id: sub.id(),
+            source: subscription.source.to_string(),
+            sink: subscription.sink.to_string(),
+        });
+        created_subscriptions.push(sub);
+    }
+
+    let mut deleted_subscriptions = vec![];
+    if prune {
+        for existing in &existing_subscriptions {
+            if desired_subscriptions.contains(&existing.id()) {
+                continue;
+            }
+            compute(vec![fork.compute_remove_subscription(existing.id())?])?;
+            changes.push(PlannedChange::DeleteSubscription {
+                id: existing.id(),
+                source: existing.source().to_string(),
+                sink: existing.sink().to_string(),
+            });
+            deleted_subscriptions.push(existing.id());
+        }
+    }
+
+    Ok(ReconcileChanges {
+        changes,
+        update_commands,
+        created_subscriptions,
+        deleted_subscriptions,
+    })
+}
+
+/// Returns true if the subscriptions differ only by their id and their consumption state.
+fn same_subscription_definition(a: &Subscription, b: &Subscription) -> bool {
+    a.source() == b.source()
+        && a.sink() == b.sink()
+        && a.metadata() == b.metadata()
+        && a.filter() == b.filter()
+        && a.routes() == b.routes()
+        && a.failure_policy() == b.failure_policy()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mocks::{kafka_subscription_uris, NoopValidator};
+    use restate_pb::mocks;
+    use restate_schema_api::deployment::{DeliveryOptions, ProtocolType};
+    use restate_test_util::{assert, assert_eq, let_assert};
+
+    fn discovered_deployment(
+        uri: &str,
+        services: &[&str],
+    ) -> (DeploymentMetadata, DiscoveredDescriptors) {
+        (
+            DeploymentMetadata::new_http(
+                uri.parse().unwrap(),
+                ProtocolType::BidiStream,
+                DeliveryOptions::default(),
+            ),
+            DiscoveredDescriptors {
+                services: services.iter().map(|svc| svc.to_string()).collect(),
+                descriptor_pool: mocks::DESCRIPTOR_POOL.clone(),
+            },
+        )
+    }
+
+    fn manifest_subscription() -> ManifestSubscription {
+        let (source, sink) = kafka_subscription_uris();
+        ManifestSubscription {
+            source,
+            sink,
+            options: None,
+        }
+    }
+
+    fn reconcile(
+        schemas: &Schemas,
+        deployments: Vec<(DeploymentMetadata, DiscoveredDescriptors)>,
+        subscriptions: Vec<ManifestSubscription>,
+        prune: bool,
+        force: Force,
+    ) -> Result<ReconcileChanges, Error> {
+        compute_reconcile_changes(
+            schemas,
+            deployments,
+            vec![],
+            subscriptions,
+            prune,
+            force,
+            || NoopValidator,
+        )
+    }
+
+    #[test]
+    fn plan_and_reconcile_again() {
+        let schemas = Schemas::default();
+
+        let reconcile_changes = reconcile(
+            &schemas,
+            vec![discovered_deployment(
+                "http://localhost:9080",
+                &[mocks::GREETER_SERVICE_NAME],
+            )],
+            vec![manifest_subscription()],
+            false,
+            Force::No,
+        )
+        .unwrap();
+        let_assert!(
+            [
+                PlannedChange::RegisterDeployment { services, .. },
+                PlannedChange::CreateSubscription { .. }
+            ] = reconcile_changes.changes.as_slice()
+        );
+        assert_eq!(services.len(), 1);
+        assert_eq!(reconcile_changes.created_subscriptions.len(), 1);
+
+        // Computing the plan doesn't modify the registry
+        assert!(schemas.get_deployments().is_empty());
+
+        schemas
+            .apply_updates(reconcile_changes.update_commands)
+            .unwrap();
+
+        // Once reconciled, there's nothing left to do
+        let reconcile_changes = reconcile(
+            &schemas,
+            vec![discovered_deployment(
+                "http://localhost:9080",
+                &[mocks::GREETER_SERVICE_NAME],
+            )],
+            vec![manifest_subscription()],
+            true,
+            Force::No,
+        )
+        .unwrap();
+        assert!(reconcile_changes.changes.is_empty());
+        assert!(reconcile_changes.update_commands.is_empty());
+    }
+
+    #[test]
+    fn prune_drains_deployments_missing_from_the_manifest() {
+        let schemas = Schemas::default();
+        schemas
+            .apply_updates(
+                reconcile(
+                    &schemas,
+                    vec![
+                        discovered_deployment(
+                            "http://localhost:9080",
+                            &[mocks::GREETER_SERVICE_NAME],
+                        ),
+                        discovered_deployment(
+                            "http://localhost:9081",
+                            &[mocks::GREETER_SERVICE_NAME],
+                        ),
+                    ],
+                    vec![manifest_subscription()],
+                    false,
+                    Force::No,
+                )
+                .unwrap()
+                .update_commands,
+            )
+            .unwrap();
+
+        // The deployment serving the latest revision is kept, while the other one is drained
+        let reconcile_changes = reconcile(
+            &schemas,
+            vec![discovered_deployment(
+                "http://localhost:9081",
+                &[mocks::GREETER_SERVICE_NAME],
+            )],
+            vec![],
+            true,
+            Force::No,
+        )
+        .unwrap();
+        let_assert!(
+            [
+                PlannedChange::DrainDeployment { address, .. },
+                PlannedChange::DeleteSubscription { .. }
+            ] = reconcile_changes.changes.as_slice()
+        );
+        assert!(address.contains("9080"));
+        assert_eq!(reconcile_changes.deleted_subscriptions.len(), 1);
+    }
+
+    #[test]
+    fn prune_latest_deployment_requires_force() {
+        let schemas = Schemas::default();
+        schemas
+            .apply_updates(
+                reconcile(
+                    &schemas,
+                    vec![discovered_deployment(
+                        "http://localhost:9080",
+                        &[mocks::GREETER_SERVICE_NAME],
+                    )],
+                    vec![],
+                    false,
+                    Force::No,
+                )
+                .unwrap()
+                .update_commands,
+            )
+            .unwrap();
+
+        let_assert!(
+            Err(Error::SchemaRegistry(
+                SchemasUpdateError::DrainLatestDeployment(_, _)
+            )) = reconcile(&schemas, vec![], vec![], true, Force::No)
+        );
+
+        let reconcile_changes = reconcile(&schemas, vec![], vec![], true, Force::Yes).unwrap();
+        let_assert!(
+            [PlannedChange::RemoveDeployment { .. }] = reconcile_changes.changes.as_slice()
+        );
+    }
+}
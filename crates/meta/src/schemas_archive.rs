@@ -0,0 +1,286 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Export and import of the schema registry.
+
+use super::error::Error;
+use super::service::Force;
+
+use std::collections::HashSet;
+
+use restate_meta_rest_model::deployments::ServiceNameRevPair;
+use restate_meta_rest_model::schemas::{
+    ArchivedDeployment, ArchivedService, SchemasArchive, SCHEMAS_ARCHIVE_VERSION,
+};
+use restate_schema_api::deployment::DeploymentResolver;
+use restate_schema_api::service::ServiceMetadataResolver;
+use restate_schema_api::subscription::{Subscription, SubscriptionResolver, SubscriptionValidator};
+use restate_schema_impl::{Schemas, SchemasUpdateCommand, SchemasUpdateError};
+use restate_types::identifiers::DeploymentId;
+
+/// Export the schema registry, including deployments, service settings, Protobuf descriptors and subscriptions.
+pub fn export_schemas(schemas: &Schemas) -> Result<SchemasArchive, Error> {
+    let mut deployments = Vec::new();
+    for (deployment, services) in schemas.get_deployments() {
+        let descriptor_pool = schemas
+            .get_deployment_descriptor_pool(&deployment.id)
+            .ok_or(SchemasUpdateError::UnknownDeployment(deployment.id))?;
+        deployments.push(ArchivedDeployment {
+            id: deployment.id,
+            state: schemas
+                .get_deployment_state(&deployment.id)
+                .unwrap_or_default(),
+            metadata: deployment.metadata,
+            services: services
+                .into_iter()
+                .map(|(name, revision)| ServiceNameRevPair { name, revision })
+                .collect(),
+            descriptor_pool,
+        });
+    }
+    deployments.sort_by_key(|deployment| deployment.metadata.created_at());
+
+    // Built-in services are not exposed by any deployment, and are not exported
+    let deployed_services: HashSet<_> = deployments
+        .iter()
+        .flat_map(|deployment| deployment.services.iter().map(|svc| svc.name.as_str()))
+        .collect();
+    let services = schemas
+        .list_services()
+        .into_iter()
+        .filter(|svc| deployed_services.contains(svc.name.as_str()))
+        .map(|svc| ArchivedService {
+            name: svc.name,
+            public: svc.public,
+            routing: svc.routing,
+            configuration: svc.configuration,
+            call_policy: svc.call_policy,
+        })
+        .collect();
+
+    Ok(SchemasArchive {
+        version: SCHEMAS_ARCHIVE_VERSION,
+        deployments,
+        services,
+        subscriptions: schemas.list_subscriptions(&[]),
+    })
+}
+
+/// Update commands importing an archive, together with the deployments and the subscriptions they register.
+pub(crate) struct ImportSchemas {
+    pub(crate) update_commands: Vec<SchemasUpdateCommand>,
+    pub(crate) deployments: Vec<DeploymentId>,
+    pub(crate) subscriptions: Vec<Subscription>,
+}
+
+/// Compute the commands to import the archive. Deployments and subscriptions already registered are skipped.
+pub(crate) fn compute_import_schemas<V: SubscriptionValidator>(
+    schemas: &Schemas,
+    archive: SchemasArchive,
+    force: Force,
+    validator: impl Fn() -> V,
+) -> Result<ImportSchemas, Error> {
+    if archive.version != SCHEMAS_ARCHIVE_VERSION {
+        return Err(Error::UnsupportedSchemasArchiveVersion(archive.version));
+    }
+
+    // Each command depends on the previous ones, so we compute them against a fork of the registry.
+    // The registry is updated only if the whole archive can be imported.
+    let fork = schemas.fork();
+    let mut update_commands = vec![];
+    let mut compute = |commands: Vec<SchemasUpdateCommand>| -> Result<(), Error> {
+        fork.apply_updates(commands.clone())?;
+        update_commands.extend(commands);
+        Ok(())
+    };
+
+    let mut deployments = archive.deployments;
+    deployments.sort_by_key(|deployment| deployment.metadata.created_at());
+    let mut imported_deployments = vec![];
+    for deployment in &deployments {
+        let commands = fork.compute_import_deployment(
+            deployment.id,
+            deployment.metadata.clone(),
+            deployment
+                .services
+                .iter()
+                .map(|svc| (svc.name.clone(), svc.revision))
+                .collect(),
+            deployment.descriptor_pool.clone(),
+            force.force_enabled(),
+        )?;
+        if !commands.is_empty() {
+            imported_deployments.push(deployment.id);
+        }
+        compute(commands)?;
+    }
+
+    for service in archive.services {
+        let existing = fork
+            .resolve_latest_service_metadata(&service.name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(service.name.clone()))?;
+        if existing.public != service.public {
+            compute(vec![
+                fork.compute_modify_service(service.name.clone(), service.public)?
+            ])?;
+        }
+        if existing.routing != service.routing {
+            compute(vec![fork.compute_modify_service_routing(
+                service.name.clone(),
+                service.routing,
+            )?])?;
+        }
+        if existing.configuration != service.configuration {
+            compute(vec![fork.compute_modify_service_configuration(
+                service.name.clone(),
+                service.configuration,
+            )?])?;
+        }
+        if existing.call_policy != service.call_policy {
+            compute(vec![fork.compute_modify_service_call_policy(
+                service.name,
+                service.call_policy,
+            )?])?;
+        }
+    }
+
+    for deployment in &deployments {
+        if deployment.state.is_draining()
+            && !fork
+                .get_deployment_state(&deployment.id)
+                .is_some_and(|state| state.is_draining())
+        {
+            compute(vec![fork.compute_drain_deployment(deployment.id)?])?;
+        }
+    }
+
+    let mut imported_subscriptions = vec![];
+    for subscription in archive.subscriptions {
+        if let Some(command) = fork.compute_import_subscription(subscription, validator())? {
+            if let SchemasUpdateCommand::AddSubscription(sub) = &command {
+                imported_subscriptions.push(sub.clone());
+            }
+            compute(vec![command])?;
+        }
+    }
+
+    Ok(ImportSchemas {
+        update_commands,
+        deployments: imported_deployments,
+        subscriptions: imported_subscriptions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mocks::{add_kafka_subscription, register_mock_deployment, NoopValidator};
+    use restate_pb::mocks;
+    use restate_schema_api::service::{ServiceConfiguration, ServiceRouting};
+    use restate_test_util::{assert_eq, let_assert};
+    use std::time::Duration;
+
+    fn schemas_to_export() -> Schemas {
+        let schemas = Schemas::default();
+
+        let deployment_1 = register_mock_deployment(&schemas, "http://localhost:9080");
+        register_mock_deployment(&schemas, "http://localhost:9081");
+
+        schemas
+            .apply_updates(vec![
+                schemas
+                    .compute_modify_service(mocks::GREETER_SERVICE_NAME.to_owned(), false)
+                    .unwrap(),
+                schemas
+                    .compute_modify_service_routing(
+                        mocks::GREETER_SERVICE_NAME.to_owned(),
+                        ServiceRouting {
+                            weights: [(1, 10), (2, 90)].into(),
+                            header_pins: vec![],
+                        },
+                    )
+                    .unwrap(),
+                schemas
+                    .compute_modify_service_configuration(
+                        mocks::GREETER_SERVICE_NAME.to_owned(),
+                        ServiceConfiguration {
+                            inactivity_timeout: Some(Duration::from_secs(5).into()),
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap(),
+            ])
+            .unwrap();
+        schemas
+            .apply_updates(vec![schemas
+                .compute_drain_deployment(deployment_1.id)
+                .unwrap()])
+            .unwrap();
+
+        add_kafka_subscription(&schemas);
+
+        schemas
+    }
+
+    fn import(schemas: &Schemas, archive: SchemasArchive) -> ImportSchemas {
+        let import = compute_import_schemas(schemas, archive, Force::No, || NoopValidator).unwrap();
+        schemas
+            .apply_updates(import.update_commands.clone())
+            .unwrap();
+        import
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let schemas = schemas_to_export();
+        let archive = export_schemas(&schemas).unwrap();
+        assert_eq!(archive.deployments.len(), 2);
+        assert_eq!(archive.subscriptions.len(), 1);
+
+        // Go through the wire format
+        let archive: SchemasArchive =
+            serde_json::from_slice(&serde_json::to_vec(&archive).unwrap()).unwrap();
+
+        let imported_schemas = Schemas::default();
+        let imported = import(&imported_schemas, archive);
+        assert_eq!(imported.deployments.len(), 2);
+        assert_eq!(imported.subscriptions.len(), 1);
+
+        assert_eq!(
+            serde_json::to_value(export_schemas(&imported_schemas).unwrap()).unwrap(),
+            serde_json::to_value(export_schemas(&schemas).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn reimport_is_noop() {
+        let schemas = schemas_to_export();
+        let archive = export_schemas(&schemas).unwrap();
+
+        let import =
+            compute_import_schemas(&schemas, archive, Force::No, || NoopValidator).unwrap();
+        assert!(import.update_commands.is_empty());
+        assert!(import.deployments.is_empty());
+        assert!(import.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn reject_unsupported_archive_version() {
+        let schemas = schemas_to_export();
+        let mut archive = export_schemas(&schemas).unwrap();
+        archive.version = SCHEMAS_ARCHIVE_VERSION + 1;
+
+        let_assert!(
+            Err(Error::UnsupportedSchemasArchiveVersion(_)) =
+                compute_import_schemas(&Schemas::default(), archive, Force::No, || NoopValidator)
+        );
+    }
+}
@@ -9,22 +9,30 @@
 // by the Apache License, Version 2.0.
 
 use super::error::Error;
+use super::reconcile::{compute_reconcile_changes, ReconcileChanges};
+use super::schemas_archive::{compute_import_schemas, ImportSchemas};
 use super::storage::MetaStorage;
 
 use std::collections::HashMap;
 use std::future::Future;
 
 use http::Uri;
+use prost_reflect::DescriptorPool;
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use restate_errors::warn_it;
 use restate_futures_util::command::{Command, UnboundedCommandReceiver, UnboundedCommandSender};
+use restate_meta_rest_model::manifest::{ManifestService, ManifestSubscription, ReconcilePlan};
+use restate_meta_rest_model::schemas::{ImportSchemasResponse, SchemasArchive};
 use restate_schema_api::deployment::{DeliveryOptions, DeploymentMetadata};
-use restate_schema_api::service::ServiceMetadata;
-use restate_schema_api::subscription::{Subscription, SubscriptionResolver};
-use restate_schema_impl::{Schemas, SchemasUpdateCommand};
-use restate_service_protocol::discovery::{DiscoverEndpoint, ServiceDiscovery};
+use restate_schema_api::service::{
+    ServiceCallPolicy, ServiceConfiguration, ServiceMetadata, ServiceRouting,
+};
+use restate_schema_api::subscription::{OffsetReset, Subscription, SubscriptionResolver};
+use restate_schema_impl::json_manifest::synthesize_descriptor_pool;
+use restate_schema_impl::{Schemas, SchemasUpdateCommand, WireIncompatibility};
+use restate_service_protocol::discovery::{DiscoverEndpoint, DiscoveredServices, ServiceDiscovery};
 use restate_types::identifiers::{DeploymentId, SubscriptionId};
 use restate_types::retries::RetryPolicy;
 use restate_worker_api::SubscriptionController;
@@ -69,33 +77,87 @@ enum MetaHandleRequest {
     },
     ModifyService {
         service_name: String,
-        public: bool,
+        public: Option<bool>,
+        configuration: Option<ServiceConfiguration>,
+        call_policy: Option<ServiceCallPolicy>,
+    },
+    ModifyServiceRouting {
+        service_name: String,
+        routing: ServiceRouting,
     },
     RemoveDeployment {
         deployment_id: DeploymentId,
     },
+    DrainDeployment {
+        deployment_id: DeploymentId,
+    },
+    UndrainDeployment {
+        deployment_id: DeploymentId,
+    },
     CreateSubscription {
         id: Option<SubscriptionId>,
         source: Uri,
         sink: Uri,
         metadata: Option<HashMap<String, String>>,
     },
+    UpdateSubscription {
+        subscription_id: SubscriptionId,
+        sink: Option<Uri>,
+        metadata: Option<HashMap<String, String>>,
+        paused: Option<bool>,
+    },
     DeleteSubscription {
         subscription_id: SubscriptionId,
     },
+    ResetSubscriptionOffsets {
+        subscription_id: SubscriptionId,
+        to: OffsetReset,
+    },
+    ImportSchemas {
+        archive: SchemasArchive,
+        force: Force,
+    },
+    Reconcile {
+        deployments: Vec<DiscoverEndpoint>,
+        services: Vec<ManifestService>,
+        subscriptions: Vec<ManifestSubscription>,
+        prune: bool,
+        force: Force,
+        apply_changes: ApplyMode,
+    },
+    ApplyReconcilePlan {
+        plan_id: u64,
+    },
 }
 
 pub struct DiscoverDeploymentResponse {
     pub deployment: DeploymentId,
     pub services: Vec<ServiceMetadata>,
+    /// Changes breaking the wire compatibility with the registered service revisions.
+    pub wire_incompatibilities: Vec<WireIncompatibility>,
+}
+
+/// Services exposed by a discovered deployment, with their descriptors.
+/// For deployments described by a JSON manifest, the descriptors are synthesized by the schema registry.
+pub(crate) struct DiscoveredDescriptors {
+    pub(crate) services: Vec<String>,
+    pub(crate) descriptor_pool: DescriptorPool,
 }
 
 enum MetaHandleResponse {
     DiscoverDeployment(Result<DiscoverDeploymentResponse, Error>),
     ModifyService(Result<(), Error>),
+    ModifyServiceRouting(Result<(), Error>),
     RemoveDeployment(Result<(), Error>),
+    DrainDeployment(Result<(), Error>),
+    UndrainDeployment(Result<(), Error>),
     CreateSubscription(Result<Subscription, Error>),
+    UpdateSubscription(Result<Subscription, Error>),
     DeleteSubscription(Result<(), Error>),
+    ResetSubscriptionOffsets(Result<Subscription, Error>),
+    ImportSchemas(Result<ImportSchemasResponse, Error>),
+    Reconcile(Result<ReconcilePlan, Error>),
+    ApplyReconcilePlan(Result<ReconcilePlan, Error>),
 }
 
 impl MetaHandle {
@@ -121,10 +183,18 @@ impl MetaHandle {
             .map_err(|_e| Error::MetaClosed)?
     }
 
-    pub async fn modify_service(&self, service_name: String, public: bool) -> Result<(), Error> {
+    pub async fn modify_service(
+        &self,
+        service_name: String,
+        public: Option<bool>,
+        configuration: Option<ServiceConfiguration>,
+        call_policy: Option<ServiceCallPolicy>,
+    ) -> Result<(), Error> {
         let (cmd, response_tx) = Command::prepare(MetaHandleRequest::ModifyService {
             service_name,
             public,
+            configuration,
+            call_policy,
         });
         self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
         response_tx
@@ -137,6 +207,26 @@ impl MetaHandle {
             .map_err(|_e| Error::MetaClosed)?
     }
 
+    pub async fn modify_service_routing(
+        &self,
+        service_name: String,
+        routing: ServiceRouting,
+    ) -> Result<(), Error> {
+        let (cmd, response_tx) = Command::prepare(MetaHandleRequest::ModifyServiceRouting {
+            service_name,
+            routing,
+        });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::ModifyServiceRouting(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
     pub async fn remove_deployment(&self, deployment_id: DeploymentId) -> Result<(), Error> {
         let (cmd, response_tx) =
             Command::prepare(MetaHandleRequest::RemoveDeployment { deployment_id });
@@ -151,6 +241,34 @@ impl MetaHandle {
             .map_err(|_e| Error::MetaClosed)?
     }
 
+    pub async fn drain_deployment(&self, deployment_id: DeploymentId) -> Result<(), Error> {
+        let (cmd, response_tx) =
+            Command::prepare(MetaHandleRequest::DrainDeployment { deployment_id });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::DrainDeployment(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
+    pub async fn undrain_deployment(&self, deployment_id: DeploymentId) -> Result<(), Error> {
+        let (cmd, response_tx) =
+            Command::prepare(MetaHandleRequest::UndrainDeployment { deployment_id });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::UndrainDeployment(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
     pub async fn create_subscription(
         &self,
         id: Option<SubscriptionId>,
@@ -175,6 +293,30 @@ impl MetaHandle {
             .map_err(|_e| Error::MetaClosed)?
     }
 
+    pub async fn update_subscription(
+        &self,
+        subscription_id: SubscriptionId,
+        sink: Option<Uri>,
+        metadata: Option<HashMap<String, String>>,
+        paused: Option<bool>,
+    ) -> Result<Subscription, Error> {
+        let (cmd, response_tx) = Command::prepare(MetaHandleRequest::UpdateSubscription {
+            subscription_id,
+            sink,
+            metadata,
+            paused,
+        });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::UpdateSubscription(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
     pub async fn delete_subscription(&self, subscription_id: SubscriptionId) -> Result<(), Error> {
         let (cmd, response_tx) =
             Command::prepare(MetaHandleRequest::DeleteSubscription { subscription_id });
@@ -188,6 +330,90 @@ impl MetaHandle {
             })
             .map_err(|_e| Error::MetaClosed)?
     }
+
+    pub async fn reset_subscription_offsets(
+        &self,
+        subscription_id: SubscriptionId,
+        to: OffsetReset,
+    ) -> Result<Subscription, Error> {
+        let (cmd, response_tx) = Command::prepare(MetaHandleRequest::ResetSubscriptionOffsets {
+            subscription_id,
+            to,
+        });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::ResetSubscriptionOffsets(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
+    pub async fn import_schemas(
+        &self,
+        archive: SchemasArchive,
+        force: Force,
+    ) -> Result<ImportSchemasResponse, Error> {
+        let (cmd, response_tx) =
+            Command::prepare(MetaHandleRequest::ImportSchemas { archive, force });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::ImportSchemas(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
+    /// Reconcile the registry against the desired deployments, services visibility and subscriptions,
+    /// returning the planned changes. In [`ApplyMode::DryRun`], the changes are computed but not applied.
+    pub async fn reconcile(
+        &self,
+        deployments: Vec<DiscoverEndpoint>,
+        services: Vec<ManifestService>,
+        subscriptions: Vec<ManifestSubscription>,
+        prune: bool,
+        force: Force,
+        apply_changes: ApplyMode,
+    ) -> Result<ReconcilePlan, Error> {
+        let (cmd, response_tx) = Command::prepare(MetaHandleRequest::Reconcile {
+            deployments,
+            services,
+            subscriptions,
+            prune,
+            force,
+            apply_changes,
+        });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::Reconcile(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
+
+    /// Apply the changes planned by the latest [`ApplyMode::DryRun`] reconciliation, as they were shown.
+    /// Fails if the registry was updated since.
+    pub async fn apply_reconcile_plan(&self, plan_id: u64) -> Result<ReconcilePlan, Error> {
+        let (cmd, response_tx) =
+            Command::prepare(MetaHandleRequest::ApplyReconcilePlan { plan_id });
+        self.0.send(cmd).map_err(|_e| Error::MetaClosed)?;
+        response_tx
+            .await
+            .map(|res| match res {
+                MetaHandleResponse::ApplyReconcilePlan(res) => res,
+                #[allow(unreachable_patterns)]
+                _ => panic!("Unexpected response message, this is a bug"),
+            })
+            .map_err(|_e| Error::MetaClosed)?
+    }
 }
 
 // -- Service implementation
@@ -203,6 +429,18 @@ pub struct MetaService<Storage> {
     api_cmd_rx: UnboundedCommandReceiver<MetaHandleRequest, MetaHandleResponse>,
 
     reloaded: bool,
+
+    // Number of updates applied to the schemas, to detect the updates applied since computing a reconcile plan
+    schemas_version: u64,
+    // Changes shown by the latest dry-run reconciliation, applied on request
+    pending_reconcile_plan: Option<PendingReconcilePlan>,
+    next_reconcile_plan_id: u64,
+}
+
+struct PendingReconcilePlan {
+    id: u64,
+    schemas_version: u64,
+    reconcile_changes: ReconcileChanges,
 }
 
 impl<Storage> MetaService<Storage>
@@ -224,6 +462,9 @@ where
             handle: MetaHandle(api_cmd_tx),
             api_cmd_rx,
             reloaded: false,
+            schemas_version: 0,
+            pending_reconcile_plan: None,
+            next_reconcile_plan_id: 0,
         }
     }
 
@@ -270,8 +511,14 @@ where
                                     warn_it!(e); e
                                 })
                         ),
-                        MetaHandleRequest::ModifyService { service_name, public } => MetaHandleResponse::ModifyService(
-                            self.modify_service(service_name, public).await
+                        MetaHandleRequest::ModifyService { service_name, public, configuration, call_policy } => MetaHandleResponse::ModifyService(
+                            self.modify_service(service_name, public, configuration, call_policy).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
+                        MetaHandleRequest::ModifyServiceRouting { service_name, routing } => MetaHandleResponse::ModifyServiceRouting(
+                            self.modify_service_routing(service_name, routing).await
                                 .map_err(|e| {
                                     warn_it!(e); e
                                 })
@@ -282,17 +529,59 @@ where
                                     warn_it!(e); e
                                 })
                         ),
+                        MetaHandleRequest::DrainDeployment { deployment_id } => MetaHandleResponse::DrainDeployment(
+                            self.drain_deployment(deployment_id).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
+                        MetaHandleRequest::UndrainDeployment { deployment_id } => MetaHandleResponse::UndrainDeployment(
+                            self.undrain_deployment(deployment_id).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
                         MetaHandleRequest::CreateSubscription { id, source, sink, metadata } => MetaHandleResponse::CreateSubscription(
                             self.create_subscription(id, source, sink, metadata, worker_handle.clone()).await
                                 .map_err(|e| {
                                     warn_it!(e); e
                                 })
                         ),
+                        MetaHandleRequest::UpdateSubscription { subscription_id, sink, metadata, paused } => MetaHandleResponse::UpdateSubscription(
+                            self.update_subscription(subscription_id, sink, metadata, paused, worker_handle.clone()).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
                         MetaHandleRequest::DeleteSubscription { subscription_id } => MetaHandleResponse::DeleteSubscription(
                             self.delete_subscription(subscription_id, worker_handle.clone()).await
                                 .map_err(|e| {
                                     warn_it!(e); e
                                 })
+                        ),
+                        MetaHandleRequest::ResetSubscriptionOffsets { subscription_id, to } => MetaHandleResponse::ResetSubscriptionOffsets(
+                            self.reset_subscription_offsets(subscription_id, to, worker_handle.clone()).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
+                        MetaHandleRequest::ImportSchemas { archive, force } => MetaHandleResponse::ImportSchemas(
+                            self.import_schemas(archive, force, worker_handle.clone()).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
+                        MetaHandleRequest::Reconcile { deployments, services, subscriptions, prune, force, apply_changes } => MetaHandleResponse::Reconcile(
+                            self.reconcile(deployments, services, subscriptions, prune, force, apply_changes, worker_handle.clone(), replier.aborted()).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
+                        ),
+                        MetaHandleRequest::ApplyReconcilePlan { plan_id } => MetaHandleResponse::ApplyReconcilePlan(
+                            self.apply_reconcile_plan(plan_id, worker_handle.clone()).await
+                                .map_err(|e| {
+                                    warn_it!(e); e
+                                })
                         )
                     };
 
@@ -311,6 +600,7 @@ where
         let update_commands = self.storage.reload().await?;
         self.schemas.apply_updates(update_commands)?;
         self.reloaded = true;
+        self.compact_storage_if_needed().await;
         Ok(())
     }
 
@@ -319,6 +609,9 @@ where
         worker_handle: &(impl restate_worker_api::Handle + Send + Sync + 'static),
     ) {
         for subscription in self.schemas.list_subscriptions(&[]) {
+            if subscription.paused() {
+                continue;
+            }
             // If the worker is closing, we can ignore this
             let _ = worker_handle
                 .subscription_controller_handle()
@@ -334,23 +627,14 @@ where
         apply_changes: ApplyMode,
         abort_signal: impl Future<Output = ()>,
     ) -> Result<DiscoverDeploymentResponse, Error> {
-        debug!(restate.deployment.address = %endpoint.address(), "Discovering deployment");
-
-        let discovered_metadata = tokio::select! {
-            res = self.service_discovery.discover(&endpoint) => res,
-            _ = abort_signal => return Err(Error::RequestAborted),
-        }?;
+        let (deployment_metadata, discovered_metadata) =
+            self.discover(endpoint, abort_signal).await?;
 
-        let deployment_metadata = match endpoint.into_inner() {
-            (Endpoint::Http(uri, _), headers) => DeploymentMetadata::new_http(
-                uri.clone(),
-                discovered_metadata.protocol_type,
-                DeliveryOptions::new(headers),
-            ),
-            (Endpoint::Lambda(arn, assume_role_arn), headers) => {
-                DeploymentMetadata::new_lambda(arn, assume_role_arn, DeliveryOptions::new(headers))
-            }
-        };
+        // Computed upfront, so they're reported also in dry-run mode and for forced updates
+        let wire_incompatibilities = self.schemas.compute_wire_incompatibilities(
+            discovered_metadata.services.clone(),
+            &discovered_metadata.descriptor_pool,
+        )?;
 
         // Compute the diff with the current state of Schemas
         let schemas_update_commands = self.schemas.compute_new_deployment(
@@ -362,8 +646,10 @@ where
         )?;
 
         // Compute the response
-        let discovery_response =
-            Self::infer_discovery_response_from_update_commands(&schemas_update_commands);
+        let discovery_response = Self::infer_discovery_response_from_update_commands(
+            &schemas_update_commands,
+            wire_incompatibilities,
+        );
 
         if apply_changes.should_apply() {
             // Propagate updates
@@ -376,11 +662,94 @@ where
         Ok(discovery_response)
     }
 
-    async fn modify_service(&mut self, service_name: String, public: bool) -> Result<(), Error> {
+    async fn discover(
+        &self,
+        endpoint: DiscoverEndpoint,
+        abort_signal: impl Future<Output = ()>,
+    ) -> Result<(DeploymentMetadata, DiscoveredDescriptors), Error> {
+        debug!(restate.deployment.address = %endpoint.address(), "Discovering deployment");
+
+        let discovered_metadata = tokio::select! {
+            res = self.service_discovery.discover(&endpoint) => res,
+            _ = abort_signal => return Err(Error::RequestAborted),
+        }?;
+
+        let deployment_metadata = match endpoint.into_inner() {
+            (Endpoint::Http(uri, _), headers) => DeploymentMetadata::new_http(
+                uri.clone(),
+                discovered_metadata.protocol_type,
+                DeliveryOptions::new(headers),
+            ),
+            (Endpoint::Lambda(arn, assume_role_arn), headers) => {
+                DeploymentMetadata::new_lambda(arn, assume_role_arn, DeliveryOptions::new(headers))
+            }
+        };
+
+        let (services, descriptor_pool) = match discovered_metadata.services {
+            DiscoveredServices::Protobuf {
+                services,
+                descriptor_pool,
+            } => (services, descriptor_pool),
+            DiscoveredServices::Json(manifest) => synthesize_descriptor_pool(&manifest)?,
+        };
+
+        Ok((
+            deployment_metadata,
+            DiscoveredDescriptors {
+                services,
+                descriptor_pool,
+            },
+        ))
+    }
+
+    async fn modify_service(
+        &mut self,
+        service_name: String,
+        public: Option<bool>,
+        configuration: Option<ServiceConfiguration>,
+        call_policy: Option<ServiceCallPolicy>,
+    ) -> Result<(), Error> {
         debug!(rpc.service = service_name, "Modify service");
 
         // Compute the diff and propagate updates
-        let update_commands = vec![self.schemas.compute_modify_service(service_name, public)?];
+        let mut update_commands = vec![];
+        if let Some(public) = public {
+            update_commands.push(
+                self.schemas
+                    .compute_modify_service(service_name.clone(), public)?,
+            );
+        }
+        if let Some(configuration) = configuration {
+            update_commands.push(
+                self.schemas
+                    .compute_modify_service_configuration(service_name.clone(), configuration)?,
+            );
+        }
+        if let Some(call_policy) = call_policy {
+            update_commands.push(
+                self.schemas
+                    .compute_modify_service_call_policy(service_name, call_policy)?,
+            );
+        }
+        if update_commands.is_empty() {
+            return Ok(());
+        }
+        self.store_and_apply_updates(update_commands).await?;
+
+        Ok(())
+    }
+
+    async fn modify_service_routing(
+        &mut self,
+        service_name: String,
+        routing: ServiceRouting,
+    ) -> Result<(), Error> {
+        debug!(rpc.service = service_name, "Modify service routing");
+
+        // Compute the diff and propagate updates
+        let update_commands = vec![self
+            .schemas
+            .compute_modify_service_routing(service_name, routing)?];
         self.store_and_apply_updates(update_commands).await?;
 
         Ok(())
@@ -396,6 +765,26 @@ where
         Ok(())
     }
 
+    async fn drain_deployment(&mut self, deployment_id: DeploymentId) -> Result<(), Error> {
+        debug!(restate.deployment.id = %deployment_id, "Drain deployment");
+
+        // Compute the diff and propagate updates
+        let update_commands = vec![self.schemas.compute_drain_deployment(deployment_id)?];
+        self.store_and_apply_updates(update_commands).await?;
+
+        Ok(())
+    }
+
+    async fn undrain_deployment(&mut self, deployment_id: DeploymentId) -> Result<(), Error> {
+        debug!(restate.deployment.id = %deployment_id, "Undrain deployment");
+
+        // Compute the diff and propagate updates
+        let update_commands = vec![self.schemas.compute_undrain_deployment(deployment_id)?];
+        self.store_and_apply_updates(update_commands).await?;
+
+        Ok(())
+    }
+
     async fn create_subscription(
         &mut self,
         id: Option<SubscriptionId>,
@@ -423,6 +812,42 @@ where
         Ok(sub)
     }
 
+    async fn update_subscription(
+        &mut self,
+        id: SubscriptionId,
+        sink: Option<Uri>,
+        metadata: Option<HashMap<String, String>>,
+        paused: Option<bool>,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+    ) -> Result<Subscription, Error> {
+        info!(restate.subscription.id = %id, "Update subscription");
+
+        // Compute the diff and propagate updates
+        let (sub, update_command) = self.schemas.compute_update_subscription(
+            id,
+            sink,
+            metadata,
+            paused,
+            worker_handle.subscription_controller_handle(),
+        )?;
+        self.store_and_apply_updates(vec![update_command]).await?;
+
+        // Restart the consumer with the new configuration. The subscription id, hence the
+        // consumer group, doesn't change, so the consumption resumes from the committed offsets.
+        worker_handle
+            .subscription_controller_handle()
+            .stop_subscription(id)
+            .await?;
+        if !sub.paused() {
+            worker_handle
+                .subscription_controller_handle()
+                .start_subscription(sub.clone())
+                .await?;
+        }
+
+        Ok(sub)
+    }
+
     async fn delete_subscription(
         &mut self,
         id: SubscriptionId,
@@ -441,6 +866,202 @@ where
         Ok(())
     }
 
+    async fn reset_subscription_offsets(
+        &mut self,
+        id: SubscriptionId,
+        to: OffsetReset,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+    ) -> Result<Subscription, Error> {
+        info!(restate.subscription.id = %id, "Reset subscription offsets to {:?}", to);
+
+        let (sub, update_command) = self.schemas.compute_reset_subscription_offsets(id)?;
+        let subscription_controller = worker_handle.subscription_controller_handle();
+
+        // The offsets can be committed only while the consumer is stopped
+        subscription_controller.stop_subscription(id).await?;
+        if let Err(e) = subscription_controller
+            .reset_subscription_offsets(sub.clone(), to)
+            .await
+        {
+            // Resume the consumption from the previous offsets
+            if !sub.paused() {
+                let previous_sub = self
+                    .schemas
+                    .get_subscription(id)
+                    .expect("subscription should exist");
+                subscription_controller
+                    .start_subscription(previous_sub)
+                    .await?;
+            }
+            return Err(e.into());
+        }
+
+        // The new generation is stored only once the offsets are reset, otherwise the events
+        // consumed again from the previous offsets would not be deduplicated
+        self.store_and_apply_updates(vec![update_command]).await?;
+        if !sub.paused() {
+            subscription_controller
+                .start_subscription(sub.clone())
+                .await?;
+        }
+
+        Ok(sub)
+    }
+
+    async fn import_schemas(
+        &mut self,
+        archive: SchemasArchive,
+        force: Force,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+    ) -> Result<ImportSchemasResponse, Error> {
+        info!(
+            restate.schemas.deployments = archive.deployments.len(),
+            restate.schemas.subscriptions = archive.subscriptions.len(),
+            "Import schemas"
+        );
+
+        let ImportSchemas {
+            update_commands,
+            deployments: imported_deployments,
+            subscriptions: imported_subscriptions,
+        } = compute_import_schemas(&self.schemas, archive, force, || {
+            worker_handle.subscription_controller_handle()
+        })?;
+
+        self.store_and_apply_updates(update_commands).await?;
+
+        for sub in &imported_subscriptions {
+            if !sub.paused() {
+                worker_handle
+                    .subscription_controller_handle()
+                    .start_subscription(sub.clone())
+                    .await?;
+            }
+        }
+
+        Ok(ImportSchemasResponse {
+            deployments: imported_deployments,
+            subscriptions: imported_subscriptions.iter().map(|sub| sub.id()).collect(),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn reconcile(
+        &mut self,
+        deployments: Vec<DiscoverEndpoint>,
+        services: Vec<ManifestService>,
+        subscriptions: Vec<ManifestSubscription>,
+        prune: bool,
+        force: Force,
+        apply_changes: ApplyMode,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+        abort_signal: impl Future<Output = ()>,
+    ) -> Result<ReconcilePlan, Error> {
+        info!(
+            restate.manifest.deployments = deployments.len(),
+            restate.manifest.subscriptions = subscriptions.len(),
+            "Reconcile schemas"
+        );
+        tokio::pin!(abort_signal);
+
+        let mut discovered_deployments = Vec::with_capacity(deployments.len());
+        for endpoint in deployments {
+            discovered_deployments.push(self.discover(endpoint, abort_signal.as_mut()).await?);
+        }
+
+        let reconcile_changes = compute_reconcile_changes(
+            &self.schemas,
+            discovered_deployments,
+            services,
+            subscriptions,
+            prune,
+            force,
+            || worker_handle.subscription_controller_handle(),
+        )?;
+
+        if reconcile_changes.update_commands.is_empty() {
+            debug!("Not applying schemas update commands because there are no changes");
+            return Ok(ReconcilePlan {
+                changes: vec![],
+                applied: false,
+                plan_id: None,
+            });
+        }
+        if !apply_changes.should_apply() {
+            debug!("Not applying schemas update commands because of dry-run mode");
+
+            // Keep the changes, to apply them as shown if requested
+            self.next_reconcile_plan_id += 1;
+            let plan_id = self.next_reconcile_plan_id;
+            let changes = reconcile_changes.changes.clone();
+            self.pending_reconcile_plan = Some(PendingReconcilePlan {
+                id: plan_id,
+                schemas_version: self.schemas_version,
+                reconcile_changes,
+            });
+            return Ok(ReconcilePlan {
+                changes,
+                applied: false,
+                plan_id: Some(plan_id),
+            });
+        }
+
+        self.apply_reconcile_changes(reconcile_changes, worker_handle)
+            .await
+    }
+
+    async fn apply_reconcile_plan(
+        &mut self,
+        plan_id: u64,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+    ) -> Result<ReconcilePlan, Error> {
+        debug!(restate.reconcile.plan_id = plan_id, "Apply reconcile plan");
+
+        let pending_plan = match self.pending_reconcile_plan.take() {
+            Some(pending_plan) if pending_plan.id == plan_id => pending_plan,
+            other => {
+                self.pending_reconcile_plan = other;
+                return Err(Error::UnknownReconcilePlan(plan_id));
+            }
+        };
+        // The changes were computed against the registry as it was when planning
+        if pending_plan.schemas_version != self.schemas_version {
+            return Err(Error::OutdatedReconcilePlan(plan_id));
+        }
+
+        self.apply_reconcile_changes(pending_plan.reconcile_changes, worker_handle)
+            .await
+    }
+
+    async fn apply_reconcile_changes(
+        &mut self,
+        reconcile_changes: ReconcileChanges,
+        worker_handle: impl restate_worker_api::Handle + Clone + Send + Sync + 'static,
+    ) -> Result<ReconcilePlan, Error> {
+        let ReconcileChanges {
+            changes,
+            update_commands,
+            created_subscriptions,
+            deleted_subscriptions,
+        } = reconcile_changes;
+
+        self.store_and_apply_updates(update_commands).await?;
+
+        let subscription_controller = worker_handle.subscription_controller_handle();
+        for id in deleted_subscriptions {
+            subscription_controller.stop_subscription(id).await?;
+        }
+        for sub in created_subscriptions {
+            subscription_controller.start_subscription(sub).await?;
+        }
+
+        Ok(ReconcilePlan {
+            changes,
+            applied: true,
+            plan_id: None,
+        })
+    }
+
     async fn store_and_apply_updates(
         &mut self,
         commands: Vec<SchemasUpdateCommand>,
@@ -450,12 +1071,35 @@ where
 
         // Propagate updates in memory
         self.schemas.apply_updates(commands)?;
+        self.schemas_version += 1;
+
+        self.compact_storage_if_needed().await;
 
         Ok(())
     }
 
+    /// Replaces the stored commands with the snapshot of the schemas, when the storage accumulated enough commands.
+    /// Failing to compact doesn't affect the stored commands, hence the compaction is retried after the next update.
+    async fn compact_storage_if_needed(&mut self) {
+        if !self.storage.needs_compaction() {
+            return;
+        }
+
+        let result = match self.schemas.compute_snapshot() {
+            Ok(snapshot) => self.storage.compact(snapshot).await.map_err(Error::from),
+            Err(err) => Err(Error::from(err)),
+        };
+        if let Err(err) = result {
+            warn!(
+                error = %err,
+                "Failed to compact the meta storage, will retry after the next update"
+            );
+        }
+    }
+
     fn infer_discovery_response_from_update_commands(
         commands: &[SchemasUpdateCommand],
+        wire_incompatibilities: Vec<WireIncompatibility>,
     ) -> DiscoverDeploymentResponse {
         for schema_update_command in commands {
             if let SchemasUpdateCommand::InsertDeployment {
@@ -480,6 +1124,7 @@ where
                                 .expect("Discovered services cannot be built-in services")
                         })
                         .collect(),
+                    wire_incompatibilities,
                 };
             }
         }
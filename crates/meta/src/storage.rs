@@ -14,13 +14,18 @@ use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use tokio::io;
-use tracing::trace;
+use tracing::{debug, info, trace};
 
 type StorageFormatVersion = u32;
 
 /// Storage format version used by the [`FileMetaStorage`] to store schema information. This value
 /// must be incremented whenever you introduce a breaking change to the schema information.
-const STORAGE_FORMAT_VERSION: StorageFormatVersion = 1;
+///
+/// Version history:
+/// * 1: one file of commands per [`MetaStorage::store`] call.
+/// * 2: adds the snapshot files written by [`MetaStorage::compact`], superseding the older files,
+///   and the fields of the subscriptions and the commands added since. See [`v1`] for the older layout.
+const STORAGE_FORMAT_VERSION: StorageFormatVersion = 2;
 
 /// Name of the file which contains the storage format version.
 const STORAGE_FORMAT_VERSION_FILE_NAME: &str = ".meta_format_version";
@@ -37,7 +42,7 @@ pub enum MetaStorageError {
     Descriptor(#[from] prost_reflect::DescriptorError),
     #[error("task error when writing to disk: {0}. This is probably a runtime bug")]
     Join(#[from] tokio::task::JoinError),
-    #[error("file ending with .restate has a bad filename: {0}. This is probably a runtime bug")]
+    #[error("file ending with .restate or .snapshot has a bad filename: {0}. This is probably a runtime bug")]
     BadFilename(PathBuf),
 }
 
@@ -50,6 +55,16 @@ pub trait MetaStorage {
     fn reload(
         &mut self,
     ) -> impl Future<Output = Result<Vec<SchemasUpdateCommand>, MetaStorageError>> + Send;
+
+    /// Returns true if enough commands were stored since the last compaction to compact them.
+    fn needs_compaction(&self) -> bool;
+
+    /// Replace all the stored commands with the given snapshot,
+    /// which must contain the commands rebuilding the state obtained by applying them.
+    fn compact(
+        &mut self,
+        snapshot: Vec<SchemasUpdateCommand>,
+    ) -> impl Future<Output = Result<(), MetaStorageError>> + Send;
 }
 
 // --- File based implementation of MetaStorage, using bincode
@@ -70,30 +85,51 @@ pub enum BuildError {
     #[error("serde error: {0}")]
     #[code(unknown)]
     Serde(#[from] serde_json::Error),
+    #[error("cannot migrate the meta storage directory: {0}")]
+    #[code(unknown)]
+    Migration(#[from] MetaStorageError),
 }
 
 const RESTATE_EXTENSION: &str = "restate";
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+const TMP_EXTENSION: &str = "tmp";
 
+/// Stores each batch of commands in the file `<index>.restate`.
+///
+/// On compaction, the snapshot of the commands stored up to the file with index `n` is written to `<n>.snapshot`,
+/// which supersedes all the files with index lower or equal than `n`.
+/// The snapshot is first written to `<n>.snapshot.tmp` and then renamed, hence a crash during the compaction
+/// leaves either the old files or a complete snapshot, and the superseded files are removed on the next reload.
 #[derive(Debug)]
 pub struct FileMetaStorage {
     root_path: PathBuf,
     next_file_index: usize,
+    // Index of the last file contained in the latest snapshot
+    snapshot_index: Option<usize>,
+    // Number of files stored since the latest snapshot triggering the compaction, 0 disables it
+    compaction_threshold: usize,
 }
 
 impl FileMetaStorage {
-    pub fn new(root_path: PathBuf) -> Result<Self, BuildError> {
+    pub fn new(root_path: PathBuf, compaction_threshold: usize) -> Result<Self, BuildError> {
         if Self::is_empty_directory(root_path.as_path()) {
             Self::write_storage_format_version_to_file(
                 root_path.as_path(),
                 STORAGE_FORMAT_VERSION,
             )?;
         } else {
-            Self::assert_compatible_storage_format_version(root_path.as_path())?;
+            match Self::read_storage_format_version(root_path.as_path())? {
+                STORAGE_FORMAT_VERSION => {}
+                1 => Self::migrate_from_v1(root_path.as_path())?,
+                version => return Err(BuildError::IncompatibleStorageFormat(version)),
+            }
         }
 
         Ok(Self {
             root_path,
             next_file_index: 0,
+            snapshot_index: None,
+            compaction_threshold,
         })
     }
 
@@ -131,23 +167,63 @@ impl FileMetaStorage {
         Ok(())
     }
 
-    fn assert_compatible_storage_format_version(
+    fn read_storage_format_version(
         root_path: impl AsRef<Path>,
-    ) -> Result<(), BuildError> {
+    ) -> Result<StorageFormatVersion, BuildError> {
         let version_file =
             std::fs::File::open(root_path.as_ref().join(STORAGE_FORMAT_VERSION_FILE_NAME));
 
-        let version = if let Ok(version_file) = version_file {
-            serde_json::from_reader(version_file)?
+        if let Ok(version_file) = version_file {
+            Ok(serde_json::from_reader(version_file)?)
         } else {
-            return Err(BuildError::MissingVersionFile);
-        };
+            Err(BuildError::MissingVersionFile)
+        }
+    }
 
-        if version != STORAGE_FORMAT_VERSION {
-            Err(BuildError::IncompatibleStorageFormat(version))
-        } else {
-            Ok(())
+    /// Decodes the files of the version 1 with the [`v1`] types, and writes all their commands to a snapshot
+    /// superseding them. The superseded files are removed on the next [`MetaStorage::reload`].
+    ///
+    /// The version file is updated only once the snapshot is durable, hence an interrupted migration
+    /// is started over from the untouched files of the version 1, overwriting the snapshot it might have written.
+    fn migrate_from_v1(root_path: &Path) -> Result<(), BuildError> {
+        info!(
+            "Migrating meta storage directory {} from storage format version 1 to {}",
+            root_path.display(),
+            STORAGE_FORMAT_VERSION
+        );
+
+        let files = StorageFiles::list(root_path)?;
+        if let Some((_, snapshot_index)) = files.commands.last() {
+            let mut commands = vec![];
+            for (path, _) in &files.commands {
+                commands.extend(v1::read_commands_file(path)?);
+            }
+
+            let tmp_file_path = root_path.join(format!(
+                "{snapshot_index}.{SNAPSHOT_EXTENSION}.{TMP_EXTENSION}"
+            ));
+            write_commands_file(&tmp_file_path, commands)?;
+            std::fs::rename(
+                &tmp_file_path,
+                root_path.join(format!("{snapshot_index}.{SNAPSHOT_EXTENSION}")),
+            )?;
+            sync_dir(root_path)?;
         }
+
+        let tmp_file_path = root_path.join(format!(
+            "{STORAGE_FORMAT_VERSION_FILE_NAME}.{TMP_EXTENSION}"
+        ));
+        let tmp_file = std::fs::File::create(&tmp_file_path)?;
+        serde_json::to_writer(&tmp_file, &STORAGE_FORMAT_VERSION)?;
+        tmp_file.sync_all()?;
+
+        std::fs::rename(
+            tmp_file_path,
+            root_path.join(STORAGE_FORMAT_VERSION_FILE_NAME),
+        )?;
+        sync_dir(root_path)?;
+
+        Ok(())
     }
 }
 
@@ -155,6 +231,246 @@ impl FileMetaStorage {
 #[serde(transparent)]
 struct CommandsFile(Vec<SchemasUpdateCommand>);
 
+/// Commands as stored with the storage format version 1.
+///
+/// Bincode encodes neither the names nor the number of the fields, and encodes the enum variants by index,
+/// hence these types mirror the layout of the version 1, and must not change. The other types of the
+/// commands haven't changed since, while the variants added to [`Source`] come after the version 1 ones.
+mod v1 {
+    use super::{MetaStorageError, Path};
+
+    use bytes::Bytes;
+    use prost_reflect::DescriptorPool;
+    use restate_schema_api::deployment::DeploymentMetadata;
+    use restate_schema_api::subscription::{
+        EventReceiverServiceInstanceType, FieldRemapType, Source,
+    };
+    use restate_schema_impl::InsertServiceUpdateCommand;
+    use restate_types::identifiers::{DeploymentId, ServiceRevision, SubscriptionId};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize)]
+    pub(super) enum SchemasUpdateCommand {
+        InsertDeployment {
+            deployment_id: DeploymentId,
+            metadata: DeploymentMetadata,
+            services: Vec<InsertServiceUpdateCommand>,
+            descriptor_pool: Bytes,
+        },
+        RemoveDeployment {
+            deployment_id: DeploymentId,
+        },
+        RemoveService {
+            name: String,
+            revision: ServiceRevision,
+        },
+        ModifyService {
+            name: String,
+            public: bool,
+        },
+        AddSubscription(Subscription),
+        RemoveSubscription(SubscriptionId),
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Subscription {
+        id: SubscriptionId,
+        source: Source,
+        sink: Sink,
+        metadata: HashMap<String, String>,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) enum Sink {
+        Service {
+            name: String,
+            method: String,
+            input_event_remap: Option<InputEventRemap>,
+            instance_type: EventReceiverServiceInstanceType,
+        },
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct InputEventRemap {
+        key: Option<(u32, FieldRemapType)>,
+        payload: Option<(u32, FieldRemapType)>,
+        attributes_index: Option<u32>,
+    }
+
+    pub(super) fn read_commands_file(
+        path: &Path,
+    ) -> Result<Vec<restate_schema_impl::SchemasUpdateCommand>, MetaStorageError> {
+        let mut file = std::fs::File::open(path)?;
+        let commands: Vec<SchemasUpdateCommand> =
+            bincode::serde::decode_from_std_read(&mut file, bincode::config::standard())?;
+        commands.into_iter().map(TryInto::try_into).collect()
+    }
+
+    impl TryFrom<SchemasUpdateCommand> for restate_schema_impl::SchemasUpdateCommand {
+        type Error = MetaStorageError;
+
+        fn try_from(command: SchemasUpdateCommand) -> Result<Self, Self::Error> {
+            Ok(match command {
+                SchemasUpdateCommand::InsertDeployment {
+                    deployment_id,
+                    metadata,
+                    services,
+                    descriptor_pool,
+                } => Self::InsertDeployment {
+                    deployment_id,
+                    metadata,
+                    services,
+                    descriptor_pool: DescriptorPool::decode(descriptor_pool)?,
+                },
+                SchemasUpdateCommand::RemoveDeployment { deployment_id } => {
+                    Self::RemoveDeployment { deployment_id }
+                }
+                SchemasUpdateCommand::RemoveService { name, revision } => {
+                    Self::RemoveService { name, revision }
+                }
+                SchemasUpdateCommand::ModifyService { name, public } => {
+                    Self::ModifyService { name, public }
+                }
+                SchemasUpdateCommand::AddSubscription(subscription) => {
+                    Self::AddSubscription(subscription.into())
+                }
+                SchemasUpdateCommand::RemoveSubscription(id) => Self::RemoveSubscription(id),
+            })
+        }
+    }
+
+    impl From<Subscription> for restate_schema_api::subscription::Subscription {
+        fn from(subscription: Subscription) -> Self {
+            let Sink::Service {
+                name,
+                method,
+                input_event_remap,
+                instance_type,
+            } = subscription.sink;
+
+            // The fields added since the version 1 get their default values
+            Self::new(
+                subscription.id,
+                subscription.source,
+                restate_schema_api::subscription::Sink::Service {
+                    name,
+                    method,
+                    input_event_remap: input_event_remap.map(|remap| {
+                        restate_schema_api::subscription::InputEventRemap {
+                            key: remap.key,
+                            payload: remap.payload,
+                            attributes_index: remap.attributes_index,
+                            payload_decoding: None,
+                        }
+                    }),
+                    instance_type,
+                },
+                subscription.metadata,
+            )
+        }
+    }
+}
+
+/// Files of the meta storage directory, sorted by index.
+#[derive(Default)]
+struct StorageFiles {
+    commands: Vec<(PathBuf, usize)>,
+    snapshots: Vec<(PathBuf, usize)>,
+    // Leftovers of interrupted compactions
+    tmp: Vec<PathBuf>,
+}
+
+impl StorageFiles {
+    fn list(root_path: &Path) -> Result<Self, MetaStorageError> {
+        let mut files = StorageFiles::default();
+        for dir_entry in std::fs::read_dir(root_path)? {
+            let path = dir_entry?.path();
+            let files_with_index = match path.extension().and_then(|os_str| os_str.to_str()) {
+                Some(RESTATE_EXTENSION) => &mut files.commands,
+                Some(SNAPSHOT_EXTENSION) => &mut files.snapshots,
+                Some(TMP_EXTENSION) => {
+                    files.tmp.push(path);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let index: usize = path
+                .file_stem()
+                .expect("If there is an extension, there must be a file stem")
+                .to_string_lossy()
+                .parse()
+                .map_err(|_| MetaStorageError::BadFilename(path.clone()))?;
+            files_with_index.push((path, index));
+        }
+        files.commands.sort_by(|a, b| a.1.cmp(&b.1));
+        files.snapshots.sort_by(|a, b| a.1.cmp(&b.1));
+
+        Ok(files)
+    }
+
+    fn latest_snapshot_index(&self) -> Option<usize> {
+        self.snapshots.last().map(|(_, index)| *index)
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        self.commands
+            .iter()
+            .chain(self.snapshots.iter())
+            .map(|(_, index)| *index)
+            .max()
+    }
+
+    /// Removes the files superseded by the snapshot with the given index, and the leftovers of interrupted compactions.
+    fn remove_superseded(&self, snapshot_index: usize) -> Result<(), MetaStorageError> {
+        let superseded = self
+            .commands
+            .iter()
+            .filter(|(_, index)| *index <= snapshot_index)
+            .chain(
+                self.snapshots
+                    .iter()
+                    .filter(|(_, index)| *index < snapshot_index),
+            )
+            .map(|(path, _)| path)
+            .chain(self.tmp.iter());
+        for path in superseded {
+            trace!("Remove superseded metadata file {}", path.display());
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_commands_file(path: &Path) -> Result<Vec<SchemasUpdateCommand>, MetaStorageError> {
+    trace!("Reloading metadata file {}", path.display());
+
+    let mut file = std::fs::File::open(path)?;
+    let commands_file: CommandsFile =
+        bincode::serde::decode_from_std_read(&mut file, bincode::config::standard())?;
+    Ok(commands_file.0)
+}
+
+fn write_commands_file(
+    path: &Path,
+    commands: Vec<SchemasUpdateCommand>,
+) -> Result<(), MetaStorageError> {
+    let mut file = std::fs::File::create(path)?;
+    bincode::serde::encode_into_std_write(
+        CommandsFile(commands),
+        &mut file,
+        bincode::config::standard(),
+    )?;
+    Ok(file.sync_all()?)
+}
+
+/// Makes the renames and removals of the directory entries durable.
+fn sync_dir(path: &Path) -> Result<(), io::Error> {
+    std::fs::File::open(path)?.sync_all()
+}
+
 impl MetaStorage for FileMetaStorage {
     async fn store(&mut self, commands: Vec<SchemasUpdateCommand>) -> Result<(), MetaStorageError> {
         let file_path = self
@@ -165,16 +481,7 @@ impl MetaStorage for FileMetaStorage {
         trace!("Write metadata file {}", file_path.display());
 
         // We use blocking spawn to use bincode::encode_into_std_write
-        tokio::task::spawn_blocking(move || {
-            let mut file = std::fs::File::create(file_path)?;
-            bincode::serde::encode_into_std_write(
-                CommandsFile(commands),
-                &mut file,
-                bincode::config::standard(),
-            )?;
-            Result::<(), MetaStorageError>::Ok(file.sync_all()?)
-        })
-        .await??;
+        tokio::task::spawn_blocking(move || write_commands_file(&file_path, commands)).await??;
         Ok(())
     }
 
@@ -184,49 +491,89 @@ impl MetaStorage for FileMetaStorage {
         // Try to create a dir, in case it doesn't exist
         restate_fs_util::create_dir_all_if_doesnt_exists(&root_path).await?;
 
-        // Find all the metadata files in the root path directory, parse the index and then sort them by index
-        let mut read_dir = tokio::fs::read_dir(root_path).await?;
-        let mut metadata_files = vec![];
-        while let Some(dir_entry) = read_dir.next_entry().await? {
-            if dir_entry
-                .path()
-                .extension()
-                .and_then(|os_str| os_str.to_str())
-                == Some(RESTATE_EXTENSION)
-            {
-                let index: usize = dir_entry
-                    .path()
-                    .file_stem()
-                    .expect("If there is an extension, there must be a file stem")
-                    .to_string_lossy()
-                    .parse()
-                    .map_err(|_| MetaStorageError::BadFilename(dir_entry.path()))?;
-
-                // Make sure self.next_file_index = max(self.next_file_index, index + 1)
-                self.next_file_index = self.next_file_index.max(index + 1);
-                metadata_files.push((dir_entry.path(), index));
+        // We use blocking spawn to use bincode::decode_from_std_read
+        let (schemas_updates, snapshot_index, max_index) = tokio::task::spawn_blocking(move || {
+            let files = StorageFiles::list(&root_path)?;
+            let snapshot_index = files.latest_snapshot_index();
+
+            let mut schemas_updates = vec![];
+            if let Some(snapshot_index) = snapshot_index {
+                let (snapshot_path, _) = files.snapshots.last().expect("Snapshot must exist");
+                schemas_updates.extend(read_commands_file(snapshot_path)?);
+
+                // Complete a compaction which was interrupted after writing the snapshot
+                files.remove_superseded(snapshot_index)?;
+            } else {
+                for tmp_file_path in &files.tmp {
+                    std::fs::remove_file(tmp_file_path)?;
+                }
             }
+
+            for (metadata_file_path, index) in &files.commands {
+                if snapshot_index.is_some_and(|snapshot_index| *index <= snapshot_index) {
+                    continue;
+                }
+                schemas_updates.extend(read_commands_file(metadata_file_path)?);
+            }
+
+            Result::<_, MetaStorageError>::Ok((schemas_updates, snapshot_index, files.max_index()))
+        })
+        .await??;
+
+        // Make sure self.next_file_index = max(self.next_file_index, max_index + 1)
+        if let Some(max_index) = max_index {
+            self.next_file_index = self.next_file_index.max(max_index + 1);
         }
-        metadata_files.sort_by(|a, b| a.1.cmp(&b.1));
+        self.snapshot_index = snapshot_index;
 
-        // We use blocking spawn to use bincode::decode_from_std_read
-        tokio::task::spawn_blocking(move || {
-            let mut schemas_updates = vec![];
+        Ok(schemas_updates)
+    }
 
-            for (metadata_file_path, _) in metadata_files {
-                // Metadata_file_path is the json metadata descriptor
-                trace!("Reloading metadata file {}", metadata_file_path.display());
+    fn needs_compaction(&self) -> bool {
+        let files_since_snapshot = match self.snapshot_index {
+            Some(snapshot_index) => self.next_file_index - snapshot_index - 1,
+            None => self.next_file_index,
+        };
+        self.compaction_threshold > 0 && files_since_snapshot >= self.compaction_threshold
+    }
 
-                let mut file = std::fs::File::open(metadata_file_path)?;
+    async fn compact(
+        &mut self,
+        snapshot: Vec<SchemasUpdateCommand>,
+    ) -> Result<(), MetaStorageError> {
+        let Some(snapshot_index) = self.next_file_index.checked_sub(1) else {
+            // Nothing stored yet
+            return Ok(());
+        };
+        if self.snapshot_index == Some(snapshot_index) {
+            return Ok(());
+        }
 
-                let commands_file: CommandsFile =
-                    bincode::serde::decode_from_std_read(&mut file, bincode::config::standard())?;
-                schemas_updates.extend(commands_file.0);
-            }
+        let root_path = self.root_path.clone();
+        let tmp_file_path = root_path.join(format!(
+            "{snapshot_index}.{SNAPSHOT_EXTENSION}.{TMP_EXTENSION}"
+        ));
+        let snapshot_file_path = root_path.join(format!("{snapshot_index}.{SNAPSHOT_EXTENSION}"));
+
+        debug!(
+            "Compacting the meta storage up to the file index {} in {}",
+            snapshot_index,
+            snapshot_file_path.display()
+        );
 
-            Result::<Vec<SchemasUpdateCommand>, MetaStorageError>::Ok(schemas_updates)
+        tokio::task::spawn_blocking(move || {
+            // The snapshot must be durable before being visible, and visible before removing the superseded files
+            write_commands_file(&tmp_file_path, snapshot)?;
+            std::fs::rename(&tmp_file_path, &snapshot_file_path)?;
+            sync_dir(&root_path)?;
+
+            StorageFiles::list(&root_path)?.remove_superseded(snapshot_index)?;
+            Result::<(), MetaStorageError>::Ok(sync_dir(&root_path)?)
         })
-        .await?
+        .await??;
+
+        self.snapshot_index = Some(snapshot_index);
+        Ok(())
     }
 }
 
@@ -240,15 +587,22 @@ mod tests {
     use test_log::test;
 
     use restate_pb::mocks;
-    use restate_schema_api::deployment::Deployment;
+    use restate_schema_api::deployment::{Deployment, DeploymentResolver};
+    use restate_schema_api::subscription::{
+        EventReceiverServiceInstanceType, EventRoute, FieldRemapType, InputEventRemap,
+        KafkaOrderingKeyFormat, Sink, Source, Subscription,
+    };
     use restate_schema_impl::Schemas;
+    use restate_test_util::let_assert;
+    use restate_types::identifiers::SubscriptionId;
+    use std::collections::HashMap;
 
     #[test(tokio::test)]
     async fn reload_in_order() {
         let schemas = Schemas::default();
         let temp_dir = tempdir().unwrap();
-        let mut file_storage =
-            FileMetaStorage::new(temp_dir.path().to_path_buf()).expect("file storage should build");
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
 
         // Generate some commands for a new deployment, with new services
         let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
@@ -293,17 +647,345 @@ mod tests {
             expected_commands.into_iter().map(Into::into).collect();
 
         // Now let's try to reload
-        let mut file_storage =
-            FileMetaStorage::new(temp_dir.path().to_path_buf()).expect("file storage should build");
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+        let actual_commands = file_storage.reload().await.unwrap();
+
+        assert_eq!(
+            actual_commands
+                .into_iter()
+                .map(SchemasUpdateCommandEquality::from)
+                .collect::<Vec<_>>(),
+            expected_commands
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn reload_subscription_with_filter_and_routes() {
+        let temp_dir = tempdir().unwrap();
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+
+        let sink = |method: &str| Sink::Service {
+            name: "greeter.Greeter".to_owned(),
+            method: method.to_owned(),
+            input_event_remap: None,
+            instance_type: EventReceiverServiceInstanceType::Unkeyed,
+        };
+        let mut subscription = Subscription::new(
+            SubscriptionId::new(),
+            Source::Kafka {
+                cluster: "my-cluster".to_owned(),
+                topic: "my-topic".to_owned(),
+                ordering_key_format: KafkaOrderingKeyFormat::ConsumerGroupTopicPartition,
+            },
+            sink("Greet"),
+            HashMap::new(),
+        );
+        subscription.set_filter(Some(r#"headers["type"] == "greeting""#.to_owned()));
+        subscription.set_routes(vec![EventRoute {
+            name: "farewell".to_owned(),
+            filter: r#"headers["type"] == "farewell""#.to_owned(),
+            sink: sink("Farewell"),
+        }]);
+
+        file_storage
+            .store(vec![SchemasUpdateCommand::AddSubscription(
+                subscription.clone(),
+            )])
+            .await
+            .unwrap();
+
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+        let commands = file_storage.reload().await.unwrap();
+
+        let_assert!([SchemasUpdateCommand::AddSubscription(reloaded)] = commands.as_slice());
+        assert_eq!(reloaded, &subscription);
+    }
+
+    #[test(tokio::test)]
+    async fn compact_and_reload() {
+        let schemas = Schemas::default();
+        let temp_dir = tempdir().unwrap();
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 2)
+            .expect("file storage should build");
+
+        let register_deployment = |uri: &str, services: &[&str]| {
+            let deployment = Deployment::mock_with_uri(uri);
+            let commands = schemas
+                .compute_new_deployment(
+                    Some(deployment.id),
+                    deployment.metadata,
+                    services.iter().map(|svc| svc.to_string()).collect(),
+                    mocks::DESCRIPTOR_POOL.clone(),
+                    false,
+                )
+                .unwrap();
+            schemas.apply_updates(commands.clone()).unwrap();
+            commands
+        };
+        let commands_1 =
+            register_deployment("http://localhost:9080", &[mocks::GREETER_SERVICE_NAME]);
+        let commands_2 = register_deployment(
+            "http://localhost:9081",
+            &[
+                mocks::GREETER_SERVICE_NAME,
+                mocks::ANOTHER_GREETER_SERVICE_NAME,
+            ],
+        );
+        let snapshot = schemas.compute_snapshot().unwrap();
+        let commands_3 = register_deployment(
+            "http://localhost:9082",
+            &[mocks::ANOTHER_GREETER_SERVICE_NAME],
+        );
+
+        file_storage.store(commands_1).await.unwrap();
+        assert!(!file_storage.needs_compaction());
+        file_storage.store(commands_2).await.unwrap();
+        assert!(file_storage.needs_compaction());
+        file_storage.compact(snapshot.clone()).await.unwrap();
+        assert!(!file_storage.needs_compaction());
+        file_storage.store(commands_3.clone()).await.unwrap();
+
+        // Only the snapshot and the commands stored after it are left
+        let files = StorageFiles::list(temp_dir.path()).unwrap();
+        assert_eq!(files.snapshots.len(), 1);
+        assert_eq!(files.commands.len(), 1);
+
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 2)
+            .expect("file storage should build");
         let actual_commands = file_storage.reload().await.unwrap();
 
+        let mut expected_commands = snapshot;
+        expected_commands.extend(commands_3);
         assert_eq!(
             actual_commands
                 .into_iter()
                 .map(SchemasUpdateCommandEquality::from)
                 .collect::<Vec<_>>(),
             expected_commands
+                .into_iter()
+                .map(SchemasUpdateCommandEquality::from)
+                .collect::<Vec<_>>()
+        );
+        assert!(!file_storage.needs_compaction());
+
+        // The rebuilt registry resolves the same deployments
+        let reloaded_schemas = Schemas::default();
+        reloaded_schemas
+            .apply_updates(file_storage.reload().await.unwrap())
+            .unwrap();
+        for service in [
+            mocks::GREETER_SERVICE_NAME,
+            mocks::ANOTHER_GREETER_SERVICE_NAME,
+        ] {
+            assert_eq!(
+                reloaded_schemas
+                    .resolve_latest_deployment_for_service(service)
+                    .map(|deployment| deployment.id),
+                schemas
+                    .resolve_latest_deployment_for_service(service)
+                    .map(|deployment| deployment.id)
+            );
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn reload_completes_interrupted_compaction() {
+        let temp_dir = tempdir().unwrap();
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+
+        let deployment = Deployment::mock_with_uri("http://localhost:9080");
+        let commands = Schemas::default()
+            .compute_new_deployment(
+                Some(deployment.id),
+                deployment.metadata,
+                vec![mocks::GREETER_SERVICE_NAME.to_owned()],
+                mocks::DESCRIPTOR_POOL.clone(),
+                false,
+            )
+            .unwrap();
+        file_storage.store(commands.clone()).await.unwrap();
+
+        // Crash before renaming the temporary snapshot file
+        write_commands_file(&temp_dir.path().join("0.snapshot.tmp"), commands.clone()).unwrap();
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+        assert_eq!(file_storage.reload().await.unwrap().len(), commands.len());
+        let files = StorageFiles::list(temp_dir.path()).unwrap();
+        assert!(files.tmp.is_empty());
+        assert!(files.snapshots.is_empty());
+        assert_eq!(files.commands.len(), 1);
+
+        // Crash before removing the superseded files
+        write_commands_file(&temp_dir.path().join("0.snapshot"), commands.clone()).unwrap();
+        let mut file_storage = FileMetaStorage::new(temp_dir.path().to_path_buf(), 0)
+            .expect("file storage should build");
+        assert_eq!(file_storage.reload().await.unwrap().len(), commands.len());
+        let files = StorageFiles::list(temp_dir.path()).unwrap();
+        assert_eq!(files.snapshots.len(), 1);
+        assert!(files.commands.is_empty());
+
+        // New commands are stored after the snapshot
+        file_storage.store(commands.clone()).await.unwrap();
+        assert!(temp_dir.path().join("1.restate").exists());
+    }
+
+    #[test]
+    fn migrate_from_storage_format_version_1() -> anyhow::Result<()> {
+        let tempdir = tempdir()?;
+
+        FileMetaStorage::write_storage_format_version_to_file(tempdir.path(), 1)?;
+        FileMetaStorage::new(tempdir.path().to_path_buf(), 0)?;
+
+        assert_eq!(
+            FileMetaStorage::read_storage_format_version(tempdir.path())?,
+            STORAGE_FORMAT_VERSION
+        );
+
+        Ok(())
+    }
+
+    // Files written by the storage format version 1:
+    // * [AddSubscription(Subscription { id, source: Kafka { cluster, topic, ordering_key_format },
+    //   sink: Service { name, method, input_event_remap: Some { payload: Some((1, String)), .. }, instance_type: Unkeyed },
+    //   metadata: { group.id } })]
+    // * [ModifyService { name, public: false }, RemoveSubscription(id)]
+    const V1_SUBSCRIPTION_ID: &str = "sub_15VqmTOnXH3Vv2pl5HOG7Ua";
+    const V1_COMMANDS_FILE_0: &[u8] = b"\x01\x04\x1bsub_15VqmTOnXH3Vv2pl5HOG7Ua\
+        \x00\x0amy-cluster\x08my-topic\x00\
+        \x00\x0fgreeter.Greeter\x05Greet\x01\x00\x01\x01\x01\x00\x01\
+        \x01\x08group.id\x08my-group";
+    const V1_COMMANDS_FILE_1: &[u8] =
+        b"\x02\x03\x0fgreeter.Greeter\x00\x05\x1bsub_15VqmTOnXH3Vv2pl5HOG7Ua";
+
+    #[test(tokio::test)]
+    async fn migrate_commands_from_storage_format_version_1() -> anyhow::Result<()> {
+        let tempdir = tempdir()?;
+
+        FileMetaStorage::write_storage_format_version_to_file(tempdir.path(), 1)?;
+        std::fs::write(tempdir.path().join("0.restate"), V1_COMMANDS_FILE_0)?;
+        std::fs::write(tempdir.path().join("1.restate"), V1_COMMANDS_FILE_1)?;
+
+        let mut file_storage = FileMetaStorage::new(tempdir.path().to_path_buf(), 0)?;
+        assert_eq!(
+            FileMetaStorage::read_storage_format_version(tempdir.path())?,
+            STORAGE_FORMAT_VERSION
+        );
+        let commands = file_storage.reload().await?;
+
+        // The files of the version 1 are superseded by the snapshot
+        let files = StorageFiles::list(tempdir.path())?;
+        assert_eq!(files.snapshots.len(), 1);
+        assert!(files.commands.is_empty());
+
+        let subscription_id: SubscriptionId = V1_SUBSCRIPTION_ID.parse()?;
+        let_assert!(
+            [
+                SchemasUpdateCommand::AddSubscription(subscription),
+                SchemasUpdateCommand::ModifyService {
+                    name,
+                    public: false
+                },
+                SchemasUpdateCommand::RemoveSubscription(removed_id)
+            ] = commands.as_slice()
+        );
+        assert_eq!(subscription.id(), subscription_id);
+        assert_eq!(
+            subscription.source(),
+            &Source::Kafka {
+                cluster: "my-cluster".to_owned(),
+                topic: "my-topic".to_owned(),
+                ordering_key_format: KafkaOrderingKeyFormat::ConsumerGroupTopicPartition,
+            }
         );
+        assert_eq!(
+            subscription.sink(),
+            &Sink::Service {
+                name: "greeter.Greeter".to_owned(),
+                method: "Greet".to_owned(),
+                input_event_remap: Some(InputEventRemap {
+                    key: None,
+                    payload: Some((1, FieldRemapType::String)),
+                    attributes_index: None,
+                    payload_decoding: None,
+                }),
+                instance_type: EventReceiverServiceInstanceType::Unkeyed,
+            }
+        );
+        assert_eq!(
+            subscription.metadata(),
+            &HashMap::from([("group.id".to_owned(), "my-group".to_owned())])
+        );
+        assert!(!subscription.paused());
+        assert_eq!(subscription.offsets_generation(), 0);
+        assert!(subscription.filter().is_none());
+        assert!(subscription.routes().is_empty());
+        assert_eq!(name, "greeter.Greeter");
+        assert_eq!(*removed_id, subscription_id);
+
+        // New commands are stored after the snapshot
+        file_storage.store(vec![]).await?;
+        assert!(tempdir.path().join("2.restate").exists());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn restart_interrupted_migration_from_storage_format_version_1() -> anyhow::Result<()> {
+        // Snapshot written by a previous migration of the same files
+        let migrated_tempdir = tempdir()?;
+        FileMetaStorage::write_storage_format_version_to_file(migrated_tempdir.path(), 1)?;
+        std::fs::write(
+            migrated_tempdir.path().join("0.restate"),
+            V1_COMMANDS_FILE_0,
+        )?;
+        std::fs::write(
+            migrated_tempdir.path().join("1.restate"),
+            V1_COMMANDS_FILE_1,
+        )?;
+        FileMetaStorage::new(migrated_tempdir.path().to_path_buf(), 0)?;
+
+        // The migration was interrupted after writing the snapshot, but before updating the version file
+        let tempdir = tempdir()?;
+        FileMetaStorage::write_storage_format_version_to_file(tempdir.path(), 1)?;
+        std::fs::write(tempdir.path().join("0.restate"), V1_COMMANDS_FILE_0)?;
+        std::fs::write(tempdir.path().join("1.restate"), V1_COMMANDS_FILE_1)?;
+        std::fs::copy(
+            migrated_tempdir.path().join("1.snapshot"),
+            tempdir.path().join("1.snapshot"),
+        )?;
+        std::fs::write(
+            tempdir.path().join(format!(
+                "{STORAGE_FORMAT_VERSION_FILE_NAME}.{TMP_EXTENSION}"
+            )),
+            b"2",
+        )?;
+
+        let mut file_storage = FileMetaStorage::new(tempdir.path().to_path_buf(), 0)?;
+        assert_eq!(
+            FileMetaStorage::read_storage_format_version(tempdir.path())?,
+            STORAGE_FORMAT_VERSION
+        );
+
+        // The commands are migrated once
+        let commands = file_storage.reload().await?;
+        let_assert!(
+            [
+                SchemasUpdateCommand::AddSubscription(_),
+                SchemasUpdateCommand::ModifyService { .. },
+                SchemasUpdateCommand::RemoveSubscription(_)
+            ] = commands.as_slice()
+        );
+
+        let files = StorageFiles::list(tempdir.path())?;
+        assert_eq!(files.snapshots.len(), 1);
+        assert!(files.commands.is_empty());
+        assert!(files.tmp.is_empty());
+
+        Ok(())
     }
 
     // Newtype to implement equality for the scope of this test
@@ -358,7 +1040,7 @@ mod tests {
             incompatible_storage_format_version,
         )?;
 
-        let build_error = FileMetaStorage::new(tempdir.into_path())
+        let build_error = FileMetaStorage::new(tempdir.into_path(), 0)
             .expect_err("should have failed with incompatible storage format version");
 
         assert_that!(
@@ -296,6 +296,10 @@ fn main() -> std::io::Result<()> {
                     "dev.restate.Ingress",
                     Box::<ManualResponseRestateBuiltInServiceGen>::default(),
                 )
+                .with_svc(
+                    "dev.restate.Kafka",
+                    Box::<ManualResponseRestateBuiltInServiceGen>::default(),
+                )
                 .with_svc(
                     "dev.restate.internal.RemoteContext",
                     Box::new(
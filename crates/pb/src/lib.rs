@@ -34,6 +34,20 @@ pub mod restate {
     #![allow(unknown_lints)]
     include!(concat!(env!("OUT_DIR"), "/dev.restate.rs"));
 
+    #[cfg(feature = "restate-types")]
+    mod conversions {
+        use super::*;
+
+        impl From<restate_types::errors::InvocationError> for batch_invoke_response::Failure {
+            fn from(value: restate_types::errors::InvocationError) -> Self {
+                Self {
+                    code: value.code().into(),
+                    message: value.message().to_string(),
+                }
+            }
+        }
+    }
+
     pub mod internal {
         #![allow(warnings)]
         #![allow(clippy::all)]
@@ -59,6 +73,51 @@ pub mod restate {
                     }
                 }
             }
+
+            impl IdempotentInvokeRequest {
+                /// Creates the request to the IdempotentInvoker executing the invocation `fid`,
+                /// together with the id of the IdempotentInvoker invocation.
+                ///
+                /// The IdempotentInvoker invocation is keyed by target service name and idempotency key,
+                /// hence invocations to the same service with the same idempotency key are executed only once.
+                pub fn new_with_idempotent_invoker_fid(
+                    fid: restate_types::identifiers::FullInvocationId,
+                    method: String,
+                    argument: prost::bytes::Bytes,
+                    idempotency_key: prost::bytes::Bytes,
+                    retention_period: Option<std::time::Duration>,
+                    headers: Vec<restate_types::invocation::Header>,
+                ) -> (restate_types::identifiers::FullInvocationId, Self) {
+                    use prost::bytes::{BufMut, BytesMut};
+
+                    let mut idempotency_fid_key = BytesMut::with_capacity(
+                        fid.service_id.service_name.len() + idempotency_key.len(),
+                    );
+                    idempotency_fid_key.put(fid.service_id.service_name.as_bytes());
+                    idempotency_fid_key.put(idempotency_key.clone());
+
+                    (
+                        restate_types::identifiers::FullInvocationId::generate(
+                            crate::IDEMPOTENT_INVOKER_SERVICE_NAME,
+                            idempotency_fid_key.freeze(),
+                        ),
+                        Self {
+                            idempotency_id: idempotency_key,
+                            service_name: fid.service_id.service_name.to_string(),
+                            service_key: fid.service_id.key,
+                            invocation_uuid: fid.invocation_uuid.into(),
+                            method,
+                            argument,
+                            retention_period_sec: retention_period.unwrap_or_default().as_secs()
+                                as u32,
+                            headers: headers
+                                .into_iter()
+                                .map(|header| (header.name.to_string(), header.value.to_string()))
+                                .collect(),
+                        },
+                    )
+                }
+            }
         }
     }
 
@@ -106,7 +165,18 @@ pub fn get_service(svc_name: &str) -> ServiceDescriptor {
 }
 
 pub const INGRESS_SERVICE_NAME: &str = "dev.restate.Ingress";
+pub const INGRESS_INVOKE_METHOD_NAME: &str = "Invoke";
+pub const INGRESS_BATCH_INVOKE_METHOD_NAME: &str = "BatchInvoke";
+/// Maximum number of invocations of a single `BatchInvoke` request.
+pub const INGRESS_BATCH_INVOKE_MAX_SIZE: usize = 1000;
+pub const INGRESS_CANCEL_METHOD_NAME: &str = "Cancel";
+pub const INGRESS_KILL_METHOD_NAME: &str = "Kill";
+pub const INGRESS_GET_STATUS_METHOD_NAME: &str = "GetStatus";
 pub const AWAKEABLES_SERVICE_NAME: &str = "dev.restate.Awakeables";
+pub const AWAKEABLES_RESOLVE_METHOD_NAME: &str = "Resolve";
+pub const AWAKEABLES_REJECT_METHOD_NAME: &str = "Reject";
+pub const KAFKA_SERVICE_NAME: &str = "dev.restate.Kafka";
+pub const KAFKA_PUBLISH_METHOD_NAME: &str = "Publish";
 pub const REFLECTION_SERVICE_NAME: &str = "grpc.reflection.v1alpha.ServerReflection";
 pub const HEALTH_SERVICE_NAME: &str = "grpc.health.v1.Health";
 pub const PROXY_SERVICE_NAME: &str = "dev.restate.internal.Proxy";
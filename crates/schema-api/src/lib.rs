@@ -12,12 +12,13 @@
 
 #[cfg(feature = "deployment")]
 pub mod deployment {
-    use super::service::ServiceMetadata;
+    use super::service::{ServiceConfiguration, ServiceMetadata};
     use bytes::Bytes;
     use bytestring::ByteString;
     use http::header::{HeaderName, HeaderValue};
     use http::Uri;
     use restate_types::identifiers::{DeploymentId, LambdaARN, ServiceRevision};
+    use restate_types::invocation::Header;
     use restate_types::time::MillisSinceEpoch;
     use std::collections::HashMap;
     use std::fmt;
@@ -164,14 +165,53 @@ pub mod deployment {
         }
     }
 
+    /// State of a deployment with respect to the new invocations.
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub enum DeploymentState {
+        /// The deployment accepts new invocations.
+        #[default]
+        Active,
+        /// The deployment accepts no new invocations, and only completes the invocations pinned to it.
+        /// Once no invocation is pinned to it anymore, the deployment can be removed.
+        Draining,
+    }
+
+    impl DeploymentState {
+        pub fn is_draining(&self) -> bool {
+            matches!(self, DeploymentState::Draining)
+        }
+    }
+
     pub trait DeploymentResolver {
         fn resolve_latest_deployment_for_service(
             &self,
             service_name: impl AsRef<str>,
         ) -> Option<Deployment>;
 
+        /// Resolve the deployment for a new invocation of the service, honoring the service routing.
+        /// See [`ServiceRouting::select_revision`](super::service::ServiceRouting::select_revision).
+        fn resolve_deployment_for_invocation(
+            &self,
+            service_name: impl AsRef<str>,
+            _routing_key: u64,
+            _headers: &[Header],
+        ) -> Option<Deployment> {
+            self.resolve_latest_deployment_for_service(service_name)
+        }
+
         fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment>;
 
+        fn get_deployment_state(&self, deployment_id: &DeploymentId) -> Option<DeploymentState>;
+
+        /// Resolve the configuration of the service, used when running its invocations.
+        fn resolve_service_configuration(
+            &self,
+            service_name: impl AsRef<str>,
+        ) -> Option<ServiceConfiguration>;
+
         fn get_deployment_descriptor_pool(&self, deployment_id: &DeploymentId) -> Option<Bytes>;
 
         fn get_deployment_and_services(
@@ -217,6 +257,7 @@ pub mod deployment {
         pub struct MockDeploymentMetadataRegistry {
             pub deployments: HashMap<DeploymentId, DeploymentMetadata>,
             pub latest_deployment: HashMap<String, DeploymentId>,
+            pub service_configurations: HashMap<String, ServiceConfiguration>,
         }
 
         impl MockDeploymentMetadataRegistry {
@@ -251,6 +292,24 @@ pub mod deployment {
                     })
             }
 
+            fn get_deployment_state(
+                &self,
+                deployment_id: &DeploymentId,
+            ) -> Option<DeploymentState> {
+                self.deployments
+                    .get(deployment_id)
+                    .map(|_| DeploymentState::Active)
+            }
+
+            fn resolve_service_configuration(
+                &self,
+                service_name: impl AsRef<str>,
+            ) -> Option<ServiceConfiguration> {
+                self.service_configurations
+                    .get(service_name.as_ref())
+                    .cloned()
+            }
+
             fn get_deployment_descriptor_pool(
                 &self,
                 _deployment_id: &DeploymentId,
@@ -298,6 +357,8 @@ pub mod deployment {
 pub mod service {
     use bytes::Bytes;
     use restate_types::identifiers::{DeploymentId, ServiceRevision};
+    use restate_types::invocation::Header;
+    use std::collections::{BTreeMap, BTreeSet};
 
     #[derive(Debug, Clone, PartialEq, Eq)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -329,6 +390,149 @@ pub mod service {
         /// If true, the service can be invoked through the ingress.
         /// If false, the service can be invoked only from another Restate service.
         pub public: bool,
+        /// # Routing
+        ///
+        /// Routing of the new invocations among the revisions of the service.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub routing: ServiceRouting,
+        /// # Configuration
+        ///
+        /// Configuration of the service, overriding the defaults of the runtime.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub configuration: ServiceConfiguration,
+        /// # Call policy
+        ///
+        /// Restricts which services can invoke the service.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub call_policy: ServiceCallPolicy,
+    }
+
+    /// Policy restricting the invocations of a service performed by other Restate services.
+    /// It doesn't affect the invocations through the ingress, see [`ServiceMetadata::public`].
+    ///
+    /// By default, any service can invoke the service.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct ServiceCallPolicy {
+        /// # Allowed callers
+        ///
+        /// Fully qualified names of the services allowed to invoke the service.
+        /// If unset, any service can invoke the service.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub allowed_callers: Option<BTreeSet<String>>,
+    }
+
+    impl ServiceCallPolicy {
+        pub fn is_caller_allowed(&self, caller_service_name: &str) -> bool {
+            match &self.allowed_callers {
+                Some(allowed_callers) => allowed_callers.contains(caller_service_name),
+                None => true,
+            }
+        }
+    }
+
+    /// Per-service configuration. Unset values fall back to the options of the runtime.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", serde_with::serde_as)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct ServiceConfiguration {
+        /// # Inactivity timeout
+        ///
+        /// Maximum time an invocation of this service can stay idle before being suspended.
+        /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub inactivity_timeout: Option<humantime::Duration>,
+        /// # Abort timeout
+        ///
+        /// Time given to an invocation of this service to complete after the inactivity timeout fired,
+        /// before it is aborted.
+        /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub abort_timeout: Option<humantime::Duration>,
+        /// # Idempotency retention
+        ///
+        /// Retention of the responses of the idempotent invocations of this service,
+        /// used when the request doesn't specify its own retention period.
+        /// Can be configured using the [`humantime`](https://docs.rs/humantime/latest/humantime/fn.parse_duration.html) format.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "serde_with::As::<Option<serde_with::DisplayFromStr>>")
+        )]
+        #[cfg_attr(feature = "serde_schema", schemars(with = "Option<String>"))]
+        pub idempotency_retention: Option<humantime::Duration>,
+        /// # Message size limit
+        ///
+        /// Maximum size in bytes of the messages sent by this service to the runtime.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub message_size_limit: Option<usize>,
+    }
+
+    /// Routing of the new invocations of a service among the deployments exposing its revisions.
+    ///
+    /// By default, all the new invocations are routed to the latest revision.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct ServiceRouting {
+        /// # Weights
+        ///
+        /// Percentage of the new invocations routed to each revision.
+        /// The remaining percentage is routed to the latest revision.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub weights: BTreeMap<ServiceRevision, u8>,
+        /// # Header pins
+        ///
+        /// New invocations carrying a matching header are routed to the pinned revision, regardless of the weights.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub header_pins: Vec<HeaderPin>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct HeaderPin {
+        /// Header name, matched case-insensitively.
+        pub name: String,
+        pub value: String,
+        pub revision: ServiceRevision,
+    }
+
+    impl ServiceRouting {
+        /// Returns the revision to route a new invocation to, or `None` for the latest revision.
+        ///
+        /// The `routing_key` spreads the invocations over the weights, hence it should be uniformly distributed.
+        pub fn select_revision(
+            &self,
+            routing_key: u64,
+            headers: &[Header],
+        ) -> Option<ServiceRevision> {
+            if let Some(pin) = self.header_pins.iter().find(|pin| {
+                headers
+                    .iter()
+                    .any(|h| h.name.eq_ignore_ascii_case(&pin.name) && h.value == pin.value)
+            }) {
+                return Some(pin.revision);
+            }
+
+            let mut bucket = (routing_key % 100) as u8;
+            for (revision, weight) in &self.weights {
+                if bucket < *weight {
+                    return Some(*revision);
+                }
+                bucket -= weight;
+            }
+            None
+        }
     }
 
     #[derive(Debug, Clone)]
@@ -365,6 +569,70 @@ pub mod service {
 
         /// Returns None if the service doesn't exists, Some(is_public) otherwise.
         fn is_service_public(&self, service_name: impl AsRef<str>) -> Option<bool>;
+
+        /// Returns None if the service doesn't exists, Some(is_allowed) otherwise.
+        /// See [`ServiceCallPolicy`].
+        fn is_call_allowed(
+            &self,
+            caller_service_name: impl AsRef<str>,
+            service_name: impl AsRef<str>,
+        ) -> Option<bool>;
+
+        /// Returns None if the service method doesn't exists, Some(is_server_streaming) otherwise.
+        fn is_server_streaming(
+            &self,
+            service_name: impl AsRef<str>,
+            method_name: impl AsRef<str>,
+        ) -> Option<bool>;
+    }
+}
+
+#[cfg(feature = "json_manifest")]
+pub mod json_manifest {
+    //! JSON description of the services exposed by a deployment,
+    //! alternative to the protobuf descriptors for the SDKs without protobuf tooling.
+
+    use super::service::InstanceType;
+    use restate_types::identifiers::DeploymentId;
+
+    /// Services exposed by a deployment.
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    pub struct JsonServicesManifest {
+        pub services: Vec<JsonService>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JsonService {
+        /// Fully qualified name of the service.
+        pub name: String,
+        pub instance_type: InstanceType,
+        pub handlers: Vec<JsonHandler>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JsonHandler {
+        pub name: String,
+        /// Property of the input holding the key of the service instance.
+        /// Required for keyed services, the property must be a string.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub key: Option<String>,
+        /// JSON schema of the input. If absent, the handler takes no input.
+        ///
+        /// The properties of object schemas can set the number of the protobuf field
+        /// they're mapped to with the `x-restate-field-number` annotation.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub input_schema: Option<serde_json::Value>,
+        /// JSON schema of the output. If absent, the handler returns no output.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub output_schema: Option<serde_json::Value>,
+    }
+
+    pub trait JsonDeploymentResolver {
+        /// Returns `true` if the deployment was registered from a [`JsonServicesManifest`],
+        /// hence it exchanges JSON payloads rather than protobuf messages.
+        fn is_json_deployment(&self, deployment_id: &DeploymentId) -> bool;
     }
 }
 
@@ -409,6 +677,14 @@ pub mod json {
             service_name: impl AsRef<str>,
             method_name: impl AsRef<str>,
         ) -> Option<(Self::JsonToProtobufMapper, Self::ProtobufToJsonMapper)>;
+
+        /// Like [`Self::resolve_json_mapper_for_service`], but the mappers convert the output
+        /// of the method from JSON and the input of the method to JSON.
+        fn resolve_reverse_json_mapper_for_service(
+            &self,
+            service_name: impl AsRef<str>,
+            method_name: impl AsRef<str>,
+        ) -> Option<(Self::JsonToProtobufMapper, Self::ProtobufToJsonMapper)>;
     }
 }
 
@@ -548,6 +824,7 @@ pub mod proto_symbol {
 pub mod subscription {
     use std::collections::HashMap;
     use std::fmt;
+    use std::str::FromStr;
 
     use restate_types::identifiers::SubscriptionId;
 
@@ -569,6 +846,10 @@ pub mod subscription {
             topic: String,
             ordering_key_format: KafkaOrderingKeyFormat,
         },
+        /// Subject of a NATS JetStream stream
+        Nats { cluster: String, subject: String },
+        /// Local file, or directory of files, where every line is an event
+        File { path: String },
     }
 
     impl fmt::Display for Source {
@@ -577,6 +858,12 @@ pub mod subscription {
                 Source::Kafka { cluster, topic, .. } => {
                     write!(f, "kafka://{}/{}", cluster, topic)
                 }
+                Source::Nats { cluster, subject } => {
+                    write!(f, "nats://{}/{}", cluster, subject)
+                }
+                Source::File { path } => {
+                    write!(f, "file://localhost{}", path)
+                }
             }
         }
     }
@@ -607,6 +894,56 @@ pub mod subscription {
         pub payload: Option<(u32, FieldRemapType)>,
         /// If != 0, index to remap the event.metadata field
         pub attributes_index: Option<u32>,
+        /// If set, the event.payload is decoded to the whole input message, rather than being remapped to a field
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub payload_decoding: Option<PayloadDecoding>,
+    }
+
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub enum PayloadFormat {
+        Json,
+        Protobuf,
+        Avro,
+    }
+
+    impl fmt::Display for PayloadFormat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                PayloadFormat::Json => "json",
+                PayloadFormat::Protobuf => "protobuf",
+                PayloadFormat::Avro => "avro",
+            })
+        }
+    }
+
+    impl FromStr for PayloadFormat {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "json" => Ok(PayloadFormat::Json),
+                "protobuf" => Ok(PayloadFormat::Protobuf),
+                "avro" => Ok(PayloadFormat::Avro),
+                _ => Err(anyhow::anyhow!(
+                    "unknown payload format '{}', supported formats are: {:?}",
+                    s,
+                    ["json", "protobuf", "avro"]
+                )),
+            }
+        }
+    }
+
+    /// Defines how to decode the event payload to the input message of the sink method.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct PayloadDecoding {
+        pub format: PayloadFormat,
+        /// If set, the payload is framed with the Confluent wire format,
+        /// and the schema id is resolved against this schema registry.
+        pub schema_registry_url: Option<String>,
     }
 
     /// Specialized version of [super::key::ServiceInstanceType]
@@ -659,6 +996,55 @@ pub mod subscription {
         source: Source,
         sink: Sink,
         metadata: HashMap<String, String>,
+        /// If true, the subscription is registered, but its events are not consumed.
+        #[cfg_attr(feature = "serde", serde(default))]
+        paused: bool,
+        /// Incremented every time the consumed offsets are reset, so that the replayed events
+        /// are not discarded by the ingress deduplication.
+        #[cfg_attr(feature = "serde", serde(default))]
+        offsets_generation: u32,
+        /// If set, only the events matching this filter expression are dispatched.
+        #[cfg_attr(feature = "serde", serde(default))]
+        filter: Option<String>,
+        /// Events are dispatched to the sink of the first route whose filter matches,
+        /// or to the subscription sink if none does.
+        #[cfg_attr(feature = "serde", serde(default))]
+        routes: Vec<EventRoute>,
+        /// Policy applied to the events whose invocation keeps failing.
+        #[cfg_attr(feature = "serde", serde(default))]
+        failure_policy: FailurePolicy,
+    }
+
+    /// What to do with an event whose invocation keeps failing.
+    ///
+    /// With the default policy the invocation is retried until it succeeds.
+    #[derive(Debug, Clone, Default, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct FailurePolicy {
+        /// If set, the invocation is given up after this number of attempts,
+        /// so the following events with the same key can be processed.
+        pub max_attempts: Option<u32>,
+        /// If set, the given up events are published to this topic, otherwise they're skipped.
+        pub dead_letter: Option<DeadLetterTopic>,
+    }
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct DeadLetterTopic {
+        pub cluster: String,
+        pub topic: String,
+    }
+
+    /// Sink for the events matching the filter expression.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub struct EventRoute {
+        pub name: String,
+        pub filter: String,
+        pub sink: Sink,
     }
 
     impl Subscription {
@@ -673,6 +1059,11 @@ pub mod subscription {
                 source,
                 sink,
                 metadata,
+                paused: false,
+                offsets_generation: 0,
+                filter: None,
+                routes: vec![],
+                failure_policy: FailurePolicy::default(),
             }
         }
 
@@ -695,6 +1086,90 @@ pub mod subscription {
         pub fn metadata_mut(&mut self) -> &mut HashMap<String, String> {
             &mut self.metadata
         }
+
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        pub fn set_paused(&mut self, paused: bool) {
+            self.paused = paused;
+        }
+
+        pub fn offsets_generation(&self) -> u32 {
+            self.offsets_generation
+        }
+
+        pub fn set_offsets_generation(&mut self, offsets_generation: u32) {
+            self.offsets_generation = offsets_generation;
+        }
+
+        pub fn filter(&self) -> Option<&str> {
+            self.filter.as_deref()
+        }
+
+        pub fn set_filter(&mut self, filter: Option<String>) {
+            self.filter = filter;
+        }
+
+        pub fn routes(&self) -> &[EventRoute] {
+            &self.routes
+        }
+
+        pub fn set_routes(&mut self, routes: Vec<EventRoute>) {
+            self.routes = routes;
+        }
+
+        pub fn failure_policy(&self) -> &FailurePolicy {
+            &self.failure_policy
+        }
+
+        pub fn set_failure_policy(&mut self, failure_policy: FailurePolicy) {
+            self.failure_policy = failure_policy;
+        }
+
+        /// Returns this subscription with the given sink, used to dispatch the events of a route.
+        pub fn with_sink(&self, sink: Sink) -> Self {
+            Self {
+                sink,
+                ..self.clone()
+            }
+        }
+    }
+
+    /// Position to reset the consumed offsets of a subscription to.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+    #[cfg_attr(feature = "serde_schema", derive(schemars::JsonSchema))]
+    pub enum OffsetReset {
+        Earliest,
+        Latest,
+        /// Earliest offset whose timestamp, in milliseconds since the unix epoch, is greater or equal to the given one.
+        Timestamp(u64),
+    }
+
+    /// Consumption progress of a partition of a subscription source.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct SubscriptionPartitionOffsets {
+        pub subscription_id: SubscriptionId,
+        pub topic: String,
+        pub partition: i32,
+        /// Offset of the next message to consume, as last stored by the consumer.
+        /// None if no message has been acknowledged since the partition was assigned.
+        pub stored_offset: Option<i64>,
+        /// Offset of the next message to be appended to the partition, as last fetched by the consumer.
+        pub high_watermark: Option<i64>,
+    }
+
+    impl SubscriptionPartitionOffsets {
+        pub fn lag(&self) -> Option<i64> {
+            match (self.stored_offset, self.high_watermark) {
+                (Some(stored_offset), Some(high_watermark)) => {
+                    Some((high_watermark - stored_offset).max(0))
+                }
+                _ => None,
+            }
+        }
     }
 
     pub enum ListSubscriptionFilter {
@@ -717,6 +1192,10 @@ pub mod subscription {
         fn list_subscriptions(&self, filters: &[ListSubscriptionFilter]) -> Vec<Subscription>;
     }
 
+    pub trait SubscriptionOffsetsResolver {
+        fn list_subscription_offsets(&self) -> Vec<SubscriptionPartitionOffsets>;
+    }
+
     pub trait SubscriptionValidator {
         type Error: Into<anyhow::Error>;
 
@@ -747,6 +1226,10 @@ pub mod subscription {
                         instance_type: EventReceiverServiceInstanceType::Unkeyed,
                     },
                     metadata: Default::default(),
+                    paused: false,
+                    offsets_generation: 0,
+                    filter: None,
+                    routes: vec![],
                 }
             }
         }
@@ -11,30 +11,53 @@
 use super::Schemas;
 use bytes::Bytes;
 
-use crate::schemas_impl::ServiceLocation;
-use restate_schema_api::deployment::{Deployment, DeploymentResolver};
-use restate_schema_api::service::ServiceMetadata;
+use restate_schema_api::deployment::{Deployment, DeploymentResolver, DeploymentState};
+use restate_schema_api::service::{ServiceConfiguration, ServiceMetadata};
 use restate_types::identifiers::{DeploymentId, ServiceRevision};
+use restate_types::invocation::Header;
 
 impl DeploymentResolver for Schemas {
     fn resolve_latest_deployment_for_service(
         &self,
         service_name: impl AsRef<str>,
+    ) -> Option<Deployment> {
+        let schemas = self.0.load();
+        let deployment_id = schemas.find_latest_deployment_for_service(service_name.as_ref())?;
+        schemas
+            .deployments
+            .get(&deployment_id)
+            .map(|schemas| Deployment {
+                id: deployment_id,
+                metadata: schemas.metadata.clone(),
+            })
+    }
+
+    fn resolve_deployment_for_invocation(
+        &self,
+        service_name: impl AsRef<str>,
+        routing_key: u64,
+        headers: &[Header],
     ) -> Option<Deployment> {
         let schemas = self.0.load();
         let service = schemas.services.get(service_name.as_ref())?;
-        match &service.location {
-            ServiceLocation::BuiltIn { .. } => None,
-            ServiceLocation::Deployment {
-                latest_deployment, ..
-            } => schemas
-                .deployments
-                .get(latest_deployment)
-                .map(|schemas| Deployment {
-                    id: *latest_deployment,
-                    metadata: schemas.metadata.clone(),
-                }),
-        }
+
+        // Fall back to the latest deployment if the selected revision is no longer available
+        let deployment_id = service
+            .routing
+            .select_revision(routing_key, headers)
+            .filter(|revision| *revision != service.revision)
+            .and_then(|revision| {
+                schemas.find_deployment_for_service_revision(service_name.as_ref(), revision)
+            })
+            .or_else(|| schemas.find_latest_deployment_for_service(service_name.as_ref()))?;
+
+        schemas
+            .deployments
+            .get(&deployment_id)
+            .map(|schemas| Deployment {
+                id: deployment_id,
+                metadata: schemas.metadata.clone(),
+            })
     }
 
     fn get_deployment(&self, deployment_id: &DeploymentId) -> Option<Deployment> {
@@ -48,6 +71,25 @@ impl DeploymentResolver for Schemas {
             })
     }
 
+    fn get_deployment_state(&self, deployment_id: &DeploymentId) -> Option<DeploymentState> {
+        let schemas = self.0.load();
+        schemas
+            .deployments
+            .get(deployment_id)
+            .map(|schemas| schemas.state)
+    }
+
+    fn resolve_service_configuration(
+        &self,
+        service_name: impl AsRef<str>,
+    ) -> Option<ServiceConfiguration> {
+        let schemas = self.0.load();
+        schemas
+            .services
+            .get(service_name.as_ref())
+            .map(|schemas| schemas.configuration.clone())
+    }
+
     fn get_deployment_descriptor_pool(&self, deployment_id: &DeploymentId) -> Option<Bytes> {
         let schemas = self.0.load();
         schemas
@@ -9,6 +9,7 @@
 // by the Apache License, Version 2.0.
 
 use super::Schemas;
+use crate::json_manifest::wrapped_value_field;
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use prost::Message;
@@ -16,6 +17,7 @@ use prost_reflect::{DeserializeOptions, DynamicMessage, MessageDescriptor, Seria
 use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper, ProtobufToJsonMapper};
 
 pub struct JsonToProtobufConverter(MessageDescriptor);
+#[derive(Clone)]
 pub struct ProtobufToJsonConverter(MessageDescriptor);
 
 impl JsonMapperResolver for Schemas {
@@ -39,6 +41,24 @@ impl JsonMapperResolver for Schemas {
         })
         .flatten()
     }
+
+    fn resolve_reverse_json_mapper_for_service(
+        &self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+    ) -> Option<(Self::JsonToProtobufMapper, Self::ProtobufToJsonMapper)> {
+        self.use_service_schema(service_name, |service_schemas| {
+            let method_desc = service_schemas
+                .methods
+                .get(method_name.as_ref())?
+                .descriptor();
+            Some((
+                JsonToProtobufConverter(method_desc.output()),
+                ProtobufToJsonConverter(method_desc.input()),
+            ))
+        })
+        .flatten()
+    }
 }
 
 mod json_impl {
@@ -46,12 +66,20 @@ mod json_impl {
     use anyhow::Error;
     use serde_json::Value;
 
+    // The messages synthesized from a JSON services manifest might wrap the payload in a `value` field,
+    // see the json_manifest module. Their JSON representation is the wrapped value.
+
     impl JsonToProtobufMapper for JsonToProtobufConverter {
         fn json_to_protobuf(
             self,
             json: Bytes,
             deserialize_options: &DeserializeOptions,
         ) -> Result<Bytes, anyhow::Error> {
+            if wrapped_value_field(&self.0).is_some() {
+                let json = serde_json::from_slice(&json)?;
+                return self.json_value_to_protobuf(json, deserialize_options);
+            }
+
             let mut deser = serde_json::Deserializer::from_reader(json.reader());
             let dynamic_message =
                 DynamicMessage::deserialize_with_options(self.0, &mut deser, deserialize_options)?;
@@ -64,6 +92,12 @@ mod json_impl {
             json: Value,
             deserialize_options: &DeserializeOptions,
         ) -> Result<Bytes, Error> {
+            let json = match wrapped_value_field(&self.0) {
+                Some(field) => {
+                    Value::Object([(field.json_name().to_owned(), json)].into_iter().collect())
+                }
+                None => json,
+            };
             let dynamic_message =
                 DynamicMessage::deserialize_with_options(self.0, json, deserialize_options)?;
             Ok(Bytes::from(dynamic_message.encode_to_vec()))
@@ -76,6 +110,11 @@ mod json_impl {
             protobuf: Bytes,
             serialize_options: &SerializeOptions,
         ) -> Result<Bytes, anyhow::Error> {
+            if wrapped_value_field(&self.0).is_some() {
+                let json = self.protobuf_to_json_value(protobuf, serialize_options)?;
+                return Ok(serde_json::to_vec(&json)?.into());
+            }
+
             let msg = DynamicMessage::decode(self.0, protobuf)?;
             let mut ser = serde_json::Serializer::new(BytesMut::new().writer());
             msg.serialize_with_options(&mut ser, serialize_options)?;
@@ -87,8 +126,25 @@ mod json_impl {
             protobuf: Bytes,
             serialize_options: &SerializeOptions,
         ) -> Result<Value, Error> {
+            let wrapped_value_field = wrapped_value_field(&self.0);
             let msg = DynamicMessage::decode(self.0, protobuf)?;
-            Ok(msg.serialize_with_options(serde_json::value::Serializer, serialize_options)?)
+
+            let Some(field) = wrapped_value_field else {
+                return Ok(
+                    msg.serialize_with_options(serde_json::value::Serializer, serialize_options)?
+                );
+            };
+            // Serialize the default value as well, as it's the whole payload
+            let json = msg.serialize_with_options(
+                serde_json::value::Serializer,
+                &serialize_options.clone().skip_default_fields(false),
+            )?;
+            Ok(match json {
+                Value::Object(mut fields) => {
+                    fields.remove(field.json_name()).unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            })
         }
     }
 }
@@ -139,6 +195,7 @@ mod tests {
                     latest_deployment,
                     public: true,
                 },
+                routing: Default::default(),
             },
         );
         schemas
@@ -0,0 +1,818 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Synthesis of the protobuf descriptors of the services described by a [`JsonServicesManifest`].
+//!
+//! Each service gets its own file, with one request and one response message per handler.
+//! JSON schemas are mapped to proto3 types as follows:
+//!
+//! * `string`, `boolean` and `number` map to `string`, `bool` and `double`
+//! * `integer` maps to `int64`, or to `int32` when the schema has `"format": "int32"`
+//! * `object` with `properties` maps to a nested message, other objects map to `google.protobuf.Struct`
+//! * `array` maps to a repeated field, nested arrays map to `google.protobuf.ListValue`
+//! * Everything else, including unions of several types, maps to `google.protobuf.Value`
+//!
+//! Handlers without input or output schema take/return `google.protobuf.Empty`.
+//! Input and output schemas which are not objects with `properties` are wrapped in a message
+//! with a single `value` field, see [`wrapped_value_field`].
+//!
+//! The number of the field synthesized from a property is set by the
+//! [`FIELD_NUMBER_ANNOTATION`] of the property schema, if present, otherwise it's derived from
+//! the property name alone. Hence adding or removing properties doesn't change the numbers of
+//! the other fields, and the payloads stored in the journals stay readable across deployments.
+
+use crate::schemas_impl::deployment::BadDescriptorError;
+use crate::{Schemas, SchemasUpdateError};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, FieldDescriptor, MessageDescriptor};
+use restate_schema_api::json_manifest::{
+    JsonDeploymentResolver, JsonHandler, JsonService, JsonServicesManifest,
+};
+use restate_schema_api::service::InstanceType;
+use restate_types::identifiers::DeploymentId;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// Prefix of the names of the synthesized files, used to tell apart the deployments registered from a manifest.
+pub(crate) const JSON_MANIFEST_FILE_PREFIX: &str = "restate/json_manifest/";
+
+/// Annotation of a property schema setting the number of the synthesized field.
+pub const FIELD_NUMBER_ANNOTATION: &str = "x-restate-field-number";
+
+const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+const RESERVED_FIELD_NUMBERS: RangeInclusive<u32> = 19000..=19999;
+
+/// Suffix of the names of the messages wrapping a schema which is not an object with `properties`.
+const WRAPPED_VALUE_SUFFIX: &str = "Value";
+const WRAPPED_VALUE_FIELD: &str = "value";
+
+const DEPENDENCIES: [&str; 4] = [
+    "google/protobuf/descriptor.proto",
+    "dev/restate/ext.proto",
+    "google/protobuf/empty.proto",
+    "google/protobuf/struct.proto",
+];
+
+const EMPTY_TYPE: &str = ".google.protobuf.Empty";
+const STRUCT_TYPE: &str = ".google.protobuf.Struct";
+const LIST_VALUE_TYPE: &str = ".google.protobuf.ListValue";
+const VALUE_TYPE: &str = ".google.protobuf.Value";
+
+/// Synthesizes the descriptor pool of the services described by the manifest.
+/// Returns the names of the services together with the pool, as they would be returned by the protobuf discovery.
+pub fn synthesize_descriptor_pool(
+    manifest: &JsonServicesManifest,
+) -> Result<(Vec<String>, DescriptorPool), SchemasUpdateError> {
+    let mut descriptor_pool = DescriptorPool::new();
+    for dependency in DEPENDENCIES {
+        let file = restate_pb::DESCRIPTOR_POOL
+            .get_file_by_name(dependency)
+            .expect(
+                "The built-in descriptor pool must contain the dependencies of synthesized files",
+            );
+        descriptor_pool
+            .decode_file_descriptor_proto(file.encode_to_vec().as_slice())
+            .map_err(BadDescriptorError::Descriptor)?;
+    }
+
+    let mut services = Vec::with_capacity(manifest.services.len());
+    for service in &manifest.services {
+        let file = synthesize_file(service)?;
+        descriptor_pool
+            .decode_file_descriptor_proto(file.encode_to_vec().as_slice())
+            .map_err(BadDescriptorError::Descriptor)?;
+        services.push(service.name.clone());
+    }
+
+    Ok((services, descriptor_pool))
+}
+
+/// Returns `true` if the file was synthesized from a [`JsonServicesManifest`].
+pub(crate) fn is_synthesized_file(file_name: &str) -> bool {
+    file_name.starts_with(JSON_MANIFEST_FILE_PREFIX)
+}
+
+/// Returns the `value` field if the message wraps the input or output schema of a handler
+/// which is not an object with `properties`. The JSON representation of these messages is the wrapped value.
+pub(crate) fn wrapped_value_field(message: &MessageDescriptor) -> Option<FieldDescriptor> {
+    if !is_synthesized_file(message.parent_file().name())
+        || message.parent_message().is_some()
+        || !message.name().ends_with(WRAPPED_VALUE_SUFFIX)
+    {
+        return None;
+    }
+    message.get_field_by_name(WRAPPED_VALUE_FIELD)
+}
+
+impl JsonDeploymentResolver for Schemas {
+    fn is_json_deployment(&self, deployment_id: &DeploymentId) -> bool {
+        self.0
+            .load()
+            .deployments
+            .get(deployment_id)
+            .is_some_and(|deployment| {
+                deployment
+                    .descriptor_pool
+                    .files()
+                    .any(|file| is_synthesized_file(file.name()))
+            })
+    }
+}
+
+fn bad_manifest(msg: impl Into<String>) -> SchemasUpdateError {
+    BadDescriptorError::BadJsonManifest(msg.into()).into()
+}
+
+fn synthesize_file(service: &JsonService) -> Result<pb::FileDescriptorProto, SchemasUpdateError> {
+    let (package, service_short_name) = match service.name.rsplit_once('.') {
+        Some((package, name)) => (Some(package.to_owned()), name),
+        None => (None, service.name.as_str()),
+    };
+    if service_short_name.is_empty() || sanitize_name(service_short_name) != service_short_name {
+        return Err(bad_manifest(format!(
+            "service name '{}' is not a valid identifier",
+            service.name
+        )));
+    }
+
+    let mut message_types = vec![];
+    let mut methods = Vec::with_capacity(service.handlers.len());
+    for handler in &service.handlers {
+        if handler.name.is_empty() || sanitize_name(&handler.name) != handler.name {
+            return Err(bad_manifest(format!(
+                "handler name '{}' of service '{}' is not a valid identifier",
+                handler.name, service.name
+            )));
+        }
+        let type_prefix = format!("{}{}", service_short_name, handler.name);
+
+        let input_type = match &handler.input_schema {
+            Some(schema) => {
+                let (name, message) = synthesize_top_level_message(
+                    package.as_deref(),
+                    format!("{type_prefix}Request"),
+                    schema,
+                )?;
+                message_types.push(check_key_field(service, handler, message)?);
+                name
+            }
+            None if service.instance_type == InstanceType::Keyed => {
+                return Err(bad_manifest(format!(
+                    "handler '{}' of keyed service '{}' must have an input schema containing the key",
+                    handler.name, service.name
+                )));
+            }
+            None => EMPTY_TYPE.to_owned(),
+        };
+        let output_type = match &handler.output_schema {
+            Some(schema) => {
+                let (name, message) = synthesize_top_level_message(
+                    package.as_deref(),
+                    format!("{type_prefix}Response"),
+                    schema,
+                )?;
+                message_types.push(message);
+                name
+            }
+            None => EMPTY_TYPE.to_owned(),
+        };
+
+        methods.push(pb::MethodDescriptorProto {
+            name: Some(handler.name.clone()),
+            input_type: Some(input_type),
+            output_type: Some(output_type),
+        });
+    }
+
+    Ok(pb::FileDescriptorProto {
+        name: Some(format!(
+            "{JSON_MANIFEST_FILE_PREFIX}{}.proto",
+            service.name.replace('.', "/")
+        )),
+        package,
+        dependency: DEPENDENCIES[1..].iter().map(|d| d.to_string()).collect(),
+        message_type: message_types,
+        service: vec![pb::ServiceDescriptorProto {
+            name: Some(service_short_name.to_owned()),
+            method: methods,
+            options: Some(pb::ServiceOptions {
+                service_type: Some(match service.instance_type {
+                    InstanceType::Unkeyed => pb::UNKEYED_SERVICE_TYPE,
+                    InstanceType::Keyed => pb::KEYED_SERVICE_TYPE,
+                    InstanceType::Singleton => pb::SINGLETON_SERVICE_TYPE,
+                }),
+            }),
+        }],
+        syntax: Some("proto3".to_owned()),
+    })
+}
+
+/// Synthesizes the request/response message of a handler, returning its fully qualified type name.
+/// Schemas which are not objects are wrapped in a message with a single `value` field,
+/// whose name ends with [`WRAPPED_VALUE_SUFFIX`].
+fn synthesize_top_level_message(
+    package: Option<&str>,
+    mut name: String,
+    schema: &Value,
+) -> Result<(String, pb::DescriptorProto), SchemasUpdateError> {
+    let is_object = schema_type(schema) == Some("object") && schema.get("properties").is_some();
+    if !is_object {
+        name.push_str(WRAPPED_VALUE_SUFFIX);
+    }
+    let full_name = match package {
+        Some(package) => format!(".{package}.{name}"),
+        None => format!(".{name}"),
+    };
+
+    let message = if is_object {
+        synthesize_message(&full_name, name, schema)?
+    } else {
+        let mut message = pb::DescriptorProto {
+            name: Some(name),
+            ..Default::default()
+        };
+        let field = synthesize_field(
+            &full_name,
+            WRAPPED_VALUE_FIELD,
+            1,
+            schema,
+            &mut message.nested_type,
+        )?;
+        message.field.push(field);
+        message
+    };
+
+    Ok((full_name, message))
+}
+
+fn synthesize_message(
+    full_name: &str,
+    name: String,
+    schema: &Value,
+) -> Result<pb::DescriptorProto, SchemasUpdateError> {
+    let mut message = pb::DescriptorProto {
+        name: Some(name),
+        ..Default::default()
+    };
+
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .ok_or_else(|| bad_manifest(format!("properties of '{full_name}' must be an object")))?;
+    let mut field_names = HashSet::with_capacity(properties.len());
+    let mut field_numbers = HashSet::with_capacity(properties.len());
+    for (property, property_schema) in properties {
+        let number = field_number(full_name, property, property_schema)?;
+        if !field_numbers.insert(number) {
+            return Err(bad_manifest(format!(
+                "the field number {number} of property '{property}' of '{full_name}' conflicts with another property, set a distinct '{FIELD_NUMBER_ANNOTATION}' to one of them"
+            )));
+        }
+        let mut field = synthesize_field(
+            full_name,
+            &sanitize_name(property),
+            number,
+            property_schema,
+            &mut message.nested_type,
+        )?;
+        if !field_names.insert(field.name.clone()) {
+            return Err(bad_manifest(format!(
+                "property '{property}' of '{full_name}' conflicts with another property"
+            )));
+        }
+        field.json_name = Some(property.clone());
+        message.field.push(field);
+    }
+
+    Ok(message)
+}
+
+/// Returns the number of the field synthesized from the property, see [`FIELD_NUMBER_ANNOTATION`].
+fn field_number(
+    full_name: &str,
+    property: &str,
+    property_schema: &Value,
+) -> Result<i32, SchemasUpdateError> {
+    let number = match property_schema.get(FIELD_NUMBER_ANNOTATION) {
+        Some(number) => number
+            .as_u64()
+            .and_then(|number| u32::try_from(number).ok())
+            .filter(|number| {
+                (1..=MAX_FIELD_NUMBER).contains(number) && !RESERVED_FIELD_NUMBERS.contains(number)
+            })
+            .ok_or_else(|| {
+                bad_manifest(format!(
+                    "the '{FIELD_NUMBER_ANNOTATION}' of property '{property}' of '{full_name}' must be a valid protobuf field number"
+                ))
+            })?,
+        None => hashed_field_number(property),
+    };
+    Ok(number as i32)
+}
+
+/// Derives the field number from the 32 bit FNV-1a hash of the property name,
+/// mapped to the valid field numbers outside the reserved range.
+fn hashed_field_number(property: &str) -> u32 {
+    let hash = property.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+
+    let reserved = RESERVED_FIELD_NUMBERS.end() - RESERVED_FIELD_NUMBERS.start() + 1;
+    let number = hash % (MAX_FIELD_NUMBER - reserved) + 1;
+    if number >= *RESERVED_FIELD_NUMBERS.start() {
+        number + reserved
+    } else {
+        number
+    }
+}
+
+fn synthesize_field(
+    parent_full_name: &str,
+    field_name: &str,
+    number: i32,
+    schema: &Value,
+    nested_types: &mut Vec<pb::DescriptorProto>,
+) -> Result<pb::FieldDescriptorProto, SchemasUpdateError> {
+    let mut field = pb::FieldDescriptorProto {
+        name: Some(field_name.to_owned()),
+        number: Some(number),
+        label: Some(pb::LABEL_OPTIONAL),
+        ..Default::default()
+    };
+
+    let (ty, type_name) = match schema_type(schema) {
+        Some("array") => {
+            let items = schema.get("items").unwrap_or(&Value::Null);
+            if schema_type(items) == Some("array") {
+                (pb::TYPE_MESSAGE, Some(LIST_VALUE_TYPE.to_owned()))
+            } else {
+                field.label = Some(pb::LABEL_REPEATED);
+                resolve_type(parent_full_name, field_name, items, nested_types)?
+            }
+        }
+        _ => resolve_type(parent_full_name, field_name, schema, nested_types)?,
+    };
+    field.r#type = Some(ty);
+    field.type_name = type_name;
+
+    Ok(field)
+}
+
+fn resolve_type(
+    parent_full_name: &str,
+    field_name: &str,
+    schema: &Value,
+    nested_types: &mut Vec<pb::DescriptorProto>,
+) -> Result<(i32, Option<String>), SchemasUpdateError> {
+    Ok(match schema_type(schema) {
+        Some("string") => (pb::TYPE_STRING, None),
+        Some("boolean") => (pb::TYPE_BOOL, None),
+        Some("number") => (pb::TYPE_DOUBLE, None),
+        Some("integer") if schema.get("format").and_then(Value::as_str) == Some("int32") => {
+            (pb::TYPE_INT32, None)
+        }
+        Some("integer") => (pb::TYPE_INT64, None),
+        Some("object") if schema.get("properties").is_some() => {
+            let name = to_pascal_case(field_name);
+            let full_name = format!("{parent_full_name}.{name}");
+            nested_types.push(synthesize_message(&full_name, name, schema)?);
+            (pb::TYPE_MESSAGE, Some(full_name))
+        }
+        Some("object") => (pb::TYPE_MESSAGE, Some(STRUCT_TYPE.to_owned())),
+        _ => (pb::TYPE_MESSAGE, Some(VALUE_TYPE.to_owned())),
+    })
+}
+
+/// Annotates the key field of keyed services' input messages.
+fn check_key_field(
+    service: &JsonService,
+    handler: &JsonHandler,
+    mut message: pb::DescriptorProto,
+) -> Result<pb::DescriptorProto, SchemasUpdateError> {
+    match (&service.instance_type, &handler.key) {
+        (InstanceType::Keyed, Some(key)) => {
+            let field = message
+                .field
+                .iter_mut()
+                .find(|field| field.json_name.as_ref() == Some(key))
+                .filter(|field| {
+                    field.r#type == Some(pb::TYPE_STRING)
+                        && field.label == Some(pb::LABEL_OPTIONAL)
+                })
+                .ok_or_else(|| {
+                    bad_manifest(format!(
+                        "the key '{}' of handler '{}' of service '{}' must be a string property of the input schema",
+                        key, handler.name, service.name
+                    ))
+                })?;
+            field.options = Some(pb::FieldOptions {
+                field: Some(pb::KEY_FIELD_TYPE),
+            });
+            Ok(message)
+        }
+        (InstanceType::Keyed, None) => Err(bad_manifest(format!(
+            "handler '{}' of keyed service '{}' must specify the key",
+            handler.name, service.name
+        ))),
+        (_, Some(_)) => Err(bad_manifest(format!(
+            "handler '{}' of service '{}' specifies a key, but the service is not keyed",
+            handler.name, service.name
+        ))),
+        (_, None) => Ok(message),
+    }
+}
+
+/// Returns the type of the schema, ignoring `null` in union types.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(ty),
+        Value::Array(types) => {
+            let mut non_null_types = types
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|ty| *ty != "null");
+            match (non_null_types.next(), non_null_types.next()) {
+                (Some(ty), None) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+mod pb {
+    // Minimal subset of the descriptor.proto messages. We define them here rather than using prost_types,
+    // because the latter don't support the dev.restate.ext extensions in the service and field options.
+
+    pub(super) const LABEL_OPTIONAL: i32 = 1;
+    pub(super) const LABEL_REPEATED: i32 = 3;
+
+    pub(super) const TYPE_DOUBLE: i32 = 1;
+    pub(super) const TYPE_INT64: i32 = 3;
+    pub(super) const TYPE_INT32: i32 = 5;
+    pub(super) const TYPE_BOOL: i32 = 8;
+    pub(super) const TYPE_STRING: i32 = 9;
+    pub(super) const TYPE_MESSAGE: i32 = 11;
+
+    // Values of dev.restate.ext.ServiceType and dev.restate.ext.FieldType
+    pub(super) const UNKEYED_SERVICE_TYPE: i32 = 0;
+    pub(super) const KEYED_SERVICE_TYPE: i32 = 1;
+    pub(super) const SINGLETON_SERVICE_TYPE: i32 = 2;
+    pub(super) const KEY_FIELD_TYPE: i32 = 0;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct FileDescriptorProto {
+        #[prost(string, optional, tag = "1")]
+        pub name: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub package: Option<String>,
+        #[prost(string, repeated, tag = "3")]
+        pub dependency: Vec<String>,
+        #[prost(message, repeated, tag = "4")]
+        pub message_type: Vec<DescriptorProto>,
+        #[prost(message, repeated, tag = "6")]
+        pub service: Vec<ServiceDescriptorProto>,
+        #[prost(string, optional, tag = "12")]
+        pub syntax: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct DescriptorProto {
+        #[prost(string, optional, tag = "1")]
+        pub name: Option<String>,
+        #[prost(message, repeated, tag = "2")]
+        pub field: Vec<FieldDescriptorProto>,
+        #[prost(message, repeated, tag = "3")]
+        pub nested_type: Vec<DescriptorProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct FieldDescriptorProto {
+        #[prost(string, optional, tag = "1")]
+        pub name: Option<String>,
+        #[prost(int32, optional, tag = "3")]
+        pub number: Option<i32>,
+        #[prost(int32, optional, tag = "4")]
+        pub label: Option<i32>,
+        #[prost(int32, optional, tag = "5")]
+        pub r#type: Option<i32>,
+        #[prost(string, optional, tag = "6")]
+        pub type_name: Option<String>,
+        #[prost(string, optional, tag = "10")]
+        pub json_name: Option<String>,
+        #[prost(message, optional, tag = "8")]
+        pub options: Option<FieldOptions>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct FieldOptions {
+        /// dev.restate.ext.field
+        #[prost(int32, optional, tag = "51234")]
+        pub field: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ServiceDescriptorProto {
+        #[prost(string, optional, tag = "1")]
+        pub name: Option<String>,
+        #[prost(message, repeated, tag = "2")]
+        pub method: Vec<MethodDescriptorProto>,
+        #[prost(message, optional, tag = "3")]
+        pub options: Option<ServiceOptions>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct ServiceOptions {
+        /// dev.restate.ext.service_type
+        #[prost(int32, optional, tag = "51234")]
+        pub service_type: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct MethodDescriptorProto {
+        #[prost(string, optional, tag = "1")]
+        pub name: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub input_type: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub output_type: Option<String>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+    use prost_reflect::{DeserializeOptions, Kind, SerializeOptions, ServiceDescriptor};
+    use restate_schema_api::deployment::Deployment;
+    use restate_schema_api::json::{
+        JsonMapperResolver, JsonToProtobufMapper, ProtobufToJsonMapper,
+    };
+    use restate_schema_api::service::ServiceMetadataResolver;
+    use serde_json::json;
+    use test_log::test;
+
+    fn greeter_manifest() -> JsonServicesManifest {
+        serde_json::from_value(json!({
+            "services": [{
+                "name": "greeter.Greeter",
+                "instanceType": "Keyed",
+                "handlers": [{
+                    "name": "Greet",
+                    "key": "user-id",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "user-id": { "type": "string" },
+                            "count": { "type": "integer" },
+                            "tags": { "type": "array", "items": { "type": "string" } },
+                            "address": {
+                                "type": "object",
+                                "properties": { "city": { "type": "string" } }
+                            },
+                            "extra": { "type": "object" }
+                        }
+                    },
+                    "outputSchema": { "type": "string" }
+                }, {
+                    "name": "Reset",
+                    "key": "user-id",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "user-id": { "type": "string" } }
+                    }
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn greet_input(pool: &DescriptorPool) -> prost_reflect::MessageDescriptor {
+        pool.get_service_by_name("greeter.Greeter")
+            .and_then(|svc: ServiceDescriptor| svc.methods().find(|m| m.name() == "Greet"))
+            .unwrap()
+            .input()
+    }
+
+    #[test]
+    fn synthesize_keyed_service() {
+        let (services, pool) = synthesize_descriptor_pool(&greeter_manifest()).unwrap();
+        assert_eq!(services, vec!["greeter.Greeter".to_owned()]);
+
+        let input = greet_input(&pool);
+        assert_eq!(input.full_name(), "greeter.GreeterGreetRequest");
+
+        let key_field = input.get_field_by_json_name("user-id").unwrap();
+        assert_eq!(key_field.name(), "user_id");
+        assert_eq!(key_field.kind(), Kind::String);
+
+        let count_field = input.get_field_by_name("count").unwrap();
+        assert_eq!(count_field.kind(), Kind::Int64);
+        assert!(input.get_field_by_name("tags").unwrap().is_list());
+        assert_eq!(
+            input
+                .get_field_by_name("address")
+                .unwrap()
+                .kind()
+                .as_message()
+                .unwrap()
+                .full_name(),
+            "greeter.GreeterGreetRequest.Address"
+        );
+        assert_eq!(
+            input
+                .get_field_by_name("extra")
+                .unwrap()
+                .kind()
+                .as_message()
+                .unwrap()
+                .full_name(),
+            "google.protobuf.Struct"
+        );
+
+        let method = pool
+            .get_service_by_name("greeter.Greeter")
+            .unwrap()
+            .methods()
+            .find(|m| m.name() == "Reset")
+            .unwrap();
+        assert_eq!(method.output().full_name(), "google.protobuf.Empty");
+    }
+
+    #[test]
+    fn field_numbers_are_stable() {
+        let (_, pool) = synthesize_descriptor_pool(&greeter_manifest()).unwrap();
+        let count_number = greet_input(&pool)
+            .get_field_by_name("count")
+            .unwrap()
+            .number();
+
+        let mut manifest = greeter_manifest();
+        let input_schema = manifest.services[0].handlers[0]
+            .input_schema
+            .as_mut()
+            .unwrap();
+        let properties = input_schema["properties"].as_object_mut().unwrap();
+        properties.remove("tags");
+        properties.insert("age".to_owned(), json!({ "type": "integer" }));
+        properties.insert(
+            "nickname".to_owned(),
+            json!({ "type": "string", FIELD_NUMBER_ANNOTATION: 7 }),
+        );
+
+        let (_, pool) = synthesize_descriptor_pool(&manifest).unwrap();
+        let input = greet_input(&pool);
+        assert_eq!(
+            input.get_field_by_name("count").unwrap().number(),
+            count_number
+        );
+        assert_eq!(input.get_field_by_name("nickname").unwrap().number(), 7);
+        assert!(input
+            .fields()
+            .all(|field| !RESERVED_FIELD_NUMBERS.contains(&field.number())));
+    }
+
+    #[test]
+    fn conflicting_field_numbers() {
+        let (_, pool) = synthesize_descriptor_pool(&greeter_manifest()).unwrap();
+        let count_number = greet_input(&pool)
+            .get_field_by_name("count")
+            .unwrap()
+            .number();
+
+        let mut manifest = greeter_manifest();
+        manifest.services[0].handlers[0]
+            .input_schema
+            .as_mut()
+            .unwrap()["properties"]["extra"][FIELD_NUMBER_ANNOTATION] = json!(count_number);
+        assert!(matches!(
+            synthesize_descriptor_pool(&manifest).unwrap_err(),
+            SchemasUpdateError::BadDescriptor(BadDescriptorError::BadJsonManifest(_))
+        ));
+
+        let mut manifest = greeter_manifest();
+        manifest.services[0].handlers[0]
+            .input_schema
+            .as_mut()
+            .unwrap()["properties"]["extra"][FIELD_NUMBER_ANNOTATION] = json!(19000);
+        assert!(matches!(
+            synthesize_descriptor_pool(&manifest).unwrap_err(),
+            SchemasUpdateError::BadDescriptor(BadDescriptorError::BadJsonManifest(_))
+        ));
+    }
+
+    fn register_greeter(schemas: &Schemas) -> DeploymentId {
+        let (services, pool) = synthesize_descriptor_pool(&greeter_manifest()).unwrap();
+        let deployment = Deployment::mock_with_uri("http://localhost:9080");
+        let commands = schemas
+            .compute_new_deployment(
+                Some(deployment.id),
+                deployment.metadata,
+                services,
+                pool,
+                false,
+            )
+            .unwrap();
+        schemas.apply_updates(commands).unwrap();
+        deployment.id
+    }
+
+    #[test]
+    fn register_synthesized_service() {
+        let schemas = Schemas::default();
+        let deployment_id = register_greeter(&schemas);
+        assert!(schemas.is_json_deployment(&deployment_id));
+
+        let metadata = schemas
+            .resolve_latest_service_metadata("greeter.Greeter")
+            .unwrap();
+        assert_eq!(metadata.instance_type, InstanceType::Keyed);
+        assert_eq!(metadata.methods.len(), 2);
+    }
+
+    #[test]
+    fn map_wrapped_value() {
+        let schemas = Schemas::default();
+        register_greeter(&schemas);
+
+        let (from_json, to_json) = schemas
+            .resolve_reverse_json_mapper_for_service("greeter.Greeter", "Greet")
+            .unwrap();
+        let protobuf = from_json
+            .json_to_protobuf(
+                Bytes::from_static(b"\"Hello Francesco\""),
+                &DeserializeOptions::default(),
+            )
+            .unwrap();
+        let input = to_json
+            .protobuf_to_json_value(Bytes::new(), &SerializeOptions::default())
+            .unwrap();
+        assert!(input.is_object());
+
+        let (_, to_json) = schemas
+            .resolve_json_mapper_for_service("greeter.Greeter", "Greet")
+            .unwrap();
+        assert_eq!(
+            to_json
+                .protobuf_to_json(protobuf, &SerializeOptions::default())
+                .unwrap(),
+            Bytes::from_static(b"\"Hello Francesco\"")
+        );
+
+        // Default values are part of the payload
+        let (_, to_json) = schemas
+            .resolve_json_mapper_for_service("greeter.Greeter", "Greet")
+            .unwrap();
+        assert_eq!(
+            to_json
+                .protobuf_to_json_value(Bytes::new(), &SerializeOptions::default())
+                .unwrap(),
+            json!("")
+        );
+    }
+
+    #[test]
+    fn keyed_service_requires_string_key() {
+        let mut manifest = greeter_manifest();
+        manifest.services[0].handlers[0].key = Some("count".to_owned());
+
+        assert!(matches!(
+            synthesize_descriptor_pool(&manifest).unwrap_err(),
+            SchemasUpdateError::BadDescriptor(BadDescriptorError::BadJsonManifest(_))
+        ));
+    }
+}
@@ -9,10 +9,13 @@
 // by the Apache License, Version 2.0.
 
 use arc_swap::ArcSwap;
+use bytes::Bytes;
 use http::Uri;
 use prost_reflect::{DescriptorPool, ServiceDescriptor};
-use restate_schema_api::deployment::DeploymentMetadata;
-use restate_schema_api::service::ServiceMetadata;
+use restate_schema_api::deployment::{DeploymentMetadata, DeploymentState};
+use restate_schema_api::service::{
+    ServiceCallPolicy, ServiceConfiguration, ServiceMetadata, ServiceRouting,
+};
 use restate_schema_api::subscription::{Subscription, SubscriptionValidator};
 use restate_types::identifiers::{DeploymentId, ServiceRevision, SubscriptionId};
 use serde::{Deserialize, Serialize};
@@ -22,6 +25,7 @@ use std::sync::Arc;
 mod deployment;
 mod json;
 mod json_key_conversion;
+pub mod json_manifest;
 mod key_expansion;
 mod key_extraction;
 mod proto_symbol;
@@ -29,6 +33,7 @@ mod schemas_impl;
 mod service;
 mod subscriptions;
 
+pub use self::schemas_impl::compatibility::WireIncompatibility;
 use self::schemas_impl::deployment::{BadDescriptorError, IncompatibleServiceChangeError};
 use self::schemas_impl::InstanceTypeMetadata;
 use self::schemas_impl::ServiceSchemas;
@@ -114,6 +119,22 @@ pub enum SchemasUpdateCommand {
     },
     AddSubscription(Subscription),
     RemoveSubscription(SubscriptionId),
+    ModifyServiceRouting {
+        name: String,
+        routing: ServiceRouting,
+    },
+    ModifyDeploymentState {
+        deployment_id: DeploymentId,
+        state: DeploymentState,
+    },
+    ModifyServiceConfiguration {
+        name: String,
+        configuration: ServiceConfiguration,
+    },
+    ModifyServiceCallPolicy {
+        name: String,
+        call_policy: ServiceCallPolicy,
+    },
 }
 
 mod descriptor_pool_serde {
@@ -166,11 +187,21 @@ pub enum SchemasUpdateError {
     ModifyInternalService(String),
     #[error("unknown deployment id {0}")]
     UnknownDeployment(DeploymentId),
+    #[error("invalid routing for service {0}: {1}")]
+    InvalidServiceRouting(String, String),
+    #[error("invalid configuration for service {0}: {1}")]
+    InvalidServiceConfiguration(String, String),
+    #[error("cannot drain deployment {0}, as no other deployment serves the latest revision of the service {1}")]
+    DrainLatestDeployment(DeploymentId, String),
+    #[error("cannot import revision {1} of service {0}, as the registry already contains the revision {2}")]
+    ImportOutdatedServiceRevision(String, ServiceRevision, ServiceRevision),
     #[error("unknown subscription id {0}")]
     UnknownSubscription(SubscriptionId),
     #[error("invalid subscription: {0}")]
     #[code(restate_errors::META0009)]
     InvalidSubscription(anyhow::Error),
+    #[error("cannot reset the offsets of subscription {0}, as only Kafka sources support it")]
+    UnsupportedOffsetsReset(SubscriptionId),
     #[error("a subscription with the same id {0} already exists in the registry")]
     OverrideSubscription(SubscriptionId),
     #[error(transparent)]
@@ -220,6 +251,18 @@ impl Schemas {
         )
     }
 
+    /// Compute the changes of the given services, as described in the descriptor pool,
+    /// which break the wire compatibility with their registered revisions.
+    pub fn compute_wire_incompatibilities(
+        &self,
+        services: Vec<String>,
+        descriptor_pool: &DescriptorPool,
+    ) -> Result<Vec<WireIncompatibility>, SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_wire_incompatibilities(services, descriptor_pool)
+    }
+
     pub fn compute_modify_service(
         &self,
         service_name: String,
@@ -230,6 +273,36 @@ impl Schemas {
             .compute_modify_service_updates(service_name, public)
     }
 
+    pub fn compute_modify_service_routing(
+        &self,
+        service_name: String,
+        routing: ServiceRouting,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_modify_service_routing(service_name, routing)
+    }
+
+    pub fn compute_modify_service_configuration(
+        &self,
+        service_name: String,
+        configuration: ServiceConfiguration,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_modify_service_configuration(service_name, configuration)
+    }
+
+    pub fn compute_modify_service_call_policy(
+        &self,
+        service_name: String,
+        call_policy: ServiceCallPolicy,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_modify_service_call_policy(service_name, call_policy)
+    }
+
     pub fn compute_remove_deployment(
         &self,
         deployment_id: DeploymentId,
@@ -237,6 +310,41 @@ impl Schemas {
         self.0.load().compute_remove_deployment(deployment_id)
     }
 
+    /// Compute the commands to register a deployment exported from another registry, keeping its id
+    /// and the revisions of its services. If the deployment is already registered, no commands are returned.
+    ///
+    /// The `descriptor_pool` is the encoded `google.protobuf.FileDescriptorSet` of the deployment.
+    pub fn compute_import_deployment(
+        &self,
+        deployment_id: DeploymentId,
+        deployment_metadata: DeploymentMetadata,
+        services: Vec<(String, ServiceRevision)>,
+        descriptor_pool: Bytes,
+        force: bool,
+    ) -> Result<Vec<SchemasUpdateCommand>, SchemasUpdateError> {
+        self.0.load().compute_import_deployment(
+            deployment_id,
+            deployment_metadata,
+            services,
+            descriptor_pool,
+            force,
+        )
+    }
+
+    pub fn compute_drain_deployment(
+        &self,
+        deployment_id: DeploymentId,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        self.0.load().compute_drain_deployment(deployment_id)
+    }
+
+    pub fn compute_undrain_deployment(
+        &self,
+        deployment_id: DeploymentId,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        self.0.load().compute_undrain_deployment(deployment_id)
+    }
+
     // Returns the [`Subscription`] id together with the update command
     pub fn compute_add_subscription<V: SubscriptionValidator>(
         &self,
@@ -251,6 +359,40 @@ impl Schemas {
             .compute_add_subscription(id, source, sink, metadata, validator)
     }
 
+    // Returns the updated [`Subscription`] together with the update command
+    pub fn compute_update_subscription<V: SubscriptionValidator>(
+        &self,
+        id: SubscriptionId,
+        sink: Option<Uri>,
+        metadata: Option<HashMap<String, String>>,
+        paused: Option<bool>,
+        validator: V,
+    ) -> Result<(Subscription, SchemasUpdateCommand), SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_update_subscription(id, sink, metadata, paused, validator)
+    }
+
+    /// Compute the command to register a subscription exported from another registry, keeping its id.
+    /// If the same subscription is already registered, no command is returned.
+    pub fn compute_import_subscription<V: SubscriptionValidator>(
+        &self,
+        subscription: Subscription,
+        validator: V,
+    ) -> Result<Option<SchemasUpdateCommand>, SchemasUpdateError> {
+        self.0
+            .load()
+            .compute_import_subscription(subscription, validator)
+    }
+
+    // Returns the [`Subscription`] with a new offsets generation together with the update command
+    pub fn compute_reset_subscription_offsets(
+        &self,
+        id: SubscriptionId,
+    ) -> Result<(Subscription, SchemasUpdateCommand), SchemasUpdateError> {
+        self.0.load().compute_reset_subscription_offsets(id)
+    }
+
     pub fn compute_remove_subscription(
         &self,
         id: SubscriptionId,
@@ -258,6 +400,18 @@ impl Schemas {
         self.0.load().compute_remove_subscription(id)
     }
 
+    /// Compute the commands rebuilding the current state of the schema registry, when applied to an empty registry.
+    /// Useful to compact the history of the applied commands.
+    pub fn compute_snapshot(&self) -> Result<Vec<SchemasUpdateCommand>, SchemasUpdateError> {
+        self.0.load().compute_snapshot()
+    }
+
+    /// Returns a copy of the schema registry, which is not affected by the updates applied to this one, and vice versa.
+    /// Useful to compute commands depending on each other, without applying them to this registry.
+    pub fn fork(&self) -> Schemas {
+        Schemas(Arc::new(ArcSwap::new(self.0.load_full())))
+    }
+
     /// Apply the updates to the schema registry.
     /// This method will update the internal pointer to the in-memory schema registry,
     /// propagating the changes to every component consuming it.
@@ -292,6 +446,24 @@ impl Schemas {
                 SchemasUpdateCommand::ModifyService { name, public } => {
                     schemas_inner.apply_modify_service(name, public)?;
                 }
+                SchemasUpdateCommand::ModifyServiceRouting { name, routing } => {
+                    schemas_inner.apply_modify_service_routing(name, routing)?;
+                }
+                SchemasUpdateCommand::ModifyServiceConfiguration {
+                    name,
+                    configuration,
+                } => {
+                    schemas_inner.apply_modify_service_configuration(name, configuration)?;
+                }
+                SchemasUpdateCommand::ModifyServiceCallPolicy { name, call_policy } => {
+                    schemas_inner.apply_modify_service_call_policy(name, call_policy)?;
+                }
+                SchemasUpdateCommand::ModifyDeploymentState {
+                    deployment_id,
+                    state,
+                } => {
+                    schemas_inner.apply_modify_deployment_state(deployment_id, state)?;
+                }
                 SchemasUpdateCommand::AddSubscription(sub) => {
                     schemas_inner.apply_add_subscription(sub)?;
                 }
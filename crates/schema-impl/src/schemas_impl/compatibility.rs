@@ -0,0 +1,226 @@
+use super::*;
+
+use prost_reflect::{EnumDescriptor, FieldDescriptor, MessageDescriptor};
+use std::collections::HashSet;
+
+/// Change of a service revision which breaks the wire compatibility with the previous revision.
+/// Such changes break the in-flight invocations and the stored journals of the service.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WireIncompatibility {
+    #[error("field '{message}.{field}' changed number from {old} to {new}")]
+    FieldNumberChanged {
+        message: String,
+        field: String,
+        old: u32,
+        new: u32,
+    },
+    #[error("field number {number} of '{message}' changed type from {old} to {new}")]
+    FieldTypeChanged {
+        message: String,
+        number: u32,
+        old: String,
+        new: String,
+    },
+    #[error("field number {number} of '{message}' changed cardinality from {old} to {new}")]
+    FieldCardinalityChanged {
+        message: String,
+        number: u32,
+        old: &'static str,
+        new: &'static str,
+    },
+    #[error("key field of method '{method}' changed number from {old} to {new}")]
+    KeyFieldChanged { method: String, old: u32, new: u32 },
+    #[error("value {number} ('{value}') of enum '{enum_name}' was removed")]
+    EnumValueRemoved {
+        enum_name: String,
+        number: i32,
+        value: String,
+    },
+}
+
+/// Checks the methods of the new revision of a service against the ones of the registered revision,
+/// in the fashion of `buf breaking` with the wire rules.
+pub(crate) fn check_service_wire_compatibility(
+    existing_service: &ServiceSchemas,
+    service_descriptor: &ServiceDescriptor,
+    instance_type: &InstanceTypeMetadata,
+) -> Vec<WireIncompatibility> {
+    let mut checker = WireCompatibilityChecker::default();
+
+    for method in service_descriptor.methods() {
+        let Some(existing_method) = existing_service.methods.get(method.name()) else {
+            continue;
+        };
+        let existing_method = existing_method.descriptor();
+        checker.check_message(&existing_method.input(), &method.input());
+        checker.check_message(&existing_method.output(), &method.output());
+    }
+
+    if let (
+        InstanceTypeMetadata::Keyed {
+            service_methods_key_field_root_number: existing_key_fields,
+            ..
+        },
+        InstanceTypeMetadata::Keyed {
+            service_methods_key_field_root_number: key_fields,
+            ..
+        },
+    ) = (&existing_service.instance_type, instance_type)
+    {
+        let mut methods: Vec<_> = existing_key_fields.keys().collect();
+        methods.sort();
+        for method in methods {
+            let old = existing_key_fields[method];
+            match key_fields.get(method) {
+                Some(new) if *new != old => {
+                    checker
+                        .incompatibilities
+                        .push(WireIncompatibility::KeyFieldChanged {
+                            method: format!("{}/{}", service_descriptor.full_name(), method),
+                            old,
+                            new: *new,
+                        })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    checker.incompatibilities
+}
+
+#[derive(Default)]
+struct WireCompatibilityChecker {
+    // Pairs of (old, new) message and enum names already checked, to cope with recursive types
+    visited: HashSet<(String, String)>,
+    incompatibilities: Vec<WireIncompatibility>,
+}
+
+impl WireCompatibilityChecker {
+    fn check_message(&mut self, old: &MessageDescriptor, new: &MessageDescriptor) {
+        if !self
+            .visited
+            .insert((old.full_name().to_owned(), new.full_name().to_owned()))
+        {
+            return;
+        }
+
+        for old_field in old.fields() {
+            if let Some(new_field) = new.get_field_by_name(old_field.name()) {
+                if new_field.number() != old_field.number() {
+                    self.incompatibilities
+                        .push(WireIncompatibility::FieldNumberChanged {
+                            message: new.full_name().to_owned(),
+                            field: old_field.name().to_owned(),
+                            old: old_field.number(),
+                            new: new_field.number(),
+                        });
+                }
+            }
+            if let Some(new_field) = new.get_field(old_field.number()) {
+                self.check_field(new, &old_field, &new_field);
+            }
+        }
+    }
+
+    fn check_field(
+        &mut self,
+        message: &MessageDescriptor,
+        old: &FieldDescriptor,
+        new: &FieldDescriptor,
+    ) {
+        if cardinality(old) != cardinality(new) {
+            self.incompatibilities
+                .push(WireIncompatibility::FieldCardinalityChanged {
+                    message: message.full_name().to_owned(),
+                    number: new.number(),
+                    old: cardinality(old),
+                    new: cardinality(new),
+                });
+            return;
+        }
+
+        match (old.kind(), new.kind()) {
+            (Kind::Message(old_message), Kind::Message(new_message)) => {
+                self.check_message(&old_message, &new_message)
+            }
+            (Kind::Enum(old_enum), Kind::Enum(new_enum)) => self.check_enum(&old_enum, &new_enum),
+            (old_kind, new_kind) if wire_class(&old_kind) == wire_class(&new_kind) => {}
+            (old_kind, new_kind) => {
+                self.incompatibilities
+                    .push(WireIncompatibility::FieldTypeChanged {
+                        message: message.full_name().to_owned(),
+                        number: new.number(),
+                        old: kind_name(&old_kind),
+                        new: kind_name(&new_kind),
+                    })
+            }
+        }
+    }
+
+    fn check_enum(&mut self, old: &EnumDescriptor, new: &EnumDescriptor) {
+        if !self
+            .visited
+            .insert((old.full_name().to_owned(), new.full_name().to_owned()))
+        {
+            return;
+        }
+
+        for old_value in old.values() {
+            if new.get_value(old_value.number()).is_none() {
+                self.incompatibilities
+                    .push(WireIncompatibility::EnumValueRemoved {
+                        enum_name: new.full_name().to_owned(),
+                        number: old_value.number(),
+                        value: old_value.name().to_owned(),
+                    });
+            }
+        }
+    }
+}
+
+fn cardinality(field: &FieldDescriptor) -> &'static str {
+    if field.is_map() {
+        "map"
+    } else if field.is_list() {
+        "repeated"
+    } else {
+        "singular"
+    }
+}
+
+/// Kinds within the same class can be decoded from each other's encoding.
+#[derive(PartialEq, Eq)]
+enum WireClass {
+    Varint,
+    ZigZag,
+    Fixed32,
+    Fixed64,
+    Float,
+    Double,
+    LengthDelimited,
+    Message,
+}
+
+fn wire_class(kind: &Kind) -> WireClass {
+    match kind {
+        Kind::Int32 | Kind::Int64 | Kind::Uint32 | Kind::Uint64 | Kind::Bool | Kind::Enum(_) => {
+            WireClass::Varint
+        }
+        Kind::Sint32 | Kind::Sint64 => WireClass::ZigZag,
+        Kind::Fixed32 | Kind::Sfixed32 => WireClass::Fixed32,
+        Kind::Fixed64 | Kind::Sfixed64 => WireClass::Fixed64,
+        Kind::Float => WireClass::Float,
+        Kind::Double => WireClass::Double,
+        Kind::String | Kind::Bytes => WireClass::LengthDelimited,
+        Kind::Message(_) => WireClass::Message,
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(message) => message.full_name().to_owned(),
+        Kind::Enum(enum_descriptor) => enum_descriptor.full_name().to_owned(),
+        kind => format!("{kind:?}").to_lowercase(),
+    }
+}
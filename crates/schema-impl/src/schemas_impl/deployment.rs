@@ -7,6 +7,7 @@ use restate_errors::*;
 use restate_schema_api::deployment::DeploymentType;
 use restate_types::identifiers::DeploymentId;
 
+use crate::schemas_impl::compatibility::{check_service_wire_compatibility, WireIncompatibility};
 use crate::schemas_impl::service::check_service_name_reserved;
 
 const SERVICE_TYPE_EXT: &str = "dev.restate.ext.service_type";
@@ -77,6 +78,9 @@ pub enum BadDescriptorError {
     )]
     #[code(META0008)]
     BadEventMetadataFieldType(MethodDescriptor),
+    #[error("bad JSON services manifest: {0}")]
+    #[code(unknown)]
+    BadJsonManifest(String),
 
     // Errors most likely related to SDK bugs
     #[error("cannot find service '{0}' in descriptor set. This might be a symptom of an SDK bug, or of the build tool/pipeline used to generate the descriptor")]
@@ -105,6 +109,12 @@ pub enum IncompatibleServiceChangeError {
     #[error("the service {0} already exists but the new revision removed the methods {1:?}")]
     #[code(restate_errors::META0006)]
     RemovedMethods(String, Vec<String>),
+    #[error(
+        "the new revision of service {0} is not wire compatible with the previous revision: {}",
+        .1.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    #[code(restate_errors::META0006)]
+    WireIncompatibleChanges(String, Vec<WireIncompatibility>),
 }
 
 impl SchemasInner {
@@ -225,6 +235,33 @@ impl SchemasInner {
                     }
                 }
 
+                let service_descriptor = descriptor_pool
+                    .get_service_by_name(&proposed_service.name)
+                    .expect("service was inferred from the descriptor pool");
+                let wire_incompatibilities = check_service_wire_compatibility(
+                    existing_service,
+                    &service_descriptor,
+                    &instance_type,
+                );
+                if !wire_incompatibilities.is_empty() {
+                    if force {
+                        warn!(
+                            restate.deployment.id = %deployment_id,
+                            restate.deployment.address = %deployment_metadata.address_display(),
+                            "Going to register a revision of service {} which is not wire compatible with the previous one due to a forced deployment update: {:?}. This is a potentially dangerous operation, and might fail in-flight invocations.",
+                            proposed_service.name,
+                            wire_incompatibilities
+                        );
+                    } else {
+                        return Err(SchemasUpdateError::IncompatibleServiceChange(
+                            IncompatibleServiceChangeError::WireIncompatibleChanges(
+                                proposed_service.name.clone(),
+                                wire_incompatibilities,
+                            ),
+                        ));
+                    }
+                }
+
                 if existing_service.instance_type != instance_type {
                     if force {
                         warn!(
@@ -274,6 +311,39 @@ impl SchemasInner {
         Ok(result_commands)
     }
 
+    /// Compute the wire incompatibilities of the given services with their registered revisions.
+    pub(crate) fn compute_wire_incompatibilities(
+        &self,
+        services: Vec<String>,
+        descriptor_pool: &DescriptorPool,
+    ) -> Result<Vec<WireIncompatibility>, SchemasUpdateError> {
+        let services = ServiceRegistrationRequest::infer_all_services_from_descriptor_pool(
+            services,
+            descriptor_pool,
+        )?;
+
+        let mut wire_incompatibilities = vec![];
+        for proposed_service in services {
+            let Some(existing_service) = self.services.get(&proposed_service.name) else {
+                continue;
+            };
+            let service_descriptor = descriptor_pool
+                .get_service_by_name(&proposed_service.name)
+                .expect("service was inferred from the descriptor pool");
+            let instance_type = InstanceTypeMetadata::from_discovered_metadata(
+                proposed_service.instance_type,
+                &proposed_service.methods,
+            );
+            wire_incompatibilities.extend(check_service_wire_compatibility(
+                existing_service,
+                &service_descriptor,
+                &instance_type,
+            ));
+        }
+
+        Ok(wire_incompatibilities)
+    }
+
     pub(crate) fn apply_insert_deployment(
         &mut self,
         deployment_id: DeploymentId,
@@ -315,7 +385,7 @@ impl SchemasInner {
                 });
             }
 
-            // We need to retain the `public` field from previous registrations
+            // We need to retain the `public`, `routing`, `configuration` and `call_policy` fields from previous registrations
             let service_schemas = self
                 .services
                 .entry(name.clone())
@@ -365,6 +435,7 @@ impl SchemasInner {
             deployment_id,
             DeploymentSchemas {
                 metadata,
+                state: DeploymentState::Active,
                 services: deployment_services,
                 descriptor_pool,
             },
@@ -373,6 +444,105 @@ impl SchemasInner {
         Ok(())
     }
 
+    /// Computes the commands to register a deployment from an archive, keeping its id and the revisions of its services.
+    /// Returns no commands if the deployment is already registered.
+    pub(crate) fn compute_import_deployment(
+        &self,
+        deployment_id: DeploymentId,
+        deployment_metadata: DeploymentMetadata,
+        services: Vec<(String, ServiceRevision)>,
+        descriptor_pool: Bytes,
+        force: bool,
+    ) -> Result<Vec<SchemasUpdateCommand>, SchemasUpdateError> {
+        if let Some(existing_deployment) = self.deployments.get(&deployment_id) {
+            return if existing_deployment.metadata.ty.normalized_address()
+                == deployment_metadata.ty.normalized_address()
+            {
+                Ok(vec![])
+            } else {
+                Err(SchemasUpdateError::OverrideDeployment(deployment_id))
+            };
+        }
+
+        // Importing an older revision would overwrite the latest one
+        for (name, revision) in &services {
+            if let Some(existing_service) = self.services.get(name) {
+                if existing_service.revision >= *revision {
+                    return Err(SchemasUpdateError::ImportOutdatedServiceRevision(
+                        name.clone(),
+                        *revision,
+                        existing_service.revision,
+                    ));
+                }
+            }
+        }
+
+        let descriptor_pool =
+            DescriptorPool::decode(descriptor_pool).map_err(BadDescriptorError::Descriptor)?;
+        let mut commands = self.compute_new_deployment(
+            Some(deployment_id),
+            deployment_metadata,
+            services.iter().map(|(name, _)| name.clone()).collect(),
+            descriptor_pool,
+            force,
+        )?;
+        for command in &mut commands {
+            if let SchemasUpdateCommand::InsertDeployment {
+                services: inserted_services,
+                ..
+            } = command
+            {
+                for inserted_service in inserted_services {
+                    if let Some((_, revision)) = services
+                        .iter()
+                        .find(|(name, _)| *name == inserted_service.name)
+                    {
+                        inserted_service.revision = *revision;
+                    }
+                }
+            }
+        }
+
+        Ok(commands)
+    }
+
+    /// Computes the command re-inserting the registered deployment with the revisions of its services,
+    /// inferring the services metadata from the deployment descriptor pool.
+    pub(crate) fn compute_insert_registered_deployment(
+        &self,
+        deployment_id: DeploymentId,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        let deployment_schemas = self
+            .deployments
+            .get(&deployment_id)
+            .ok_or(SchemasUpdateError::UnknownDeployment(deployment_id))?;
+
+        let services = ServiceRegistrationRequest::infer_all_services_from_descriptor_pool(
+            deployment_schemas
+                .services
+                .iter()
+                .map(|svc| svc.name.clone())
+                .collect(),
+            &deployment_schemas.descriptor_pool,
+        )?;
+
+        Ok(SchemasUpdateCommand::InsertDeployment {
+            deployment_id,
+            metadata: deployment_schemas.metadata.clone(),
+            services: services
+                .into_iter()
+                .zip(&deployment_schemas.services)
+                .map(|(request, svc)| InsertServiceUpdateCommand {
+                    name: request.name,
+                    revision: svc.revision,
+                    instance_type: request.instance_type,
+                    methods: request.methods,
+                })
+                .collect(),
+            descriptor_pool: deployment_schemas.descriptor_pool.clone(),
+        })
+    }
+
     pub(crate) fn compute_remove_deployment(
         &self,
         deployment_id: DeploymentId,
@@ -402,6 +572,71 @@ impl SchemasInner {
 
         Ok(())
     }
+
+    pub(crate) fn compute_drain_deployment(
+        &self,
+        deployment_id: DeploymentId,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        let deployment_schemas = self
+            .deployments
+            .get(&deployment_id)
+            .ok_or(SchemasUpdateError::UnknownDeployment(deployment_id))?;
+
+        // New invocations of the latest revisions must still find a deployment
+        for svc in &deployment_schemas.services {
+            let is_latest_revision = self
+                .services
+                .get(&svc.name)
+                .is_some_and(|service_schemas| service_schemas.revision == svc.revision);
+            let served_elsewhere = self.deployments.iter().any(|(id, schemas)| {
+                *id != deployment_id
+                    && !schemas.state.is_draining()
+                    && schemas
+                        .services
+                        .iter()
+                        .any(|other| other.name == svc.name && other.revision == svc.revision)
+            });
+            if is_latest_revision && !served_elsewhere {
+                return Err(SchemasUpdateError::DrainLatestDeployment(
+                    deployment_id,
+                    svc.name.clone(),
+                ));
+            }
+        }
+
+        Ok(SchemasUpdateCommand::ModifyDeploymentState {
+            deployment_id,
+            state: DeploymentState::Draining,
+        })
+    }
+
+    pub(crate) fn compute_undrain_deployment(
+        &self,
+        deployment_id: DeploymentId,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        if !self.deployments.contains_key(&deployment_id) {
+            return Err(SchemasUpdateError::UnknownDeployment(deployment_id));
+        }
+
+        Ok(SchemasUpdateCommand::ModifyDeploymentState {
+            deployment_id,
+            state: DeploymentState::Active,
+        })
+    }
+
+    pub(crate) fn apply_modify_deployment_state(
+        &mut self,
+        deployment_id: DeploymentId,
+        state: DeploymentState,
+    ) -> Result<(), SchemasUpdateError> {
+        let deployment_schemas = self
+            .deployments
+            .get_mut(&deployment_id)
+            .ok_or(SchemasUpdateError::UnknownDeployment(deployment_id))?;
+        deployment_schemas.state = state;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -703,9 +938,13 @@ mod tests {
 
     use test_log::test;
 
-    use restate_schema_api::deployment::{Deployment, DeploymentResolver};
-    use restate_schema_api::service::ServiceMetadataResolver;
+    use restate_schema_api::deployment::{Deployment, DeploymentResolver, DeploymentState};
+    use restate_schema_api::service::{
+        HeaderPin, ServiceCallPolicy, ServiceConfiguration, ServiceMetadataResolver, ServiceRouting,
+    };
     use restate_test_util::{assert, assert_eq, let_assert};
+    use restate_types::invocation::Header;
+    use std::time::Duration;
 
     load_mock_descriptor!(DESCRIPTOR, "generic");
     const GREETER_SERVICE_NAME: &str = "greeter.Greeter";
@@ -800,6 +1039,468 @@ mod tests {
         schemas.assert_service_revision(ANOTHER_GREETER_SERVICE_NAME, 1);
     }
 
+    #[test]
+    fn routing_between_revisions() {
+        let schemas = Schemas::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment_1.id),
+                        deployment_1.metadata.clone(),
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+
+        // Keep all the traffic on the first revision before registering the second one
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_routing(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceRouting {
+                        weights: [(1, 100)].into(),
+                        header_pins: vec![],
+                    },
+                )
+                .unwrap()])
+            .unwrap();
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment_2.id),
+                        deployment_2.metadata.clone(),
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+
+        let resolve = |routing_key, headers: &[Header]| {
+            schemas
+                .resolve_deployment_for_invocation(GREETER_SERVICE_NAME, routing_key, headers)
+                .unwrap()
+                .id
+        };
+        assert_eq!(resolve(42, &[]), deployment_1.id);
+
+        // Canary: 5% of the traffic to the second revision
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_routing(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceRouting {
+                        weights: [(1, 95)].into(),
+                        header_pins: vec![HeaderPin {
+                            name: "x-canary".to_owned(),
+                            value: "true".to_owned(),
+                            revision: 2,
+                        }],
+                    },
+                )
+                .unwrap()])
+            .unwrap();
+        assert_eq!(resolve(42, &[]), deployment_1.id);
+        assert_eq!(resolve(97, &[]), deployment_2.id);
+        assert_eq!(
+            resolve(42, &[Header::new("X-Canary", "true")]),
+            deployment_2.id
+        );
+
+        // Weights over 100% and unknown revisions are rejected
+        let_assert!(
+            Err(SchemasUpdateError::InvalidServiceRouting(_, _)) = schemas
+                .compute_modify_service_routing(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceRouting {
+                        weights: [(1, 60), (2, 60)].into(),
+                        header_pins: vec![],
+                    },
+                )
+        );
+        let_assert!(
+            Err(SchemasUpdateError::InvalidServiceRouting(_, _)) = schemas
+                .compute_modify_service_routing(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceRouting {
+                        weights: [(3, 10)].into(),
+                        header_pins: vec![],
+                    },
+                )
+        );
+    }
+
+    #[test]
+    fn drain_deployment() {
+        let schemas = Schemas::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment_1.id),
+                        deployment_1.metadata.clone(),
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+
+        // Cannot drain the only deployment serving the latest revision
+        let_assert!(
+            Err(SchemasUpdateError::DrainLatestDeployment(_, _)) =
+                schemas.compute_drain_deployment(deployment_1.id)
+        );
+
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_routing(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceRouting {
+                        weights: [(1, 100)].into(),
+                        header_pins: vec![],
+                    },
+                )
+                .unwrap()])
+            .unwrap();
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment_2.id),
+                        deployment_2.metadata.clone(),
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+
+        schemas
+            .apply_updates(vec![schemas
+                .compute_drain_deployment(deployment_1.id)
+                .unwrap()])
+            .unwrap();
+        assert_eq!(
+            schemas.get_deployment_state(&deployment_1.id),
+            Some(DeploymentState::Draining)
+        );
+
+        // New invocations skip the draining deployment, regardless of the routing
+        assert_eq!(
+            schemas
+                .resolve_deployment_for_invocation(GREETER_SERVICE_NAME, 42, &[])
+                .unwrap()
+                .id,
+            deployment_2.id
+        );
+        schemas.assert_resolves_deployment(GREETER_SERVICE_NAME, deployment_2.id);
+
+        // Undraining makes the deployment accept new invocations again
+        schemas
+            .apply_updates(vec![schemas
+                .compute_undrain_deployment(deployment_1.id)
+                .unwrap()])
+            .unwrap();
+        assert_eq!(
+            schemas.get_deployment_state(&deployment_1.id),
+            Some(DeploymentState::Active)
+        );
+        assert_eq!(
+            schemas
+                .resolve_deployment_for_invocation(GREETER_SERVICE_NAME, 42, &[])
+                .unwrap()
+                .id,
+            deployment_1.id
+        );
+
+        let unknown_deployment = Deployment::mock_with_uri("http://localhost:9082");
+        let_assert!(
+            Err(SchemasUpdateError::UnknownDeployment(_)) =
+                schemas.compute_undrain_deployment(unknown_deployment.id)
+        );
+    }
+
+    #[test]
+    fn snapshot_rebuilds_registry() {
+        let schemas = Schemas::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+        let deployment_3 = Deployment::mock_with_uri("http://localhost:9082");
+
+        let register = |deployment: &Deployment, services: &[&str]| {
+            schemas
+                .apply_updates(
+                    schemas
+                        .compute_new_deployment(
+                            Some(deployment.id),
+                            deployment.metadata.clone(),
+                            services.iter().map(|svc| svc.to_string()).collect(),
+                            DESCRIPTOR.clone(),
+                            false,
+                        )
+                        .unwrap(),
+                )
+                .unwrap();
+        };
+        register(
+            &deployment_1,
+            &[GREETER_SERVICE_NAME, ANOTHER_GREETER_SERVICE_NAME],
+        );
+        register(&deployment_2, &[GREETER_SERVICE_NAME]);
+        schemas
+            .apply_updates(vec![
+                schemas
+                    .compute_modify_service(ANOTHER_GREETER_SERVICE_NAME.to_owned(), false)
+                    .unwrap(),
+                schemas
+                    .compute_modify_service_routing(
+                        GREETER_SERVICE_NAME.to_owned(),
+                        ServiceRouting {
+                            weights: [(1, 100)].into(),
+                            header_pins: vec![],
+                        },
+                    )
+                    .unwrap(),
+                schemas
+                    .compute_modify_service_configuration(
+                        GREETER_SERVICE_NAME.to_owned(),
+                        ServiceConfiguration {
+                            inactivity_timeout: Some(Duration::from_secs(5).into()),
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap(),
+                schemas
+                    .compute_modify_service_call_policy(
+                        GREETER_SERVICE_NAME.to_owned(),
+                        ServiceCallPolicy {
+                            allowed_callers: Some([ANOTHER_GREETER_SERVICE_NAME.to_owned()].into()),
+                        },
+                    )
+                    .unwrap(),
+            ])
+            .unwrap();
+        register(&deployment_3, &[GREETER_SERVICE_NAME]);
+        schemas
+            .apply_updates(vec![schemas
+                .compute_drain_deployment(deployment_2.id)
+                .unwrap()])
+            .unwrap();
+
+        let rebuilt_schemas = Schemas::default();
+        rebuilt_schemas
+            .apply_updates(schemas.compute_snapshot().unwrap())
+            .unwrap();
+
+        let services = |schemas: &Schemas| {
+            let mut services: Vec<_> = ServiceMetadataResolver::list_services(schemas)
+                .into_iter()
+                .map(|svc| {
+                    (
+                        svc.name,
+                        svc.deployment_id,
+                        svc.revision,
+                        svc.public,
+                        svc.routing,
+                        svc.configuration,
+                        svc.call_policy,
+                    )
+                })
+                .collect();
+            services.sort_by(|a, b| a.0.cmp(&b.0));
+            services
+        };
+        let deployments = |schemas: &Schemas| {
+            let mut deployments: Vec<_> = schemas
+                .get_deployments()
+                .into_iter()
+                .map(|(deployment, mut services)| {
+                    services.sort();
+                    (
+                        deployment.id,
+                        schemas.get_deployment_state(&deployment.id),
+                        services,
+                    )
+                })
+                .collect();
+            deployments.sort_by_key(|(id, _, _)| *id);
+            deployments
+        };
+        assert_eq!(services(&rebuilt_schemas), services(&schemas));
+        assert_eq!(deployments(&rebuilt_schemas), deployments(&schemas));
+        rebuilt_schemas.assert_service_revision(GREETER_SERVICE_NAME, 3);
+        rebuilt_schemas.assert_resolves_deployment(GREETER_SERVICE_NAME, deployment_3.id);
+        rebuilt_schemas.assert_resolves_deployment(ANOTHER_GREETER_SERVICE_NAME, deployment_1.id);
+    }
+
+    #[test]
+    fn modify_service_configuration() {
+        let schemas = Schemas::default();
+
+        let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+        let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+        let register = |deployment: &Deployment| {
+            schemas
+                .apply_updates(
+                    schemas
+                        .compute_new_deployment(
+                            Some(deployment.id),
+                            deployment.metadata.clone(),
+                            vec![GREETER_SERVICE_NAME.to_owned()],
+                            DESCRIPTOR.clone(),
+                            false,
+                        )
+                        .unwrap(),
+                )
+                .unwrap();
+        };
+        register(&deployment_1);
+        assert_eq!(
+            schemas.resolve_service_configuration(GREETER_SERVICE_NAME),
+            Some(ServiceConfiguration::default())
+        );
+
+        let configuration = ServiceConfiguration {
+            abort_timeout: Some(Duration::from_secs(10).into()),
+            idempotency_retention: Some(Duration::from_secs(60 * 60).into()),
+            message_size_limit: Some(1024),
+            ..Default::default()
+        };
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_configuration(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    configuration.clone(),
+                )
+                .unwrap()])
+            .unwrap();
+
+        // The configuration is retained by the new revisions of the service
+        register(&deployment_2);
+        schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+        assert_eq!(
+            schemas.resolve_service_configuration(GREETER_SERVICE_NAME),
+            Some(configuration.clone())
+        );
+        assert_eq!(
+            schemas
+                .resolve_latest_service_metadata(GREETER_SERVICE_NAME)
+                .unwrap()
+                .configuration,
+            configuration
+        );
+
+        let_assert!(
+            Err(SchemasUpdateError::UnknownService(_)) = schemas
+                .compute_modify_service_configuration(
+                    ANOTHER_GREETER_SERVICE_NAME.to_owned(),
+                    ServiceConfiguration::default(),
+                )
+        );
+
+        for invalid_configuration in [
+            ServiceConfiguration {
+                inactivity_timeout: Some(Duration::ZERO.into()),
+                ..Default::default()
+            },
+            ServiceConfiguration {
+                abort_timeout: Some(Duration::ZERO.into()),
+                ..Default::default()
+            },
+            ServiceConfiguration {
+                idempotency_retention: Some(Duration::ZERO.into()),
+                ..Default::default()
+            },
+            ServiceConfiguration {
+                message_size_limit: Some(0),
+                ..Default::default()
+            },
+        ] {
+            let_assert!(
+                Err(SchemasUpdateError::InvalidServiceConfiguration(_, _)) = schemas
+                    .compute_modify_service_configuration(
+                        GREETER_SERVICE_NAME.to_owned(),
+                        invalid_configuration,
+                    )
+            );
+        }
+    }
+
+    #[test]
+    fn service_call_policy() {
+        let schemas = Schemas::default();
+
+        let deployment = Deployment::mock();
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment.id),
+                        deployment.metadata.clone(),
+                        vec![
+                            GREETER_SERVICE_NAME.to_owned(),
+                            ANOTHER_GREETER_SERVICE_NAME.to_owned(),
+                        ],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            schemas.is_call_allowed("some.Caller", GREETER_SERVICE_NAME),
+            Some(true)
+        );
+
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_call_policy(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceCallPolicy {
+                        allowed_callers: Some([ANOTHER_GREETER_SERVICE_NAME.to_owned()].into()),
+                    },
+                )
+                .unwrap()])
+            .unwrap();
+
+        assert_eq!(
+            schemas.is_call_allowed(ANOTHER_GREETER_SERVICE_NAME, GREETER_SERVICE_NAME),
+            Some(true)
+        );
+        assert_eq!(
+            schemas.is_call_allowed("some.Caller", GREETER_SERVICE_NAME),
+            Some(false)
+        );
+        assert_eq!(
+            schemas.is_call_allowed("some.Caller", ANOTHER_GREETER_SERVICE_NAME),
+            Some(true)
+        );
+        assert_eq!(
+            schemas.is_call_allowed("some.Caller", "greeter.Unknown"),
+            None
+        );
+    }
+
     mod change_instance_type {
         use super::*;
 
@@ -1101,6 +1802,94 @@ mod tests {
         }
     }
 
+    mod wire_incompatible {
+        use super::*;
+
+        use test_log::test;
+
+        use restate_test_util::{check, let_assert};
+
+        load_mock_descriptor!(WIRE_INCOMPATIBLE_DESCRIPTOR_V1, "wire_incompatible/v1");
+        load_mock_descriptor!(WIRE_INCOMPATIBLE_DESCRIPTOR_V2, "wire_incompatible/v2");
+
+        #[test]
+        fn reject_wire_incompatible_changes() {
+            let schemas = Schemas::default();
+
+            let deployment_1 = Deployment::mock_with_uri("http://localhost:9080");
+            let deployment_2 = Deployment::mock_with_uri("http://localhost:9081");
+
+            let commands = schemas.compute_new_deployment(
+                Some(deployment_1.id),
+                deployment_1.metadata,
+                vec![GREETER_SERVICE_NAME.to_owned()],
+                WIRE_INCOMPATIBLE_DESCRIPTOR_V1.clone(),
+                false,
+            );
+            schemas.apply_updates(commands.unwrap()).unwrap();
+
+            let wire_incompatibilities = schemas
+                .compute_wire_incompatibilities(
+                    vec![GREETER_SERVICE_NAME.to_owned()],
+                    &WIRE_INCOMPATIBLE_DESCRIPTOR_V2,
+                )
+                .unwrap();
+            check!(
+                wire_incompatibilities
+                    == std::vec![
+                        WireIncompatibility::FieldNumberChanged {
+                            message: "greeter.GreetingRequest".to_owned(),
+                            field: "person".to_owned(),
+                            old: 1,
+                            new: 5,
+                        },
+                        WireIncompatibility::FieldTypeChanged {
+                            message: "greeter.GreetingRequest".to_owned(),
+                            number: 2,
+                            old: "int32".to_owned(),
+                            new: "string".to_owned(),
+                        },
+                        WireIncompatibility::EnumValueRemoved {
+                            enum_name: "greeter.Mood".to_owned(),
+                            number: 2,
+                            value: "SAD".to_owned(),
+                        },
+                        WireIncompatibility::KeyFieldChanged {
+                            method: "greeter.Greeter/Greet".to_owned(),
+                            old: 1,
+                            new: 5,
+                        },
+                    ]
+            );
+
+            let rejection = schemas.compute_new_deployment(
+                Some(deployment_2.id),
+                deployment_2.metadata.clone(),
+                vec![GREETER_SERVICE_NAME.to_owned()],
+                WIRE_INCOMPATIBLE_DESCRIPTOR_V2.clone(),
+                false,
+            );
+            let_assert!(
+                Err(SchemasUpdateError::IncompatibleServiceChange(
+                    IncompatibleServiceChangeError::WireIncompatibleChanges(service, _)
+                )) = rejection
+            );
+            check!(service == "greeter.Greeter");
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 1); // unchanged
+
+            // Forcing the registration succeeds
+            let commands = schemas.compute_new_deployment(
+                Some(deployment_2.id),
+                deployment_2.metadata,
+                vec![GREETER_SERVICE_NAME.to_owned()],
+                WIRE_INCOMPATIBLE_DESCRIPTOR_V2.clone(),
+                true,
+            );
+            schemas.apply_updates(commands.unwrap()).unwrap();
+            schemas.assert_service_revision(GREETER_SERVICE_NAME, 2);
+        }
+    }
+
     mod bad_key_wrong_type {
         use super::*;
 
@@ -14,7 +14,9 @@ use crate::service::map_to_service_metadata;
 use anyhow::anyhow;
 use prost_reflect::{DescriptorPool, Kind, MethodDescriptor, ServiceDescriptor};
 use proto_symbol::ProtoSymbols;
-use restate_schema_api::service::InstanceType;
+use restate_schema_api::service::{
+    InstanceType, ServiceCallPolicy, ServiceConfiguration, ServiceRouting,
+};
 use restate_schema_api::subscription::{
     EventReceiverServiceInstanceType, FieldRemapType, InputEventRemap, Sink, Source,
 };
@@ -23,8 +25,10 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
+pub(crate) mod compatibility;
 pub(crate) mod deployment;
 mod service;
+mod snapshot;
 mod subscription;
 
 impl Schemas {
@@ -78,6 +82,9 @@ pub(crate) struct ServiceSchemas {
     pub(crate) methods: HashMap<String, MethodSchemas>,
     pub(crate) instance_type: InstanceTypeMetadata,
     pub(crate) location: ServiceLocation,
+    pub(crate) routing: ServiceRouting,
+    pub(crate) configuration: ServiceConfiguration,
+    pub(crate) call_policy: ServiceCallPolicy,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -162,6 +169,9 @@ impl ServiceSchemas {
                 latest_deployment,
                 public: true,
             },
+            routing: ServiceRouting::default(),
+            configuration: ServiceConfiguration::default(),
+            call_policy: ServiceCallPolicy::default(),
         }
     }
 
@@ -186,6 +196,9 @@ impl ServiceSchemas {
                 .collect(),
             instance_type,
             location: ServiceLocation::BuiltIn { ingress_available },
+            routing: ServiceRouting::default(),
+            configuration: ServiceConfiguration::default(),
+            call_policy: ServiceCallPolicy::default(),
         }
     }
 
@@ -244,6 +257,7 @@ impl ServiceLocation {
 #[derive(Debug, Clone)]
 pub(crate) struct DeploymentSchemas {
     pub(crate) metadata: DeploymentMetadata,
+    pub(crate) state: DeploymentState,
 
     // We need to store ServiceSchemas and DescriptorPool here only for queries
     // We could optimize the memory impact of this by reading these info from disk
@@ -309,6 +323,11 @@ impl Default for SchemasInner {
             InstanceTypeMetadata::Unkeyed,
             Visibility::Public,
         );
+        register_built_in(
+            restate_pb::KAFKA_SERVICE_NAME,
+            InstanceTypeMetadata::Unkeyed,
+            Visibility::Public,
+        );
         register_built_in(
             restate_pb::PROXY_SERVICE_NAME,
             // Key must be manually provided when invoking the proxy service
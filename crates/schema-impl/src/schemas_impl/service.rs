@@ -55,6 +55,180 @@ impl SchemasInner {
         Ok(())
     }
 
+    pub(crate) fn compute_modify_service_routing(
+        &self,
+        name: String,
+        routing: ServiceRouting,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        check_service_name_reserved(&name)?;
+        let schemas = self
+            .services
+            .get(&name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(name.clone()))?;
+
+        let total_weight: u32 = routing.weights.values().map(|w| u32::from(*w)).sum();
+        if total_weight > 100 {
+            return Err(SchemasUpdateError::InvalidServiceRouting(
+                name,
+                format!("the sum of the weights must not exceed 100, was {total_weight}"),
+            ));
+        }
+        for revision in routing
+            .weights
+            .keys()
+            .chain(routing.header_pins.iter().map(|pin| &pin.revision))
+        {
+            if *revision != schemas.revision
+                && self
+                    .find_deployment_for_service_revision(&name, *revision)
+                    .is_none()
+            {
+                return Err(SchemasUpdateError::InvalidServiceRouting(
+                    name,
+                    format!("no deployment exposes the revision {revision}"),
+                ));
+            }
+        }
+
+        Ok(SchemasUpdateCommand::ModifyServiceRouting { name, routing })
+    }
+
+    pub(crate) fn apply_modify_service_routing(
+        &mut self,
+        name: String,
+        routing: ServiceRouting,
+    ) -> Result<(), SchemasUpdateError> {
+        let schemas = self
+            .services
+            .get_mut(&name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(name.clone()))?;
+        schemas.routing = routing;
+
+        Ok(())
+    }
+
+    pub(crate) fn compute_modify_service_configuration(
+        &self,
+        name: String,
+        configuration: ServiceConfiguration,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        check_service_name_reserved(&name)?;
+        if !self.services.contains_key(&name) {
+            return Err(SchemasUpdateError::UnknownService(name));
+        }
+
+        // Zero values would make every invocation time out, or drop the responses right away
+        for (field, duration) in [
+            ("inactivity_timeout", &configuration.inactivity_timeout),
+            ("abort_timeout", &configuration.abort_timeout),
+            (
+                "idempotency_retention",
+                &configuration.idempotency_retention,
+            ),
+        ] {
+            if duration.as_ref().is_some_and(|duration| duration.is_zero()) {
+                return Err(SchemasUpdateError::InvalidServiceConfiguration(
+                    name,
+                    format!("{field} must be greater than zero"),
+                ));
+            }
+        }
+        if configuration.message_size_limit == Some(0) {
+            return Err(SchemasUpdateError::InvalidServiceConfiguration(
+                name,
+                "message_size_limit must be greater than zero".to_owned(),
+            ));
+        }
+
+        Ok(SchemasUpdateCommand::ModifyServiceConfiguration {
+            name,
+            configuration,
+        })
+    }
+
+    pub(crate) fn apply_modify_service_configuration(
+        &mut self,
+        name: String,
+        configuration: ServiceConfiguration,
+    ) -> Result<(), SchemasUpdateError> {
+        let schemas = self
+            .services
+            .get_mut(&name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(name.clone()))?;
+        schemas.configuration = configuration;
+
+        Ok(())
+    }
+
+    pub(crate) fn compute_modify_service_call_policy(
+        &self,
+        name: String,
+        call_policy: ServiceCallPolicy,
+    ) -> Result<SchemasUpdateCommand, SchemasUpdateError> {
+        check_service_name_reserved(&name)?;
+        if !self.services.contains_key(&name) {
+            return Err(SchemasUpdateError::UnknownService(name));
+        }
+
+        Ok(SchemasUpdateCommand::ModifyServiceCallPolicy { name, call_policy })
+    }
+
+    pub(crate) fn apply_modify_service_call_policy(
+        &mut self,
+        name: String,
+        call_policy: ServiceCallPolicy,
+    ) -> Result<(), SchemasUpdateError> {
+        let schemas = self
+            .services
+            .get_mut(&name)
+            .ok_or_else(|| SchemasUpdateError::UnknownService(name.clone()))?;
+        schemas.call_policy = call_policy;
+
+        Ok(())
+    }
+
+    /// Find the deployment accepting the new invocations of the latest revision of the service.
+    pub(crate) fn find_latest_deployment_for_service(&self, name: &str) -> Option<DeploymentId> {
+        let service = self.services.get(name)?;
+        let ServiceLocation::Deployment {
+            latest_deployment, ..
+        } = &service.location
+        else {
+            return None;
+        };
+
+        if self
+            .deployments
+            .get(latest_deployment)
+            .is_some_and(|schemas| !schemas.state.is_draining())
+        {
+            Some(*latest_deployment)
+        } else {
+            // Another deployment might expose the same revision
+            self.find_deployment_for_service_revision(name, service.revision)
+        }
+    }
+
+    /// Find the most recent deployment exposing the given revision of the service,
+    /// excluding the draining deployments.
+    pub(crate) fn find_deployment_for_service_revision(
+        &self,
+        name: &str,
+        revision: ServiceRevision,
+    ) -> Option<DeploymentId> {
+        self.deployments
+            .iter()
+            .filter(|(_, schemas)| {
+                !schemas.state.is_draining()
+                    && schemas
+                        .services
+                        .iter()
+                        .any(|svc| svc.name == name && svc.revision == revision)
+            })
+            .max_by_key(|(_, schemas)| schemas.metadata.created_at())
+            .map(|(deployment_id, _)| *deployment_id)
+    }
+
     pub(crate) fn apply_remove_service(
         &mut self,
         name: String,
@@ -0,0 +1,148 @@
+use super::*;
+
+use std::collections::HashSet;
+
+impl SchemasInner {
+    /// Computes the commands rebuilding the current state of the registry, when applied to an empty registry.
+    pub(crate) fn compute_snapshot(&self) -> Result<Vec<SchemasUpdateCommand>, SchemasUpdateError> {
+        let mut commands = vec![];
+
+        // Revision of each service after applying the commands inserting the deployments
+        let mut inserted_revisions = HashMap::new();
+        for deployment_id in self.deployments_in_insertion_order() {
+            let command = self.compute_insert_registered_deployment(deployment_id)?;
+            if let SchemasUpdateCommand::InsertDeployment { services, .. } = &command {
+                for svc in services {
+                    inserted_revisions.insert(svc.name.clone(), svc.revision);
+                }
+            }
+            commands.push(command);
+        }
+
+        // Services might have been removed while older deployments exposing them are still registered
+        let mut removed_services: Vec<_> = inserted_revisions
+            .into_iter()
+            .filter(|(name, _)| !self.services.contains_key(name))
+            .collect();
+        removed_services.sort();
+        for (name, revision) in removed_services {
+            commands.push(SchemasUpdateCommand::RemoveService { name, revision });
+        }
+
+        let mut services: Vec<_> = self.services.iter().collect();
+        services.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, schemas) in services {
+            let ServiceLocation::Deployment { public, .. } = &schemas.location else {
+                continue;
+            };
+            if !public {
+                commands.push(SchemasUpdateCommand::ModifyService {
+                    name: name.clone(),
+                    public: false,
+                });
+            }
+            if schemas.routing != ServiceRouting::default() {
+                commands.push(SchemasUpdateCommand::ModifyServiceRouting {
+                    name: name.clone(),
+                    routing: schemas.routing.clone(),
+                });
+            }
+            if schemas.configuration != ServiceConfiguration::default() {
+                commands.push(SchemasUpdateCommand::ModifyServiceConfiguration {
+                    name: name.clone(),
+                    configuration: schemas.configuration.clone(),
+                });
+            }
+            if schemas.call_policy != ServiceCallPolicy::default() {
+                commands.push(SchemasUpdateCommand::ModifyServiceCallPolicy {
+                    name: name.clone(),
+                    call_policy: schemas.call_policy.clone(),
+                });
+            }
+        }
+
+        let mut draining_deployments: Vec<_> = self
+            .deployments
+            .iter()
+            .filter(|(_, schemas)| schemas.state.is_draining())
+            .map(|(deployment_id, _)| *deployment_id)
+            .collect();
+        draining_deployments.sort();
+        for deployment_id in draining_deployments {
+            commands.push(SchemasUpdateCommand::ModifyDeploymentState {
+                deployment_id,
+                state: DeploymentState::Draining,
+            });
+        }
+
+        let mut subscriptions: Vec<_> = self.subscriptions.values().cloned().collect();
+        subscriptions.sort_by_key(|sub| sub.id());
+        commands.extend(
+            subscriptions
+                .into_iter()
+                .map(SchemasUpdateCommand::AddSubscription),
+        );
+
+        Ok(commands)
+    }
+
+    /// Orders the deployments such that the latest deployment of each service is inserted
+    /// after the other deployments exposing the same service, otherwise by creation time.
+    fn deployments_in_insertion_order(&self) -> Vec<DeploymentId> {
+        let mut remaining: Vec<_> = self
+            .deployments
+            .iter()
+            .map(|(deployment_id, schemas)| (schemas.metadata.created_at, *deployment_id))
+            .collect();
+        remaining.sort();
+
+        let mut ordered = Vec::with_capacity(remaining.len());
+        let mut inserted = HashSet::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .position(|(_, deployment_id)| {
+                    self.latest_deployment_preconditions(deployment_id)
+                        .all(|other| other == *deployment_id || inserted.contains(&other))
+                })
+                // Cannot happen with a registry built through commands, as the insertion order exists
+                .unwrap_or(0);
+            let (_, deployment_id) = remaining.remove(next);
+            inserted.insert(deployment_id);
+            ordered.push(deployment_id);
+        }
+
+        ordered
+    }
+
+    /// Returns the deployments to insert before the given one,
+    /// as they expose services of which the given deployment is the latest deployment.
+    fn latest_deployment_preconditions<'a>(
+        &'a self,
+        deployment_id: &'a DeploymentId,
+    ) -> impl Iterator<Item = DeploymentId> + 'a {
+        let latest_services: Vec<_> = self.deployments[deployment_id]
+            .services
+            .iter()
+            .filter(|svc| {
+                self.services.get(&svc.name).is_some_and(|schemas| {
+                    matches!(
+                        &schemas.location,
+                        ServiceLocation::Deployment { latest_deployment, .. } if latest_deployment == deployment_id
+                    )
+                })
+            })
+            .map(|svc| svc.name.as_str())
+            .collect();
+
+        self.deployments
+            .iter()
+            .filter(move |(_, schemas)| {
+                schemas
+                    .services
+                    .iter()
+                    .any(|svc| latest_services.contains(&svc.name.as_str()))
+            })
+            .map(|(other, _)| *other)
+    }
+}
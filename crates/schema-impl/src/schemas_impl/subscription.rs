@@ -1,5 +1,27 @@
 use super::*;
 
+use restate_schema_api::subscription::{
+    DeadLetterTopic, EventRoute, FailurePolicy, PayloadDecoding, PayloadFormat,
+};
+use std::collections::BTreeMap;
+
+/// Subscription option to decode the event payload to the input message of the sink method.
+const PAYLOAD_FORMAT_OPTION: &str = "payload.format";
+/// Subscription option for the Confluent-compatible schema registry used to decode the payload.
+const SCHEMA_REGISTRY_URL_OPTION: &str = "schema.registry.url";
+/// Subscription option for the expression filtering the dispatched events.
+const FILTER_OPTION: &str = "filter";
+/// Prefix of the subscription options `route.<name>.filter` and `route.<name>.sink`.
+/// Routes are evaluated in the lexicographic order of their names.
+const ROUTE_OPTION_PREFIX: &str = "route.";
+/// Subscription option for the maximum number of attempts to process an event.
+const MAX_ATTEMPTS_OPTION: &str = "max.attempts";
+/// Subscription option for the Kafka topic where the events exceeding the attempts are published.
+const DEAD_LETTER_TOPIC_OPTION: &str = "dead.letter.topic";
+/// Subscription option for the Kafka cluster of the dead letter topic.
+/// Defaults to the source cluster for Kafka sources.
+const DEAD_LETTER_CLUSTER_OPTION: &str = "dead.letter.cluster";
+
 impl SchemasInner {
     pub(crate) fn compute_add_subscription<V: SubscriptionValidator>(
         &self,
@@ -16,10 +38,168 @@ impl SchemasInner {
             return Err(SchemasUpdateError::OverrideSubscription(id));
         }
 
-        // TODO This logic to parse source and sink should be moved elsewhere to abstract over the known source/sink providers
-        //  Maybe together with the validator?
+        let source = Self::compute_source(&source)?;
+        let (service_name, method_name) = Self::parse_sink(&sink)?;
+        let subscription =
+            self.compute_subscription(id, source, service_name, method_name, metadata, validator)?;
+
+        Ok((
+            subscription.clone(),
+            SchemasUpdateCommand::AddSubscription(subscription),
+        ))
+    }
+
+    pub(crate) fn compute_update_subscription<V: SubscriptionValidator>(
+        &self,
+        id: SubscriptionId,
+        sink: Option<Uri>,
+        metadata: Option<HashMap<String, String>>,
+        paused: Option<bool>,
+        validator: V,
+    ) -> Result<(Subscription, SchemasUpdateCommand), SchemasUpdateError> {
+        let existing = self
+            .subscriptions
+            .get(&id)
+            .ok_or(SchemasUpdateError::UnknownSubscription(id))?;
+
+        // The provided options are merged with the existing ones, so the consumer group,
+        // and with it the consumed offsets, is retained unless explicitly overridden.
+        let mut merged_metadata = subscription_options(existing);
+        merged_metadata.extend(metadata.unwrap_or_default());
+
+        let (service_name, method_name) = match sink {
+            Some(sink) => Self::parse_sink(&sink)?,
+            None => {
+                let Sink::Service { name, method, .. } = existing.sink();
+                (name.clone(), method.clone())
+            }
+        };
+
+        let mut subscription = self.compute_subscription(
+            id,
+            existing.source().clone(),
+            service_name,
+            method_name,
+            Some(merged_metadata),
+            validator,
+        )?;
+        subscription.set_paused(paused.unwrap_or(existing.paused()));
+        subscription.set_offsets_generation(existing.offsets_generation());
+
+        // Adding a subscription with the same id replaces the existing one
+        Ok((
+            subscription.clone(),
+            SchemasUpdateCommand::AddSubscription(subscription),
+        ))
+    }
+
+    /// Computes the command to register a subscription from an archive, keeping its id.
+    /// Returns no command if the same subscription is already registered.
+    pub(crate) fn compute_import_subscription<V: SubscriptionValidator>(
+        &self,
+        subscription: Subscription,
+        validator: V,
+    ) -> Result<Option<SchemasUpdateCommand>, SchemasUpdateError> {
+        if let Some(existing) = self.subscriptions.get(&subscription.id()) {
+            return if *existing == subscription {
+                Ok(None)
+            } else {
+                Err(SchemasUpdateError::OverrideSubscription(subscription.id()))
+            };
+        }
+
+        let Sink::Service { name, method, .. } = subscription.sink();
+        let mut imported = self.compute_subscription(
+            subscription.id(),
+            subscription.source().clone(),
+            name.clone(),
+            method.clone(),
+            Some(subscription_options(&subscription)),
+            validator,
+        )?;
+        imported.set_paused(subscription.paused());
+        imported.set_offsets_generation(subscription.offsets_generation());
+
+        Ok(Some(SchemasUpdateCommand::AddSubscription(imported)))
+    }
+
+    pub(crate) fn compute_reset_subscription_offsets(
+        &self,
+        id: SubscriptionId,
+    ) -> Result<(Subscription, SchemasUpdateCommand), SchemasUpdateError> {
+        let mut subscription = self
+            .subscriptions
+            .get(&id)
+            .ok_or(SchemasUpdateError::UnknownSubscription(id))?
+            .clone();
+        if !matches!(subscription.source(), Source::Kafka { .. }) {
+            return Err(SchemasUpdateError::UnsupportedOffsetsReset(id));
+        }
+
+        // A new generation starts a new deduplication scope, otherwise the replayed events
+        // would be discarded by the ingress as already processed.
+        subscription.set_offsets_generation(subscription.offsets_generation() + 1);
+
+        Ok((
+            subscription.clone(),
+            SchemasUpdateCommand::AddSubscription(subscription),
+        ))
+    }
+
+    fn compute_subscription<V: SubscriptionValidator>(
+        &self,
+        id: SubscriptionId,
+        source: Source,
+        service_name: String,
+        method_name: String,
+        metadata: Option<HashMap<String, String>>,
+        validator: V,
+    ) -> Result<Subscription, SchemasUpdateError> {
+        let mut metadata = metadata.unwrap_or_default();
+
+        // These options are consumed here, the remaining ones are passed to the source
+        let payload_decoding = metadata
+            .remove(PAYLOAD_FORMAT_OPTION)
+            .map(|format| {
+                Ok::<_, SchemasUpdateError>(PayloadDecoding {
+                    format: format
+                        .parse::<PayloadFormat>()
+                        .map_err(SchemasUpdateError::InvalidSubscription)?,
+                    schema_registry_url: metadata.remove(SCHEMA_REGISTRY_URL_OPTION),
+                })
+            })
+            .transpose()?;
+        if let Some(PayloadDecoding {
+            format: PayloadFormat::Avro,
+            schema_registry_url: None,
+        }) = &payload_decoding
+        {
+            return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                "the avro payload format requires the option '{}'",
+                SCHEMA_REGISTRY_URL_OPTION
+            )));
+        }
+
+        let filter = metadata
+            .remove(FILTER_OPTION)
+            .filter(|filter| !filter.is_empty());
+        let routes = self.compute_routes(&mut metadata, &payload_decoding)?;
+        let failure_policy = Self::compute_failure_policy(&mut metadata, &source)?;
+        let sink = self.compute_service_sink(service_name, method_name, payload_decoding)?;
 
-        // Parse source
+        let mut subscription = Subscription::new(id, source, sink, metadata);
+        subscription.set_filter(filter);
+        subscription.set_routes(routes);
+        subscription.set_failure_policy(failure_policy);
+
+        validator
+            .validate(subscription)
+            .map_err(|e| SchemasUpdateError::InvalidSubscription(e.into()))
+    }
+
+    // TODO This logic to parse source and sink should be moved elsewhere to abstract over the known source/sink providers
+    //  Maybe together with the validator?
+    fn compute_source(source: &Uri) -> Result<Source, SchemasUpdateError> {
         let source = match source.scheme_str() {
             Some("kafka") => {
                 let cluster_name = source.authority().ok_or_else(|| SchemasUpdateError::InvalidSubscription(anyhow!(
@@ -33,131 +213,306 @@ impl SchemasInner {
                     ordering_key_format: Default::default(),
                 }
             }
+            Some("nats") => {
+                let cluster_name = source.authority().ok_or_else(|| SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "source URI of NATS type must have a authority segment containing the cluster name. Was '{}'",
+                    source
+                )))?.as_str();
+                let subject = &source.path()[1..];
+                if subject.is_empty() {
+                    return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                        "source URI of NATS type must have a path segment containing the subject. Was '{}'",
+                        source
+                    )));
+                }
+                Source::Nats {
+                    cluster: cluster_name.to_string(),
+                    subject: subject.to_string(),
+                }
+            }
+            Some("file") => {
+                if source.host() != Some("localhost") {
+                    return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                        "source URI of file type must have 'localhost' as authority segment. Was '{}'",
+                        source
+                    )));
+                }
+                Source::File {
+                    path: source.path().to_string(),
+                }
+            }
+            Some(scheme @ ("amqp" | "amqps" | "stdin")) => {
+                return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "sources of type '{}' are not supported, with supported schemes: {:?}. Was '{}'",
+                    scheme,
+                    ["kafka", "nats", "file"],
+                    source
+                )))
+            }
             _ => {
                 return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
                     "source URI must have a scheme segment, with supported schemes: {:?}. Was '{}'",
-                    ["kafka"],
+                    ["kafka", "nats", "file"],
                     source
                 )))
             }
         };
 
-        // Parse sink
-        let sink = match sink.scheme_str() {
-            Some("service") => {
-                let service_name = sink.authority().ok_or_else(|| SchemasUpdateError::InvalidSubscription(anyhow!(
-                    "sink URI of service type must have a authority segment containing the service name. Was '{}'",
-                    sink
-                )))?.as_str();
-                let method_name = &sink.path()[1..];
+        Ok(source)
+    }
 
-                // Retrieve service and method in the schema registry
-                let service_schemas = self.services.get(service_name).ok_or_else(|| {
-                    SchemasUpdateError::InvalidSubscription(anyhow!(
-                        "cannot find service specified in the sink URI. Was '{}'",
-                        sink
-                    ))
-                })?;
-                let method_schemas = service_schemas.methods.get(method_name).ok_or_else(|| {
+    /// Consumes the route options, resolving the sink of every route.
+    fn compute_routes(
+        &self,
+        metadata: &mut HashMap<String, String>,
+        payload_decoding: &Option<PayloadDecoding>,
+    ) -> Result<Vec<EventRoute>, SchemasUpdateError> {
+        let route_options: Vec<String> = metadata
+            .keys()
+            .filter(|k| k.starts_with(ROUTE_OPTION_PREFIX))
+            .cloned()
+            .collect();
+
+        let mut routes: BTreeMap<String, (Option<String>, Option<String>)> = BTreeMap::new();
+        for option in route_options {
+            let value = metadata.remove(&option).expect("option key must exist");
+            let (name, field) = option[ROUTE_OPTION_PREFIX.len()..]
+                .rsplit_once('.')
+                .ok_or_else(|| {
                     SchemasUpdateError::InvalidSubscription(anyhow!(
-                        "cannot find service method specified in the sink URI. Was '{}'",
-                        sink
+                        "route options must have the form '{}<name>.filter' or '{}<name>.sink'. Was '{}'",
+                        ROUTE_OPTION_PREFIX,
+                        ROUTE_OPTION_PREFIX,
+                        option
                     ))
                 })?;
+            let route = routes.entry(name.to_owned()).or_default();
+            match field {
+                "filter" => route.0 = Some(value),
+                "sink" => route.1 = Some(value),
+                _ => {
+                    return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                        "unknown route option '{}', supported route options are: filter, sink",
+                        option
+                    )))
+                }
+            }
+        }
 
-                let input_type = method_schemas.descriptor().input();
-                let input_event_remap = if input_type.full_name() == "dev.restate.Event" {
-                    // No remapping needed
-                    None
-                } else {
-                    let key = if let Some(index) =
-                        method_schemas.input_field_annotated(FieldAnnotation::Key)
-                    {
-                        let kind = input_type.get_field(index).unwrap().kind();
-                        if kind == Kind::String {
-                            Some((index, FieldRemapType::String))
-                        } else {
-                            Some((index, FieldRemapType::Bytes))
-                        }
-                    } else {
-                        None
-                    };
-
-                    let payload = if let Some(index) =
-                        method_schemas.input_field_annotated(FieldAnnotation::EventPayload)
-                    {
-                        let kind = input_type.get_field(index).unwrap().kind();
-                        if kind == Kind::String {
-                            Some((index, FieldRemapType::String))
-                        } else {
-                            Some((index, FieldRemapType::Bytes))
-                        }
-                    } else {
-                        None
-                    };
-
-                    Some(InputEventRemap {
-                        key,
-                        payload,
-                        attributes_index: method_schemas
-                            .input_field_annotated(FieldAnnotation::EventMetadata),
-                    })
-                };
+        let mut result = Vec::with_capacity(routes.len());
+        for (name, (filter, sink)) in routes {
+            // An empty sink removes the route
+            let Some(sink) = sink.filter(|sink| !sink.is_empty()) else {
+                continue;
+            };
+            let filter = filter.ok_or_else(|| {
+                SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "route '{}' requires the option '{}{}.filter'",
+                    name,
+                    ROUTE_OPTION_PREFIX,
+                    name
+                ))
+            })?;
+            let sink = sink.parse::<Uri>().map_err(|e| {
+                SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "sink of route '{}' must be a valid URI: {}",
+                    name,
+                    e
+                ))
+            })?;
+            let (service_name, method_name) = Self::parse_sink(&sink)?;
+            result.push(EventRoute {
+                name,
+                filter,
+                sink: self.compute_service_sink(
+                    service_name,
+                    method_name,
+                    payload_decoding.clone(),
+                )?,
+            });
+        }
 
-                let instance_type = match service_schemas.instance_type {
-                    InstanceTypeMetadata::Keyed { .. } => {
-                        // Verify the type is supported!
-                        let key_field_kind = method_schemas.descriptor.input().get_field(
-                            method_schemas.input_field_annotated(FieldAnnotation::Key).expect("There must be a key field for every method input type")
-                        ).unwrap().kind();
-                        if key_field_kind != Kind::String && key_field_kind != Kind::Bytes {
-                            return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
-                                "Key type {:?} for sink {} is invalid, only bytes and string are supported.",
-                                key_field_kind, sink
-                            )));
-                        }
-
-                        EventReceiverServiceInstanceType::Keyed { ordering_key_is_key: false }
-                    }
-                    InstanceTypeMetadata::Unkeyed => EventReceiverServiceInstanceType::Unkeyed,
-                    InstanceTypeMetadata::Singleton => EventReceiverServiceInstanceType::Singleton,
-                    InstanceTypeMetadata::Unsupported | InstanceTypeMetadata::Custom { .. } => {
+        Ok(result)
+    }
+
+    /// Consumes the max attempts and dead letter options. Empty values unset them.
+    fn compute_failure_policy(
+        metadata: &mut HashMap<String, String>,
+        source: &Source,
+    ) -> Result<FailurePolicy, SchemasUpdateError> {
+        let max_attempts = metadata
+            .remove(MAX_ATTEMPTS_OPTION)
+            .filter(|max_attempts| !max_attempts.is_empty())
+            .map(|max_attempts| match max_attempts.parse::<u32>() {
+                Ok(max_attempts) if max_attempts > 0 => Ok(max_attempts),
+                _ => Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "the option '{}' must be a positive integer. Was '{}'",
+                    MAX_ATTEMPTS_OPTION,
+                    max_attempts
+                ))),
+            })
+            .transpose()?;
+
+        let dead_letter_cluster = metadata
+            .remove(DEAD_LETTER_CLUSTER_OPTION)
+            .filter(|cluster| !cluster.is_empty());
+        let dead_letter = metadata
+            .remove(DEAD_LETTER_TOPIC_OPTION)
+            .filter(|topic| !topic.is_empty())
+            .map(|topic| {
+                let cluster = match (dead_letter_cluster, source) {
+                    (Some(cluster), _) => cluster,
+                    (None, Source::Kafka { cluster, .. }) => cluster.clone(),
+                    (None, _) => {
                         return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
-                            "trying to use a built-in service as sink {}. This is currently unsupported.",
-                            sink
+                            "the option '{}' requires the option '{}' for non Kafka sources",
+                            DEAD_LETTER_TOPIC_OPTION,
+                            DEAD_LETTER_CLUSTER_OPTION
                         )))
                     }
                 };
+                Ok(DeadLetterTopic { cluster, topic })
+            })
+            .transpose()?;
+
+        Ok(FailurePolicy {
+            max_attempts,
+            dead_letter,
+        })
+    }
+
+    /// Returns the service and method names of the sink URI.
+    fn parse_sink(sink: &Uri) -> Result<(String, String), SchemasUpdateError> {
+        match sink.scheme_str() {
+            Some("service") => {
+                let service_name = sink.authority().ok_or_else(|| SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "sink URI of service type must have a authority segment containing the service name. Was '{}'",
+                    sink
+                )))?.as_str();
+                Ok((service_name.to_owned(), sink.path()[1..].to_owned()))
+            }
+            _ => Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                "sink URI must have a scheme segment, with supported schemes: {:?}. Was '{}'",
+                ["service"],
+                sink
+            ))),
+        }
+    }
+
+    fn compute_service_sink(
+        &self,
+        service_name: String,
+        method_name: String,
+        payload_decoding: Option<PayloadDecoding>,
+    ) -> Result<Sink, SchemasUpdateError> {
+        // Used in the error messages
+        let sink = format!("service://{service_name}/{method_name}");
+
+        // Retrieve service and method in the schema registry
+        let service_schemas = self.services.get(&service_name).ok_or_else(|| {
+            SchemasUpdateError::InvalidSubscription(anyhow!(
+                "cannot find service specified in the sink URI. Was '{}'",
+                sink
+            ))
+        })?;
+        let method_schemas = service_schemas.methods.get(&method_name).ok_or_else(|| {
+            SchemasUpdateError::InvalidSubscription(anyhow!(
+                "cannot find service method specified in the sink URI. Was '{}'",
+                sink
+            ))
+        })?;
+
+        let input_type = method_schemas.descriptor().input();
+        let input_event_remap = if input_type.full_name() == "dev.restate.Event" {
+            if payload_decoding.is_some() {
+                return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                    "the option '{}' cannot be used with sink {}, because its input type is dev.restate.Event",
+                    PAYLOAD_FORMAT_OPTION, sink
+                )));
+            }
+            // No remapping needed
+            None
+        } else {
+            let key =
+                if let Some(index) = method_schemas.input_field_annotated(FieldAnnotation::Key) {
+                    let kind = input_type.get_field(index).unwrap().kind();
+                    if kind == Kind::String {
+                        Some((index, FieldRemapType::String))
+                    } else {
+                        Some((index, FieldRemapType::Bytes))
+                    }
+                } else {
+                    None
+                };
+
+            let payload = if let Some(index) =
+                method_schemas.input_field_annotated(FieldAnnotation::EventPayload)
+            {
+                if payload_decoding.is_some() {
+                    return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                        "the option '{}' decodes the payload to the whole input message of sink {}, hence its input type cannot have a field annotated as event payload",
+                        PAYLOAD_FORMAT_OPTION, sink
+                    )));
+                }
+                let kind = input_type.get_field(index).unwrap().kind();
+                if kind == Kind::String {
+                    Some((index, FieldRemapType::String))
+                } else {
+                    Some((index, FieldRemapType::Bytes))
+                }
+            } else {
+                None
+            };
 
-                Sink::Service {
-                    name: service_name.to_string(),
-                    method: method_name.to_string(),
-                    input_event_remap,
-                    instance_type,
+            Some(InputEventRemap {
+                key,
+                payload,
+                attributes_index: method_schemas
+                    .input_field_annotated(FieldAnnotation::EventMetadata),
+                payload_decoding,
+            })
+        };
+
+        let instance_type = match service_schemas.instance_type {
+            InstanceTypeMetadata::Keyed { .. } => {
+                // Verify the type is supported!
+                let key_field_kind = method_schemas
+                    .descriptor
+                    .input()
+                    .get_field(
+                        method_schemas
+                            .input_field_annotated(FieldAnnotation::Key)
+                            .expect("There must be a key field for every method input type"),
+                    )
+                    .unwrap()
+                    .kind();
+                if key_field_kind != Kind::String && key_field_kind != Kind::Bytes {
+                    return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
+                        "Key type {:?} for sink {} is invalid, only bytes and string are supported.",
+                        key_field_kind, sink
+                    )));
+                }
+
+                EventReceiverServiceInstanceType::Keyed {
+                    ordering_key_is_key: false,
                 }
             }
-            _ => {
+            InstanceTypeMetadata::Unkeyed => EventReceiverServiceInstanceType::Unkeyed,
+            InstanceTypeMetadata::Singleton => EventReceiverServiceInstanceType::Singleton,
+            InstanceTypeMetadata::Unsupported | InstanceTypeMetadata::Custom { .. } => {
                 return Err(SchemasUpdateError::InvalidSubscription(anyhow!(
-                    "sink URI must have a scheme segment, with supported schemes: {:?}. Was '{}'",
-                    ["service"],
+                    "trying to use a built-in service as sink {}. This is currently unsupported.",
                     sink
                 )))
             }
         };
 
-        let subscription = validator
-            .validate(Subscription::new(
-                id,
-                source,
-                sink,
-                metadata.unwrap_or_default(),
-            ))
-            .map_err(|e| SchemasUpdateError::InvalidSubscription(e.into()))?;
-
-        Ok((
-            subscription.clone(),
-            SchemasUpdateCommand::AddSubscription(subscription),
-        ))
+        Ok(Sink::Service {
+            name: service_name,
+            method: method_name,
+            input_event_remap,
+            instance_type,
+        })
     }
 
     pub(crate) fn apply_add_subscription(
@@ -189,3 +544,301 @@ impl SchemasInner {
         Ok(())
     }
 }
+
+/// Returns the options to compute the given subscription again,
+/// including the ones consumed by [`SchemasInner::compute_subscription`].
+fn subscription_options(subscription: &Subscription) -> HashMap<String, String> {
+    let mut options = subscription.metadata().clone();
+    let Sink::Service {
+        input_event_remap, ..
+    } = subscription.sink();
+    if let Some(PayloadDecoding {
+        format,
+        schema_registry_url,
+    }) = input_event_remap
+        .as_ref()
+        .and_then(|remap| remap.payload_decoding.as_ref())
+    {
+        options.insert(PAYLOAD_FORMAT_OPTION.to_owned(), format.to_string());
+        if let Some(schema_registry_url) = schema_registry_url {
+            options.insert(
+                SCHEMA_REGISTRY_URL_OPTION.to_owned(),
+                schema_registry_url.clone(),
+            );
+        }
+    }
+    if let Some(filter) = subscription.filter() {
+        options.insert(FILTER_OPTION.to_owned(), filter.to_owned());
+    }
+    for route in subscription.routes() {
+        options.insert(
+            format!("{ROUTE_OPTION_PREFIX}{}.filter", route.name),
+            route.filter.clone(),
+        );
+        options.insert(
+            format!("{ROUTE_OPTION_PREFIX}{}.sink", route.name),
+            route.sink.to_string(),
+        );
+    }
+    let failure_policy = subscription.failure_policy();
+    if let Some(max_attempts) = failure_policy.max_attempts {
+        options.insert(MAX_ATTEMPTS_OPTION.to_owned(), max_attempts.to_string());
+    }
+    if let Some(DeadLetterTopic { cluster, topic }) = &failure_policy.dead_letter {
+        options.insert(DEAD_LETTER_CLUSTER_OPTION.to_owned(), cluster.clone());
+        options.insert(DEAD_LETTER_TOPIC_OPTION.to_owned(), topic.clone());
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    use restate_schema_api::deployment::Deployment;
+    use restate_schema_api::subscription::{KafkaOrderingKeyFormat, SubscriptionResolver};
+    use restate_test_util::{assert, assert_eq, let_assert};
+    use std::convert::Infallible;
+
+    load_mock_descriptor!(DESCRIPTOR, "generic");
+    const GREETER_SERVICE_NAME: &str = "greeter.Greeter";
+    const ANOTHER_GREETER_SERVICE_NAME: &str = "greeter.AnotherGreeter";
+
+    struct NoopValidator;
+
+    impl SubscriptionValidator for NoopValidator {
+        type Error = Infallible;
+
+        fn validate(&self, subscription: Subscription) -> Result<Subscription, Self::Error> {
+            Ok(subscription)
+        }
+    }
+
+    fn schemas_with_subscription() -> (Schemas, Subscription) {
+        let schemas = Schemas::default();
+
+        let deployment = Deployment::mock();
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment.id),
+                        deployment.metadata,
+                        vec![
+                            GREETER_SERVICE_NAME.to_owned(),
+                            ANOTHER_GREETER_SERVICE_NAME.to_owned(),
+                        ],
+                        DESCRIPTOR.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let (subscription, command) = schemas
+            .compute_add_subscription(
+                None,
+                "kafka://my-cluster/my-topic".parse().unwrap(),
+                format!("service://{GREETER_SERVICE_NAME}/Greet")
+                    .parse()
+                    .unwrap(),
+                Some(HashMap::from([
+                    ("group.id".to_owned(), "my-group".to_owned()),
+                    (PAYLOAD_FORMAT_OPTION.to_owned(), "json".to_owned()),
+                ])),
+                NoopValidator,
+            )
+            .unwrap();
+        schemas.apply_updates(vec![command]).unwrap();
+
+        (schemas, subscription)
+    }
+
+    #[test]
+    fn update_subscription_retains_source_and_sink() {
+        let (schemas, subscription) = schemas_with_subscription();
+
+        let (updated, command) = schemas
+            .compute_update_subscription(
+                subscription.id(),
+                None,
+                Some(HashMap::from([(
+                    "auto.offset.reset".to_owned(),
+                    "earliest".to_owned(),
+                )])),
+                Some(true),
+                NoopValidator,
+            )
+            .unwrap();
+        schemas.apply_updates(vec![command]).unwrap();
+
+        assert_eq!(
+            updated.source(),
+            &Source::Kafka {
+                cluster: "my-cluster".to_owned(),
+                topic: "my-topic".to_owned(),
+                ordering_key_format: KafkaOrderingKeyFormat::ConsumerGroupTopicPartition,
+            }
+        );
+        assert_eq!(updated.sink(), subscription.sink());
+        let_assert!(
+            Sink::Service {
+                input_event_remap: Some(InputEventRemap {
+                    payload_decoding: Some(PayloadDecoding {
+                        format: PayloadFormat::Json,
+                        ..
+                    }),
+                    ..
+                }),
+                ..
+            } = updated.sink()
+        );
+        assert_eq!(updated.metadata().get("group.id").unwrap(), "my-group");
+        assert_eq!(
+            updated.metadata().get("auto.offset.reset").unwrap(),
+            "earliest"
+        );
+        assert!(updated.paused());
+        assert_eq!(
+            schemas.get_subscription(subscription.id()).unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn update_subscription_sink() {
+        let (schemas, subscription) = schemas_with_subscription();
+
+        let (updated, _) = schemas
+            .compute_update_subscription(
+                subscription.id(),
+                Some(
+                    format!("service://{ANOTHER_GREETER_SERVICE_NAME}/Greet")
+                        .parse()
+                        .unwrap(),
+                ),
+                None,
+                None,
+                NoopValidator,
+            )
+            .unwrap();
+
+        assert_eq!(updated.source(), subscription.source());
+        let_assert!(Sink::Service { name, method, .. } = updated.sink());
+        assert_eq!(name, ANOTHER_GREETER_SERVICE_NAME);
+        assert_eq!(method, "Greet");
+        assert!(!updated.paused());
+    }
+
+    #[test]
+    fn update_subscription_with_unknown_sink_method() {
+        let (schemas, subscription) = schemas_with_subscription();
+
+        let_assert!(
+            Err(SchemasUpdateError::InvalidSubscription(_)) = schemas.compute_update_subscription(
+                subscription.id(),
+                Some(
+                    format!("service://{GREETER_SERVICE_NAME}/Unknown")
+                        .parse()
+                        .unwrap()
+                ),
+                None,
+                None,
+                NoopValidator,
+            )
+        );
+    }
+
+    #[test]
+    fn update_unknown_subscription() {
+        let (schemas, _) = schemas_with_subscription();
+
+        let id = SubscriptionId::new();
+        let_assert!(
+            Err(SchemasUpdateError::UnknownSubscription(unknown_id)) =
+                schemas.compute_update_subscription(id, None, None, Some(true), NoopValidator)
+        );
+        assert_eq!(unknown_id, id);
+    }
+
+    #[test]
+    fn compute_nats_and_file_sources() {
+        assert_eq!(
+            SchemasInner::compute_source(&"nats://my-cluster/orders.created".parse().unwrap())
+                .unwrap(),
+            Source::Nats {
+                cluster: "my-cluster".to_owned(),
+                subject: "orders.created".to_owned(),
+            }
+        );
+        assert_eq!(
+            SchemasInner::compute_source(&"file://localhost/var/events".parse().unwrap()).unwrap(),
+            Source::File {
+                path: "/var/events".to_owned(),
+            }
+        );
+        assert!(SchemasInner::compute_source(&"nats://my-cluster".parse().unwrap()).is_err());
+        assert!(
+            SchemasInner::compute_source(&"file://remote-host/var/events".parse().unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_sources() {
+        for source in ["amqp://my-broker/my-queue", "stdin://localhost"] {
+            let_assert!(
+                Err(SchemasUpdateError::InvalidSubscription(e)) =
+                    SchemasInner::compute_source(&source.parse().unwrap())
+            );
+            assert!(e.to_string().contains("not supported"));
+        }
+    }
+
+    #[test]
+    fn reset_subscription_offsets_starts_new_generation() {
+        let (schemas, subscription) = schemas_with_subscription();
+
+        let (reset, command) = schemas
+            .compute_reset_subscription_offsets(subscription.id())
+            .unwrap();
+        schemas.apply_updates(vec![command]).unwrap();
+
+        assert_eq!(
+            reset.offsets_generation(),
+            subscription.offsets_generation() + 1
+        );
+        assert_eq!(
+            schemas
+                .get_subscription(subscription.id())
+                .unwrap()
+                .offsets_generation(),
+            reset.offsets_generation()
+        );
+    }
+
+    #[test]
+    fn reset_subscription_offsets_requires_kafka_source() {
+        let (schemas, _) = schemas_with_subscription();
+        let (subscription, command) = schemas
+            .compute_add_subscription(
+                None,
+                "nats://my-cluster/orders".parse().unwrap(),
+                format!("service://{GREETER_SERVICE_NAME}/Greet")
+                    .parse()
+                    .unwrap(),
+                None,
+                NoopValidator,
+            )
+            .unwrap();
+        schemas.apply_updates(vec![command]).unwrap();
+
+        let_assert!(
+            Err(SchemasUpdateError::UnsupportedOffsetsReset(id)) =
+                schemas.compute_reset_subscription_offsets(subscription.id())
+        );
+        assert_eq!(id, subscription.id());
+    }
+}
@@ -46,6 +46,32 @@ impl ServiceMetadataResolver for Schemas {
             service_schemas.location.is_ingress_available()
         })
     }
+
+    fn is_call_allowed(
+        &self,
+        caller_service_name: impl AsRef<str>,
+        service_name: impl AsRef<str>,
+    ) -> Option<bool> {
+        self.use_service_schema(service_name.as_ref(), |service_schemas| {
+            service_schemas
+                .call_policy
+                .is_caller_allowed(caller_service_name.as_ref())
+        })
+    }
+
+    fn is_server_streaming(
+        &self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+    ) -> Option<bool> {
+        self.use_service_schema(service_name.as_ref(), |service_schemas| {
+            service_schemas
+                .methods
+                .get(method_name.as_ref())
+                .map(|method_desc| method_desc.descriptor().is_server_streaming())
+        })
+        .flatten()
+    }
 }
 
 pub(crate) fn map_to_service_metadata(
@@ -82,6 +108,9 @@ pub(crate) fn map_to_service_metadata(
             deployment_id: *latest_deployment,
             revision: service_schemas.revision,
             public: *public,
+            routing: service_schemas.routing.clone(),
+            configuration: service_schemas.configuration.clone(),
+            call_policy: service_schemas.call_policy.clone(),
         }),
     }
 }
@@ -68,6 +68,7 @@ impl RawEntryCodec for ProtobufRawEntryCodec {
         match_decode!(entry_type, entry_value, {
             PollInputStream,
             OutputStream,
+            OutputStreamChunk,
             GetState,
             SetState,
             ClearState,
@@ -137,8 +138,8 @@ mod mocks {
         get_state_keys_entry_message, invoke_entry_message, output_stream_entry_message,
         AwakeableEntryMessage, BackgroundInvokeEntryMessage, ClearAllStateEntryMessage,
         ClearStateEntryMessage, CompleteAwakeableEntryMessage, Failure, GetStateEntryMessage,
-        GetStateKeysEntryMessage, InvokeEntryMessage, OutputStreamEntryMessage,
-        PollInputStreamEntryMessage, SetStateEntryMessage,
+        GetStateKeysEntryMessage, Header, InvokeEntryMessage, OutputStreamChunkEntryMessage,
+        OutputStreamEntryMessage, PollInputStreamEntryMessage, SetStateEntryMessage,
     };
     use restate_types::journal::enriched::{
         AwakeableEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry,
@@ -173,6 +174,12 @@ mod mocks {
                     .encode_to_vec()
                     .into(),
                 ),
+                Entry::OutputStreamChunk(entry) => PlainRawEntry::new(
+                    PlainEntryHeader::OutputStreamChunk {},
+                    OutputStreamChunkEntryMessage { value: entry.value }
+                        .encode_to_vec()
+                        .into(),
+                ),
                 Entry::GetState(entry) => PlainRawEntry::new(
                     PlainEntryHeader::GetState {
                         is_completed: entry.is_completed(),
@@ -221,6 +228,15 @@ mod mocks {
                         service_name: entry.request.service_name.into(),
                         method_name: entry.request.method_name.into(),
                         parameter: entry.request.parameter,
+                        headers: entry
+                            .request
+                            .headers
+                            .into_iter()
+                            .map(|header| Header {
+                                key: header.name.into(),
+                                value: header.value.into(),
+                            })
+                            .collect(),
                         result: entry.result.map(|r| match r {
                             EntryResult::Success(v) => invoke_entry_message::Result::Value(v),
                             EntryResult::Failure(code, msg) => {
@@ -242,6 +258,15 @@ mod mocks {
                         service_name: entry.request.service_name.into(),
                         method_name: entry.request.method_name.into(),
                         parameter: entry.request.parameter,
+                        headers: entry
+                            .request
+                            .headers
+                            .into_iter()
+                            .map(|header| Header {
+                                key: header.name.into(),
+                                value: header.value.into(),
+                            })
+                            .collect(),
                         invoke_time: entry.invoke_time,
                     }
                     .encode_to_vec()
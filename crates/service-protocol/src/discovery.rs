@@ -25,6 +25,7 @@ use prost_reflect::{DescriptorError, DescriptorPool};
 use codederror::CodedError;
 use restate_errors::{warn_it, META0003};
 use restate_schema_api::deployment::ProtocolType;
+use restate_schema_api::json_manifest::JsonServicesManifest;
 use restate_service_client::{Endpoint, Parts, Request, ServiceClient, ServiceClientError};
 
 use restate_types::retries::{RetryIter, RetryPolicy};
@@ -33,6 +34,10 @@ use restate_types::retries::{RetryIter, RetryPolicy};
 // https://github.com/rust-lang/rust/issues/40543#issuecomment-1212981256
 #[allow(clippy::declare_interior_mutable_const)]
 const APPLICATION_PROTO: HeaderValue = HeaderValue::from_static("application/proto");
+const APPLICATION_JSON: &str = "application/json";
+#[allow(clippy::declare_interior_mutable_const)]
+const ACCEPT_PROTO_OR_JSON: HeaderValue =
+    HeaderValue::from_static("application/proto, application/json");
 
 const DISCOVER_PATH: &str = "/discover";
 
@@ -73,6 +78,22 @@ mod pb {
         #[prost(enumeration = "ProtocolMode", tag = "5")]
         pub protocol_mode: i32,
     }
+
+    /// JSON alternative to [`ServiceDiscoveryResponse`], for SDKs without protobuf tooling.
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JsonServiceDiscoveryResponse {
+        pub protocol_mode: JsonProtocolMode,
+        #[serde(flatten)]
+        pub manifest: JsonServicesManifest,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    pub enum JsonProtocolMode {
+        BidiStream,
+        RequestResponse,
+    }
 }
 
 #[derive(Debug)]
@@ -109,7 +130,7 @@ impl DiscoverEndpoint {
     fn request(&self) -> Request<Body> {
         let mut headers = HeaderMap::from_iter([
             (CONTENT_TYPE, APPLICATION_PROTO),
-            (ACCEPT, APPLICATION_PROTO),
+            (ACCEPT, ACCEPT_PROTO_OR_JSON),
         ]);
         headers.extend(self.1.clone());
         let path = PathAndQuery::from_static(DISCOVER_PATH);
@@ -120,10 +141,20 @@ impl DiscoverEndpoint {
     }
 }
 
+#[derive(Debug)]
+pub enum DiscoveredServices {
+    Protobuf {
+        services: Vec<String>,
+        descriptor_pool: DescriptorPool,
+    },
+    /// The deployment described its services with a JSON manifest,
+    /// from which the schema registry synthesizes the descriptors.
+    Json(JsonServicesManifest),
+}
+
 #[derive(Debug)]
 pub struct DiscoveredEndpointMetadata {
-    pub services: Vec<String>,
-    pub descriptor_pool: DescriptorPool,
+    pub services: DiscoveredServices,
     pub protocol_type: ProtocolType,
 }
 
@@ -139,6 +170,9 @@ pub enum ServiceDiscoveryError {
     #[error("received a bad response from the SDK with a descriptor set that cannot be reconstructed: {0}. This might be a symptom of an SDK bug")]
     #[code(unknown)]
     Descriptor(#[from] DescriptorError),
+    #[error("received a bad response from the SDK with a JSON manifest that cannot be decoded: {0}. This might be a symptom of an SDK bug")]
+    #[code(unknown)]
+    DecodeJson(#[from] serde_json::Error),
 
     // Network related retryable errors
     #[error("retry limit exhausted. Last bad status code: {0}")]
@@ -169,21 +203,33 @@ impl ServiceDiscovery {
         match content_type {
             // False positive with Bytes field
             #[allow(clippy::borrow_interior_mutable_const)]
-            Some(ct) if ct == APPLICATION_PROTO => {}
-            _ => {
-                return Err(ServiceDiscoveryError::BadResponse(
-                    "Bad content type header",
-                ));
+            Some(ct) if ct == APPLICATION_PROTO => Self::decode_proto_response(body),
+            // JSON responses might carry a charset parameter
+            Some(ct)
+                if ct.to_str().is_ok_and(|ct| {
+                    ct.split(';').next().map(str::trim) == Some(APPLICATION_JSON)
+                }) =>
+            {
+                Self::decode_json_response(body)
             }
+            _ => Err(ServiceDiscoveryError::BadResponse(
+                "Bad content type header",
+            )),
         }
+    }
 
+    fn decode_proto_response(
+        body: Bytes,
+    ) -> Result<DiscoveredEndpointMetadata, ServiceDiscoveryError> {
         // Build the descriptor pool
         let response: pb::ServiceDiscoveryResponse = pb::ServiceDiscoveryResponse::decode(body)?;
         let descriptor_pool = DescriptorPool::decode(patch_built_in_descriptors(response.files)?)?;
 
         Ok(DiscoveredEndpointMetadata {
-            services: response.services,
-            descriptor_pool,
+            services: DiscoveredServices::Protobuf {
+                services: response.services,
+                descriptor_pool,
+            },
             protocol_type: match pb::ProtocolMode::try_from(response.protocol_mode) {
                 Ok(pb::ProtocolMode::BidiStream) => ProtocolType::BidiStream,
                 Ok(pb::ProtocolMode::RequestResponse) => ProtocolType::RequestResponse,
@@ -196,6 +242,20 @@ impl ServiceDiscovery {
         })
     }
 
+    fn decode_json_response(
+        body: Bytes,
+    ) -> Result<DiscoveredEndpointMetadata, ServiceDiscoveryError> {
+        let response: pb::JsonServiceDiscoveryResponse = serde_json::from_slice(&body)?;
+
+        Ok(DiscoveredEndpointMetadata {
+            services: DiscoveredServices::Json(response.manifest),
+            protocol_type: match response.protocol_mode {
+                pb::JsonProtocolMode::BidiStream => ProtocolType::BidiStream,
+                pb::JsonProtocolMode::RequestResponse => ProtocolType::RequestResponse,
+            },
+        })
+    }
+
     async fn invoke_discovery_endpoint(
         client: &ServiceClient,
         address: impl Display,
@@ -534,3 +594,49 @@ mod prost_reflect_types {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_schema_api::service::InstanceType;
+    use restate_test_util::{assert, assert_eq, let_assert};
+
+    #[test]
+    fn decode_json_response() {
+        let metadata = ServiceDiscovery::decode_json_response(Bytes::from_static(
+            br#"{
+                "protocolMode": "REQUEST_RESPONSE",
+                "services": [{
+                    "name": "greeter.Greeter",
+                    "instanceType": "Keyed",
+                    "handlers": [{
+                        "name": "Greet",
+                        "key": "name",
+                        "inputSchema": { "type": "object", "properties": { "name": { "type": "string" } } }
+                    }]
+                }]
+            }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(metadata.protocol_type, ProtocolType::RequestResponse);
+        let_assert!(DiscoveredServices::Json(manifest) = metadata.services);
+        assert_eq!(manifest.services.len(), 1);
+        assert_eq!(manifest.services[0].instance_type, InstanceType::Keyed);
+        assert_eq!(
+            manifest.services[0].handlers[0].key.as_deref(),
+            Some("name")
+        );
+        assert!(manifest.services[0].handlers[0].output_schema.is_none());
+    }
+
+    #[test]
+    fn decode_json_response_with_unknown_protocol_mode() {
+        let_assert!(
+            Err(ServiceDiscoveryError::DecodeJson(_)) = ServiceDiscovery::decode_json_response(
+                Bytes::from_static(br#"{ "protocolMode": "STREAMING", "services": [] }"#,)
+            )
+        );
+    }
+}
@@ -36,6 +36,7 @@ pub mod pb {
 mod pb_into {
     use super::pb::protocol::*;
 
+    use restate_types::invocation;
     use restate_types::journal::*;
 
     impl TryFrom<PollInputStreamEntryMessage> for Entry {
@@ -68,6 +69,16 @@ mod pb_into {
         }
     }
 
+    impl TryFrom<OutputStreamChunkEntryMessage> for Entry {
+        type Error = &'static str;
+
+        fn try_from(msg: OutputStreamChunkEntryMessage) -> Result<Self, Self::Error> {
+            Ok(Entry::OutputStreamChunk(OutputStreamChunkEntry {
+                value: msg.value,
+            }))
+        }
+    }
+
     impl TryFrom<GetStateEntryMessage> for Entry {
         type Error = &'static str;
 
@@ -154,6 +165,7 @@ mod pb_into {
                     service_name: msg.service_name.into(),
                     method_name: msg.method_name.into(),
                     parameter: msg.parameter,
+                    headers: msg.headers.into_iter().map(Into::into).collect(),
                 },
                 result: msg.result.map(|v| match v {
                     invoke_entry_message::Result::Value(r) => EntryResult::Success(r),
@@ -174,6 +186,7 @@ mod pb_into {
                     service_name: msg.service_name.into(),
                     method_name: msg.method_name.into(),
                     parameter: msg.parameter,
+                    headers: msg.headers.into_iter().map(Into::into).collect(),
                 },
                 invoke_time: msg.invoke_time,
             }))
@@ -211,4 +224,10 @@ mod pb_into {
             }))
         }
     }
+
+    impl From<Header> for invocation::Header {
+        fn from(header: Header) -> Self {
+            invocation::Header::new(header.key, header.value)
+        }
+    }
 }
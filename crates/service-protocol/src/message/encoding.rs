@@ -289,6 +289,7 @@ fn message_header_to_raw_header(message_header: &MessageHeader) -> PlainEntryHea
             is_completed: expect_flag!(message_header, completed),
         },
         MessageType::OutputStreamEntry => PlainEntryHeader::OutputStream {},
+        MessageType::OutputStreamChunkEntry => PlainEntryHeader::OutputStreamChunk {},
         MessageType::GetStateEntry => PlainEntryHeader::GetState {
             is_completed: expect_flag!(message_header, completed),
         },
@@ -322,6 +323,7 @@ fn raw_header_to_message_type(entry_header: &PlainEntryHeader) -> MessageType {
     match entry_header {
         PlainEntryHeader::PollInputStream { .. } => MessageType::PollInputStreamEntry,
         PlainEntryHeader::OutputStream { .. } => MessageType::OutputStreamEntry,
+        PlainEntryHeader::OutputStreamChunk { .. } => MessageType::OutputStreamChunkEntry,
         PlainEntryHeader::GetState { .. } => MessageType::GetStateEntry,
         PlainEntryHeader::SetState { .. } => MessageType::SetStateEntry,
         PlainEntryHeader::ClearState { .. } => MessageType::ClearStateEntry,
@@ -353,7 +355,7 @@ mod tests {
         let mut decoder = Decoder::default();
 
         let expected_msg_0 =
-            ProtocolMessage::new_start_message("key".into(), "key".into(), 1, true, vec![]);
+            ProtocolMessage::new_start_message("key".into(), "key".into(), 1, true, vec![], vec![]);
 
         let expected_msg_1: ProtocolMessage =
             ProtobufRawEntryCodec::serialize_as_unary_input_entry(Bytes::from_static(
@@ -34,6 +34,7 @@ pub enum MessageType {
     EntryAck,
     PollInputStreamEntry,
     OutputStreamEntry,
+    OutputStreamChunkEntry,
     GetStateEntry,
     SetStateEntry,
     ClearStateEntry,
@@ -58,6 +59,7 @@ impl MessageType {
             MessageType::EntryAck => MessageKind::Core,
             MessageType::PollInputStreamEntry => MessageKind::IO,
             MessageType::OutputStreamEntry => MessageKind::IO,
+            MessageType::OutputStreamChunkEntry => MessageKind::IO,
             MessageType::GetStateEntry => MessageKind::State,
             MessageType::SetStateEntry => MessageKind::State,
             MessageType::ClearStateEntry => MessageKind::State,
@@ -104,6 +106,7 @@ const ENTRY_ACK_MESSAGE_TYPE: u16 = 0x0004;
 const END_MESSAGE_TYPE: u16 = 0x0005;
 const POLL_INPUT_STREAM_ENTRY_MESSAGE_TYPE: u16 = 0x0400;
 const OUTPUT_STREAM_ENTRY_MESSAGE_TYPE: u16 = 0x0401;
+const OUTPUT_STREAM_CHUNK_ENTRY_MESSAGE_TYPE: u16 = 0x0402;
 const GET_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0800;
 const SET_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0801;
 const CLEAR_STATE_ENTRY_MESSAGE_TYPE: u16 = 0x0802;
@@ -126,6 +129,7 @@ impl From<MessageType> for MessageTypeId {
             MessageType::EntryAck => ENTRY_ACK_MESSAGE_TYPE,
             MessageType::PollInputStreamEntry => POLL_INPUT_STREAM_ENTRY_MESSAGE_TYPE,
             MessageType::OutputStreamEntry => OUTPUT_STREAM_ENTRY_MESSAGE_TYPE,
+            MessageType::OutputStreamChunkEntry => OUTPUT_STREAM_CHUNK_ENTRY_MESSAGE_TYPE,
             MessageType::GetStateEntry => GET_STATE_ENTRY_MESSAGE_TYPE,
             MessageType::SetStateEntry => SET_STATE_ENTRY_MESSAGE_TYPE,
             MessageType::ClearStateEntry => CLEAR_STATE_ENTRY_MESSAGE_TYPE,
@@ -158,6 +162,7 @@ impl TryFrom<MessageTypeId> for MessageType {
             ENTRY_ACK_MESSAGE_TYPE => Ok(MessageType::EntryAck),
             POLL_INPUT_STREAM_ENTRY_MESSAGE_TYPE => Ok(MessageType::PollInputStreamEntry),
             OUTPUT_STREAM_ENTRY_MESSAGE_TYPE => Ok(MessageType::OutputStreamEntry),
+            OUTPUT_STREAM_CHUNK_ENTRY_MESSAGE_TYPE => Ok(MessageType::OutputStreamChunkEntry),
             GET_STATE_ENTRY_MESSAGE_TYPE => Ok(MessageType::GetStateEntry),
             SET_STATE_ENTRY_MESSAGE_TYPE => Ok(MessageType::SetStateEntry),
             CLEAR_STATE_ENTRY_MESSAGE_TYPE => Ok(MessageType::ClearStateEntry),
@@ -16,6 +16,7 @@ use super::pb;
 use bytes::Bytes;
 use prost::Message;
 use restate_types::errors::InvocationError;
+use restate_types::invocation::Header;
 use restate_types::journal::raw::PlainRawEntry;
 use restate_types::journal::CompletionResult;
 use restate_types::journal::{Completion, EntryIndex};
@@ -47,6 +48,7 @@ impl ProtocolMessage {
         known_entries: u32,
         partial_state: bool,
         state_map_entries: impl IntoIterator<Item = (Bytes, Bytes)>,
+        headers: impl IntoIterator<Item = Header>,
     ) -> Self {
         Self::Start(pb::protocol::StartMessage {
             id,
@@ -57,6 +59,13 @@ impl ProtocolMessage {
                 .into_iter()
                 .map(|(key, value)| pb::protocol::start_message::StateEntry { key, value })
                 .collect(),
+            headers: headers
+                .into_iter()
+                .map(|header| pb::protocol::Header {
+                    key: header.name.into(),
+                    value: header.value.into(),
+                })
+                .collect(),
         })
     }
 
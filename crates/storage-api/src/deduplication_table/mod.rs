@@ -34,6 +34,12 @@ pub trait DeduplicationTable {
         sequence_number: u64,
     ) -> impl Future<Output = ()> + Send;
 
+    fn delete_sequence_number(
+        &mut self,
+        partition_id: PartitionId,
+        source: SequenceNumberSource,
+    ) -> impl Future<Output = ()> + Send;
+
     fn get_all_sequence_numbers(
         &mut self,
         partition_id: PartitionId,
@@ -9,9 +9,10 @@
 // by the Apache License, Version 2.0.
 
 use crate::Result;
+use bytes::Bytes;
 use restate_types::identifiers::{FullInvocationId, PartitionId};
 use restate_types::invocation::{
-    InvocationResponse, InvocationTermination, ResponseResult, ServiceInvocation,
+    Header, InvocationResponse, InvocationTermination, ResponseResult, ServiceInvocation,
 };
 use restate_types::GenerationalNodeId;
 use std::future::Future;
@@ -34,8 +35,31 @@ pub enum OutboxMessage {
         response: ResponseResult,
     },
 
+    /// Single message of a server-streaming response to send to an ingress
+    IngressResponseChunk {
+        to_node_id: GenerationalNodeId,
+        full_invocation_id: FullInvocationId,
+        chunk: Bytes,
+    },
+
     /// Terminate invocation to send to another partition processor
     InvocationTermination(InvocationTermination),
+
+    /// Record to publish to a Kafka topic
+    KafkaRecord(KafkaRecord),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KafkaRecord {
+    /// Identifier of the record, unique per publish request. It can be used by consumers to
+    /// deduplicate records in case the publishing is retried.
+    pub message_id: String,
+    pub cluster: String,
+    pub topic: String,
+    pub key: Option<Bytes>,
+    pub payload: Bytes,
+    pub headers: Vec<Header>,
 }
 
 pub trait OutboxTable {
@@ -15,7 +15,8 @@ use restate_types::identifiers::{
     DeploymentId, EntryIndex, FullInvocationId, InvocationUuid, PartitionKey, ServiceId,
 };
 use restate_types::invocation::{
-    ServiceInvocationResponseSink, ServiceInvocationSpanContext, Source,
+    Header, InvocationFailurePolicy, ServiceInvocationResponseSink, ServiceInvocationSpanContext,
+    Source,
 };
 use restate_types::time::MillisSinceEpoch;
 use std::collections::HashSet;
@@ -188,9 +189,15 @@ pub struct InvocationMetadata {
     pub response_sink: Option<ServiceInvocationResponseSink>,
     pub timestamps: StatusTimestamps,
     pub source: Source,
+    pub headers: Vec<Header>,
+    pub failure_policy: InvocationFailurePolicy,
+    /// Number of failed attempts to execute the invocation.
+    /// Tracked only if the failure policy limits the attempts.
+    pub attempts: u32,
 }
 
 impl InvocationMetadata {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         invocation_uuid: InvocationUuid,
         journal_metadata: JournalMetadata,
@@ -199,6 +206,9 @@ impl InvocationMetadata {
         response_sink: Option<ServiceInvocationResponseSink>,
         timestamps: StatusTimestamps,
         source: Source,
+        headers: Vec<Header>,
+        failure_policy: InvocationFailurePolicy,
+        attempts: u32,
     ) -> Self {
         Self {
             invocation_uuid,
@@ -208,6 +218,9 @@ impl InvocationMetadata {
             response_sink,
             timestamps,
             source,
+            headers,
+            failure_policy,
+            attempts,
         }
     }
 }
@@ -257,6 +270,9 @@ mod mocks {
                 response_sink: None,
                 timestamps: StatusTimestamps::now(),
                 source: Source::Ingress,
+                headers: vec![],
+                failure_policy: InvocationFailurePolicy::default(),
+                attempts: 0,
             }
         }
     }
@@ -10,6 +10,7 @@
 
 use crate::timer_table::Timer::CompleteSleepEntry;
 use crate::Result;
+use bytestring::ByteString;
 use futures_util::Stream;
 use restate_types::identifiers::PartitionId;
 use restate_types::identifiers::{InvocationUuid, ServiceId};
@@ -45,6 +46,8 @@ impl Ord for TimerKey {
 pub enum Timer {
     CompleteSleepEntry(ServiceId),
     Invoke(ServiceId, ServiceInvocation),
+    /// Removes the deduplication entry of an idempotent ingress request once its retention expired.
+    CleanIngressDeduplication(ServiceId, ByteString),
 }
 
 impl Timer {
@@ -52,6 +55,7 @@ impl Timer {
         match self {
             CompleteSleepEntry(service_id) => service_id,
             Timer::Invoke(service_id, _) => service_id,
+            Timer::CleanIngressDeduplication(service_id, _) => service_id,
         }
     }
 }
@@ -22,7 +22,8 @@ pub mod storage {
         pub mod pb_conversion {
             use crate::storage::v1::enriched_entry_header::{
                 Awakeable, BackgroundCall, ClearAllState, ClearState, CompleteAwakeable, Custom,
-                GetState, GetStateKeys, Invoke, OutputStream, PollInputStream, SetState, Sleep,
+                GetState, GetStateKeys, Invoke, OutputStream, OutputStreamChunk, PollInputStream,
+                SetState, Sleep,
             };
             use crate::storage::v1::invocation_status::{Free, Invoked, Suspended, Virtual};
             use crate::storage::v1::journal_entry::completion_result::{Empty, Failure, Success};
@@ -30,20 +31,21 @@ pub mod storage {
                 completion_result, CompletionResult, Entry, Kind,
             };
             use crate::storage::v1::outbox_message::{
-                OutboxCancel, OutboxIngressResponse, OutboxKill, OutboxServiceInvocation,
-                OutboxServiceInvocationResponse,
+                OutboxCancel, OutboxIngressResponse, OutboxIngressResponseChunk, OutboxKafkaRecord,
+                OutboxKill, OutboxServiceInvocation, OutboxServiceInvocationResponse,
             };
             use crate::storage::v1::service_invocation_response_sink::{
                 Ingress, NewInvocation, PartitionProcessor, ResponseSink,
             };
             use crate::storage::v1::{
-                enriched_entry_header, inbox_entry, invocation_resolution_result,
+                enriched_entry_header, failure_policy, inbox_entry, invocation_resolution_result,
                 invocation_status, maybe_full_invocation_id, outbox_message, response_result,
                 source, span_relation, timer, BackgroundCallResolutionResult, EnrichedEntryHeader,
-                FullInvocationId, InboxEntry, InvocationResolutionResult, InvocationStatus,
-                JournalEntry, JournalMeta, KvPair, MaybeFullInvocationId, OutboxMessage,
-                ResponseResult, ServiceId, ServiceInvocation, ServiceInvocationResponseSink,
-                Source, SpanContext, SpanRelation, StateMutation, Timer,
+                FailurePolicy, FullInvocationId, Header, InboxEntry, InvocationResolutionResult,
+                InvocationStatus, JournalEntry, JournalMeta, KvPair, MaybeFullInvocationId,
+                OutboxMessage, ResponseResult, ServiceId, ServiceInvocation,
+                ServiceInvocationResponseSink, Source, SpanContext, SpanRelation, StateMutation,
+                Timer,
             };
             use anyhow::anyhow;
             use bytes::{Buf, Bytes};
@@ -219,6 +221,9 @@ pub mod storage {
                             MillisSinceEpoch::new(value.modification_time),
                         ),
                         source,
+                        value.headers.into_iter().map(Into::into).collect(),
+                        value.failure_policy.map(Into::into).unwrap_or_default(),
+                        value.attempts,
                     ))
                 }
             }
@@ -233,6 +238,9 @@ pub mod storage {
                         journal_metadata,
                         timestamps,
                         source,
+                        headers,
+                        failure_policy,
+                        attempts,
                     } = value;
 
                     Invoked {
@@ -249,6 +257,9 @@ pub mod storage {
                         creation_time: timestamps.creation_time().as_u64(),
                         modification_time: timestamps.modification_time().as_u64(),
                         source: Some(Source::from(source)),
+                        headers: headers.into_iter().map(Into::into).collect(),
+                        failure_policy: Some(FailurePolicy::from(failure_policy)),
+                        attempts,
                     }
                 }
             }
@@ -311,6 +322,9 @@ pub mod storage {
                                 MillisSinceEpoch::new(value.modification_time),
                             ),
                             caller,
+                            value.headers.into_iter().map(Into::into).collect(),
+                            value.failure_policy.map(Into::into).unwrap_or_default(),
+                            value.attempts,
                         ),
                         waiting_for_completed_entries,
                     ))
@@ -352,6 +366,9 @@ pub mod storage {
                         modification_time: metadata.timestamps.modification_time().as_u64(),
                         waiting_for_completed_entries,
                         source: Some(Source::from(metadata.source)),
+                        headers: metadata.headers.into_iter().map(Into::into).collect(),
+                        failure_policy: Some(FailurePolicy::from(metadata.failure_policy)),
+                        attempts: metadata.attempts,
                     }
                 }
             }
@@ -591,6 +608,8 @@ pub mod storage {
                         span_context,
                         argument,
                         source,
+                        headers,
+                        failure_policy,
                     } = value;
 
                     let id = restate_types::identifiers::FullInvocationId::try_from(
@@ -622,6 +641,8 @@ pub mod storage {
                         source,
                         response_sink,
                         span_context,
+                        headers: headers.into_iter().map(Into::into).collect(),
+                        failure_policy: failure_policy.map(Into::into).unwrap_or_default(),
                     })
                 }
             }
@@ -641,6 +662,45 @@ pub mod storage {
                         method_name,
                         argument: value.argument,
                         source: Some(source),
+                        headers: value.headers.into_iter().map(Into::into).collect(),
+                        failure_policy: Some(FailurePolicy::from(value.failure_policy)),
+                    }
+                }
+            }
+
+            impl From<Header> for restate_types::invocation::Header {
+                fn from(value: Header) -> Self {
+                    restate_types::invocation::Header::new(value.name, value.value)
+                }
+            }
+
+            impl From<restate_types::invocation::Header> for Header {
+                fn from(value: restate_types::invocation::Header) -> Self {
+                    Header {
+                        name: value.name.into(),
+                        value: value.value.into(),
+                    }
+                }
+            }
+
+            impl From<FailurePolicy> for restate_types::invocation::InvocationFailurePolicy {
+                fn from(value: FailurePolicy) -> Self {
+                    restate_types::invocation::InvocationFailurePolicy {
+                        max_attempts: value.max_attempts,
+                        dead_letter: value
+                            .dead_letter
+                            .map(|dead_letter| (dead_letter.cluster, dead_letter.topic)),
+                    }
+                }
+            }
+
+            impl From<restate_types::invocation::InvocationFailurePolicy> for FailurePolicy {
+                fn from(value: restate_types::invocation::InvocationFailurePolicy) -> Self {
+                    FailurePolicy {
+                        max_attempts: value.max_attempts,
+                        dead_letter: value
+                            .dead_letter
+                            .map(|(cluster, topic)| failure_policy::DeadLetter { cluster, topic }),
                     }
                 }
             }
@@ -1180,6 +1240,10 @@ pub mod storage {
                             restate_types::journal::enriched::EnrichedEntryHeader::OutputStream {
                                                         }
                         }
+                        enriched_entry_header::Kind::OutputStreamChunk(_) => {
+                            restate_types::journal::enriched::EnrichedEntryHeader::OutputStreamChunk {
+                            }
+                        }
                         enriched_entry_header::Kind::GetState(get_state) => {
                             restate_types::journal::enriched::EnrichedEntryHeader::GetState {
                                                             is_completed: get_state.is_completed,
@@ -1273,6 +1337,9 @@ pub mod storage {
                         restate_types::journal::enriched::EnrichedEntryHeader::OutputStream{..} => {
                             enriched_entry_header::Kind::OutputStream(OutputStream {})
                         }
+                        restate_types::journal::enriched::EnrichedEntryHeader::OutputStreamChunk{..} => {
+                            enriched_entry_header::Kind::OutputStreamChunk(OutputStreamChunk {})
+                        }
                         restate_types::journal::enriched::EnrichedEntryHeader::GetState { is_completed, .. } => {
                             enriched_entry_header::Kind::GetState(GetState { is_completed })
                         }
@@ -1354,12 +1421,18 @@ pub mod storage {
                             let service_key = success.service_key;
                             let service_name = ByteString::try_from(success.service_name)
                                 .map_err(ConversionError::invalid_data)?;
+                            let denied_by_call_policy = success
+                                .denied_by_call_policy
+                                .map(ByteString::try_from)
+                                .transpose()
+                                .map_err(ConversionError::invalid_data)?;
 
                             Some(restate_types::journal::enriched::InvokeEnrichmentResult {
                                 span_context,
                                 invocation_uuid,
                                 service_key,
                                 service_name,
+                                denied_by_call_policy,
                             })
                         }
                     };
@@ -1382,12 +1455,15 @@ pub mod storage {
                                 service_key,
                                 service_name,
                                 span_context,
+                                denied_by_call_policy,
                             } => invocation_resolution_result::Result::Success(
                                 invocation_resolution_result::Success {
                                     invocation_uuid: invocation_uuid.into(),
                                     service_key,
                                     service_name: service_name.into_bytes(),
                                     span_context: Some(SpanContext::from(span_context)),
+                                    denied_by_call_policy: denied_by_call_policy
+                                        .map(ByteString::into_bytes),
                                 },
                             ),
                         },
@@ -1415,12 +1491,18 @@ pub mod storage {
                     let service_key = value.service_key;
                     let service_name = ByteString::try_from(value.service_name)
                         .map_err(ConversionError::invalid_data)?;
+                    let denied_by_call_policy = value
+                        .denied_by_call_policy
+                        .map(ByteString::try_from)
+                        .transpose()
+                        .map_err(ConversionError::invalid_data)?;
 
                     Ok(restate_types::journal::enriched::InvokeEnrichmentResult {
                         span_context,
                         invocation_uuid,
                         service_key,
                         service_name,
+                        denied_by_call_policy,
                     })
                 }
             }
@@ -1434,6 +1516,9 @@ pub mod storage {
                         service_key: value.service_key,
                         service_name: value.service_name.into_bytes(),
                         span_context: Some(SpanContext::from(value.span_context)),
+                        denied_by_call_policy: value
+                            .denied_by_call_policy
+                            .map(ByteString::into_bytes),
                     }
                 }
             }
@@ -1490,6 +1575,23 @@ pub mod storage {
                                 )?,
                             }
                         }
+                        outbox_message::OutboxMessage::IngressResponseChunk(
+                            ingress_response_chunk,
+                        ) => {
+                            restate_storage_api::outbox_table::OutboxMessage::IngressResponseChunk {
+                                full_invocation_id:
+                                    restate_types::identifiers::FullInvocationId::try_from(
+                                        ingress_response_chunk.full_invocation_id.ok_or(
+                                            ConversionError::missing_field("full_invocation_id"),
+                                        )?,
+                                    )?,
+                                to_node_id: ingress_response_chunk
+                                    .ingress_node_id
+                                    .ok_or(ConversionError::missing_field("ingress_node_id"))?
+                                    .into(),
+                                chunk: ingress_response_chunk.chunk,
+                            }
+                        }
                         outbox_message::OutboxMessage::Kill(outbox_kill) => {
                             let maybe_fid = outbox_kill.maybe_full_invocation_id.ok_or(
                                 ConversionError::missing_field("maybe_full_invocation_id"),
@@ -1514,6 +1616,22 @@ pub mod storage {
                                 ),
                             )
                         }
+                        outbox_message::OutboxMessage::KafkaRecord(kafka_record) => {
+                            restate_storage_api::outbox_table::OutboxMessage::KafkaRecord(
+                                restate_storage_api::outbox_table::KafkaRecord {
+                                    message_id: kafka_record.message_id,
+                                    cluster: kafka_record.cluster,
+                                    topic: kafka_record.topic,
+                                    key: kafka_record.key,
+                                    payload: kafka_record.payload,
+                                    headers: kafka_record
+                                        .headers
+                                        .into_iter()
+                                        .map(Into::into)
+                                        .collect(),
+                                },
+                            )
+                        }
                     };
 
                     Ok(result)
@@ -1558,6 +1676,19 @@ pub mod storage {
                                 response_result: Some(ResponseResult::from(response)),
                             })
                         }
+                        restate_storage_api::outbox_table::OutboxMessage::IngressResponseChunk {
+                            to_node_id: node_id,
+                            full_invocation_id,
+                            chunk,
+                        } => outbox_message::OutboxMessage::IngressResponseChunk(
+                            OutboxIngressResponseChunk {
+                                full_invocation_id: Some(FullInvocationId::from(
+                                    full_invocation_id,
+                                )),
+                                ingress_node_id: Some(node_id.into()),
+                                chunk,
+                            },
+                        ),
                         restate_storage_api::outbox_table::OutboxMessage::InvocationTermination(
                             invocation_termination,
                         ) => match invocation_termination.flavor {
@@ -1576,6 +1707,16 @@ pub mod storage {
                                 })
                             }
                         },
+                        restate_storage_api::outbox_table::OutboxMessage::KafkaRecord(
+                            kafka_record,
+                        ) => outbox_message::OutboxMessage::KafkaRecord(OutboxKafkaRecord {
+                            message_id: kafka_record.message_id,
+                            cluster: kafka_record.cluster,
+                            topic: kafka_record.topic,
+                            key: kafka_record.key,
+                            payload: kafka_record.payload,
+                            headers: kafka_record.headers.into_iter().map(Into::into).collect(),
+                        }),
                     };
 
                     OutboxMessage {
@@ -1654,6 +1795,13 @@ pub mod storage {
                                     restate_types::invocation::ServiceInvocation::try_from(si)?,
                                 )
                             }
+                            timer::Value::CleanIngressDeduplication(source_id) => {
+                                restate_storage_api::timer_table::Timer::CleanIngressDeduplication(
+                                    service_id,
+                                    ByteString::try_from(source_id)
+                                        .map_err(ConversionError::invalid_data)?,
+                                )
+                            }
                         },
                     )
                 }
@@ -1674,6 +1822,16 @@ pub mod storage {
                             service_key: service_id.key,
                             value: Some(timer::Value::Invoke(ServiceInvocation::from(si))),
                         },
+                        restate_storage_api::timer_table::Timer::CleanIngressDeduplication(
+                            service_id,
+                            source_id,
+                        ) => Timer {
+                            service_name: service_id.service_name.into_bytes(),
+                            service_key: service_id.key,
+                            value: Some(timer::Value::CleanIngressDeduplication(
+                                source_id.into_bytes(),
+                            )),
+                        },
                     }
                 }
             }
@@ -8,7 +8,10 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::mutation::{MutationHandlerRef, WriteStatement};
 use crate::{analyzer, physical_optimizer};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
 use datafusion::execution::context::SessionState;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
 use datafusion::physical_plan::SendableRecordBatchStream;
@@ -19,6 +22,10 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct QueryContext {
     datafusion_context: SessionContext,
+    /// Set only when the write statements are enabled.
+    mutation_handler: Option<MutationHandlerRef>,
+    /// Only the restricted contexts of the callers authorized to mutate run the write statements.
+    allow_mutations: bool,
 }
 
 impl Default for QueryContext {
@@ -56,6 +63,11 @@ impl QueryContext {
             .with_allow_symmetric_joins_without_pruning(true)
             .with_information_schema(true)
             .with_default_catalog_and_schema("restate", "public");
+
+        Self::with_config_rt(session_config, runtime)
+    }
+
+    fn with_config_rt(session_config: SessionConfig, runtime: Arc<RuntimeEnv>) -> Self {
         //
         // build the state
         //
@@ -70,7 +82,56 @@ impl QueryContext {
 
         Self {
             datafusion_context: ctx,
+            mutation_handler: None,
+            allow_mutations: false,
+        }
+    }
+
+    /// Creates a context sharing the runtime of this one, exposing only the given tables.
+    /// Write statements are rejected unless `allow_mutations` is set, which fails
+    /// if the write statements are not enabled.
+    pub async fn restricted(
+        &self,
+        tables: &[String],
+        allow_mutations: bool,
+    ) -> datafusion::common::Result<QueryContext> {
+        let state = self.datafusion_context.state();
+        let mut restricted =
+            Self::with_config_rt(state.config().clone(), state.runtime_env().clone());
+        for table in tables {
+            let provider = self
+                .datafusion_context
+                .table_provider(table.as_str())
+                .await?;
+            restricted
+                .datafusion_context
+                .register_table(table.as_str(), provider)?;
         }
+        if allow_mutations {
+            restricted.mutation_handler = Some(self.enabled_mutation_handler()?);
+            restricted.allow_mutations = true;
+        }
+        Ok(restricted)
+    }
+
+    /// Creates a context exposing the same tables as this one, running the write statements too.
+    /// Fails if the write statements are not enabled.
+    pub fn allowing_mutations(&self) -> datafusion::common::Result<QueryContext> {
+        Ok(QueryContext {
+            datafusion_context: self.datafusion_context.clone(),
+            mutation_handler: Some(self.enabled_mutation_handler()?),
+            allow_mutations: true,
+        })
+    }
+
+    fn enabled_mutation_handler(&self) -> datafusion::common::Result<MutationHandlerRef> {
+        self.mutation_handler.clone().ok_or_else(|| {
+            DataFusionError::Configuration("write statements are not enabled".to_owned())
+        })
+    }
+
+    pub(crate) fn set_mutation_handler(&mut self, mutation_handler: MutationHandlerRef) {
+        self.mutation_handler = Some(mutation_handler);
     }
 
     pub async fn execute(
@@ -79,10 +140,33 @@ impl QueryContext {
     ) -> datafusion::common::Result<SendableRecordBatchStream> {
         let state = self.datafusion_context.state();
         let statement = state.sql_to_statement(sql, "postgres")?;
+        if let Some(write_statement) = WriteStatement::parse(&statement)? {
+            let Some(mutation_handler) = self
+                .mutation_handler
+                .as_ref()
+                .filter(|_| self.allow_mutations)
+            else {
+                return Err(DataFusionError::NotImplemented(
+                    "write statements are not allowed in this query context".to_owned(),
+                ));
+            };
+            return write_statement
+                .execute(self, mutation_handler.as_ref())
+                .await;
+        }
         let plan = state.statement_to_plan(statement).await?;
         let df = self.datafusion_context.execute_logical_plan(plan).await?;
         df.execute_stream().await
     }
+
+    /// Executes a read-only query, collecting its results.
+    pub(crate) async fn collect(&self, sql: &str) -> datafusion::common::Result<Vec<RecordBatch>> {
+        let state = self.datafusion_context.state();
+        let statement = state.sql_to_statement(sql, "postgres")?;
+        let plan = state.statement_to_plan(statement).await?;
+        let df = self.datafusion_context.execute_logical_plan(plan).await?;
+        df.collect().await
+    }
 }
 
 impl AsRef<SessionContext> for QueryContext {
@@ -90,3 +174,83 @@ impl AsRef<SessionContext> for QueryContext {
         &self.datafusion_context
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use datafusion::arrow::array::UInt64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::MemTable;
+
+    fn register_table(ctx: &QueryContext, name: &str) {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::UInt64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        ctx.as_ref()
+            .register_table(
+                name,
+                Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()),
+            )
+            .unwrap();
+    }
+
+    fn num_rows(batches: Vec<RecordBatch>) -> usize {
+        batches.iter().map(RecordBatch::num_rows).sum()
+    }
+
+    #[tokio::test]
+    async fn restricted_exposes_only_the_given_tables() {
+        let ctx = QueryContext::default();
+        register_table(&ctx, "allowed");
+        register_table(&ctx, "denied");
+
+        let restricted = ctx
+            .restricted(&["allowed".to_owned()], false)
+            .await
+            .unwrap();
+        assert_eq!(
+            num_rows(restricted.collect("SELECT id FROM allowed").await.unwrap()),
+            3
+        );
+        assert!(restricted.collect("SELECT id FROM denied").await.is_err());
+        assert_eq!(
+            num_rows(
+                restricted
+                    .collect("SELECT table_name FROM information_schema.tables WHERE table_schema = 'public'")
+                    .await
+                    .unwrap()
+            ),
+            1
+        );
+
+        // The original context is left untouched
+        assert_eq!(
+            num_rows(ctx.collect("SELECT id FROM denied").await.unwrap()),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn restricted_to_unknown_tables() {
+        let ctx = QueryContext::default();
+        register_table(&ctx, "allowed");
+
+        assert!(ctx
+            .restricted(&["allowed".to_owned(), "unknown".to_owned()], false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn mutations_must_be_enabled() {
+        let ctx = QueryContext::default();
+        register_table(&ctx, "allowed");
+
+        assert!(ctx.restricted(&["allowed".to_owned()], true).await.is_err());
+        assert!(ctx.allowing_mutations().is_err());
+    }
+}
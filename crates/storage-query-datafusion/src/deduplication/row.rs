@@ -0,0 +1,35 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::schema::DeduplicationBuilder;
+use restate_storage_api::deduplication_table::SequenceNumberSource;
+use restate_storage_rocksdb::deduplication_table::OwnedDeduplicationRow;
+
+#[inline]
+pub(crate) fn append_deduplication_row(
+    builder: &mut DeduplicationBuilder,
+    dedup_row: OwnedDeduplicationRow,
+) {
+    let mut row = builder.row();
+    row.partition_id(dedup_row.partition_id);
+
+    match dedup_row.source {
+        SequenceNumberSource::Partition(source_partition_id) => {
+            row.source_type("partition");
+            row.source_partition_id(source_partition_id);
+        }
+        SequenceNumberSource::Ingress(ingress) => {
+            row.source_type("ingress");
+            row.source_ingress(&ingress);
+        }
+    }
+
+    row.sequence_number(dedup_row.sequence_number);
+}
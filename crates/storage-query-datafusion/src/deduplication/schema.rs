@@ -0,0 +1,25 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![allow(dead_code)]
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_table!(deduplication(
+    partition_id: DataType::UInt64,
+
+    source_type: DataType::LargeUtf8,
+    source_partition_id: DataType::UInt64,
+    source_ingress: DataType::LargeUtf8,
+
+    sequence_number: DataType::UInt64,
+));
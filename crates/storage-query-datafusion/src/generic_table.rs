@@ -18,16 +18,17 @@ use async_trait::async_trait;
 use datafusion::arrow::datatypes::SchemaRef;
 
 use crate::table_util::compute_ordering;
-use datafusion::common::{DataFusionError, Statistics};
+use datafusion::common::{DataFusionError, ScalarValue, Statistics};
 use datafusion::datasource::{TableProvider, TableType};
 use datafusion::execution::context::{SessionState, TaskContext};
-use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Operator, TableProviderFilterPushDown};
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::{
-    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    DisplayAs, DisplayFormatType, EmptyRecordBatchStream, ExecutionPlan, Partitioning,
+    SendableRecordBatchStream,
 };
 pub use datafusion_expr::UserDefinedLogicalNode;
-use restate_types::identifiers::PartitionKey;
+use restate_types::identifiers::{PartitionId, PartitionKey};
 
 // TODO This trait assumes every table's primary key contains a PartitionKey.
 //      This assumption is incorrect with some tables, such as sys_deployment.
@@ -41,14 +42,77 @@ pub(crate) trait RangeScanner: Send + Sync + Debug + 'static {
 
 pub(crate) type RangeScannerRef = Arc<dyn RangeScanner>;
 
+/// The partition key ranges of the partitions, used to scan the tables stored by partition id.
+#[derive(Debug, Clone)]
+pub(crate) struct PartitionTable(Arc<[(PartitionId, RangeInclusive<PartitionKey>)]>);
+
+impl PartitionTable {
+    pub(crate) fn new(
+        partitions: impl IntoIterator<Item = (PartitionId, RangeInclusive<PartitionKey>)>,
+    ) -> Self {
+        Self(partitions.into_iter().collect())
+    }
+
+    /// Returns the ids of the partitions overlapping the given partition key range,
+    /// or `None` if there is no such partition.
+    pub(crate) fn partition_ids(
+        &self,
+        partition_keys: &RangeInclusive<PartitionKey>,
+    ) -> Option<RangeInclusive<PartitionId>> {
+        if partition_keys.is_empty() {
+            return None;
+        }
+        self.0
+            .iter()
+            .filter(|(_, keys)| {
+                keys.start() <= partition_keys.end() && partition_keys.start() <= keys.end()
+            })
+            .fold(None, |ids, (id, _)| {
+                Some(match ids {
+                    None => *id..=*id,
+                    Some(ids) => *ids.start().min(id)..=*ids.end().max(id),
+                })
+            })
+    }
+
+    /// Returns the partition key range covered by the given partitions.
+    fn partition_keys(
+        &self,
+        partition_ids: &RangeInclusive<PartitionId>,
+    ) -> RangeInclusive<PartitionKey> {
+        self.0
+            .iter()
+            .filter(|(id, _)| partition_ids.contains(id))
+            .fold(EMPTY_RANGE, |range, (_, keys)| {
+                if range.is_empty() {
+                    keys.clone()
+                } else {
+                    *range.start().min(keys.start())..=*range.end().max(keys.end())
+                }
+            })
+    }
+}
+
 pub(crate) struct GenericTableProvider {
     schema: SchemaRef,
     scanner: RangeScannerRef,
+    partition_table: Option<PartitionTable>,
 }
 
 impl GenericTableProvider {
     pub(crate) fn new(schema: SchemaRef, scanner: RangeScannerRef) -> Self {
-        Self { schema, scanner }
+        Self {
+            schema,
+            scanner,
+            partition_table: None,
+        }
+    }
+
+    /// For the tables stored by partition id: the filters on the `partition_id` column restrict
+    /// the scanned partition key range too, and the rows are not ordered by partition key.
+    pub(crate) fn with_partition_table(mut self, partition_table: PartitionTable) -> Self {
+        self.partition_table = Some(partition_table);
+        self
     }
 }
 
@@ -70,17 +134,23 @@ impl TableProvider for GenericTableProvider {
         &self,
         _state: &SessionState,
         projection: Option<&Vec<usize>>,
-        _filters: &[Expr],
+        filters: &[Expr],
         _limit: Option<usize>,
     ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
         let projected_schema = match projection {
             Some(p) => SchemaRef::new(self.schema.project(p)?),
             None => self.schema.clone(),
         };
+        let output_ordering = if self.partition_table.is_some() {
+            None
+        } else {
+            compute_ordering(projected_schema.clone())
+        };
 
         Ok(Arc::new(GenericTableExecutionPlan {
-            output_ordering: compute_ordering(projected_schema.clone()),
+            output_ordering,
             projected_schema,
+            range: partition_key_range(filters, self.partition_table.as_ref()),
             scanner: Arc::clone(&self.scanner),
         }))
     }
@@ -102,6 +172,7 @@ impl TableProvider for GenericTableProvider {
 struct GenericTableExecutionPlan {
     output_ordering: Option<Vec<PhysicalSortExpr>>,
     projected_schema: SchemaRef,
+    range: RangeInclusive<PartitionKey>,
     scanner: RangeScannerRef,
 }
 
@@ -140,8 +211,14 @@ impl ExecutionPlan for GenericTableExecutionPlan {
         _partition: usize,
         _context: Arc<TaskContext>,
     ) -> datafusion::common::Result<SendableRecordBatchStream> {
-        let range = 0..=PartitionKey::MAX;
-        let stream = self.scanner.scan(range, self.projected_schema.clone());
+        if self.range.is_empty() {
+            return Ok(Box::pin(EmptyRecordBatchStream::new(
+                self.projected_schema.clone(),
+            )));
+        }
+        let stream = self
+            .scanner
+            .scan(self.range.clone(), self.projected_schema.clone());
         Ok(stream)
     }
 
@@ -154,8 +231,212 @@ impl DisplayAs for GenericTableExecutionPlan {
     fn fmt_as(&self, t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default | DisplayFormatType::Verbose => {
-                write!(f, "GenericTableExecutionPlan()",)
+                write!(f, "GenericTableExecutionPlan(range={:?})", self.range)
+            }
+        }
+    }
+}
+
+#[allow(clippy::reversed_empty_ranges)]
+const EMPTY_RANGE: RangeInclusive<PartitionKey> = 1..=0;
+
+/// Returns the partition key range satisfying the filters on the `partition_key` column,
+/// and on the `partition_id` column if the table is stored by partition id.
+///
+/// The filters are re-applied on the scanned rows, so the other filters can be ignored.
+fn partition_key_range(
+    filters: &[Expr],
+    partition_table: Option<&PartitionTable>,
+) -> RangeInclusive<PartitionKey> {
+    filters.iter().fold(0..=PartitionKey::MAX, |range, filter| {
+        let range = match column_range(filter, "partition_key") {
+            Some(keys) => intersect(range, keys),
+            None => range,
+        };
+        match (partition_table, column_range(filter, "partition_id")) {
+            (Some(partition_table), Some(ids)) => {
+                intersect(range, partition_table.partition_keys(&ids))
             }
+            _ => range,
         }
+    })
+}
+
+/// Returns the range of the values of the given `u64` column satisfying the filter,
+/// or `None` if the filter doesn't restrict them.
+fn column_range(filter: &Expr, column: &str) -> Option<RangeInclusive<u64>> {
+    match filter {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        }) => match (column_range(left, column), column_range(right, column)) {
+            (Some(left), Some(right)) => Some(intersect(left, right)),
+            (left, right) => left.or(right),
+        },
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let (op, value) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(c), Expr::Literal(value)) if c.name == column => (*op, value),
+                (Expr::Literal(value), Expr::Column(c)) if c.name == column => (op.swap()?, value),
+                _ => return None,
+            };
+            let value = literal_value(value)?;
+            match op {
+                Operator::Eq => Some(value..=value),
+                Operator::Lt if value == 0 => Some(EMPTY_RANGE),
+                Operator::Lt => Some(0..=value - 1),
+                Operator::LtEq => Some(0..=value),
+                Operator::Gt if value == u64::MAX => Some(EMPTY_RANGE),
+                Operator::Gt => Some(value + 1..=u64::MAX),
+                Operator::GtEq => Some(value..=u64::MAX),
+                _ => None,
+            }
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => match (expr.as_ref(), low.as_ref(), high.as_ref()) {
+            (Expr::Column(c), Expr::Literal(low), Expr::Literal(high)) if c.name == column => {
+                Some(literal_value(low)?..=literal_value(high)?)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn literal_value(value: &ScalarValue) -> Option<u64> {
+    match value {
+        ScalarValue::UInt64(Some(value)) => Some(*value),
+        ScalarValue::UInt32(Some(value)) => Some(u64::from(*value)),
+        ScalarValue::Int64(Some(value)) => u64::try_from(*value).ok(),
+        ScalarValue::Int32(Some(value)) => u64::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+fn intersect(a: RangeInclusive<u64>, b: RangeInclusive<u64>) -> RangeInclusive<u64> {
+    *a.start().max(b.start())..=*a.end().min(b.end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use datafusion::prelude::{col, lit};
+
+    fn partition_table() -> PartitionTable {
+        PartitionTable::new([(0, 0..=99), (1, 100..=199), (2, 200..=PartitionKey::MAX)])
+    }
+
+    #[test]
+    fn no_filters_scan_everything() {
+        assert_eq!(partition_key_range(&[], None), 0..=PartitionKey::MAX);
+        assert_eq!(
+            partition_key_range(&[col("service").eq(lit("greeter"))], None),
+            0..=PartitionKey::MAX
+        );
+    }
+
+    #[test]
+    fn partition_key_filters() {
+        assert_eq!(
+            partition_key_range(&[col("partition_key").eq(lit(42u64))], None),
+            42..=42
+        );
+        assert_eq!(
+            partition_key_range(
+                &[
+                    col("partition_key").gt_eq(lit(10u64)),
+                    col("partition_key").lt(lit(20i64))
+                ],
+                None
+            ),
+            10..=19
+        );
+        assert_eq!(
+            partition_key_range(
+                &[lit(10u64)
+                    .lt(col("partition_key"))
+                    .and(col("partition_key").lt_eq(lit(20u64)))],
+                None
+            ),
+            11..=20
+        );
+        assert_eq!(
+            partition_key_range(&[col("partition_key").between(lit(5u64), lit(7u64))], None),
+            5..=7
+        );
+        assert!(partition_key_range(&[col("partition_key").lt(lit(0u64))], None).is_empty());
+        assert!(partition_key_range(
+            &[
+                col("partition_key").eq(lit(1u64)),
+                col("partition_key").eq(lit(2u64))
+            ],
+            None
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn unsupported_filters_are_ignored() {
+        assert_eq!(
+            partition_key_range(
+                &[
+                    col("partition_key").not_eq(lit(42u64)),
+                    col("partition_key").gt(lit(-1i64)),
+                    col("partition_key")
+                        .eq(lit(1u64))
+                        .or(col("partition_key").eq(lit(2u64))),
+                ],
+                None
+            ),
+            0..=PartitionKey::MAX
+        );
+    }
+
+    #[test]
+    fn partition_id_filters() {
+        let partition_table = partition_table();
+
+        // without partition table, partition ids are not partition keys
+        assert_eq!(
+            partition_key_range(&[col("partition_id").eq(lit(1u64))], None),
+            0..=PartitionKey::MAX
+        );
+        assert_eq!(
+            partition_key_range(&[col("partition_id").eq(lit(1u64))], Some(&partition_table)),
+            100..=199
+        );
+        assert_eq!(
+            partition_key_range(
+                &[col("partition_id").gt_eq(lit(1u64))],
+                Some(&partition_table)
+            ),
+            100..=PartitionKey::MAX
+        );
+        assert!(
+            partition_key_range(&[col("partition_id").eq(lit(3u64))], Some(&partition_table))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn partition_ids_of_partition_keys() {
+        let partition_table = partition_table();
+
+        assert_eq!(partition_table.partition_ids(&(0..=0)), Some(0..=0));
+        assert_eq!(partition_table.partition_ids(&(99..=100)), Some(0..=1));
+        assert_eq!(
+            partition_table.partition_ids(&(150..=PartitionKey::MAX)),
+            Some(1..=2)
+        );
+        assert_eq!(partition_table.partition_ids(&EMPTY_RANGE), None);
+        assert_eq!(
+            PartitionTable::new(std::iter::empty()).partition_ids(&(0..=10)),
+            None
+        );
     }
 }
@@ -10,17 +10,22 @@
 
 mod analyzer;
 pub mod context;
+mod deduplication;
 mod deployment;
 mod generic_table;
 mod inbox;
 mod invocation_state;
 mod journal;
+mod mutation;
 mod options;
+mod outbox;
 mod physical_optimizer;
 mod service;
 mod state;
 mod status;
+mod subscription_offsets;
 mod table_macro;
 mod table_util;
+mod timer;
 
 pub use crate::options::{BuildError, Options, OptionsBuilder, OptionsBuilderError};
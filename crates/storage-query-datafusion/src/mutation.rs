@@ -0,0 +1,680 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Write statements, translated into commands sent to the worker:
+//!
+//! * `DELETE FROM state [WHERE ...]` removes the matching state entries, issuing one
+//!   [`ExternalStateMutation`] per affected service instance.
+//! * `CALL restate.cancel(<id>)` and `CALL restate.kill(<id>)` terminate invocations, issuing one
+//!   [`InvocationTermination`] per invocation. The argument is either an invocation id literal,
+//!   or a parenthesized query returning the invocation ids in its first column,
+//!   e.g. `CALL restate.cancel((SELECT id FROM sys_status WHERE pinned_deployment_id = '...'))`.
+//!
+//! Both statements return the number of affected rows/invocations in a `count` column.
+//!
+//! The write statements are not atomic: every command is applied on its own. All the commands are
+//! computed and validated before sending the first one, but if sending one of them fails, the
+//! commands sent before it stay applied.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::{Array, AsArray, UInt64Array};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, UInt64Type};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{
+    Expr, FunctionArg, FunctionArgExpr, Statement, TableFactor, Value,
+};
+use restate_types::identifiers::{InvocationId, ServiceId};
+use restate_types::invocation::{InvocationTermination, TerminationFlavor};
+use restate_types::state_mut::{ExternalStateMutation, StateMutationVersion};
+
+use crate::context::QueryContext;
+
+/// Object safe counterpart of [`restate_worker_api::Handle`], used by the write statements.
+#[async_trait]
+pub(crate) trait MutationHandler: Send + Sync {
+    async fn external_state_mutation(
+        &self,
+        mutation: ExternalStateMutation,
+    ) -> Result<(), restate_worker_api::Error>;
+
+    async fn terminate_invocation(
+        &self,
+        invocation_termination: InvocationTermination,
+    ) -> Result<(), restate_worker_api::Error>;
+}
+
+#[async_trait]
+impl<H> MutationHandler for H
+where
+    H: restate_worker_api::Handle + Send + Sync + 'static,
+{
+    async fn external_state_mutation(
+        &self,
+        mutation: ExternalStateMutation,
+    ) -> Result<(), restate_worker_api::Error> {
+        restate_worker_api::Handle::external_state_mutation(self, mutation).await
+    }
+
+    async fn terminate_invocation(
+        &self,
+        invocation_termination: InvocationTermination,
+    ) -> Result<(), restate_worker_api::Error> {
+        restate_worker_api::InvocationHandle::terminate_invocation(self, invocation_termination)
+            .await
+    }
+}
+
+pub(crate) type MutationHandlerRef = Arc<dyn MutationHandler>;
+
+pub(crate) enum WriteStatement {
+    DeleteState {
+        selection: Option<Expr>,
+    },
+    TerminateInvocations {
+        flavor: TerminationFlavor,
+        ids: Expr,
+    },
+}
+
+impl WriteStatement {
+    /// Returns `None` if the statement is not a write statement.
+    pub(crate) fn parse(statement: &DFStatement) -> datafusion::common::Result<Option<Self>> {
+        let DFStatement::Statement(statement) = statement else {
+            return Ok(None);
+        };
+
+        match statement.as_ref() {
+            Statement::Delete {
+                from, selection, ..
+            } => {
+                let is_state_table = matches!(
+                    from.as_slice(),
+                    [table] if table.joins.is_empty() && matches!(
+                        &table.relation,
+                        TableFactor::Table { name, alias: None, .. } if name.to_string().eq_ignore_ascii_case("state")
+                    )
+                );
+                if !is_state_table {
+                    return Err(DataFusionError::NotImplemented(
+                        "DELETE is only supported on the state table".to_owned(),
+                    ));
+                }
+
+                Ok(Some(WriteStatement::DeleteState {
+                    selection: selection.clone(),
+                }))
+            }
+            Statement::Call(function) => {
+                let flavor = match function.name.to_string().to_ascii_lowercase().as_str() {
+                    "restate.cancel" => TerminationFlavor::Cancel,
+                    "restate.kill" => TerminationFlavor::Kill,
+                    other => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "unknown procedure '{other}', supported procedures are restate.cancel and restate.kill"
+                        )))
+                    }
+                };
+                let [FunctionArg::Unnamed(FunctionArgExpr::Expr(ids))] = function.args.as_slice()
+                else {
+                    return Err(DataFusionError::Plan(format!(
+                        "{} expects a single argument, either an invocation id or a query returning invocation ids",
+                        function.name
+                    )));
+                };
+
+                Ok(Some(WriteStatement::TerminateInvocations {
+                    flavor,
+                    ids: ids.clone(),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) async fn execute(
+        self,
+        ctx: &QueryContext,
+        handler: &dyn MutationHandler,
+    ) -> datafusion::common::Result<SendableRecordBatchStream> {
+        let count = match self {
+            WriteStatement::DeleteState { selection } => {
+                delete_state(ctx, handler, selection).await?
+            }
+            WriteStatement::TerminateInvocations { flavor, ids } => {
+                terminate_invocations(ctx, handler, flavor, ids).await?
+            }
+        };
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "count",
+            DataType::UInt64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt64Array::from(vec![count]))],
+        )?;
+        Ok(Box::pin(MemoryStream::try_new(vec![batch], schema, None)?))
+    }
+}
+
+#[derive(Default)]
+struct ServiceInstanceState {
+    state: Vec<(Bytes, Bytes)>,
+    retained: HashMap<Bytes, Bytes>,
+    deleted_keys: usize,
+    has_non_utf8_keys: bool,
+}
+
+/// Deletes the matching state entries, returning their number.
+///
+/// The whole state of the affected service instances is read to compute their new state.
+async fn delete_state(
+    ctx: &QueryContext,
+    handler: &dyn MutationHandler,
+    selection: Option<Expr>,
+) -> datafusion::common::Result<u64> {
+    let (filter, deleted) = match selection {
+        Some(selection) => (
+            format!("WHERE {selection}"),
+            format!("coalesce(({selection}), false)"),
+        ),
+        None => (String::new(), "true".to_owned()),
+    };
+
+    // Find the affected service instances
+    let mut affected_instances = BTreeSet::new();
+    for batch in ctx
+        .collect(&format!(
+            "SELECT DISTINCT partition_key, service, service_key FROM state {filter}"
+        ))
+        .await?
+    {
+        let partition_keys = batch.column(0).as_primitive::<UInt64Type>();
+        let services = batch.column(1).as_string::<i64>();
+        let service_keys = batch.column(2).as_string::<i64>();
+        for i in 0..batch.num_rows() {
+            affected_instances.insert((
+                partition_keys.value(i),
+                services.value(i).to_owned(),
+                service_keys.value(i).to_owned(),
+            ));
+        }
+    }
+
+    // Read the whole state of each of them, to compute the mutations
+    let mut mutations = Vec::with_capacity(affected_instances.len());
+    for (partition_key, service, service_key) in affected_instances {
+        let mut instance_state = ServiceInstanceState::default();
+        for batch in ctx
+            .collect(&format!(
+                "SELECT key, value, {deleted} AS deleted FROM state \
+                 WHERE partition_key = {partition_key} AND service = {} AND service_key = {}",
+                Value::SingleQuotedString(service.clone()),
+                Value::SingleQuotedString(service_key.clone()),
+            ))
+            .await?
+        {
+            let keys = batch.column(0).as_string::<i64>();
+            let values = batch.column(1).as_binary::<i64>();
+            let deleted = batch.column(2).as_boolean();
+            for i in 0..batch.num_rows() {
+                if keys.is_null(i) {
+                    // The key cannot be represented in the new state
+                    instance_state.has_non_utf8_keys = true;
+                    continue;
+                }
+                let entry = (
+                    Bytes::copy_from_slice(keys.value(i).as_bytes()),
+                    Bytes::copy_from_slice(values.value(i)),
+                );
+                if deleted.value(i) {
+                    instance_state.deleted_keys += 1;
+                } else {
+                    instance_state
+                        .retained
+                        .insert(entry.0.clone(), entry.1.clone());
+                }
+                instance_state.state.push(entry);
+            }
+        }
+
+        if instance_state.deleted_keys == 0 {
+            // The state changed between the two reads
+            continue;
+        }
+        if instance_state.has_non_utf8_keys {
+            return Err(DataFusionError::Execution(format!(
+                "cannot delete the state of {service}/{service_key}, as it contains keys which are not valid UTF-8"
+            )));
+        }
+
+        // The mutation is rejected if the state changed since it was read
+        let version = StateMutationVersion::from_user_state(&instance_state.state).into_inner();
+        mutations.push((
+            ExternalStateMutation {
+                service_id: ServiceId::new(service, service_key),
+                version: Some(version),
+                state: instance_state.retained,
+            },
+            instance_state.deleted_keys as u64,
+        ));
+    }
+
+    let mut count = 0;
+    for (applied, (mutation, deleted_keys)) in mutations.into_iter().enumerate() {
+        handler
+            .external_state_mutation(mutation)
+            .await
+            .map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "{e}, the state of {applied} service instances was already deleted"
+                ))
+            })?;
+        count += deleted_keys;
+    }
+
+    Ok(count)
+}
+
+/// Terminates the given invocations, returning their number.
+async fn terminate_invocations(
+    ctx: &QueryContext,
+    handler: &dyn MutationHandler,
+    flavor: TerminationFlavor,
+    ids: Expr,
+) -> datafusion::common::Result<u64> {
+    let ids = match ids {
+        Expr::Value(Value::SingleQuotedString(id)) => vec![id],
+        Expr::Subquery(query) => {
+            let mut ids = vec![];
+            for batch in ctx.collect(&query.to_string()).await? {
+                let column = cast(batch.column(0), &DataType::Utf8)?;
+                let column = column.as_string::<i32>();
+                ids.extend(column.iter().flatten().map(str::to_owned));
+            }
+            ids
+        }
+        other => {
+            return Err(DataFusionError::Plan(format!(
+                "expected an invocation id or a parenthesized query, got '{other}'"
+            )))
+        }
+    };
+    let invocation_ids = ids
+        .into_iter()
+        .map(|id| {
+            id.parse::<InvocationId>().map_err(|e| {
+                DataFusionError::Execution(format!("invalid invocation id '{id}': {e}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut count = 0;
+    for invocation_id in invocation_ids {
+        handler
+            .terminate_invocation(InvocationTermination {
+                maybe_fid: invocation_id.into(),
+                flavor,
+            })
+            .await
+            .map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "{e}, {count} invocations were already terminated"
+                ))
+            })?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use datafusion::arrow::array::{LargeBinaryArray, LargeStringArray, StringArray};
+    use datafusion::datasource::MemTable;
+    use datafusion::physical_plan::common::collect;
+    use restate_types::identifiers::InvocationUuid;
+    use restate_types::invocation::MaybeFullInvocationId;
+
+    #[derive(Clone, Default)]
+    struct MockMutationHandler {
+        state_mutations: Arc<Mutex<Vec<ExternalStateMutation>>>,
+        invocation_terminations: Arc<Mutex<Vec<InvocationTermination>>>,
+        /// Number of commands accepted before failing, unset to accept all of them.
+        fail_after: Option<usize>,
+    }
+
+    impl MockMutationHandler {
+        fn accept(&self, commands: usize) -> Result<(), restate_worker_api::Error> {
+            match self.fail_after {
+                Some(fail_after) if commands >= fail_after => {
+                    Err(restate_worker_api::Error::Unreachable)
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl MutationHandler for MockMutationHandler {
+        async fn external_state_mutation(
+            &self,
+            mutation: ExternalStateMutation,
+        ) -> Result<(), restate_worker_api::Error> {
+            let mut state_mutations = self.state_mutations.lock().unwrap();
+            self.accept(state_mutations.len())?;
+            state_mutations.push(mutation);
+            Ok(())
+        }
+
+        async fn terminate_invocation(
+            &self,
+            invocation_termination: InvocationTermination,
+        ) -> Result<(), restate_worker_api::Error> {
+            let mut invocation_terminations = self.invocation_terminations.lock().unwrap();
+            self.accept(invocation_terminations.len())?;
+            invocation_terminations.push(invocation_termination);
+            Ok(())
+        }
+    }
+
+    const TABLES: [&str; 2] = ["state", "sys_status"];
+
+    /// Returns a context where the caller is allowed to mutate, with the given invocation ids in `sys_status`.
+    async fn query_context(
+        handler: &MockMutationHandler,
+        invocation_ids: &[String],
+    ) -> QueryContext {
+        let mut ctx = QueryContext::default();
+
+        let state = [
+            (1, "counter", "a", "count", "1"),
+            (1, "counter", "a", "total", "10"),
+            (2, "counter", "b", "count", "2"),
+            (3, "greeter", "a", "name", "bob"),
+        ];
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("partition_key", DataType::UInt64, true),
+            Field::new("service", DataType::LargeUtf8, true),
+            Field::new("service_key", DataType::LargeUtf8, true),
+            Field::new("key", DataType::LargeUtf8, true),
+            Field::new("value_utf8", DataType::LargeUtf8, true),
+            Field::new("value", DataType::LargeBinary, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(UInt64Array::from_iter_values(state.iter().map(|row| row.0))),
+                Arc::new(LargeStringArray::from_iter_values(
+                    state.iter().map(|row| row.1),
+                )),
+                Arc::new(LargeStringArray::from_iter_values(
+                    state.iter().map(|row| row.2),
+                )),
+                Arc::new(LargeStringArray::from_iter_values(
+                    state.iter().map(|row| row.3),
+                )),
+                Arc::new(LargeStringArray::from_iter_values(
+                    state.iter().map(|row| row.4),
+                )),
+                Arc::new(LargeBinaryArray::from_iter_values(
+                    state.iter().map(|row| row.4.as_bytes()),
+                )),
+            ],
+        )
+        .unwrap();
+        ctx.as_ref()
+            .register_table(
+                "state",
+                Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()),
+            )
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Utf8, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from_iter_values(invocation_ids))],
+        )
+        .unwrap();
+        ctx.as_ref()
+            .register_table(
+                "sys_status",
+                Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()),
+            )
+            .unwrap();
+
+        ctx.set_mutation_handler(Arc::new(handler.clone()));
+        ctx.restricted(&TABLES.map(str::to_owned), true)
+            .await
+            .unwrap()
+    }
+
+    async fn execute(ctx: &QueryContext, sql: &str) -> datafusion::common::Result<u64> {
+        let batches = collect(ctx.execute(sql).await?).await?;
+        Ok(batches[0].column(0).as_primitive::<UInt64Type>().value(0))
+    }
+
+    fn state_mutation(service: &str, key: &str, state: &[(&str, &str)]) -> ExternalStateMutation {
+        let state: Vec<_> = state
+            .iter()
+            .map(|(k, v)| {
+                (
+                    Bytes::copy_from_slice(k.as_bytes()),
+                    Bytes::copy_from_slice(v.as_bytes()),
+                )
+            })
+            .collect();
+        ExternalStateMutation {
+            service_id: ServiceId::new(service.to_owned(), key.to_owned()),
+            version: None,
+            state: state.into_iter().collect(),
+        }
+    }
+
+    /// Drops the versions, which are tested by [`StateMutationVersion`].
+    fn state_mutations(handler: &MockMutationHandler) -> Vec<ExternalStateMutation> {
+        handler
+            .state_mutations
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .map(|mutation| {
+                assert!(mutation.version.is_some());
+                ExternalStateMutation {
+                    version: None,
+                    ..mutation
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn delete_state_entries() {
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[]).await;
+
+        assert_eq!(
+            execute(&ctx, "DELETE FROM state WHERE key = 'count'")
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            state_mutations(&handler),
+            vec![
+                state_mutation("counter", "a", &[("total", "10")]),
+                state_mutation("counter", "b", &[]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_whole_state() {
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[]).await;
+
+        assert_eq!(execute(&ctx, "DELETE FROM state").await.unwrap(), 4);
+        assert_eq!(
+            state_mutations(&handler),
+            vec![
+                state_mutation("counter", "a", &[]),
+                state_mutation("counter", "b", &[]),
+                state_mutation("greeter", "a", &[]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_nothing() {
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[]).await;
+
+        assert_eq!(
+            execute(&ctx, "DELETE FROM state WHERE service = 'unknown'")
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(state_mutations(&handler).is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_state_partial_failure() {
+        let handler = MockMutationHandler {
+            fail_after: Some(1),
+            ..Default::default()
+        };
+        let ctx = query_context(&handler, &[]).await;
+
+        let error = execute(&ctx, "DELETE FROM state WHERE service = 'counter'")
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("the state of 1 service instances was already deleted"));
+        assert_eq!(
+            state_mutations(&handler),
+            vec![state_mutation("counter", "a", &[])]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_is_only_supported_on_state() {
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[]).await;
+
+        assert!(execute(&ctx, "DELETE FROM sys_status").await.is_err());
+        assert!(state_mutations(&handler).is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_invocations() {
+        let invocation_ids: Vec<_> = (0..3)
+            .map(|partition_key| InvocationId::new(partition_key, InvocationUuid::new()))
+            .collect();
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(
+            &handler,
+            &invocation_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        )
+        .await;
+
+        assert_eq!(
+            execute(
+                &ctx,
+                &format!("CALL restate.cancel('{}')", invocation_ids[0])
+            )
+            .await
+            .unwrap(),
+            1
+        );
+        assert_eq!(
+            execute(&ctx, "CALL restate.kill((SELECT id FROM sys_status))")
+                .await
+                .unwrap(),
+            3
+        );
+
+        let invocation_terminations = handler.invocation_terminations.lock().unwrap();
+        assert_eq!(
+            invocation_terminations
+                .iter()
+                .map(|termination| (termination.maybe_fid.clone(), termination.flavor))
+                .collect::<Vec<_>>(),
+            [(invocation_ids[0].clone(), TerminationFlavor::Cancel)]
+                .into_iter()
+                .chain(
+                    invocation_ids
+                        .iter()
+                        .map(|id| (id.clone(), TerminationFlavor::Kill))
+                )
+                .map(|(id, flavor)| (MaybeFullInvocationId::from(id), flavor))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_invalid_invocation_ids() {
+        let invocation_id = InvocationId::new(0, InvocationUuid::new()).to_string();
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[invocation_id, "invalid".to_owned()]).await;
+
+        // No invocation is cancelled if one of the ids is invalid
+        assert!(
+            execute(&ctx, "CALL restate.cancel((SELECT id FROM sys_status))")
+                .await
+                .is_err()
+        );
+        assert!(execute(&ctx, "CALL restate.cancel(42)").await.is_err());
+        assert!(execute(&ctx, "CALL restate.pause('id')").await.is_err());
+        assert!(handler.invocation_terminations.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mutations_require_authorization() {
+        let handler = MockMutationHandler::default();
+        let ctx = query_context(&handler, &[]).await;
+
+        let read_only = ctx
+            .restricted(&TABLES.map(str::to_owned), false)
+            .await
+            .unwrap();
+        assert!(execute(&read_only, "DELETE FROM state").await.is_err());
+        assert!(read_only.allowing_mutations().is_err());
+
+        // The write statements are not enabled without mutation handler
+        let disabled = QueryContext::default();
+        assert!(disabled.restricted(&[], true).await.is_err());
+        assert!(execute(&disabled, "CALL restate.cancel('id')")
+            .await
+            .is_err());
+
+        assert!(state_mutations(&handler).is_empty());
+        assert!(handler.invocation_terminations.lock().unwrap().is_empty());
+    }
+}
@@ -9,13 +9,18 @@
 // by the Apache License, Version 2.0.
 
 use crate::context::QueryContext;
+use crate::generic_table::PartitionTable;
 use codederror::CodedError;
 use datafusion::error::DataFusionError;
 use restate_invoker_api::StatusHandle;
 use restate_schema_api::deployment::DeploymentResolver;
 use restate_schema_api::service::ServiceMetadataResolver;
+use restate_schema_api::subscription::SubscriptionOffsetsResolver;
 use restate_storage_rocksdb::RocksDBStorage;
+use restate_types::identifiers::{PartitionId, PartitionKey};
 use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 
 /// # Storage query datafusion options
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, derive_builder::Builder)]
@@ -40,6 +45,14 @@ pub struct Options {
     ///
     /// The number of parallel partitions to use for a query execution
     pub query_parallelism: Option<usize>,
+
+    /// # Allow mutations
+    ///
+    /// Whether write statements, such as `DELETE FROM state`, are enabled.
+    /// Even when enabled, they can be run only by the admin query roles and the psql users allowed to mutate.
+    #[serde(default)]
+    #[builder(default)]
+    pub allow_mutations: bool,
 }
 
 impl Options {
@@ -54,21 +67,33 @@ impl Options {
             + Debug
             + Clone
             + 'static,
+        subscription_offsets: impl SubscriptionOffsetsResolver + Send + Sync + Debug + 'static,
+        worker_handle: impl restate_worker_api::Handle + Send + Sync + 'static,
+        partitions: impl IntoIterator<Item = (PartitionId, RangeInclusive<PartitionKey>)>,
     ) -> Result<QueryContext, BuildError> {
         let Options {
             memory_limit,
             temp_folder,
             query_parallelism,
+            allow_mutations,
         } = self;
 
-        let ctx = QueryContext::new(memory_limit, temp_folder, query_parallelism);
+        let partition_table = PartitionTable::new(partitions);
+        let mut ctx = QueryContext::new(memory_limit, temp_folder, query_parallelism);
+        if allow_mutations {
+            ctx.set_mutation_handler(Arc::new(worker_handle));
+        }
         crate::status::register_self(&ctx, rocksdb.clone())?;
         crate::state::register_self(&ctx, rocksdb.clone())?;
         crate::journal::register_self(&ctx, rocksdb.clone())?;
         crate::invocation_state::register_self(&ctx, status)?;
-        crate::inbox::register_self(&ctx, rocksdb)?;
+        crate::inbox::register_self(&ctx, rocksdb.clone())?;
+        crate::timer::register_self(&ctx, rocksdb.clone(), partition_table.clone())?;
+        crate::outbox::register_self(&ctx, rocksdb.clone(), partition_table.clone())?;
+        crate::deduplication::register_self(&ctx, rocksdb, partition_table)?;
         crate::deployment::register_self(&ctx, schemas.clone())?;
         crate::service::register_self(&ctx, schemas)?;
+        crate::subscription_offsets::register_self(&ctx, subscription_offsets)?;
 
         Ok(ctx)
     }
@@ -0,0 +1,83 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::schema::OutboxBuilder;
+use crate::table_util::format_using;
+use restate_storage_api::outbox_table::{KafkaRecord, OutboxMessage};
+use restate_storage_rocksdb::outbox_table::OwnedOutboxRow;
+use restate_types::identifiers::InvocationId;
+use restate_types::invocation::{
+    InvocationResponse, InvocationTermination, MaybeFullInvocationId, ServiceInvocation,
+};
+
+#[inline]
+pub(crate) fn append_outbox_row(
+    builder: &mut OutboxBuilder,
+    output: &mut String,
+    outbox_row: OwnedOutboxRow,
+) {
+    let mut row = builder.row();
+    row.partition_id(outbox_row.partition_id);
+    row.sequence_number(outbox_row.message_index);
+
+    let target = match outbox_row.outbox_message {
+        OutboxMessage::ServiceInvocation(ServiceInvocation {
+            fid, method_name, ..
+        }) => {
+            row.message_type("invocation");
+            row.target_method(&method_name);
+            Some(MaybeFullInvocationId::Full(fid))
+        }
+        OutboxMessage::ServiceResponse(InvocationResponse { id, .. }) => {
+            row.message_type("response");
+            Some(id)
+        }
+        OutboxMessage::IngressResponse {
+            full_invocation_id, ..
+        } => {
+            row.message_type("ingress_response");
+            Some(MaybeFullInvocationId::Full(full_invocation_id))
+        }
+        OutboxMessage::IngressResponseChunk {
+            full_invocation_id, ..
+        } => {
+            row.message_type("ingress_response_chunk");
+            Some(MaybeFullInvocationId::Full(full_invocation_id))
+        }
+        OutboxMessage::InvocationTermination(InvocationTermination { maybe_fid, .. }) => {
+            row.message_type("termination");
+            Some(maybe_fid)
+        }
+        OutboxMessage::KafkaRecord(KafkaRecord { cluster, topic, .. }) => {
+            row.message_type("kafka_record");
+            row.kafka_cluster(&cluster);
+            row.kafka_topic(&topic);
+            None
+        }
+    };
+
+    match target {
+        Some(MaybeFullInvocationId::Full(fid)) => {
+            row.target_service(&fid.service_id.service_name);
+            row.target_service_key(
+                std::str::from_utf8(&fid.service_id.key).expect("The key must be a string!"),
+            );
+            if row.is_target_id_defined() {
+                row.target_id(format_using(output, &InvocationId::from(&fid)));
+            }
+        }
+        Some(MaybeFullInvocationId::Partial(invocation_id)) => {
+            if row.is_target_id_defined() {
+                row.target_id(format_using(output, &invocation_id));
+            }
+        }
+        None => {}
+    }
+}
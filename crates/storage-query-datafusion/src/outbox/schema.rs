@@ -0,0 +1,30 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![allow(dead_code)]
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_table!(outbox(
+    partition_id: DataType::UInt64,
+    sequence_number: DataType::UInt64,
+
+    message_type: DataType::LargeUtf8,
+
+    target_service: DataType::LargeUtf8,
+    target_method: DataType::LargeUtf8,
+    target_service_key: DataType::LargeUtf8,
+    target_id: DataType::LargeUtf8,
+
+    kafka_cluster: DataType::LargeUtf8,
+    kafka_topic: DataType::LargeUtf8,
+));
@@ -0,0 +1,72 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+
+use crate::context::QueryContext;
+use crate::generic_table::{GenericTableProvider, PartitionTable, RangeScanner};
+use crate::outbox::row::append_outbox_row;
+use crate::outbox::schema::OutboxBuilder;
+use crate::table_util::send_batches;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::SendableRecordBatchStream;
+pub use datafusion_expr::UserDefinedLogicalNode;
+use restate_storage_rocksdb::RocksDBStorage;
+use restate_types::identifiers::PartitionKey;
+
+pub(crate) fn register_self(
+    ctx: &QueryContext,
+    storage: RocksDBStorage,
+    partition_table: PartitionTable,
+) -> datafusion::common::Result<()> {
+    let outbox_table = GenericTableProvider::new(
+        OutboxBuilder::schema(),
+        Arc::new(OutboxScanner(storage, partition_table.clone())),
+    )
+    .with_partition_table(partition_table);
+
+    ctx.as_ref()
+        .register_table("sys_outbox", Arc::new(outbox_table))
+        .map(|_| ())
+}
+
+#[derive(Debug, Clone)]
+struct OutboxScanner(RocksDBStorage, PartitionTable);
+
+/// The outboxes are stored by partition id, so only the partitions overlapping the range are scanned.
+impl RangeScanner for OutboxScanner {
+    fn scan(
+        &self,
+        range: RangeInclusive<PartitionKey>,
+        projection: SchemaRef,
+    ) -> SendableRecordBatchStream {
+        let db = self.0.clone();
+        let partition_ids = self.1.partition_ids(&range);
+        let schema = projection.clone();
+        let mut stream_builder = RecordBatchReceiverStream::builder(projection, 16);
+        let tx = stream_builder.tx();
+        let background_task = move || {
+            let Some(partition_ids) = partition_ids else {
+                return;
+            };
+            let rows = db.all_outboxes(partition_ids);
+            let mut temp = String::new();
+            send_batches(schema, tx, rows, |builder: &mut OutboxBuilder, row| {
+                append_outbox_row(builder, &mut temp, row)
+            });
+        };
+        stream_builder.spawn_blocking(background_task);
+        stream_builder.build()
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::schema::SubscriptionOffsetsBuilder;
+use crate::table_util::format_using;
+use restate_schema_api::subscription::SubscriptionPartitionOffsets;
+
+#[inline]
+pub(crate) fn append_subscription_offsets_row(
+    builder: &mut SubscriptionOffsetsBuilder,
+    output: &mut String,
+    offsets: SubscriptionPartitionOffsets,
+) {
+    let mut row = builder.row();
+    row.subscription_id(format_using(output, &offsets.subscription_id));
+    row.topic(&offsets.topic);
+    row.partition(offsets.partition);
+    if let Some(stored_offset) = offsets.stored_offset {
+        row.stored_offset(stored_offset);
+    }
+    if let Some(high_watermark) = offsets.high_watermark {
+        row.high_watermark(high_watermark);
+    }
+    if let Some(lag) = offsets.lag() {
+        row.lag(lag);
+    }
+}
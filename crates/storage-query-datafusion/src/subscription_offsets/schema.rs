@@ -0,0 +1,24 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![allow(dead_code)]
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_table!(subscription_offsets(
+    subscription_id: DataType::LargeUtf8,
+    topic: DataType::LargeUtf8,
+    partition: DataType::Int32,
+    stored_offset: DataType::Int64,
+    high_watermark: DataType::Int64,
+    lag: DataType::Int64,
+));
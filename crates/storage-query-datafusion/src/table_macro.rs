@@ -30,6 +30,9 @@ macro_rules! define_builder {
     (DataType::Int32) => {
         ::datafusion::arrow::array::Int32Builder
     };
+    (DataType::Int64) => {
+        ::datafusion::arrow::array::Int64Builder
+    };
     (DataType::Date64) => {
         ::datafusion::arrow::array::Date64Builder
     };
@@ -57,6 +60,9 @@ macro_rules! define_primitive_trait {
     (DataType::Int32) => {
         i32
     };
+    (DataType::Int64) => {
+        i64
+    };
     (DataType::Date64) => {
         i64
     };
@@ -435,6 +441,26 @@ macro_rules! define_table {
 
         }
 
+        impl crate::table_util::BatchBuilder for [< $table_name:camel Builder >] {
+
+            fn new(projected_schema: ::datafusion::arrow::datatypes::SchemaRef) -> Self {
+                [< $table_name:camel Builder >]::new(projected_schema)
+            }
+
+            fn full(&self) -> bool {
+                [< $table_name:camel Builder >]::full(self)
+            }
+
+            fn empty(&self) -> bool {
+                [< $table_name:camel Builder >]::empty(self)
+            }
+
+            fn finish(self) -> ::datafusion::arrow::record_batch::RecordBatch {
+                [< $table_name:camel Builder >]::finish(self)
+            }
+
+        }
+
     })
 }
 
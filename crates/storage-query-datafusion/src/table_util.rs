@@ -9,9 +9,19 @@
 // by the Apache License, Version 2.0.
 
 use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::physical_expr::expressions::col;
 use datafusion::physical_expr::PhysicalSortExpr;
 use std::fmt::Write;
+use tokio::sync::mpsc::Sender;
+
+/// Implemented by the builders generated with `define_table!`.
+pub(crate) trait BatchBuilder {
+    fn new(projected_schema: SchemaRef) -> Self;
+    fn full(&self) -> bool;
+    fn empty(&self) -> bool;
+    fn finish(self) -> RecordBatch;
+}
 
 pub(crate) fn compute_ordering(schema: SchemaRef) -> Option<Vec<PhysicalSortExpr>> {
     let ordering = vec![PhysicalSortExpr {
@@ -22,6 +32,33 @@ pub(crate) fn compute_ordering(schema: SchemaRef) -> Option<Vec<PhysicalSortExpr
     Some(ordering)
 }
 
+/// Appends the rows to record batches, sending every batch through `tx` as soon as it is full.
+pub(crate) fn send_batches<B, R>(
+    schema: SchemaRef,
+    tx: Sender<datafusion::common::Result<RecordBatch>>,
+    rows: impl Iterator<Item = R>,
+    mut append_row: impl FnMut(&mut B, R),
+) where
+    B: BatchBuilder,
+{
+    let mut builder = B::new(schema.clone());
+    for row in rows {
+        append_row(&mut builder, row);
+        if builder.full() {
+            let batch = builder.finish();
+            if tx.blocking_send(Ok(batch)).is_err() {
+                // the other side has hung up on us, there is no one left to send the rows to
+                return;
+            }
+            builder = B::new(schema.clone());
+        }
+    }
+    if !builder.empty() {
+        let result = builder.finish();
+        let _ = tx.blocking_send(Ok(result));
+    }
+}
+
 #[inline]
 pub(crate) fn format_using<'a>(output: &'a mut String, what: &impl std::fmt::Display) -> &'a str {
     output.clear();
@@ -0,0 +1,72 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::schema::TimerBuilder;
+use crate::table_util::format_using;
+use restate_storage_api::timer_table::Timer;
+use restate_storage_rocksdb::timer_table::OwnedTimerRow;
+use restate_types::identifiers::{FullInvocationId, InvocationId, WithPartitionKey};
+
+#[inline]
+pub(crate) fn append_timer_row(
+    builder: &mut TimerBuilder,
+    output: &mut String,
+    timer_row: OwnedTimerRow,
+) {
+    let OwnedTimerRow {
+        partition_id,
+        timer_key,
+        timer,
+    } = timer_row;
+
+    let mut row = builder.row();
+    let service_id = timer.service_id();
+    row.partition_key(service_id.partition_key());
+    row.partition_id(partition_id);
+
+    row.service(&service_id.service_name);
+    row.service_key(std::str::from_utf8(&service_id.key).expect("The key must be a string!"));
+    if row.is_id_defined() {
+        row.id(format_using(
+            output,
+            &InvocationId::from(&FullInvocationId::with_service_id(
+                service_id.clone(),
+                timer_key.invocation_uuid,
+            )),
+        ));
+    }
+    row.journal_index(timer_key.journal_index);
+    row.wake_up_at(timer_key.timestamp as i64);
+
+    match timer {
+        Timer::CompleteSleepEntry(_) => {
+            row.timer_type("complete_sleep");
+        }
+        Timer::Invoke(_, service_invocation) => {
+            row.timer_type("invoke");
+            row.invoked_service(&service_invocation.fid.service_id.service_name);
+            row.invoked_method(&service_invocation.method_name);
+            row.invoked_service_key(
+                std::str::from_utf8(&service_invocation.fid.service_id.key)
+                    .expect("The key must be a string!"),
+            );
+            if row.is_invoked_id_defined() {
+                row.invoked_id(format_using(
+                    output,
+                    &InvocationId::from(&service_invocation.fid),
+                ));
+            }
+        }
+        Timer::CleanIngressDeduplication(_, idempotency_key) => {
+            row.timer_type("clean_ingress_deduplication");
+            row.idempotency_key(&idempotency_key);
+        }
+    }
+}
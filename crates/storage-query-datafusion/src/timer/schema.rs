@@ -0,0 +1,35 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![allow(dead_code)]
+
+use crate::table_macro::*;
+
+use datafusion::arrow::datatypes::DataType;
+
+define_table!(timer(
+    partition_key: DataType::UInt64,
+    partition_id: DataType::UInt64,
+
+    service: DataType::LargeUtf8,
+    service_key: DataType::LargeUtf8,
+    id: DataType::LargeUtf8,
+    journal_index: DataType::UInt32,
+
+    timer_type: DataType::LargeUtf8,
+    wake_up_at: DataType::Date64,
+
+    invoked_service: DataType::LargeUtf8,
+    invoked_method: DataType::LargeUtf8,
+    invoked_service_key: DataType::LargeUtf8,
+    invoked_id: DataType::LargeUtf8,
+
+    idempotency_key: DataType::LargeUtf8,
+));
@@ -0,0 +1,74 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+
+use crate::context::QueryContext;
+use crate::generic_table::{GenericTableProvider, PartitionTable, RangeScanner};
+use crate::table_util::send_batches;
+use crate::timer::row::append_timer_row;
+use crate::timer::schema::TimerBuilder;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::SendableRecordBatchStream;
+pub use datafusion_expr::UserDefinedLogicalNode;
+use restate_storage_rocksdb::RocksDBStorage;
+use restate_types::identifiers::{PartitionKey, WithPartitionKey};
+
+pub(crate) fn register_self(
+    ctx: &QueryContext,
+    storage: RocksDBStorage,
+    partition_table: PartitionTable,
+) -> datafusion::common::Result<()> {
+    let timer_table = GenericTableProvider::new(
+        TimerBuilder::schema(),
+        Arc::new(TimerScanner(storage, partition_table.clone())),
+    )
+    .with_partition_table(partition_table);
+
+    ctx.as_ref()
+        .register_table("sys_timers", Arc::new(timer_table))
+        .map(|_| ())
+}
+
+#[derive(Debug, Clone)]
+struct TimerScanner(RocksDBStorage, PartitionTable);
+
+/// The timers are stored by partition id, so only the partitions overlapping the range are scanned.
+impl RangeScanner for TimerScanner {
+    fn scan(
+        &self,
+        range: RangeInclusive<PartitionKey>,
+        projection: SchemaRef,
+    ) -> SendableRecordBatchStream {
+        let db = self.0.clone();
+        let partition_ids = self.1.partition_ids(&range);
+        let schema = projection.clone();
+        let mut stream_builder = RecordBatchReceiverStream::builder(projection, 16);
+        let tx = stream_builder.tx();
+        let background_task = move || {
+            let Some(partition_ids) = partition_ids else {
+                return;
+            };
+            let rows = db
+                .all_timers(partition_ids)
+                .filter(|row| range.contains(&row.timer.service_id().partition_key()));
+            let mut temp = String::new();
+            send_batches(schema, tx, rows, |builder: &mut TimerBuilder, row| {
+                append_timer_row(builder, &mut temp, row)
+            });
+        };
+        stream_builder.spawn_blocking(background_task);
+        stream_builder.build()
+    }
+}
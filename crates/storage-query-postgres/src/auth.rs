@@ -0,0 +1,302 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::Sink;
+use pgwire::api::auth::cleartext::CleartextPasswordAuthStartupHandler;
+use pgwire::api::auth::noop::NoopStartupHandler;
+use pgwire::api::auth::scram::{gen_salted_password, SASLScramAuthStartupHandler};
+use pgwire::api::auth::{
+    AuthSource, DefaultServerParameterProvider, LoginInfo, Password, StartupHandler,
+};
+use pgwire::api::{ClientInfo, METADATA_USER};
+use pgwire::error::{PgWireError, PgWireResult};
+use pgwire::messages::startup::{Password as PasswordMessage, PasswordMessageFamily};
+use pgwire::messages::{PgWireBackendMessage, PgWireFrontendMessage};
+use rand::RngCore;
+use subtle::ConstantTimeEq;
+
+use crate::options::{AuthenticationMethod, AuthenticationOptions, UserOptions};
+use crate::service::Error;
+
+const SCRAM_ITERATIONS: usize = 4096;
+const SCRAM_SALT_LEN: usize = 16;
+
+/// Startup handler of the connections, depending on the configured authentication.
+pub(crate) enum Authenticator {
+    Noop(NoopStartupHandler),
+    Cleartext(
+        CleartextPasswordAuthStartupHandler<UsersAuthSource, DefaultServerParameterProvider>,
+        UsersAuthSource,
+    ),
+    Scram(SASLScramAuthStartupHandler<UsersAuthSource, DefaultServerParameterProvider>),
+}
+
+impl Authenticator {
+    /// Fails if the passwords would be sent in clear text over unencrypted connections,
+    /// or if a password is empty.
+    pub(crate) fn new(
+        authentication: Option<AuthenticationOptions>,
+        tls: bool,
+    ) -> Result<Self, Error> {
+        let Some(AuthenticationOptions { method, users }) = authentication else {
+            return Ok(Authenticator::Noop(NoopStartupHandler));
+        };
+        if method == AuthenticationMethod::Cleartext && !tls {
+            return Err(Error::CleartextWithoutTls);
+        }
+        if let Some(user) = users
+            .iter()
+            .find_map(|(user, options)| options.password.is_empty().then_some(user))
+        {
+            return Err(Error::EmptyPassword(user.clone()));
+        }
+
+        Ok(match method {
+            AuthenticationMethod::Cleartext => {
+                let users = UsersAuthSource::cleartext(users);
+                Authenticator::Cleartext(
+                    CleartextPasswordAuthStartupHandler::new(
+                        users.clone(),
+                        DefaultServerParameterProvider::default(),
+                    ),
+                    users,
+                )
+            }
+            AuthenticationMethod::Scram => {
+                let mut handler = SASLScramAuthStartupHandler::new(
+                    Arc::new(UsersAuthSource::scram(users)),
+                    DefaultServerParameterProvider::default(),
+                );
+                handler.set_iterations(SCRAM_ITERATIONS);
+                Authenticator::Scram(handler)
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl StartupHandler for Authenticator {
+    async fn on_startup<C>(
+        &self,
+        client: &mut C,
+        message: PgWireFrontendMessage,
+    ) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        match self {
+            Authenticator::Noop(handler) => handler.on_startup(client, message).await,
+            Authenticator::Cleartext(handler, users) => {
+                let message = match message {
+                    PgWireFrontendMessage::PasswordMessageFamily(password) => {
+                        // pgwire doesn't compare the passwords in constant time, so it gets the
+                        // password only if it's correct, and an empty one otherwise, which never matches
+                        let password = password.into_password()?;
+                        let user = client.metadata().get(METADATA_USER);
+                        let password = match user {
+                            Some(user) if users.verify_cleartext(user, password.password()) => {
+                                password
+                            }
+                            _ => PasswordMessage::new(String::new()),
+                        };
+                        PgWireFrontendMessage::PasswordMessageFamily(
+                            PasswordMessageFamily::Password(password),
+                        )
+                    }
+                    message => message,
+                };
+                handler.on_startup(client, message).await
+            }
+            Authenticator::Scram(handler) => handler.on_startup(client, message).await,
+        }
+    }
+}
+
+/// Passwords of the configured users. For SCRAM, only the salted passwords are kept.
+#[derive(Clone)]
+pub(crate) struct UsersAuthSource {
+    passwords: Arc<HashMap<String, Password>>,
+}
+
+impl UsersAuthSource {
+    fn cleartext(users: HashMap<String, UserOptions>) -> Self {
+        Self {
+            passwords: Arc::new(
+                users
+                    .into_iter()
+                    .map(|(user, options)| {
+                        (user, Password::new(None, options.password.into_bytes()))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn scram(users: HashMap<String, UserOptions>) -> Self {
+        let mut rng = rand::thread_rng();
+        Self {
+            passwords: Arc::new(
+                users
+                    .into_iter()
+                    .map(|(user, options)| {
+                        let mut salt = vec![0; SCRAM_SALT_LEN];
+                        rng.fill_bytes(&mut salt);
+                        let salted_password =
+                            gen_salted_password(&options.password, &salt, SCRAM_ITERATIONS);
+                        (user, Password::new(Some(salt), salted_password))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn password(&self, user: &str) -> PgWireResult<Password> {
+        self.passwords
+            .get(user)
+            .cloned()
+            .ok_or_else(|| PgWireError::InvalidPassword(user.to_owned()))
+    }
+
+    /// Whether the given cleartext password is the one of the user, compared in constant time.
+    fn verify_cleartext(&self, user: &str, password: &str) -> bool {
+        self.passwords.get(user).is_some_and(|expected| {
+            expected
+                .password()
+                .as_slice()
+                .ct_eq(password.as_bytes())
+                .into()
+        })
+    }
+}
+
+#[async_trait]
+impl AuthSource for UsersAuthSource {
+    async fn get_password(&self, login: &LoginInfo) -> PgWireResult<Password> {
+        let user = login.user().ok_or(PgWireError::UserNameRequired)?;
+        self.password(user.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users() -> HashMap<String, UserOptions> {
+        HashMap::from([
+            (
+                "reader".to_owned(),
+                UserOptions {
+                    password: "reader-password".to_owned(),
+                    allow_mutations: false,
+                },
+            ),
+            (
+                "writer".to_owned(),
+                UserOptions {
+                    password: "writer-token".to_owned(),
+                    allow_mutations: true,
+                },
+            ),
+        ])
+    }
+
+    fn authentication(method: AuthenticationMethod) -> Option<AuthenticationOptions> {
+        Some(AuthenticationOptions {
+            method,
+            users: users(),
+        })
+    }
+
+    #[test]
+    fn authentication_method() {
+        assert!(matches!(
+            Authenticator::new(None, false),
+            Ok(Authenticator::Noop(_))
+        ));
+        assert!(matches!(
+            Authenticator::new(authentication(AuthenticationMethod::Scram), false),
+            Ok(Authenticator::Scram(_))
+        ));
+        assert!(matches!(
+            Authenticator::new(authentication(AuthenticationMethod::Cleartext), true),
+            Ok(Authenticator::Cleartext(_, _))
+        ));
+    }
+
+    #[test]
+    fn cleartext_requires_tls() {
+        assert!(matches!(
+            Authenticator::new(authentication(AuthenticationMethod::Cleartext), false),
+            Err(Error::CleartextWithoutTls)
+        ));
+    }
+
+    #[test]
+    fn empty_passwords_are_rejected() {
+        let mut users = users();
+        users.get_mut("writer").unwrap().password.clear();
+
+        assert!(matches!(
+            Authenticator::new(
+                Some(AuthenticationOptions {
+                    method: AuthenticationMethod::Scram,
+                    users,
+                }),
+                true
+            ),
+            Err(Error::EmptyPassword(user)) if user == "writer"
+        ));
+    }
+
+    #[test]
+    fn cleartext_passwords() {
+        let users = UsersAuthSource::cleartext(users());
+
+        assert!(users.verify_cleartext("reader", "reader-password"));
+        assert!(users.verify_cleartext("writer", "writer-token"));
+        assert!(!users.verify_cleartext("reader", "writer-token"));
+        assert!(!users.verify_cleartext("reader", "reader-passwor"));
+        assert!(!users.verify_cleartext("reader", "reader-password2"));
+        assert!(!users.verify_cleartext("reader", ""));
+        assert!(!users.verify_cleartext("unknown", "reader-password"));
+
+        assert_eq!(
+            users.password("reader").unwrap().password(),
+            b"reader-password"
+        );
+        assert!(users.password("unknown").is_err());
+    }
+
+    #[test]
+    fn scram_passwords_are_salted() {
+        let users = UsersAuthSource::scram(users());
+
+        let reader = users.password("reader").unwrap();
+        let writer = users.password("writer").unwrap();
+        let reader_salt = reader.salt().as_ref().unwrap();
+        let writer_salt = writer.salt().as_ref().unwrap();
+        assert_eq!(reader_salt.len(), SCRAM_SALT_LEN);
+        assert_ne!(reader_salt, writer_salt);
+        assert_eq!(
+            reader.password(),
+            &gen_salted_password("reader-password", reader_salt, SCRAM_ITERATIONS)
+        );
+        // The cleartext passwords are not kept
+        assert!(!users.verify_cleartext("reader", "reader-password"));
+        assert!(users.password("unknown").is_err());
+    }
+}
@@ -8,10 +8,14 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod auth;
 mod extended_query;
 pub mod options;
 mod pgwire_server;
 pub mod service;
 
-pub use crate::options::{Options, OptionsBuilder, OptionsBuilderError};
+pub use crate::options::{
+    AuthenticationMethod, AuthenticationOptions, Options, OptionsBuilder, OptionsBuilderError,
+    TlsOptions, UserOptions,
+};
 pub use service::Error;
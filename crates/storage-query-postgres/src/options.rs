@@ -10,7 +10,9 @@
 
 use crate::service::PostgresQueryService;
 use restate_storage_query_datafusion::context::QueryContext;
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 /// # Storage query postgres options
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, derive_builder::Builder)]
@@ -25,23 +27,106 @@ pub struct Options {
     ///
     /// The address to bind for the psql service.
     pub bind_address: SocketAddr,
+
+    /// # Authentication
+    ///
+    /// Users allowed to connect to the psql service. Unset means that any client can connect, but cannot run write statements.
+    #[builder(default)]
+    pub authentication: Option<AuthenticationOptions>,
+
+    /// # TLS
+    ///
+    /// Certificate and private key used to encrypt the connections. Unset means that connections are not encrypted.
+    #[builder(default)]
+    pub tls: Option<TlsOptions>,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             bind_address: "0.0.0.0:9071".parse().unwrap(),
+            authentication: None,
+            tls: None,
         }
     }
 }
 
 impl Options {
     pub fn build(self, query_context: QueryContext) -> PostgresQueryService {
-        let Options { bind_address } = self;
+        let Options {
+            bind_address,
+            authentication,
+            tls,
+        } = self;
 
         PostgresQueryService {
             bind_address,
+            authentication,
+            tls,
             query_context,
         }
     }
 }
+
+/// # Authentication options
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub struct AuthenticationOptions {
+    /// # Method
+    ///
+    /// Password exchange method used to authenticate the users.
+    #[serde(default)]
+    pub method: AuthenticationMethod,
+
+    /// # Users
+    ///
+    /// Users by name. Tokens can be configured as passwords of dedicated users.
+    pub users: HashMap<String, UserOptions>,
+}
+
+/// # User options
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub struct UserOptions {
+    /// # Password
+    ///
+    /// Password of the user, it must not be empty.
+    pub password: String,
+
+    /// # Allow mutations
+    ///
+    /// Whether the user can run write statements, such as `DELETE FROM state`.
+    /// Requires the write statements to be enabled in the storage query options.
+    #[serde(default)]
+    pub allow_mutations: bool,
+}
+
+/// # Authentication method
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub enum AuthenticationMethod {
+    /// # Cleartext
+    ///
+    /// The client sends the password in clear text, hence it requires TLS.
+    Cleartext,
+    /// # SCRAM
+    ///
+    /// SCRAM-SHA-256 challenge-response exchange, the password is never sent to the server.
+    #[default]
+    Scram,
+}
+
+/// # TLS options
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "options_schema", derive(schemars::JsonSchema))]
+pub struct TlsOptions {
+    /// # Certificate path
+    ///
+    /// Path to the PEM encoded certificate chain.
+    pub cert_path: PathBuf,
+
+    /// # Key path
+    ///
+    /// Path to the PEM encoded PKCS#8 or RSA private key.
+    pub key_path: PathBuf,
+}
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
@@ -31,12 +32,13 @@ use datafusion::physical_plan::SendableRecordBatchStream;
 use futures::{stream, StreamExt};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
+use tokio_rustls::TlsAcceptor;
 
+use crate::auth::Authenticator;
 use crate::extended_query::NoopExtendedQueryHandler;
-use pgwire::api::auth::noop::NoopStartupHandler;
 use pgwire::api::query::SimpleQueryHandler;
 use pgwire::api::results::{DataRowEncoder, FieldFormat, FieldInfo, QueryResponse, Response};
-use pgwire::api::{ClientInfo, MakeHandler, StatelessMakeHandler, Type};
+use pgwire::api::{ClientInfo, MakeHandler, StatelessMakeHandler, Type, METADATA_USER};
 use pgwire::error::{ErrorInfo, PgWireError, PgWireResult};
 use pgwire::messages::data::DataRow;
 use pgwire::tokio::process_socket;
@@ -46,24 +48,34 @@ use tracing::warn;
 pub(crate) struct HandlerFactory {
     processor: Arc<StatelessMakeHandler<DfSessionService>>,
     placeholder: Arc<StatelessMakeHandler<NoopExtendedQueryHandler>>,
-    authenticator: Arc<StatelessMakeHandler<NoopStartupHandler>>,
+    authenticator: Arc<StatelessMakeHandler<Authenticator>>,
+    tls_acceptor: Option<Arc<TlsAcceptor>>,
 }
 
 impl HandlerFactory {
-    pub fn new(ctx: QueryContext) -> Self {
+    pub fn new(
+        ctx: QueryContext,
+        mutation_ctx: Option<QueryContext>,
+        mutation_users: HashSet<String>,
+        authenticator: Authenticator,
+        tls_acceptor: Option<TlsAcceptor>,
+    ) -> Self {
         let processor = Arc::new(StatelessMakeHandler::new(Arc::new(DfSessionService::new(
             ctx,
+            mutation_ctx,
+            mutation_users,
         ))));
         // We have not implemented extended query in this server, use placeholder instead
         let placeholder = Arc::new(StatelessMakeHandler::new(Arc::new(
             NoopExtendedQueryHandler::new(),
         )));
-        let authenticator = Arc::new(StatelessMakeHandler::new(Arc::new(NoopStartupHandler)));
+        let authenticator = Arc::new(StatelessMakeHandler::new(Arc::new(authenticator)));
 
         Self {
             processor,
             placeholder,
             authenticator,
+            tls_acceptor: tls_acceptor.map(Arc::new),
         }
     }
 
@@ -71,10 +83,11 @@ impl HandlerFactory {
         let authenticator_ref = self.authenticator.make();
         let processor_ref = self.processor.make();
         let placeholder_ref = self.placeholder.make();
+        let tls_acceptor_ref = self.tls_acceptor.clone();
         tokio::spawn(async move {
             let result = process_socket(
                 incoming_socket,
-                None,
+                tls_acceptor_ref,
                 authenticator_ref,
                 processor_ref,
                 placeholder_ref,
@@ -90,23 +103,38 @@ impl HandlerFactory {
 
 pub struct DfSessionService {
     session_context: Mutex<QueryContext>,
+    /// Runs the write statements too, used only for the authenticated users allowed to mutate.
+    mutation_session_context: Option<Mutex<QueryContext>>,
+    mutation_users: HashSet<String>,
 }
 
 impl DfSessionService {
-    pub fn new(ctx: QueryContext) -> DfSessionService {
+    pub fn new(
+        ctx: QueryContext,
+        mutation_ctx: Option<QueryContext>,
+        mutation_users: HashSet<String>,
+    ) -> DfSessionService {
         DfSessionService {
             session_context: Mutex::new(ctx),
+            mutation_session_context: mutation_ctx.map(Mutex::new),
+            mutation_users,
         }
     }
 }
 
 #[async_trait]
 impl SimpleQueryHandler for DfSessionService {
-    async fn do_query<'a, C>(&self, _client: &C, query: &'a str) -> PgWireResult<Vec<Response<'a>>>
+    async fn do_query<'a, C>(&self, client: &C, query: &'a str) -> PgWireResult<Vec<Response<'a>>>
     where
         C: ClientInfo + Unpin + Send + Sync,
     {
-        let ctx = self.session_context.lock().await;
+        let user = client.metadata().get(METADATA_USER);
+        let ctx = match (&self.mutation_session_context, user) {
+            (Some(mutation_session_context), Some(user)) if self.mutation_users.contains(user) => {
+                mutation_session_context.lock().await
+            }
+            _ => self.session_context.lock().await,
+        };
         let df = ctx
             .execute(query)
             .await
@@ -8,14 +8,23 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::auth::Authenticator;
+use crate::options::{AuthenticationOptions, TlsOptions};
 use crate::pgwire_server::HandlerFactory;
 use codederror::CodedError;
+use datafusion::error::DataFusionError;
 use restate_storage_query_datafusion::context::QueryContext;
 
-use std::io::ErrorKind;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::select;
+use tokio_rustls::TlsAcceptor;
 use tracing::warn;
 
 pub type GenericError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -27,6 +36,24 @@ pub enum Error {
     )]
     #[code(unknown)]
     AddrInUse(SocketAddr),
+    #[error("failed reading the TLS certificate '{}' specified in 'worker.storage_query_postgres.tls.cert_path': {}", .0.display(), .1)]
+    #[code(unknown)]
+    TlsCertificate(PathBuf, std::io::Error),
+    #[error("failed reading the TLS private key '{}' specified in 'worker.storage_query_postgres.tls.key_path': {}", .0.display(), .1)]
+    #[code(unknown)]
+    TlsPrivateKey(PathBuf, std::io::Error),
+    #[error("invalid TLS configuration: {0}")]
+    #[code(unknown)]
+    Tls(#[from] rustls::Error),
+    #[error("the cleartext authentication specified in 'worker.storage_query_postgres.authentication.method' requires 'worker.storage_query_postgres.tls' to be configured")]
+    #[code(unknown)]
+    CleartextWithoutTls,
+    #[error("the password of the user '{0}' specified in 'worker.storage_query_postgres.authentication.users' must not be empty")]
+    #[code(unknown)]
+    EmptyPassword(String),
+    #[error("cannot allow the users specified in 'worker.storage_query_postgres.authentication.users' to mutate: {0}")]
+    #[code(unknown)]
+    Mutations(DataFusionError),
     #[error("error: {0:?}")]
     #[code(unknown)]
     Other(#[from] GenericError),
@@ -34,6 +61,8 @@ pub enum Error {
 
 pub struct PostgresQueryService {
     pub bind_address: SocketAddr,
+    pub authentication: Option<AuthenticationOptions>,
+    pub tls: Option<TlsOptions>,
     pub query_context: QueryContext,
 }
 
@@ -41,9 +70,35 @@ impl PostgresQueryService {
     pub async fn run(self, drain: drain::Watch) -> Result<(), Error> {
         let PostgresQueryService {
             bind_address,
+            authentication,
+            tls,
             query_context,
         } = self;
 
+        let tls_acceptor = tls.map(tls_acceptor).transpose()?;
+        let authenticator = Authenticator::new(authentication.clone(), tls_acceptor.is_some())?;
+
+        // Only the authenticated users allowed to mutate can run write statements
+        let mutation_users: HashSet<_> = authentication
+            .map(|authentication| {
+                authentication
+                    .users
+                    .into_iter()
+                    .filter(|(_, options)| options.allow_mutations)
+                    .map(|(user, _)| user)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mutation_query_context = if mutation_users.is_empty() {
+            None
+        } else {
+            Some(
+                query_context
+                    .allowing_mutations()
+                    .map_err(Error::Mutations)?,
+            )
+        };
+
         let listener = TcpListener::bind(&bind_address).await.map_err(|e| {
             if e.kind() == ErrorKind::AddrInUse {
                 Error::AddrInUse(bind_address)
@@ -55,7 +110,17 @@ impl PostgresQueryService {
         let shutdown = drain.signaled();
         tokio::pin!(shutdown);
 
-        let factory = HandlerFactory::new(query_context);
+        if matches!(authenticator, Authenticator::Noop(_)) && !bind_address.ip().is_loopback() {
+            warn!("The psql service accepts unauthenticated connections on '{bind_address}', configure 'worker.storage_query_postgres.authentication' to restrict the access to the storage");
+        }
+
+        let factory = HandlerFactory::new(
+            query_context,
+            mutation_query_context,
+            mutation_users,
+            authenticator,
+            tls_acceptor,
+        );
         loop {
             select! {
                 incoming_socket = listener.accept() => {
@@ -75,3 +140,50 @@ impl PostgresQueryService {
         Ok(())
     }
 }
+
+fn tls_acceptor(tls: TlsOptions) -> Result<TlsAcceptor, Error> {
+    let TlsOptions {
+        cert_path,
+        key_path,
+    } = tls;
+
+    let certs = File::open(&cert_path)
+        .and_then(|file| rustls_pemfile::certs(&mut BufReader::new(file)))
+        .map_err(|e| Error::TlsCertificate(cert_path.clone(), e))?;
+    if certs.is_empty() {
+        return Err(Error::TlsCertificate(
+            cert_path,
+            std::io::Error::new(ErrorKind::InvalidData, "no certificate found"),
+        ));
+    }
+
+    let key = File::open(&key_path)
+        .and_then(|file| {
+            let mut reader = BufReader::new(file);
+            loop {
+                match rustls_pemfile::read_one(&mut reader)? {
+                    Some(rustls_pemfile::Item::PKCS8Key(key))
+                    | Some(rustls_pemfile::Item::RSAKey(key))
+                    | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(key),
+                    Some(_) => continue,
+                    None => {
+                        return Err(std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            "no private key found",
+                        ))
+                    }
+                }
+            }
+        })
+        .map_err(|e| Error::TlsPrivateKey(key_path, e))?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            certs.into_iter().map(Certificate).collect(),
+            PrivateKey(key),
+        )?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
@@ -9,14 +9,18 @@
 // by the Apache License, Version 2.0.
 
 use crate::keys::{define_table_key, TableKey};
+use crate::owned_iter::OwnedIterator;
 use crate::TableKind::Deduplication;
-use crate::{RocksDBTransaction, StorageAccess, TableScan, TableScanIterationDecision};
+use crate::{
+    RocksDBStorage, RocksDBTransaction, StorageAccess, TableScan, TableScanIterationDecision,
+};
 use futures::Stream;
 use futures_util::stream;
 use restate_storage_api::deduplication_table::{DeduplicationTable, SequenceNumberSource};
 use restate_storage_api::{Result, StorageError};
 use restate_types::identifiers::PartitionId;
 use std::io::Cursor;
+use std::ops::RangeInclusive;
 
 define_table_key!(
     Deduplication,
@@ -56,6 +60,17 @@ impl<'a> DeduplicationTable for RocksDBTransaction<'a> {
         self.put_kv(key, sequence_number);
     }
 
+    async fn delete_sequence_number(
+        &mut self,
+        partition_id: PartitionId,
+        source: SequenceNumberSource,
+    ) {
+        let key = DeduplicationKey::default()
+            .partition_id(partition_id)
+            .source(source);
+        self.delete_key(&key);
+    }
+
     fn get_all_sequence_numbers(
         &mut self,
         partition_id: PartitionId,
@@ -80,3 +95,38 @@ impl<'a> DeduplicationTable for RocksDBTransaction<'a> {
         ))
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct OwnedDeduplicationRow {
+    pub partition_id: PartitionId,
+    pub source: SequenceNumberSource,
+    pub sequence_number: u64,
+}
+
+impl RocksDBStorage {
+    /// Returns the sequence numbers of the given partitions, ordered by partition id and source.
+    pub fn all_sequence_numbers(
+        &self,
+        partition_ids: RangeInclusive<PartitionId>,
+    ) -> impl Iterator<Item = OwnedDeduplicationRow> + '_ {
+        let iter = self.iterator_from(TableScan::KeyRangeInclusive(
+            DeduplicationKey::default().partition_id(*partition_ids.start()),
+            DeduplicationKey::default().partition_id(*partition_ids.end()),
+        ));
+        OwnedIterator::new(iter).map(|(mut key, value)| {
+            let dedup_key = DeduplicationKey::deserialize_from(&mut key)
+                .expect("deduplication key must deserialize into DeduplicationKey");
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&value);
+            OwnedDeduplicationRow {
+                partition_id: dedup_key
+                    .partition_id
+                    .expect("deduplication key must have a partition id"),
+                source: dedup_key
+                    .source
+                    .expect("deduplication key must have a source"),
+                sequence_number: u64::from_be_bytes(buf),
+            }
+        })
+    }
+}
@@ -10,6 +10,7 @@
 
 use crate::codec::ProtoValue;
 use crate::keys::{define_table_key, TableKey};
+use crate::owned_iter::OwnedIterator;
 use crate::TableKind::Outbox;
 use crate::{RocksDBStorage, RocksDBTransaction, StorageAccess, TableScan};
 
@@ -19,7 +20,7 @@ use restate_storage_api::{Result, StorageError};
 use restate_storage_proto::storage;
 use restate_types::identifiers::PartitionId;
 use std::io::Cursor;
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 
 define_table_key!(
     Outbox,
@@ -96,6 +97,40 @@ fn truncate_outbox<S: StorageAccess>(
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct OwnedOutboxRow {
+    pub partition_id: PartitionId,
+    pub message_index: u64,
+    pub outbox_message: OutboxMessage,
+}
+
+impl RocksDBStorage {
+    /// Returns the messages of the outboxes of the given partitions, ordered by partition id and message index.
+    pub fn all_outboxes(
+        &self,
+        partition_ids: RangeInclusive<PartitionId>,
+    ) -> impl Iterator<Item = OwnedOutboxRow> + '_ {
+        let iter = self.iterator_from(TableScan::KeyRangeInclusive(
+            OutboxKey::default().partition_id(*partition_ids.start()),
+            OutboxKey::default().partition_id(*partition_ids.end()),
+        ));
+        OwnedIterator::new(iter).map(|(mut key, value)| {
+            let outbox_key = OutboxKey::deserialize_from(&mut key)
+                .expect("outbox key must deserialize into OutboxKey");
+            OwnedOutboxRow {
+                partition_id: outbox_key
+                    .partition_id
+                    .expect("outbox key must have a partition id"),
+                message_index: outbox_key
+                    .message_index
+                    .expect("outbox key must have a message index"),
+                outbox_message: decode_value(&value)
+                    .expect("outbox message must deserialize into OutboxMessage"),
+            }
+        })
+    }
+}
+
 impl OutboxTable for RocksDBStorage {
     async fn add_message(
         &mut self,
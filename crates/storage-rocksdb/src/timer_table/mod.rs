@@ -10,6 +10,7 @@
 
 use crate::codec::ProtoValue;
 use crate::keys::{define_table_key, TableKey};
+use crate::owned_iter::OwnedIterator;
 use crate::TableKind::Timers;
 use crate::TableScanIterationDecision::Emit;
 use crate::{RocksDBStorage, RocksDBTransaction, StorageAccess};
@@ -21,6 +22,7 @@ use restate_storage_api::timer_table::{Timer, TimerKey, TimerTable};
 use restate_storage_api::{Result, StorageError};
 use restate_storage_proto::storage;
 use restate_types::identifiers::{InvocationUuid, PartitionId};
+use std::ops::RangeInclusive;
 
 define_table_key!(
     Timers,
@@ -172,6 +174,50 @@ impl<'a> TimerTable for RocksDBTransaction<'a> {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct OwnedTimerRow {
+    pub partition_id: PartitionId,
+    pub timer_key: TimerKey,
+    pub timer: Timer,
+}
+
+impl RocksDBStorage {
+    /// Returns the timers of the given partitions, ordered by partition id and wake up time.
+    pub fn all_timers(
+        &self,
+        partition_ids: RangeInclusive<PartitionId>,
+    ) -> impl Iterator<Item = OwnedTimerRow> + '_ {
+        let iter = self.iterator_from(TableScan::KeyRangeInclusive(
+            TimersKey::default().partition_id(*partition_ids.start()),
+            TimersKey::default().partition_id(*partition_ids.end()),
+        ));
+        OwnedIterator::new(iter).map(|(mut key, value)| {
+            let timer_key = TimersKey::deserialize_from(&mut key)
+                .expect("timer key must deserialize into TimersKey");
+            let timer =
+                storage::v1::Timer::decode(value).expect("timer must deserialize into Timer");
+            let timer = Timer::try_from(timer).expect("timer must convert from proto");
+            OwnedTimerRow {
+                partition_id: timer_key
+                    .partition_id
+                    .expect("timer key must have a partition id"),
+                timer_key: TimerKey {
+                    timestamp: timer_key
+                        .timestamp
+                        .expect("timer key must have a timestamp"),
+                    invocation_uuid: timer_key
+                        .invocation_id
+                        .expect("timer key must have an invocation id"),
+                    journal_index: timer_key
+                        .journal_index
+                        .expect("timer key must have a journal index"),
+                },
+                timer,
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
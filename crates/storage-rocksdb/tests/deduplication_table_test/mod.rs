@@ -0,0 +1,56 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use restate_storage_api::deduplication_table::{DeduplicationTable, SequenceNumberSource};
+use restate_storage_api::Transaction;
+use restate_storage_rocksdb::RocksDBStorage;
+
+async fn populate_data<T: DeduplicationTable>(txn: &mut T) {
+    txn.put_sequence_number(1337, SequenceNumberSource::Partition(1), 10)
+        .await;
+    txn.put_sequence_number(1337, SequenceNumberSource::Ingress("ingress-1".into()), 20)
+        .await;
+
+    // add a successor and a predecessor partitions
+    txn.put_sequence_number(1336, SequenceNumberSource::Partition(1), 30)
+        .await;
+    txn.put_sequence_number(1338, SequenceNumberSource::Partition(1), 40)
+        .await;
+}
+
+fn verify_all_sequence_numbers_of_partitions(rocksdb: &RocksDBStorage) {
+    let sequence_numbers: Vec<_> = rocksdb
+        .all_sequence_numbers(1337..=1337)
+        .map(|row| (row.partition_id, row.source, row.sequence_number))
+        .collect();
+    assert_eq!(
+        sequence_numbers,
+        vec![
+            (1337, SequenceNumberSource::Partition(1), 10),
+            (1337, SequenceNumberSource::Ingress("ingress-1".into()), 20)
+        ]
+    );
+
+    let partition_ids: Vec<_> = rocksdb
+        .all_sequence_numbers(1337..=u64::MAX)
+        .map(|row| row.partition_id)
+        .collect();
+    assert_eq!(partition_ids, vec![1337, 1337, 1338]);
+
+    assert_eq!(rocksdb.all_sequence_numbers(0..=1335).count(), 0);
+}
+
+pub(crate) async fn run_tests(mut rocksdb: RocksDBStorage) {
+    let mut txn = rocksdb.transaction();
+    populate_data(&mut txn).await;
+    txn.commit().await.expect("should not fail");
+
+    verify_all_sequence_numbers_of_partitions(&rocksdb);
+}
@@ -23,6 +23,7 @@ use std::pin::pin;
 use tempfile::tempdir;
 use tokio_stream::StreamExt;
 
+mod deduplication_table_test;
 mod inbox_table_test;
 mod journal_table_test;
 mod outbox_table_test;
@@ -65,6 +66,7 @@ async fn test_read_write() {
     //
     // run the tests
     //
+    deduplication_table_test::run_tests(rocksdb.clone()).await;
     inbox_table_test::run_tests(rocksdb.clone()).await;
     journal_table_test::run_tests(rocksdb.clone()).await;
     outbox_table_test::run_tests(rocksdb.clone()).await;
@@ -47,6 +47,22 @@ pub(crate) async fn verify_outbox_is_empty_after_truncation<T: OutboxTable>(txn:
     assert_eq!(result, None);
 }
 
+fn verify_all_outboxes_of_partitions(rocksdb: &RocksDBStorage) {
+    assert_eq!(rocksdb.all_outboxes(1337..=1337).count(), 0);
+
+    let partition_ids: Vec<_> = rocksdb
+        .all_outboxes(1336..=1338)
+        .map(|row| (row.partition_id, row.message_index))
+        .collect();
+    assert_eq!(partition_ids, vec![(1336, 0), (1338, 0)]);
+
+    let partition_ids: Vec<_> = rocksdb
+        .all_outboxes(0..=1336)
+        .map(|row| row.partition_id)
+        .collect();
+    assert_eq!(partition_ids, vec![1336]);
+}
+
 pub(crate) async fn run_tests(mut rocksdb: RocksDBStorage) {
     let mut txn = rocksdb.transaction();
 
@@ -57,4 +73,5 @@ pub(crate) async fn run_tests(mut rocksdb: RocksDBStorage) {
 
     let mut txn = rocksdb.transaction();
     verify_outbox_is_empty_after_truncation(&mut txn).await;
+    verify_all_outboxes_of_partitions(&rocksdb);
 }
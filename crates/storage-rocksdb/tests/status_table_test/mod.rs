@@ -14,7 +14,9 @@ use restate_storage_api::status_table::{
 };
 use restate_storage_rocksdb::RocksDBStorage;
 use restate_types::identifiers::{FullInvocationId, InvocationUuid, ServiceId};
-use restate_types::invocation::{ServiceInvocationSpanContext, Source};
+use restate_types::invocation::{
+    Header, InvocationFailurePolicy, ServiceInvocationSpanContext, Source,
+};
 use restate_types::time::MillisSinceEpoch;
 use std::collections::HashSet;
 
@@ -30,6 +32,12 @@ fn invoked_status(invocation_id: impl Into<InvocationUuid>) -> InvocationStatus
         None,
         StatusTimestamps::new(MillisSinceEpoch::new(0), MillisSinceEpoch::new(0)),
         Source::Ingress,
+        vec![Header::new("x-tenant-id", "my-tenant")],
+        InvocationFailurePolicy {
+            max_attempts: Some(3),
+            dead_letter: Some(("my-cluster".to_owned(), "my-dlq".to_owned())),
+        },
+        2,
     ))
 }
 
@@ -43,6 +51,9 @@ fn suspended_status(invocation_id: impl Into<InvocationUuid>) -> InvocationStatu
             None,
             StatusTimestamps::new(MillisSinceEpoch::new(0), MillisSinceEpoch::new(0)),
             Source::Ingress,
+            vec![],
+            InvocationFailurePolicy::default(),
+            0,
         ),
         waiting_for_completed_entries: HashSet::default(),
     }
@@ -146,6 +146,22 @@ async fn verify_next_timer_after_deletion<T: TimerTable>(txn: &mut T) {
     }
 }
 
+fn verify_all_timers_of_partitions(rocksdb: &RocksDBStorage) {
+    let partition_ids: Vec<_> = rocksdb
+        .all_timers(1337..=1337)
+        .map(|row| (row.partition_id, row.timer_key.journal_index))
+        .collect();
+    assert_eq!(partition_ids, vec![(1337, 1), (1337, 2)]);
+
+    let partition_ids: Vec<_> = rocksdb
+        .all_timers(1336..=1338)
+        .map(|row| row.partition_id)
+        .collect();
+    assert_eq!(partition_ids, vec![1336, 1337, 1337, 1338]);
+
+    assert_eq!(rocksdb.all_timers(1339..=u64::MAX).count(), 0);
+}
+
 pub(crate) async fn run_tests(mut rocksdb: RocksDBStorage) {
     let mut txn = rocksdb.transaction();
 
@@ -158,4 +174,5 @@ pub(crate) async fn run_tests(mut rocksdb: RocksDBStorage) {
 
     let mut txn = rocksdb.transaction();
     verify_next_timer_after_deletion(&mut txn).await;
+    verify_all_timers_of_partitions(&rocksdb);
 }
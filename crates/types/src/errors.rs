@@ -298,6 +298,14 @@ impl InvocationError {
         }
     }
 
+    pub fn service_call_not_allowed(caller: impl Display, service: impl Display) -> Self {
+        Self {
+            code: UserErrorCode::PermissionDenied.into(),
+            message: Cow::Owned(format!("Service '{}' is not allowed to invoke service '{}'. Check the call policy of the invoked service.", caller, service)),
+            description: None,
+        }
+    }
+
     pub fn with_static_message(mut self, message: &'static str) -> InvocationError {
         self.message = Cow::Borrowed(message);
         self
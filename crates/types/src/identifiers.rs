@@ -160,6 +160,22 @@ impl InvocationUuid {
         Self(Ulid::new())
     }
 
+    /// Derives a stable invocation uuid from the given parts, so that retries of the same
+    /// request (e.g. with the same idempotency key) result in the same invocation uuid.
+    pub fn deterministic(parts: &[&[u8]]) -> Self {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        let digest = hasher.finalize();
+        let mut bytes = [0; Self::SIZE_IN_BYTES];
+        bytes.copy_from_slice(&digest[..Self::SIZE_IN_BYTES]);
+        Self(Ulid::from_bytes(bytes))
+    }
+
     pub fn from_slice(b: &[u8]) -> Result<Self, IdDecodeError> {
         let ulid = Ulid::from_bytes(b.try_into().map_err(|_| IdDecodeError::Length)?);
         debug_assert!(!ulid.is_nil());
@@ -719,6 +735,18 @@ mod tests {
         )
     }
 
+    #[test]
+    fn deterministic_invocation_uuid() {
+        assert_eq!(
+            InvocationUuid::deterministic(&[b"greeter", b"my-key"]),
+            InvocationUuid::deterministic(&[b"greeter", b"my-key"])
+        );
+        assert_ne!(
+            InvocationUuid::deterministic(&[b"greeter", b"my-key"]),
+            InvocationUuid::deterministic(&[b"greeterm", b"y-key"])
+        );
+    }
+
     #[test]
     fn invocation_codec_capacity() {
         assert_eq!(38, IdEncoder::<InvocationId>::estimate_buf_capacity())
@@ -40,6 +40,8 @@ pub struct ServiceInvocation {
     pub source: Source,
     pub response_sink: Option<ServiceInvocationResponseSink>,
     pub span_context: ServiceInvocationSpanContext,
+    pub headers: Vec<Header>,
+    pub failure_policy: InvocationFailurePolicy,
 }
 
 impl ServiceInvocation {
@@ -65,8 +67,50 @@ impl ServiceInvocation {
             source,
             response_sink,
             span_context,
+            headers: vec![],
+            failure_policy: InvocationFailurePolicy::default(),
         }
     }
+
+    /// Attach the given headers to this invocation. Headers are delivered to the service deployment.
+    pub fn with_headers(mut self, headers: Vec<Header>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_failure_policy(mut self, failure_policy: InvocationFailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+}
+
+/// Header propagated with an invocation, from the ingress or from the calling service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    pub name: ByteString,
+    pub value: ByteString,
+}
+
+impl Header {
+    pub fn new(name: impl Into<ByteString>, value: impl Into<ByteString>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Policy applied to an invocation which keeps failing, resolved from the subscription which
+/// started it. It's stored together with the invocation, so it's not affected by later changes
+/// of the subscription.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvocationFailurePolicy {
+    /// If set, the invocation is failed after this number of attempts, instead of being retried.
+    pub max_attempts: Option<u32>,
+    /// If set, the input of the failed invocation is published to this Kafka cluster and topic.
+    pub dead_letter: Option<(String, String)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -381,7 +425,7 @@ pub enum SpanRelationCause {
     ),
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub enum SpanRelation {
     #[default]
     None,
@@ -523,6 +567,8 @@ mod mocks {
                 source: Source::Service(FullInvocationId::mock_random()),
                 response_sink: None,
                 span_context: Default::default(),
+                headers: vec![],
+                failure_policy: Default::default(),
             }
         }
     }
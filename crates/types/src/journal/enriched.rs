@@ -27,6 +27,9 @@ pub struct InvokeEnrichmentResult {
     pub service_name: ByteString,
     // When resolving the service and generating its id, we also generate the associated span
     pub span_context: ServiceInvocationSpanContext,
+    // Set to the name of the invoked service if its call policy doesn't allow the caller.
+    // In this case the invocation is not sent, and the call fails with a terminal error.
+    pub denied_by_call_policy: Option<ByteString>,
 }
 
 #[derive(Debug, Clone)]
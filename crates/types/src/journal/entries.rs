@@ -14,6 +14,7 @@ use super::*;
 
 use crate::errors::{InvocationError, UserErrorCode};
 use crate::identifiers::EntryIndex;
+use crate::invocation::Header;
 use crate::time::MillisSinceEpoch;
 use std::fmt;
 
@@ -22,6 +23,7 @@ pub enum Entry {
     // IO
     PollInputStream(PollInputStreamEntry),
     OutputStream(OutputStreamEntry),
+    OutputStreamChunk(OutputStreamChunkEntry),
 
     // State access
     GetState(GetStateEntry),
@@ -50,6 +52,12 @@ impl Entry {
         Entry::OutputStream(OutputStreamEntry { result })
     }
 
+    pub fn output_stream_chunk(value: impl Into<Bytes>) -> Self {
+        Entry::OutputStreamChunk(OutputStreamChunkEntry {
+            value: value.into(),
+        })
+    }
+
     pub fn get_state(key: impl Into<Bytes>, value: Option<GetStateResult>) -> Self {
         Entry::GetState(GetStateEntry {
             key: key.into(),
@@ -145,6 +153,7 @@ impl From<&InvocationError> for CompletionResult {
 pub enum EntryType {
     PollInputStream,
     OutputStream,
+    OutputStreamChunk,
     GetState,
     SetState,
     ClearState,
@@ -205,6 +214,12 @@ pub struct OutputStreamEntry {
     pub result: EntryResult,
 }
 
+/// A single message of a server-streaming response, forwarded to the caller as soon as it's journaled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputStreamChunkEntry {
+    pub value: Bytes,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GetStateResult {
     Empty,
@@ -275,6 +290,7 @@ pub struct InvokeRequest {
     pub service_name: ByteString,
     pub method_name: ByteString,
     pub parameter: Bytes,
+    pub headers: Vec<Header>,
 }
 
 impl InvokeRequest {
@@ -287,8 +303,14 @@ impl InvokeRequest {
             service_name: service_name.into(),
             method_name: method_name.into(),
             parameter: parameter.into(),
+            headers: vec![],
         }
     }
+
+    pub fn with_headers(mut self, headers: Vec<Header>) -> Self {
+        self.headers = headers;
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -105,6 +105,7 @@ pub enum EntryHeader<InvokeEnrichmentResult, AwakeableEnrichmentResult> {
         is_completed: bool,
     },
     OutputStream,
+    OutputStreamChunk,
     GetState {
         is_completed: bool,
     },
@@ -142,6 +143,7 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
         match self {
             EntryHeader::PollInputStream { is_completed, .. } => Some(*is_completed),
             EntryHeader::OutputStream { .. } => None,
+            EntryHeader::OutputStreamChunk { .. } => None,
             EntryHeader::GetState { is_completed, .. } => Some(*is_completed),
             EntryHeader::SetState { .. } => None,
             EntryHeader::ClearState { .. } => None,
@@ -160,6 +162,7 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
         match self {
             EntryHeader::PollInputStream { is_completed, .. } => *is_completed = true,
             EntryHeader::OutputStream { .. } => {}
+            EntryHeader::OutputStreamChunk { .. } => {}
             EntryHeader::GetState { is_completed, .. } => *is_completed = true,
             EntryHeader::SetState { .. } => {}
             EntryHeader::ClearState { .. } => {}
@@ -178,6 +181,7 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
         match self {
             EntryHeader::PollInputStream { .. } => EntryType::PollInputStream,
             EntryHeader::OutputStream { .. } => EntryType::OutputStream,
+            EntryHeader::OutputStreamChunk { .. } => EntryType::OutputStreamChunk,
             EntryHeader::GetState { .. } => EntryType::GetState,
             EntryHeader::SetState { .. } => EntryType::SetState,
             EntryHeader::ClearState { .. } => EntryType::ClearState,
@@ -198,6 +202,7 @@ impl<InvokeEnrichmentResult, AwakeableEnrichmentResult>
                 EntryHeader::PollInputStream { is_completed }
             }
             EntryHeader::OutputStream {} => EntryHeader::OutputStream {},
+            EntryHeader::OutputStreamChunk {} => EntryHeader::OutputStreamChunk {},
             EntryHeader::GetState { is_completed } => EntryHeader::GetState { is_completed },
             EntryHeader::SetState {} => EntryHeader::SetState {},
             EntryHeader::ClearState {} => EntryHeader::ClearState {},
@@ -8,9 +8,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use restate_schema_api::subscription::{Subscription, SubscriptionValidator};
-use restate_types::identifiers::SubscriptionId;
-use restate_types::invocation::InvocationTermination;
+use restate_schema_api::subscription::{OffsetReset, Subscription, SubscriptionValidator};
+use restate_types::identifiers::{InvocationId, SubscriptionId};
+use restate_types::invocation::{Header, InvocationTermination};
 use restate_types::state_mut::ExternalStateMutation;
 use std::future::Future;
 
@@ -18,6 +18,10 @@ use std::future::Future;
 pub enum Error {
     #[error("worker is unreachable")]
     Unreachable,
+    #[error("storage error: {0}")]
+    Storage(String),
+    #[error("cannot reset the subscription offsets: {0}")]
+    ResetOffsets(String),
 }
 
 // This is just an interface to isolate the interaction between meta and subscription controller.
@@ -31,17 +35,68 @@ pub trait SubscriptionController: SubscriptionValidator {
         &self,
         id: SubscriptionId,
     ) -> impl Future<Output = Result<(), Error>> + Send;
+    /// Resets the consumed offsets of a stopped subscription, returning once they're committed.
+    fn reset_subscription_offsets(
+        &self,
+        subscription: Subscription,
+        to: OffsetReset,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
 }
 
-pub trait Handle: Clone {
-    type SubscriptionControllerHandle: SubscriptionController + Send + Sync;
+/// Coarse status of an invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvocationStatusKind {
+    /// The invocation is enqueued, waiting for the service instance to be free.
+    Pending,
+    Running,
+    Suspended,
+    /// The invocation is executed by a built-in service.
+    Virtual,
+    /// The invocation completed, or is unknown.
+    Completed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationStatus {
+    pub kind: InvocationStatusKind,
+    /// Headers the invocation was started with. Empty if the invocation is completed.
+    pub headers: Vec<Header>,
+}
+
+impl InvocationStatus {
+    pub fn completed() -> Self {
+        Self {
+            kind: InvocationStatusKind::Completed,
+            headers: vec![],
+        }
+    }
 
+    /// Returns true if the invocation was started with the given header value. Header names are case-insensitive.
+    pub fn has_header(&self, name: &str, value: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.name.eq_ignore_ascii_case(name) && header.value == value)
+    }
+}
+
+/// Interface to manage single invocations.
+pub trait InvocationHandle: Clone {
     /// Send a command to terminate an invocation. This command is best-effort.
     fn terminate_invocation(
         &self,
         invocation_termination: InvocationTermination,
     ) -> impl Future<Output = Result<(), Error>> + Send;
 
+    /// Read the current status of an invocation.
+    fn invocation_status(
+        &self,
+        invocation_id: InvocationId,
+    ) -> impl Future<Output = Result<InvocationStatus, Error>> + Send;
+}
+
+pub trait Handle: InvocationHandle {
+    type SubscriptionControllerHandle: SubscriptionController + Send + Sync;
+
     /// Send a command to mutate a state. This command is best-effort.
     fn external_state_mutation(
         &self,
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use crate::services::WorkerCommandSender;
 use codederror::CodedError;
 use restate_ingress_dispatcher::{
     IngressDispatcherOutput, Service as IngressDispatcherService,
@@ -18,7 +19,7 @@ use restate_schema_impl::Schemas;
 use tokio::select;
 use tokio::sync::mpsc;
 
-type ExternalClientIngress = HyperServerIngress<Schemas>;
+type ExternalClientIngress = HyperServerIngress<Schemas, WorkerCommandSender>;
 
 #[derive(Debug, thiserror::Error, CodedError)]
 pub enum IngressIntegrationError {
@@ -10,39 +10,52 @@
 
 use assert2::let_assert;
 use bytes::Bytes;
+use bytestring::ByteString;
+use prost::Message;
+use prost_reflect::{DeserializeOptions, SerializeOptions};
+use restate_invoker_api::{Transcoder, TranscoderResolver};
+use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper, ProtobufToJsonMapper};
+use restate_schema_api::json_manifest::JsonDeploymentResolver;
 use restate_schema_api::key::extraction;
+use restate_schema_api::service::ServiceMetadataResolver;
 use restate_service_protocol::awakeable_id::AwakeableIdentifier;
+use restate_service_protocol::pb::protocol::{
+    invoke_entry_message, output_stream_entry_message, poll_input_stream_entry_message,
+    BackgroundInvokeEntryMessage, InvokeEntryMessage, OutputStreamEntryMessage,
+    PollInputStreamEntryMessage,
+};
 use restate_types::errors::{InvocationError, UserErrorCode};
-use restate_types::identifiers::{FullInvocationId, InvocationUuid};
+use restate_types::identifiers::{DeploymentId, EntryIndex, FullInvocationId, InvocationUuid};
 use restate_types::invocation::{ServiceInvocationSpanContext, SpanRelation};
 use restate_types::journal::enriched::{
     AwakeableEnrichmentResult, EnrichedEntryHeader, EnrichedRawEntry, InvokeEnrichmentResult,
 };
 use restate_types::journal::raw::{PlainEntryHeader, PlainRawEntry, RawEntry, RawEntryCodec};
 use restate_types::journal::{BackgroundInvokeEntry, CompleteAwakeableEntry, Entry, InvokeEntry};
-use restate_types::journal::{EntryType, InvokeRequest};
+use restate_types::journal::{Completion, CompletionResult, EntryType, InvokeRequest};
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
-pub(super) struct EntryEnricher<KeyExtractor, Codec> {
-    key_extractor: KeyExtractor,
+pub(super) struct EntryEnricher<Schemas, Codec> {
+    schemas: Schemas,
 
     _codec: PhantomData<Codec>,
 }
 
-impl<KeyExtractor, Codec> EntryEnricher<KeyExtractor, Codec> {
-    pub(super) fn new(key_extractor: KeyExtractor) -> Self {
+impl<Schemas, Codec> EntryEnricher<Schemas, Codec> {
+    pub(super) fn new(schemas: Schemas) -> Self {
         Self {
-            key_extractor,
+            schemas,
             _codec: Default::default(),
         }
     }
 }
 
-impl<KeyExtractor, Codec> EntryEnricher<KeyExtractor, Codec>
+impl<Schemas, Codec> EntryEnricher<Schemas, Codec>
 where
-    KeyExtractor: restate_schema_api::key::KeyExtractor,
+    Schemas: restate_schema_api::key::KeyExtractor + ServiceMetadataResolver,
     Codec: RawEntryCodec,
 {
     fn resolve_service_invocation_target(
@@ -50,13 +63,18 @@ where
         entry_type: EntryType,
         serialized_entry: &Bytes,
         request_extractor: impl Fn(Entry) -> InvokeRequest,
+        caller: &FullInvocationId,
         span_relation: SpanRelation,
     ) -> Result<InvokeEnrichmentResult, InvocationError> {
         let entry = Codec::deserialize(entry_type, serialized_entry.clone())
             .map_err(InvocationError::internal)?;
         let request = request_extractor(entry);
 
-        let service_key = match self.key_extractor.extract(
+        // The denied calls are failed by the partition processor, see the command interpreter
+        let denied_by_call_policy =
+            denied_by_call_policy(&self.schemas, &caller.service_id.service_name, &request);
+
+        let service_key = match self.schemas.extract(
             &request.service_name,
             &request.method_name,
             request.parameter,
@@ -88,18 +106,66 @@ where
             service_key,
             service_name: request.service_name,
             span_context,
+            denied_by_call_policy,
         })
     }
 }
 
-impl<KeyExtractor, Codec> restate_invoker_api::EntryEnricher for EntryEnricher<KeyExtractor, Codec>
+/// Returns the name of the service whose call policy doesn't allow the `caller` to perform the `request`.
+///
+/// The invocations through `dev.restate.Ingress` and `dev.restate.internal.IdempotentInvoker` are checked against
+/// the services they target, as these built-in services invoke them on behalf of the caller.
+pub(crate) fn denied_by_call_policy(
+    schemas: &impl ServiceMetadataResolver,
+    caller: &str,
+    request: &InvokeRequest,
+) -> Option<ByteString> {
+    // Malformed requests are rejected by the built-in services themselves
+    let targets = match (&*request.service_name, &*request.method_name) {
+        (restate_pb::INGRESS_SERVICE_NAME, restate_pb::INGRESS_INVOKE_METHOD_NAME) => {
+            restate_pb::restate::InvokeRequest::decode(request.parameter.clone())
+                .map(|invoke_request| vec![invoke_request.service])
+                .unwrap_or_default()
+        }
+        (restate_pb::INGRESS_SERVICE_NAME, restate_pb::INGRESS_BATCH_INVOKE_METHOD_NAME) => {
+            restate_pb::restate::BatchInvokeRequest::decode(request.parameter.clone())
+                .map(|batch_invoke_request| {
+                    batch_invoke_request
+                        .invocations
+                        .into_iter()
+                        .filter_map(|item| item.invocation)
+                        .map(|invoke_request| invoke_request.service)
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        (
+            restate_pb::IDEMPOTENT_INVOKER_SERVICE_NAME,
+            restate_pb::IDEMPOTENT_INVOKER_INVOKE_METHOD_NAME,
+        ) => restate_pb::restate::internal::IdempotentInvokeRequest::decode(
+            request.parameter.clone(),
+        )
+        .map(|idempotent_invoke_request| vec![idempotent_invoke_request.service_name])
+        .unwrap_or_default(),
+        _ => vec![request.service_name.to_string()],
+    };
+
+    // Unknown services are reported when resolving the invocation target
+    targets
+        .into_iter()
+        .find(|target| schemas.is_call_allowed(caller, target) == Some(false))
+        .map(ByteString::from)
+}
+
+impl<Schemas, Codec> restate_invoker_api::EntryEnricher for EntryEnricher<Schemas, Codec>
 where
-    KeyExtractor: restate_schema_api::key::KeyExtractor,
+    Schemas: restate_schema_api::key::KeyExtractor + ServiceMetadataResolver,
     Codec: RawEntryCodec,
 {
     fn enrich_entry(
         &self,
         raw_entry: PlainRawEntry,
+        full_invocation_id: &FullInvocationId,
         invocation_span_context: &ServiceInvocationSpanContext,
     ) -> Result<EnrichedRawEntry, InvocationError> {
         let (header, serialized_entry) = raw_entry.into_inner();
@@ -108,6 +174,7 @@ where
                 EnrichedEntryHeader::PollInputStream { is_completed }
             }
             PlainEntryHeader::OutputStream {} => EnrichedEntryHeader::OutputStream {},
+            PlainEntryHeader::OutputStreamChunk {} => EnrichedEntryHeader::OutputStreamChunk {},
             PlainEntryHeader::GetState { is_completed } => {
                 EnrichedEntryHeader::GetState { is_completed }
             }
@@ -127,6 +194,7 @@ where
                             let_assert!(Entry::Invoke(InvokeEntry { request, .. }) = entry);
                             request
                         },
+                        full_invocation_id,
                         invocation_span_context.as_parent(),
                     )?;
 
@@ -152,6 +220,7 @@ where
                         );
                         request
                     },
+                    full_invocation_id,
                     invocation_span_context.as_linked(),
                 )?;
 
@@ -188,3 +257,502 @@ where
         Ok(RawEntry::new(enriched_header, serialized_entry))
     }
 }
+
+impl<Schemas, Codec> TranscoderResolver for EntryEnricher<Schemas, Codec>
+where
+    Schemas: JsonMapperResolver + JsonDeploymentResolver + Clone + Send + 'static,
+{
+    fn resolve_transcoder(
+        &self,
+        deployment_id: &DeploymentId,
+        service_name: &str,
+        method_name: &str,
+    ) -> Option<Box<dyn Transcoder + Send>> {
+        if !self.schemas.is_json_deployment(deployment_id) {
+            return None;
+        }
+        Some(Box::new(JsonTranscoder {
+            schemas: self.schemas.clone(),
+            service_name: service_name.to_owned(),
+            method_name: method_name.to_owned(),
+            pending_invokes: Default::default(),
+        }))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Payload {
+    Input,
+    Output,
+}
+
+/// Transcodes the payloads exchanged with the deployments registered from a JSON services manifest.
+///
+/// The payloads are transcoded using the latest schemas of the services, and replayed payloads are
+/// canonical re-serializations of the JSON originally sent by the deployment, hence the SDKs must compare them semantically.
+/// State values and awakeable results are opaque to the runtime and are not transcoded.
+struct JsonTranscoder<Schemas> {
+    schemas: Schemas,
+    service_name: String,
+    method_name: String,
+    /// Callee of the invoke entries waiting for their completion
+    pending_invokes: HashMap<EntryIndex, (String, String)>,
+}
+
+impl<Schemas: JsonMapperResolver> JsonTranscoder<Schemas> {
+    fn to_json(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        payload: Payload,
+        value: Bytes,
+    ) -> Result<Bytes, InvocationError> {
+        let (_, mapper) = match payload {
+            Payload::Input => self
+                .schemas
+                .resolve_reverse_json_mapper_for_service(service_name, method_name),
+            Payload::Output => self
+                .schemas
+                .resolve_json_mapper_for_service(service_name, method_name),
+        }
+        .ok_or_else(|| InvocationError::service_method_not_found(service_name, method_name))?;
+
+        mapper
+            .protobuf_to_json(
+                value,
+                &SerializeOptions::new()
+                    .stringify_64_bit_integers(false)
+                    .skip_default_fields(false),
+            )
+            .map_err(InvocationError::internal)
+    }
+
+    fn from_json(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        payload: Payload,
+        value: Bytes,
+    ) -> Result<Bytes, InvocationError> {
+        let (mapper, _) = match payload {
+            Payload::Input => self
+                .schemas
+                .resolve_json_mapper_for_service(service_name, method_name),
+            Payload::Output => self
+                .schemas
+                .resolve_reverse_json_mapper_for_service(service_name, method_name),
+        }
+        .ok_or_else(|| InvocationError::service_method_not_found(service_name, method_name))?;
+
+        mapper
+            .json_to_protobuf(value, &DeserializeOptions::new().deny_unknown_fields(false))
+            .map_err(|e| {
+                InvocationError::new(
+                    UserErrorCode::InvalidArgument,
+                    format!(
+                        "The payload doesn't match the schema of the {} of {}/{}: {}",
+                        match payload {
+                            Payload::Input => "input",
+                            Payload::Output => "output",
+                        },
+                        service_name,
+                        method_name,
+                        e
+                    ),
+                )
+            })
+    }
+
+    fn transcode_entry(
+        &mut self,
+        entry_index: EntryIndex,
+        entry: PlainRawEntry,
+        transcode: impl Fn(&Self, &str, &str, Payload, Bytes) -> Result<Bytes, InvocationError>,
+    ) -> Result<PlainRawEntry, InvocationError> {
+        let (header, serialized_entry) = entry.into_inner();
+        let serialized_entry = match header {
+            PlainEntryHeader::PollInputStream { .. } => {
+                let mut msg = PollInputStreamEntryMessage::decode(serialized_entry)
+                    .map_err(InvocationError::internal)?;
+                if let Some(poll_input_stream_entry_message::Result::Value(value)) = &mut msg.result
+                {
+                    *value = transcode(
+                        self,
+                        &self.service_name,
+                        &self.method_name,
+                        Payload::Input,
+                        std::mem::take(value),
+                    )?;
+                }
+                msg.encode_to_vec().into()
+            }
+            PlainEntryHeader::OutputStream {} => {
+                let mut msg = OutputStreamEntryMessage::decode(serialized_entry)
+                    .map_err(InvocationError::internal)?;
+                if let Some(output_stream_entry_message::Result::Value(value)) = &mut msg.result {
+                    *value = transcode(
+                        self,
+                        &self.service_name,
+                        &self.method_name,
+                        Payload::Output,
+                        std::mem::take(value),
+                    )?;
+                }
+                msg.encode_to_vec().into()
+            }
+            PlainEntryHeader::Invoke { .. } => {
+                let mut msg = InvokeEntryMessage::decode(serialized_entry)
+                    .map_err(InvocationError::internal)?;
+                msg.parameter = transcode(
+                    self,
+                    &msg.service_name,
+                    &msg.method_name,
+                    Payload::Input,
+                    std::mem::take(&mut msg.parameter),
+                )?;
+                match &mut msg.result {
+                    Some(invoke_entry_message::Result::Value(value)) => {
+                        *value = transcode(
+                            self,
+                            &msg.service_name,
+                            &msg.method_name,
+                            Payload::Output,
+                            std::mem::take(value),
+                        )?;
+                    }
+                    Some(invoke_entry_message::Result::Failure(_)) => {}
+                    None => {
+                        self.pending_invokes.insert(
+                            entry_index,
+                            (msg.service_name.clone(), msg.method_name.clone()),
+                        );
+                    }
+                }
+                msg.encode_to_vec().into()
+            }
+            PlainEntryHeader::BackgroundInvoke { .. } => {
+                let mut msg = BackgroundInvokeEntryMessage::decode(serialized_entry)
+                    .map_err(InvocationError::internal)?;
+                msg.parameter = transcode(
+                    self,
+                    &msg.service_name,
+                    &msg.method_name,
+                    Payload::Input,
+                    std::mem::take(&mut msg.parameter),
+                )?;
+                msg.encode_to_vec().into()
+            }
+            _ => serialized_entry,
+        };
+
+        Ok(RawEntry::new(header, serialized_entry))
+    }
+}
+
+impl<Schemas: JsonMapperResolver> Transcoder for JsonTranscoder<Schemas> {
+    fn encode_entry(
+        &mut self,
+        entry_index: EntryIndex,
+        entry: PlainRawEntry,
+    ) -> Result<PlainRawEntry, InvocationError> {
+        self.transcode_entry(entry_index, entry, Self::to_json)
+    }
+
+    fn decode_entry(
+        &mut self,
+        entry_index: EntryIndex,
+        entry: PlainRawEntry,
+    ) -> Result<PlainRawEntry, InvocationError> {
+        self.transcode_entry(entry_index, entry, Self::from_json)
+    }
+
+    fn encode_completion(
+        &mut self,
+        mut completion: Completion,
+    ) -> Result<Completion, InvocationError> {
+        if let Some((service_name, method_name)) =
+            self.pending_invokes.remove(&completion.entry_index)
+        {
+            if let CompletionResult::Success(value) = &mut completion.result {
+                *value = self.to_json(
+                    &service_name,
+                    &method_name,
+                    Payload::Output,
+                    std::mem::take(value),
+                )?;
+            }
+        }
+        Ok(completion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use restate_invoker_api::EntryEnricher as _;
+    use restate_pb::mocks::{DESCRIPTOR_POOL, GREETER_SERVICE_NAME};
+    use restate_schema_api::deployment::Deployment;
+    use restate_schema_api::service::ServiceCallPolicy;
+    use restate_service_protocol::codec::ProtobufRawEntryCodec;
+    use restate_test_util::{assert_eq, let_assert};
+    use restate_types::journal::{EntryResult, OutputStreamEntry};
+
+    const ALLOWED_CALLER: &str = "greeter.AllowedCaller";
+    const DENIED_CALLER: &str = "greeter.DeniedCaller";
+
+    fn enricher() -> EntryEnricher<restate_schema_impl::Schemas, ProtobufRawEntryCodec> {
+        let schemas = restate_schema_impl::Schemas::default();
+        let deployment = Deployment::mock();
+        schemas
+            .apply_updates(
+                schemas
+                    .compute_new_deployment(
+                        Some(deployment.id),
+                        deployment.metadata,
+                        vec![GREETER_SERVICE_NAME.to_owned()],
+                        DESCRIPTOR_POOL.clone(),
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        schemas
+            .apply_updates(vec![schemas
+                .compute_modify_service_call_policy(
+                    GREETER_SERVICE_NAME.to_owned(),
+                    ServiceCallPolicy {
+                        allowed_callers: Some([ALLOWED_CALLER.to_owned()].into()),
+                    },
+                )
+                .unwrap()])
+            .unwrap();
+
+        EntryEnricher::new(schemas)
+    }
+
+    fn enrich_invoke(
+        enricher: &EntryEnricher<restate_schema_impl::Schemas, ProtobufRawEntryCodec>,
+        caller: &str,
+        service_name: &str,
+        method_name: &str,
+        parameter: impl Into<Bytes>,
+    ) -> InvokeEnrichmentResult {
+        let entry = ProtobufRawEntryCodec::serialize(Entry::invoke(
+            InvokeRequest::new(service_name, method_name, parameter.into()),
+            None,
+        ));
+        let enriched_entry = enricher
+            .enrich_entry(
+                entry,
+                &FullInvocationId::new(caller, Bytes::new(), InvocationUuid::new()),
+                &ServiceInvocationSpanContext::empty(),
+            )
+            .unwrap();
+
+        let_assert!(
+            EnrichedEntryHeader::Invoke {
+                enrichment_result: Some(enrichment_result),
+                ..
+            } = enriched_entry.header()
+        );
+        enrichment_result.clone()
+    }
+
+    #[test]
+    fn call_policy() {
+        let enricher = enricher();
+
+        assert_eq!(
+            enrich_invoke(&enricher, ALLOWED_CALLER, GREETER_SERVICE_NAME, "Greet", "")
+                .denied_by_call_policy,
+            None
+        );
+        assert_eq!(
+            enrich_invoke(&enricher, DENIED_CALLER, GREETER_SERVICE_NAME, "Greet", "")
+                .denied_by_call_policy,
+            Some(ByteString::from_static(GREETER_SERVICE_NAME))
+        );
+    }
+
+    #[test]
+    fn call_policy_through_ingress() {
+        let enricher = enricher();
+        let ingress_invoke_request = restate_pb::restate::InvokeRequest {
+            service: GREETER_SERVICE_NAME.to_owned(),
+            method: "Greet".to_owned(),
+            argument: None,
+        }
+        .encode_to_vec();
+
+        assert_eq!(
+            enrich_invoke(
+                &enricher,
+                ALLOWED_CALLER,
+                restate_pb::INGRESS_SERVICE_NAME,
+                restate_pb::INGRESS_INVOKE_METHOD_NAME,
+                ingress_invoke_request.clone()
+            )
+            .denied_by_call_policy,
+            None
+        );
+        assert_eq!(
+            enrich_invoke(
+                &enricher,
+                DENIED_CALLER,
+                restate_pb::INGRESS_SERVICE_NAME,
+                restate_pb::INGRESS_INVOKE_METHOD_NAME,
+                ingress_invoke_request
+            )
+            .denied_by_call_policy,
+            Some(ByteString::from_static(GREETER_SERVICE_NAME))
+        );
+    }
+
+    const JSON_GREETER_SERVICE_NAME: &str = "greeter.JsonGreeter";
+
+    fn json_enricher() -> (
+        EntryEnricher<restate_schema_impl::Schemas, ProtobufRawEntryCodec>,
+        DeploymentId,
+    ) {
+        let enricher = enricher();
+        let (services, descriptor_pool) =
+            restate_schema_impl::json_manifest::synthesize_descriptor_pool(
+                &serde_json::from_value(serde_json::json!({
+                    "services": [{
+                        "name": JSON_GREETER_SERVICE_NAME,
+                        "instanceType": "Unkeyed",
+                        "handlers": [{
+                            "name": "Greet",
+                            "inputSchema": {
+                                "type": "object",
+                                "properties": { "count": { "type": "integer" } }
+                            },
+                            "outputSchema": { "type": "string" }
+                        }]
+                    }]
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+        let deployment = Deployment::mock_with_uri("http://localhost:9081");
+        enricher
+            .schemas
+            .apply_updates(
+                enricher
+                    .schemas
+                    .compute_new_deployment(
+                        Some(deployment.id),
+                        deployment.metadata,
+                        services,
+                        descriptor_pool,
+                        false,
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+
+        (enricher, deployment.id)
+    }
+
+    fn entry_payload(entry: PlainRawEntry) -> Bytes {
+        match ProtobufRawEntryCodec::deserialize(
+            entry.header().as_entry_type(),
+            entry.into_inner().1,
+        )
+        .unwrap()
+        {
+            Entry::PollInputStream(entry) => {
+                let_assert!(EntryResult::Success(value) = entry.result);
+                value
+            }
+            Entry::OutputStream(entry) => {
+                let_assert!(EntryResult::Success(value) = entry.result);
+                value
+            }
+            Entry::Invoke(entry) => entry.request.parameter,
+            entry => panic!("Unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn json_transcoding() {
+        let (enricher, deployment_id) = json_enricher();
+        let mut transcoder = enricher
+            .resolve_transcoder(&deployment_id, JSON_GREETER_SERVICE_NAME, "Greet")
+            .unwrap();
+
+        // The string output is wrapped in the field 1 of the synthesized message
+        let output = entry_payload(
+            transcoder
+                .decode_entry(
+                    0,
+                    ProtobufRawEntryCodec::serialize(Entry::OutputStream(OutputStreamEntry {
+                        result: EntryResult::Success(Bytes::from_static(b"\"Hello\"")),
+                    })),
+                )
+                .unwrap(),
+        );
+        assert_eq!(output, Bytes::from_static(b"\x0a\x05Hello"));
+        assert_eq!(
+            entry_payload(
+                transcoder
+                    .encode_entry(
+                        0,
+                        ProtobufRawEntryCodec::serialize(Entry::OutputStream(OutputStreamEntry {
+                            result: EntryResult::Success(output.clone()),
+                        }))
+                    )
+                    .unwrap()
+            ),
+            Bytes::from_static(b"\"Hello\"")
+        );
+
+        let invoke = transcoder
+            .decode_entry(
+                1,
+                ProtobufRawEntryCodec::serialize(Entry::invoke(
+                    InvokeRequest::new(
+                        JSON_GREETER_SERVICE_NAME,
+                        "Greet",
+                        Bytes::from_static(br#"{"count": 42}"#),
+                    ),
+                    None,
+                )),
+            )
+            .unwrap();
+        // The 64 bit integers are sent as JSON numbers
+        assert_eq!(
+            entry_payload(transcoder.encode_entry(1, invoke).unwrap()),
+            Bytes::from_static(br#"{"count":42}"#)
+        );
+
+        // The completion of the invoke entry is transcoded with the output schema of the callee
+        let completion = transcoder
+            .encode_completion(Completion::new(1, CompletionResult::Success(output)))
+            .unwrap();
+        assert_eq!(
+            completion.result,
+            CompletionResult::Success(Bytes::from_static(b"\"Hello\""))
+        );
+
+        // Payloads not matching the schema are rejected
+        assert_eq!(
+            transcoder
+                .decode_entry(
+                    2,
+                    ProtobufRawEntryCodec::serialize(Entry::invoke(
+                        InvokeRequest::new(
+                            JSON_GREETER_SERVICE_NAME,
+                            "Greet",
+                            Bytes::from_static(br#"{"count": "many"}"#),
+                        ),
+                        None,
+                    )),
+                )
+                .unwrap_err()
+                .code(),
+            restate_types::errors::InvocationErrorCode::from(UserErrorCode::InvalidArgument)
+        );
+    }
+}
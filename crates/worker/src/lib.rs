@@ -21,8 +21,9 @@ use futures::StreamExt;
 use partition::shuffle;
 use restate_bifrost::Bifrost;
 use restate_consensus::Consensus;
+use restate_ingress_dispatcher::PayloadDecoder;
 use restate_ingress_dispatcher::Service as IngressDispatcherService;
-use restate_ingress_kafka::Service as IngressKafkaService;
+use restate_ingress_kafka::{KafkaEgress, Service as IngressKafkaService};
 use restate_invoker_impl::{
     ChannelServiceHandle as InvokerChannelServiceHandle, Service as InvokerService,
 };
@@ -266,15 +267,13 @@ impl Worker {
 
         let ingress_dispatcher_service = IngressDispatcherService::new(my_node_id, channel_size);
 
-        // ingress_grpc
-        let external_client_ingress = ingress_grpc.build(
-            ingress_dispatcher_service.create_ingress_request_sender(),
-            schemas.clone(),
-        );
-
         // ingress_kafka
+        let kafka_egress = kafka.build_egress();
         let kafka_config_clone = kafka.clone();
-        let ingress_kafka = kafka.build(ingress_dispatcher_service.create_ingress_request_sender());
+        let ingress_kafka = kafka.build(
+            ingress_dispatcher_service.create_ingress_request_sender(),
+            PayloadDecoder::new(schemas.clone()),
+        );
         let subscription_controller_handle =
             subscription_integration::SubscriptionControllerHandle::new(
                 kafka_config_clone,
@@ -306,14 +305,27 @@ impl Worker {
             schemas.clone(),
         );
 
+        let services = Services::new(
+            consensus.create_proposal_sender(),
+            subscription_controller_handle,
+            partition_table.clone(),
+            rocksdb_storage.clone(),
+            channel_size,
+        );
+
         let storage_query_context = storage_query_datafusion.build(
             rocksdb_storage.clone(),
             invoker.status_reader(),
             schemas.clone(),
+            ingress_kafka.subscription_offsets(),
+            services.worker_command_tx(),
+            partition_table.partitioner(),
         )?;
         let storage_query_postgres = storage_query_postgres.build(storage_query_context.clone());
 
         let partitioner = partition_table.partitioner();
+        // The built-in ingress service checks the invocation owner like the ingress does
+        let invocation_owner_header = ingress_grpc.invocation_owner_header().map(str::to_owned);
 
         let (command_senders, processors): (Vec<_>, Vec<_>) = partitioner
             .map(|(idx, partition_range)| {
@@ -328,9 +340,11 @@ impl Worker {
                     proposal_sender,
                     invoker_sender,
                     network_handle.clone(),
+                    kafka_egress.clone(),
                     network.create_partition_processor_sender(),
                     rocksdb_storage.clone(),
                     schemas.clone(),
+                    invocation_owner_header.clone(),
                     partition_processor_options.clone(),
                 )
             })
@@ -338,11 +352,11 @@ impl Worker {
 
         consensus.register_state_machines(command_senders);
 
-        let services = Services::new(
-            consensus.create_proposal_sender(),
-            subscription_controller_handle,
-            partition_table,
-            channel_size,
+        // ingress_grpc
+        let external_client_ingress = ingress_grpc.build(
+            ingress_dispatcher_service.create_ingress_request_sender(),
+            schemas.clone(),
+            services.worker_command_tx(),
         );
 
         Ok(Self {
@@ -373,9 +387,11 @@ impl Worker {
         proposal_sender: mpsc::Sender<ConsensusMsg>,
         invoker_sender: InvokerChannelServiceHandle,
         network_handle: UnboundedNetworkHandle<shuffle::ShuffleInput, shuffle::ShuffleOutput>,
+        kafka_egress: KafkaEgress,
         ack_sender: PartitionProcessorSender<partition::StateMachineAckResponse>,
         rocksdb_storage: RocksDBStorage,
         schemas: Schemas,
+        invocation_owner_header: Option<String>,
         partition_processor_options: partition::Options,
     ) -> ((PeerId, mpsc::Sender<ConsensusCommand>), PartitionProcessor) {
         let (command_tx, command_rx) = mpsc::channel(channel_size);
@@ -389,9 +405,11 @@ impl Worker {
             IdentitySender::new(peer_id, proposal_sender),
             invoker_sender,
             network_handle,
+            kafka_egress,
             ack_sender,
             rocksdb_storage,
             schemas,
+            invocation_owner_header,
             partition_processor_options,
         );
 
@@ -40,6 +40,7 @@ mod ingress_integration {
     use restate_types::identifiers::{PartitionKey, PeerId};
     use restate_types::invocation::ServiceInvocation;
     use restate_types::message::{AckKind, MessageIndex};
+    use restate_types::time::MillisSinceEpoch;
     use restate_types::GenerationalNodeId;
 
     impl TargetConsensusOrShuffle<IngressToConsensus, IngressToShuffle>
@@ -51,11 +52,13 @@ mod ingress_integration {
                     service_invocation,
                     from_node_id: ingress_dispatcher_id,
                     deduplication_source,
+                    deduplication_expiration_time,
                     msg_index,
                 } => ConsensusOrShuffleTarget::Consensus(IngressToConsensus {
                     service_invocation,
                     from_node_id: ingress_dispatcher_id,
                     deduplication_source,
+                    deduplication_expiration_time,
                     msg_index,
                 }),
                 restate_ingress_dispatcher::IngressDispatcherOutput::Ack(
@@ -76,6 +79,7 @@ mod ingress_integration {
         service_invocation: ServiceInvocation,
         from_node_id: GenerationalNodeId,
         deduplication_source: Option<String>,
+        deduplication_expiration_time: Option<MillisSinceEpoch>,
         msg_index: MessageIndex,
     }
 
@@ -91,6 +95,7 @@ mod ingress_integration {
                 service_invocation,
                 from_node_id,
                 deduplication_source,
+                deduplication_expiration_time,
                 msg_index,
             } = ingress_to_consensus;
 
@@ -107,6 +112,7 @@ mod ingress_integration {
                         from_node_id,
                         source_id,
                         msg_index,
+                        deduplication_expiration_time,
                     ),
                 ),
             }
@@ -232,6 +238,9 @@ mod shuffle_integration {
                         msg_index,
                     })
                 }
+                shuffle::ShuffleMessageDestination::Kafka(_) => {
+                    unreachable!("Kafka records are published by the shuffle itself")
+                }
             }
         }
     }
@@ -249,10 +258,22 @@ mod shuffle_integration {
                 shuffle_id,
             } = value;
 
+            let ack_target = restate_ingress_dispatcher::AckTarget::new(shuffle_id, msg_index);
+
             let result = match response {
-                ResponseResult::Success(result) => Ok(result),
-                ResponseResult::Failure(err_code, error_msg) => {
-                    Err(InvocationError::new(err_code, error_msg.to_string()))
+                shuffle::IngressResponseKind::Result(ResponseResult::Success(result)) => Ok(result),
+                shuffle::IngressResponseKind::Result(ResponseResult::Failure(
+                    err_code,
+                    error_msg,
+                )) => Err(InvocationError::new(err_code, error_msg.to_string())),
+                shuffle::IngressResponseKind::Chunk(chunk) => {
+                    return restate_ingress_dispatcher::IngressDispatcherInput::response_chunk(
+                        restate_ingress_dispatcher::IngressResponseChunkMessage {
+                            full_invocation_id,
+                            chunk,
+                            ack_target,
+                        },
+                    );
                 }
             };
 
@@ -260,7 +281,7 @@ mod shuffle_integration {
                 restate_ingress_dispatcher::IngressResponseMessage {
                     full_invocation_id,
                     result,
-                    ack_target: restate_ingress_dispatcher::AckTarget::new(shuffle_id, msg_index),
+                    ack_target,
                 },
             )
         }
@@ -152,6 +152,7 @@ where
                 response_sink,
                 method,
                 argument,
+                headers,
             } => {
                 non_deterministic_service_invoker
                     .invoke(
@@ -160,6 +161,7 @@ where
                         span_context,
                         response_sink,
                         argument,
+                        headers,
                     )
                     .await;
             }
@@ -32,6 +32,7 @@ use crate::partition::state_machine::Action;
 use crate::util::IdentitySender;
 pub(crate) use action_collector::{ActionEffect, ActionEffectStream, LeaderAwareActionCollector};
 use restate_errors::NotRunningError;
+use restate_ingress_kafka::KafkaEgress;
 use restate_schema_impl::Schemas;
 use restate_storage_api::status_table::InvocationStatus;
 use restate_storage_rocksdb::RocksDBStorage;
@@ -59,6 +60,7 @@ pub(crate) struct FollowerState<I, N> {
     channel_size: usize,
     invoker_tx: I,
     network_handle: N,
+    kafka_egress: KafkaEgress,
     ack_tx: restate_network::PartitionProcessorSender<StateMachineAckResponse>,
     self_proposal_tx: IdentitySender<StateMachineAckCommand>,
 }
@@ -97,6 +99,7 @@ where
         channel_size: usize,
         invoker_tx: InvokerInputSender,
         network_handle: NetworkHandle,
+        kafka_egress: KafkaEgress,
         ack_tx: restate_network::PartitionProcessorSender<StateMachineAckResponse>,
         self_proposal_tx: IdentitySender<StateMachineAckCommand>,
     ) -> (ActionEffectStream, Self) {
@@ -109,6 +112,7 @@ where
                 channel_size,
                 invoker_tx,
                 network_handle,
+                kafka_egress,
                 ack_tx,
                 self_proposal_tx,
             }),
@@ -125,6 +129,7 @@ where
         partition_key_range: RangeInclusive<PartitionKey>,
         partition_storage: &mut PartitionStorage,
         schemas: &'a Schemas,
+        invocation_owner_header: Option<&'a str>,
     ) -> Result<
         (
             ActionEffectStream,
@@ -138,6 +143,7 @@ where
                 partition_key_range,
                 partition_storage,
                 schemas,
+                invocation_owner_header,
             )
             .await
         } else {
@@ -149,6 +155,7 @@ where
                     partition_key_range,
                     partition_storage,
                     schemas,
+                    invocation_owner_header,
                 )
                 .await
         }
@@ -160,6 +167,7 @@ where
         partition_key_range: RangeInclusive<PartitionKey>,
         partition_storage: &mut PartitionStorage,
         schemas: &'a Schemas,
+        invocation_owner_header: Option<&'a str>,
     ) -> Result<
         (
             ActionEffectStream,
@@ -169,7 +177,11 @@ where
     > {
         if let LeadershipState::Follower(mut follower_state) = self {
             let (mut service_invoker, service_invoker_output_rx) =
-                non_deterministic::ServiceInvoker::new(partition_storage.clone(), schemas);
+                non_deterministic::ServiceInvoker::new(
+                    partition_storage.clone(),
+                    schemas,
+                    invocation_owner_header,
+                );
 
             let invoker_rx = Self::resume_invoked_invocations(
                 &mut follower_state.invoker_tx,
@@ -194,6 +206,7 @@ where
                 follower_state.partition_id,
                 partition_storage.clone(),
                 follower_state.network_handle.create_shuffle_sender(),
+                follower_state.kafka_egress.clone(),
                 shuffle_tx,
                 follower_state.channel_size,
             );
@@ -302,6 +315,7 @@ where
                     metadata.journal_metadata.span_context,
                     response_sink,
                     argument,
+                    metadata.headers,
                 )
                 .await;
         }
@@ -327,6 +341,7 @@ where
                     timer_service_options: num_in_memory_timers,
                     mut invoker_tx,
                     network_handle,
+                    kafka_egress,
                     ack_tx,
                     self_proposal_tx,
                 },
@@ -360,6 +375,7 @@ where
                 channel_size,
                 invoker_tx,
                 network_handle,
+                kafka_egress,
                 ack_tx,
                 self_proposal_tx,
             ))
@@ -18,6 +18,7 @@ use crate::partition::storage::{PartitionStorage, Transaction};
 use crate::util::IdentitySender;
 use futures::StreamExt;
 use metrics::counter;
+use restate_ingress_kafka::KafkaEgress;
 use restate_schema_impl::Schemas;
 use restate_storage_rocksdb::RocksDBStorage;
 use restate_types::identifiers::{PartitionId, PartitionKey, PeerId};
@@ -64,11 +65,14 @@ pub(super) struct PartitionProcessor<RawEntryCodec, InvokerInputSender, NetworkH
 
     network_handle: NetworkHandle,
 
+    kafka_egress: KafkaEgress,
+
     ack_tx: restate_network::PartitionProcessorSender<StateMachineAckResponse>,
 
     rocksdb_storage: RocksDBStorage,
 
     schemas: Schemas,
+    invocation_owner_header: Option<String>,
 
     options: Options,
 
@@ -93,9 +97,11 @@ where
         proposal_sender: IdentitySender<StateMachineAckCommand>,
         invoker_tx: InvokerInputSender,
         network_handle: NetworkHandle,
+        kafka_egress: KafkaEgress,
         ack_tx: restate_network::PartitionProcessorSender<StateMachineAckResponse>,
         rocksdb_storage: RocksDBStorage,
         schemas: Schemas,
+        invocation_owner_header: Option<String>,
         options: Options,
     ) -> Self {
         Self {
@@ -108,10 +114,12 @@ where
             proposal_tx: proposal_sender,
             invoker_tx,
             network_handle,
+            kafka_egress,
             ack_tx,
             _entry_codec: Default::default(),
             rocksdb_storage,
             schemas,
+            invocation_owner_header,
             options,
         }
     }
@@ -127,10 +135,12 @@ where
             mut command_rx,
             invoker_tx,
             network_handle,
+            kafka_egress,
             proposal_tx,
             ack_tx,
             rocksdb_storage,
             schemas,
+            invocation_owner_header,
             options,
             ..
         } = self;
@@ -148,6 +158,7 @@ where
             channel_size,
             invoker_tx,
             network_handle,
+            kafka_egress,
             ack_tx,
             proposal_tx.clone(),
         );
@@ -202,6 +213,7 @@ where
                                     partition_key_range.clone(),
                                     &mut partition_storage,
                                     &schemas,
+                                    invocation_owner_header.as_deref(),
 
                                 )
                                 .await?;
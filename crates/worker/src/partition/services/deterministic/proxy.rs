@@ -12,7 +12,7 @@ use super::*;
 
 use restate_pb::restate::internal::*;
 use restate_types::identifiers::InvocationUuid;
-use restate_types::invocation::{ServiceInvocation, Source};
+use restate_types::invocation::{InvocationFailurePolicy, ServiceInvocation, Source};
 use tracing::{instrument, trace};
 
 impl ProxyBuiltInService for &mut ServiceInvoker<'_> {
@@ -26,15 +26,21 @@ impl ProxyBuiltInService for &mut ServiceInvoker<'_> {
         );
         trace!(restate.invocation.id = %target_fid, "Proxying");
 
-        self.send_message(OutboxMessage::ServiceInvocation(ServiceInvocation::new(
-            target_fid,
-            req.target_method,
-            req.input,
-            // Proxy service is only used by the ingress dispatcher to for deduplication purposes.
-            Source::Ingress,
-            None,
-            self.span_context.as_parent(),
-        )));
+        self.send_message(OutboxMessage::ServiceInvocation(
+            ServiceInvocation::new(
+                target_fid,
+                req.target_method,
+                req.input,
+                // Proxy service is only used by the ingress dispatcher to for deduplication purposes.
+                Source::Ingress,
+                None,
+                self.span_context.as_parent(),
+            )
+            .with_failure_policy(InvocationFailurePolicy {
+                max_attempts: req.max_attempts,
+                dead_letter: req.dead_letter_cluster.zip(req.dead_letter_topic),
+            }),
+        ));
 
         Ok(())
     }
@@ -12,9 +12,10 @@ use crate::partition::storage::PartitionStorage;
 use bytes::Bytes;
 use restate_storage_api::status_table::JournalMetadata;
 use restate_storage_rocksdb::RocksDBStorage;
-use restate_types::identifiers::{InvocationUuid, ServiceId};
+use restate_types::identifiers::{InvocationId, InvocationUuid, ServiceId};
 use restate_types::journal::enriched::EnrichedRawEntry;
 use restate_types::journal::EntryIndex;
+use restate_worker_api::InvocationStatus;
 use std::borrow::Cow;
 use std::fmt;
 use std::future::Future;
@@ -42,6 +43,12 @@ trait StateReader {
         service_id: &ServiceId,
         entry_index: EntryIndex,
     ) -> impl Future<Output = Result<Option<EnrichedRawEntry>, anyhow::Error>> + Send;
+
+    /// Reads the status of an invocation, which might be owned by another partition.
+    fn read_invocation_status(
+        &mut self,
+        invocation_id: InvocationId,
+    ) -> impl Future<Output = Result<InvocationStatus, anyhow::Error>> + Send;
 }
 
 impl StateReader for PartitionStorage<RocksDBStorage> {
@@ -77,6 +84,17 @@ impl StateReader for PartitionStorage<RocksDBStorage> {
     ) -> Result<Option<EnrichedRawEntry>, anyhow::Error> {
         Ok(self.load_journal_entry(service_id, entry_index).await?)
     }
+
+    async fn read_invocation_status(
+        &mut self,
+        invocation_id: InvocationId,
+    ) -> Result<InvocationStatus, anyhow::Error> {
+        Ok(crate::services::read_invocation_status(
+            &mut self.unpartitioned_storage(),
+            invocation_id,
+        )
+        .await?)
+    }
 }
 
 // -- Serde
@@ -191,6 +209,7 @@ mod tests {
     pub(super) struct MockStateReader(
         pub(super) HashMap<String, Bytes>,
         pub(super) Option<(InvocationUuid, JournalMetadata, Vec<EnrichedRawEntry>)>,
+        pub(super) HashMap<InvocationId, InvocationStatus>,
     );
 
     impl MockStateReader {
@@ -223,6 +242,15 @@ mod tests {
             self
         }
 
+        pub(super) fn set_invocation_status(
+            &mut self,
+            invocation_id: InvocationId,
+            status: InvocationStatus,
+        ) -> &mut Self {
+            self.2.insert(invocation_id, status);
+            self
+        }
+
         pub(super) fn complete_entry(&mut self, completion: Completion) -> &mut Self {
             let (_, _, v) = self.1.as_mut().expect("There must be a journal");
             ProtobufRawEntryCodec::write_completion(
@@ -319,5 +347,16 @@ mod tests {
                 .and_then(|(_, _, v)| v.get(entry_index as usize))
                 .cloned())
         }
+
+        async fn read_invocation_status(
+            &mut self,
+            invocation_id: InvocationId,
+        ) -> Result<InvocationStatus, Error> {
+            Ok(self
+                .2
+                .get(&invocation_id)
+                .cloned()
+                .unwrap_or_else(InvocationStatus::completed))
+        }
     }
 }
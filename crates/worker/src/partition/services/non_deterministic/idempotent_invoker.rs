@@ -13,8 +13,9 @@ use super::*;
 use prost::Message;
 use restate_pb::builtin_service::ResponseSerializer;
 use restate_pb::restate::internal::*;
+use restate_schema_api::deployment::DeploymentResolver;
 use restate_types::identifiers::InvocationUuid;
-use restate_types::invocation::{ServiceInvocation, SpanRelation};
+use restate_types::invocation::{Header, ServiceInvocation, SpanRelation};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime};
 use tracing::{instrument, trace};
@@ -92,7 +93,14 @@ impl<'a, State: StateReader + Send + Sync> IdempotentInvokerBuiltInService
                 service_name: request.service_name.to_string(),
                 method_name: request.method.to_string(),
                 retention_period_sec: if request.retention_period_sec == 0 {
-                    DEFAULT_RETENTION_PERIOD
+                    // Fall back to the retention configured for the target service
+                    self.schemas
+                        .resolve_service_configuration(&request.service_name)
+                        .and_then(|configuration| configuration.idempotency_retention)
+                        .map(|retention| {
+                            u32::try_from(Duration::from(retention).as_secs()).unwrap_or(u32::MAX)
+                        })
+                        .unwrap_or(DEFAULT_RETENTION_PERIOD)
                 } else {
                     request.retention_period_sec
                 },
@@ -102,21 +110,31 @@ impl<'a, State: StateReader + Send + Sync> IdempotentInvokerBuiltInService
         trace!(restate.invocation.id = %fid, "Invoking target service");
 
         // Invoke service
-        self.send_message(OutboxMessage::ServiceInvocation(ServiceInvocation::new(
-            fid,
-            request.method,
-            request.argument,
-            Source::Service(self.full_invocation_id.clone()),
-            Some(ServiceInvocationResponseSink::NewInvocation {
-                target: FullInvocationId::with_service_id(
-                    self.full_invocation_id.service_id.clone(),
-                    InvocationUuid::new(),
-                ),
-                method: restate_pb::IDEMPOTENT_INVOKER_INTERNAL_ON_RESPONSE_METHOD_NAME.to_string(),
-                caller_context: Default::default(),
-            }),
-            SpanRelation::None,
-        )));
+        self.send_message(OutboxMessage::ServiceInvocation(
+            ServiceInvocation::new(
+                fid,
+                request.method,
+                request.argument,
+                Source::Service(self.full_invocation_id.clone()),
+                Some(ServiceInvocationResponseSink::NewInvocation {
+                    target: FullInvocationId::with_service_id(
+                        self.full_invocation_id.service_id.clone(),
+                        InvocationUuid::new(),
+                    ),
+                    method: restate_pb::IDEMPOTENT_INVOKER_INTERNAL_ON_RESPONSE_METHOD_NAME
+                        .to_string(),
+                    caller_context: Default::default(),
+                }),
+                SpanRelation::None,
+            )
+            .with_headers(
+                request
+                    .headers
+                    .into_iter()
+                    .map(|(name, value)| Header::new(name, value))
+                    .collect(),
+            ),
+        ));
 
         Ok(())
     }
@@ -179,6 +197,7 @@ impl<'a, State: StateReader + Send + Sync> IdempotentInvokerBuiltInService
             None,
             expiry_time.into(),
             0,
+            vec![],
         );
 
         // Send response to registered sinks
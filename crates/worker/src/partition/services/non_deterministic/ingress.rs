@@ -10,13 +10,15 @@
 
 use super::*;
 
+use prost::Message;
 use prost_reflect::{DeserializeOptions, ReflectMessage};
 use restate_pb::builtin_service::ResponseSerializer;
 use restate_pb::restate::*;
 use restate_schema_api::json::{JsonMapperResolver, JsonToProtobufMapper};
 use restate_schema_api::key::KeyExtractor;
-use restate_types::identifiers::InvocationUuid;
-use restate_types::invocation::ServiceInvocation;
+use restate_types::identifiers::{InvocationId, InvocationUuid};
+use restate_types::invocation::{InvocationTermination, ServiceInvocation};
+use restate_worker_api::InvocationStatusKind;
 use serde::Serialize;
 use tracing::instrument;
 
@@ -66,21 +68,11 @@ impl<'a, State> InvocationContext<'a, State> {
     }
 }
 
-impl<'a, State: StateReader + Send + Sync> IngressBuiltInService for InvocationContext<'a, State> {
-    #[instrument(
-        level = "trace",
-        skip_all,
-        fields(
-            restate.invocation.id = %self.full_invocation_id,
-            restate.ingress_invoke.target_service = request.service,
-            restate.ingress_invoke.target_method = request.method
-        )
-    )]
-    async fn invoke(
+impl<'a, State> InvocationContext<'a, State> {
+    fn prepare_invocation(
         &mut self,
         request: InvokeRequest,
-        response_serializer: ResponseSerializer<InvokeResponse>,
-    ) -> Result<(), InvocationError> {
+    ) -> Result<(FullInvocationId, String, Bytes), InvocationError> {
         use restate_pb::restate::invoke_request::Argument;
 
         // Extract the argument
@@ -95,39 +87,247 @@ impl<'a, State: StateReader + Send + Sync> IngressBuiltInService for InvocationC
         // Extract the fid
         let fid = self.generate_fid(request.service, &request.method, &argument)?;
 
+        Ok((fid, request.method, argument))
+    }
+}
+
+impl<'a, State: StateReader + Send + Sync> IngressBuiltInService for InvocationContext<'a, State> {
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %self.full_invocation_id,
+            restate.ingress_invoke.target_service = request.service,
+            restate.ingress_invoke.target_method = request.method
+        )
+    )]
+    async fn invoke(
+        &mut self,
+        request: InvokeRequest,
+        response_serializer: ResponseSerializer<InvokeResponse>,
+    ) -> Result<(), InvocationError> {
+        let (fid, method, argument) = self.prepare_invocation(request)?;
+
         // Respond to caller
         self.reply_to_caller(response_serializer.serialize_success(InvokeResponse {
             id: fid.to_string(),
         }));
 
-        // Invoke service
-        self.send_message(OutboxMessage::ServiceInvocation(ServiceInvocation::new(
-            fid,
-            request.method,
-            argument,
-            Source::Service(self.full_invocation_id.clone()),
-            None,
-            self.span_context.as_linked(),
-        )));
+        // Invoke service, propagating the headers the ingress received with this request
+        self.send_message(OutboxMessage::ServiceInvocation(
+            ServiceInvocation::new(
+                fid,
+                method,
+                argument,
+                Source::Service(self.full_invocation_id.clone()),
+                None,
+                self.span_context.as_linked(),
+            )
+            .with_headers(self.headers.to_vec()),
+        ));
+
+        Ok(())
+    }
+
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %self.full_invocation_id,
+            restate.ingress_batch_invoke.size = request.invocations.len()
+        )
+    )]
+    async fn batch_invoke(
+        &mut self,
+        request: BatchInvokeRequest,
+        response_serializer: ResponseSerializer<BatchInvokeResponse>,
+    ) -> Result<(), InvocationError> {
+        use restate_pb::restate::batch_invoke_response::{item, Item};
+
+        if request.invocations.len() > restate_pb::INGRESS_BATCH_INVOKE_MAX_SIZE {
+            return Err(InvocationError::new(
+                UserErrorCode::InvalidArgument,
+                format!(
+                    "BatchInvoke supports at most {} invocations",
+                    restate_pb::INGRESS_BATCH_INVOKE_MAX_SIZE
+                ),
+            ));
+        }
+
+        let mut results = Vec::with_capacity(request.invocations.len());
+        for batch_item in request.invocations {
+            let (fid, method, argument) =
+                match self.prepare_invocation(batch_item.invocation.unwrap_or_default()) {
+                    Ok(invocation) => invocation,
+                    Err(err) => {
+                        results.push(Item {
+                            result: Some(item::Result::Failure(err.into())),
+                        });
+                        continue;
+                    }
+                };
+            results.push(Item {
+                result: Some(item::Result::Id(fid.to_string())),
+            });
+
+            let service_invocation = if batch_item.idempotency_key.is_empty() {
+                ServiceInvocation::new(
+                    fid,
+                    method,
+                    argument,
+                    Source::Service(self.full_invocation_id.clone()),
+                    None,
+                    self.span_context.as_linked(),
+                )
+                .with_headers(self.headers.to_vec())
+            } else {
+                // Same as the ingress, the idempotent invocations go through the IdempotentInvoker
+                let (idempotent_invoker_fid, idempotent_invoke_request) =
+                    internal::IdempotentInvokeRequest::new_with_idempotent_invoker_fid(
+                        fid,
+                        method,
+                        argument,
+                        batch_item.idempotency_key.into(),
+                        None,
+                        self.headers.to_vec(),
+                    );
+
+                ServiceInvocation::new(
+                    idempotent_invoker_fid,
+                    restate_pb::IDEMPOTENT_INVOKER_INVOKE_METHOD_NAME,
+                    idempotent_invoke_request.encode_to_vec(),
+                    Source::Service(self.full_invocation_id.clone()),
+                    None,
+                    self.span_context.as_linked(),
+                )
+            };
+            self.send_message(OutboxMessage::ServiceInvocation(service_invocation));
+        }
+
+        self.reply_to_caller(response_serializer.serialize_success(BatchInvokeResponse {
+            invocations: results,
+        }));
+
+        Ok(())
+    }
+
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %self.full_invocation_id,
+            restate.ingress_cancel.target_id = request.id
+        )
+    )]
+    async fn cancel(
+        &mut self,
+        request: TerminateInvocationRequest,
+        response_serializer: ResponseSerializer<()>,
+    ) -> Result<(), InvocationError> {
+        let invocation_id = parse_invocation_id(&request.id)?;
+        self.check_invocation_owner(&invocation_id).await?;
+        self.send_message(OutboxMessage::InvocationTermination(
+            InvocationTermination::cancel(invocation_id),
+        ));
+        self.reply_to_caller(response_serializer.serialize_success(()));
+        Ok(())
+    }
+
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %self.full_invocation_id,
+            restate.ingress_kill.target_id = request.id
+        )
+    )]
+    async fn kill(
+        &mut self,
+        request: TerminateInvocationRequest,
+        response_serializer: ResponseSerializer<()>,
+    ) -> Result<(), InvocationError> {
+        let invocation_id = parse_invocation_id(&request.id)?;
+        self.check_invocation_owner(&invocation_id).await?;
+        self.send_message(OutboxMessage::InvocationTermination(
+            InvocationTermination::kill(invocation_id),
+        ));
+        self.reply_to_caller(response_serializer.serialize_success(()));
+        Ok(())
+    }
+
+    async fn get_status(
+        &mut self,
+        _: GetInvocationStatusRequest,
+        _: ResponseSerializer<GetInvocationStatusResponse>,
+    ) -> Result<(), InvocationError> {
+        // The status lives in the partition owning the invocation, which might not be this one
+        Err(InvocationError::new(
+            UserErrorCode::Unimplemented,
+            "GetStatus is available only through the ingress",
+        ))
+    }
+}
+
+impl<State: StateReader> InvocationContext<'_, State> {
+    /// Checks whether the caller started the given invocation, like the ingress does for the requests it receives,
+    /// by comparing the owner header of this invocation with the one persisted together with the given invocation.
+    async fn check_invocation_owner(
+        &mut self,
+        invocation_id: &InvocationId,
+    ) -> Result<(), InvocationError> {
+        let Some(owner_header) = self.invocation_owner_header else {
+            return Err(InvocationError::new(
+                UserErrorCode::PermissionDenied,
+                "Invocation management is disabled, configure 'worker.ingress_grpc.invocation_owner_header' to enable it",
+            ));
+        };
+
+        let status = self
+            .state_reader
+            .read_invocation_status(invocation_id.clone())
+            .await
+            .map_err(InvocationError::internal)?;
+        // Completed invocations don't retain their headers, and can't be terminated anyway
+        let is_owner = self
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(owner_header))
+            .is_some_and(|caller| status.has_header(owner_header, &caller.value));
+        if status.kind != InvocationStatusKind::Completed && !is_owner {
+            return Err(InvocationError::new(
+                UserErrorCode::PermissionDenied,
+                format!(
+                    "Invocation {} was not started by this client",
+                    invocation_id
+                ),
+            ));
+        }
 
         Ok(())
     }
 }
 
+fn parse_invocation_id(id: &str) -> Result<InvocationId, InvocationError> {
+    id.parse()
+        .map_err(|e| InvocationError::new(UserErrorCode::InvalidArgument, e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use futures::FutureExt;
     use googletest::assert_that;
-    use googletest::{all, pat};
+    use googletest::{all, elements_are, pat};
     use prost::Message;
     use prost_reflect::DynamicMessage;
     use test_log::test;
 
     use restate_schema_api::deployment::Deployment;
     use restate_test_util::matchers::*;
-    use restate_types::invocation::ServiceInvocation;
+    use restate_types::errors::InvocationErrorCode;
+    use restate_types::invocation::{Header, ServiceInvocation};
+    use restate_worker_api::InvocationStatus;
 
     use crate::partition::services::non_deterministic::tests::TestInvocationContext;
 
@@ -189,7 +389,8 @@ mod tests {
         ) -> invoke_request::Argument,
     ) {
         let mut ctx = TestInvocationContext::new(restate_pb::INGRESS_SERVICE_NAME)
-            .with_schemas(mock_schemas());
+            .with_schemas(mock_schemas())
+            .with_headers(vec![Header::new("x-tenant-id", "my-tenant")]);
 
         let expected_req = restate_pb::mocks::greeter::GreetingRequest {
             person: "Francesco".to_string(),
@@ -231,10 +432,208 @@ mod tests {
                         }),
                         method_name: displays_as(eq("Greet")),
                         argument: protobuf_decoded(eq(expected_req)),
+                        response_sink: none(),
+                        headers: elements_are![eq(Header::new("x-tenant-id", "my-tenant"))]
+                    }))
+                ))))
+            )
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn batch_invoke() {
+        let mut ctx = TestInvocationContext::new(restate_pb::INGRESS_SERVICE_NAME)
+            .with_schemas(mock_schemas())
+            .with_headers(vec![Header::new("x-tenant-id", "my-tenant")]);
+
+        let expected_req = restate_pb::mocks::greeter::GreetingRequest {
+            person: "Francesco".to_string(),
+        };
+        let (_, effects) = ctx
+            .invoke(|ctx| {
+                ctx.batch_invoke(
+                    BatchInvokeRequest {
+                        invocations: vec![
+                            batch_invoke_request::Item {
+                                invocation: Some(InvokeRequest {
+                                    service: restate_pb::mocks::GREETER_SERVICE_NAME.to_string(),
+                                    method: "Greet".to_string(),
+                                    argument: Some(invoke_request::Argument::Pb(
+                                        expected_req.encode_to_vec().into(),
+                                    )),
+                                }),
+                                idempotency_key: "".to_string(),
+                            },
+                            batch_invoke_request::Item {
+                                invocation: Some(InvokeRequest {
+                                    service: "UnknownService".to_string(),
+                                    method: "Greet".to_string(),
+                                    argument: None,
+                                }),
+                                idempotency_key: "".to_string(),
+                            },
+                            batch_invoke_request::Item {
+                                invocation: Some(InvokeRequest {
+                                    service: restate_pb::mocks::GREETER_SERVICE_NAME.to_string(),
+                                    method: "Greet".to_string(),
+                                    argument: Some(invoke_request::Argument::Pb(
+                                        expected_req.encode_to_vec().into(),
+                                    )),
+                                }),
+                                idempotency_key: "123".to_string(),
+                            },
+                        ],
+                    },
+                    ResponseSerializer::default(),
+                )
+                .boxed_local()
+            })
+            .await
+            .unwrap();
+
+        assert_that!(
+            effects,
+            all!(
+                contains(pat!(Effect::OutboxMessage(pat!(
+                    OutboxMessage::IngressResponse {
+                        response: pat!(ResponseResult::Success(protobuf_decoded(pat!(
+                            BatchInvokeResponse {
+                                invocations: elements_are![
+                                    pat!(batch_invoke_response::Item {
+                                        result: some(pat!(
+                                            batch_invoke_response::item::Result::Id(anything())
+                                        ))
+                                    }),
+                                    pat!(batch_invoke_response::Item {
+                                        result: some(pat!(
+                                            batch_invoke_response::item::Result::Failure(anything())
+                                        ))
+                                    }),
+                                    pat!(batch_invoke_response::Item {
+                                        result: some(pat!(
+                                            batch_invoke_response::item::Result::Id(anything())
+                                        ))
+                                    })
+                                ]
+                            }
+                        ))))
+                    }
+                )))),
+                contains(pat!(Effect::OutboxMessage(pat!(
+                    OutboxMessage::ServiceInvocation(pat!(ServiceInvocation {
+                        method_name: displays_as(eq("Greet")),
+                        argument: protobuf_decoded(eq(expected_req.clone())),
+                        response_sink: none(),
+                        headers: elements_are![eq(Header::new("x-tenant-id", "my-tenant"))]
+                    }))
+                )))),
+                contains(pat!(Effect::OutboxMessage(pat!(
+                    OutboxMessage::ServiceInvocation(pat!(ServiceInvocation {
+                        fid: pat!(FullInvocationId {
+                            service_id: pat!(ServiceId {
+                                service_name: displays_as(eq(
+                                    restate_pb::IDEMPOTENT_INVOKER_SERVICE_NAME
+                                ))
+                            })
+                        }),
+                        argument: protobuf_decoded(pat!(internal::IdempotentInvokeRequest {
+                            headers: eq(std::collections::HashMap::from([(
+                                "x-tenant-id".to_string(),
+                                "my-tenant".to_string()
+                            )]))
+                        })),
                         response_sink: none()
                     }))
                 ))))
             )
         );
     }
+
+    fn running_invocation(owner: &str) -> InvocationStatus {
+        InvocationStatus {
+            kind: InvocationStatusKind::Running,
+            headers: vec![Header::new("x-tenant-id", owner)],
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn cancel_disabled_without_owner_header() {
+        let mut ctx = TestInvocationContext::new(restate_pb::INGRESS_SERVICE_NAME)
+            .with_headers(vec![Header::new("x-tenant-id", "my-tenant")]);
+        let invocation_id = InvocationId::from(FullInvocationId::mock_random());
+        ctx.state_mut()
+            .set_invocation_status(invocation_id.clone(), running_invocation("my-tenant"));
+
+        assert_eq!(
+            ctx.invoke(|ctx| ctx
+                .cancel(
+                    TerminateInvocationRequest {
+                        id: invocation_id.to_string(),
+                    },
+                    ResponseSerializer::default()
+                )
+                .boxed_local())
+                .await
+                .unwrap_err()
+                .code(),
+            InvocationErrorCode::User(UserErrorCode::PermissionDenied)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn kill_requires_invocation_owner() {
+        let mut ctx = TestInvocationContext::new(restate_pb::INGRESS_SERVICE_NAME)
+            .with_invocation_owner_header("X-Tenant-Id")
+            .with_headers(vec![Header::new("x-tenant-id", "another-tenant")]);
+        let invocation_id = InvocationId::from(FullInvocationId::mock_random());
+        ctx.state_mut()
+            .set_invocation_status(invocation_id.clone(), running_invocation("my-tenant"));
+
+        assert_eq!(
+            ctx.invoke(|ctx| ctx
+                .kill(
+                    TerminateInvocationRequest {
+                        id: invocation_id.to_string(),
+                    },
+                    ResponseSerializer::default()
+                )
+                .boxed_local())
+                .await
+                .unwrap_err()
+                .code(),
+            InvocationErrorCode::User(UserErrorCode::PermissionDenied)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn kill_by_invocation_owner() {
+        let mut ctx = TestInvocationContext::new(restate_pb::INGRESS_SERVICE_NAME)
+            .with_invocation_owner_header("X-Tenant-Id")
+            .with_headers(vec![Header::new("x-tenant-id", "my-tenant")]);
+        let invocation_id = InvocationId::from(FullInvocationId::mock_random());
+        ctx.state_mut()
+            .set_invocation_status(invocation_id.clone(), running_invocation("my-tenant"));
+
+        let (_, effects) = ctx
+            .invoke(|ctx| {
+                ctx.kill(
+                    TerminateInvocationRequest {
+                        id: invocation_id.to_string(),
+                    },
+                    ResponseSerializer::default(),
+                )
+                .boxed_local()
+            })
+            .await
+            .unwrap();
+
+        assert_that!(
+            effects,
+            contains(pat!(Effect::OutboxMessage(pat!(
+                OutboxMessage::InvocationTermination(eq(InvocationTermination::kill(
+                    invocation_id
+                )))
+            ))))
+        );
+    }
 }
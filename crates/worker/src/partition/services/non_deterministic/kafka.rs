@@ -0,0 +1,121 @@
+// Copyright (c) 2023 -  Restate Software, Inc., Restate GmbH.
+// All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use super::*;
+
+use restate_pb::builtin_service::ResponseSerializer;
+use restate_pb::restate::*;
+use restate_storage_api::outbox_table::KafkaRecord;
+use restate_types::identifiers::InvocationId;
+use tracing::instrument;
+
+impl<'a, State: StateReader + Send + Sync> KafkaBuiltInService for InvocationContext<'a, State> {
+    #[instrument(
+        level = "trace",
+        skip_all,
+        fields(
+            restate.invocation.id = %self.full_invocation_id,
+            restate.kafka_publish.cluster = request.cluster,
+            restate.kafka_publish.topic = request.topic
+        )
+    )]
+    async fn publish(
+        &mut self,
+        request: PublishRequest,
+        response_serializer: ResponseSerializer<()>,
+    ) -> Result<(), InvocationError> {
+        if request.cluster.is_empty() || request.topic.is_empty() {
+            return Err(InvocationError::new(
+                UserErrorCode::InvalidArgument,
+                "cluster and topic must be non empty",
+            ));
+        }
+
+        // The record is written to the outbox together with the completion of this invocation,
+        // hence the invocation id uniquely identifies it even if the shuffle publishes it again.
+        self.send_message(OutboxMessage::KafkaRecord(KafkaRecord {
+            message_id: InvocationId::from(self.full_invocation_id).to_string(),
+            cluster: request.cluster,
+            topic: request.topic,
+            key: if request.key.is_empty() {
+                None
+            } else {
+                Some(request.key)
+            },
+            payload: request.value,
+            headers: request
+                .headers
+                .into_iter()
+                .map(|(name, value)| Header::new(name, value))
+                .collect(),
+        }));
+        self.reply_to_caller(response_serializer.serialize_success(()));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::FutureExt;
+    use googletest::assert_that;
+    use googletest::{all, elements_are, pat};
+    use test_log::test;
+
+    use restate_test_util::matchers::*;
+
+    use crate::partition::services::non_deterministic::tests::TestInvocationContext;
+
+    #[test(tokio::test)]
+    async fn publish() {
+        let mut ctx = TestInvocationContext::new(restate_pb::KAFKA_SERVICE_NAME);
+
+        let (fid, effects) = ctx
+            .invoke(|ctx| {
+                ctx.publish(
+                    PublishRequest {
+                        cluster: "my-cluster".to_string(),
+                        topic: "my-topic".to_string(),
+                        key: Bytes::new(),
+                        value: Bytes::from_static(b"123"),
+                        headers: [("content-type".to_string(), "text/plain".to_string())].into(),
+                    },
+                    ResponseSerializer::default(),
+                )
+                .boxed_local()
+            })
+            .await
+            .unwrap();
+
+        assert_that!(
+            effects,
+            all!(
+                contains(pat!(Effect::OutboxMessage(pat!(
+                    OutboxMessage::IngressResponse {
+                        full_invocation_id: eq(fid.clone()),
+                        response: pat!(ResponseResult::Success(anything()))
+                    }
+                )))),
+                contains(pat!(Effect::OutboxMessage(pat!(
+                    OutboxMessage::KafkaRecord(pat!(KafkaRecord {
+                        message_id: eq(InvocationId::from(&fid).to_string()),
+                        cluster: displays_as(eq("my-cluster")),
+                        topic: displays_as(eq("my-topic")),
+                        key: none(),
+                        payload: eq(Bytes::from_static(b"123")),
+                        headers: elements_are![eq(Header::new("content-type", "text/plain"))]
+                    }))
+                ))))
+            )
+        );
+    }
+}
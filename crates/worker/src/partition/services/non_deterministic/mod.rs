@@ -17,6 +17,7 @@ use restate_pb::builtin_service::ManualResponseBuiltInService;
 use restate_pb::restate::internal::IdempotentInvokerInvoker;
 use restate_pb::restate::internal::RemoteContextInvoker;
 use restate_pb::restate::IngressInvoker;
+use restate_pb::restate::KafkaInvoker;
 use restate_schema_impl::Schemas;
 use restate_storage_api::outbox_table::OutboxMessage;
 use restate_storage_api::status_table::NotificationTarget;
@@ -24,7 +25,7 @@ use restate_storage_rocksdb::RocksDBStorage;
 use restate_types::errors::{InvocationError, UserErrorCode};
 use restate_types::identifiers::{EntryIndex, FullInvocationId, InvocationUuid};
 use restate_types::invocation::{
-    ResponseResult, ServiceInvocationResponseSink, ServiceInvocationSpanContext, Source,
+    Header, ResponseResult, ServiceInvocationResponseSink, ServiceInvocationSpanContext, Source,
 };
 use restate_types::time::MillisSinceEpoch;
 use std::borrow::Cow;
@@ -35,6 +36,7 @@ use tracing::warn;
 
 mod idempotent_invoker;
 mod ingress;
+mod kafka;
 mod remote_context;
 
 #[derive(Debug)]
@@ -90,6 +92,7 @@ pub(crate) enum Effect {
         response_sink: Option<ServiceInvocationResponseSink>,
         time: MillisSinceEpoch,
         timer_index: EntryIndex,
+        headers: Vec<Header>,
     },
 
     End(
@@ -106,6 +109,7 @@ pub(crate) struct ServiceInvoker<'a> {
     storage: PartitionStorage<RocksDBStorage>,
     effects_tx: EffectsSender,
     schemas: &'a Schemas,
+    invocation_owner_header: Option<&'a str>,
 }
 
 impl<'a> ServiceInvoker<'a> {
@@ -121,6 +125,7 @@ impl<'a> ServiceInvoker<'a> {
     pub(crate) fn new(
         storage: PartitionStorage<RocksDBStorage>,
         schemas: &'a Schemas,
+        invocation_owner_header: Option<&'a str>,
     ) -> (Self, EffectsReceiver) {
         let (effects_tx, effects_rx) = mpsc::unbounded_channel();
 
@@ -128,6 +133,7 @@ impl<'a> ServiceInvoker<'a> {
             ServiceInvoker {
                 storage,
                 schemas,
+                invocation_owner_header,
                 effects_tx,
             },
             effects_rx,
@@ -141,6 +147,7 @@ impl<'a> ServiceInvoker<'a> {
         span_context: ServiceInvocationSpanContext,
         response_sink: Option<ServiceInvocationResponseSink>,
         argument: Bytes,
+        headers: Vec<Header>,
     ) {
         let mut out_effects = vec![];
         let mut state_and_journal_transitions = StateAndJournalTransitions::default();
@@ -149,7 +156,9 @@ impl<'a> ServiceInvoker<'a> {
             span_context: &span_context,
             state_reader: &mut self.storage,
             schemas: self.schemas,
+            invocation_owner_header: self.invocation_owner_header,
             response_sink: response_sink.as_ref(),
+            headers: &headers,
             effects_buffer: &mut out_effects,
             state_and_journal_transitions: &mut state_and_journal_transitions,
         };
@@ -160,6 +169,11 @@ impl<'a> ServiceInvoker<'a> {
                     .invoke_builtin(method, argument)
                     .await
             }
+            restate_pb::KAFKA_SERVICE_NAME => {
+                KafkaInvoker(invocation_context)
+                    .invoke_builtin(method, argument)
+                    .await
+            }
             restate_pb::REMOTE_CONTEXT_SERVICE_NAME => {
                 RemoteContextInvoker(invocation_context)
                     .invoke_builtin(method, argument)
@@ -241,8 +255,11 @@ struct InvocationContext<'a, S> {
     full_invocation_id: &'a FullInvocationId,
     state_reader: &'a mut S,
     schemas: &'a Schemas,
+    /// Header identifying the client which started an invocation, see the ingress options.
+    invocation_owner_header: Option<&'a str>,
     span_context: &'a ServiceInvocationSpanContext,
     response_sink: Option<&'a ServiceInvocationResponseSink>,
+    headers: &'a [Header],
 
     effects_buffer: &'a mut Vec<Effect>,
     state_and_journal_transitions: &'a mut StateAndJournalTransitions,
@@ -402,6 +419,7 @@ impl<S: StateReader> InvocationContext<'_, S> {
         response_sink: Option<ServiceInvocationResponseSink>,
         time: MillisSinceEpoch,
         timer_index: EntryIndex,
+        headers: Vec<Header>,
     ) {
         // Perhaps we can internally keep track of the timer index here?
         self.effects_buffer.push(Effect::DelayedInvoke {
@@ -412,6 +430,7 @@ impl<S: StateReader> InvocationContext<'_, S> {
             response_sink,
             time,
             timer_index,
+            headers,
         });
     }
 
@@ -486,6 +505,8 @@ mod tests {
         state_reader: MockStateReader,
         schemas: Schemas,
         response_sink: Option<ServiceInvocationResponseSink>,
+        headers: Vec<Header>,
+        invocation_owner_header: Option<String>,
     }
 
     impl TestInvocationContext {
@@ -501,6 +522,8 @@ mod tests {
                 response_sink: Some(ServiceInvocationResponseSink::Ingress(
                     GenerationalNodeId::new(1, 1),
                 )),
+                headers: vec![],
+                invocation_owner_header: None,
             }
         }
 
@@ -509,6 +532,16 @@ mod tests {
             self
         }
 
+        pub(super) fn with_headers(mut self, headers: Vec<Header>) -> Self {
+            self.headers = headers;
+            self
+        }
+
+        pub(super) fn with_invocation_owner_header(mut self, header_name: &str) -> Self {
+            self.invocation_owner_header = Some(header_name.to_owned());
+            self
+        }
+
         pub(super) fn state(&self) -> &MockStateReader {
             &self.state_reader
         }
@@ -539,7 +572,9 @@ mod tests {
                 span_context: &ServiceInvocationSpanContext::empty(),
                 state_reader: &mut self.state_reader,
                 schemas: &self.schemas,
+                invocation_owner_header: self.invocation_owner_header.as_deref(),
                 response_sink: self.response_sink.as_ref(),
+                headers: &self.headers,
                 effects_buffer: &mut out_effects,
                 state_and_journal_transitions: &mut state_and_journal_transitions,
             };
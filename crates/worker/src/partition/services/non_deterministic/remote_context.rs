@@ -269,6 +269,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                             .map_err(InvocationError::internal)?
                     );
 
+                    check_call_policy(self.schemas, virtual_journal_fid, &request)?;
                     let fid = self.generate_fid_from_invoke_request(&request)?;
                     let span_context =
                         ServiceInvocationSpanContext::start(&fid, journal_span_context.as_parent());
@@ -283,6 +284,8 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                             entry_index,
                         }),
                         span_context: span_context.clone(),
+                        headers: request.headers,
+                        failure_policy: Default::default(),
                     }));
 
                     EnrichedRawEntry::new(
@@ -293,6 +296,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                                 service_key: fid.service_id.key,
                                 service_name: fid.service_id.service_name,
                                 span_context,
+                                denied_by_call_policy: None,
                             }),
                         },
                         entry.into_inner().1,
@@ -317,6 +321,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                         .map_err(InvocationError::internal)?
                 );
 
+                check_call_policy(self.schemas, virtual_journal_fid, &request)?;
                 let fid = self.generate_fid_from_invoke_request(&request)?;
                 let span_context =
                     ServiceInvocationSpanContext::start(&fid, journal_span_context.as_linked());
@@ -330,6 +335,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                         None,
                         invoke_time.into(),
                         entry_index,
+                        request.headers,
                     )
                 } else {
                     self.send_message(OutboxMessage::ServiceInvocation(ServiceInvocation {
@@ -339,6 +345,8 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                         source: Source::Service(virtual_journal_fid.clone()),
                         response_sink: None,
                         span_context: span_context.clone(),
+                        headers: request.headers,
+                        failure_policy: Default::default(),
                     }))
                 }
                 EnrichedRawEntry::new(
@@ -348,6 +356,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                             service_key: fid.service_id.key,
                             service_name: fid.service_id.service_name,
                             span_context,
+                            denied_by_call_policy: None,
                         },
                     },
                     entry.into_inner().1,
@@ -397,6 +406,7 @@ impl<'a, State: StateReader> InvocationContext<'a, State> {
                 ))
             }
             PlainEntryHeader::Sleep { .. }
+            | PlainEntryHeader::OutputStreamChunk { .. }
             | PlainEntryHeader::GetStateKeys { .. }
             | PlainEntryHeader::ClearAllState { .. } => {
                 return Err(InvocationError::new(
@@ -783,6 +793,7 @@ impl<'a, State: StateReader + Send + Sync> RemoteContextBuiltInService
                     length,
                     true, // TODO add eager state
                     iter::empty(),
+                    iter::empty(),
                 ),
             )
             .expect("Encoding messages to a BytesMut should be infallible, unless OOM is reached.");
@@ -1159,6 +1170,24 @@ fn check_state_key(key: Bytes) -> Result<StateKey<Raw>, InvocationError> {
     ))
 }
 
+fn check_call_policy(
+    schemas: &Schemas,
+    caller: &FullInvocationId,
+    request: &InvokeRequest,
+) -> Result<(), InvocationError> {
+    match crate::invoker_integration::denied_by_call_policy(
+        schemas,
+        &caller.service_id.service_name,
+        request,
+    ) {
+        Some(service_name) => Err(InvocationError::service_call_not_allowed(
+            &caller.service_id.service_name,
+            service_name,
+        )),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
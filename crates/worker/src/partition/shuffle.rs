@@ -10,17 +10,23 @@
 
 use crate::partition::shuffle::state_machine::StateMachine;
 use async_channel::{TryRecvError, TrySendError};
-use restate_storage_api::outbox_table::OutboxMessage;
+use bytes::Bytes;
+use futures::future::Either;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use restate_ingress_kafka::KafkaEgress;
+use restate_storage_api::outbox_table::{KafkaRecord, OutboxMessage};
 use restate_types::identifiers::{FullInvocationId, PartitionId, PeerId};
 use restate_types::invocation::{
     InvocationResponse, InvocationTermination, ResponseResult, ServiceInvocation,
 };
 use restate_types::message::{AckKind, MessageIndex};
 use restate_types::GenerationalNodeId;
+use std::collections::HashSet;
 use std::future::Future;
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::debug;
+use tracing::{debug, trace, warn};
 
 #[derive(Debug)]
 pub(crate) struct NewOutboxMessage {
@@ -64,7 +70,13 @@ pub(crate) enum PartitionProcessorMessage {
 pub(crate) struct IngressResponse {
     pub(crate) _to_node_id: GenerationalNodeId,
     pub(crate) full_invocation_id: FullInvocationId,
-    pub(crate) response: ResponseResult,
+    pub(crate) response: IngressResponseKind,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum IngressResponseKind {
+    Result(ResponseResult),
+    Chunk(Bytes),
 }
 
 #[derive(Debug, Clone)]
@@ -106,6 +118,8 @@ impl ShuffleOutput {
 pub(crate) enum ShuffleMessageDestination {
     PartitionProcessor(PartitionProcessorMessage),
     Ingress(IngressResponse),
+    /// Records are published by the shuffle itself, rather than being sent through the network
+    Kafka(KafkaRecord),
 }
 
 impl From<OutboxMessage> for ShuffleMessageDestination {
@@ -118,7 +132,16 @@ impl From<OutboxMessage> for ShuffleMessageDestination {
             } => ShuffleMessageDestination::Ingress(IngressResponse {
                 _to_node_id: to_node_id,
                 full_invocation_id,
-                response,
+                response: IngressResponseKind::Result(response),
+            }),
+            OutboxMessage::IngressResponseChunk {
+                to_node_id,
+                full_invocation_id,
+                chunk,
+            } => ShuffleMessageDestination::Ingress(IngressResponse {
+                _to_node_id: to_node_id,
+                full_invocation_id,
+                response: IngressResponseKind::Chunk(chunk),
             }),
             OutboxMessage::ServiceResponse(response) => {
                 ShuffleMessageDestination::PartitionProcessor(PartitionProcessorMessage::Response(
@@ -135,6 +158,7 @@ impl From<OutboxMessage> for ShuffleMessageDestination {
                     PartitionProcessorMessage::InvocationTermination(invocation_termination),
                 )
             }
+            OutboxMessage::KafkaRecord(record) => ShuffleMessageDestination::Kafka(record),
         }
     }
 }
@@ -215,6 +239,9 @@ pub(super) struct Shuffle<OR> {
     // used to send messages to different partitions
     network_tx: mpsc::Sender<ShuffleOutput>,
 
+    // used to publish Kafka records
+    kafka_egress: KafkaEgress,
+
     network_in_rx: mpsc::Receiver<ShuffleInput>,
 
     // used to tell partition processor about outbox truncations
@@ -236,6 +263,7 @@ where
         partition_id: PartitionId,
         outbox_reader: OR,
         network_tx: mpsc::Sender<ShuffleOutput>,
+        kafka_egress: KafkaEgress,
         truncation_tx: mpsc::Sender<OutboxTruncation>,
         channel_size: usize,
     ) -> Self {
@@ -247,6 +275,7 @@ where
             partition_id,
             outbox_reader,
             network_tx,
+            kafka_egress,
             network_in_rx,
             network_in_tx,
             truncation_tx,
@@ -275,6 +304,7 @@ where
             mut network_in_rx,
             outbox_reader,
             network_tx,
+            kafka_egress,
             truncation_tx,
             ..
         } = self;
@@ -284,11 +314,24 @@ where
         let shutdown = shutdown_watch.signaled();
         tokio::pin!(shutdown);
 
+        // Kafka records are handed over to the egress loop below, which acks them once published
+        let (egress_tx, mut egress_rx) = mpsc::channel(1);
+        let mut in_flight_records = FuturesUnordered::new();
+        // The state machine resends unacknowledged records after the retry timeout, which must not
+        // start a second publish of a record that is still in flight
+        let mut in_flight_indexes = HashSet::new();
+
         let state_machine = StateMachine::new(
             peer_id,
             partition_id,
             outbox_reader,
-            |msg| network_tx.send(msg),
+            |msg: ShuffleOutput| {
+                if matches!(msg.message, ShuffleMessageDestination::Kafka(_)) {
+                    Either::Right(egress_tx.send(msg))
+                } else {
+                    Either::Left(network_tx.send(msg))
+                }
+            },
             &mut hint_rx,
             Duration::from_secs(60),
         );
@@ -307,6 +350,36 @@ where
                         let _ = truncation_tx.try_send(OutboxTruncation::new(truncation_index));
                     }
                 },
+                Some(output) = egress_rx.recv() => {
+                    let (_, _, msg_index, message) = output.into_inner();
+                    let ShuffleMessageDestination::Kafka(record) = message else {
+                        unreachable!("only Kafka records are sent to the egress");
+                    };
+                    if !in_flight_indexes.insert(msg_index) {
+                        trace!(restate.outbox.seq = msg_index, "Kafka record is already being published");
+                        continue;
+                    }
+                    let kafka_egress = kafka_egress.clone();
+                    in_flight_records.push(async move { (msg_index, kafka_egress.publish(record).await) });
+                },
+                Some((msg_index, result)) = in_flight_records.next(), if !in_flight_records.is_empty() => {
+                    in_flight_indexes.remove(&msg_index);
+                    match result {
+                        Ok(()) => {},
+                        Err(err) if err.is_permanent() => {
+                            warn!(restate.outbox.seq = msg_index, "Dropping Kafka record which cannot be published: {err}");
+                        },
+                        Err(err) => {
+                            // The state machine will try to publish the record again after the retry timeout
+                            warn!(restate.outbox.seq = msg_index, "Failed publishing Kafka record: {err}");
+                            continue;
+                        }
+                    }
+
+                    if let Some(truncation_index) = state_machine.as_mut().on_network_input(ShuffleInput(AckKind::Acknowledge(msg_index))) {
+                        let _ = truncation_tx.try_send(OutboxTruncation::new(truncation_index));
+                    }
+                },
                 _ = &mut shutdown => {
                     break;
                 }
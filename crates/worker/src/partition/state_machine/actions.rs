@@ -16,7 +16,9 @@ use restate_invoker_api::InvokeInputJournal;
 use restate_storage_api::outbox_table::OutboxMessage;
 use restate_storage_api::timer_table::TimerKey;
 use restate_types::identifiers::{EntryIndex, FullInvocationId, InvocationUuid, ServiceId};
-use restate_types::invocation::{ServiceInvocationResponseSink, ServiceInvocationSpanContext};
+use restate_types::invocation::{
+    Header, ServiceInvocationResponseSink, ServiceInvocationSpanContext,
+};
 use restate_types::journal::Completion;
 use restate_types::message::MessageIndex;
 
@@ -32,6 +34,7 @@ pub enum Action {
         span_context: ServiceInvocationSpanContext,
         response_sink: Option<ServiceInvocationResponseSink>,
         argument: Bytes,
+        headers: Vec<Header>,
     },
     NotifyVirtualJournalCompletion {
         target_service: ServiceId,
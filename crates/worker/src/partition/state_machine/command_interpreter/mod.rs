@@ -24,7 +24,7 @@ use futures::{Stream, StreamExt};
 use restate_service_protocol::codec::ProtobufRawEntryCodec;
 use restate_storage_api::inbox_table::{InboxEntry, SequenceNumberInvocation};
 use restate_storage_api::journal_table::JournalEntry;
-use restate_storage_api::outbox_table::OutboxMessage;
+use restate_storage_api::outbox_table::{KafkaRecord, OutboxMessage};
 use restate_storage_api::status_table::{InvocationMetadata, InvocationStatus, NotificationTarget};
 use restate_storage_api::timer_table::{Timer, TimerKey};
 use restate_storage_api::Result as StorageResult;
@@ -35,7 +35,7 @@ use restate_types::identifiers::{
     EntryIndex, FullInvocationId, InvocationId, InvocationUuid, ServiceId,
 };
 use restate_types::invocation::{
-    InvocationResponse, InvocationTermination, MaybeFullInvocationId, ResponseResult,
+    Header, InvocationResponse, InvocationTermination, MaybeFullInvocationId, ResponseResult,
     ServiceInvocation, ServiceInvocationResponseSink, ServiceInvocationSpanContext, Source,
     SpanRelation, SpanRelationCause, TerminationFlavor,
 };
@@ -53,7 +53,12 @@ use std::future::Future;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::pin::pin;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, instrument, trace, warn};
+
+/// Headers of the records published to the dead letter topics, identifying the failed invocation.
+const DEAD_LETTER_INVOCATION_ID_HEADER: &str = "restate.invocation.id";
+const DEAD_LETTER_ERROR_CODE_HEADER: &str = "restate.error.code";
+const DEAD_LETTER_ERROR_MESSAGE_HEADER: &str = "restate.error.message";
 
 pub trait StateReader {
     fn get_invocation_status(
@@ -340,6 +345,7 @@ where
                 time,
                 timer_index,
                 source: caller,
+                headers,
             } => {
                 effects.register_timer(
                     TimerValue::new_invoke(
@@ -353,7 +359,8 @@ where
                             caller,
                             response_sink,
                             SpanRelation::default(),
-                        ),
+                        )
+                        .with_headers(headers),
                     ),
                     ServiceInvocationSpanContext::empty(),
                 );
@@ -821,6 +828,10 @@ where
                     SpanRelation::None,
                 ))
             }
+            Timer::CleanIngressDeduplication(_, source_id) => {
+                effects.delete_ingress_deduplication(source_id);
+                Ok((None, SpanRelation::None))
+            }
         }
     }
 
@@ -920,7 +931,23 @@ where
                 self.end_invocation(effects, full_invocation_id, invocation_metadata)
                     .await?;
             }
+            InvokerEffectKind::AttemptFailed => {
+                // Stored, so that the attempts are not reset when the invoker restarts
+                effects.store_invocation_attempts(
+                    full_invocation_id.service_id,
+                    invocation_metadata.attempts + 1,
+                    invocation_metadata,
+                );
+            }
             InvokerEffectKind::Failed(e) => {
+                self.apply_failure_policy(
+                    effects,
+                    state,
+                    &full_invocation_id,
+                    &invocation_metadata,
+                    &e,
+                )
+                .await?;
                 self.fail_invocation(effects, full_invocation_id, invocation_metadata, e)
                     .await?;
             }
@@ -983,6 +1010,74 @@ where
         .await
     }
 
+    /// Publishes the input of a failed invocation to the dead letter topic of its failure policy, if any.
+    async fn apply_failure_policy<State: StateReader>(
+        &mut self,
+        effects: &mut Effects,
+        state: &mut State,
+        full_invocation_id: &FullInvocationId,
+        invocation_metadata: &InvocationMetadata,
+        error: &InvocationError,
+    ) -> Result<(), Error> {
+        let failure_policy = &invocation_metadata.failure_policy;
+        let Some((cluster, topic)) = failure_policy.dead_letter.clone() else {
+            if failure_policy.max_attempts.is_some() {
+                warn!(
+                    restate.invocation.id = %full_invocation_id,
+                    "Skipping the event of the failed invocation: {}", error
+                );
+            }
+            return Ok(());
+        };
+
+        // The input entry is always the first entry of the journal
+        let mut journal = pin!(state.get_journal(&full_invocation_id.service_id, 1));
+        let input = match journal.next().await.transpose()? {
+            Some((_, JournalEntry::Entry(input_entry))) => {
+                match input_entry.deserialize_entry_ref::<Codec>()? {
+                    Entry::PollInputStream(PollInputStreamEntry {
+                        result: EntryResult::Success(input),
+                    }) => input,
+                    _ => Bytes::new(),
+                }
+            }
+            _ => Bytes::new(),
+        };
+
+        warn!(
+            restate.invocation.id = %full_invocation_id,
+            "Publishing the event of the failed invocation to the dead letter topic '{}': {}", topic, error
+        );
+        let key = &full_invocation_id.service_id.key;
+        self.send_message(
+            OutboxMessage::KafkaRecord(KafkaRecord {
+                message_id: InvocationId::from(full_invocation_id).to_string(),
+                cluster,
+                topic,
+                key: if key.is_empty() {
+                    None
+                } else {
+                    Some(key.clone())
+                },
+                payload: input,
+                headers: vec![
+                    Header::new(
+                        DEAD_LETTER_INVOCATION_ID_HEADER,
+                        full_invocation_id.to_string(),
+                    ),
+                    Header::new(
+                        DEAD_LETTER_ERROR_CODE_HEADER,
+                        u32::from(error.code()).to_string(),
+                    ),
+                    Header::new(DEAD_LETTER_ERROR_MESSAGE_HEADER, error.message()),
+                ],
+            }),
+            effects,
+        );
+
+        Ok(())
+    }
+
     fn try_send_failure_response(
         &mut self,
         effects: &mut Effects,
@@ -1042,6 +1137,27 @@ where
                     );
                 }
             }
+            EnrichedEntryHeader::OutputStreamChunk { .. } => {
+                // Only the ingress can consume server-streaming responses, other callers
+                // observe just the final response carried by the OutputStream entry.
+                if let Some(ServiceInvocationResponseSink::Ingress(ingress_node_id)) =
+                    &invocation_metadata.response_sink
+                {
+                    let_assert!(
+                        Entry::OutputStreamChunk(OutputStreamChunkEntry { value }) =
+                            journal_entry.deserialize_entry_ref::<Codec>()?
+                    );
+
+                    self.send_message(
+                        OutboxMessage::IngressResponseChunk {
+                            to_node_id: *ingress_node_id,
+                            full_invocation_id: full_invocation_id.clone(),
+                            chunk: value,
+                        },
+                        effects,
+                    );
+                }
+            }
             EnrichedEntryHeader::GetState { is_completed, .. } => {
                 if !is_completed {
                     let_assert!(
@@ -1136,6 +1252,23 @@ where
                 enrichment_result, ..
             } => {
                 if let Some(InvokeEnrichmentResult {
+                    denied_by_call_policy: Some(service_name),
+                    ..
+                }) = enrichment_result
+                {
+                    // The call policy of the invoked service doesn't allow this invocation, the entry is completed right away
+                    let completion_result =
+                        CompletionResult::from(&InvocationError::service_call_not_allowed(
+                            &full_invocation_id.service_id.service_name,
+                            service_name,
+                        ));
+                    Codec::write_completion(&mut journal_entry, completion_result.clone())?;
+
+                    effects.forward_completion(
+                        full_invocation_id.clone(),
+                        Completion::new(entry_index, completion_result),
+                    );
+                } else if let Some(InvokeEnrichmentResult {
                     service_key,
                     invocation_uuid: invocation_id,
                     span_context,
@@ -1166,6 +1299,32 @@ where
             EnrichedEntryHeader::BackgroundInvoke {
                 enrichment_result, ..
             } => {
+                if let Some(service_name) = &enrichment_result.denied_by_call_policy {
+                    // The call policy of the invoked service doesn't allow this invocation. Background invocations
+                    // have no completion to report the failure to, hence the calling invocation fails.
+                    let error = InvocationError::service_call_not_allowed(
+                        &full_invocation_id.service_id.service_name,
+                        service_name,
+                    );
+                    self.apply_failure_policy(
+                        effects,
+                        state,
+                        &full_invocation_id,
+                        &invocation_metadata,
+                        &error,
+                    )
+                    .await?;
+                    self.fail_invocation(
+                        effects,
+                        full_invocation_id.clone(),
+                        invocation_metadata,
+                        error,
+                    )
+                    .await?;
+                    effects.abort_invocation(full_invocation_id);
+                    return Ok(());
+                }
+
                 let InvokeEnrichmentResult {
                     service_key,
                     invocation_uuid: invocation_id,
@@ -1467,6 +1626,7 @@ where
             service_name,
             method_name,
             parameter,
+            headers,
         } = invoke_request;
 
         let response_sink = if let Some((caller, entry_index)) = response_target {
@@ -1485,6 +1645,8 @@ where
             source,
             response_sink,
             span_context,
+            headers,
+            failure_policy: Default::default(),
         }
     }
 }
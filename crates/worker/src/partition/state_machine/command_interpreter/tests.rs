@@ -395,6 +395,36 @@ async fn send_response_using_invocation_id() {
     );
 }
 
+#[test(tokio::test)]
+async fn clean_ingress_deduplication_timer() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(0, 0);
+    let mut effects = Effects::default();
+    let mut state_reader = StateReaderMock::default();
+
+    let fid = FullInvocationId::mock_random();
+    let source_id = ByteString::from_static("idempotency-key/MySvc/TXlLZXk=/123");
+    let timer_value = TimerValue::new_clean_ingress_deduplication(
+        fid,
+        MillisSinceEpoch::now(),
+        source_id.clone(),
+    );
+    let (timer_key, _) = timer_value.clone().into_inner();
+
+    command_interpreter
+        .on_apply(Command::Timer(timer_value), &mut effects, &mut state_reader)
+        .await?;
+
+    assert_that!(
+        effects.into_inner(),
+        all!(
+            contains(pat!(Effect::DeleteTimer(eq(timer_key)))),
+            contains(pat!(Effect::DeleteIngressDeduplication(eq(source_id))))
+        )
+    );
+
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn kill_inboxed_invocation() -> Result<(), Error> {
     let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(0, 0);
@@ -519,6 +549,61 @@ async fn kill_call_tree() -> Result<(), Error> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn publish_failed_invocation_to_dead_letter_topic() -> Result<(), Error> {
+    let mut command_interpreter = CommandInterpreter::<ProtobufRawEntryCodec>::new(0, 0);
+    let mut state_reader = StateReaderMock::default();
+    let mut effects = Effects::default();
+
+    let fid = FullInvocationId::mock_random();
+    let input = Bytes::from_static(b"event");
+
+    state_reader.register_invocation_status(
+        fid.clone(),
+        InvocationStatus::Invoked(InvocationMetadata {
+            failure_policy: InvocationFailurePolicy {
+                max_attempts: Some(3),
+                dead_letter: Some(("my-cluster".to_owned(), "my-dlq".to_owned())),
+            },
+            ..StateReaderMock::mock_invocation_metadata(1, fid.invocation_uuid)
+        }),
+        vec![JournalEntry::Entry(
+            ProtobufRawEntryCodec::serialize_as_unary_input_entry(input.clone()),
+        )],
+    );
+
+    command_interpreter
+        .on_apply(
+            Command::Invoker(InvokerEffect {
+                full_invocation_id: fid.clone(),
+                kind: EffectKind::Failed(InvocationError::internal("boom")),
+            }),
+            &mut effects,
+            &mut state_reader,
+        )
+        .await?;
+
+    let effects = effects.into_inner();
+
+    assert_that!(
+        effects,
+        all!(
+            contains(pat!(Effect::EnqueueIntoOutbox {
+                message: pat!(OutboxMessage::KafkaRecord(pat!(KafkaRecord {
+                    cluster: eq("my-cluster"),
+                    topic: eq("my-dlq"),
+                    payload: eq(input),
+                }))),
+            })),
+            contains(pat!(Effect::DropJournalAndPopInbox {
+                service_id: eq(fid.service_id.clone()),
+            }))
+        )
+    );
+
+    Ok(())
+}
+
 fn completed_invoke_entry(target_fid: FullInvocationId) -> JournalEntry {
     JournalEntry::Entry(EnrichedRawEntry::new(
         EnrichedEntryHeader::Invoke {
@@ -528,6 +613,7 @@ fn completed_invoke_entry(target_fid: FullInvocationId) -> JournalEntry {
                 service_key: target_fid.service_id.key,
                 service_name: target_fid.service_id.service_name,
                 span_context: ServiceInvocationSpanContext::empty(),
+                denied_by_call_policy: None,
             }),
         },
         Bytes::default(),
@@ -542,6 +628,7 @@ fn background_invoke_entry(target_fid: FullInvocationId) -> JournalEntry {
                 service_key: target_fid.service_id.key,
                 service_name: target_fid.service_id.service_name,
                 span_context: ServiceInvocationSpanContext::empty(),
+                denied_by_call_policy: None,
             },
         },
         Bytes::default(),
@@ -557,6 +644,7 @@ fn uncompleted_invoke_entry(target_fid: FullInvocationId) -> JournalEntry {
                 service_key: target_fid.service_id.key,
                 service_name: target_fid.service_id.service_name,
                 span_context: ServiceInvocationSpanContext::empty(),
+                denied_by_call_policy: None,
             }),
         },
         Bytes::default(),
@@ -14,6 +14,7 @@ use restate_types::identifiers::{PartitionId, PeerId};
 use restate_types::invocation::{InvocationResponse, InvocationTermination, ServiceInvocation};
 use restate_types::message::{AckKind, MessageIndex};
 use restate_types::state_mut::ExternalStateMutation;
+use restate_types::time::MillisSinceEpoch;
 use restate_types::GenerationalNodeId;
 
 /// Envelope for [`partition::Command`] that might require an explicit acknowledge.
@@ -73,6 +74,8 @@ pub enum DeduplicationSource {
         // String used to distinguish between different seq_numbers indexes produced by the ingress
         source_id: String,
         seq_number: MessageIndex,
+        // If set, the deduplication entry is removed once expired
+        expiration_time: Option<MillisSinceEpoch>,
     },
 }
 
@@ -93,11 +96,13 @@ impl DeduplicationSource {
         from_node_id: GenerationalNodeId,
         source_id: String,
         seq_number: MessageIndex,
+        expiration_time: Option<MillisSinceEpoch>,
     ) -> Self {
         DeduplicationSource::Ingress {
             from_node_id,
             source_id,
             seq_number,
+            expiration_time,
         }
     }
 
@@ -115,6 +120,7 @@ impl DeduplicationSource {
                 from_node_id,
                 seq_number,
                 source_id,
+                ..
             } => AckResponse::Ingress(IngressAckResponse {
                 _from_node_id: from_node_id,
                 dedup_source: Some(source_id),
@@ -140,6 +146,7 @@ impl DeduplicationSource {
                 from_node_id: node_id,
                 seq_number,
                 source_id,
+                ..
             } => AckResponse::Ingress(IngressAckResponse {
                 _from_node_id: node_id,
                 dedup_source: Some(source_id),
@@ -8,14 +8,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use super::{AckCommand, AckMode, Effects, Error};
+use super::{AckCommand, AckMode, Command, Effects, Error};
 
 use crate::partition::state_machine::commands::DeduplicationSource;
 use crate::partition::state_machine::{
     Action, ActionCollector, InterpretationResult, StateMachine,
 };
 use crate::partition::storage::Transaction;
+use crate::partition::TimerValue;
 use restate_storage_api::deduplication_table::SequenceNumberSource;
+use restate_types::invocation::ServiceInvocationSpanContext;
 use restate_types::journal::raw::RawEntryCodec;
 use restate_types::message::MessageIndex;
 
@@ -54,6 +56,23 @@ where
                 message_collector.collect(Action::SendAckResponse(ack_target.acknowledge()));
             }
             AckMode::Dedup(deduplication_source) => {
+                // Idempotent ingress requests are deduplicated only for their retention period
+                let clean_up = match (&deduplication_source, &fsm_command) {
+                    (
+                        DeduplicationSource::Ingress {
+                            source_id,
+                            expiration_time: Some(expiration_time),
+                            ..
+                        },
+                        Command::Invocation(service_invocation),
+                    ) => Some(TimerValue::new_clean_ingress_deduplication(
+                        service_invocation.fid.clone(),
+                        *expiration_time,
+                        source_id.clone().into(),
+                    )),
+                    _ => None,
+                };
+
                 let (source, seq_number) = match deduplication_source {
                     DeduplicationSource::Shuffle {
                         seq_number,
@@ -85,6 +104,9 @@ where
                 }
 
                 transaction.store_dedup_seq_number(source, seq_number).await;
+                if let Some(timer_value) = clean_up {
+                    effects.register_timer(timer_value, ServiceInvocationSpanContext::empty());
+                }
                 message_collector
                     .collect(Action::SendAckResponse(deduplication_source.acknowledge()));
             }
@@ -18,6 +18,7 @@ use crate::partition::{CommitError, Committable};
 use bytes::Bytes;
 use futures::{Stream, TryStreamExt};
 use restate_invoker_api::InvokeInputJournal;
+use restate_storage_api::deduplication_table::SequenceNumberSource;
 use restate_storage_api::inbox_table::{InboxEntry, SequenceNumberInboxEntry};
 use restate_storage_api::outbox_table::OutboxMessage;
 use restate_storage_api::status_table::{
@@ -159,6 +160,12 @@ pub trait StateStorage {
         &mut self,
         timer_key: &TimerKey,
     ) -> impl Future<Output = StorageResult<()>> + Send;
+
+    // Deduplication
+    fn delete_dedup_seq_number(
+        &mut self,
+        source: SequenceNumberSource,
+    ) -> impl Future<Output = StorageResult<()>> + Send;
 }
 
 #[must_use = "Don't forget to commit the interpretation result"]
@@ -309,6 +316,11 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                 state_storage.delete_timer(&timer_key).await?;
                 collector.collect(Action::DeleteTimer { timer_key });
             }
+            Effect::DeleteIngressDeduplication(source_id) => {
+                state_storage
+                    .delete_dedup_seq_number(SequenceNumberSource::Ingress(source_id))
+                    .await?;
+            }
             Effect::StoreDeploymentId {
                 service_id,
                 deployment_id,
@@ -325,6 +337,16 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                     .store_invocation_status(&service_id, InvocationStatus::Invoked(metadata))
                     .await?;
             }
+            Effect::StoreInvocationAttempts {
+                service_id,
+                attempts,
+                mut metadata,
+            } => {
+                metadata.attempts = attempts;
+                state_storage
+                    .store_invocation_status(&service_id, InvocationStatus::Invoked(metadata))
+                    .await?;
+            }
             Effect::AppendJournalEntry {
                 service_id,
                 previous_invocation_status,
@@ -527,6 +549,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                     service_invocation.response_sink.clone(),
                     StatusTimestamps::now(),
                     service_invocation.source,
+                    service_invocation.headers.clone(),
+                    service_invocation.failure_policy.clone(),
+                    0,
                 )),
             )
             .await?;
@@ -542,6 +567,7 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                 response_sink: service_invocation.response_sink,
                 method: service_invocation.method_name,
                 argument: service_invocation.argument.clone(),
+                headers: service_invocation.headers,
             });
 
             // TODO clean up custom entry hack by allowing to store bytes directly?
@@ -562,7 +588,9 @@ impl<Codec: RawEntryCodec> EffectInterpreter<Codec> {
                         journal_metadata.span_context,
                         service_invocation.method_name.clone(),
                         None,
-                    ),
+                        service_invocation.headers,
+                    )
+                    .with_attempts(service_invocation.failure_policy.max_attempts, 0),
                     vec![PlainRawEntry::new(
                         header.clone().erase_enrichment(),
                         serialized_entry.clone(),
@@ -99,12 +99,20 @@ pub(crate) enum Effect {
     },
     DeleteTimer(TimerKey),
 
+    // Deduplication
+    DeleteIngressDeduplication(ByteString),
+
     // Journal operations
     StoreDeploymentId {
         service_id: ServiceId,
         deployment_id: DeploymentId,
         metadata: InvocationMetadata,
     },
+    StoreInvocationAttempts {
+        service_id: ServiceId,
+        attempts: u32,
+        metadata: InvocationMetadata,
+    },
     AppendJournalEntry {
         service_id: ServiceId,
         // We pass around the invocation_status here to avoid an additional read.
@@ -343,6 +351,28 @@ impl Effect {
                 failure_code,
                 failure_msg
             ),
+            Effect::EnqueueIntoOutbox {
+                seq_number,
+                message:
+                    OutboxMessage::IngressResponseChunk {
+                        full_invocation_id, ..
+                    },
+            } => trace!(
+                rpc.service = %full_invocation_id.service_id.service_name,
+                restate.invocation.id = %full_invocation_id,
+                restate.outbox.seq = seq_number,
+                "Effect: Send response chunk to ingress"
+            ),
+            Effect::EnqueueIntoOutbox {
+                seq_number,
+                message: OutboxMessage::KafkaRecord(kafka_record),
+            } => debug_if_leader!(
+                is_leader,
+                restate.kafka.cluster = %kafka_record.cluster,
+                restate.kafka.topic = %kafka_record.topic,
+                restate.outbox.seq = seq_number,
+                "Effect: Publish record to Kafka"
+            ),
             Effect::TruncateOutbox(seq_number) => {
                 trace!(restate.outbox.seq = seq_number, "Effect: Truncate outbox")
             }
@@ -454,6 +484,15 @@ impl Effect {
                         "Effect: Register background invoke timer"
                     )
                 }
+                Timer::CleanIngressDeduplication(_, source_id) => {
+                    debug_if_leader!(
+                        is_leader,
+                        restate.deduplication.source = %source_id,
+                        restate.timer.key = %TimerKeyDisplay(timer_value.key()),
+                        restate.timer.wake_up_time = %timer_value.wake_up_time(),
+                        "Effect: Register ingress deduplication cleanup timer"
+                    )
+                }
             },
             Effect::DeleteTimer(timer_key) => {
                 let timer_key_display = TimerKeyDisplay(timer_key);
@@ -463,11 +502,21 @@ impl Effect {
                     "Effect: Delete timer"
                 )
             }
+            Effect::DeleteIngressDeduplication(source_id) => debug_if_leader!(
+                is_leader,
+                restate.deduplication.source = %source_id,
+                "Effect: Delete ingress deduplication entry"
+            ),
             Effect::StoreDeploymentId { deployment_id, .. } => debug_if_leader!(
                 is_leader,
                 restate.deployment.id = %deployment_id,
                 "Effect: Store deployment id to storage"
             ),
+            Effect::StoreInvocationAttempts { attempts, .. } => debug_if_leader!(
+                is_leader,
+                "Effect: Store {} failed attempts to storage",
+                attempts
+            ),
             Effect::AppendJournalEntry {
                 journal_entry,
                 entry_index,
@@ -773,6 +822,11 @@ impl Effects {
         self.effects.push(Effect::DeleteTimer(timer_key));
     }
 
+    pub(crate) fn delete_ingress_deduplication(&mut self, source_id: ByteString) {
+        self.effects
+            .push(Effect::DeleteIngressDeduplication(source_id));
+    }
+
     pub(crate) fn store_chosen_deployment(
         &mut self,
         service_id: ServiceId,
@@ -786,6 +840,19 @@ impl Effects {
         })
     }
 
+    pub(crate) fn store_invocation_attempts(
+        &mut self,
+        service_id: ServiceId,
+        attempts: u32,
+        metadata: InvocationMetadata,
+    ) {
+        self.effects.push(Effect::StoreInvocationAttempts {
+            service_id,
+            attempts,
+            metadata,
+        })
+    }
+
     pub(crate) fn append_journal_entry(
         &mut self,
         service_id: ServiceId,
@@ -113,11 +113,12 @@ mod tests {
     use restate_test_util::matchers::*;
     use restate_types::errors::UserErrorCode;
     use restate_types::identifiers::{
-        FullInvocationId, InvocationUuid, PartitionId, PartitionKey, ServiceId, WithPartitionKey,
+        FullInvocationId, InvocationId, InvocationUuid, PartitionId, PartitionKey, ServiceId,
+        WithPartitionKey,
     };
     use restate_types::invocation::{
-        InvocationResponse, InvocationTermination, MaybeFullInvocationId, ResponseResult,
-        ServiceInvocation, ServiceInvocationResponseSink, Source,
+        Header, InvocationResponse, InvocationTermination, MaybeFullInvocationId, ResponseResult,
+        ServiceInvocation, ServiceInvocationResponseSink, Source, SpanRelation,
     };
     use restate_types::journal::enriched::EnrichedRawEntry;
     use restate_types::journal::{Completion, CompletionResult};
@@ -234,6 +235,41 @@ mod tests {
         state_machine.shutdown().await
     }
 
+    #[test(tokio::test)]
+    async fn invocation_status_retains_owner_header() -> TestResult {
+        let mut state_machine = MockStateMachine::default();
+        let fid = FullInvocationId::generate("MySvc", Bytes::default());
+
+        // Invocation started through the ingress by the owner
+        state_machine
+            .apply(Command::Invocation(
+                ServiceInvocation::new(
+                    fid.clone(),
+                    "MyMethod",
+                    Bytes::default(),
+                    Source::Ingress,
+                    None,
+                    SpanRelation::None,
+                )
+                .with_headers(vec![Header::new("x-tenant-id", "my-tenant")]),
+            ))
+            .await;
+
+        let status = crate::services::read_invocation_status(
+            state_machine.storage(),
+            InvocationId::from(fid),
+        )
+        .await?;
+        assert_eq!(
+            status.kind,
+            restate_worker_api::InvocationStatusKind::Running
+        );
+        assert!(status.has_header("X-Tenant-Id", "my-tenant"));
+        assert!(!status.has_header("X-Tenant-Id", "another-tenant"));
+
+        state_machine.shutdown().await
+    }
+
     #[test(tokio::test)]
     async fn awakeable_completion_received_before_entry() -> TestResult {
         let mut state_machine = MockStateMachine::default();
@@ -794,6 +830,8 @@ mod tests {
                 source: Source::Ingress,
                 response_sink: None,
                 span_context: Default::default(),
+                headers: vec![],
+                failure_policy: Default::default(),
             }))
             .await;
 
@@ -55,6 +55,11 @@ where
                 invoked_status.journal_metadata.span_context,
                 invoked_status.method,
                 invoked_status.deployment_id,
+                invoked_status.headers,
+            )
+            .with_attempts(
+                invoked_status.failure_policy.max_attempts,
+                invoked_status.attempts,
             );
             let journal_stream = self
                 .0
@@ -64,6 +64,15 @@ impl<Storage> PartitionStorage<Storage> {
     pub fn assert_partition_key(&self, partition_key: &impl WithPartitionKey) {
         assert_partition_key(&self.partition_key_range, partition_key);
     }
+
+    /// Returns the underlying storage, which is not restricted to the partition key range.
+    /// Use it only to read the data of other partitions.
+    pub(crate) fn unpartitioned_storage(&self) -> Storage
+    where
+        Storage: Clone,
+    {
+        self.storage.clone()
+    }
 }
 
 impl<Storage> PartitionStorage<Storage>
@@ -558,6 +567,13 @@ where
         self.inner.delete_timer(self.partition_id, timer_key).await;
         Ok(())
     }
+
+    async fn delete_dedup_seq_number(&mut self, source: SequenceNumberSource) -> StorageResult<()> {
+        self.inner
+            .delete_sequence_number(self.partition_id, source)
+            .await;
+        Ok(())
+    }
 }
 
 mod fsm_variable {
@@ -8,6 +8,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use bytestring::ByteString;
 use prost::Message;
 use restate_storage_api::outbox_table::OutboxMessage;
 use restate_storage_api::timer_table::{Timer, TimerKey};
@@ -74,6 +75,23 @@ impl TimerValue {
         }
     }
 
+    pub(crate) fn new_clean_ingress_deduplication(
+        full_invocation_id: FullInvocationId,
+        expiration_time: MillisSinceEpoch,
+        source_id: ByteString,
+    ) -> Self {
+        let timer_key = TimerKeyWrapper(TimerKey {
+            invocation_uuid: full_invocation_id.invocation_uuid,
+            timestamp: expiration_time.as_u64(),
+            journal_index: 0,
+        });
+
+        Self {
+            timer_key,
+            value: Timer::CleanIngressDeduplication(full_invocation_id.service_id, source_id),
+        }
+    }
+
     pub fn into_inner(self) -> (TimerKey, Timer) {
         (self.timer_key.0, self.value)
     }
@@ -13,7 +13,11 @@ use super::subscription_integration;
 use crate::partition::{StateMachineAckCommand, StateMachineCommand};
 use restate_consensus::ProposalSender;
 use restate_network::PartitionTableError;
-use restate_types::identifiers::WithPartitionKey;
+use restate_storage_api::inbox_table::InboxTable;
+use restate_storage_api::status_table::{self, ReadOnlyStatusTable};
+use restate_storage_api::StorageError;
+use restate_storage_rocksdb::RocksDBStorage;
+use restate_types::identifiers::{InvocationId, WithPartitionKey};
 use restate_types::invocation::InvocationTermination;
 use restate_types::message::PartitionTarget;
 use restate_types::state_mut::ExternalStateMutation;
@@ -31,23 +35,24 @@ enum WorkerCommand {
 pub struct WorkerCommandSender {
     command_tx: mpsc::Sender<WorkerCommand>,
     subscription_controller_handle: subscription_integration::SubscriptionControllerHandle,
+    rocksdb_storage: RocksDBStorage,
 }
 
 impl WorkerCommandSender {
     fn new(
         command_tx: mpsc::Sender<WorkerCommand>,
         subscription_controller_handle: subscription_integration::SubscriptionControllerHandle,
+        rocksdb_storage: RocksDBStorage,
     ) -> Self {
         Self {
             command_tx,
             subscription_controller_handle,
+            rocksdb_storage,
         }
     }
 }
 
-impl restate_worker_api::Handle for WorkerCommandSender {
-    type SubscriptionControllerHandle = subscription_integration::SubscriptionControllerHandle;
-
+impl restate_worker_api::InvocationHandle for WorkerCommandSender {
     async fn terminate_invocation(
         &self,
         invocation_termination: InvocationTermination,
@@ -58,6 +63,63 @@ impl restate_worker_api::Handle for WorkerCommandSender {
             .map_err(|_| restate_worker_api::Error::Unreachable)
     }
 
+    async fn invocation_status(
+        &self,
+        invocation_id: InvocationId,
+    ) -> Result<restate_worker_api::InvocationStatus, restate_worker_api::Error> {
+        read_invocation_status(&mut self.rocksdb_storage.clone(), invocation_id)
+            .await
+            .map_err(|e| restate_worker_api::Error::Storage(e.to_string()))
+    }
+}
+
+/// Reads the status of an invocation, regardless of the partition owning it.
+pub(crate) async fn read_invocation_status(
+    storage: &mut RocksDBStorage,
+    invocation_id: InvocationId,
+) -> Result<restate_worker_api::InvocationStatus, StorageError> {
+    use restate_worker_api::{InvocationStatus, InvocationStatusKind};
+
+    if let Some((_, status)) = storage
+        .get_invocation_status_from(
+            invocation_id.partition_key(),
+            invocation_id.invocation_uuid(),
+        )
+        .await?
+    {
+        return Ok(match status {
+            status_table::InvocationStatus::Invoked(metadata) => InvocationStatus {
+                kind: InvocationStatusKind::Running,
+                headers: metadata.headers,
+            },
+            status_table::InvocationStatus::Suspended { metadata, .. } => InvocationStatus {
+                kind: InvocationStatusKind::Suspended,
+                headers: metadata.headers,
+            },
+            // Built-in service invocations don't retain their headers
+            status_table::InvocationStatus::Virtual { .. } => InvocationStatus {
+                kind: InvocationStatusKind::Virtual,
+                headers: vec![],
+            },
+            // A free service instance isn't executing any invocation
+            status_table::InvocationStatus::Free => InvocationStatus::completed(),
+        });
+    }
+
+    // Not started yet, check whether it's waiting in the inbox
+    let mut transaction = storage.transaction();
+    Ok(match transaction.get_invocation(invocation_id).await? {
+        Some(inboxed) => InvocationStatus {
+            kind: InvocationStatusKind::Pending,
+            headers: inboxed.invocation.headers,
+        },
+        None => InvocationStatus::completed(),
+    })
+}
+
+impl restate_worker_api::Handle for WorkerCommandSender {
+    type SubscriptionControllerHandle = subscription_integration::SubscriptionControllerHandle;
+
     async fn external_state_mutation(
         &self,
         mutation: ExternalStateMutation,
@@ -98,13 +160,18 @@ where
         proposal_tx: ProposalSender<PartitionTarget<StateMachineAckCommand>>,
         subscription_controller_handle: subscription_integration::SubscriptionControllerHandle,
         partition_table: PartitionTable,
+        rocksdb_storage: RocksDBStorage,
         channel_size: usize,
     ) -> Self {
         let (command_tx, command_rx) = mpsc::channel(channel_size);
 
         Self {
             command_rx,
-            command_tx: WorkerCommandSender::new(command_tx, subscription_controller_handle),
+            command_tx: WorkerCommandSender::new(
+                command_tx,
+                subscription_controller_handle,
+                rocksdb_storage,
+            ),
             proposal_tx,
             partition_table,
         }
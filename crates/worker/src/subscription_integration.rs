@@ -9,11 +9,12 @@
 // by the Apache License, Version 2.0.
 
 use restate_ingress_kafka::SubscriptionCommandSender;
-use restate_schema_api::subscription::{Subscription, SubscriptionValidator};
+use restate_schema_api::subscription::{OffsetReset, Subscription, SubscriptionValidator};
 use restate_types::identifiers::SubscriptionId;
 use restate_worker_api::SubscriptionController;
 use std::ops::Deref;
 use std::sync::Arc;
+use tokio::sync::oneshot;
 
 #[derive(Debug, Clone)]
 pub struct SubscriptionControllerHandle(
@@ -57,4 +58,24 @@ impl SubscriptionController for SubscriptionControllerHandle {
             .await
             .map_err(|_| restate_worker_api::Error::Unreachable)
     }
+
+    async fn reset_subscription_offsets(
+        &self,
+        subscription: Subscription,
+        to: OffsetReset,
+    ) -> Result<(), restate_worker_api::Error> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.1
+            .send(restate_ingress_kafka::Command::ResetOffsets(
+                subscription,
+                to,
+                reply_tx,
+            ))
+            .await
+            .map_err(|_| restate_worker_api::Error::Unreachable)?;
+        reply_rx
+            .await
+            .map_err(|_| restate_worker_api::Error::Unreachable)?
+            .map_err(|e| restate_worker_api::Error::ResetOffsets(e.to_string()))
+    }
 }